@@ -0,0 +1,77 @@
+//! Minimal assembler for the bytecode of the scenarios.
+
+use revm::{
+    interpreter::opcode,
+    primitives::{Address, Bytes, HashMap, U256},
+};
+
+/// Builds bytecode from opcodes, pushes and labeled jump destinations.
+#[derive(Debug, Default)]
+pub(crate) struct Assembler {
+    code: Vec<u8>,
+    labels: HashMap<&'static str, usize>,
+    /// Offsets of the `PUSH2` immediates to patch with the offset of a label.
+    fixups: Vec<(usize, &'static str)>,
+}
+
+impl Assembler {
+    /// Appends opcodes.
+    pub(crate) fn ops(&mut self, ops: &[u8]) -> &mut Self {
+        self.code.extend_from_slice(ops);
+        self
+    }
+
+    /// Appends the shortest push of `value`.
+    pub(crate) fn push(&mut self, value: u64) -> &mut Self {
+        self.push_word(U256::from(value))
+    }
+
+    /// Appends a `PUSH20` of `address`.
+    pub(crate) fn push_address(&mut self, address: Address) -> &mut Self {
+        self.code.push(opcode::PUSH20);
+        self.code.extend_from_slice(address.as_slice());
+        self
+    }
+
+    /// Appends the shortest push of `value`.
+    pub(crate) fn push_word(&mut self, value: U256) -> &mut Self {
+        let bytes = value.to_be_bytes::<32>();
+        let len = 32 - bytes.iter().take_while(|byte| **byte == 0).count();
+        if len == 0 {
+            self.code.push(opcode::PUSH0);
+        } else {
+            self.code.push(opcode::PUSH0 + len as u8);
+            self.code.extend_from_slice(&bytes[32 - len..]);
+        }
+        self
+    }
+
+    /// Appends a push of the offset of `label`.
+    pub(crate) fn push_label(&mut self, label: &'static str) -> &mut Self {
+        self.code.push(opcode::PUSH2);
+        self.fixups.push((self.code.len(), label));
+        self.code.extend_from_slice(&[0, 0]);
+        self
+    }
+
+    /// Appends a `JUMPDEST` for `label`.
+    pub(crate) fn label(&mut self, label: &'static str) -> &mut Self {
+        self.labels.insert(label, self.code.len());
+        self.code.push(opcode::JUMPDEST);
+        self
+    }
+
+    /// Returns the bytecode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pushed label is not defined.
+    pub(crate) fn build(&mut self) -> Bytes {
+        let mut code = core::mem::take(&mut self.code);
+        for (offset, label) in self.fixups.drain(..) {
+            let target = self.labels[label] as u16;
+            code[offset..offset + 2].copy_from_slice(&target.to_be_bytes());
+        }
+        code.into()
+    }
+}
@@ -0,0 +1,400 @@
+//! Standardized benchmark scenarios of revm.
+//!
+//! Every [Scenario] is a self-contained database and transaction, so the same workload can be
+//! executed against any handler or instruction table configuration of an [Evm] and the results
+//! compared between changes.
+#![warn(unreachable_pub)]
+
+mod asm;
+
+use asm::Assembler;
+use revm::{
+    db::{CacheDB, EmptyDB},
+    interpreter::opcode,
+    primitives::{
+        keccak256, AccountInfo, Address, Bytecode, Bytes, EVMError, ExecutionResult, TransactTo,
+        TxEnv, U256,
+    },
+    Evm,
+};
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
+/// Database of the scenarios.
+pub type ScenarioDB = CacheDB<EmptyDB>;
+
+/// Sender of the transactions of the scenarios.
+pub const CALLER: Address = Address::with_last_byte(0xca);
+/// Address of the contract called by the scenarios.
+pub const TARGET: Address = Address::with_last_byte(0xee);
+
+/// Reproducible workload: a database and a transaction executed on it.
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    /// Short name of the scenario.
+    pub name: &'static str,
+    /// What the scenario exercises.
+    pub description: &'static str,
+    /// State the transaction is executed on.
+    pub db: ScenarioDB,
+    /// Transaction of the scenario.
+    pub tx: TxEnv,
+}
+
+/// Timing of the repeated executions of a [Scenario].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    /// Name of the scenario.
+    pub name: &'static str,
+    /// Number of timed executions.
+    pub iterations: u32,
+    /// Gas used by one execution.
+    pub gas_used: u64,
+    /// Total time of the timed executions.
+    pub total: Duration,
+}
+
+impl Measurement {
+    /// Returns the mean time of an execution.
+    pub fn mean(&self) -> Duration {
+        self.total / self.iterations.max(1)
+    }
+
+    /// Returns the executed gas per second.
+    pub fn gas_per_second(&self) -> f64 {
+        self.gas_used as f64 * self.iterations as f64 / self.total.as_secs_f64()
+    }
+}
+
+impl Scenario {
+    fn new(name: &'static str, description: &'static str, code: Bytes, data: Bytes) -> Self {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(CALLER, AccountInfo::from_balance(U256::MAX));
+        insert_code(&mut db, TARGET, code);
+        Self {
+            name,
+            description,
+            db,
+            tx: TxEnv {
+                caller: CALLER,
+                transact_to: TransactTo::Call(TARGET),
+                data,
+                gas_limit: 30_000_000,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Returns an EVM with the default configuration, set up to execute the scenario.
+    pub fn evm(&self) -> Evm<'static, (), ScenarioDB> {
+        Evm::builder()
+            .with_db(self.db.clone())
+            .with_tx_env(self.tx.clone())
+            .build()
+    }
+
+    /// Sets the database and the transaction of the scenario on `evm`, keeping its handler and
+    /// instruction table.
+    pub fn prepare<EXT>(&self, evm: &mut Evm<'_, EXT, ScenarioDB>) {
+        *evm.db_mut() = self.db.clone();
+        *evm.tx_mut() = self.tx.clone();
+    }
+
+    /// Executes the scenario once on `evm`, without committing its state.
+    pub fn run<EXT>(
+        &self,
+        evm: &mut Evm<'_, EXT, ScenarioDB>,
+    ) -> Result<ExecutionResult, EVMError<Infallible>> {
+        self.prepare(evm);
+        Ok(evm.transact()?.result)
+    }
+
+    /// Executes the scenario once to warm up, then `iterations` timed times.
+    ///
+    /// The state is not committed, every execution starts from the same state.
+    pub fn bench<EXT>(
+        &self,
+        evm: &mut Evm<'_, EXT, ScenarioDB>,
+        iterations: u32,
+    ) -> Result<Measurement, EVMError<Infallible>> {
+        let gas_used = self.run(evm)?.gas_used();
+        let start = Instant::now();
+        for _ in 0..iterations {
+            evm.transact()?;
+        }
+        Ok(Measurement {
+            name: self.name,
+            iterations,
+            gas_used,
+            total: start.elapsed(),
+        })
+    }
+}
+
+/// Returns all scenarios.
+pub fn scenarios() -> Vec<Scenario> {
+    vec![
+        erc20_transfers(500),
+        swap_path(4),
+        keccak(10_000),
+        memory(4_096),
+        recursion(256),
+    ]
+}
+
+/// `count` transfers of ERC-20 style balances from the caller to new holders, reading and
+/// writing a `mapping(address => uint256)` for each.
+pub fn erc20_transfers(count: u64) -> Scenario {
+    let mut asm = Assembler::default();
+    asm.push(0).ops(&[opcode::CALLDATALOAD]).push(0);
+    loop_header(&mut asm);
+    // balances[caller] -= 1
+    asm.ops(&[opcode::CALLER]).push(0).ops(&[opcode::MSTORE]);
+    asm.push(0).push(32).ops(&[opcode::MSTORE]);
+    asm.push(64).push(0).ops(&[opcode::KECCAK256]);
+    asm.ops(&[opcode::DUP1, opcode::SLOAD]).push(1).ops(&[
+        opcode::SWAP1,
+        opcode::SUB,
+        opcode::SWAP1,
+        opcode::SSTORE,
+    ]);
+    // balances[i + 1] += 1
+    asm.ops(&[opcode::DUP1])
+        .push(1)
+        .ops(&[opcode::ADD])
+        .push(0)
+        .ops(&[opcode::MSTORE]);
+    asm.push(64).push(0).ops(&[opcode::KECCAK256]);
+    asm.ops(&[opcode::DUP1, opcode::SLOAD]).push(1).ops(&[
+        opcode::ADD,
+        opcode::SWAP1,
+        opcode::SSTORE,
+    ]);
+    loop_footer(&mut asm);
+
+    let mut scenario = Scenario::new(
+        "erc20_transfers",
+        "storm of ERC-20 style transfers to new holders, storage heavy",
+        asm.build(),
+        word(count),
+    );
+    let mut key = [0u8; 64];
+    key[12..32].copy_from_slice(CALLER.as_slice());
+    scenario
+        .db
+        .insert_account_storage(
+            TARGET,
+            U256::from_be_bytes(keccak256(key).0),
+            U256::from(count),
+        )
+        .unwrap();
+    scenario
+}
+
+/// Swap through a path of `hops` constant product pools, each called by a router and updating
+/// its reserves.
+pub fn swap_path(hops: u8) -> Scenario {
+    // amount_out = amount_in * 997 * reserve_out / (reserve_in * 1000 + amount_in * 997)
+    let mut pool = Assembler::default();
+    pool.push(0)
+        .ops(&[opcode::CALLDATALOAD])
+        .push(997)
+        .ops(&[opcode::MUL]);
+    pool.push(1)
+        .ops(&[opcode::SLOAD, opcode::DUP2, opcode::MUL]);
+    pool.push(0).ops(&[opcode::SLOAD]).push(1000).ops(&[
+        opcode::MUL,
+        opcode::DUP3,
+        opcode::ADD,
+        opcode::SWAP1,
+        opcode::DIV,
+    ]);
+    // reserve_in += amount_in, reserve_out -= amount_out
+    pool.push(0)
+        .ops(&[opcode::CALLDATALOAD])
+        .push(0)
+        .ops(&[opcode::SLOAD, opcode::ADD])
+        .push(0)
+        .ops(&[opcode::SSTORE]);
+    pool.ops(&[opcode::DUP1])
+        .push(1)
+        .ops(&[opcode::SLOAD, opcode::SUB])
+        .push(1)
+        .ops(&[opcode::SSTORE]);
+    pool.push(0)
+        .ops(&[opcode::MSTORE])
+        .push(32)
+        .push(0)
+        .ops(&[opcode::RETURN]);
+    let pool = pool.build();
+
+    // The output of every pool is written over the input of the next one.
+    let mut router = Assembler::default();
+    router
+        .push(0)
+        .ops(&[opcode::CALLDATALOAD])
+        .push(0)
+        .ops(&[opcode::MSTORE]);
+    // Pools are placed after the precompiles.
+    let pools: Vec<_> = (1..=hops)
+        .map(|hop| Address::with_last_byte(0x10 + hop))
+        .collect();
+    for pool in &pools {
+        router.push(32).push(0).push(32).push(0).push(0);
+        router
+            .push_address(*pool)
+            .ops(&[opcode::GAS, opcode::CALL, opcode::POP]);
+    }
+    router.push(32).push(0).ops(&[opcode::RETURN]);
+
+    let mut scenario = Scenario::new(
+        "swap_path",
+        "multi-hop swap through constant product pools, call and arithmetic heavy",
+        router.build(),
+        word(1_000_000_000_000_000_000),
+    );
+    let reserve = U256::from(10).pow(U256::from(24));
+    for address in pools {
+        insert_code(&mut scenario.db, address, pool.clone());
+        for slot in [U256::ZERO, U256::from(1)] {
+            scenario
+                .db
+                .insert_account_storage(address, slot, reserve)
+                .unwrap();
+        }
+    }
+    scenario
+}
+
+/// `count` chained hashes of 256 bytes of memory.
+pub fn keccak(count: u64) -> Scenario {
+    let mut asm = Assembler::default();
+    asm.push(0).ops(&[opcode::CALLDATALOAD]).push(0);
+    loop_header(&mut asm);
+    asm.push(256)
+        .push(0)
+        .ops(&[opcode::KECCAK256])
+        .push(0)
+        .ops(&[opcode::MSTORE]);
+    loop_footer(&mut asm);
+    Scenario::new(
+        "keccak",
+        "chained keccak256 of memory, hashing heavy",
+        asm.build(),
+        word(count),
+    )
+}
+
+/// `count` words written at the end of a growing memory, each followed by a copy of its first
+/// kilobyte.
+pub fn memory(count: u64) -> Scenario {
+    let mut asm = Assembler::default();
+    asm.push(0).ops(&[opcode::CALLDATALOAD]).push(0);
+    loop_header(&mut asm);
+    // mstore(i * 32, i)
+    asm.ops(&[opcode::DUP1]).push(5).ops(&[
+        opcode::SHL,
+        opcode::DUP2,
+        opcode::SWAP1,
+        opcode::MSTORE,
+    ]);
+    // mcopy(i * 32, 0, 1024)
+    asm.push(1024)
+        .push(0)
+        .ops(&[opcode::DUP3])
+        .push(5)
+        .ops(&[opcode::SHL, opcode::MCOPY]);
+    loop_footer(&mut asm);
+    Scenario::new(
+        "memory",
+        "memory expansion, stores and copies, memory heavy",
+        asm.build(),
+        word(count),
+    )
+}
+
+/// Contract calling itself recursively `depth` times.
+pub fn recursion(depth: u64) -> Scenario {
+    let mut asm = Assembler::default();
+    asm.push(0)
+        .ops(&[opcode::CALLDATALOAD, opcode::DUP1, opcode::ISZERO])
+        .push_label("end")
+        .ops(&[opcode::JUMPI]);
+    // call(gas, address, 0, 0, 32, 0, 0) with depth - 1
+    asm.push(1)
+        .ops(&[opcode::SWAP1, opcode::SUB])
+        .push(0)
+        .ops(&[opcode::MSTORE]);
+    asm.push(0).push(0).push(32).push(0).push(0);
+    asm.ops(&[opcode::ADDRESS, opcode::GAS, opcode::CALL, opcode::POP]);
+    asm.label("end").ops(&[opcode::STOP]);
+    Scenario::new(
+        "recursion",
+        "deep recursion of calls, frame creation heavy",
+        asm.build(),
+        word(depth),
+    )
+}
+
+/// Appends the header of a loop over `i` from `0` to `n`, with the stack holding `n i`.
+fn loop_header(asm: &mut Assembler) {
+    asm.label("loop")
+        .ops(&[opcode::DUP2, opcode::DUP2, opcode::LT, opcode::ISZERO])
+        .push_label("end")
+        .ops(&[opcode::JUMPI]);
+}
+
+/// Appends the end of a loop started with [loop_header].
+fn loop_footer(asm: &mut Assembler) {
+    asm.push(1)
+        .ops(&[opcode::ADD])
+        .push_label("loop")
+        .ops(&[opcode::JUMP]);
+    asm.label("end").ops(&[opcode::STOP]);
+}
+
+fn word(value: u64) -> Bytes {
+    U256::from(value).to_be_bytes::<32>().into()
+}
+
+fn insert_code(db: &mut ScenarioDB, address: Address, code: Bytes) {
+    let code = Bytecode::new_raw(code);
+    db.insert_account_info(
+        address,
+        AccountInfo::new(U256::ZERO, 1, code.hash_slow(), code),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scenarios_succeed() {
+        for scenario in scenarios() {
+            let mut evm = scenario.evm();
+            let result = scenario.run(&mut evm).unwrap();
+            assert!(result.is_success(), "{}: {result:?}", scenario.name);
+        }
+    }
+
+    #[test]
+    fn transfer_balances() {
+        let scenario = erc20_transfers(3);
+        let mut evm = scenario.evm();
+        let state = evm.transact().unwrap().state;
+        // The balance of the caller is moved to three holders.
+        let mut balances: Vec<_> = state[&TARGET]
+            .storage
+            .values()
+            .map(|slot| slot.present_value())
+            .collect();
+        balances.sort_unstable();
+        assert_eq!(
+            balances,
+            [U256::ZERO, U256::from(1), U256::from(1), U256::from(1)]
+        );
+    }
+}
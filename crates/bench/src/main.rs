@@ -0,0 +1,46 @@
+//! Runs the benchmark scenarios with the default configuration.
+//!
+//! Usage: `revm-bench [scenario] [iterations]`
+
+use revm_bench::scenarios;
+use std::process::ExitCode;
+
+const DEFAULT_ITERATIONS: u32 = 20;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let filter = args.next().filter(|name| name != "all");
+    let iterations = match args.next().map(|arg| arg.parse()) {
+        Some(Ok(iterations)) => iterations,
+        Some(Err(error)) => {
+            eprintln!("invalid number of iterations: {error}");
+            return ExitCode::FAILURE;
+        }
+        None => DEFAULT_ITERATIONS,
+    };
+
+    println!(
+        "{:<16} {:>12} {:>14} {:>10}",
+        "scenario", "gas", "mean", "Mgas/s"
+    );
+    for scenario in scenarios() {
+        if filter.as_deref().is_some_and(|name| name != scenario.name) {
+            continue;
+        }
+        let mut evm = scenario.evm();
+        match scenario.bench(&mut evm, iterations) {
+            Ok(measurement) => println!(
+                "{:<16} {:>12} {:>14?} {:>10.1}",
+                measurement.name,
+                measurement.gas_used,
+                measurement.mean(),
+                measurement.gas_per_second() / 1e6
+            ),
+            Err(error) => {
+                eprintln!("{}: {error}", scenario.name);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
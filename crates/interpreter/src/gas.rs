@@ -98,6 +98,14 @@ impl Gas {
         self.refunded = (self.refunded() as u64).min(self.spent() / max_refund_quotient) as i64;
     }
 
+    /// Caps the refund value to `max_refund`.
+    ///
+    /// Used instead of [Gas::set_final_refund] when the refund limits are configured.
+    #[inline]
+    pub fn cap_refund(&mut self, max_refund: u64) {
+        self.refunded = (self.refunded() as u64).min(max_refund) as i64;
+    }
+
     /// Set a refund value. This overrides the current refund value.
     #[inline]
     pub fn set_refund(&mut self, refund: i64) {
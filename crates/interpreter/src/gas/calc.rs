@@ -364,6 +364,20 @@ const fn new_cost(
     NEWACCOUNT
 }
 
+/// EIP-150: Gas cost changes for IO-heavy operations
+///
+/// Returns the gas forwarded to a sub frame: all but one 64th of the `remaining` gas of the
+/// caller, at most `requested`.
+#[inline]
+pub const fn forwarded_gas(remaining: u64, requested: u64) -> u64 {
+    let limit = remaining - remaining / 64;
+    if requested < limit {
+        requested
+    } else {
+        limit
+    }
+}
+
 /// Memory expansion cost calculation.
 #[inline]
 pub const fn memory_gas(a: usize) -> u64 {
@@ -424,3 +438,14 @@ pub fn validate_initial_tx_gas(
 
     initial_gas
 }
+
+/// EIP-7623: Increase calldata cost
+///
+/// Minimum gas a transaction is charged for its calldata. A zero byte counts as one token and
+/// a non-zero byte as four.
+pub fn calc_tx_floor_cost(input: &[u8]) -> u64 {
+    let zero_data_len = input.iter().filter(|v| **v == 0).count() as u64;
+    let non_zero_data_len = input.len() as u64 - zero_data_len;
+    let tokens = zero_data_len + non_zero_data_len * STANDARD_TOKEN_COST;
+    21000 + tokens * TOTAL_COST_FLOOR_PER_TOKEN
+}
@@ -41,4 +41,26 @@ pub const WARM_SSTORE_RESET: u64 = SSTORE_RESET - COLD_SLOAD_COST;
 /// EIP-3860 : Limit and meter initcode
 pub const INITCODE_WORD_COST: u64 = 2;
 
+/// EIP-7623: Increase calldata cost
+pub const STANDARD_TOKEN_COST: u64 = 4;
+pub const TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+
 pub const CALL_STIPEND: u64 = 2300;
+
+/// EIP-4200: EOF - Static relative jumps
+pub const CONDITION_JUMP_GAS: u64 = 4;
+/// EIP-4750: EOF - Functions
+pub const RETF_GAS: u64 = 3;
+/// EIP-7480: EOF - Data section access instructions
+pub const DATA_LOAD_GAS: u64 = 4;
+
+/// EIP-7069: Revamped CALL instructions
+pub const MIN_RETAINED_GAS: u64 = 5000;
+pub const MIN_CALLEE_GAS: u64 = 2300;
+
+/// EIP-4762: Statelessness gas cost changes
+pub const WITNESS_BRANCH_COST: u64 = 1900;
+pub const WITNESS_CHUNK_COST: u64 = 200;
+pub const SUBTREE_EDIT_COST: u64 = 3000;
+pub const CHUNK_EDIT_COST: u64 = 500;
+pub const CHUNK_FILL_COST: u64 = 6200;
@@ -1,4 +1,47 @@
+//! Boundary between the interpreter and the state it executes against.
+//!
+//! The interpreter does not depend on revm: everything it needs from the outside goes through
+//! the [Host] trait, so it can be embedded in other state machines by implementing [Host] for
+//! their state. Instructions that create frames are not executed by the interpreter, it stops
+//! and returns an [InterpreterAction](crate::InterpreterAction) instead:
+//!
+//! - [InterpreterAction::Call](crate::InterpreterAction::Call) and
+//!   [InterpreterAction::Create](crate::InterpreterAction::Create) ask the embedder to execute a
+//!   sub frame, usually with a new [Interpreter](crate::Interpreter), and to pass its outcome to
+//!   [Interpreter::insert_call_outcome](crate::Interpreter::insert_call_outcome) or
+//!   [Interpreter::insert_create_outcome](crate::Interpreter::insert_create_outcome) before
+//!   running the frame again. Value transfers, checkpoints and precompiles are up to the
+//!   embedder.
+//! - [InterpreterAction::Return](crate::InterpreterAction::Return) ends the frame.
+//!
+//! ```
+//! use revm_interpreter::{
+//!     opcode::{self, make_instruction_table},
+//!     primitives::{Bytecode, Bytes, CancunSpec},
+//!     Contract, DummyHost, Interpreter, SharedMemory,
+//! };
+//!
+//! // SSTORE(0, 1) STOP
+//! let code = Bytes::from_static(&[opcode::PUSH1, 1, opcode::PUSH0, opcode::SSTORE, opcode::STOP]);
+//! let contract = Contract::new(
+//!     Bytes::new(),
+//!     Bytecode::new_raw(code),
+//!     Default::default(),
+//!     Default::default(),
+//!     Default::default(),
+//!     Default::default(),
+//! );
+//!
+//! let mut host = DummyHost::default();
+//! let table = make_instruction_table::<DummyHost, CancunSpec>();
+//! let mut interpreter = Interpreter::new(contract, 100_000, false);
+//! let action = interpreter.run(SharedMemory::new(), &table, &mut host);
+//!
+//! assert!(action.into_result_return().unwrap().is_ok());
+//! assert_eq!(host.storage.len(), 1);
+//! ```
 use crate::{
+    gas,
     primitives::{Address, Bytecode, Env, Log, B256, U256},
     SelfDestructResult,
 };
@@ -6,7 +49,12 @@ use crate::{
 mod dummy;
 pub use dummy::DummyHost;
 
-/// EVM context host.
+/// EVM context host, see the [module documentation](self).
+///
+/// Methods returning `None` signal a failure of the host, for example a database error, which
+/// halts the interpreter with
+/// [InstructionResult::FatalExternalError](crate::InstructionResult::FatalExternalError). The
+/// embedder keeps the error to report it. The `is_cold` flags select the EIP-2929 access cost.
 pub trait Host {
     /// Returns a reference to the environment.
     fn env(&self) -> &Env;
@@ -50,6 +98,21 @@ pub trait Host {
 
     /// Mark `address` to be deleted, with funds transferred to `target`.
     fn selfdestruct(&mut self, address: Address, target: Address) -> Option<SelfDestructResult>;
+
+    /// Returns the gas forwarded to a sub frame by the `CALL` and `CREATE` instruction families
+    /// from Tangerine Whistle on, given the `remaining` gas of the caller once the instruction
+    /// is charged and the `requested` gas, `u64::MAX` for creations.
+    ///
+    /// Defaults to the 63/64 rule of EIP-150, see [gas::forwarded_gas]. The forwarded gas is
+    /// charged to the caller, forwarding more than `remaining` makes the instruction run out of
+    /// gas. EOF calls keep their own rule.
+    fn forwarded_gas(&mut self, remaining: u64, requested: u64) -> u64 {
+        gas::forwarded_gas(remaining, requested)
+    }
+
+    /// Validates the host state after an instruction and panics if it is inconsistent.
+    #[cfg(feature = "invariant-checks")]
+    fn check_invariants(&self) {}
 }
 
 /// Represents the result of an `sstore` operation.
@@ -77,4 +140,52 @@ mod tests {
         assert_host::<DummyHost>();
         assert_host::<dyn Host>();
     }
+
+    #[test]
+    fn embedder_executes_calls() {
+        use crate::{
+            opcode::{self, make_instruction_table},
+            primitives::{Bytes, CancunSpec},
+            CallOutcome, Contract, Gas, InstructionResult, Interpreter, InterpreterAction,
+            InterpreterResult, SharedMemory,
+        };
+
+        // SSTORE(0, CALL(gas, 0x42, 0, 0, 0, 0, 0)) STOP
+        let mut code = vec![opcode::PUSH0; 5];
+        code.extend([opcode::PUSH1, 0x42, opcode::GAS, opcode::CALL]);
+        code.extend([opcode::PUSH0, opcode::SSTORE, opcode::STOP]);
+        let contract = Contract::new(
+            Bytes::new(),
+            Bytecode::new_raw(code.into()),
+            B256::ZERO,
+            Address::ZERO,
+            Address::ZERO,
+            U256::ZERO,
+        );
+
+        let mut host = DummyHost::default();
+        let table = make_instruction_table::<DummyHost, CancunSpec>();
+        let mut interpreter = Interpreter::new(contract, 100_000, false);
+        let action = interpreter.run(SharedMemory::new(), &table, &mut host);
+        let InterpreterAction::Call { inputs } = action else {
+            panic!("expected a call");
+        };
+        assert_eq!(inputs.contract, Address::with_last_byte(0x42));
+
+        // The embedder executes the call, here it succeeds without using gas.
+        let mut memory = interpreter.take_memory();
+        let outcome = CallOutcome::new(
+            InterpreterResult::new(
+                InstructionResult::Stop,
+                Bytes::new(),
+                Gas::new(inputs.gas_limit),
+            ),
+            inputs.return_memory_offset.clone(),
+        );
+        interpreter.insert_call_outcome(&mut memory, outcome);
+        let action = interpreter.run(memory, &table, &mut host);
+
+        assert!(action.into_result_return().unwrap().is_ok());
+        assert_eq!(host.storage.get(&U256::ZERO), Some(&U256::from(1)));
+    }
 }
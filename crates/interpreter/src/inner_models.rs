@@ -1,5 +1,7 @@
 pub use crate::primitives::CreateScheme;
-use crate::primitives::{Address, Bytes, TransactTo, TxEnv, U256};
+use crate::primitives::{
+    create2_address, create_address, init_code_hash, Address, Bytes, TransactTo, TxEnv, U256,
+};
 use core::ops::Range;
 use std::boxed::Box;
 
@@ -37,6 +39,8 @@ pub struct CreateInputs {
     pub init_code: Bytes,
     /// The gas limit of the call.
     pub gas_limit: u64,
+    /// Call data of the init code. Only EOF init code receives call data.
+    pub input: Bytes,
 }
 
 impl CallInputs {
@@ -86,6 +90,7 @@ impl CreateInputs {
             value: tx_env.value,
             init_code: tx_env.data.clone(),
             gas_limit,
+            input: Bytes::new(),
         })
     }
 
@@ -97,10 +102,10 @@ impl CreateInputs {
     /// Returns the address that this create call will create.
     pub fn created_address(&self, nonce: u64) -> Address {
         match self.scheme {
-            CreateScheme::Create => self.caller.create(nonce),
-            CreateScheme::Create2 { salt } => self
-                .caller
-                .create2_from_code(salt.to_be_bytes(), &self.init_code),
+            CreateScheme::Create => create_address(self.caller, nonce),
+            CreateScheme::Create2 { salt } | CreateScheme::EofCreate { salt } => {
+                create2_address(self.caller, salt, init_code_hash(&self.init_code))
+            }
         }
     }
 }
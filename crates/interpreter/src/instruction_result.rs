@@ -10,11 +10,21 @@ pub enum InstructionResult {
     Stop,
     Return,
     SelfDestruct,
+    /// EOF `RETURNCONTRACT` returned the container to deploy.
+    ReturnContract,
+    /// Custom instruction of a chain extension ended the frame successfully. The output and the
+    /// state changes are kept, as for `RETURN`. The code chosen by the extension is in
+    /// [Interpreter::custom_code](crate::Interpreter::custom_code).
+    CustomReturn,
 
     // revert codes
     Revert = 0x10, // revert opcode
     CallTooDeep,
     OutOfFunds,
+    /// Custom instruction of a chain extension reverted the frame. The output is kept and the
+    /// state changes are reverted, as for `REVERT`. The code chosen by the extension is in
+    /// [Interpreter::custom_code](crate::Interpreter::custom_code).
+    CustomRevert,
 
     // Actions
     CallOrCreate = 0x20,
@@ -44,9 +54,36 @@ pub enum InstructionResult {
     CreateContractStartingWithEF,
     /// EIP-3860: Limit and meter initcode. Initcode size limit exceeded.
     CreateInitCodeSizeLimit,
+    /// Execution was cancelled by the host.
+    Cancelled,
+    /// Returned data exceeds the configured return data limit.
+    ReturnDataLimit,
+    /// The configured limit of executed instructions was reached.
+    StepLimit,
+    /// Code format or EOF version is not supported.
+    UnsupportedCodeVersion,
+    /// Opcode that is only valid in legacy code was found in EOF code.
+    LegacyOpcodeInEof,
+    /// EOF `CALLF` exceeded the return stack limit.
+    EOFFunctionStackOverflow,
+    /// Target of an EOF call is not a valid address.
+    InvalidEXTCALLTarget,
+    /// Init code container is not valid EOF.
+    InvalidEOFInitCode,
+    /// Legacy create called with EOF init code.
+    CreateInitCodeStartingEF00,
+    /// Deployed EOF data section is shorter than declared.
+    EofAuxDataTooSmall,
+    /// Deployed EOF data section is longer than the maximum size.
+    EofAuxDataOverflow,
 
     /// Fatal external error. Returned by database.
     FatalExternalError,
+    /// Custom instruction of a chain extension halted the frame. All gas and the state changes
+    /// are lost, the output is returned to the caller and reported as [HaltReason::Custom] with
+    /// the code in [Interpreter::custom_code](crate::Interpreter::custom_code) for the
+    /// transaction.
+    CustomHalt,
 }
 
 impl From<SuccessReason> for InstructionResult {
@@ -87,6 +124,18 @@ impl From<HaltReason> for InstructionResult {
             HaltReason::CallNotAllowedInsideStatic => Self::CallNotAllowedInsideStatic,
             HaltReason::OutOfFunds => Self::OutOfFunds,
             HaltReason::CallTooDeep => Self::CallTooDeep,
+            HaltReason::Cancelled => Self::Cancelled,
+            HaltReason::ReturnDataLimit => Self::ReturnDataLimit,
+            HaltReason::StepLimit => Self::StepLimit,
+            HaltReason::UnsupportedCodeVersion => Self::UnsupportedCodeVersion,
+            HaltReason::LegacyOpcodeInEof => Self::LegacyOpcodeInEof,
+            HaltReason::EOFFunctionStackOverflow => Self::EOFFunctionStackOverflow,
+            HaltReason::InvalidEXTCALLTarget => Self::InvalidEXTCALLTarget,
+            HaltReason::InvalidEOFInitCode => Self::InvalidEOFInitCode,
+            HaltReason::CreateInitCodeStartingEF00 => Self::CreateInitCodeStartingEF00,
+            HaltReason::EofAuxDataTooSmall => Self::EofAuxDataTooSmall,
+            HaltReason::EofAuxDataOverflow => Self::EofAuxDataOverflow,
+            HaltReason::Custom(_) => Self::CustomHalt,
             #[cfg(feature = "optimism")]
             HaltReason::FailedDeposit => Self::FatalExternalError,
         }
@@ -100,13 +149,18 @@ macro_rules! return_ok {
             | InstructionResult::Stop
             | InstructionResult::Return
             | InstructionResult::SelfDestruct
+            | InstructionResult::ReturnContract
+            | InstructionResult::CustomReturn
     };
 }
 
 #[macro_export]
 macro_rules! return_revert {
     () => {
-        InstructionResult::Revert | InstructionResult::CallTooDeep | InstructionResult::OutOfFunds
+        InstructionResult::Revert
+            | InstructionResult::CallTooDeep
+            | InstructionResult::OutOfFunds
+            | InstructionResult::CustomRevert
     };
 }
 
@@ -134,7 +188,19 @@ macro_rules! return_error {
             | InstructionResult::CreateContractSizeLimit
             | InstructionResult::CreateContractStartingWithEF
             | InstructionResult::CreateInitCodeSizeLimit
+            | InstructionResult::Cancelled
+            | InstructionResult::ReturnDataLimit
+            | InstructionResult::StepLimit
+            | InstructionResult::UnsupportedCodeVersion
+            | InstructionResult::LegacyOpcodeInEof
+            | InstructionResult::EOFFunctionStackOverflow
+            | InstructionResult::InvalidEXTCALLTarget
+            | InstructionResult::InvalidEOFInitCode
+            | InstructionResult::CreateInitCodeStartingEF00
+            | InstructionResult::EofAuxDataTooSmall
+            | InstructionResult::EofAuxDataOverflow
             | InstructionResult::FatalExternalError
+            | InstructionResult::CustomHalt
     };
 }
 
@@ -206,16 +272,18 @@ impl SuccessOrHalt {
             _ => None,
         }
     }
-}
 
-impl From<InstructionResult> for SuccessOrHalt {
-    fn from(result: InstructionResult) -> Self {
+    /// Converts `result`, with `custom_code` as the code of a [InstructionResult::CustomHalt],
+    /// which is kept out of band.
+    pub fn from_result(result: InstructionResult, custom_code: u8) -> Self {
         match result {
             InstructionResult::Continue => Self::InternalContinue, // used only in interpreter loop
             InstructionResult::Stop => Self::Success(SuccessReason::Stop),
             InstructionResult::Return => Self::Success(SuccessReason::Return),
             InstructionResult::SelfDestruct => Self::Success(SuccessReason::SelfDestruct),
-            InstructionResult::Revert => Self::Revert,
+            InstructionResult::ReturnContract => Self::Success(SuccessReason::Return),
+            InstructionResult::CustomReturn => Self::Success(SuccessReason::Return),
+            InstructionResult::Revert | InstructionResult::CustomRevert => Self::Revert,
             InstructionResult::CallOrCreate => Self::InternalCallOrCreate, // used only in interpreter loop
             InstructionResult::CallTooDeep => Self::Halt(HaltReason::CallTooDeep), // not gonna happen for first call
             InstructionResult::OutOfFunds => Self::Halt(HaltReason::OutOfFunds), // Check for first call is done separately.
@@ -254,14 +322,35 @@ impl From<InstructionResult> for SuccessOrHalt {
             InstructionResult::CreateInitCodeSizeLimit => {
                 Self::Halt(HaltReason::CreateInitCodeSizeLimit)
             }
+            InstructionResult::Cancelled => Self::Halt(HaltReason::Cancelled),
+            InstructionResult::ReturnDataLimit => Self::Halt(HaltReason::ReturnDataLimit),
+            InstructionResult::StepLimit => Self::Halt(HaltReason::StepLimit),
+            InstructionResult::UnsupportedCodeVersion => {
+                Self::Halt(HaltReason::UnsupportedCodeVersion)
+            }
+            InstructionResult::LegacyOpcodeInEof => Self::Halt(HaltReason::LegacyOpcodeInEof),
+            InstructionResult::EOFFunctionStackOverflow => {
+                Self::Halt(HaltReason::EOFFunctionStackOverflow)
+            }
+            InstructionResult::InvalidEXTCALLTarget => Self::Halt(HaltReason::InvalidEXTCALLTarget),
+            InstructionResult::InvalidEOFInitCode => Self::Halt(HaltReason::InvalidEOFInitCode),
+            InstructionResult::CreateInitCodeStartingEF00 => {
+                Self::Halt(HaltReason::CreateInitCodeStartingEF00)
+            }
+            InstructionResult::EofAuxDataTooSmall => Self::Halt(HaltReason::EofAuxDataTooSmall),
+            InstructionResult::EofAuxDataOverflow => Self::Halt(HaltReason::EofAuxDataOverflow),
             InstructionResult::FatalExternalError => Self::FatalExternalError,
+            InstructionResult::CustomHalt => Self::Halt(HaltReason::Custom(custom_code)),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::InstructionResult;
+    use crate::{
+        primitives::{HaltReason, SuccessReason},
+        InstructionResult, SuccessOrHalt,
+    };
 
     #[test]
     fn all_results_are_covered() {
@@ -273,6 +362,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn custom_results() {
+        assert_eq!(core::mem::size_of::<InstructionResult>(), 1);
+        assert_eq!(
+            SuccessOrHalt::from_result(InstructionResult::CustomReturn, 7),
+            SuccessOrHalt::Success(SuccessReason::Return)
+        );
+        assert_eq!(
+            SuccessOrHalt::from_result(InstructionResult::CustomRevert, 7),
+            SuccessOrHalt::Revert
+        );
+        let halt = SuccessOrHalt::from_result(InstructionResult::CustomHalt, 7);
+        assert_eq!(halt, SuccessOrHalt::Halt(HaltReason::Custom(7)));
+        assert_eq!(
+            InstructionResult::from(halt.to_halt().unwrap()),
+            InstructionResult::CustomHalt
+        );
+    }
+
     #[test]
     fn test_results() {
         let ok_results = vec![
@@ -280,6 +388,8 @@ mod tests {
             InstructionResult::Stop,
             InstructionResult::Return,
             InstructionResult::SelfDestruct,
+            InstructionResult::ReturnContract,
+            InstructionResult::CustomReturn,
         ];
 
         for result in ok_results {
@@ -292,6 +402,7 @@ mod tests {
             InstructionResult::Revert,
             InstructionResult::CallTooDeep,
             InstructionResult::OutOfFunds,
+            InstructionResult::CustomRevert,
         ];
 
         for result in revert_results {
@@ -322,7 +433,19 @@ mod tests {
             InstructionResult::CreateContractSizeLimit,
             InstructionResult::CreateContractStartingWithEF,
             InstructionResult::CreateInitCodeSizeLimit,
+            InstructionResult::Cancelled,
+            InstructionResult::ReturnDataLimit,
+            InstructionResult::StepLimit,
+            InstructionResult::UnsupportedCodeVersion,
+            InstructionResult::LegacyOpcodeInEof,
+            InstructionResult::EOFFunctionStackOverflow,
+            InstructionResult::InvalidEXTCALLTarget,
+            InstructionResult::InvalidEOFInitCode,
+            InstructionResult::CreateInitCodeStartingEF00,
+            InstructionResult::EofAuxDataTooSmall,
+            InstructionResult::EofAuxDataOverflow,
             InstructionResult::FatalExternalError,
+            InstructionResult::CustomHalt,
         ];
 
         for result in error_results {
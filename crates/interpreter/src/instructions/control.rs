@@ -1,9 +1,125 @@
 use crate::{
     gas,
-    primitives::{Bytes, Spec, U256},
-    Host, InstructionResult, Interpreter, InterpreterResult,
+    interpreter::FUNCTION_STACK_LIMIT,
+    primitives::{Bytes, Eof, Spec, U256},
+    Host, InstructionResult, Interpreter, InterpreterResult, STACK_LIMIT,
 };
 
+/// Reads a big-endian `i16` immediate.
+///
+/// # Safety
+///
+/// `ptr` must point to two readable bytes, which validation guarantees for EOF immediates.
+#[inline(always)]
+unsafe fn read_i16(ptr: *const u8) -> i16 {
+    i16::from_be_bytes([*ptr, *ptr.add(1)])
+}
+
+/// Reads a big-endian `u16` immediate, see [read_i16].
+#[inline(always)]
+unsafe fn read_u16(ptr: *const u8) -> u16 {
+    u16::from_be_bytes([*ptr, *ptr.add(1)])
+}
+
+/// EIP-4200: EOF - Static relative jumps
+pub fn rjump<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::BASE);
+    // The offset is relative to the end of the immediate.
+    let offset = unsafe { read_i16(interpreter.instruction_pointer) } as isize;
+    interpreter.instruction_pointer = unsafe { interpreter.instruction_pointer.offset(offset + 2) };
+}
+
+/// EIP-4200: EOF - Static relative jumps
+pub fn rjumpi<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::CONDITION_JUMP_GAS);
+    pop!(interpreter, condition);
+    let mut offset = 2;
+    if condition != U256::ZERO {
+        offset += unsafe { read_i16(interpreter.instruction_pointer) } as isize;
+    }
+    interpreter.instruction_pointer = unsafe { interpreter.instruction_pointer.offset(offset) };
+}
+
+/// EIP-4200: EOF - Static relative jumps
+pub fn rjumpv<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::CONDITION_JUMP_GAS);
+    pop!(interpreter, case);
+    let max_index = unsafe { *interpreter.instruction_pointer } as usize;
+    // One byte for the max index and two for each entry of the jump table.
+    let mut offset = (max_index as isize + 1) * 2 + 1;
+    let case = as_usize_saturated!(case);
+    if case <= max_index {
+        offset += unsafe { read_i16(interpreter.instruction_pointer.add(1 + case * 2)) } as isize;
+    }
+    interpreter.instruction_pointer = unsafe { interpreter.instruction_pointer.offset(offset) };
+}
+
+/// Fails the instruction if entering the code section `idx` would overflow the stack.
+#[inline(always)]
+fn check_function_stack(interpreter: &mut Interpreter, idx: usize) -> bool {
+    let Some(types) = interpreter
+        .contract
+        .eof
+        .as_ref()
+        .and_then(|eof| eof.types().get(idx).copied())
+    else {
+        interpreter.instruction_result = InstructionResult::OpcodeNotFound;
+        return false;
+    };
+    let height = interpreter.stack.len() + types.max_stack_height as usize;
+    if height - types.inputs as usize > STACK_LIMIT {
+        interpreter.instruction_result = InstructionResult::StackOverflow;
+        return false;
+    }
+    true
+}
+
+/// EIP-4750: EOF - Functions
+pub fn callf<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::LOW);
+    let idx = unsafe { read_u16(interpreter.instruction_pointer) } as usize;
+    if !check_function_stack(interpreter, idx) {
+        return;
+    }
+    if interpreter.function_stack.len() >= FUNCTION_STACK_LIMIT {
+        interpreter.instruction_result = InstructionResult::EOFFunctionStackOverflow;
+        return;
+    }
+    let pc = interpreter.program_counter() + 2;
+    interpreter.function_stack.push(pc, idx);
+    interpreter.load_code_section(idx);
+}
+
+/// EIP-4750: EOF - Functions
+pub fn retf<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::RETF_GAS);
+    let Some(frame) = interpreter.function_stack.pop() else {
+        // Validation rejects `RETF` in the first code section.
+        interpreter.instruction_result = InstructionResult::OpcodeNotFound;
+        return;
+    };
+    // SAFETY: the return address is within the code section of the caller.
+    interpreter.instruction_pointer =
+        unsafe { interpreter.contract.bytecode.as_ptr().add(frame.pc) };
+}
+
+/// EIP-6206: EOF - JUMPF and non-returning functions
+pub fn jumpf<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::LOW);
+    let idx = unsafe { read_u16(interpreter.instruction_pointer) } as usize;
+    if !check_function_stack(interpreter, idx) {
+        return;
+    }
+    interpreter.function_stack.set_current_code_idx(idx);
+    interpreter.load_code_section(idx);
+}
+
 pub fn jump<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
     gas!(interpreter, gas::MID);
     pop!(interpreter, target);
@@ -55,11 +171,7 @@ fn return_inner(interpreter: &mut Interpreter, instruction_result: InstructionRe
     }
     interpreter.instruction_result = instruction_result;
     interpreter.next_action = crate::InterpreterAction::Return {
-        result: InterpreterResult {
-            output,
-            gas: interpreter.gas,
-            result: instruction_result,
-        },
+        result: InterpreterResult::new(instruction_result, output, interpreter.gas),
     };
 }
 
@@ -67,6 +179,47 @@ pub fn ret<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
     return_inner(interpreter, InstructionResult::Return);
 }
 
+/// EIP-7620: EOF Contract Creation
+///
+/// Returns the container section to deploy with the memory range appended to its data.
+pub fn return_contract<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    let idx = unsafe { *interpreter.instruction_pointer } as usize;
+    let Some(container) = interpreter
+        .contract
+        .eof
+        .as_ref()
+        .and_then(|eof| eof.container_section(idx))
+        .and_then(|container| Eof::decode(container).ok())
+    else {
+        // Validation checks that the container exists.
+        interpreter.instruction_result = InstructionResult::OpcodeNotFound;
+        return;
+    };
+    pop!(interpreter, offset, len);
+    let len = as_usize_or_fail!(interpreter, len);
+    let mut aux_data: &[u8] = &[];
+    if len != 0 {
+        let offset = as_usize_or_fail!(interpreter, offset);
+        resize_memory!(interpreter, offset, len);
+        aux_data = interpreter.shared_memory.slice(offset, len);
+    }
+    let data_size = container.data_section().len() + aux_data.len();
+    if data_size < container.declared_data_size() as usize {
+        interpreter.instruction_result = InstructionResult::EofAuxDataTooSmall;
+        return;
+    }
+    let Some(output) = container.with_aux_data(aux_data) else {
+        interpreter.instruction_result = InstructionResult::EofAuxDataOverflow;
+        return;
+    };
+
+    interpreter.instruction_result = InstructionResult::ReturnContract;
+    interpreter.next_action = crate::InterpreterAction::Return {
+        result: InterpreterResult::new(InstructionResult::ReturnContract, output, interpreter.gas),
+    };
+}
+
 /// EIP-140: REVERT instruction
 pub fn revert<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, _host: &mut H) {
     check!(interpreter, BYZANTIUM);
@@ -87,3 +240,120 @@ pub fn invalid<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
 pub fn unknown<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
     interpreter.instruction_result = InstructionResult::OpcodeNotFound;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        opcode::make_instruction_table,
+        primitives::{hex_literal::hex, Address, Bytecode, OsakaSpec, B256},
+        Contract, DummyHost, EMPTY_SHARED_MEMORY,
+    };
+
+    /// Runs the validated runtime `container` until it halts.
+    fn run(container: &[u8], gas_limit: u64) -> Interpreter {
+        let contract = Contract::new(
+            Bytes::new(),
+            Bytecode::new_raw(Bytes::copy_from_slice(container)),
+            B256::ZERO,
+            Address::ZERO,
+            Address::ZERO,
+            U256::ZERO,
+        )
+        .with_eof();
+        assert!(contract.eof.is_some(), "container is invalid");
+        let mut interpreter = Interpreter::new(contract, gas_limit, false);
+        let table = make_instruction_table::<DummyHost, OsakaSpec>();
+        interpreter.run(EMPTY_SHARED_MEMORY, &table, &mut DummyHost::default());
+        interpreter
+    }
+
+    // PUSH1 3, loop: PUSH1 1 SWAP1 SUB DUP1 RJUMPI loop, STOP
+    const COUNTDOWN: [u8; 30] =
+        hex!("ef0001010004020001000b040000000080000260036001900380e1fff800");
+
+    #[test]
+    fn rjumpi_loop() {
+        // PUSH1 and three iterations of 16 gas.
+        let interpreter = run(&COUNTDOWN, 51);
+        assert_eq!(interpreter.instruction_result, InstructionResult::Stop);
+        assert_eq!(interpreter.stack.data(), &[U256::ZERO]);
+        assert_eq!(interpreter.gas.remaining(), 0);
+
+        let interpreter = run(&COUNTDOWN, 50);
+        assert_eq!(interpreter.instruction_result, InstructionResult::OutOfGas);
+    }
+
+    #[test]
+    fn rjumpv_and_rjump() {
+        // PUSH1 case RJUMPV [a, b]
+        // PUSH1 10 RJUMP end, a: PUSH1 11 RJUMP end, b: PUSH1 12, end: STOP
+        let switch = |case: u8| {
+            let container = [
+                &hex!("ef00010100040200010015040000000080000160")[..],
+                &[case],
+                &hex!("e2010005000a600ae00007600be00002600c00"),
+            ]
+            .concat();
+            run(&container, 100).stack.data().clone()
+        };
+        assert_eq!(switch(0), [U256::from(11)]);
+        assert_eq!(switch(1), [U256::from(12)]);
+        // Out of range cases fall through.
+        assert_eq!(switch(2), [U256::from(10)]);
+        assert_eq!(switch(0xff), [U256::from(10)]);
+    }
+
+    #[test]
+    fn functions() {
+        // CALLF 1 JUMPF 2, section 1: PUSH1 2 RETF, section 2: PUSH1 3 ADD STOP
+        let interpreter = run(
+            &hex!(
+                "ef000101000c02000300060003000404000000008000010001000101800002"
+                "e30001e500026002e460030100"
+            ),
+            100,
+        );
+        assert_eq!(interpreter.instruction_result, InstructionResult::Stop);
+        assert_eq!(interpreter.stack.data(), &[U256::from(5)]);
+        assert_eq!(interpreter.function_stack.len(), 0);
+        // CALLF, PUSH1, RETF, JUMPF, PUSH1 and ADD.
+        assert_eq!(interpreter.gas.spent(), 5 + 3 + 3 + 5 + 3 + 3);
+    }
+
+    #[test]
+    fn callf_stack_overflow() {
+        // CALLF 1 STOP, section 1: PUSH0 PUSH0 CALLF 1 POP POP RETF
+        let interpreter = run(
+            &hex!(
+                "ef000101000802000200040008040000000080000000000002"
+                "e30001005f5fe300015050e4"
+            ),
+            u64::MAX,
+        );
+        assert_eq!(
+            interpreter.instruction_result,
+            InstructionResult::StackOverflow
+        );
+        // Two items are pushed by every call, the last one would need two more.
+        assert_eq!(interpreter.stack.len(), STACK_LIMIT);
+        assert_eq!(interpreter.function_stack.len(), STACK_LIMIT / 2);
+    }
+
+    #[test]
+    fn callf_function_stack_overflow() {
+        // CALLF 1 STOP, section 1: CALLF 1 RETF
+        let interpreter = run(
+            &hex!(
+                "ef000101000802000200040004040000000080000000000000"
+                "e3000100e30001e4"
+            ),
+            u64::MAX,
+        );
+        assert_eq!(
+            interpreter.instruction_result,
+            InstructionResult::EOFFunctionStackOverflow
+        );
+        assert_eq!(interpreter.function_stack.len(), FUNCTION_STACK_LIMIT);
+    }
+}
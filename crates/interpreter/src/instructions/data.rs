@@ -0,0 +1,151 @@
+//! EIP-7480: EOF - Data section access instructions
+use crate::{
+    gas,
+    primitives::{B256, U256},
+    Contract, Host, Interpreter,
+};
+
+/// Returns the data section of the executed container.
+#[inline]
+fn data_section(contract: &Contract) -> &[u8] {
+    contract
+        .eof
+        .as_ref()
+        .map(|eof| eof.data_section())
+        .unwrap_or_default()
+}
+
+/// Reads a word of the data section, padded with zeros past its end.
+#[inline]
+fn read_word(data: &[u8], offset: usize) -> B256 {
+    let mut word = B256::ZERO;
+    if let Some(data) = data.get(offset..) {
+        let len = data.len().min(32);
+        word[..len].copy_from_slice(&data[..len]);
+    }
+    word
+}
+
+pub fn data_load<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::DATA_LOAD_GAS);
+    pop_top!(interpreter, offset_ptr);
+    let offset = as_usize_saturated!(offset_ptr);
+    *offset_ptr = read_word(data_section(&interpreter.contract), offset).into();
+}
+
+pub fn data_loadn<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::VERYLOW);
+    // The offset is checked to be within the declared data section by validation.
+    let offset = unsafe {
+        let ptr = interpreter.instruction_pointer;
+        u16::from_be_bytes([*ptr, *ptr.add(1)])
+    } as usize;
+    let word = read_word(data_section(&interpreter.contract), offset);
+    push_b256!(interpreter, word);
+    interpreter.instruction_pointer = unsafe { interpreter.instruction_pointer.add(2) };
+}
+
+pub fn data_size<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::BASE);
+    let len = data_section(&interpreter.contract).len();
+    push!(interpreter, U256::from(len));
+}
+
+pub fn data_copy<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    pop!(interpreter, memory_offset, data_offset, len);
+    let len = as_usize_or_fail!(interpreter, len);
+    gas_or_fail!(interpreter, gas::verylowcopy_cost(len as u64));
+    if len == 0 {
+        return;
+    }
+    let memory_offset = as_usize_or_fail!(interpreter, memory_offset);
+    let data_offset = as_usize_saturated!(data_offset);
+    resize_memory!(interpreter, memory_offset, len);
+
+    // Note: this can't panic because we resized memory to fit.
+    interpreter.shared_memory.set_data(
+        memory_offset,
+        data_offset,
+        len,
+        data_section(&interpreter.contract),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{hex_literal::hex, Address, Bytecode, Bytes, Env, Eof},
+        DummyHost, InstructionResult,
+    };
+    use std::sync::Arc;
+
+    fn interpreter() -> Interpreter {
+        // DATALOADN 1 STOP, data section 0xaabbcc
+        let container = hex!("ef000101000402000100040400030000800001d1000100aabbcc");
+        let mut contract = Contract::new(
+            Bytes::new(),
+            Bytecode::new_raw(container.to_vec().into()),
+            B256::ZERO,
+            Address::ZERO,
+            Address::ZERO,
+            U256::ZERO,
+        );
+        // `DATALOADN 1` reads past the data section, which validation rejects, so the container
+        // is set without it to check the padding.
+        contract.eof = Some(Arc::new(Eof::decode(container.to_vec().into()).unwrap()));
+        let mut interpreter = Interpreter::new(contract, u64::MAX, false);
+        interpreter.shared_memory = crate::SharedMemory::new();
+        interpreter
+    }
+
+    #[test]
+    fn load_and_size() {
+        let mut host = DummyHost::new(Env::default());
+        let mut interpreter = interpreter();
+
+        interpreter.stack.push(U256::from(1)).unwrap();
+        data_load(&mut interpreter, &mut host);
+        let mut expected = B256::ZERO;
+        expected[..2].copy_from_slice(&hex!("bbcc"));
+        assert_eq!(interpreter.stack.pop(), Ok(expected.into()));
+
+        // Skip the opcode, the instruction reads its immediate.
+        interpreter.instruction_pointer = unsafe { interpreter.instruction_pointer.add(1) };
+        data_loadn(&mut interpreter, &mut host);
+        assert_eq!(interpreter.stack.pop(), Ok(expected.into()));
+
+        data_size(&mut interpreter, &mut host);
+        assert_eq!(interpreter.stack.pop(), Ok(U256::from(3)));
+        assert_eq!(interpreter.instruction_result, InstructionResult::Continue);
+    }
+
+    #[test]
+    fn copy() {
+        let mut host = DummyHost::new(Env::default());
+        let mut interpreter = interpreter();
+
+        // Copy four bytes from offset 1 to memory offset 0.
+        interpreter.stack.push(U256::from(4)).unwrap();
+        interpreter.stack.push(U256::from(1)).unwrap();
+        interpreter.stack.push(U256::ZERO).unwrap();
+        data_copy(&mut interpreter, &mut host);
+        assert_eq!(interpreter.instruction_result, InstructionResult::Continue);
+        assert_eq!(interpreter.shared_memory.slice(0, 4), &hex!("bbcc0000"));
+    }
+
+    #[test]
+    fn requires_eof() {
+        let mut host = DummyHost::new(Env::default());
+        let mut interpreter = Interpreter::new(Contract::default(), u64::MAX, false);
+        data_size(&mut interpreter, &mut host);
+        assert_eq!(
+            interpreter.instruction_result,
+            InstructionResult::OpcodeNotFound
+        );
+    }
+}
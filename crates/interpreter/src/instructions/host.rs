@@ -1,11 +1,15 @@
 mod call_helpers;
 
-pub use call_helpers::{calc_call_gas, get_memory_input_and_out_ranges};
+pub use call_helpers::{
+    calc_call_gas, calc_extcall_gas, get_memory_input, get_memory_input_and_out_ranges,
+};
 
 use crate::{
     gas::{self, COLD_ACCOUNT_ACCESS_COST, WARM_STORAGE_READ_COST},
     interpreter::{Interpreter, InterpreterAction},
-    primitives::{Bytes, Log, LogData, Spec, SpecId::*, B256, U256},
+    primitives::{
+        Address, Bytes, Log, LogData, Spec, SpecId::*, B256, EOF_MAGIC, EOF_MAGIC_HASH, U256,
+    },
     CallContext, CallInputs, CallScheme, CreateInputs, CreateScheme, Host, InstructionResult,
     SStoreResult, Transfer, MAX_INITCODE_SIZE,
 };
@@ -65,7 +69,12 @@ pub fn extcodesize<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter,
         gas!(interpreter, 20);
     }
 
-    push!(interpreter, U256::from(code.len()));
+    // EIP-3540: EOF code is seen as the EOF magic by legacy code.
+    if SPEC::enabled(OSAKA) && code.code_version().is_eof() {
+        push!(interpreter, U256::from(EOF_MAGIC.len()));
+    } else {
+        push!(interpreter, U256::from(code.len()));
+    }
 }
 
 /// EIP-1052: EXTCODEHASH opcode
@@ -90,6 +99,17 @@ pub fn extcodehash<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter,
     } else {
         gas!(interpreter, 400);
     }
+    if SPEC::enabled(OSAKA) {
+        let Some((code, _)) = host.code(address) else {
+            interpreter.instruction_result = InstructionResult::FatalExternalError;
+            return;
+        };
+        // EIP-3540: EOF code is seen as the EOF magic by legacy code.
+        if code.code_version().is_eof() {
+            push_b256!(interpreter, EOF_MAGIC_HASH);
+            return;
+        }
+    }
     push_b256!(interpreter, code_hash);
 }
 
@@ -101,6 +121,12 @@ pub fn extcodecopy<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter,
         interpreter.instruction_result = InstructionResult::FatalExternalError;
         return;
     };
+    // EIP-3540: EOF code is seen as the EOF magic by legacy code.
+    let code = if SPEC::enabled(OSAKA) && code.code_version().is_eof() {
+        Bytes::from_static(&EOF_MAGIC)
+    } else {
+        code.bytes().clone()
+    };
 
     let len = as_usize_or_fail!(interpreter, len_u256);
     gas_or_fail!(
@@ -117,7 +143,7 @@ pub fn extcodecopy<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter,
     // Note: this can't panic because we resized memory to fit.
     interpreter
         .shared_memory
-        .set_data(memory_offset, code_offset, len, code.bytes());
+        .set_data(memory_offset, code_offset, len, &code);
 }
 
 pub fn blockhash<H: Host + ?Sized>(interpreter: &mut Interpreter, host: &mut H) {
@@ -299,8 +325,7 @@ pub fn create<const IS_CREATE2: bool, H: Host + ?Sized, SPEC: Spec>(
 
     // EIP-150: Gas cost changes for IO-heavy operations
     if SPEC::enabled(TANGERINE) {
-        // take remaining gas and deduce l64 part of it.
-        gas_limit -= gas_limit / 64
+        gas_limit = host.forwarded_gas(gas_limit, u64::MAX);
     }
     gas!(interpreter, gas_limit);
 
@@ -312,6 +337,7 @@ pub fn create<const IS_CREATE2: bool, H: Host + ?Sized, SPEC: Spec>(
             value,
             init_code: code,
             gas_limit,
+            input: Bytes::new(),
         }),
     };
     interpreter.instruction_result = InstructionResult::CallOrCreate;
@@ -523,3 +549,209 @@ pub fn static_call<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter,
     };
     interpreter.instruction_result = InstructionResult::CallOrCreate;
 }
+
+/// EIP-7620: EOF Contract Creation
+pub fn eofcreate<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
+    require_eof!(interpreter);
+    check_staticcall!(interpreter);
+    let idx = unsafe { *interpreter.instruction_pointer } as usize;
+    let Some(init_code) = interpreter
+        .contract
+        .eof
+        .as_ref()
+        .and_then(|eof| eof.container_section(idx))
+    else {
+        // Validation checks that the container exists.
+        interpreter.instruction_result = InstructionResult::OpcodeNotFound;
+        return;
+    };
+    pop!(interpreter, value, salt);
+    let Some(input) = get_memory_input(interpreter) else {
+        return;
+    };
+    // Same as `CREATE2`, the initcontainer is hashed to derive the address.
+    gas_or_fail!(interpreter, gas::create2_cost(init_code.len() as u64));
+
+    let gas_limit = host.forwarded_gas(interpreter.gas().remaining(), u64::MAX);
+    gas!(interpreter, gas_limit);
+
+    interpreter.instruction_pointer = unsafe { interpreter.instruction_pointer.offset(1) };
+    interpreter.next_action = InterpreterAction::Create {
+        inputs: Box::new(CreateInputs {
+            caller: interpreter.contract.address,
+            scheme: CreateScheme::EofCreate { salt },
+            value,
+            init_code,
+            gas_limit,
+            input,
+        }),
+    };
+    interpreter.instruction_result = InstructionResult::CallOrCreate;
+}
+
+/// Pops the target of an EOF call. Fails the instruction if it is not a valid address.
+#[inline]
+fn pop_extcall_target(interpreter: &mut Interpreter) -> Option<Address> {
+    pop_ret!(interpreter, target, None);
+    let target = B256::from(target);
+    if target[..12].iter().any(|byte| *byte != 0) {
+        interpreter.instruction_result = InstructionResult::InvalidEXTCALLTarget;
+        return None;
+    }
+    Some(Address::from_word(target))
+}
+
+/// Fails the EOF call without executing it, pushing the same result as a revert.
+#[inline]
+fn extcall_light_failure(interpreter: &mut Interpreter) {
+    interpreter.return_data_buffer = Bytes::new();
+    push!(interpreter, U256::from(1));
+}
+
+/// EIP-7069: Revamped CALL instructions
+pub fn extcall<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
+    require_eof!(interpreter);
+    let Some(target) = pop_extcall_target(interpreter) else {
+        return;
+    };
+    let Some(input) = get_memory_input(interpreter) else {
+        return;
+    };
+    pop!(interpreter, value);
+    let has_transfer = value != U256::ZERO;
+    if interpreter.is_static && has_transfer {
+        interpreter.instruction_result = InstructionResult::CallNotAllowedInsideStatic;
+        return;
+    }
+    let Some(gas_limit) =
+        calc_extcall_gas::<H, SPEC>(interpreter, host, target, has_transfer, true, true)
+    else {
+        return;
+    };
+    if gas_limit == 0 {
+        extcall_light_failure(interpreter);
+        return;
+    }
+    gas!(interpreter, gas_limit);
+
+    interpreter.next_action = InterpreterAction::Call {
+        inputs: Box::new(CallInputs {
+            contract: target,
+            transfer: Transfer {
+                source: interpreter.contract.address,
+                target,
+                value,
+            },
+            input,
+            gas_limit,
+            context: CallContext {
+                address: target,
+                caller: interpreter.contract.address,
+                code_address: target,
+                apparent_value: value,
+                scheme: CallScheme::Call,
+            },
+            is_static: interpreter.is_static,
+            return_memory_offset: 0..0,
+        }),
+    };
+    interpreter.instruction_result = InstructionResult::CallOrCreate;
+}
+
+/// EIP-7069: Revamped CALL instructions
+///
+/// Only EOF code can be the target of a delegate call from EOF code.
+pub fn extdelegatecall<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
+    require_eof!(interpreter);
+    let Some(target) = pop_extcall_target(interpreter) else {
+        return;
+    };
+    let Some(input) = get_memory_input(interpreter) else {
+        return;
+    };
+    let Some(gas_limit) =
+        calc_extcall_gas::<H, SPEC>(interpreter, host, target, false, false, false)
+    else {
+        return;
+    };
+    let Some((code, _)) = host.code(target) else {
+        interpreter.instruction_result = InstructionResult::FatalExternalError;
+        return;
+    };
+    if gas_limit == 0 || !code.code_version().is_eof() {
+        extcall_light_failure(interpreter);
+        return;
+    }
+    gas!(interpreter, gas_limit);
+
+    interpreter.next_action = InterpreterAction::Call {
+        inputs: Box::new(CallInputs {
+            contract: target,
+            // This is dummy send for StaticCall and DelegateCall,
+            // it should do nothing and not touch anything.
+            transfer: Transfer {
+                source: interpreter.contract.address,
+                target: interpreter.contract.address,
+                value: U256::ZERO,
+            },
+            input,
+            gas_limit,
+            context: CallContext {
+                address: interpreter.contract.address,
+                caller: interpreter.contract.caller,
+                code_address: target,
+                apparent_value: interpreter.contract.value,
+                scheme: CallScheme::DelegateCall,
+            },
+            is_static: interpreter.is_static,
+            return_memory_offset: 0..0,
+        }),
+    };
+    interpreter.instruction_result = InstructionResult::CallOrCreate;
+}
+
+/// EIP-7069: Revamped CALL instructions
+pub fn extstaticcall<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
+    require_eof!(interpreter);
+    let Some(target) = pop_extcall_target(interpreter) else {
+        return;
+    };
+    let Some(input) = get_memory_input(interpreter) else {
+        return;
+    };
+    let Some(gas_limit) =
+        calc_extcall_gas::<H, SPEC>(interpreter, host, target, false, false, true)
+    else {
+        return;
+    };
+    if gas_limit == 0 {
+        extcall_light_failure(interpreter);
+        return;
+    }
+    gas!(interpreter, gas_limit);
+
+    interpreter.next_action = InterpreterAction::Call {
+        inputs: Box::new(CallInputs {
+            contract: target,
+            // This is dummy send for StaticCall and DelegateCall,
+            // it should do nothing and not touch anything.
+            transfer: Transfer {
+                source: interpreter.contract.address,
+                target: interpreter.contract.address,
+                value: U256::ZERO,
+            },
+            input,
+            gas_limit,
+            context: CallContext {
+                address: target,
+                caller: interpreter.contract.address,
+                code_address: target,
+                apparent_value: U256::ZERO,
+                scheme: CallScheme::StaticCall,
+            },
+            is_static: true,
+            return_memory_offset: 0..0,
+        }),
+    };
+    interpreter.instruction_result = InstructionResult::CallOrCreate;
+}
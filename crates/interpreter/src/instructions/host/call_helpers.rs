@@ -4,7 +4,7 @@ use crate::{
     primitives::{Address, Bytes, Spec, SpecId::*},
     Host, InstructionResult,
 };
-use core::{cmp::min, ops::Range};
+use core::{cmp::max, ops::Range};
 
 #[inline]
 pub fn get_memory_input_and_out_ranges(
@@ -62,12 +62,62 @@ pub fn calc_call_gas<H: Host + ?Sized, SPEC: Spec>(
 
     // EIP-150: Gas cost changes for IO-heavy operations
     let gas_limit = if SPEC::enabled(TANGERINE) {
-        let gas = interpreter.gas().remaining();
-        // take l64 part of gas_limit
-        min(gas - gas / 64, local_gas_limit)
+        host.forwarded_gas(interpreter.gas().remaining(), local_gas_limit)
     } else {
         local_gas_limit
     };
 
     Some(gas_limit)
 }
+
+/// Pops the input offset and length of an EOF call and returns the input.
+#[inline]
+pub fn get_memory_input(interpreter: &mut Interpreter) -> Option<Bytes> {
+    pop_ret!(interpreter, in_offset, in_len, None);
+
+    let in_len = as_usize_or_fail_ret!(interpreter, in_len, None);
+    if in_len == 0 {
+        return Some(Bytes::new());
+    }
+    let in_offset = as_usize_or_fail_ret!(interpreter, in_offset, None);
+    resize_memory!(interpreter, in_offset, in_len, None);
+    Some(Bytes::copy_from_slice(
+        interpreter.shared_memory.slice(in_offset, in_len),
+    ))
+}
+
+/// EIP-7069: Revamped CALL instructions
+///
+/// Charges the cost of an EOF call and returns the gas forwarded to the callee, which is zero
+/// if the caller can not retain enough gas or the callee would get too little.
+#[inline]
+pub fn calc_extcall_gas<H: Host + ?Sized, SPEC: Spec>(
+    interpreter: &mut Interpreter,
+    host: &mut H,
+    target: Address,
+    has_transfer: bool,
+    is_call: bool,
+    is_call_or_staticcall: bool,
+) -> Option<u64> {
+    let Some((is_cold, exist)) = host.load_account(target) else {
+        interpreter.instruction_result = InstructionResult::FatalExternalError;
+        return None;
+    };
+    let call_cost = gas::call_cost(
+        SPEC::SPEC_ID,
+        has_transfer,
+        !exist,
+        is_cold,
+        is_call,
+        is_call_or_staticcall,
+    );
+    gas!(interpreter, call_cost, None);
+
+    let gas = interpreter.gas().remaining();
+    let retained = max(gas / 64, gas::MIN_RETAINED_GAS);
+    let gas_limit = gas.saturating_sub(retained);
+    if gas_limit < gas::MIN_CALLEE_GAS {
+        return Some(0);
+    }
+    Some(gas_limit)
+}
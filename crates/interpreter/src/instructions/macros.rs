@@ -23,6 +23,17 @@ macro_rules! check {
     };
 }
 
+/// Fails the instruction if the code is not executed as EOF.
+#[macro_export]
+macro_rules! require_eof {
+    ($interp:expr) => {
+        if !$interp.is_eof() {
+            $interp.instruction_result = $crate::InstructionResult::OpcodeNotFound;
+            return;
+        }
+    };
+}
+
 /// Records a `gas` cost and fails the instruction if it would exceed the available gas.
 #[macro_export]
 macro_rules! gas {
@@ -234,6 +245,9 @@ macro_rules! push {
 }
 
 /// Converts a `U256` value to a `u64`, saturating to `MAX` if the value is too large.
+///
+/// The limbs of a `U256` are ordered from the least significant one on every target, so this
+/// does not depend on the endianness of the target.
 #[macro_export]
 macro_rules! as_u64_saturated {
     ($v:expr) => {{
@@ -247,6 +261,8 @@ macro_rules! as_u64_saturated {
 }
 
 /// Converts a `U256` value to a `usize`, saturating to `MAX` if the value is too large.
+///
+/// On 32-bit targets, values above `u32::MAX` saturate.
 #[macro_export]
 macro_rules! as_usize_saturated {
     ($v:expr) => {
@@ -291,3 +307,31 @@ macro_rules! as_usize_or_fail_ret {
         val
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives::U256;
+
+    #[test]
+    fn saturated_conversions() {
+        assert_eq!(as_u64_saturated!(U256::ZERO), 0);
+        assert_eq!(as_u64_saturated!(U256::from(u64::MAX)), u64::MAX);
+        assert_eq!(
+            as_u64_saturated!(U256::from(u64::MAX) + U256::from(1)),
+            u64::MAX
+        );
+        assert_eq!(as_u64_saturated!(U256::from(1) << 255), u64::MAX);
+        assert_eq!(
+            as_u64_saturated!(U256::from(0x0102_0304_0506_0708_u64)),
+            0x0102_0304_0506_0708
+        );
+
+        assert_eq!(as_usize_saturated!(U256::from(u32::MAX)), u32::MAX as usize);
+        assert_eq!(as_usize_saturated!(U256::MAX), usize::MAX);
+        let above_u32 = U256::from(u64::from(u32::MAX) + 1);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(as_usize_saturated!(above_u32), 1 << 32);
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(as_usize_saturated!(above_u32), usize::MAX);
+    }
+}
@@ -3,7 +3,7 @@
 use super::*;
 use crate::{
     gas,
-    primitives::{Spec, SpecId},
+    primitives::{GasTable, Spec, SpecId},
     Host, Interpreter,
 };
 use core::fmt;
@@ -156,6 +156,10 @@ macro_rules! opcodes {
 
         /// Returns the instruction function for the given opcode and spec.
         pub const fn instruction<H: Host + ?Sized, SPEC: Spec>(opcode: u8) -> Instruction<H> {
+            // EOF opcodes are undefined before Osaka.
+            if OpCode(opcode).is_eof_only() && !SpecId::enabled(SPEC::SPEC_ID, SpecId::OSAKA) {
+                return control::unknown;
+            }
             match opcode {
                 $($name => $f,)*
                 _ => control::unknown,
@@ -384,10 +388,10 @@ opcodes! {
     // 0xCD
     // 0xCE
     // 0xCF
-    // 0xD0
-    // 0xD1
-    // 0xD2
-    // 0xD3
+    0xD0 => DATALOAD       => data::data_load,
+    0xD1 => DATALOADN      => data::data_loadn,
+    0xD2 => DATASIZE       => data::data_size,
+    0xD3 => DATACOPY       => data::data_copy,
     // 0xD4
     // 0xD5
     // 0xD6
@@ -400,38 +404,38 @@ opcodes! {
     // 0xDD
     // 0xDE
     // 0xDF
-    // 0xE0
-    // 0xE1
-    // 0xE2
-    // 0xE3
-    // 0xE4
-    // 0xE5
+    0xE0 => RJUMP          => control::rjump,
+    0xE1 => RJUMPI         => control::rjumpi,
+    0xE2 => RJUMPV         => control::rjumpv,
+    0xE3 => CALLF          => control::callf,
+    0xE4 => RETF           => control::retf,
+    0xE5 => JUMPF          => control::jumpf,
     // 0xE6
     // 0xE7
     // 0xE8
     // 0xE9
     // 0xEA
     // 0xEB
-    // 0xEC
+    0xEC => EOFCREATE      => host::eofcreate::<H, SPEC>,
     // 0xED
-    // 0xEE
+    0xEE => RETURNCONTRACT => control::return_contract,
     // 0xEF
-    0xF0 => CREATE       => host::create::<false, H, SPEC>,
-    0xF1 => CALL         => host::call::<H, SPEC>,
-    0xF2 => CALLCODE     => host::call_code::<H, SPEC>,
-    0xF3 => RETURN       => control::ret,
-    0xF4 => DELEGATECALL => host::delegate_call::<H, SPEC>,
-    0xF5 => CREATE2      => host::create::<true, H, SPEC>,
+    0xF0 => CREATE          => host::create::<false, H, SPEC>,
+    0xF1 => CALL            => host::call::<H, SPEC>,
+    0xF2 => CALLCODE        => host::call_code::<H, SPEC>,
+    0xF3 => RETURN          => control::ret,
+    0xF4 => DELEGATECALL    => host::delegate_call::<H, SPEC>,
+    0xF5 => CREATE2         => host::create::<true, H, SPEC>,
     // 0xF6
-    // 0xF7
-    // 0xF8
-    // 0xF9
-    0xFA => STATICCALL   => host::static_call::<H, SPEC>,
-    // 0xFB
+    0xF7 => RETURNDATALOAD  => system::returndataload,
+    0xF8 => EXTCALL         => host::extcall::<H, SPEC>,
+    0xF9 => EXTDELEGATECALL => host::extdelegatecall::<H, SPEC>,
+    0xFA => STATICCALL      => host::static_call::<H, SPEC>,
+    0xFB => EXTSTATICCALL   => host::extstaticcall::<H, SPEC>,
     // 0xFC
-    0xFD => REVERT       => control::revert::<H, SPEC>,
-    0xFE => INVALID      => control::invalid,
-    0xFF => SELFDESTRUCT => host::selfdestruct::<H, SPEC>,
+    0xFD => REVERT          => control::revert::<H, SPEC>,
+    0xFE => INVALID         => control::invalid,
+    0xFF => SELFDESTRUCT    => host::selfdestruct::<H, SPEC>,
 }
 
 /// An EVM opcode.
@@ -512,10 +516,66 @@ impl OpCode {
                 | OpCode::CODECOPY
                 | OpCode::CALLDATACOPY
                 | OpCode::RETURNDATACOPY
+                | OpCode::DATACOPY
+                | OpCode::CALL
+                | OpCode::CALLCODE
+                | OpCode::DELEGATECALL
+                | OpCode::STATICCALL
+                | OpCode::EOFCREATE
+                | OpCode::EXTCALL
+                | OpCode::EXTDELEGATECALL
+                | OpCode::EXTSTATICCALL
+        )
+    }
+
+    /// Returns true if the opcode is only valid in legacy code and is rejected in EOF code.
+    ///
+    /// EOF removes dynamic jumps, code and gas introspection, the legacy call and create
+    /// opcodes and `SELFDESTRUCT` (EIP-3670, EIP-4750, EIP-7069).
+    #[inline]
+    pub const fn is_legacy_only(&self) -> bool {
+        matches!(
+            *self,
+            OpCode::JUMP
+                | OpCode::JUMPI
+                | OpCode::PC
+                | OpCode::GAS
+                | OpCode::CODESIZE
+                | OpCode::CODECOPY
+                | OpCode::EXTCODESIZE
+                | OpCode::EXTCODECOPY
+                | OpCode::EXTCODEHASH
+                | OpCode::CREATE
+                | OpCode::CREATE2
                 | OpCode::CALL
                 | OpCode::CALLCODE
                 | OpCode::DELEGATECALL
                 | OpCode::STATICCALL
+                | OpCode::SELFDESTRUCT
+        )
+    }
+
+    /// Returns true if the opcode only exists in EOF code and is undefined in legacy code.
+    #[inline]
+    pub const fn is_eof_only(&self) -> bool {
+        matches!(
+            *self,
+            OpCode::DATALOAD
+                | OpCode::DATALOADN
+                | OpCode::DATASIZE
+                | OpCode::DATACOPY
+                | OpCode::RJUMP
+                | OpCode::RJUMPI
+                | OpCode::RJUMPV
+                | OpCode::CALLF
+                | OpCode::RETF
+                | OpCode::JUMPF
+                | OpCode::EOFCREATE
+                | OpCode::RETURNCONTRACT
+                | OpCode::RETURNDATALOAD
+                | OpCode::EXTCALL
+                | OpCode::EXTDELEGATECALL
+                | OpCode::EXTSTATICCALL
         )
     }
 }
@@ -595,6 +655,9 @@ impl OpInfo {
 }
 
 const fn opcode_gas_info(opcode: u8, spec: SpecId) -> OpInfo {
+    if OpCode(opcode).is_eof_only() && !SpecId::enabled(spec, SpecId::OSAKA) {
+        return OpInfo::none();
+    }
     match opcode {
         STOP => OpInfo::gas_block_end(0),
         ADD => OpInfo::gas(gas::VERYLOW),
@@ -877,10 +940,10 @@ const fn opcode_gas_info(opcode: u8, spec: SpecId) -> OpInfo {
         0xCD => OpInfo::none(),
         0xCE => OpInfo::none(),
         0xCF => OpInfo::none(),
-        0xD0 => OpInfo::none(),
-        0xD1 => OpInfo::none(),
-        0xD2 => OpInfo::none(),
-        0xD3 => OpInfo::none(),
+        DATALOAD => OpInfo::gas(gas::DATA_LOAD_GAS),
+        DATALOADN => OpInfo::gas(gas::VERYLOW),
+        DATASIZE => OpInfo::gas(gas::BASE),
+        DATACOPY => OpInfo::dynamic_gas(),
         0xD4 => OpInfo::none(),
         0xD5 => OpInfo::none(),
         0xD6 => OpInfo::none(),
@@ -893,21 +956,21 @@ const fn opcode_gas_info(opcode: u8, spec: SpecId) -> OpInfo {
         0xDD => OpInfo::none(),
         0xDE => OpInfo::none(),
         0xDF => OpInfo::none(),
-        0xE0 => OpInfo::none(),
-        0xE1 => OpInfo::none(),
-        0xE2 => OpInfo::none(),
-        0xE3 => OpInfo::none(),
-        0xE4 => OpInfo::none(),
-        0xE5 => OpInfo::none(),
+        RJUMP => OpInfo::gas_block_end(gas::BASE),
+        RJUMPI => OpInfo::gas_block_end(gas::CONDITION_JUMP_GAS),
+        RJUMPV => OpInfo::gas_block_end(gas::CONDITION_JUMP_GAS),
+        CALLF => OpInfo::gas_block_end(gas::LOW),
+        RETF => OpInfo::gas_block_end(gas::RETF_GAS),
+        JUMPF => OpInfo::gas_block_end(gas::LOW),
         0xE6 => OpInfo::none(),
         0xE7 => OpInfo::none(),
         0xE8 => OpInfo::none(),
         0xE9 => OpInfo::none(),
         0xEA => OpInfo::none(),
         0xEB => OpInfo::none(),
-        0xEC => OpInfo::none(),
+        EOFCREATE => OpInfo::gas_block_end(0),
         0xED => OpInfo::none(),
-        0xEE => OpInfo::none(),
+        RETURNCONTRACT => OpInfo::gas_block_end(0),
         0xEF => OpInfo::none(),
         CREATE => OpInfo::gas_block_end(0),
         CALL => OpInfo::gas_block_end(0),
@@ -916,11 +979,11 @@ const fn opcode_gas_info(opcode: u8, spec: SpecId) -> OpInfo {
         DELEGATECALL => OpInfo::gas_block_end(0),
         CREATE2 => OpInfo::gas_block_end(0),
         0xF6 => OpInfo::none(),
-        0xF7 => OpInfo::none(),
-        0xF8 => OpInfo::none(),
-        0xF9 => OpInfo::none(),
+        RETURNDATALOAD => OpInfo::gas(gas::VERYLOW),
+        EXTCALL => OpInfo::gas_block_end(0),
+        EXTDELEGATECALL => OpInfo::gas_block_end(0),
         STATICCALL => OpInfo::gas_block_end(0),
-        0xFB => OpInfo::none(),
+        EXTSTATICCALL => OpInfo::gas_block_end(0),
         0xFC => OpInfo::none(),
         REVERT => OpInfo::gas_block_end(0),
         INVALID => OpInfo::gas_block_end(0),
@@ -993,6 +1056,21 @@ pub const fn spec_opcode_gas(spec_id: SpecId) -> &'static [OpInfo; 256] {
         MERGE,
         SHANGHAI,
         CANCUN,
+        PRAGUE,
+        OSAKA,
         LATEST,
     )
 }
+
+/// Returns the static opcode costs of the given [`SpecId`] as a [`GasTable`], to be repriced and
+/// set in [`CfgEnv::gas_table`](crate::primitives::CfgEnv::gas_table).
+///
+/// Opcodes whose whole cost is dynamic, such as `SLOAD` or `CALL`, have a cost of zero, and a
+/// cost set for them is charged on top of their dynamic cost.
+pub fn spec_gas_table(spec_id: SpecId) -> GasTable {
+    let mut costs = [0; 256];
+    for (cost, info) in costs.iter_mut().zip(spec_opcode_gas(spec_id)) {
+        *cost = info.get_gas() as u64;
+    }
+    GasTable::new(costs)
+}
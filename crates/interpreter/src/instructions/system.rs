@@ -139,6 +139,22 @@ pub fn returndatacopy<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interprete
     }
 }
 
+/// EIP-7069: Revamped CALL instructions
+///
+/// Reads a word of the return data, padded with zeros past its end.
+pub fn returndataload<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    require_eof!(interpreter);
+    gas!(interpreter, gas::VERYLOW);
+    pop_top!(interpreter, offset_ptr);
+    let offset = as_usize_saturated!(offset_ptr);
+    let mut word = B256::ZERO;
+    if let Some(data) = interpreter.return_data_buffer.get(offset..) {
+        let len = data.len().min(32);
+        word[..len].copy_from_slice(&data[..len]);
+    }
+    *offset_ptr = word.into();
+}
+
 pub fn gas<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
     gas!(interpreter, gas::BASE);
     push!(interpreter, U256::from(interpreter.gas.remaining()));
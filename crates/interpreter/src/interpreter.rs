@@ -1,16 +1,24 @@
 pub mod analysis;
 mod contract;
+mod eof_compat;
+mod eof_validation;
+mod function_stack;
+#[cfg(feature = "invariant-checks")]
+mod invariants;
 mod shared_memory;
 mod stack;
 
 pub use analysis::BytecodeLocked;
 pub use contract::Contract;
+pub use eof_compat::{EofCompatibility, LegacyCodeChange};
+pub use eof_validation::{validate_eof, ContainerKind, EofValidationError};
+pub use function_stack::{FunctionReturnFrame, FunctionStack, FUNCTION_STACK_LIMIT};
 pub use shared_memory::{next_multiple_of_32, SharedMemory, EMPTY_SHARED_MEMORY};
 pub use stack::{Stack, STACK_LIMIT};
 
 use crate::{
     primitives::Bytes, push, push_b256, return_ok, return_revert, CallInputs, CallOutcome,
-    CreateInputs, CreateOutcome, Gas, Host, InstructionResult,
+    CreateInputs, CreateOutcome, Gas, Host, InstructionResult, SuccessOrHalt,
 };
 use core::cmp::min;
 use revm_primitives::U256;
@@ -49,6 +57,14 @@ pub struct Interpreter {
     /// Set inside CALL or CREATE instructions and RETURN or REVERT instructions. Additionally those instructions will set
     /// InstructionResult to CallOrCreate/Return/Revert so we know the reason.
     pub next_action: InterpreterAction,
+    /// Return stack of the EOF functions being executed.
+    pub function_stack: FunctionStack,
+    /// Code chosen by a chain extension for a custom instruction result, such as
+    /// [InstructionResult::CustomHalt]. Zero otherwise.
+    pub custom_code: u8,
+    /// Counters of the instructions executed since they were last taken.
+    #[cfg(feature = "instrumentation")]
+    pub counters: revm_primitives::ExecutionCounters,
 }
 
 /// The result of an interpreter operation.
@@ -60,6 +76,8 @@ pub struct InterpreterResult {
     pub output: Bytes,
     /// The gas usage information.
     pub gas: Gas,
+    /// Code chosen by a chain extension for a custom instruction result, zero otherwise.
+    pub custom_code: u8,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -116,8 +134,15 @@ impl InterpreterAction {
 impl Interpreter {
     /// Create new interpreter
     pub fn new(contract: Contract, gas_limit: u64, is_static: bool) -> Self {
+        // EOF code starts at the first code section, legacy code at its first byte.
+        let start = contract
+            .eof
+            .as_ref()
+            .and_then(|eof| eof.code_section_range(0))
+            .map_or(0, |range| range.start);
         Self {
-            instruction_pointer: contract.bytecode.as_ptr(),
+            // SAFETY: the first code section is within the container.
+            instruction_pointer: unsafe { contract.bytecode.as_ptr().add(start) },
             contract,
             gas: Gas::new(gas_limit),
             instruction_result: InstructionResult::Continue,
@@ -126,9 +151,38 @@ impl Interpreter {
             shared_memory: EMPTY_SHARED_MEMORY,
             stack: Stack::new(),
             next_action: InterpreterAction::None,
+            function_stack: FunctionStack::new(),
+            custom_code: 0,
+            #[cfg(feature = "instrumentation")]
+            counters: Default::default(),
         }
     }
 
+    /// Returns `true` if the code is executed as EOF.
+    #[inline]
+    pub fn is_eof(&self) -> bool {
+        self.contract.eof.is_some()
+    }
+
+    /// Continues execution at the start of the EOF code section `idx`.
+    ///
+    /// Sections are checked to exist when the container is validated.
+    #[inline]
+    pub(crate) fn load_code_section(&mut self, idx: usize) {
+        let Some(start) = self
+            .contract
+            .eof
+            .as_ref()
+            .and_then(|eof| eof.code_section_range(idx))
+            .map(|range| range.start)
+        else {
+            self.instruction_result = InstructionResult::OpcodeNotFound;
+            return;
+        };
+        // SAFETY: the code section is within the container.
+        self.instruction_pointer = unsafe { self.contract.bytecode.as_ptr().add(start) };
+    }
+
     /// Inserts the output of a `create` call into the interpreter.
     ///
     /// This function is used after a `create` call has been executed. It processes the outcome
@@ -220,6 +274,8 @@ impl Interpreter {
         self.return_data_buffer.clone_from(call_outcome.output());
         let target_len = min(out_len, self.return_data_buffer.len());
 
+        // EOF calls push 0 on success, 1 on revert and 2 on failure.
+        let is_eof = self.is_eof();
         match call_outcome.instruction_result() {
             return_ok!() => {
                 // return unspend gas.
@@ -228,18 +284,18 @@ impl Interpreter {
                 self.gas.erase_cost(remaining);
                 self.gas.record_refund(refunded);
                 shared_memory.set(out_offset, &self.return_data_buffer[..target_len]);
-                push!(self, U256::from(1));
+                push!(self, if is_eof { U256::ZERO } else { U256::from(1) });
             }
             return_revert!() => {
                 self.gas.erase_cost(call_outcome.gas().remaining());
                 shared_memory.set(out_offset, &self.return_data_buffer[..target_len]);
-                push!(self, U256::ZERO);
+                push!(self, if is_eof { U256::from(1) } else { U256::ZERO });
             }
             InstructionResult::FatalExternalError => {
                 panic!("Fatal external error in insert_call_outcome");
             }
             _ => {
-                push!(self, U256::ZERO);
+                push!(self, if is_eof { U256::from(2) } else { U256::ZERO });
             }
         }
     }
@@ -289,6 +345,8 @@ impl Interpreter {
     {
         // Get current opcode.
         let opcode = unsafe { *self.instruction_pointer };
+        #[cfg(feature = "instrumentation")]
+        let memory_len = self.shared_memory.len();
 
         // SAFETY: In analysis we are doing padding of bytecode so that we are sure that last
         // byte instruction is STOP so we are safe to just increment program_counter bcs on last instruction
@@ -296,7 +354,37 @@ impl Interpreter {
         self.instruction_pointer = unsafe { self.instruction_pointer.offset(1) };
 
         // execute instruction.
-        (instruction_table[opcode as usize])(self, host)
+        (instruction_table[opcode as usize])(self, host);
+
+        #[cfg(feature = "instrumentation")]
+        self.count_instruction(opcode, self.shared_memory.len() > memory_len);
+
+        #[cfg(feature = "invariant-checks")]
+        {
+            self.check_invariants(opcode);
+            host.check_invariants();
+        }
+    }
+
+    /// Records an executed instruction in the counters.
+    #[cfg(feature = "instrumentation")]
+    #[inline]
+    fn count_instruction(&mut self, op: u8, expanded_memory: bool) {
+        use crate::opcode;
+
+        let counters = &mut self.counters;
+        counters.instructions += 1;
+        counters.memory_expansions += expanded_memory as u64;
+        match op {
+            opcode::SLOAD => counters.sloads += 1,
+            opcode::SSTORE => counters.sstores += 1,
+            opcode::CALL | opcode::EXTCALL => counters.calls += 1,
+            opcode::CALLCODE => counters.call_codes += 1,
+            opcode::DELEGATECALL | opcode::EXTDELEGATECALL => counters.delegate_calls += 1,
+            opcode::STATICCALL | opcode::EXTSTATICCALL => counters.static_calls += 1,
+            opcode::CREATE | opcode::CREATE2 | opcode::EOFCREATE => counters.creates += 1,
+            _ => {}
+        }
     }
 
     /// Take memory and replace it with empty memory.
@@ -304,6 +392,24 @@ impl Interpreter {
         core::mem::replace(&mut self.shared_memory, EMPTY_SHARED_MEMORY)
     }
 
+    /// Ends the frame with `result` and `output`.
+    ///
+    /// Used by custom instructions of chain extensions to terminate execution with one of the
+    /// custom results, such as [InstructionResult::CustomHalt], a code for it and data for the
+    /// caller. The output of a halted frame is otherwise empty.
+    pub fn halt(&mut self, result: InstructionResult, custom_code: u8, output: Bytes) {
+        self.instruction_result = result;
+        self.custom_code = custom_code;
+        self.next_action = InterpreterAction::Return {
+            result: InterpreterResult {
+                result,
+                output,
+                gas: self.gas,
+                custom_code,
+            },
+        };
+    }
+
     /// Executes the interpreter until it returns or stops.
     pub fn run<FN, H: Host + ?Sized>(
         &mut self,
@@ -332,12 +438,30 @@ impl Interpreter {
                 // return empty bytecode
                 output: Bytes::new(),
                 gas: self.gas,
+                custom_code: self.custom_code,
             },
         }
     }
 }
 
 impl InterpreterResult {
+    /// Returns a new result without a custom code.
+    #[inline]
+    pub const fn new(result: InstructionResult, output: Bytes, gas: Gas) -> Self {
+        Self {
+            result,
+            output,
+            gas,
+            custom_code: 0,
+        }
+    }
+
+    /// Converts the result, with its custom code, see [SuccessOrHalt::from_result].
+    #[inline]
+    pub fn success_or_halt(&self) -> SuccessOrHalt {
+        SuccessOrHalt::from_result(self.result, self.custom_code)
+    }
+
     /// Returns whether the instruction result is a success.
     #[inline]
     pub const fn is_ok(&self) -> bool {
@@ -361,7 +485,7 @@ impl InterpreterResult {
 mod tests {
     use super::*;
     use crate::{opcode::InstructionTable, DummyHost};
-    use revm_primitives::CancunSpec;
+    use revm_primitives::{Address, Bytecode, CancunSpec, B256};
 
     #[test]
     fn object_safety() {
@@ -377,4 +501,37 @@ mod tests {
             crate::opcode::make_instruction_table::<dyn Host, CancunSpec>();
         let _ = interp.run(EMPTY_SHARED_MEMORY, &table, host);
     }
+
+    #[test]
+    fn custom_halt_with_output() {
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x0c]));
+        let contract = Contract::new(
+            Bytes::new(),
+            code,
+            B256::ZERO,
+            Address::ZERO,
+            Address::ZERO,
+            U256::ZERO,
+        );
+        let mut interp = Interpreter::new(contract, 1_000, false);
+        let mut table: InstructionTable<DummyHost> =
+            crate::opcode::make_instruction_table::<DummyHost, CancunSpec>();
+        table[0x0c] = |interp, _| {
+            interp.halt(
+                InstructionResult::CustomHalt,
+                3,
+                Bytes::from_static(&[1, 2]),
+            )
+        };
+
+        let mut host = DummyHost::default();
+        let action = interp.run(EMPTY_SHARED_MEMORY, &table, &mut host);
+        let InterpreterAction::Return { result } = action else {
+            panic!("unexpected action {action:?}");
+        };
+        assert_eq!(result.result, InstructionResult::CustomHalt);
+        assert_eq!(result.custom_code, 3);
+        assert!(result.is_error());
+        assert_eq!(result.output, Bytes::from_static(&[1, 2]));
+    }
 }
@@ -1,6 +1,8 @@
 use super::analysis::{to_analysed, BytecodeLocked};
-use crate::primitives::{Address, Bytecode, Bytes, Env, TransactTo, B256, U256};
+use super::eof_validation::{validate_eof, ContainerKind};
+use crate::primitives::{Address, Bytecode, Bytes, Env, Eof, TransactTo, B256, U256};
 use crate::CallContext;
+use std::sync::Arc;
 
 /// EVM contract information.
 #[derive(Clone, Debug, Default)]
@@ -18,6 +20,8 @@ pub struct Contract {
     pub caller: Address,
     /// Value send to contract.
     pub value: U256,
+    /// EOF container of the code, if the code is executed as EOF.
+    pub eof: Option<Arc<Eof>>,
 }
 
 impl Contract {
@@ -40,6 +44,7 @@ impl Contract {
             address,
             caller,
             value,
+            eof: None,
         }
     }
 
@@ -78,6 +83,24 @@ impl Contract {
         )
     }
 
+    /// Executes the code as EOF if it is a valid EOF container, see [Contract::validated_eof].
+    #[inline]
+    pub fn with_eof(mut self) -> Self {
+        self.eof = Self::validated_eof(self.bytecode.original_bytecode());
+        self
+    }
+
+    /// Decodes and validates the runtime container in `code`.
+    ///
+    /// Returns `None` if `code` is not a valid container. Such code runs as legacy code, which
+    /// halts on the leading `0xEF` byte, as the unchecked immediates of the EOF instructions are
+    /// only safe to read in validated code.
+    pub fn validated_eof(code: Bytes) -> Option<Arc<Eof>> {
+        let eof = Eof::decode(code).ok()?;
+        validate_eof(&eof, ContainerKind::Runtime).ok()?;
+        Some(Arc::new(eof))
+    }
+
     /// Returns whether the given position is a valid jump destination.
     #[inline]
     pub fn is_valid_jump(&self, pos: usize) -> bool {
@@ -0,0 +1,211 @@
+//! Checks of how far legacy code is from being expressible as EOF code.
+//!
+//! The analysis is best effort: data appended after the code, other than compiler metadata, is
+//! decoded as instructions as well.
+use crate::{
+    opcode::{self, OpCode},
+    primitives::{CodeMetadata, U256},
+};
+use std::vec::Vec;
+
+/// Change needed to express an instruction of legacy code in EOF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LegacyCodeChange {
+    /// `JUMP` or `JUMPI` to a destination pushed right before it, which translates to `RJUMP`
+    /// or `RJUMPI`.
+    StaticJump {
+        /// Position of the jump.
+        pc: usize,
+        /// Jump destination.
+        target: usize,
+    },
+    /// `JUMP` or `JUMPI` to a computed destination, which EOF can not express.
+    DynamicJump {
+        /// Position of the jump.
+        pc: usize,
+    },
+    /// Opcode removed in EOF.
+    DeprecatedOpcode {
+        /// Position of the instruction.
+        pc: usize,
+        /// The removed opcode.
+        opcode: OpCode,
+        /// EOF opcode covering the same use, if there is one.
+        replacement: Option<OpCode>,
+    },
+    /// Byte that is not a defined opcode in legacy code.
+    UndefinedOpcode {
+        /// Position of the byte.
+        pc: usize,
+        /// The byte.
+        opcode: u8,
+    },
+    /// `PUSH` whose immediate is cut by the end of the code.
+    TruncatedPush {
+        /// Position of the push.
+        pc: usize,
+    },
+}
+
+impl LegacyCodeChange {
+    /// Returns `true` if the change can be made without changing the behavior of the code.
+    pub const fn is_translatable(&self) -> bool {
+        matches!(self, Self::StaticJump { .. })
+    }
+}
+
+/// Report of the changes needed to express legacy code in EOF.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EofCompatibility {
+    /// Changes needed, in code order.
+    pub changes: Vec<LegacyCodeChange>,
+}
+
+impl EofCompatibility {
+    /// Analyzes legacy code, ignoring the compiler metadata appended to it.
+    pub fn analyze(code: &[u8]) -> Self {
+        let code = match CodeMetadata::parse(code) {
+            Some(metadata) => &code[..code.len() - metadata.len],
+            None => code,
+        };
+
+        let mut changes = Vec::new();
+        // Destination pushed by the previous instruction.
+        let mut pushed = None;
+        let mut pc = 0;
+        while pc < code.len() {
+            let byte = code[pc];
+            let Some(op) = OpCode::new(byte).filter(|op| !op.is_eof_only()) else {
+                changes.push(LegacyCodeChange::UndefinedOpcode { pc, opcode: byte });
+                pushed = None;
+                pc += 1;
+                continue;
+            };
+
+            if (opcode::PUSH1..=opcode::PUSH32).contains(&byte) {
+                let len = (byte - opcode::PUSH1 + 1) as usize;
+                let Some(immediate) = code.get(pc + 1..pc + 1 + len) else {
+                    changes.push(LegacyCodeChange::TruncatedPush { pc });
+                    break;
+                };
+                pushed = usize::try_from(U256::from_be_slice(immediate)).ok();
+                pc += 1 + len;
+                continue;
+            }
+
+            if matches!(op, OpCode::JUMP | OpCode::JUMPI) {
+                changes.push(match pushed {
+                    Some(target) => LegacyCodeChange::StaticJump { pc, target },
+                    None => LegacyCodeChange::DynamicJump { pc },
+                });
+            } else if op.is_legacy_only() {
+                changes.push(LegacyCodeChange::DeprecatedOpcode {
+                    pc,
+                    opcode: op,
+                    replacement: eof_replacement(op),
+                });
+            }
+            pushed = None;
+            pc += 1;
+        }
+        Self { changes }
+    }
+
+    /// Returns `true` if all needed changes are translatable, see
+    /// [LegacyCodeChange::is_translatable].
+    pub fn is_compatible(&self) -> bool {
+        self.changes.iter().all(LegacyCodeChange::is_translatable)
+    }
+
+    /// Returns the deprecated opcodes used by the code, without duplicates.
+    pub fn deprecated_opcodes(&self) -> Vec<OpCode> {
+        let mut opcodes = Vec::new();
+        for change in &self.changes {
+            if let LegacyCodeChange::DeprecatedOpcode { opcode, .. } = change {
+                if !opcodes.contains(opcode) {
+                    opcodes.push(*opcode);
+                }
+            }
+        }
+        opcodes
+    }
+
+    /// Returns `true` if the code computes a jump destination.
+    pub fn has_dynamic_jumps(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| matches!(change, LegacyCodeChange::DynamicJump { .. }))
+    }
+}
+
+/// Returns the EOF opcode covering the use of a deprecated legacy opcode.
+const fn eof_replacement(op: OpCode) -> Option<OpCode> {
+    Some(match op {
+        OpCode::CALL => OpCode::EXTCALL,
+        OpCode::DELEGATECALL => OpCode::EXTDELEGATECALL,
+        OpCode::STATICCALL => OpCode::EXTSTATICCALL,
+        OpCode::CREATE | OpCode::CREATE2 => OpCode::EOFCREATE,
+        // Code is mostly copied to read constants, which EOF keeps in the data section.
+        OpCode::CODESIZE => OpCode::DATASIZE,
+        OpCode::CODECOPY => OpCode::DATACOPY,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::hex_literal::hex;
+
+    #[test]
+    fn static_and_dynamic_jumps() {
+        // PUSH1 4 JUMP INVALID JUMPDEST CALLVALUE JUMPI
+        let report = EofCompatibility::analyze(&hex!("600456fe5b3457"));
+        assert_eq!(
+            report.changes,
+            [
+                LegacyCodeChange::StaticJump { pc: 2, target: 4 },
+                LegacyCodeChange::DynamicJump { pc: 6 },
+            ]
+        );
+        assert!(report.has_dynamic_jumps());
+        assert!(!report.is_compatible());
+
+        let report = EofCompatibility::analyze(&hex!("600456fe5b00"));
+        assert!(report.is_compatible());
+    }
+
+    #[test]
+    fn deprecated_and_undefined_opcodes() {
+        // GAS CALL CALL RJUMP PUSH2 0x01
+        let report = EofCompatibility::analyze(&hex!("5af1f1e06101"));
+        assert_eq!(report.deprecated_opcodes(), [OpCode::GAS, OpCode::CALL]);
+        assert_eq!(
+            report.changes[1],
+            LegacyCodeChange::DeprecatedOpcode {
+                pc: 1,
+                opcode: OpCode::CALL,
+                replacement: Some(OpCode::EXTCALL),
+            }
+        );
+        assert_eq!(
+            report.changes[3..],
+            [
+                LegacyCodeChange::UndefinedOpcode {
+                    pc: 3,
+                    opcode: 0xe0
+                },
+                LegacyCodeChange::TruncatedPush { pc: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_metadata() {
+        // STOP followed by {"solc": 0.8.26}
+        let report = EofCompatibility::analyze(&hex!("00a164736f6c634300081a000a"));
+        assert!(report.changes.is_empty());
+    }
+}
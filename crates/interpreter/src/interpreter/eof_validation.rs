@@ -0,0 +1,583 @@
+//! Validation of EOF containers.
+//!
+//! Containers are validated once, when they are deployed, so that execution can rely on the
+//! code being well formed: every opcode is defined, immediates are not truncated, jumps land on
+//! instructions, code sections end with a terminating instruction and the stack height at every
+//! instruction is known statically (EIP-3670, EIP-4200, EIP-4750, EIP-5450, EIP-6206, EIP-7620).
+use crate::{
+    opcode,
+    primitives::{Eof, EofDecodeError, TypesSection, EOF_MAX_STACK_HEIGHT},
+    STACK_LIMIT,
+};
+use core::fmt;
+use std::{vec, vec::Vec};
+
+/// How the code of a container ends its execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContainerKind {
+    /// Init code, which returns the container to deploy with `RETURNCONTRACT` and can not use
+    /// `STOP` or `RETURN`.
+    Initcode,
+    /// Deployed code, which can not use `RETURNCONTRACT`.
+    Runtime,
+}
+
+/// Error returned when validating an EOF container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EofValidationError {
+    /// A subcontainer could not be decoded.
+    Decode(EofDecodeError),
+    /// The data section is shorter than declared.
+    MissingData,
+    /// The first code section takes inputs or returns, or a section exceeds the type limits.
+    InvalidTypes,
+    /// The opcode is not defined in EOF code.
+    UnknownOpcode(u8),
+    /// The immediate of the last instruction is truncated.
+    MissingImmediate,
+    /// A relative jump does not land on an instruction of its code section.
+    InvalidJumpTarget,
+    /// `CALLF` or `JUMPF` refers to a code section that does not exist.
+    InvalidCodeSectionIndex,
+    /// `CALLF` calls a section that never returns.
+    CallToNonReturning,
+    /// `EOFCREATE` or `RETURNCONTRACT` refers to a container that does not exist.
+    InvalidContainerIndex,
+    /// `DATALOADN` reads past the declared data section.
+    InvalidDataloadnOffset,
+    /// An instruction or code section can not be reached.
+    UnreachableCode,
+    /// A subcontainer is not referenced by any instruction.
+    UnreferencedContainer,
+    /// A subcontainer is referenced by both `EOFCREATE` and `RETURNCONTRACT`.
+    ContainerKindConflict,
+    /// The instruction is not allowed in this kind of container.
+    InvalidInstructionForKind,
+    /// Execution can run past the end of a code section.
+    NoTerminatingInstruction,
+    /// A section returns although it is declared non-returning, or never returns although it
+    /// is declared returning.
+    InvalidReturn,
+    /// An instruction takes more items than the stack can hold at that point.
+    StackUnderflow,
+    /// The stack can exceed its limit.
+    StackOverflow,
+    /// The stack height differs between the paths reaching an instruction where it must be
+    /// exact.
+    StackHeightMismatch,
+    /// The maximum stack height of a section differs from its type.
+    MaxStackHeightMismatch,
+}
+
+impl From<EofDecodeError> for EofValidationError {
+    fn from(error: EofDecodeError) -> Self {
+        Self::Decode(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EofValidationError {}
+
+impl fmt::Display for EofValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(error) => write!(f, "invalid subcontainer: {error}"),
+            Self::MissingData => f.write_str("data section is shorter than declared"),
+            Self::InvalidTypes => f.write_str("invalid code section types"),
+            Self::UnknownOpcode(opcode) => write!(f, "unknown opcode 0x{opcode:02X}"),
+            Self::MissingImmediate => f.write_str("truncated immediate"),
+            Self::InvalidJumpTarget => f.write_str("invalid relative jump target"),
+            Self::InvalidCodeSectionIndex => f.write_str("invalid code section index"),
+            Self::CallToNonReturning => f.write_str("call to a non-returning section"),
+            Self::InvalidContainerIndex => f.write_str("invalid container index"),
+            Self::InvalidDataloadnOffset => f.write_str("DATALOADN offset out of bounds"),
+            Self::UnreachableCode => f.write_str("unreachable code"),
+            Self::UnreferencedContainer => f.write_str("unreferenced subcontainer"),
+            Self::ContainerKindConflict => {
+                f.write_str("subcontainer is used both as init code and as runtime code")
+            }
+            Self::InvalidInstructionForKind => {
+                f.write_str("instruction not allowed in this kind of container")
+            }
+            Self::NoTerminatingInstruction => f.write_str("code section does not terminate"),
+            Self::InvalidReturn => f.write_str("code section returns against its type"),
+            Self::StackUnderflow => f.write_str("stack underflow"),
+            Self::StackOverflow => f.write_str("stack overflow"),
+            Self::StackHeightMismatch => f.write_str("stack height mismatch"),
+            Self::MaxStackHeightMismatch => f.write_str("max stack height mismatch"),
+        }
+    }
+}
+
+/// Validates a container and its subcontainers.
+pub fn validate_eof(eof: &Eof, kind: ContainerKind) -> Result<(), EofValidationError> {
+    if !eof.is_data_filled() {
+        return Err(EofValidationError::MissingData);
+    }
+    validate_container(eof, kind)
+}
+
+fn validate_container(eof: &Eof, kind: ContainerKind) -> Result<(), EofValidationError> {
+    let types = eof.types();
+    let first = types[0];
+    if first.inputs != 0 || !first.is_non_returning() {
+        return Err(EofValidationError::InvalidTypes);
+    }
+    let valid_type = |types: &TypesSection| {
+        types.inputs <= 0x7f
+            && (types.outputs <= 0x7f || types.is_non_returning())
+            && types.max_stack_height <= EOF_MAX_STACK_HEIGHT
+    };
+    if !types.iter().all(valid_type) {
+        return Err(EofValidationError::InvalidTypes);
+    }
+
+    let mut refs = References {
+        sections: vec![false; eof.code_sections_len()],
+        containers: vec![None; eof.container_sections_len()],
+    };
+    refs.sections[0] = true;
+    let mut queue = vec![0];
+    while let Some(idx) = queue.pop() {
+        let referenced = validate_code(eof, idx, kind, &mut refs)?;
+        queue.extend(referenced);
+    }
+    if refs.sections.contains(&false) {
+        return Err(EofValidationError::UnreachableCode);
+    }
+
+    for (idx, kind) in refs.containers.iter().enumerate() {
+        let Some(kind) = *kind else {
+            return Err(EofValidationError::UnreferencedContainer);
+        };
+        let container = eof
+            .container_section(idx)
+            .expect("references are checked against the number of containers");
+        let container = Eof::decode(container)?;
+        // Only containers deployed by `RETURNCONTRACT` are filled with auxiliary data.
+        if kind == ContainerKind::Initcode && !container.is_data_filled() {
+            return Err(EofValidationError::MissingData);
+        }
+        validate_container(&container, kind)?;
+    }
+    Ok(())
+}
+
+/// Code sections and containers referenced by the validated code.
+struct References {
+    sections: Vec<bool>,
+    containers: Vec<Option<ContainerKind>>,
+}
+
+impl References {
+    fn container(&mut self, idx: usize, kind: ContainerKind) -> Result<(), EofValidationError> {
+        let slot = self
+            .containers
+            .get_mut(idx)
+            .ok_or(EofValidationError::InvalidContainerIndex)?;
+        match *slot {
+            Some(existing) if existing != kind => Err(EofValidationError::ContainerKindConflict),
+            _ => {
+                *slot = Some(kind);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Validates a code section and returns the code sections it newly references.
+fn validate_code(
+    eof: &Eof,
+    idx: usize,
+    kind: ContainerKind,
+    refs: &mut References,
+) -> Result<Vec<usize>, EofValidationError> {
+    let code = eof.code_section(idx).expect("section index is checked");
+    let types = eof.types();
+    let this = types[idx];
+
+    // Instruction boundaries.
+    let mut is_instruction = vec![false; code.len()];
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        if stack_io(op).is_none() {
+            return Err(EofValidationError::UnknownOpcode(op));
+        }
+        is_instruction[pc] = true;
+        let next = pc + 1 + immediate_size(code, pc).ok_or(EofValidationError::MissingImmediate)?;
+        if next > code.len() {
+            return Err(EofValidationError::MissingImmediate);
+        }
+        pc = next;
+    }
+
+    let mut referenced = Vec::new();
+    let mut returns = false;
+    let mut max_height = this.inputs as usize;
+    // Minimum and maximum stack height before each instruction.
+    let mut heights: Vec<Option<(usize, usize)>> = vec![None; code.len()];
+    heights[0] = Some((this.inputs as usize, this.inputs as usize));
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        let next = pc + 1 + immediate_size(code, pc).expect("immediates are checked");
+        let Some((min, max)) = heights[pc] else {
+            return Err(EofValidationError::UnreachableCode);
+        };
+
+        let (mut pops, mut pushes) = stack_io(op).expect("opcodes are checked");
+        let mut targets = Vec::new();
+        let mut terminates = false;
+        match op {
+            opcode::CALLF | opcode::JUMPF => {
+                let target = read_u16(code, pc + 1) as usize;
+                let target_types = *types
+                    .get(target)
+                    .ok_or(EofValidationError::InvalidCodeSectionIndex)?;
+                if !refs.sections[target] {
+                    refs.sections[target] = true;
+                    referenced.push(target);
+                }
+                let target_height = max + target_types.max_stack_height as usize;
+                if target_height.saturating_sub(target_types.inputs as usize) > STACK_LIMIT {
+                    return Err(EofValidationError::StackOverflow);
+                }
+                pops = target_types.inputs;
+                if op == opcode::CALLF {
+                    if target_types.is_non_returning() {
+                        return Err(EofValidationError::CallToNonReturning);
+                    }
+                    pushes = target_types.outputs;
+                } else {
+                    terminates = true;
+                    if !target_types.is_non_returning() {
+                        // Tail call, the target returns to the caller of this section.
+                        if this.is_non_returning() || this.outputs < target_types.outputs {
+                            return Err(EofValidationError::InvalidReturn);
+                        }
+                        let exact = this.outputs as usize + target_types.inputs as usize
+                            - target_types.outputs as usize;
+                        if min != exact || max != exact {
+                            return Err(EofValidationError::StackHeightMismatch);
+                        }
+                        returns = true;
+                    }
+                }
+            }
+            opcode::RETF => {
+                if this.is_non_returning() {
+                    return Err(EofValidationError::InvalidReturn);
+                }
+                if min != this.outputs as usize || max != this.outputs as usize {
+                    return Err(EofValidationError::StackHeightMismatch);
+                }
+                returns = true;
+                terminates = true;
+            }
+            opcode::RJUMP | opcode::RJUMPI => {
+                targets.push(relative_target(next, read_u16(code, pc + 1) as i16));
+                terminates = op == opcode::RJUMP;
+            }
+            opcode::RJUMPV => {
+                for case in 0..=code[pc + 1] as usize {
+                    let offset = read_u16(code, pc + 2 + case * 2) as i16;
+                    targets.push(relative_target(next, offset));
+                }
+            }
+            opcode::DATALOADN => {
+                let offset = read_u16(code, pc + 1) as usize;
+                if offset + 32 > eof.declared_data_size() as usize {
+                    return Err(EofValidationError::InvalidDataloadnOffset);
+                }
+            }
+            opcode::EOFCREATE => refs.container(code[pc + 1] as usize, ContainerKind::Initcode)?,
+            opcode::RETURNCONTRACT => {
+                if kind != ContainerKind::Initcode {
+                    return Err(EofValidationError::InvalidInstructionForKind);
+                }
+                refs.container(code[pc + 1] as usize, ContainerKind::Runtime)?;
+                terminates = true;
+            }
+            opcode::STOP | opcode::RETURN => {
+                if kind != ContainerKind::Runtime {
+                    return Err(EofValidationError::InvalidInstructionForKind);
+                }
+                terminates = true;
+            }
+            opcode::REVERT | opcode::INVALID => terminates = true,
+            _ => {}
+        }
+
+        if min < pops as usize {
+            return Err(EofValidationError::StackUnderflow);
+        }
+        let after = (
+            min - pops as usize + pushes as usize,
+            max - pops as usize + pushes as usize,
+        );
+        max_height = max_height.max(after.1);
+        if max_height > STACK_LIMIT {
+            return Err(EofValidationError::StackOverflow);
+        }
+
+        if !terminates {
+            if next >= code.len() {
+                return Err(EofValidationError::NoTerminatingInstruction);
+            }
+            targets.push(Some(next));
+        }
+        for target in targets {
+            let target = target
+                .filter(|target| *target < code.len() && is_instruction[*target])
+                .ok_or(EofValidationError::InvalidJumpTarget)?;
+            if target <= pc {
+                // Backward jumps need the exact height the target was reached with.
+                if after.0 != after.1 || heights[target] != Some(after) {
+                    return Err(EofValidationError::StackHeightMismatch);
+                }
+            } else {
+                heights[target] = Some(match heights[target] {
+                    Some((min, max)) => (min.min(after.0), max.max(after.1)),
+                    None => after,
+                });
+            }
+        }
+        pc = next;
+    }
+
+    if max_height != this.max_stack_height as usize {
+        return Err(EofValidationError::MaxStackHeightMismatch);
+    }
+    if !this.is_non_returning() && !returns {
+        return Err(EofValidationError::InvalidReturn);
+    }
+    Ok(referenced)
+}
+
+fn read_u16(code: &[u8], pos: usize) -> u16 {
+    u16::from_be_bytes([code[pos], code[pos + 1]])
+}
+
+fn relative_target(next: usize, offset: i16) -> Option<usize> {
+    next.checked_add_signed(offset as isize)
+}
+
+/// Returns the size of the immediate of the instruction at `pc`, or `None` if it is truncated
+/// before its size is known.
+fn immediate_size(code: &[u8], pc: usize) -> Option<usize> {
+    Some(match code[pc] {
+        op @ opcode::PUSH1..=opcode::PUSH32 => (op - opcode::PUSH1 + 1) as usize,
+        opcode::RJUMP | opcode::RJUMPI | opcode::CALLF | opcode::JUMPF | opcode::DATALOADN => 2,
+        opcode::RJUMPV => 1 + (*code.get(pc + 1)? as usize + 1) * 2,
+        opcode::EOFCREATE | opcode::RETURNCONTRACT => 1,
+        _ => 0,
+    })
+}
+
+/// Returns the number of stack items taken and returned by the opcode, or `None` if the opcode
+/// is not defined in EOF code.
+///
+/// `CALLF` and `JUMPF` depend on the type of the target section and are resolved by the caller.
+const fn stack_io(op: u8) -> Option<(u8, u8)> {
+    use opcode::*;
+    Some(match op {
+        STOP | INVALID | RJUMP | CALLF | JUMPF | RETF => (0, 0),
+        ADD | MUL | SUB | DIV | SDIV | MOD | SMOD | EXP | SIGNEXTEND => (2, 1),
+        ADDMOD | MULMOD => (3, 1),
+        LT | GT | SLT | SGT | EQ | AND | OR | XOR | BYTE | SHL | SHR | SAR => (2, 1),
+        ISZERO | NOT => (1, 1),
+        KECCAK256 => (2, 1),
+        ADDRESS | ORIGIN | CALLER | CALLVALUE | CALLDATASIZE | GASPRICE | RETURNDATASIZE => (0, 1),
+        BALANCE | CALLDATALOAD => (1, 1),
+        CALLDATACOPY | RETURNDATACOPY | MCOPY | DATACOPY => (3, 0),
+        BLOCKHASH | BLOBHASH => (1, 1),
+        COINBASE | TIMESTAMP | NUMBER | DIFFICULTY | GASLIMIT | CHAINID | SELFBALANCE | BASEFEE
+        | BLOBBASEFEE | MSIZE | PUSH0 | DATALOADN | DATASIZE => (0, 1),
+        POP | RJUMPI | RJUMPV => (1, 0),
+        MLOAD | SLOAD | TLOAD | RETURNDATALOAD | DATALOAD => (1, 1),
+        MSTORE | MSTORE8 | SSTORE | TSTORE | RETURN | REVERT | RETURNCONTRACT => (2, 0),
+        JUMPDEST => (0, 0),
+        PUSH1..=PUSH32 => (0, 1),
+        DUP1..=DUP16 => (op - DUP1 + 1, op - DUP1 + 2),
+        SWAP1..=SWAP16 => (op - SWAP1 + 2, op - SWAP1 + 2),
+        LOG0..=LOG4 => (op - LOG0 + 2, 0),
+        EOFCREATE => (4, 1),
+        EXTCALL => (4, 1),
+        EXTDELEGATECALL | EXTSTATICCALL => (3, 1),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{hex_literal::hex, Bytes};
+
+    /// Builds a container with the given code sections and subcontainers.
+    fn container(sections: &[(TypesSection, &[u8])], containers: &[&[u8]], data: &[u8]) -> Eof {
+        let mut raw = vec![0xEF, 0x00, 0x01, 0x01];
+        raw.extend_from_slice(&(sections.len() as u16 * 4).to_be_bytes());
+        raw.push(0x02);
+        raw.extend_from_slice(&(sections.len() as u16).to_be_bytes());
+        for (_, code) in sections {
+            raw.extend_from_slice(&(code.len() as u16).to_be_bytes());
+        }
+        if !containers.is_empty() {
+            raw.push(0x03);
+            raw.extend_from_slice(&(containers.len() as u16).to_be_bytes());
+            for container in containers {
+                raw.extend_from_slice(&(container.len() as u16).to_be_bytes());
+            }
+        }
+        raw.push(0x04);
+        raw.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        raw.push(0x00);
+        for (types, _) in sections {
+            raw.extend_from_slice(&[types.inputs, types.outputs]);
+            raw.extend_from_slice(&types.max_stack_height.to_be_bytes());
+        }
+        for (_, code) in sections {
+            raw.extend_from_slice(code);
+        }
+        for container in containers {
+            raw.extend_from_slice(container);
+        }
+        raw.extend_from_slice(data);
+        Eof::decode(Bytes::from(raw)).unwrap()
+    }
+
+    const fn types(inputs: u8, outputs: u8, max_stack_height: u16) -> TypesSection {
+        TypesSection {
+            inputs,
+            outputs,
+            max_stack_height,
+        }
+    }
+
+    const MAIN: TypesSection = types(0, TypesSection::NON_RETURNING, 0);
+
+    fn validate(sections: &[(TypesSection, &[u8])]) -> Result<(), EofValidationError> {
+        validate_eof(&container(sections, &[], &[]), ContainerKind::Runtime)
+    }
+
+    #[test]
+    fn valid_code() {
+        // PUSH1 1 RJUMPI +1 STOP (JUMPDEST) CALLF 1 POP STOP
+        let main = hex!("6001e10001005be300015000");
+        // PUSH1 2 RETF
+        let function = hex!("6002e4");
+        assert_eq!(
+            validate(&[(types(0, 0x80, 1), &main), (types(0, 1, 1), &function)]),
+            Ok(())
+        );
+
+        // PUSH1 0 RJUMPV [0, 1] STOP STOP
+        assert_eq!(
+            validate(&[(types(0, 0x80, 1), &hex!("6000e201000000010000"))]),
+            Ok(())
+        );
+        assert_eq!(
+            validate(&[(types(0, 0x80, 1), &hex!("6000e2010000"))]),
+            Err(EofValidationError::MissingImmediate)
+        );
+    }
+
+    #[test]
+    fn invalid_code() {
+        // Legacy-only opcode.
+        assert_eq!(
+            validate(&[(MAIN, &hex!("5600"))]),
+            Err(EofValidationError::UnknownOpcode(0x56))
+        );
+        // Falls off the end.
+        assert_eq!(
+            validate(&[(types(0, 0x80, 1), &hex!("6001"))]),
+            Err(EofValidationError::NoTerminatingInstruction)
+        );
+        // Jump into an immediate.
+        assert_eq!(
+            validate(&[(types(0, 0x80, 1), &hex!("6001e1fffc00"))]),
+            Err(EofValidationError::InvalidJumpTarget)
+        );
+        // Unreachable STOP after RJUMP.
+        assert_eq!(
+            validate(&[(MAIN, &hex!("e000010000"))]),
+            Err(EofValidationError::UnreachableCode)
+        );
+        // POP on an empty stack.
+        assert_eq!(
+            validate(&[(MAIN, &hex!("5000"))]),
+            Err(EofValidationError::StackUnderflow)
+        );
+        // Declared max stack height is wrong.
+        assert_eq!(
+            validate(&[(types(0, 0x80, 2), &hex!("600100"))]),
+            Err(EofValidationError::MaxStackHeightMismatch)
+        );
+        // Loop that grows the stack.
+        assert_eq!(
+            validate(&[(types(0, 0x80, 1), &hex!("6001e0fffb"))]),
+            Err(EofValidationError::StackHeightMismatch)
+        );
+        // RETF in the first section.
+        assert_eq!(
+            validate(&[(MAIN, &hex!("e4"))]),
+            Err(EofValidationError::InvalidReturn)
+        );
+        // Unreachable code section.
+        assert_eq!(
+            validate(&[(MAIN, &hex!("00")), (types(0, 0, 0), &hex!("e4"))]),
+            Err(EofValidationError::UnreachableCode)
+        );
+    }
+
+    #[test]
+    fn dataloadn_offset() {
+        // DATALOADN 0 POP STOP
+        let code = hex!("d100005000");
+        let eof = container(&[(types(0, 0x80, 1), &code)], &[], &[0; 32]);
+        assert_eq!(validate_eof(&eof, ContainerKind::Runtime), Ok(()));
+        let eof = container(&[(types(0, 0x80, 1), &code)], &[], &[0; 31]);
+        assert_eq!(
+            validate_eof(&eof, ContainerKind::Runtime),
+            Err(EofValidationError::InvalidDataloadnOffset)
+        );
+    }
+
+    #[test]
+    fn container_kinds() {
+        let runtime = container(&[(MAIN, &hex!("00"))], &[], &[]);
+        // PUSH0 PUSH0 RETURNCONTRACT 0
+        let initcode = container(
+            &[(types(0, 0x80, 2), &hex!("5f5fee00"))],
+            &[&runtime.raw()[..]],
+            &[],
+        );
+        assert_eq!(validate_eof(&initcode, ContainerKind::Initcode), Ok(()));
+        assert_eq!(
+            validate_eof(&initcode, ContainerKind::Runtime),
+            Err(EofValidationError::InvalidInstructionForKind)
+        );
+        assert_eq!(
+            validate_eof(&runtime, ContainerKind::Initcode),
+            Err(EofValidationError::InvalidInstructionForKind)
+        );
+
+        // PUSH0 PUSH0 PUSH0 PUSH0 EOFCREATE 0 POP STOP
+        let factory = container(
+            &[(types(0, 0x80, 4), &hex!("5f5f5f5fec005000"))],
+            &[&initcode.raw()[..]],
+            &[],
+        );
+        assert_eq!(validate_eof(&factory, ContainerKind::Runtime), Ok(()));
+
+        let unreferenced = container(&[(MAIN, &hex!("00"))], &[&runtime.raw()[..]], &[]);
+        assert_eq!(
+            validate_eof(&unreferenced, ContainerKind::Runtime),
+            Err(EofValidationError::UnreferencedContainer)
+        );
+    }
+}
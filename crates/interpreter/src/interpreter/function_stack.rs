@@ -0,0 +1,68 @@
+use std::vec::Vec;
+
+/// Maximum depth of nested EOF function calls.
+pub const FUNCTION_STACK_LIMIT: usize = 1024;
+
+/// Where execution continues when an EOF function returns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionReturnFrame {
+    /// Index of the code section of the caller.
+    pub idx: usize,
+    /// Program counter of the instruction after the `CALLF`.
+    pub pc: usize,
+}
+
+/// Return stack of the EOF `CALLF` and `RETF` instructions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionStack {
+    /// Frames of the functions that called the current one.
+    pub return_stack: Vec<FunctionReturnFrame>,
+    /// Index of the code section being executed.
+    pub current_code_idx: usize,
+}
+
+impl FunctionStack {
+    /// Creates an empty stack executing the first code section.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of functions that called the current one.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.return_stack.len()
+    }
+
+    /// Returns `true` if the first code section is executing.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.return_stack.is_empty()
+    }
+
+    /// Enters the code section `idx`, returning to `pc` in the current one.
+    #[inline]
+    pub fn push(&mut self, pc: usize, idx: usize) {
+        self.return_stack.push(FunctionReturnFrame {
+            idx: self.current_code_idx,
+            pc,
+        });
+        self.current_code_idx = idx;
+    }
+
+    /// Returns to the caller of the current code section.
+    #[inline]
+    pub fn pop(&mut self) -> Option<FunctionReturnFrame> {
+        let frame = self.return_stack.pop()?;
+        self.current_code_idx = frame.idx;
+        Some(frame)
+    }
+
+    /// Continues in the code section `idx` without returning to the current one.
+    #[inline]
+    pub fn set_current_code_idx(&mut self, idx: usize) {
+        self.current_code_idx = idx;
+    }
+}
@@ -0,0 +1,75 @@
+use super::{Interpreter, STACK_LIMIT};
+use std::{format, vec::Vec};
+
+impl Interpreter {
+    /// Validates the interpreter state after executing `opcode` and panics with a dump of the
+    /// state if it is inconsistent.
+    ///
+    /// Checked invariants are:
+    /// * the stack holds at most [STACK_LIMIT] words,
+    /// * the memory of the current context is word aligned,
+    /// * remaining gas does not exceed the gas limit, which catches wrapped gas arithmetic.
+    ///
+    /// With the `invariant-checks` feature this is called after every instruction.
+    #[track_caller]
+    pub fn check_invariants(&self, opcode: u8) {
+        let mut violations = Vec::new();
+        if self.stack.len() > STACK_LIMIT {
+            violations.push(format!(
+                "stack holds {} words, limit is {STACK_LIMIT}",
+                self.stack.len()
+            ));
+        }
+        if self.shared_memory.len() % 32 != 0 {
+            violations.push(format!(
+                "memory length {} is not word aligned",
+                self.shared_memory.len()
+            ));
+        }
+        if self.gas.remaining() > self.gas.limit() {
+            violations.push(format!(
+                "remaining gas {} exceeds gas limit {}",
+                self.gas.remaining(),
+                self.gas.limit()
+            ));
+        }
+        if !violations.is_empty() {
+            panic!(
+                "interpreter invariants violated after opcode {opcode:#04x}:\n  {}\n\
+                 address: {}\n\
+                 pc: {}\n\
+                 result: {:?}\n\
+                 gas: {:?}\n\
+                 stack: {}\n\
+                 memory: {} bytes",
+                violations.join("\n  "),
+                self.contract.address,
+                self.program_counter(),
+                self.instruction_result,
+                self.gas,
+                self.stack,
+                self.shared_memory.len(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Contract;
+
+    #[test]
+    fn valid_state_passes() {
+        let interpreter = Interpreter::new(Contract::default(), 100, false);
+        interpreter.check_invariants(0x00);
+    }
+
+    #[test]
+    #[should_panic(expected = "remaining gas 110 exceeds gas limit 100")]
+    fn returned_gas_above_limit_panics() {
+        let mut interpreter = Interpreter::new(Contract::default(), 100, false);
+        interpreter.gas.erase_cost(10);
+        interpreter.check_invariants(0x00);
+    }
+}
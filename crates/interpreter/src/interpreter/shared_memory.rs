@@ -90,12 +90,21 @@ impl SharedMemory {
         }
     }
 
+    /// Sets the upper bound for allocation size, see [Self::new_with_memory_limit].
+    #[cfg(feature = "memory_limit")]
+    #[inline]
+    pub fn set_memory_limit(&mut self, memory_limit: u64) {
+        self.memory_limit = memory_limit;
+    }
+
     /// Returns `true` if the `new_size` for the current context memory will
     /// make the shared buffer length exceed the `memory_limit`.
     #[cfg(feature = "memory_limit")]
     #[inline]
     pub fn limit_reached(&self, new_size: usize) -> bool {
-        (self.last_checkpoint + new_size) as u64 > self.memory_limit
+        // `new_size` may be saturated to `usize::MAX`, add in `u64` to not overflow on 32-bit
+        // targets.
+        (self.last_checkpoint as u64).saturating_add(new_size as u64) > self.memory_limit
     }
 
     /// Prepares the shared memory for a new context.
@@ -116,12 +125,26 @@ impl SharedMemory {
         }
     }
 
+    /// Frees all contexts, keeping the allocated buffer for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.checkpoints.clear();
+        self.last_checkpoint = 0;
+    }
+
     /// Returns the length of the current memory range.
     #[inline]
     pub fn len(&self) -> usize {
         self.buffer.len() - self.last_checkpoint
     }
 
+    /// Returns the length of the memory of all contexts.
+    #[inline]
+    pub fn total_len(&self) -> usize {
+        self.buffer.len()
+    }
+
     /// Returns `true` if the current memory range is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -408,4 +431,33 @@ mod tests {
         assert_eq!(shared_memory.len(), 64);
         assert_eq!(shared_memory.buffer.get(0..64), Some(&[0_u8; 64] as &[u8]));
     }
+
+    #[test]
+    fn words_are_big_endian() {
+        let mut shared_memory = SharedMemory::new();
+        shared_memory.new_context();
+        shared_memory.resize(64);
+
+        let value = U256::from(0x0102_0304_0506_0708_u64) << 128;
+        shared_memory.set_u256(0, value);
+        assert_eq!(
+            shared_memory.slice(8, 8),
+            &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]
+        );
+        assert_eq!(shared_memory.get_u256(0), value);
+        assert_eq!(shared_memory.get_word(0), B256::from(value));
+
+        shared_memory.set_byte(63, 0xff);
+        assert_eq!(shared_memory.get_u256(32), U256::from(0xff));
+    }
+
+    #[cfg(feature = "memory_limit")]
+    #[test]
+    fn limit_reached_saturated_size() {
+        let mut shared_memory = SharedMemory::new_with_memory_limit(u32::MAX as u64);
+        shared_memory.resize(64);
+        shared_memory.new_context();
+        assert!(!shared_memory.limit_reached(1024));
+        assert!(shared_memory.limit_reached(usize::MAX));
+    }
 }
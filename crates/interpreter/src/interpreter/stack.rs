@@ -250,6 +250,10 @@ impl Stack {
             return Err(InstructionResult::StackOverflow);
         }
 
+        // `U256` is an array of native-endian `u64` limbs ordered from the least significant one
+        // on every target, so the limbs are written as values, in that order.
+        const _: () = assert!(core::mem::size_of::<U256>() == 4 * core::mem::size_of::<u64>());
+
         // SAFETY: length checked above.
         unsafe {
             let dst = self.data.as_mut_ptr().add(self.data.len()).cast::<u64>();
@@ -335,6 +339,40 @@ impl<'de> serde::Deserialize<'de> for Stack {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Stack {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=STACK_LIMIT)?;
+        let mut stack = Self::new();
+        for _ in 0..len {
+            stack
+                .data
+                .push(U256::from_be_bytes(u.arbitrary::<[u8; 32]>()?));
+        }
+        Ok(stack)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl proptest::arbitrary::Arbitrary for Stack {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        proptest::collection::vec(any::<[u8; 32]>(), 0..=STACK_LIMIT)
+            .prop_map(|words| {
+                let mut stack = Self::new();
+                stack
+                    .data
+                    .extend(words.into_iter().map(U256::from_be_bytes));
+                stack
+            })
+            .boxed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -395,4 +433,27 @@ mod tests {
             assert_eq!(stack.data, [U256::ZERO, U256::ZERO, U256::from(n)]);
         });
     }
+
+    #[test]
+    fn push_slices_match_from_be_slice() {
+        let bytes: Vec<u8> = (1..=96).collect();
+        for len in 1..=bytes.len() {
+            let slice = &bytes[..len];
+            run(|stack| {
+                stack.push_slice(slice).unwrap();
+                let words: Vec<_> = slice
+                    .chunks(32)
+                    .map(|word| {
+                        let mut padded = [0u8; 32];
+                        padded[32 - word.len()..].copy_from_slice(word);
+                        U256::from_be_bytes(padded)
+                    })
+                    .collect();
+                assert_eq!(stack.data, words, "length {len}");
+                for (value, word) in stack.data.iter().zip(slice.chunks(32)) {
+                    assert_eq!(&value.to_be_bytes::<32>()[32 - word.len()..], word);
+                }
+            });
+        }
+    }
 }
@@ -30,8 +30,9 @@ pub use inner_models::*;
 pub use instruction_result::*;
 pub use instructions::{opcode, Instruction, OpCode, OPCODE_JUMPMAP};
 pub use interpreter::{
-    analysis, next_multiple_of_32, BytecodeLocked, Contract, Interpreter, InterpreterAction,
-    InterpreterResult, SharedMemory, Stack, EMPTY_SHARED_MEMORY, STACK_LIMIT,
+    analysis, next_multiple_of_32, validate_eof, BytecodeLocked, ContainerKind, Contract,
+    EofCompatibility, EofValidationError, Interpreter, InterpreterAction, InterpreterResult,
+    LegacyCodeChange, SharedMemory, Stack, EMPTY_SHARED_MEMORY, STACK_LIMIT,
 };
 pub use primitives::{MAX_CODE_SIZE, MAX_INITCODE_SIZE};
 
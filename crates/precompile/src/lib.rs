@@ -20,13 +20,17 @@ pub mod modexp;
 pub mod secp256k1;
 pub mod utilities;
 
+// The `secp256k1` backend is replaced by `k256` in zkVMs.
+#[cfg(all(feature = "secp256k1", feature = "zkvm"))]
+use ::secp256k1 as _;
+
 use core::hash::Hash;
 use once_cell::race::OnceBox;
 #[doc(hidden)]
 pub use revm_primitives as primitives;
 pub use revm_primitives::{
     precompile::{PrecompileError as Error, *},
-    Address, Bytes, HashMap, Log, B256,
+    Address, Bytes, ChainPreset, HashMap, Log, B256,
 };
 use std::{boxed::Box, vec::Vec};
 
@@ -161,6 +165,16 @@ impl Precompiles {
         Self::cancun()
     }
 
+    /// Returns the precompiles of a chain at the block with the given number and timestamp,
+    /// for example the Cancun precompiles for mainnet blocks since the Dencun upgrade.
+    ///
+    /// Chain specific precompiles, like the ArbOS precompiles of Arbitrum One, are not included.
+    pub fn for_chain(preset: &ChainPreset, number: u64, timestamp: u64) -> &'static Self {
+        Self::new(PrecompileSpecId::from_spec_id(
+            preset.spec_id_at(number, timestamp),
+        ))
+    }
+
     /// Returns an iterator over the precompiles addresses.
     #[inline]
     pub fn addresses(&self) -> impl Iterator<Item = &Address> {
@@ -245,7 +259,7 @@ impl PrecompileSpecId {
             BYZANTIUM | CONSTANTINOPLE | PETERSBURG => Self::BYZANTIUM,
             ISTANBUL | MUIR_GLACIER => Self::ISTANBUL,
             BERLIN | LONDON | ARROW_GLACIER | GRAY_GLACIER | MERGE | SHANGHAI => Self::BERLIN,
-            CANCUN => Self::CANCUN,
+            CANCUN | PRAGUE | OSAKA => Self::CANCUN,
             LATEST => Self::LATEST,
             #[cfg(feature = "optimism")]
             BEDROCK | REGOLITH | CANYON => Self::BERLIN,
@@ -266,3 +280,21 @@ pub const fn u64_to_address(x: u64) -> Address {
         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, x[0], x[1], x[2], x[3], x[4], x[5], x[6], x[7],
     ])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_precompiles() {
+        let mainnet = ChainPreset::mainnet();
+        let at = |number, timestamp| Precompiles::for_chain(&mainnet, number, timestamp);
+        assert!(core::ptr::eq(at(0, 0), Precompiles::homestead()));
+        assert!(core::ptr::eq(at(4_370_000, 0), Precompiles::byzantium()));
+        assert!(core::ptr::eq(at(12_244_000, 0), Precompiles::berlin()));
+        assert!(core::ptr::eq(
+            at(19_426_587, 1_710_338_135),
+            Precompiles::cancun()
+        ));
+    }
+}
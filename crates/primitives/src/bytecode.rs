@@ -1,3 +1,12 @@
+mod eof;
+mod metadata;
+
+pub use eof::{
+    Eof, EofDecodeError, TypesSection, EOF_MAX_CODE_SECTIONS, EOF_MAX_CONTAINER_SECTIONS,
+    EOF_MAX_STACK_HEIGHT,
+};
+pub use metadata::{CodeMetadata, CodeVersion, SolcVersion, EOF_MAGIC, EOF_MAGIC_HASH};
+
 use crate::{hex, keccak256, Bytes, B256, KECCAK_EMPTY};
 use bitvec::{
     prelude::{bitvec, Lsb0},
@@ -150,6 +159,28 @@ impl Bytecode {
         self.len() == 0
     }
 
+    /// Returns the format of the code.
+    #[inline]
+    pub fn code_version(&self) -> CodeVersion {
+        CodeVersion::of(&self.bytecode)
+    }
+
+    /// Decodes the code as an EOF container.
+    pub fn decode_eof(&self) -> Result<Eof, EofDecodeError> {
+        Eof::decode(self.original_bytes())
+    }
+
+    /// Returns the data section of the code if it is an EOF container.
+    pub fn eof_data_section(&self) -> Option<Bytes> {
+        let eof = self.decode_eof().ok()?;
+        Some(eof.raw().slice(eof.data_section_range()))
+    }
+
+    /// Returns the compiler metadata appended to the code, if any.
+    pub fn metadata(&self) -> Option<CodeMetadata> {
+        CodeMetadata::parse(&self.original_bytes())
+    }
+
     /// Returns the [`BytecodeState`].
     #[inline]
     pub fn state(&self) -> &BytecodeState {
@@ -0,0 +1,428 @@
+//! EOF container format, see EIP-3540.
+//!
+//! Decoding only checks the layout of the container. Whether its code is allowed to execute is
+//! decided by the validation in the interpreter crate.
+
+use super::EOF_MAGIC;
+use crate::Bytes;
+use core::{fmt, ops::Range};
+use std::vec::Vec;
+
+/// Maximum number of code sections in a container.
+pub const EOF_MAX_CODE_SECTIONS: usize = 1024;
+
+/// Maximum number of container sections in a container.
+pub const EOF_MAX_CONTAINER_SECTIONS: usize = 256;
+
+/// Maximum stack height of a code section.
+pub const EOF_MAX_STACK_HEIGHT: u16 = 1023;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+/// Entry of the types section describing one code section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypesSection {
+    /// Number of stack items the section takes.
+    pub inputs: u8,
+    /// Number of stack items the section returns, or [TypesSection::NON_RETURNING].
+    pub outputs: u8,
+    /// Maximum stack height reached while executing the section.
+    pub max_stack_height: u16,
+}
+
+impl TypesSection {
+    /// Outputs of a section that never returns to its caller.
+    pub const NON_RETURNING: u8 = 0x80;
+
+    /// Returns `true` if the section never returns to its caller.
+    #[inline]
+    pub const fn is_non_returning(&self) -> bool {
+        self.outputs == Self::NON_RETURNING
+    }
+}
+
+/// Error returned when decoding an EOF container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EofDecodeError {
+    /// The code does not start with the EOF magic.
+    MissingMagic,
+    /// The container version is not supported.
+    UnsupportedVersion(u8),
+    /// The header is truncated or its sections are not in the expected order.
+    InvalidHeader,
+    /// The size of the types section does not match the number of code sections.
+    TypesSizeMismatch,
+    /// There are no code sections or more than [EOF_MAX_CODE_SECTIONS].
+    InvalidCodeSectionCount,
+    /// There are no container sections or more than [EOF_MAX_CONTAINER_SECTIONS].
+    InvalidContainerSectionCount,
+    /// A code or container section is empty.
+    EmptySection,
+    /// The body is shorter than the sections declared in the header.
+    MissingBody,
+    /// There are bytes after the data section.
+    DanglingBytes,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EofDecodeError {}
+
+impl fmt::Display for EofDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingMagic => f.write_str("missing EOF magic"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported EOF version {version}"),
+            Self::InvalidHeader => f.write_str("invalid EOF header"),
+            Self::TypesSizeMismatch => {
+                f.write_str("types section size does not match the code sections")
+            }
+            Self::InvalidCodeSectionCount => f.write_str("invalid number of code sections"),
+            Self::InvalidContainerSectionCount => {
+                f.write_str("invalid number of container sections")
+            }
+            Self::EmptySection => f.write_str("empty code or container section"),
+            Self::MissingBody => f.write_str("EOF body is shorter than its header declares"),
+            Self::DanglingBytes => f.write_str("dangling bytes after the EOF container"),
+        }
+    }
+}
+
+/// Decoded EOF container.
+///
+/// Sections are kept as ranges of the raw container, which is what gets deployed and hashed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Eof {
+    raw: Bytes,
+    types: Vec<TypesSection>,
+    code_sections: Vec<Range<usize>>,
+    container_sections: Vec<Range<usize>>,
+    data: Range<usize>,
+    data_size: u16,
+    data_size_offset: usize,
+}
+
+impl Eof {
+    /// Decodes a container.
+    ///
+    /// The data section may be shorter than declared, as it is in containers that are filled
+    /// with auxiliary data when deployed.
+    pub fn decode(raw: Bytes) -> Result<Self, EofDecodeError> {
+        Self::decode_inner(raw, false).map(|(eof, _)| eof)
+    }
+
+    /// Decodes a container followed by other data, such as the call data of a creation
+    /// transaction. Returns the container and the bytes after it.
+    ///
+    /// The data section must be complete.
+    pub fn decode_dangling(raw: Bytes) -> Result<(Self, Bytes), EofDecodeError> {
+        Self::decode_inner(raw, true)
+    }
+
+    fn decode_inner(raw: Bytes, dangling: bool) -> Result<(Self, Bytes), EofDecodeError> {
+        if !raw.starts_with(&EOF_MAGIC) {
+            return Err(EofDecodeError::MissingMagic);
+        }
+        let mut reader = Reader {
+            bytes: &raw,
+            pos: EOF_MAGIC.len(),
+        };
+        let version = reader.u8()?;
+        if version != 1 {
+            return Err(EofDecodeError::UnsupportedVersion(version));
+        }
+
+        reader.expect(KIND_TYPES)?;
+        let types_size = reader.u16()? as usize;
+
+        reader.expect(KIND_CODE)?;
+        let code_sizes = reader.sizes()?;
+        if code_sizes.is_empty() || code_sizes.len() > EOF_MAX_CODE_SECTIONS {
+            return Err(EofDecodeError::InvalidCodeSectionCount);
+        }
+        if types_size != code_sizes.len() * 4 {
+            return Err(EofDecodeError::TypesSizeMismatch);
+        }
+
+        let mut container_sizes = Vec::new();
+        if reader.peek()? == KIND_CONTAINER {
+            reader.pos += 1;
+            container_sizes = reader.sizes()?;
+            if container_sizes.is_empty() || container_sizes.len() > EOF_MAX_CONTAINER_SECTIONS {
+                return Err(EofDecodeError::InvalidContainerSectionCount);
+            }
+        }
+        if code_sizes.contains(&0) || container_sizes.contains(&0) {
+            return Err(EofDecodeError::EmptySection);
+        }
+
+        reader.expect(KIND_DATA)?;
+        let data_size_offset = reader.pos;
+        let data_size = reader.u16()?;
+        reader.expect(TERMINATOR)?;
+
+        let mut types = Vec::with_capacity(code_sizes.len());
+        for _ in 0..code_sizes.len() {
+            let inputs = reader.body_u8()?;
+            let outputs = reader.body_u8()?;
+            let max_stack_height = u16::from_be_bytes([reader.body_u8()?, reader.body_u8()?]);
+            types.push(TypesSection {
+                inputs,
+                outputs,
+                max_stack_height,
+            });
+        }
+        let code_sections = reader.ranges(&code_sizes)?;
+        let container_sections = reader.ranges(&container_sizes)?;
+
+        let available = raw.len() - reader.pos;
+        let data_len = if dangling {
+            if available < data_size as usize {
+                return Err(EofDecodeError::MissingBody);
+            }
+            data_size as usize
+        } else {
+            if available > data_size as usize {
+                return Err(EofDecodeError::DanglingBytes);
+            }
+            available
+        };
+        let data = reader.pos..reader.pos + data_len;
+        let rest = raw.slice(data.end..);
+
+        let eof = Self {
+            raw: raw.slice(..data.end),
+            types,
+            code_sections,
+            container_sections,
+            data,
+            data_size,
+            data_size_offset,
+        };
+        Ok((eof, rest))
+    }
+
+    /// Returns the raw container.
+    #[inline]
+    pub fn raw(&self) -> &Bytes {
+        &self.raw
+    }
+
+    /// Returns the types of the code sections.
+    #[inline]
+    pub fn types(&self) -> &[TypesSection] {
+        &self.types
+    }
+
+    /// Returns the number of code sections.
+    #[inline]
+    pub fn code_sections_len(&self) -> usize {
+        self.code_sections.len()
+    }
+
+    /// Returns the code section with the given index.
+    #[inline]
+    pub fn code_section(&self, index: usize) -> Option<&[u8]> {
+        self.code_sections
+            .get(index)
+            .map(|range| &self.raw[range.clone()])
+    }
+
+    /// Returns the position of the code section with the given index in the raw container.
+    #[inline]
+    pub fn code_section_range(&self, index: usize) -> Option<Range<usize>> {
+        self.code_sections.get(index).cloned()
+    }
+
+    /// Returns the number of container sections.
+    #[inline]
+    pub fn container_sections_len(&self) -> usize {
+        self.container_sections.len()
+    }
+
+    /// Returns the container section with the given index.
+    #[inline]
+    pub fn container_section(&self, index: usize) -> Option<Bytes> {
+        self.container_sections
+            .get(index)
+            .map(|range| self.raw.slice(range.clone()))
+    }
+
+    /// Returns the data section present in the container.
+    #[inline]
+    pub fn data_section(&self) -> &[u8] {
+        &self.raw[self.data.clone()]
+    }
+
+    /// Returns the position of the data section in the raw container.
+    #[inline]
+    pub fn data_section_range(&self) -> Range<usize> {
+        self.data.clone()
+    }
+
+    /// Returns the size of the data section declared in the header.
+    #[inline]
+    pub fn declared_data_size(&self) -> u16 {
+        self.data_size
+    }
+
+    /// Returns `true` if the data section is as long as declared in the header.
+    #[inline]
+    pub fn is_data_filled(&self) -> bool {
+        self.data.len() == self.data_size as usize
+    }
+
+    /// Returns the raw container with `aux_data` appended to its data section and the declared
+    /// data size updated, as deployed by `RETURNCONTRACT` (EIP-7620).
+    ///
+    /// Returns `None` if the resulting data section is shorter than declared or does not fit in
+    /// the header.
+    pub fn with_aux_data(&self, aux_data: &[u8]) -> Option<Bytes> {
+        let data_size = self.data.len() + aux_data.len();
+        if data_size < self.data_size as usize {
+            return None;
+        }
+        let data_size = u16::try_from(data_size).ok()?;
+        let mut raw = Vec::with_capacity(self.raw.len() + aux_data.len());
+        raw.extend_from_slice(&self.raw);
+        raw.extend_from_slice(aux_data);
+        raw[self.data_size_offset..self.data_size_offset + 2]
+            .copy_from_slice(&data_size.to_be_bytes());
+        Some(raw.into())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Result<u8, EofDecodeError> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or(EofDecodeError::InvalidHeader)
+    }
+
+    fn u8(&mut self) -> Result<u8, EofDecodeError> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> Result<u16, EofDecodeError> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn expect(&mut self, kind: u8) -> Result<(), EofDecodeError> {
+        if self.u8()? != kind {
+            return Err(EofDecodeError::InvalidHeader);
+        }
+        Ok(())
+    }
+
+    /// Reads a section count followed by the size of every section.
+    fn sizes(&mut self) -> Result<Vec<u16>, EofDecodeError> {
+        let count = self.u16()?;
+        (0..count).map(|_| self.u16()).collect()
+    }
+
+    fn body_u8(&mut self) -> Result<u8, EofDecodeError> {
+        self.u8().map_err(|_| EofDecodeError::MissingBody)
+    }
+
+    fn ranges(&mut self, sizes: &[u16]) -> Result<Vec<Range<usize>>, EofDecodeError> {
+        let mut ranges = Vec::with_capacity(sizes.len());
+        for size in sizes {
+            let end = self.pos + *size as usize;
+            if end > self.bytes.len() {
+                return Err(EofDecodeError::MissingBody);
+            }
+            ranges.push(self.pos..end);
+            self.pos = end;
+        }
+        Ok(ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hex_literal::hex, Bytecode};
+
+    #[test]
+    fn decode_container() {
+        // One code section `STOP` taking no inputs, one subcontainer and two bytes of data.
+        let sub = hex!("ef000101000402000100010400000000800000fe");
+        let mut raw = hex!("ef000101000402000100010300010014040002000080000000").to_vec();
+        raw.extend_from_slice(&sub);
+        raw.extend_from_slice(&[0xaa, 0xbb]);
+        let eof = Eof::decode(raw.clone().into()).unwrap();
+
+        assert_eq!(eof.types()[0].max_stack_height, 0);
+        assert!(eof.types()[0].is_non_returning());
+        assert_eq!(eof.code_section(0), Some(&[0x00][..]));
+        assert_eq!(eof.container_section(0).unwrap(), &sub[..]);
+        assert_eq!(eof.data_section(), &[0xaa, 0xbb]);
+        assert!(eof.is_data_filled());
+        assert_eq!(eof.data_section_range(), raw.len() - 2..raw.len());
+        assert_eq!(
+            Bytecode::new_raw(raw.clone().into()).eof_data_section(),
+            Some(Bytes::from_static(&[0xaa, 0xbb]))
+        );
+
+        raw.push(0xcc);
+        assert_eq!(
+            Eof::decode(raw.clone().into()),
+            Err(EofDecodeError::DanglingBytes)
+        );
+        let (dangling, rest) = Eof::decode_dangling(raw.into()).unwrap();
+        assert_eq!(dangling, eof);
+        assert_eq!(rest, &[0xcc][..]);
+    }
+
+    #[test]
+    fn truncated_data_and_aux_data() {
+        // Declares four bytes of data, only two are present.
+        let raw = hex!("ef000101000402000100010400040000800000fe1122");
+        let eof = Eof::decode(raw.into()).unwrap();
+        assert!(!eof.is_data_filled());
+        assert_eq!(eof.with_aux_data(&[0x33]), None);
+
+        let deployed = eof.with_aux_data(&[0x33, 0x44, 0x55]).unwrap();
+        let deployed = Eof::decode(deployed).unwrap();
+        assert_eq!(deployed.declared_data_size(), 5);
+        assert_eq!(deployed.data_section(), &[0x11, 0x22, 0x33, 0x44, 0x55]);
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(
+            Eof::decode(Bytes::from_static(&[0x60, 0x00])),
+            Err(EofDecodeError::MissingMagic)
+        );
+        assert_eq!(
+            Eof::decode(hex!("ef0002").into()),
+            Err(EofDecodeError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            Eof::decode(hex!("ef00010100080200010001040000").into()),
+            Err(EofDecodeError::TypesSizeMismatch)
+        );
+        assert_eq!(
+            Eof::decode(hex!("ef000101000402000100000400000000800000").into()),
+            Err(EofDecodeError::EmptySection)
+        );
+        assert_eq!(
+            Eof::decode(hex!("ef0001010004020001000104000000008000").into()),
+            Err(EofDecodeError::MissingBody)
+        );
+    }
+}
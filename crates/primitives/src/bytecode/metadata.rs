@@ -0,0 +1,211 @@
+//! Detection of the code format and compiler metadata of a bytecode.
+
+use crate::{b256, B256};
+
+/// Magic bytes that start an EOF container, see EIP-3540.
+pub const EOF_MAGIC: [u8; 2] = [0xEF, 0x00];
+
+/// The Keccak-256 hash of [`EOF_MAGIC`], which legacy code sees as the hash of EOF code.
+pub const EOF_MAGIC_HASH: B256 =
+    b256!("9dbf3648db8210552e9c4f75c6a1c3057c0ca432043bd648be15fe7be05646f5");
+
+/// Format of a contract code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CodeVersion {
+    /// Legacy code, executed from its first byte.
+    Legacy,
+    /// EOF container of the given version.
+    Eof(u8),
+}
+
+impl CodeVersion {
+    /// Detects the format of `code` from its prefix.
+    ///
+    /// Code starting with the EOF magic but too short to hold a version is reported as EOF
+    /// version zero, which is not a valid version.
+    pub fn of(code: &[u8]) -> Self {
+        match code {
+            [0xEF, 0x00, version, ..] => Self::Eof(*version),
+            [0xEF, 0x00] => Self::Eof(0),
+            _ => Self::Legacy,
+        }
+    }
+
+    /// Returns `true` if the code is an EOF container.
+    pub fn is_eof(&self) -> bool {
+        matches!(self, Self::Eof(_))
+    }
+}
+
+/// Version of the Solidity compiler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolcVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl SolcVersion {
+    /// Creates a version.
+    pub const fn new(major: u8, minor: u8, patch: u8) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses the `major.minor.patch` prefix of a version string, such as
+    /// `0.8.26-nightly.2024.5.1`.
+    fn parse(version: &[u8]) -> Option<Self> {
+        let mut parts = version
+            .split(|byte| !byte.is_ascii_digit())
+            .map(|part| core::str::from_utf8(part).ok()?.parse().ok());
+        Some(Self::new(parts.next()??, parts.next()??, parts.next()??))
+    }
+}
+
+impl core::fmt::Display for SolcVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Compiler metadata appended to the end of legacy code.
+///
+/// Solidity and Vyper append a CBOR encoded map followed by its length as two big-endian bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeMetadata {
+    /// Version of the Solidity compiler, if recorded.
+    pub solc: Option<SolcVersion>,
+    /// Whether experimental compiler features were used.
+    pub experimental: bool,
+    /// Length of the metadata including the two length bytes.
+    pub len: usize,
+}
+
+impl CodeMetadata {
+    /// Parses the metadata at the end of `code`. Returns `None` if there is none.
+    pub fn parse(code: &[u8]) -> Option<Self> {
+        let [.., hi, lo] = code else { return None };
+        let cbor_len = u16::from_be_bytes([*hi, *lo]) as usize;
+        let start = code.len().checked_sub(cbor_len + 2)?;
+        let mut cbor = Cbor(&code[start..code.len() - 2]);
+
+        let mut metadata = Self {
+            solc: None,
+            experimental: false,
+            len: cbor_len + 2,
+        };
+        let entries = cbor.header(5)?;
+        for _ in 0..entries {
+            let key = cbor.text()?;
+            match cbor.item()? {
+                CborItem::Bytes(&[major, minor, patch]) if key == b"solc" => {
+                    metadata.solc = Some(SolcVersion::new(major, minor, patch));
+                }
+                CborItem::Text(version) if key == b"solc" => {
+                    metadata.solc = SolcVersion::parse(version);
+                }
+                CborItem::Bool(experimental) if key == b"experimental" => {
+                    metadata.experimental = experimental;
+                }
+                _ => {}
+            }
+        }
+        cbor.0.is_empty().then_some(metadata)
+    }
+}
+
+enum CborItem<'a> {
+    Bytes(&'a [u8]),
+    Text(&'a [u8]),
+    Bool(bool),
+}
+
+/// Reader of the small subset of CBOR used by compiler metadata.
+struct Cbor<'a>(&'a [u8]);
+
+impl<'a> Cbor<'a> {
+    /// Reads the header of an item of the given major type and returns its argument.
+    fn header(&mut self, major: u8) -> Option<usize> {
+        let (&first, rest) = self.0.split_first()?;
+        if first >> 5 != major {
+            return None;
+        }
+        let (arg, rest) = match first & 0x1f {
+            arg @ 0..=23 => (arg as usize, rest),
+            24 => (*rest.first()? as usize, &rest[1..]),
+            25 => (
+                u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize,
+                &rest[2..],
+            ),
+            _ => return None,
+        };
+        self.0 = rest;
+        Some(arg)
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn text(&mut self) -> Option<&'a [u8]> {
+        let len = self.header(3)?;
+        self.take(len)
+    }
+
+    fn item(&mut self) -> Option<CborItem<'a>> {
+        match *self.0.first()? {
+            0xf4 | 0xf5 => {
+                let value = self.0[0] == 0xf5;
+                self.0 = &self.0[1..];
+                Some(CborItem::Bool(value))
+            }
+            first if first >> 5 == 2 => {
+                let len = self.header(2)?;
+                self.take(len).map(CborItem::Bytes)
+            }
+            _ => self.text().map(CborItem::Text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+
+    #[test]
+    fn code_version() {
+        assert_eq!(CodeVersion::of(&[0x60, 0x00]), CodeVersion::Legacy);
+        assert_eq!(
+            CodeVersion::of(&[0xEF, 0x00, 0x01, 0x01]),
+            CodeVersion::Eof(1)
+        );
+        assert_eq!(CodeVersion::of(&[0xEF, 0x01]), CodeVersion::Legacy);
+    }
+
+    #[test]
+    fn solc_metadata() {
+        // STOP followed by {"ipfs": <34 bytes>, "solc": 0.8.26}
+        let code = hex::decode(
+            "00a26469706673582212201b6a2c2d3a0c6b4a0f4fbbf0d2bb1d3c9b5a7d3ff6f7a2d5a7c3f6a1b2c3d4e564736f6c634300081a0033",
+        )
+        .unwrap();
+        let metadata = CodeMetadata::parse(&code).unwrap();
+        assert_eq!(metadata.solc, Some(SolcVersion::new(0, 8, 26)));
+        assert!(!metadata.experimental);
+        assert_eq!(metadata.len, code.len() - 1);
+
+        assert_eq!(CodeMetadata::parse(&[0x60, 0x00, 0x00, 0x01]), None);
+    }
+}
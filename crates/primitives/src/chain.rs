@@ -0,0 +1,205 @@
+//! Configuration presets of well known chains.
+use crate::{CfgEnv, CfgEnvWithHandlerCfg, ForkCondition, HandlerCfg, HardforkSchedule, SpecId};
+
+/// Ethereum mainnet chain id.
+pub const MAINNET_CHAIN_ID: u64 = 1;
+/// Sepolia testnet chain id.
+pub const SEPOLIA_CHAIN_ID: u64 = 11155111;
+/// OP mainnet chain id.
+pub const OP_MAINNET_CHAIN_ID: u64 = 10;
+/// Base mainnet chain id.
+pub const BASE_CHAIN_ID: u64 = 8453;
+/// Arbitrum One chain id.
+pub const ARBITRUM_ONE_CHAIN_ID: u64 = 42161;
+/// Polygon PoS chain id.
+pub const POLYGON_CHAIN_ID: u64 = 137;
+
+/// Chain id and hardfork activations of a chain.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChainPreset {
+    /// Chain id.
+    pub chain_id: u64,
+    /// Hardfork activations.
+    pub hardforks: HardforkSchedule,
+    /// Whether the chain runs the Optimism handler.
+    #[cfg(feature = "optimism")]
+    pub is_optimism: bool,
+}
+
+impl ChainPreset {
+    /// Returns the preset of the chain with the given id, if it is known.
+    ///
+    /// OP mainnet and Base are only known with the `optimism` feature enabled.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            MAINNET_CHAIN_ID => Some(Self::mainnet()),
+            SEPOLIA_CHAIN_ID => Some(Self::sepolia()),
+            #[cfg(feature = "optimism")]
+            OP_MAINNET_CHAIN_ID => Some(Self::op_mainnet()),
+            #[cfg(feature = "optimism")]
+            BASE_CHAIN_ID => Some(Self::base()),
+            ARBITRUM_ONE_CHAIN_ID => Some(Self::arbitrum_one()),
+            POLYGON_CHAIN_ID => Some(Self::polygon()),
+            _ => None,
+        }
+    }
+
+    /// Creates a preset of an Ethereum chain.
+    fn ethereum(chain_id: u64, hardforks: HardforkSchedule) -> Self {
+        Self {
+            chain_id,
+            hardforks,
+            #[cfg(feature = "optimism")]
+            is_optimism: false,
+        }
+    }
+
+    /// Ethereum mainnet.
+    pub fn mainnet() -> Self {
+        let hardforks = [
+            (SpecId::FRONTIER, 0),
+            (SpecId::FRONTIER_THAWING, 200_000),
+            (SpecId::HOMESTEAD, 1_150_000),
+            (SpecId::DAO_FORK, 1_920_000),
+            (SpecId::TANGERINE, 2_463_000),
+            (SpecId::SPURIOUS_DRAGON, 2_675_000),
+            (SpecId::BYZANTIUM, 4_370_000),
+            (SpecId::CONSTANTINOPLE, 7_280_000),
+            (SpecId::PETERSBURG, 7_280_000),
+            (SpecId::ISTANBUL, 9_069_000),
+            (SpecId::MUIR_GLACIER, 9_200_000),
+            (SpecId::BERLIN, 12_244_000),
+            (SpecId::LONDON, 12_965_000),
+            (SpecId::ARROW_GLACIER, 13_773_000),
+            (SpecId::GRAY_GLACIER, 15_050_000),
+            (SpecId::MERGE, 15_537_394),
+        ]
+        .into_iter()
+        .fold(HardforkSchedule::new(), |schedule, (spec_id, block)| {
+            schedule.with_fork(spec_id, ForkCondition::Block(block))
+        })
+        .with_fork(SpecId::SHANGHAI, ForkCondition::Timestamp(1_681_338_455))
+        .with_fork(SpecId::CANCUN, ForkCondition::Timestamp(1_710_338_135));
+        Self::ethereum(MAINNET_CHAIN_ID, hardforks)
+    }
+
+    /// Sepolia testnet.
+    pub fn sepolia() -> Self {
+        let hardforks = HardforkSchedule::new()
+            .with_fork(SpecId::LONDON, ForkCondition::Block(0))
+            .with_fork(SpecId::MERGE, ForkCondition::Block(1_735_371))
+            .with_fork(SpecId::SHANGHAI, ForkCondition::Timestamp(1_677_557_088))
+            .with_fork(SpecId::CANCUN, ForkCondition::Timestamp(1_706_655_072));
+        Self::ethereum(SEPOLIA_CHAIN_ID, hardforks)
+    }
+
+    /// Arbitrum One, starting from the Nitro genesis block.
+    ///
+    /// Pre-Nitro blocks can't be executed by revm. ArbOS upgrades are activated on chain and
+    /// are not scheduled here, set the spec of later blocks explicitly.
+    pub fn arbitrum_one() -> Self {
+        let hardforks =
+            HardforkSchedule::new().with_fork(SpecId::LONDON, ForkCondition::Block(22_207_817));
+        Self::ethereum(ARBITRUM_ONE_CHAIN_ID, hardforks)
+    }
+
+    /// Polygon PoS.
+    ///
+    /// Polygon has no merge, blocks from Shanghai onwards still need `prevrandao` to be set in
+    /// the block environment.
+    pub fn polygon() -> Self {
+        let hardforks = HardforkSchedule::new()
+            .with_fork(SpecId::PETERSBURG, ForkCondition::Block(0))
+            .with_fork(SpecId::MUIR_GLACIER, ForkCondition::Block(3_395_000))
+            .with_fork(SpecId::BERLIN, ForkCondition::Block(14_750_000))
+            .with_fork(SpecId::LONDON, ForkCondition::Block(23_850_000))
+            .with_fork(SpecId::SHANGHAI, ForkCondition::Block(50_523_000))
+            .with_fork(SpecId::CANCUN, ForkCondition::Block(54_876_000));
+        Self::ethereum(POLYGON_CHAIN_ID, hardforks)
+    }
+
+    /// OP mainnet, starting from the Bedrock block.
+    #[cfg(feature = "optimism")]
+    pub fn op_mainnet() -> Self {
+        Self {
+            chain_id: OP_MAINNET_CHAIN_ID,
+            hardforks: Self::op_stack_hardforks(105_235_063),
+            is_optimism: true,
+        }
+    }
+
+    /// Base mainnet.
+    #[cfg(feature = "optimism")]
+    pub fn base() -> Self {
+        Self {
+            chain_id: BASE_CHAIN_ID,
+            hardforks: Self::op_stack_hardforks(0),
+            is_optimism: true,
+        }
+    }
+
+    /// Hardforks of an OP stack chain that launched with Bedrock at the given block and follows
+    /// the superchain upgrade schedule.
+    #[cfg(feature = "optimism")]
+    fn op_stack_hardforks(bedrock_block: u64) -> HardforkSchedule {
+        HardforkSchedule::new()
+            .with_fork(SpecId::BEDROCK, ForkCondition::Block(bedrock_block))
+            .with_fork(SpecId::REGOLITH, ForkCondition::Block(bedrock_block))
+            .with_fork(SpecId::SHANGHAI, ForkCondition::Timestamp(1_704_992_401))
+            .with_fork(SpecId::CANYON, ForkCondition::Timestamp(1_704_992_401))
+            .with_fork(SpecId::CANCUN, ForkCondition::Timestamp(1_710_374_401))
+            .with_fork(SpecId::ECOTONE, ForkCondition::Timestamp(1_710_374_401))
+    }
+
+    /// Returns the [SpecId] of the block with the given number and timestamp.
+    pub fn spec_id_at(&self, number: u64, timestamp: u64) -> SpecId {
+        self.hardforks.spec_id_at(number, timestamp)
+    }
+
+    /// Returns the [HandlerCfg] of the block with the given number and timestamp.
+    pub fn handler_cfg_at(&self, number: u64, timestamp: u64) -> HandlerCfg {
+        let spec_id = self.spec_id_at(number, timestamp);
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "optimism")] {
+                HandlerCfg::new_with_optimism(spec_id, self.is_optimism)
+            } else {
+                HandlerCfg::new(spec_id)
+            }
+        }
+    }
+
+    /// Returns the [CfgEnv] of the chain.
+    pub fn cfg_env(&self) -> CfgEnv {
+        let mut cfg = CfgEnv::default();
+        cfg.chain_id = self.chain_id;
+        cfg
+    }
+
+    /// Returns the [CfgEnvWithHandlerCfg] of the block with the given number and timestamp.
+    pub fn cfg_env_with_handler_cfg_at(&self, number: u64, timestamp: u64) -> CfgEnvWithHandlerCfg {
+        CfgEnvWithHandlerCfg::new(self.cfg_env(), self.handler_cfg_at(number, timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mainnet_activations() {
+        let mainnet = ChainPreset::from_chain_id(MAINNET_CHAIN_ID).unwrap();
+        assert_eq!(mainnet.spec_id_at(0, 0), SpecId::FRONTIER);
+        assert_eq!(mainnet.spec_id_at(7_280_000, 0), SpecId::PETERSBURG);
+        assert_eq!(mainnet.spec_id_at(15_537_394, 1_663_224_179), SpecId::MERGE);
+        assert_eq!(
+            mainnet.spec_id_at(19_426_587, 1_710_338_135),
+            SpecId::CANCUN
+        );
+        assert_eq!(mainnet.cfg_env().chain_id, MAINNET_CHAIN_ID);
+    }
+
+    #[test]
+    fn unknown_chain() {
+        assert_eq!(ChainPreset::from_chain_id(31337), None);
+    }
+}
@@ -12,9 +12,26 @@ pub const BLOCK_HASH_HISTORY: usize = 256;
 /// Limit of maximum initcode size is 2 * MAX_CODE_SIZE
 pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
 
+// EIP-7934 constants
+/// Maximum size of a block in bytes.
+pub const MAX_BLOCK_SIZE: u64 = 10_485_760;
+/// Margin left for the block header and the consensus layer.
+pub const BLOCK_SIZE_SAFETY_MARGIN: u64 = 2_097_152;
+/// Maximum size of the RLP encoded execution block since Osaka.
+pub const MAX_RLP_BLOCK_SIZE: u64 = MAX_BLOCK_SIZE - BLOCK_SIZE_SAFETY_MARGIN;
+
 /// Precompile 3 is special in few places
 pub const PRECOMPILE3: Address =
     Address::new([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+
+// EIP-1559 constants
+/// Bound divisor of the base fee change between blocks.
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+/// Ratio of the block gas limit to the gas target.
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+/// Base fee of the first London block.
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+
 // EIP-4844 constants
 /// Gas consumption of a single data blob (== blob byte size).
 pub const GAS_PER_BLOB: u64 = 1 << 17;
@@ -30,5 +47,19 @@ pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = TARGET_BLOB_NUMBER_PER_BLOCK * GAS_PE
 pub const MIN_BLOB_GASPRICE: u64 = 1;
 /// Controls the maximum rate of change for blob gas price.
 pub const BLOB_GASPRICE_UPDATE_FRACTION: u64 = 3338477;
+
+// EIP-7691 constants
+/// Target number of the blob per block since Prague.
+pub const TARGET_BLOB_NUMBER_PER_BLOCK_ELECTRA: u64 = 6;
+/// Max number of blobs per block since Prague.
+pub const MAX_BLOB_NUMBER_PER_BLOCK_ELECTRA: u64 = 9;
+/// Maximum consumable blob gas for data blobs per block since Prague.
+pub const MAX_BLOB_GAS_PER_BLOCK_ELECTRA: u64 = MAX_BLOB_NUMBER_PER_BLOCK_ELECTRA * GAS_PER_BLOB;
+/// Target consumable blob gas for data blobs per block since Prague.
+pub const TARGET_BLOB_GAS_PER_BLOCK_ELECTRA: u64 =
+    TARGET_BLOB_NUMBER_PER_BLOCK_ELECTRA * GAS_PER_BLOB;
+/// Controls the maximum rate of change for blob gas price since Prague.
+pub const BLOB_GASPRICE_UPDATE_FRACTION_ELECTRA: u64 = 5007716;
+
 /// First version of the blob.
 pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
@@ -1,14 +1,25 @@
+#[cfg(feature = "alloy-consensus")]
+mod envelope;
+mod gas_table;
 pub mod handler_cfg;
+#[cfg(feature = "alloy-rpc-types")]
+mod rpc;
 
+pub use gas_table::GasTable;
 pub use handler_cfg::{CfgEnvWithHandlerCfg, EnvWithHandlerCfg, HandlerCfg};
+#[cfg(feature = "alloy-rpc-types")]
+pub use rpc::RpcConversionError;
+#[cfg(feature = "alloy-consensus")]
+pub use envelope::TxEnvelopeError;
 
 use crate::{
     calc_blob_gasprice, Account, Address, Bytes, InvalidHeader, InvalidTransaction, Spec, SpecId,
-    B256, GAS_PER_BLOB, KECCAK_EMPTY, MAX_BLOB_NUMBER_PER_BLOCK, MAX_INITCODE_SIZE, U256,
-    VERSIONED_HASH_VERSION_KZG,
+    TransactionFees, B256, GAS_PER_BLOB, KECCAK_EMPTY, MAX_BLOB_NUMBER_PER_BLOCK,
+    MAX_INITCODE_SIZE, MAX_RLP_BLOCK_SIZE, U256, VERSIONED_HASH_VERSION_KZG,
 };
 use core::cmp::{min, Ordering};
 use std::boxed::Box;
+use std::sync::Arc;
 use std::vec::Vec;
 
 /// EVM environment configuration.
@@ -36,6 +47,13 @@ impl Env {
         Box::new(Self { cfg, block, tx })
     }
 
+    /// Returns the beneficiary of the transaction: its [TxEnv::coinbase] override if set,
+    /// otherwise the [BlockEnv::coinbase].
+    #[inline]
+    pub fn effective_coinbase(&self) -> Address {
+        self.tx.coinbase.unwrap_or(self.block.coinbase)
+    }
+
     /// Calculates the effective gas price of the transaction.
     #[inline]
     pub fn effective_gas_price(&self) -> U256 {
@@ -71,6 +89,29 @@ impl Env {
         })
     }
 
+    /// Returns the fees paid by the transaction for `gas_used`.
+    ///
+    /// Before London the whole gas price goes to the beneficiary and nothing is burnt.
+    /// System transactions pay no fee.
+    pub fn transaction_fees(&self, spec_id: SpecId, gas_used: u64) -> TransactionFees {
+        if self.tx.is_system_tx {
+            return TransactionFees::default();
+        }
+        let effective_gas_price = self.effective_gas_price();
+        let base_fee = if spec_id.is_enabled_in(SpecId::LONDON) {
+            min(self.block.basefee, effective_gas_price)
+        } else {
+            U256::ZERO
+        };
+        let gas_used = U256::from(gas_used);
+        TransactionFees {
+            effective_gas_price,
+            priority_fee: (effective_gas_price - base_fee).saturating_mul(gas_used),
+            burnt_base_fee: base_fee.saturating_mul(gas_used),
+            blob_fee: self.calc_data_fee().unwrap_or_default(),
+        }
+    }
+
     /// Validate the block environment.
     #[inline]
     pub fn validate_block_env<SPEC: Spec>(&self) -> Result<(), InvalidHeader> {
@@ -85,6 +126,26 @@ impl Env {
         Ok(())
     }
 
+    /// Validates the RLP encoded size of a block that includes the transaction.
+    ///
+    /// Not called during execution, block builders call it before including the transaction.
+    /// The limit is [CfgEnv::limit_block_rlp_size], or [MAX_RLP_BLOCK_SIZE] since Osaka
+    /// (EIP-7934).
+    #[inline]
+    pub fn validate_block_rlp_size<SPEC: Spec>(
+        &self,
+        block_rlp_size: u64,
+    ) -> Result<(), InvalidTransaction> {
+        let limit = self
+            .cfg
+            .limit_block_rlp_size
+            .or(SPEC::enabled(SpecId::OSAKA).then_some(MAX_RLP_BLOCK_SIZE));
+        match limit {
+            Some(limit) if block_rlp_size > limit => Err(InvalidTransaction::BlockRlpSizeLimit),
+            _ => Ok(()),
+        }
+    }
+
     /// Validate transaction data that is set inside ENV and return error if something is wrong.
     ///
     /// Return initial spend gas (Gas needed to execute transaction).
@@ -101,6 +162,7 @@ impl Env {
 
             // check minimal cost against basefee
             if !self.cfg.is_base_fee_check_disabled()
+                && !self.tx.is_system_tx
                 && self.effective_gas_price() < self.block.basefee
             {
                 return Err(InvalidTransaction::GasPriceLessThanBasefee);
@@ -109,6 +171,7 @@ impl Env {
 
         // Check if gas_limit is more than block_gas_limit
         if !self.cfg.is_block_gas_limit_disabled()
+            && !self.tx.is_system_tx
             && U256::from(self.tx.gas_limit) > self.block.gas_limit
         {
             return Err(InvalidTransaction::CallerGasLimitMoreThanBlock);
@@ -126,9 +189,21 @@ impl Env {
             }
         }
 
+        // Check the calldata and encoded size limits of the config
+        if let Some(limit) = self.cfg.limit_calldata_size {
+            if self.tx.data.len() > limit {
+                return Err(InvalidTransaction::CalldataSizeLimit);
+            }
+        }
+        if let (Some(limit), Some(stats)) = (self.cfg.limit_tx_size, self.tx.envelope_stats) {
+            if stats.size > limit {
+                return Err(InvalidTransaction::TxSizeLimit);
+            }
+        }
+
         // Check if the transaction's chain id is correct
         if let Some(tx_chain_id) = self.tx.chain_id {
-            if tx_chain_id != self.cfg.chain_id {
+            if !self.cfg.is_chain_id_check_disabled() && tx_chain_id != self.cfg.chain_id {
                 return Err(InvalidTransaction::InvalidChainId);
             }
         }
@@ -188,54 +263,107 @@ impl Env {
     }
 
     /// Validate transaction against state.
+    ///
+    /// System transactions skip the sender checks and pay no fee, only their value has to be
+    /// covered by the balance of the caller.
     #[inline]
     pub fn validate_tx_against_state<SPEC: Spec>(
         &self,
         account: &mut Account,
     ) -> Result<(), InvalidTransaction> {
-        // EIP-3607: Reject transactions from senders with deployed code
-        // This EIP is introduced after london but there was no collision in past
-        // so we can leave it enabled always
-        if !self.cfg.is_eip3607_disabled() && account.info.code_hash != KECCAK_EMPTY {
-            return Err(InvalidTransaction::RejectCallerWithCode);
+        if self.tx.is_system_tx {
+            return self.validate_balance(account, self.tx.value);
         }
+        self.validate_sender(account)?;
 
-        // Check that the transaction's nonce is correct
-        if let Some(tx) = self.tx.nonce {
-            let state = account.info.nonce;
-            match tx.cmp(&state) {
-                Ordering::Greater => {
-                    return Err(InvalidTransaction::NonceTooHigh { tx, state });
-                }
-                Ordering::Less => {
-                    return Err(InvalidTransaction::NonceTooLow { tx, state });
-                }
-                _ => {}
-            }
-        }
+        let balance_check = self
+            .max_tx_fee::<SPEC>()?
+            .checked_add(self.tx.value)
+            .ok_or(InvalidTransaction::OverflowPaymentInTransaction)?;
+
+        // Check if account has enough balance for gas_limit*gas_price and value transfer.
+        // Transfer will be done inside `*_inner` functions.
+        self.validate_balance(account, balance_check)
+    }
 
-        let mut balance_check = U256::from(self.tx.gas_limit)
+    /// Validates the caller of a transaction whose fees are paid by another account.
+    ///
+    /// The caller only needs the balance for the value transfer, the fee payer is validated
+    /// with [Env::validate_fee_payer_against_state].
+    pub fn validate_sponsored_tx_against_state<SPEC: Spec>(
+        &self,
+        account: &mut Account,
+    ) -> Result<(), InvalidTransaction> {
+        self.validate_sender(account)?;
+        self.validate_balance(account, self.tx.value)
+    }
+
+    /// Validates that the account paying the fees of the transaction has enough balance for them.
+    pub fn validate_fee_payer_against_state<SPEC: Spec>(
+        &self,
+        account: &mut Account,
+    ) -> Result<(), InvalidTransaction> {
+        let max_fee = self.max_tx_fee::<SPEC>()?;
+        self.validate_balance(account, max_fee)
+    }
+
+    /// Returns the maximum fee of the transaction, `gas_limit * gas_price` plus the maximum
+    /// blob fee since Cancun.
+    pub fn max_tx_fee<SPEC: Spec>(&self) -> Result<U256, InvalidTransaction> {
+        let mut max_fee = U256::from(self.tx.gas_limit)
             .checked_mul(self.tx.gas_price)
-            .and_then(|gas_cost| gas_cost.checked_add(self.tx.value))
             .ok_or(InvalidTransaction::OverflowPaymentInTransaction)?;
 
         if SPEC::enabled(SpecId::CANCUN) {
             // if the tx is not a blob tx, this will be None, so we add zero
             let data_fee = self.calc_max_data_fee().unwrap_or_default();
-            balance_check = balance_check
+            max_fee = max_fee
                 .checked_add(U256::from(data_fee))
                 .ok_or(InvalidTransaction::OverflowPaymentInTransaction)?;
         }
+        Ok(max_fee)
+    }
 
-        // Check if account has enough balance for gas_limit*gas_price and value transfer.
-        // Transfer will be done inside `*_inner` functions.
-        if balance_check > account.info.balance {
+    /// Validates the code and the nonce of the sender of the transaction.
+    fn validate_sender(&self, account: &Account) -> Result<(), InvalidTransaction> {
+        // EIP-3607: Reject transactions from senders with deployed code
+        // This EIP is introduced after london but there was no collision in past
+        // so we can leave it enabled always
+        if !self.cfg.is_eip3607_disabled() && account.info.code_hash != KECCAK_EMPTY {
+            return Err(InvalidTransaction::RejectCallerWithCode);
+        }
+
+        // Check that the transaction's nonce is correct
+        match self.tx.nonce {
+            Some(tx) if !self.cfg.is_nonce_check_disabled() => {
+                Self::validate_nonce(tx, account.info.nonce)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates the nonce of a transaction against the nonce of its sender.
+    pub fn validate_nonce(tx: u64, state: u64) -> Result<(), InvalidTransaction> {
+        match tx.cmp(&state) {
+            Ordering::Greater => Err(InvalidTransaction::NonceTooHigh { tx, state }),
+            Ordering::Less => Err(InvalidTransaction::NonceTooLow { tx, state }),
+            Ordering::Equal => Ok(()),
+        }
+    }
+
+    /// Validates that the account has the required balance.
+    fn validate_balance(
+        &self,
+        account: &mut Account,
+        required: U256,
+    ) -> Result<(), InvalidTransaction> {
+        if required > account.info.balance {
             if self.cfg.is_balance_check_disabled() {
                 // Add transaction cost to balance to ensure execution doesn't fail.
-                account.info.balance = balance_check;
+                account.info.balance = required;
             } else {
                 return Err(InvalidTransaction::LackOfFundForMaxFee {
-                    fee: Box::new(balance_check),
+                    fee: Box::new(required),
                     balance: Box::new(account.info.balance),
                 });
             }
@@ -265,6 +393,20 @@ pub struct CfgEnv {
     /// If some it will effects EIP-170: Contract code size limit. Useful to increase this because of tests.
     /// By default it is 0x6000 (~25kb).
     pub limit_contract_code_size: Option<usize>,
+    /// If some, transactions with more calldata are rejected in the validation stage.
+    /// By default, it is set to `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub limit_calldata_size: Option<usize>,
+    /// If some, transactions whose [TxEnvelopeStats::size] is bigger are rejected in the
+    /// validation stage. Transactions without envelope stats are not checked.
+    /// By default, it is set to `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub limit_tx_size: Option<u64>,
+    /// If some, overrides the RLP encoded block size limit checked by
+    /// [Env::validate_block_rlp_size].
+    /// By default, it is set to `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub limit_block_rlp_size: Option<u64>,
     /// A hard memory limit in bytes beyond which [crate::result::OutOfGasError::Memory] cannot be resized.
     ///
     /// In cases where the gas limit may be extraordinarily high, it is recommended to set this to
@@ -275,6 +417,15 @@ pub struct CfgEnv {
     /// Skip balance checks if true. Adds transaction cost to balance to ensure execution doesn't fail.
     #[cfg(feature = "optional_balance_check")]
     pub disable_balance_check: bool,
+    /// Skips the check of the transaction nonce against the nonce of the sender. The nonce of
+    /// the sender is still incremented.
+    /// By default, it is set to `false`.
+    #[cfg(feature = "optional_nonce_check")]
+    pub disable_nonce_check: bool,
+    /// Accepts transactions signed for another chain than [CfgEnv::chain_id].
+    /// By default, it is set to `false`.
+    #[cfg(feature = "optional_chain_id_check")]
+    pub disable_chain_id_check: bool,
     /// There are use cases where it's allowed to provide a gas limit that's higher than a block's gas limit. To that
     /// end, you can disable the block gas limit validation.
     /// By default, it is set to `false`.
@@ -299,6 +450,25 @@ pub struct CfgEnv {
     /// By default, it is set to `false`.
     #[cfg(feature = "optional_beneficiary_reward")]
     pub disable_beneficiary_reward: bool,
+    /// Keeps accessed addresses and storage slots warm for the following transactions, for
+    /// simulating chains with relaxed warming rules.
+    /// By default, it is set to [WarmCarryover::None].
+    #[cfg(feature = "optional_warm_carryover")]
+    pub warm_carryover: WarmCarryover,
+    /// Static opcode costs replacing the ones of the spec, for experimenting with repricings.
+    /// Applied by the gas table handle register of revm. The interpreter's `spec_gas_table`
+    /// returns the costs of a spec to start from.
+    /// By default, it is set to `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub gas_table: Option<Arc<GasTable>>,
+    /// Limits of the gas refunded at the end of the transaction.
+    /// By default, it is set to the limits of the spec.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub refund_policy: RefundPolicy,
+    /// Cap on the size of the data returned by calls, for hosts executing untrusted code.
+    /// By default, it is set to `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub return_data_limit: Option<ReturnDataLimit>,
 }
 
 impl CfgEnv {
@@ -322,6 +492,26 @@ impl CfgEnv {
         false
     }
 
+    #[cfg(feature = "optional_nonce_check")]
+    pub fn is_nonce_check_disabled(&self) -> bool {
+        self.disable_nonce_check
+    }
+
+    #[cfg(not(feature = "optional_nonce_check"))]
+    pub fn is_nonce_check_disabled(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "optional_chain_id_check")]
+    pub fn is_chain_id_check_disabled(&self) -> bool {
+        self.disable_chain_id_check
+    }
+
+    #[cfg(not(feature = "optional_chain_id_check"))]
+    pub fn is_chain_id_check_disabled(&self) -> bool {
+        false
+    }
+
     #[cfg(feature = "optional_gas_refund")]
     pub fn is_gas_refund_disabled(&self) -> bool {
         self.disable_gas_refund
@@ -361,6 +551,44 @@ impl CfgEnv {
     pub fn is_beneficiary_reward_disabled(&self) -> bool {
         false
     }
+
+    /// Disables every transaction validation check that can be disabled with the enabled
+    /// `optional_*` features: nonce, balance, base fee, chain ID and block gas limit checks.
+    ///
+    /// This is the configuration of simulators executing transactions that would not be
+    /// included in a block, such as `eth_call`. EIP-3607 stays enforced.
+    pub fn disable_validation_checks(&mut self) {
+        #[cfg(feature = "optional_nonce_check")]
+        {
+            self.disable_nonce_check = true;
+        }
+        #[cfg(feature = "optional_balance_check")]
+        {
+            self.disable_balance_check = true;
+        }
+        #[cfg(feature = "optional_no_base_fee")]
+        {
+            self.disable_base_fee = true;
+        }
+        #[cfg(feature = "optional_chain_id_check")]
+        {
+            self.disable_chain_id_check = true;
+        }
+        #[cfg(feature = "optional_block_gas_limit")]
+        {
+            self.disable_block_gas_limit = true;
+        }
+    }
+
+    #[cfg(feature = "optional_warm_carryover")]
+    pub fn warm_carryover(&self) -> WarmCarryover {
+        self.warm_carryover
+    }
+
+    #[cfg(not(feature = "optional_warm_carryover"))]
+    pub fn warm_carryover(&self) -> WarmCarryover {
+        WarmCarryover::None
+    }
 }
 
 impl Default for CfgEnv {
@@ -369,12 +597,19 @@ impl Default for CfgEnv {
             chain_id: 1,
             perf_analyse_created_bytecodes: AnalysisKind::default(),
             limit_contract_code_size: None,
+            limit_calldata_size: None,
+            limit_tx_size: None,
+            limit_block_rlp_size: None,
             #[cfg(feature = "c-kzg")]
             kzg_settings: crate::kzg::EnvKzgSettings::Default,
             #[cfg(feature = "memory_limit")]
             memory_limit: (1 << 32) - 1,
             #[cfg(feature = "optional_balance_check")]
             disable_balance_check: false,
+            #[cfg(feature = "optional_nonce_check")]
+            disable_nonce_check: false,
+            #[cfg(feature = "optional_chain_id_check")]
+            disable_chain_id_check: false,
             #[cfg(feature = "optional_block_gas_limit")]
             disable_block_gas_limit: false,
             #[cfg(feature = "optional_eip3607")]
@@ -385,6 +620,11 @@ impl Default for CfgEnv {
             disable_base_fee: false,
             #[cfg(feature = "optional_beneficiary_reward")]
             disable_beneficiary_reward: false,
+            #[cfg(feature = "optional_warm_carryover")]
+            warm_carryover: WarmCarryover::None,
+            gas_table: None,
+            refund_policy: RefundPolicy::default(),
+            return_data_limit: None,
         }
     }
 }
@@ -539,6 +779,26 @@ pub struct TxEnv {
     /// [EIP-4844]: https://eips.ethereum.org/EIPS/eip-4844
     pub max_fee_per_blob_gas: Option<U256>,
 
+    /// Size and byte statistics of the RLP (EIP-2718) encoded transaction.
+    ///
+    /// Not used by mainnet execution. L2 handlers use it to compute the L1 data fee
+    /// without re-encoding the transaction.
+    pub envelope_stats: Option<TxEnvelopeStats>,
+
+    /// Whether the transaction is a system transaction, such as an L2 deposit, a genesis call or
+    /// a pre-block system operation.
+    ///
+    /// System transactions skip the nonce, code, base fee and block gas limit checks, pay no
+    /// fee and don't bump the nonce of the caller on calls. Transferred value still has to be
+    /// covered by the caller.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub is_system_tx: bool,
+
+    /// Overrides the [BlockEnv::coinbase] for this transaction. The override receives the fees
+    /// and is returned by the `COINBASE` opcode.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub coinbase: Option<Address>,
+
     #[cfg_attr(feature = "serde", serde(flatten))]
     #[cfg(feature = "optimism")]
     pub optimism: OptimismFields,
@@ -575,12 +835,40 @@ impl Default for TxEnv {
             access_list: Vec::new(),
             blob_hashes: Vec::new(),
             max_fee_per_blob_gas: None,
+            envelope_stats: None,
+            is_system_tx: false,
+            coinbase: None,
             #[cfg(feature = "optimism")]
             optimism: OptimismFields::default(),
         }
     }
 }
 
+/// Size and zero/non-zero byte counts of an encoded transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxEnvelopeStats {
+    /// Length of the encoded transaction in bytes.
+    pub size: u64,
+    /// Number of zero bytes in the encoded transaction.
+    pub zero_bytes: u64,
+    /// Number of non-zero bytes in the encoded transaction.
+    pub non_zero_bytes: u64,
+}
+
+impl TxEnvelopeStats {
+    /// Computes the statistics of an encoded transaction.
+    pub fn new(encoded: &[u8]) -> Self {
+        let zero_bytes = encoded.iter().filter(|byte| **byte == 0).count() as u64;
+        let size = encoded.len() as u64;
+        Self {
+            size,
+            zero_bytes,
+            non_zero_bytes: size - zero_bytes,
+        }
+    }
+}
+
 /// Structure holding block blob excess gas and it calculates blob fee.
 ///
 /// Incorporated as part of the Cancun upgrade via [EIP-4844].
@@ -693,6 +981,12 @@ pub enum CreateScheme {
         /// Salt.
         salt: U256,
     },
+    /// Create scheme of the EOF `EOFCREATE`, addressed like `CREATE2` with the hash of the
+    /// initcontainer.
+    EofCreate {
+        /// Salt.
+        salt: U256,
+    },
 }
 
 /// What bytecode analysis to perform.
@@ -708,6 +1002,118 @@ pub enum AnalysisKind {
     Analyse,
 }
 
+/// What stays warm from one transaction to the next.
+///
+/// Mainnet resets the accessed addresses and storage slots after every transaction (EIP-2929).
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WarmCarryover {
+    /// Nothing, every transaction starts cold.
+    #[default]
+    None,
+    /// Accessed addresses stay warm.
+    Addresses,
+    /// Accessed addresses and storage slots stay warm.
+    AddressesAndSlots,
+}
+
+/// Limits of the gas refunded at the end of a transaction.
+///
+/// The refund is capped to the gas spent divided by the quotient, and to the absolute cap if
+/// there is one.
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefundPolicy {
+    /// Quotient of the gas spent that can be refunded. A quotient of zero disables refunds.
+    ///
+    /// If `None`, the quotient of the spec is used: 2 before London and 5 since (EIP-3529).
+    pub max_refund_quotient: Option<u64>,
+    /// Maximum gas refunded, regardless of the gas spent.
+    pub max_refund: Option<u64>,
+}
+
+impl RefundPolicy {
+    /// Policy of chains without gas refunds.
+    pub const DISABLED: Self = Self {
+        max_refund_quotient: Some(0),
+        max_refund: Some(0),
+    };
+
+    /// Returns the quotient of the gas spent that can be refunded in the given spec.
+    pub fn refund_quotient(&self, spec_id: SpecId) -> u64 {
+        let spec_quotient = if spec_id.is_enabled_in(SpecId::LONDON) {
+            5
+        } else {
+            2
+        };
+        self.max_refund_quotient.unwrap_or(spec_quotient)
+    }
+
+    /// Returns the maximum gas refunded for the gas spent in the given spec.
+    pub fn max_refund(&self, spec_id: SpecId, gas_spent: u64) -> u64 {
+        let max_refund = gas_spent
+            .checked_div(self.refund_quotient(spec_id))
+            .unwrap_or_default();
+        self.max_refund.map_or(max_refund, |cap| max_refund.min(cap))
+    }
+}
+
+/// Cap on the size of the data returned by calls.
+///
+/// The cap applies to the output of calls and to the revert data of creates. Code returned by
+/// creates is limited by the contract code size limit instead.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReturnDataLimit {
+    /// Maximum size of the returned data in bytes.
+    pub max_size: usize,
+    /// Whether the cap applies to every frame instead of only the top-level one.
+    pub per_frame: bool,
+    /// What happens to a frame returning more data than the cap.
+    pub action: ReturnDataLimitAction,
+}
+
+impl ReturnDataLimit {
+    /// Creates a cap on the top-level return data that halts execution when exceeded.
+    pub const fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            per_frame: false,
+            action: ReturnDataLimitAction::Halt,
+        }
+    }
+
+    /// Applies the cap to every frame.
+    pub const fn per_frame(mut self) -> Self {
+        self.per_frame = true;
+        self
+    }
+
+    /// Truncates the returned data to the cap instead of halting.
+    pub const fn truncating(mut self) -> Self {
+        self.action = ReturnDataLimitAction::Truncate;
+        self
+    }
+
+    /// Returns `true` if the cap applies to a frame at the given depth, starting at 1 for the
+    /// top-level frame.
+    pub const fn applies_to_depth(&self, depth: u64) -> bool {
+        self.per_frame || depth <= 1
+    }
+}
+
+/// What happens to a frame returning more data than the [ReturnDataLimit].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReturnDataLimitAction {
+    /// The frame halts with [HaltReason::ReturnDataLimit](crate::HaltReason::ReturnDataLimit)
+    /// and its state changes are reverted.
+    #[default]
+    Halt,
+    /// The returned data is truncated to the cap.
+    Truncate,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -723,6 +1129,78 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(all(
+        feature = "optional_nonce_check",
+        feature = "optional_balance_check",
+        feature = "optional_no_base_fee",
+        feature = "optional_chain_id_check",
+        feature = "optional_block_gas_limit"
+    ))]
+    fn test_disable_validation_checks() {
+        let mut env = Env::default();
+        env.tx.chain_id = Some(2);
+        env.tx.gas_limit = u64::MAX;
+        env.tx.gas_price = U256::from(1);
+        env.tx.nonce = Some(5);
+        env.block.basefee = U256::from(10);
+        env.block.gas_limit = U256::from(30_000_000);
+        let mut account = Account::default();
+        assert!(env.validate_tx::<crate::LatestSpec>().is_err());
+        assert!(env
+            .validate_tx_against_state::<crate::LatestSpec>(&mut account)
+            .is_err());
+
+        env.cfg.disable_validation_checks();
+        assert_eq!(env.validate_tx::<crate::LatestSpec>(), Ok(()));
+        assert_eq!(
+            env.validate_tx_against_state::<crate::LatestSpec>(&mut account),
+            Ok(())
+        );
+        assert_eq!(account.info.balance, U256::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_system_tx_value_against_state() {
+        let mut env = Env::default();
+        env.tx.is_system_tx = true;
+        env.tx.nonce = Some(5);
+        env.tx.gas_price = U256::from(1);
+        env.tx.value = U256::from(10);
+        let mut account = Account::default();
+        account.info.balance = U256::from(10);
+        // Nonce and fee are not checked, the value is.
+        assert_eq!(
+            env.validate_tx_against_state::<crate::LatestSpec>(&mut account),
+            Ok(())
+        );
+
+        env.tx.value = U256::from(11);
+        assert_eq!(
+            env.validate_tx_against_state::<crate::LatestSpec>(&mut account),
+            Err(InvalidTransaction::LackOfFundForMaxFee {
+                fee: Box::new(U256::from(11)),
+                balance: Box::new(U256::from(10)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_refund_policy() {
+        let policy = RefundPolicy::default();
+        assert_eq!(policy.max_refund(SpecId::BERLIN, 100_000), 50_000);
+        assert_eq!(policy.max_refund(SpecId::LONDON, 100_000), 20_000);
+
+        let policy = RefundPolicy {
+            max_refund_quotient: Some(4),
+            max_refund: Some(10_000),
+        };
+        assert_eq!(policy.max_refund(SpecId::LONDON, 20_000), 5_000);
+        assert_eq!(policy.max_refund(SpecId::LONDON, 100_000), 10_000);
+
+        assert_eq!(RefundPolicy::DISABLED.max_refund(SpecId::LONDON, 100_000), 0);
+    }
+
     #[test]
     fn test_validate_tx_access_list() {
         let mut env = Env::default();
@@ -732,4 +1210,57 @@ mod tests {
             Err(InvalidTransaction::AccessListNotSupported)
         );
     }
+
+    #[test]
+    fn test_validate_tx_size_limits() {
+        let mut env = Env::default();
+        env.tx.data = Bytes::from(vec![1; 10]);
+        env.tx.envelope_stats = Some(TxEnvelopeStats::new(&[1; 20]));
+        assert_eq!(env.validate_tx::<crate::LatestSpec>(), Ok(()));
+
+        env.cfg.limit_tx_size = Some(16);
+        assert_eq!(
+            env.validate_tx::<crate::LatestSpec>(),
+            Err(InvalidTransaction::TxSizeLimit)
+        );
+
+        env.cfg.limit_calldata_size = Some(8);
+        assert_eq!(
+            env.validate_tx::<crate::LatestSpec>(),
+            Err(InvalidTransaction::CalldataSizeLimit)
+        );
+    }
+
+    #[test]
+    fn test_validate_block_rlp_size() {
+        let mut env = Env::default();
+        let size = MAX_RLP_BLOCK_SIZE + 1;
+        assert_eq!(
+            env.validate_block_rlp_size::<crate::PragueSpec>(size),
+            Ok(())
+        );
+        assert_eq!(
+            env.validate_block_rlp_size::<crate::OsakaSpec>(size),
+            Err(InvalidTransaction::BlockRlpSizeLimit)
+        );
+
+        env.cfg.limit_block_rlp_size = Some(size);
+        assert_eq!(
+            env.validate_block_rlp_size::<crate::OsakaSpec>(size),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_tx_envelope_stats() {
+        let stats = TxEnvelopeStats::new(&[0x02, 0x00, 0x00, 0xff]);
+        assert_eq!(
+            stats,
+            TxEnvelopeStats {
+                size: 4,
+                zero_bytes: 2,
+                non_zero_bytes: 2,
+            }
+        );
+    }
 }
@@ -0,0 +1,308 @@
+//! Conversions from signed `alloy-consensus` transaction envelopes.
+//!
+//! Legacy, EIP-2930, EIP-1559 and EIP-4844 transactions are supported. EIP-7702 set code
+//! transactions are out of scope: [TxEnv] has no authorization list, and the pinned
+//! `alloy-consensus` can't decode them.
+use super::{TransactTo, TxEnv};
+use crate::{Address, U256};
+use alloy_consensus::{TxEip4844Variant, TxEnvelope};
+use alloy_eips::eip2930::AccessList;
+use alloy_primitives::TxKind;
+use core::fmt;
+use std::vec::Vec;
+
+/// Error returned when a signed transaction can't be converted to a [TxEnv].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TxEnvelopeError {
+    /// The caller could not be recovered from the signature.
+    InvalidSignature,
+    /// Field value does not fit into the environment type.
+    Overflow(&'static str),
+    /// Transaction type is not supported by [TxEnv], like EIP-7702 set code transactions.
+    UnsupportedType(u8),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TxEnvelopeError {}
+
+impl fmt::Display for TxEnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSignature => f.write_str("invalid transaction signature"),
+            Self::Overflow(field) => write!(f, "field `{field}` overflows"),
+            Self::UnsupportedType(ty) => write!(f, "unsupported transaction type {ty}"),
+        }
+    }
+}
+
+impl TxEnv {
+    /// Creates the transaction environment from a signed transaction envelope.
+    ///
+    /// The caller is recovered from the signature. Use [`TxEnv::from_envelope_with_caller`] if
+    /// the sender is already known.
+    pub fn try_from_envelope(envelope: &TxEnvelope) -> Result<Self, TxEnvelopeError> {
+        let caller = match envelope {
+            TxEnvelope::Legacy(tx) => tx.recover_signer(),
+            TxEnvelope::Eip2930(tx) => tx.recover_signer(),
+            TxEnvelope::Eip1559(tx) => tx.recover_signer(),
+            TxEnvelope::Eip4844(tx) => tx.recover_signer(),
+            _ => return Err(TxEnvelopeError::UnsupportedType(envelope.tx_type() as u8)),
+        }
+        .map_err(|_| TxEnvelopeError::InvalidSignature)?;
+        Self::from_envelope_with_caller(envelope, caller)
+    }
+
+    /// Creates the transaction environment from a transaction envelope and a known caller,
+    /// skipping signature recovery.
+    pub fn from_envelope_with_caller(
+        envelope: &TxEnvelope,
+        caller: Address,
+    ) -> Result<Self, TxEnvelopeError> {
+        let gas_limit = |gas_limit| {
+            u64::try_from(gas_limit).map_err(|_| TxEnvelopeError::Overflow("gas_limit"))
+        };
+        let mut env = Self {
+            caller,
+            ..Default::default()
+        };
+        match envelope {
+            TxEnvelope::Legacy(tx) => {
+                let tx = tx.tx();
+                env.gas_limit = gas_limit(tx.gas_limit)?;
+                env.gas_price = U256::from(tx.gas_price);
+                env.transact_to = transact_to(tx.to);
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.nonce = Some(tx.nonce);
+                env.chain_id = tx.chain_id;
+            }
+            TxEnvelope::Eip2930(tx) => {
+                let tx = tx.tx();
+                env.gas_limit = gas_limit(tx.gas_limit)?;
+                env.gas_price = U256::from(tx.gas_price);
+                env.transact_to = transact_to(tx.to);
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.nonce = Some(tx.nonce);
+                env.chain_id = Some(tx.chain_id);
+                env.access_list = access_list(&tx.access_list);
+            }
+            TxEnvelope::Eip1559(tx) => {
+                let tx = tx.tx();
+                env.gas_limit = gas_limit(tx.gas_limit)?;
+                env.gas_price = U256::from(tx.max_fee_per_gas);
+                env.gas_priority_fee = Some(U256::from(tx.max_priority_fee_per_gas));
+                env.transact_to = transact_to(tx.to);
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.nonce = Some(tx.nonce);
+                env.chain_id = Some(tx.chain_id);
+                env.access_list = access_list(&tx.access_list);
+            }
+            TxEnvelope::Eip4844(tx) => {
+                let tx = match tx.tx() {
+                    TxEip4844Variant::TxEip4844(tx) => tx,
+                    TxEip4844Variant::TxEip4844WithSidecar(tx) => &tx.tx,
+                };
+                env.gas_limit = gas_limit(tx.gas_limit)?;
+                env.gas_price = U256::from(tx.max_fee_per_gas);
+                env.gas_priority_fee = Some(U256::from(tx.max_priority_fee_per_gas));
+                env.transact_to = TransactTo::call(tx.to);
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.nonce = Some(tx.nonce);
+                env.chain_id = Some(tx.chain_id);
+                env.access_list = access_list(&tx.access_list);
+                env.blob_hashes = tx.blob_versioned_hashes.clone();
+                env.max_fee_per_blob_gas = Some(U256::from(tx.max_fee_per_blob_gas));
+            }
+            // Envelopes of later transaction types, see the module documentation.
+            _ => return Err(TxEnvelopeError::UnsupportedType(envelope.tx_type() as u8)),
+        }
+        Ok(env)
+    }
+}
+
+#[inline]
+fn transact_to(kind: TxKind) -> TransactTo {
+    match kind {
+        TxKind::Call(address) => TransactTo::call(address),
+        TxKind::Create => TransactTo::create(),
+    }
+}
+
+#[inline]
+fn access_list(list: &AccessList) -> Vec<(Address, Vec<U256>)> {
+    list.0
+        .iter()
+        .map(|item| {
+            (
+                item.address,
+                item.storage_keys
+                    .iter()
+                    .map(|key| U256::from_be_bytes(key.0))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{address, bytes, Bytes, B256};
+    use alloy_consensus::{SignableTransaction, TxEip1559, TxEip2930, TxEip4844, TxLegacy};
+    use alloy_eips::{
+        eip2718::{Decodable2718, Encodable2718},
+        eip2930::AccessListItem,
+    };
+    use alloy_primitives::Signature;
+
+    const CALLER: Address = address!("00000000000000000000000000000000000000ca");
+    const TO: Address = address!("00000000000000000000000000000000000000cc");
+
+    fn signature(r: u64) -> Signature {
+        Signature::from_rs_and_parity(U256::from(r), U256::from(1), false).unwrap()
+    }
+
+    fn access_list() -> AccessList {
+        AccessList(vec![AccessListItem {
+            address: TO,
+            storage_keys: vec![B256::with_last_byte(1)],
+        }])
+    }
+
+    /// Encodes and decodes the envelope, and converts both with the same caller.
+    fn round_trip(envelope: TxEnvelope) -> TxEnv {
+        let encoded = envelope.encoded_2718();
+        let decoded = TxEnvelope::decode_2718(&mut encoded.as_slice()).unwrap();
+        let env = TxEnv::from_envelope_with_caller(&decoded, CALLER).unwrap();
+        assert_eq!(
+            env,
+            TxEnv::from_envelope_with_caller(&envelope, CALLER).unwrap()
+        );
+        env
+    }
+
+    #[test]
+    fn legacy() {
+        let tx = TxLegacy {
+            chain_id: Some(1),
+            nonce: 3,
+            gas_price: 10,
+            gas_limit: 21_000,
+            to: TxKind::Call(TO),
+            value: U256::from(5),
+            input: bytes!("01"),
+        };
+        let env = round_trip(TxEnvelope::Legacy(tx.into_signed(signature(1))));
+        assert_eq!(env.caller, CALLER);
+        assert_eq!(env.gas_limit, 21_000);
+        assert_eq!(env.gas_price, U256::from(10));
+        assert_eq!(env.gas_priority_fee, None);
+        assert_eq!(env.transact_to, TransactTo::call(TO));
+        assert_eq!(env.value, U256::from(5));
+        assert_eq!(env.data, bytes!("01"));
+        assert_eq!(env.nonce, Some(3));
+        assert_eq!(env.chain_id, Some(1));
+    }
+
+    #[test]
+    fn eip2930() {
+        let tx = TxEip2930 {
+            chain_id: 1,
+            nonce: 3,
+            gas_price: 10,
+            gas_limit: 30_000,
+            to: TxKind::Call(TO),
+            value: U256::ZERO,
+            access_list: access_list(),
+            input: Bytes::new(),
+        };
+        let env = round_trip(TxEnvelope::Eip2930(tx.into_signed(signature(1))));
+        assert_eq!(env.gas_price, U256::from(10));
+        assert_eq!(env.chain_id, Some(1));
+        assert_eq!(env.access_list, [(TO, vec![U256::from(1)])]);
+    }
+
+    #[test]
+    fn eip1559() {
+        let tx = TxEip1559 {
+            chain_id: 1,
+            nonce: 3,
+            gas_limit: 100_000,
+            max_fee_per_gas: 20,
+            max_priority_fee_per_gas: 2,
+            to: TxKind::Create,
+            value: U256::ZERO,
+            access_list: access_list(),
+            input: bytes!("6000"),
+        };
+        let env = round_trip(TxEnvelope::Eip1559(tx.into_signed(signature(1))));
+        assert_eq!(env.gas_price, U256::from(20));
+        assert_eq!(env.gas_priority_fee, Some(U256::from(2)));
+        assert_eq!(env.transact_to, TransactTo::create());
+        assert_eq!(env.data, bytes!("6000"));
+        assert_eq!(env.access_list, [(TO, vec![U256::from(1)])]);
+    }
+
+    #[test]
+    fn eip4844() {
+        let blob_hash = B256::with_last_byte(0xbb);
+        let tx = TxEip4844 {
+            chain_id: 1,
+            nonce: 3,
+            gas_limit: 100_000,
+            max_fee_per_gas: 20,
+            max_priority_fee_per_gas: 2,
+            to: TO,
+            value: U256::ZERO,
+            access_list: AccessList::default(),
+            blob_versioned_hashes: vec![blob_hash],
+            max_fee_per_blob_gas: 7,
+            input: Bytes::new(),
+        };
+        let tx = TxEip4844Variant::TxEip4844(tx);
+        let env = round_trip(TxEnvelope::Eip4844(tx.into_signed(signature(1))));
+        assert_eq!(env.transact_to, TransactTo::call(TO));
+        assert_eq!(env.gas_priority_fee, Some(U256::from(2)));
+        assert_eq!(env.blob_hashes, [blob_hash]);
+        assert_eq!(env.max_fee_per_blob_gas, Some(U256::from(7)));
+    }
+
+    #[test]
+    fn eip7702_is_unsupported() {
+        // Type 0x04 followed by an empty list.
+        assert!(TxEnvelope::decode_2718(&mut [0x04, 0xc0].as_slice()).is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let tx = TxLegacy {
+            gas_limit: 21_000,
+            to: TxKind::Call(TO),
+            ..Default::default()
+        };
+        // A zero `r` recovers no signer.
+        let envelope = TxEnvelope::Legacy(tx.clone().into_signed(signature(0)));
+        assert_eq!(
+            TxEnv::try_from_envelope(&envelope),
+            Err(TxEnvelopeError::InvalidSignature)
+        );
+
+        let tx = TxLegacy {
+            gas_limit: u128::from(u64::MAX) + 1,
+            ..tx
+        };
+        let envelope = TxEnvelope::Legacy(tx.into_signed(signature(1)));
+        assert_eq!(
+            TxEnv::from_envelope_with_caller(&envelope, CALLER),
+            Err(TxEnvelopeError::Overflow("gas_limit"))
+        );
+
+        // Truncated encoding.
+        let encoded = envelope.encoded_2718();
+        let truncated = &encoded[..encoded.len() - 1];
+        assert!(TxEnvelope::decode_2718(&mut &truncated[..]).is_err());
+    }
+}
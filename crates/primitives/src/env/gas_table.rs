@@ -0,0 +1,41 @@
+/// Static gas cost of every opcode, used to reprice opcodes without changing the interpreter.
+///
+/// The cost of an opcode replaces the constant part it is charged for by its spec. Costs that
+/// depend on the operands or on the state, such as memory expansion or cold account access,
+/// are charged on top of it as usual.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GasTable {
+    costs: [u64; 256],
+}
+
+impl GasTable {
+    /// Creates a table from the cost of every opcode, indexed by opcode.
+    pub const fn new(costs: [u64; 256]) -> Self {
+        Self { costs }
+    }
+
+    /// Returns the cost of the opcode.
+    #[inline]
+    pub const fn cost(&self, opcode: u8) -> u64 {
+        self.costs[opcode as usize]
+    }
+
+    /// Returns the costs of all opcodes, indexed by opcode.
+    #[inline]
+    pub const fn costs(&self) -> &[u64; 256] {
+        &self.costs
+    }
+
+    /// Sets the cost of the opcode.
+    #[inline]
+    pub fn set_cost(&mut self, opcode: u8, cost: u64) {
+        self.costs[opcode as usize] = cost;
+    }
+
+    /// Returns the table with the cost of the opcode replaced.
+    #[inline]
+    pub fn with_cost(mut self, opcode: u8, cost: u64) -> Self {
+        self.set_cost(opcode, cost);
+        self
+    }
+}
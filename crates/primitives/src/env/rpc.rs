@@ -0,0 +1,465 @@
+//! Conversions from `alloy-rpc-types` blocks and transactions.
+use super::{BlobExcessGasAndPrice, BlockEnv, TransactTo, TxEnv};
+use crate::U256;
+use alloy_rpc_types::{Block, Header, Transaction};
+use core::fmt;
+
+/// Error returned when an RPC block or transaction can't be converted to an environment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RpcConversionError {
+    /// Field required by the environment is missing, for example the number of a pending block.
+    MissingField(&'static str),
+    /// Field value does not fit into the environment type.
+    Overflow(&'static str),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RpcConversionError {}
+
+impl fmt::Display for RpcConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "missing field `{field}`"),
+            Self::Overflow(field) => write!(f, "field `{field}` overflows"),
+        }
+    }
+}
+
+impl BlockEnv {
+    /// Creates the block environment from an RPC block.
+    ///
+    /// Fails if the block has no number (pending block) or if the excess blob gas overflows `u64`.
+    pub fn try_from_rpc_block(block: &Block) -> Result<Self, RpcConversionError> {
+        Self::try_from_rpc_header(&block.header)
+    }
+
+    /// Creates the block environment from an RPC block header.
+    ///
+    /// See [`BlockEnv::try_from_rpc_block`].
+    pub fn try_from_rpc_header(header: &Header) -> Result<Self, RpcConversionError> {
+        let number = header
+            .number
+            .ok_or(RpcConversionError::MissingField("number"))?;
+        let blob_excess_gas_and_price = header
+            .excess_blob_gas
+            .map(|excess| {
+                u64::try_from(excess).map_err(|_| RpcConversionError::Overflow("excess_blob_gas"))
+            })
+            .transpose()?
+            .map(BlobExcessGasAndPrice::new);
+
+        Ok(Self {
+            number: U256::from(number),
+            coinbase: header.miner,
+            timestamp: U256::from(header.timestamp),
+            gas_limit: U256::from(header.gas_limit),
+            basefee: header.base_fee_per_gas.map(U256::from).unwrap_or_default(),
+            difficulty: header.difficulty,
+            prevrandao: header.mix_hash,
+            blob_excess_gas_and_price,
+        })
+    }
+}
+
+impl TxEnv {
+    /// Creates the transaction environment from an RPC transaction.
+    ///
+    /// For fee market transactions `gas_price` is set to the max fee per gas, as the
+    /// `gasPrice` returned for mined transactions is the effective gas price.
+    pub fn try_from_rpc_transaction(tx: &Transaction) -> Result<Self, RpcConversionError> {
+        let gas_price = tx
+            .max_fee_per_gas
+            .or(tx.gas_price)
+            .map(U256::from)
+            .ok_or(RpcConversionError::MissingField("gas_price"))?;
+
+        Ok(Self {
+            caller: tx.from,
+            gas_limit: u64::try_from(tx.gas).map_err(|_| RpcConversionError::Overflow("gas"))?,
+            gas_price,
+            transact_to: match tx.to {
+                Some(to) => TransactTo::call(to),
+                None => TransactTo::create(),
+            },
+            value: tx.value,
+            data: tx.input.clone(),
+            nonce: Some(tx.nonce),
+            chain_id: tx.chain_id,
+            access_list: tx
+                .access_list
+                .as_ref()
+                .map(|list| {
+                    list.0
+                        .iter()
+                        .map(|item| {
+                            (
+                                item.address,
+                                item.storage_keys
+                                    .iter()
+                                    .map(|key| U256::from_be_bytes(key.0))
+                                    .collect(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            gas_priority_fee: tx.max_priority_fee_per_gas.map(U256::from),
+            blob_hashes: tx.blob_versioned_hashes.clone().unwrap_or_default(),
+            max_fee_per_blob_gas: tx.max_fee_per_blob_gas.map(U256::from),
+            envelope_stats: None,
+            is_system_tx: false,
+            coinbase: None,
+            #[cfg(feature = "optimism")]
+            optimism: Default::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{address, b256, bytes, Bytes};
+
+    fn block(json: &str) -> Block {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn transaction(json: &str) -> Transaction {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn cancun_block() {
+        let block = block(
+            r#"{
+                "hash": "0x9c1e7a0c4b1e0d9a3c2b2a1f0e9d8c7b6a5f4e3d2c1b0a998877665544332211",
+                "parentHash": "0x5f1d8c6e4a3b2c1d0e9f8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0a9b8c7d",
+                "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+                "miner": "0x95222290dd7278aa3ddd389cc1e1d165cc4bafe5",
+                "stateRoot": "0x0d1b3e5f7a9c2e4f6a8b0d2f4a6c8e0b2d4f6a8c0e2b4d6f8a0c2e4b6d8f0a2c",
+                "transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                "difficulty": "0x0",
+                "number": "0x12a05f2",
+                "gasLimit": "0x1c9c380",
+                "gasUsed": "0x0",
+                "timestamp": "0x65f1b057",
+                "extraData": "0x6265617665726275696c642e6f7267",
+                "mixHash": "0x7a3f1e0c2b4d6f8a9c1e3b5d7f9a2c4e6b8d0f1a3c5e7b9d2f4a6c8e0b1d3f5a",
+                "nonce": "0x0000000000000000",
+                "baseFeePerGas": "0x5d21dba00",
+                "withdrawalsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "blobGasUsed": "0x20000",
+                "excessBlobGas": "0x40000",
+                "parentBeaconBlockRoot": "0x3e1b5a7c9d2f4e6a8b0c1d3e5f7a9b2c4d6e8f0a1b3c5d7e9f2a4b6c8d0e1f3a",
+                "uncles": [],
+                "transactions": [],
+                "withdrawals": []
+            }"#,
+        );
+        let env = BlockEnv::try_from_rpc_block(&block).unwrap();
+        assert_eq!(env.number, U256::from(19_531_250));
+        assert_eq!(
+            env.coinbase,
+            address!("95222290dd7278aa3ddd389cc1e1d165cc4bafe5")
+        );
+        assert_eq!(env.timestamp, U256::from(0x65f1b057));
+        assert_eq!(env.gas_limit, U256::from(30_000_000));
+        assert_eq!(env.basefee, U256::from(25_000_000_000u64));
+        assert_eq!(env.difficulty, U256::ZERO);
+        assert_eq!(
+            env.prevrandao,
+            Some(b256!(
+                "7a3f1e0c2b4d6f8a9c1e3b5d7f9a2c4e6b8d0f1a3c5e7b9d2f4a6c8e0b1d3f5a"
+            ))
+        );
+        assert_eq!(
+            env.blob_excess_gas_and_price,
+            Some(BlobExcessGasAndPrice::new(0x40000))
+        );
+    }
+
+    #[test]
+    fn pre_london_header() {
+        // No base fee, blob gas or withdrawals fields.
+        let block = block(
+            r#"{
+                "hash": "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6",
+                "parentHash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+                "miner": "0x05a56e2d52c817161883f50c441c3228cfe54d9f",
+                "stateRoot": "0xd67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3",
+                "transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                "difficulty": "0x3ff800000",
+                "totalDifficulty": "0x7ff800000",
+                "number": "0x1",
+                "gasLimit": "0x1388",
+                "gasUsed": "0x0",
+                "timestamp": "0x55ba4224",
+                "extraData": "0x476574682f76312e302e302f6c696e75782f676f312e342e32",
+                "mixHash": "0x969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59",
+                "nonce": "0x539bd4979fef1ec4",
+                "size": "0x219",
+                "uncles": [],
+                "transactions": []
+            }"#,
+        );
+        let env = BlockEnv::try_from_rpc_block(&block).unwrap();
+        assert_eq!(env.number, U256::from(1));
+        assert_eq!(env.difficulty, U256::from(0x3ff800000u64));
+        assert_eq!(env.basefee, U256::ZERO);
+        assert_eq!(env.blob_excess_gas_and_price, None);
+    }
+
+    #[test]
+    fn pending_header() {
+        let block = block(
+            r#"{
+                "hash": null,
+                "parentHash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+                "miner": "0x0000000000000000000000000000000000000000",
+                "stateRoot": "0xd67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3",
+                "transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                "difficulty": "0x0",
+                "number": null,
+                "gasLimit": "0x1c9c380",
+                "gasUsed": "0x0",
+                "timestamp": "0x65f1b063",
+                "extraData": "0x",
+                "uncles": [],
+                "transactions": []
+            }"#,
+        );
+        assert_eq!(
+            BlockEnv::try_from_rpc_block(&block),
+            Err(RpcConversionError::MissingField("number"))
+        );
+    }
+
+    #[test]
+    fn legacy_transaction() {
+        // Pre EIP-155 transaction: no chain id and no type.
+        let tx = transaction(
+            r#"{
+                "hash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+                "nonce": "0x0",
+                "blockHash": "0x4e3a3754410177e6937ef1f84bba68ea139e8d1a2258c5f85db9f1cd715a1bdd",
+                "blockNumber": "0xb443",
+                "transactionIndex": "0x0",
+                "from": "0xa1e4380a3b1f749673e270229993ee55f35663b4",
+                "to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+                "value": "0x7a69",
+                "gasPrice": "0x2d79883d2000",
+                "gas": "0x5208",
+                "input": "0x",
+                "v": "0x1c",
+                "r": "0x88ff6cf0fefd94db46111149ae4bfc179e9b94721fffd821d38d16464b3f71d0",
+                "s": "0x45e0aff800961cfce805daef7016b9b675c137a6a41a548f7b60a3484c06a33a"
+            }"#,
+        );
+        let env = TxEnv::try_from_rpc_transaction(&tx).unwrap();
+        assert_eq!(
+            env.caller,
+            address!("a1e4380a3b1f749673e270229993ee55f35663b4")
+        );
+        assert_eq!(env.gas_limit, 21_000);
+        assert_eq!(env.gas_price, U256::from(50_000_000_000_000u64));
+        assert_eq!(env.gas_priority_fee, None);
+        assert_eq!(
+            env.transact_to,
+            TransactTo::call(address!("5df9b87991262f6ba471f09758cde1c0fc1de734"))
+        );
+        assert_eq!(env.value, U256::from(31337));
+        assert_eq!(env.data, Bytes::new());
+        assert_eq!(env.nonce, Some(0));
+        assert_eq!(env.chain_id, None);
+        assert!(env.access_list.is_empty());
+        assert!(env.blob_hashes.is_empty());
+        assert_eq!(env.max_fee_per_blob_gas, None);
+    }
+
+    #[test]
+    fn eip2930_transaction() {
+        let tx = transaction(
+            r#"{
+                "hash": "0x3f1e3c7b5a9d2e4f6a8b0c1d3e5f7a9b2c4d6e8f0a1b3c5d7e9f2a4b6c8d0e1f",
+                "type": "0x1",
+                "nonce": "0x2a",
+                "blockHash": "0x1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f809",
+                "blockNumber": "0xbe2fd8",
+                "transactionIndex": "0x4",
+                "from": "0x8ba1f109551bd432803012645ac136ddd64dba72",
+                "to": "0xdac17f958d2ee523a2206206994597c13d831ec7",
+                "value": "0x0",
+                "gasPrice": "0x12a05f200",
+                "gas": "0x186a0",
+                "input": "0xa9059cbb",
+                "chainId": "0x1",
+                "accessList": [
+                    {
+                        "address": "0xdac17f958d2ee523a2206206994597c13d831ec7",
+                        "storageKeys": [
+                            "0x0000000000000000000000000000000000000000000000000000000000000003"
+                        ]
+                    }
+                ],
+                "v": "0x0",
+                "yParity": "0x0",
+                "r": "0x7e2b4b1c6a9f0a3ad2c3b1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b4a3f2e1d0",
+                "s": "0x1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b2a3f4e5d6c7b8a9f0e1d2c"
+            }"#,
+        );
+        let env = TxEnv::try_from_rpc_transaction(&tx).unwrap();
+        assert_eq!(env.gas_price, U256::from(5_000_000_000u64));
+        assert_eq!(env.gas_priority_fee, None);
+        assert_eq!(env.data, bytes!("a9059cbb"));
+        assert_eq!(env.nonce, Some(42));
+        assert_eq!(env.chain_id, Some(1));
+        assert_eq!(
+            env.access_list,
+            [(
+                address!("dac17f958d2ee523a2206206994597c13d831ec7"),
+                vec![U256::from(3)]
+            )]
+        );
+    }
+
+    #[test]
+    fn eip1559_transaction() {
+        // Mined contract creation: `gasPrice` is the effective gas price and `to` is null.
+        let tx = transaction(
+            r#"{
+                "hash": "0x6a4f0e1c3b5d7f9a2c4e6b8d0f1a3c5e7b9d2f4a6c8e0b1d3f5a7c9e2b4d6f8a",
+                "type": "0x2",
+                "nonce": "0x7",
+                "blockHash": "0x2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a",
+                "blockNumber": "0x1234567",
+                "transactionIndex": "0x10",
+                "from": "0x8ba1f109551bd432803012645ac136ddd64dba72",
+                "to": null,
+                "value": "0x0",
+                "gasPrice": "0x6fc23ac00",
+                "maxFeePerGas": "0xba43b7400",
+                "maxPriorityFeePerGas": "0x3b9aca00",
+                "gas": "0x2dc6c0",
+                "input": "0x6080604052",
+                "chainId": "0x1",
+                "accessList": [],
+                "v": "0x1",
+                "yParity": "0x1",
+                "r": "0x4c5b6a7f8e9d0c1b2a3f4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b",
+                "s": "0x2e3d4c5b6a7f8e9d0c1b2a3f4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d"
+            }"#,
+        );
+        let env = TxEnv::try_from_rpc_transaction(&tx).unwrap();
+        assert_eq!(env.gas_limit, 3_000_000);
+        assert_eq!(env.gas_price, U256::from(50_000_000_000u64));
+        assert_eq!(env.gas_priority_fee, Some(U256::from(1_000_000_000)));
+        assert_eq!(env.transact_to, TransactTo::create());
+        assert_eq!(env.data, bytes!("6080604052"));
+        assert!(env.access_list.is_empty());
+    }
+
+    #[test]
+    fn eip4844_transaction() {
+        let tx = transaction(
+            r#"{
+                "hash": "0x7b5d3f1a9c2e4b6d8f0a1c3e5b7d9f2a4c6e8b0d1f3a5c7e9b2d4f6a8c0e1b3d",
+                "type": "0x3",
+                "nonce": "0x1b4",
+                "blockHash": "0x3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b",
+                "blockNumber": "0x12a05f2",
+                "transactionIndex": "0x1",
+                "from": "0xc1b634853cb333d3ad8663715b08f41a3aec47cc",
+                "to": "0xff00000000000000000000000000000000000010",
+                "value": "0x0",
+                "gasPrice": "0x5d21dba00",
+                "maxFeePerGas": "0x746a528800",
+                "maxPriorityFeePerGas": "0x3b9aca00",
+                "maxFeePerBlobGas": "0x2540be400",
+                "gas": "0x5208",
+                "input": "0x",
+                "chainId": "0x1",
+                "accessList": [],
+                "blobVersionedHashes": [
+                    "0x01a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+                    "0x01b6c1ef6c5e8ab2d5a4f9b8e2a7d0c3f1e4b7a9d2c5f8e1b4a7d0c3f6e9b2a5"
+                ],
+                "v": "0x0",
+                "yParity": "0x0",
+                "r": "0x5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c",
+                "s": "0x3f4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b2a3f4e"
+            }"#,
+        );
+        let env = TxEnv::try_from_rpc_transaction(&tx).unwrap();
+        assert_eq!(env.gas_price, U256::from(500_000_000_000u64));
+        assert_eq!(env.gas_priority_fee, Some(U256::from(1_000_000_000)));
+        assert_eq!(
+            env.blob_hashes,
+            [
+                b256!("01a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8"),
+                b256!("01b6c1ef6c5e8ab2d5a4f9b8e2a7d0c3f1e4b7a9d2c5f8e1b4a7d0c3f6e9b2a5"),
+            ]
+        );
+        assert_eq!(
+            env.max_fee_per_blob_gas,
+            Some(U256::from(10_000_000_000u64))
+        );
+    }
+
+    #[test]
+    fn missing_gas_price() {
+        // Pending transaction without any gas price field.
+        let tx = transaction(
+            r#"{
+                "hash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+                "nonce": "0x0",
+                "blockHash": null,
+                "blockNumber": null,
+                "transactionIndex": null,
+                "from": "0xa1e4380a3b1f749673e270229993ee55f35663b4",
+                "to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+                "value": "0x0",
+                "gas": "0x5208",
+                "input": "0x"
+            }"#,
+        );
+        assert_eq!(
+            TxEnv::try_from_rpc_transaction(&tx),
+            Err(RpcConversionError::MissingField("gas_price"))
+        );
+    }
+
+    #[test]
+    fn bad_hex() {
+        let json = r#"{
+            "hash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+            "nonce": "0x0",
+            "from": "0xa1e4380a3b1f749673e270229993ee55f35663bz",
+            "to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+            "value": "0x0",
+            "gasPrice": "0x1",
+            "gas": "0x5208",
+            "input": "0x"
+        }"#;
+        assert!(serde_json::from_str::<Transaction>(json).is_err());
+
+        let json = json
+            .replace("63bz", "63b4")
+            .replace(r#""input": "0x""#, r#""input": "0x0g""#);
+        assert!(serde_json::from_str::<Transaction>(&json).is_err());
+
+        // Odd number of digits.
+        let json = json.replace("0x0g", "0x123");
+        assert!(serde_json::from_str::<Transaction>(&json).is_err());
+
+        let json = json.replace("0x123", "0x1234");
+        assert!(serde_json::from_str::<Transaction>(&json).is_ok());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::SpecId;
+use std::vec::Vec;
+
+/// Condition under which a hardfork becomes active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForkCondition {
+    /// Active from the given block number onwards.
+    Block(u64),
+    /// Active from the given block timestamp onwards.
+    Timestamp(u64),
+}
+
+impl ForkCondition {
+    /// Returns `true` if the fork is active at the given block number and timestamp.
+    #[inline]
+    pub const fn is_active_at(&self, number: u64, timestamp: u64) -> bool {
+        match *self {
+            Self::Block(block) => number >= block,
+            Self::Timestamp(time) => timestamp >= time,
+        }
+    }
+}
+
+/// Activation points of hardforks of a chain.
+///
+/// Forks are looked up by [SpecId], the newest active fork determines the spec of a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HardforkSchedule {
+    /// Forks sorted by [SpecId].
+    forks: Vec<(SpecId, ForkCondition)>,
+}
+
+impl HardforkSchedule {
+    /// Creates an empty schedule. Blocks of an empty schedule are [SpecId::FRONTIER] blocks.
+    pub const fn new() -> Self {
+        Self { forks: Vec::new() }
+    }
+
+    /// Returns the schedule with the given fork added.
+    pub fn with_fork(mut self, spec_id: SpecId, condition: ForkCondition) -> Self {
+        self.insert(spec_id, condition);
+        self
+    }
+
+    /// Sets the activation condition of the fork, replacing the previous one if present.
+    pub fn insert(&mut self, spec_id: SpecId, condition: ForkCondition) {
+        match self.forks.binary_search_by_key(&spec_id, |(id, _)| *id) {
+            Ok(index) => self.forks[index].1 = condition,
+            Err(index) => self.forks.insert(index, (spec_id, condition)),
+        }
+    }
+
+    /// Returns the activation condition of the fork, if it is scheduled.
+    pub fn fork(&self, spec_id: SpecId) -> Option<ForkCondition> {
+        self.forks
+            .binary_search_by_key(&spec_id, |(id, _)| *id)
+            .ok()
+            .map(|index| self.forks[index].1)
+    }
+
+    /// Returns all scheduled forks, sorted by [SpecId].
+    pub fn forks(&self) -> &[(SpecId, ForkCondition)] {
+        &self.forks
+    }
+
+    /// Returns the [SpecId] of the block with the given number and timestamp.
+    pub fn spec_id_at(&self, number: u64, timestamp: u64) -> SpecId {
+        self.forks
+            .iter()
+            .rev()
+            .find(|(_, condition)| condition.is_active_at(number, timestamp))
+            .map(|(spec_id, _)| *spec_id)
+            .unwrap_or(SpecId::FRONTIER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_id_at() {
+        let schedule = HardforkSchedule::new()
+            .with_fork(SpecId::LONDON, ForkCondition::Block(10))
+            .with_fork(SpecId::BERLIN, ForkCondition::Block(0))
+            .with_fork(SpecId::SHANGHAI, ForkCondition::Timestamp(1000));
+
+        assert_eq!(schedule.spec_id_at(0, 0), SpecId::BERLIN);
+        assert_eq!(schedule.spec_id_at(10, 999), SpecId::LONDON);
+        assert_eq!(schedule.spec_id_at(11, 1000), SpecId::SHANGHAI);
+        assert_eq!(
+            HardforkSchedule::new().spec_id_at(100, 100),
+            SpecId::FRONTIER
+        );
+        assert_eq!(
+            schedule.fork(SpecId::LONDON),
+            Some(ForkCondition::Block(10))
+        );
+    }
+}
@@ -10,15 +10,19 @@
 extern crate alloc as std;
 
 mod bytecode;
+pub mod chain;
 mod constants;
 pub mod db;
 pub mod env;
+pub mod hardfork;
 #[cfg(feature = "c-kzg")]
 pub mod kzg;
 pub mod precompile;
 pub mod result;
 pub mod specification;
 pub mod state;
+#[cfg(feature = "arbitrary")]
+pub mod strategies;
 pub mod utilities;
 pub use alloy_primitives::{
     self, address, b256, bytes, fixed_bytes, hex, hex_literal, ruint, uint, Address, Bytes,
@@ -26,8 +30,10 @@ pub use alloy_primitives::{
 };
 pub use bitvec;
 pub use bytecode::*;
+pub use chain::*;
 pub use constants::*;
 pub use env::*;
+pub use hardfork::*;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
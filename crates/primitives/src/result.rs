@@ -1,4 +1,4 @@
-use crate::{Address, Bytes, Log, State, U256};
+use crate::{Address, Bytes, HashMap, Log, State, B256, U256};
 use core::fmt;
 use std::{boxed::Box, string::String, vec::Vec};
 
@@ -15,6 +15,240 @@ pub struct ResultAndState {
     pub result: ExecutionResult,
     /// State that got updated
     pub state: State,
+    /// Data collected alongside the execution.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: ExecutionMetadata,
+}
+
+/// Data collected alongside the execution of a transaction.
+///
+/// Most of it is opt-in and only set by the handle registers or features that collect it. New
+/// fields can be added in minor releases, start from [ExecutionMetadata::default] to build one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[non_exhaustive]
+pub struct ExecutionMetadata {
+    /// Resource usage of the execution, if it was collected.
+    pub resources: Option<ResourceReport>,
+    /// Gas used by the execution against the calldata floor, since Prague.
+    pub calldata_floor: Option<CalldataFloor>,
+    /// Balance change of the beneficiary, if it was tracked.
+    pub coinbase_payment: Option<CoinbasePayment>,
+    /// Instruction counters of the execution, collected with the `instrumentation` feature.
+    pub counters: Option<ExecutionCounters>,
+    /// Statistics of the code loads of the execution, collected with the `instrumentation`
+    /// feature.
+    pub code_cache_stats: Option<CodeCacheStats>,
+    /// Fees paid by the transaction.
+    pub fees: TransactionFees,
+    /// Keccak preimages of the addresses and storage keys loaded by the transaction, if they
+    /// were recorded.
+    pub preimages: Option<HashMap<B256, Bytes>>,
+    /// Snapshots of the frames that reverted or halted, innermost first, if they were collected
+    /// and the transaction failed.
+    pub failure_snapshots: Option<Vec<FailureSnapshot>>,
+    /// Precompile calls of the transaction, in execution order, if they were recorded.
+    pub precompile_calls: Option<Vec<PrecompileCall>>,
+}
+
+/// Counters of the instructions executed by a transaction, summed over all of its frames.
+///
+/// Calls and creates are counted when their instruction is executed, including the ones that
+/// fail before entering the new frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionCounters {
+    /// Number of executed instructions.
+    pub instructions: u64,
+    /// Number of instructions that expanded the memory.
+    pub memory_expansions: u64,
+    /// Number of `SLOAD` instructions.
+    pub sloads: u64,
+    /// Number of `SSTORE` instructions.
+    pub sstores: u64,
+    /// Number of `CALL` and `EXTCALL` instructions.
+    pub calls: u64,
+    /// Number of `CALLCODE` instructions.
+    pub call_codes: u64,
+    /// Number of `DELEGATECALL` and `EXTDELEGATECALL` instructions.
+    pub delegate_calls: u64,
+    /// Number of `STATICCALL` and `EXTSTATICCALL` instructions.
+    pub static_calls: u64,
+    /// Number of `CREATE`, `CREATE2` and `EOFCREATE` instructions.
+    pub creates: u64,
+}
+
+impl ExecutionCounters {
+    /// Adds the counters of `other` to these counters.
+    pub fn merge(&mut self, other: &Self) {
+        self.instructions += other.instructions;
+        self.memory_expansions += other.memory_expansions;
+        self.sloads += other.sloads;
+        self.sstores += other.sstores;
+        self.calls += other.calls;
+        self.call_codes += other.call_codes;
+        self.delegate_calls += other.delegate_calls;
+        self.static_calls += other.static_calls;
+        self.creates += other.creates;
+    }
+}
+
+/// Statistics of code loads done by `EXTCODESIZE`, `EXTCODEHASH`, `EXTCODECOPY` and calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeCacheStats {
+    /// Loads served without a database access.
+    pub hits: u64,
+    /// Loads that read the code from the database.
+    pub misses: u64,
+}
+
+/// Balance change of the beneficiary over a transaction.
+///
+/// Separates the fees from the value paid to the beneficiary by the execution itself, which
+/// MEV bundle rules use to detect coinbase payments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoinbasePayment {
+    /// Beneficiary of the transaction.
+    pub beneficiary: Address,
+    /// Balance of the beneficiary before the transaction.
+    pub balance_before: U256,
+    /// Balance of the beneficiary after the transaction.
+    pub balance_after: U256,
+    /// Fees credited to the beneficiary after execution.
+    pub fees: U256,
+}
+
+impl CoinbasePayment {
+    /// Returns the balance gained by the beneficiary, or zero if it lost balance.
+    pub fn delta(&self) -> U256 {
+        self.balance_after.saturating_sub(self.balance_before)
+    }
+
+    /// Returns the value paid to the beneficiary by the execution, on top of the fees.
+    pub fn direct_payment(&self) -> U256 {
+        self.delta().saturating_sub(self.fees)
+    }
+}
+
+/// Gas used by the execution of a transaction and its calldata floor, see EIP-7623.
+///
+/// The transaction is charged the larger of the two.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalldataFloor {
+    /// Gas used by the execution, after refunds.
+    pub execution_gas_used: u64,
+    /// Minimum gas charged for the calldata of the transaction.
+    pub floor_gas: u64,
+}
+
+impl CalldataFloor {
+    /// Returns `true` if the transaction is charged the floor instead of its execution gas.
+    pub fn is_applied(&self) -> bool {
+        self.floor_gas > self.execution_gas_used
+    }
+
+    /// Returns the gas charged to the transaction.
+    pub fn gas_used(&self) -> u64 {
+        self.floor_gas.max(self.execution_gas_used)
+    }
+}
+
+/// Fees paid by a transaction, see [Env::transaction_fees](crate::Env::transaction_fees).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransactionFees {
+    /// Price paid for every unit of gas.
+    pub effective_gas_price: U256,
+    /// Priority fee paid to the beneficiary.
+    pub priority_fee: U256,
+    /// Base fee burnt, since London.
+    pub burnt_base_fee: U256,
+    /// Blob fee burnt, since Cancun.
+    pub blob_fee: U256,
+}
+
+impl TransactionFees {
+    /// Returns the total fee paid by the sender.
+    pub fn total(&self) -> U256 {
+        self.priority_fee
+            .saturating_add(self.burnt_base_fee)
+            .saturating_add(self.blob_fee)
+    }
+}
+
+/// Peak resource usage of a transaction execution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceReport {
+    /// Peak size of the memory of all active frames, in bytes.
+    pub max_memory: usize,
+    /// Maximum number of nested frames that executed code.
+    pub max_call_depth: usize,
+    /// Maximum number of items on the stack of a single frame.
+    pub max_stack_depth: usize,
+}
+
+/// Input and output of a precompile call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrecompileCall {
+    /// Address of the precompile.
+    pub address: Address,
+    /// Call depth of the precompile frame, `1` if the transaction calls the precompile.
+    pub depth: usize,
+    /// Input bytes.
+    pub input: Bytes,
+    /// Gas limit of the call.
+    pub gas_limit: u64,
+    /// Gas used by the call, the gas limit if it failed.
+    pub gas_used: u64,
+    /// Output bytes, empty if the call failed.
+    pub output: Bytes,
+    /// Whether the precompile succeeded within the gas limit.
+    pub success: bool,
+}
+
+/// State of a frame at the instruction that made it revert or halt.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailureSnapshot {
+    /// Address of the account whose code was executed.
+    pub address: Address,
+    /// Call depth of the frame, `1` for the frame of the transaction.
+    pub depth: usize,
+    /// Program counter of the failing instruction.
+    pub pc: usize,
+    /// Opcode of the failing instruction.
+    pub opcode: u8,
+    /// Reason of the halt, or `None` if the frame reverted.
+    pub halt_reason: Option<HaltReason>,
+    /// Topmost items of the stack, top first.
+    ///
+    /// For `REVERT` these are taken before the instruction, so they include its operands.
+    pub stack: Vec<U256>,
+    /// Offset of [memory](Self::memory) in the memory of the frame.
+    pub memory_offset: usize,
+    /// Last bytes of the memory of the frame.
+    pub memory: Bytes,
+    /// Last instructions executed by the frame, oldest first and ending with the failing one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recent_opcodes: Vec<ExecutedOpcode>,
+}
+
+/// Instruction executed by a frame, as recorded in [FailureSnapshot::recent_opcodes].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutedOpcode {
+    /// Program counter of the instruction.
+    pub pc: usize,
+    /// Opcode of the instruction.
+    pub opcode: u8,
+    /// Gas remaining in the frame before the instruction.
+    pub gas_remaining: u64,
 }
 
 /// Result of a transaction execution.
@@ -98,6 +332,15 @@ impl ExecutionResult {
             | Self::Halt { gas_used, .. } => gas_used,
         }
     }
+
+    /// Returns the chain defined halt reason if the execution halted with one, see
+    /// [CustomHaltReason].
+    pub fn custom_halt_reason<R: CustomHaltReason>(&self) -> Option<R> {
+        match self {
+            Self::Halt { reason, .. } => reason.to_custom(),
+            _ => None,
+        }
+    }
 }
 
 /// Output of a transaction execution.
@@ -142,8 +385,8 @@ pub enum EVMError<DBError> {
     Transaction(InvalidTransaction),
     /// Header validation error.
     Header(InvalidHeader),
-    /// Database error.
-    Database(DBError),
+    /// Database error, with the read and the point of execution it failed at.
+    Database(DatabaseError<DBError>),
     /// Custom error.
     ///
     /// Useful for handler registers where custom logic would want to return their own custom error.
@@ -173,6 +416,165 @@ impl<DBError: fmt::Display> fmt::Display for EVMError<DBError> {
     }
 }
 
+impl<DBError> EVMError<DBError> {
+    /// Creates a database error without context, in place of the former
+    /// `map_err(EVMError::Database)`.
+    pub fn database(error: DBError) -> Self {
+        Self::Database(DatabaseError::new(error))
+    }
+
+    /// Creates a database error of the given read.
+    pub fn database_at(error: DBError, access: DatabaseAccess) -> Self {
+        Self::Database(DatabaseError::at(error, access))
+    }
+
+    /// Records the stage of a database error, unless it is already known.
+    pub fn with_stage(self, stage: ExecutionStage) -> Self {
+        match self {
+            Self::Database(e) => Self::Database(e.with_stage(stage)),
+            e => e,
+        }
+    }
+
+    /// Records the opcode that caused a database error, unless it is already known.
+    pub fn with_opcode(self, opcode: u8) -> Self {
+        match self {
+            Self::Database(e) => Self::Database(e.with_opcode(opcode)),
+            e => e,
+        }
+    }
+}
+
+/// Database read that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DatabaseAccess {
+    /// Basic info of an account.
+    Account(Address),
+    /// Storage slot of an account.
+    Storage(Address, U256),
+    /// Code by its hash.
+    Code(B256),
+    /// Hash of a block by its number.
+    BlockHash(U256),
+}
+
+impl fmt::Display for DatabaseAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Account(address) => write!(f, "account {address}"),
+            Self::Storage(address, index) => write!(f, "storage slot {index} of {address}"),
+            Self::Code(hash) => write!(f, "code {hash}"),
+            Self::BlockHash(number) => write!(f, "hash of block {number}"),
+        }
+    }
+}
+
+/// Stage of the transaction execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExecutionStage {
+    /// Validation of the transaction against the state.
+    Validation,
+    /// Loading of accounts and deduction of the caller balance.
+    PreExecution,
+    /// Execution of the call frames.
+    Execution,
+    /// Reimbursement, rewards and output.
+    PostExecution,
+}
+
+impl fmt::Display for ExecutionStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Validation => "validation",
+            Self::PreExecution => "pre-execution",
+            Self::Execution => "execution",
+            Self::PostExecution => "post-execution",
+        })
+    }
+}
+
+/// Error of the database together with the context it occurred in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DatabaseError<E> {
+    /// Error returned by the database.
+    pub error: E,
+    /// The read that failed, if known.
+    pub access: Option<DatabaseAccess>,
+    /// Stage of the execution, if known.
+    pub stage: Option<ExecutionStage>,
+    /// Opcode that caused the read, if the read was made by an instruction.
+    pub opcode: Option<u8>,
+}
+
+impl<E> DatabaseError<E> {
+    /// Creates an error without context.
+    pub fn new(error: E) -> Self {
+        Self {
+            error,
+            access: None,
+            stage: None,
+            opcode: None,
+        }
+    }
+
+    /// Creates an error of the given read.
+    pub fn at(error: E, access: DatabaseAccess) -> Self {
+        Self {
+            access: Some(access),
+            ..Self::new(error)
+        }
+    }
+
+    /// Records the stage, unless it is already known.
+    pub fn with_stage(mut self, stage: ExecutionStage) -> Self {
+        self.stage.get_or_insert(stage);
+        self
+    }
+
+    /// Records the opcode, unless it is already known.
+    pub fn with_opcode(mut self, opcode: u8) -> Self {
+        self.opcode.get_or_insert(opcode);
+        self
+    }
+
+    /// Returns the error of the database.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E> From<E> for DatabaseError<E> {
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for DatabaseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(access) = &self.access {
+            write!(f, " reading {access}")?;
+        }
+        if let Some(opcode) = self.opcode {
+            write!(f, " in opcode {opcode:#04x}")?;
+        }
+        if let Some(stage) = self.stage {
+            write!(f, " during {stage}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for DatabaseError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 impl<DBError> From<InvalidTransaction> for EVMError<DBError> {
     fn from(value: InvalidTransaction) -> Self {
         Self::Transaction(value)
@@ -205,6 +607,8 @@ pub enum InvalidTransaction {
     /// - initial stipend gas
     /// - gas for access list and input data
     CallGasCostMoreThanGasLimit,
+    /// EIP-7623: the calldata floor of the transaction exceeds its gas limit.
+    GasFloorMoreThanGasLimit,
     /// EIP-3607 Reject transactions from senders with deployed code
     RejectCallerWithCode,
     /// Transaction account does not have enough amount of ether to cover transferred value and gas_limit*gas_price.
@@ -226,6 +630,12 @@ pub enum InvalidTransaction {
     },
     /// EIP-3860: Limit and meter initcode
     CreateInitCodeSizeLimit,
+    /// Calldata exceeds the limit of the config.
+    CalldataSizeLimit,
+    /// Encoded transaction exceeds the size limit of the config.
+    TxSizeLimit,
+    /// EIP-7934: block including the transaction exceeds the RLP encoded size limit.
+    BlockRlpSizeLimit,
     /// Transaction chain id does not match the config chain id.
     InvalidChainId,
     /// Access list is not supported for blocks before the Berlin hardfork.
@@ -297,6 +707,9 @@ impl fmt::Display for InvalidTransaction {
             Self::CallGasCostMoreThanGasLimit => {
                 write!(f, "call gas cost exceeds the gas limit")
             }
+            Self::GasFloorMoreThanGasLimit => {
+                write!(f, "calldata floor gas exceeds the gas limit")
+            }
             Self::RejectCallerWithCode => {
                 write!(f, "reject transactions from senders with deployed code")
             }
@@ -318,6 +731,9 @@ impl fmt::Display for InvalidTransaction {
             Self::CreateInitCodeSizeLimit => {
                 write!(f, "create initcode size limit")
             }
+            Self::CalldataSizeLimit => write!(f, "calldata size limit"),
+            Self::TxSizeLimit => write!(f, "transaction size limit"),
+            Self::BlockRlpSizeLimit => write!(f, "block RLP size limit"),
             Self::InvalidChainId => write!(f, "invalid chain ID"),
             Self::AccessListNotSupported => write!(f, "access list not supported"),
             Self::MaxFeePerBlobGasNotSupported => {
@@ -412,11 +828,66 @@ pub enum HaltReason {
     OutOfFunds,
     CallTooDeep,
 
+    /// Execution was cancelled by the host before it finished.
+    Cancelled,
+    /// Returned data exceeds the [ReturnDataLimit](crate::ReturnDataLimit) of the config.
+    ReturnDataLimit,
+    /// The limit of executed instructions was reached.
+    StepLimit,
+
+    /// Code format or EOF version is not supported.
+    UnsupportedCodeVersion,
+    /// Opcode that is only valid in legacy code was found in EOF code.
+    LegacyOpcodeInEof,
+    /// EOF `CALLF` exceeded the return stack limit.
+    EOFFunctionStackOverflow,
+    /// Target of an EOF call is not a valid address.
+    InvalidEXTCALLTarget,
+    /// Init code container is not valid EOF.
+    InvalidEOFInitCode,
+    /// Legacy create called with EOF init code.
+    CreateInitCodeStartingEF00,
+    /// Deployed EOF data section is shorter than declared.
+    EofAuxDataTooSmall,
+    /// Deployed EOF data section is longer than the maximum size.
+    EofAuxDataOverflow,
+    /// Halt reason defined by the chain, for example by a custom instruction of a chain
+    /// extension, see [CustomHaltReason].
+    Custom(u8),
+
     /* Optimism errors */
     #[cfg(feature = "optimism")]
     FailedDeposit,
 }
 
+/// Halt reason defined by a chain, stored in [HaltReason::Custom] as a code.
+///
+/// Chains with failure modes of their own, such as an L2 rejecting a transaction that cannot
+/// pay its L1 data fee, define them as an enum implementing this trait instead of reporting an
+/// unrelated reason like [HaltReason::OutOfGas].
+pub trait CustomHaltReason: Sized {
+    /// Returns the code of the reason.
+    fn code(&self) -> u8;
+
+    /// Returns the reason with the given code, or `None` if no reason has it.
+    fn from_code(code: u8) -> Option<Self>;
+}
+
+impl HaltReason {
+    /// Creates a [HaltReason::Custom] holding the code of `reason`.
+    pub fn custom(reason: impl CustomHaltReason) -> Self {
+        Self::Custom(reason.code())
+    }
+
+    /// Returns the chain defined reason if this is a [HaltReason::Custom] with one of its codes.
+    pub fn to_custom<R: CustomHaltReason>(&self) -> Option<R> {
+        match *self {
+            Self::Custom(code) => R::from_code(code),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutOfGasError {
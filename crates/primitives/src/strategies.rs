@@ -0,0 +1,270 @@
+//! Generators of valid core types for fuzzing and property tests.
+//!
+//! [arbitrary::Arbitrary] and [proptest::arbitrary::Arbitrary] are implemented for [TxEnv],
+//! [BlockEnv], [Bytecode] and [AccountInfo]. Generated values are valid inputs rather than
+//! uniformly random ones: gas limits are within the block gas limit, fees are consistent and
+//! blob hashes carry the KZG version byte, so that most generated transactions reach execution.
+use crate::{
+    AccountInfo, Address, BlobExcessGasAndPrice, BlockEnv, Bytecode, Bytes, TransactTo, TxEnv,
+    B256, U256, VERSIONED_HASH_VERSION_KZG,
+};
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::{collection::vec, option, prelude::*};
+use std::vec::Vec;
+
+/// Maximum block and transaction gas limit of generated values.
+pub const MAX_GAS_LIMIT: u64 = 30_000_000;
+
+/// Maximum length of generated bytecode and calldata.
+pub const MAX_CODE_LEN: usize = 1024;
+
+fn versioned_hash(mut hash: B256) -> B256 {
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+impl<'a> Arbitrary<'a> for Bytecode {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=MAX_CODE_LEN)?;
+        Ok(Self::new_raw(Bytes::copy_from_slice(u.bytes(len)?)))
+    }
+}
+
+impl<'a> Arbitrary<'a> for AccountInfo {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let code = Bytecode::arbitrary(u)?;
+        Ok(Self::new(
+            U256::from(u128::arbitrary(u)?),
+            u.int_in_range(0..=u64::MAX - 1)?,
+            code.hash_slow(),
+            code,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for BlockEnv {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            number: U256::from(u64::arbitrary(u)?),
+            coinbase: Address::arbitrary(u)?,
+            timestamp: U256::from(u64::arbitrary(u)?),
+            gas_limit: U256::from(MAX_GAS_LIMIT),
+            basefee: U256::from(u32::arbitrary(u)?),
+            difficulty: U256::ZERO,
+            prevrandao: Some(B256::arbitrary(u)?),
+            blob_excess_gas_and_price: Some(BlobExcessGasAndPrice::new(
+                u.int_in_range(0..=1 << 24)?,
+            )),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for TxEnv {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let transact_to = if u.arbitrary()? {
+            TransactTo::Call(Address::arbitrary(u)?)
+        } else {
+            TransactTo::create()
+        };
+        let gas_price = U256::from(u64::arbitrary(u)?);
+        let gas_priority_fee = if u.arbitrary()? {
+            Some(U256::from(u.int_in_range(0..=gas_price.to::<u64>())?))
+        } else {
+            None
+        };
+        let (blob_hashes, max_fee_per_blob_gas) = if transact_to.is_call() && u.arbitrary()? {
+            let count = u.int_in_range(1..=6)?;
+            let hashes = (0..count)
+                .map(|_| B256::arbitrary(u).map(versioned_hash))
+                .collect::<arbitrary::Result<Vec<_>>>()?;
+            (hashes, Some(U256::from(u64::arbitrary(u)?)))
+        } else {
+            (Vec::new(), None)
+        };
+        let data_len = u.int_in_range(0..=MAX_CODE_LEN)?;
+        Ok(Self {
+            caller: Address::arbitrary(u)?,
+            gas_limit: u.int_in_range(21_000..=MAX_GAS_LIMIT)?,
+            gas_price,
+            transact_to,
+            value: U256::from(u128::arbitrary(u)?),
+            data: Bytes::copy_from_slice(u.bytes(data_len)?),
+            nonce: u.arbitrary()?,
+            chain_id: u.arbitrary()?,
+            access_list: u
+                .arbitrary_iter::<(Address, Vec<[u8; 32]>)>()?
+                .take(8)
+                .map(|item| {
+                    item.map(|(address, slots)| {
+                        (
+                            address,
+                            slots.into_iter().map(U256::from_be_bytes).collect(),
+                        )
+                    })
+                })
+                .collect::<arbitrary::Result<_>>()?,
+            gas_priority_fee,
+            blob_hashes,
+            max_fee_per_blob_gas,
+            ..Default::default()
+        })
+    }
+}
+
+/// Returns a strategy for addresses.
+pub fn address() -> impl Strategy<Value = Address> {
+    any::<[u8; 20]>().prop_map(Address::from)
+}
+
+/// Returns a strategy for 256-bit words.
+pub fn b256() -> impl Strategy<Value = B256> {
+    any::<[u8; 32]>().prop_map(B256::from)
+}
+
+/// Returns a strategy for byte strings of up to [MAX_CODE_LEN] bytes.
+pub fn bytes() -> impl Strategy<Value = Bytes> {
+    vec(any::<u8>(), 0..=MAX_CODE_LEN).prop_map(Bytes::from)
+}
+
+impl proptest::arbitrary::Arbitrary for Bytecode {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        bytes().prop_map(Self::new_raw).boxed()
+    }
+}
+
+impl proptest::arbitrary::Arbitrary for AccountInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<u128>(), 0..u64::MAX, any::<Bytecode>())
+            .prop_map(|(balance, nonce, code)| {
+                Self::new(U256::from(balance), nonce, code.hash_slow(), code)
+            })
+            .boxed()
+    }
+}
+
+impl proptest::arbitrary::Arbitrary for BlockEnv {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<u64>(),
+            address(),
+            any::<u64>(),
+            any::<u32>(),
+            b256(),
+            0..=1u64 << 24,
+        )
+            .prop_map(
+                |(number, coinbase, timestamp, basefee, prevrandao, excess_blob_gas)| Self {
+                    number: U256::from(number),
+                    coinbase,
+                    timestamp: U256::from(timestamp),
+                    gas_limit: U256::from(MAX_GAS_LIMIT),
+                    basefee: U256::from(basefee),
+                    difficulty: U256::ZERO,
+                    prevrandao: Some(prevrandao),
+                    blob_excess_gas_and_price: Some(BlobExcessGasAndPrice::new(excess_blob_gas)),
+                },
+            )
+            .boxed()
+    }
+}
+
+impl proptest::arbitrary::Arbitrary for TxEnv {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let transact_to = option::of(address()).prop_map(|to| match to {
+            Some(address) => TransactTo::Call(address),
+            None => TransactTo::create(),
+        });
+        let fees =
+            any::<u64>().prop_flat_map(|gas_price| (Just(gas_price), option::of(0..=gas_price)));
+        let blobs = (vec(b256(), 0..=6), any::<u64>());
+        let access_list = vec((address(), vec(any::<[u8; 32]>(), 0..4)), 0..4);
+        (
+            (address(), 21_000..=MAX_GAS_LIMIT, fees, transact_to),
+            (
+                any::<u128>(),
+                bytes(),
+                any::<Option<u64>>(),
+                any::<Option<u64>>(),
+            ),
+            (access_list, blobs),
+        )
+            .prop_map(
+                |(
+                    (caller, gas_limit, (gas_price, gas_priority_fee), transact_to),
+                    (value, data, nonce, chain_id),
+                    (access_list, (blob_hashes, max_fee_per_blob_gas)),
+                )| {
+                    // Blob transactions can not create contracts.
+                    let blob_hashes: Vec<_> = if transact_to.is_call() {
+                        blob_hashes.into_iter().map(versioned_hash).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    Self {
+                        caller,
+                        gas_limit,
+                        gas_price: U256::from(gas_price),
+                        transact_to,
+                        value: U256::from(value),
+                        data,
+                        nonce,
+                        chain_id,
+                        access_list: access_list
+                            .into_iter()
+                            .map(|(address, slots)| {
+                                (
+                                    address,
+                                    slots.into_iter().map(U256::from_be_bytes).collect(),
+                                )
+                            })
+                            .collect(),
+                        gas_priority_fee: gas_priority_fee.map(U256::from),
+                        max_fee_per_blob_gas: (!blob_hashes.is_empty())
+                            .then(|| U256::from(max_fee_per_blob_gas)),
+                        blob_hashes,
+                        ..Default::default()
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn generated_tx_is_consistent(tx in any::<TxEnv>()) {
+            prop_assert!(tx.gas_limit >= 21_000 && tx.gas_limit <= MAX_GAS_LIMIT);
+            prop_assert!(tx.gas_priority_fee.map_or(true, |fee| fee <= tx.gas_price));
+            prop_assert!(tx.blob_hashes.iter().all(|hash| hash[0] == VERSIONED_HASH_VERSION_KZG));
+            prop_assert_eq!(tx.blob_hashes.is_empty(), tx.max_fee_per_blob_gas.is_none());
+        }
+
+        #[test]
+        fn generated_account_hash_matches_code(info in any::<AccountInfo>()) {
+            prop_assert_eq!(info.code_hash, info.code.unwrap().hash_slow());
+        }
+    }
+
+    #[test]
+    fn arbitrary_tx_from_bytes() {
+        let data = [0xab; 512];
+        let tx = TxEnv::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(tx.gas_limit >= 21_000 && tx.gas_limit <= MAX_GAS_LIMIT);
+    }
+}
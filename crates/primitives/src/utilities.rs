@@ -1,5 +1,8 @@
 use crate::{
-    b256, B256, BLOB_GASPRICE_UPDATE_FRACTION, MIN_BLOB_GASPRICE, TARGET_BLOB_GAS_PER_BLOCK,
+    b256, Address, BlobExcessGasAndPrice, B256, BASE_FEE_MAX_CHANGE_DENOMINATOR,
+    BLOB_GASPRICE_UPDATE_FRACTION, BLOB_GASPRICE_UPDATE_FRACTION_ELECTRA, ELASTICITY_MULTIPLIER,
+    GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK, MAX_BLOB_GAS_PER_BLOCK_ELECTRA, MIN_BLOB_GASPRICE,
+    TARGET_BLOB_GAS_PER_BLOCK, TARGET_BLOB_GAS_PER_BLOCK_ELECTRA, U256,
 };
 pub use alloy_primitives::keccak256;
 
@@ -7,13 +10,66 @@ pub use alloy_primitives::keccak256;
 pub const KECCAK_EMPTY: B256 =
     b256!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
 
+/// Returns the hash of the init code, as used in the `CREATE2` address.
+#[inline]
+pub fn init_code_hash(init_code: &[u8]) -> B256 {
+    keccak256(init_code)
+}
+
+/// Returns the address of the contract created with `CREATE` by `deployer` with the given
+/// nonce.
+#[inline]
+pub fn create_address(deployer: Address, nonce: u64) -> Address {
+    deployer.create(nonce)
+}
+
+/// Returns the address of the contract created with `CREATE2` by `deployer`.
+///
+/// See [EIP-1014](https://eips.ethereum.org/EIPS/eip-1014).
+#[inline]
+pub fn create2_address(deployer: Address, salt: U256, init_code_hash: B256) -> Address {
+    deployer.create2(salt.to_be_bytes(), init_code_hash)
+}
+
+/// Returns the `CREATE2` addresses of the same init code deployed with each of the salts.
+pub fn create2_addresses<I>(
+    deployer: Address,
+    init_code_hash: B256,
+    salts: I,
+) -> impl Iterator<Item = (U256, Address)>
+where
+    I: IntoIterator<Item = U256>,
+{
+    salts
+        .into_iter()
+        .map(move |salt| (salt, create2_address(deployer, salt, init_code_hash)))
+}
+
 /// Calculates the `excess_blob_gas` from the parent header's `blob_gas_used` and `excess_blob_gas`.
 ///
 /// See also [the EIP-4844 helpers]<https://eips.ethereum.org/EIPS/eip-4844#helpers>
 /// (`calc_excess_blob_gas`).
 #[inline]
 pub fn calc_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_used: u64) -> u64 {
-    (parent_excess_blob_gas + parent_blob_gas_used).saturating_sub(TARGET_BLOB_GAS_PER_BLOCK)
+    calc_excess_blob_gas_with_target(
+        parent_excess_blob_gas,
+        parent_blob_gas_used,
+        TARGET_BLOB_GAS_PER_BLOCK,
+    )
+}
+
+/// Calculates the `excess_blob_gas` from the parent header's `blob_gas_used` and `excess_blob_gas`
+/// for the given target blob gas per block.
+///
+/// Use [`TARGET_BLOB_GAS_PER_BLOCK_ELECTRA`](crate::TARGET_BLOB_GAS_PER_BLOCK_ELECTRA) for
+/// blocks since Prague, see [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691).
+#[inline]
+pub fn calc_excess_blob_gas_with_target(
+    parent_excess_blob_gas: u64,
+    parent_blob_gas_used: u64,
+    target_blob_gas_per_block: u64,
+) -> u64 {
+    (parent_excess_blob_gas + parent_blob_gas_used).saturating_sub(target_blob_gas_per_block)
 }
 
 /// Calculates the blob gas price from the header's excess blob gas field.
@@ -22,11 +78,159 @@ pub fn calc_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_used: u
 /// (`get_blob_gasprice`).
 #[inline]
 pub fn calc_blob_gasprice(excess_blob_gas: u64) -> u128 {
-    fake_exponential(
-        MIN_BLOB_GASPRICE,
-        excess_blob_gas,
-        BLOB_GASPRICE_UPDATE_FRACTION,
-    )
+    calc_blob_gasprice_with_update_fraction(excess_blob_gas, BLOB_GASPRICE_UPDATE_FRACTION)
+}
+
+/// Calculates the blob gas price from the header's excess blob gas field and the given update
+/// fraction.
+///
+/// Use [`BLOB_GASPRICE_UPDATE_FRACTION_ELECTRA`](crate::BLOB_GASPRICE_UPDATE_FRACTION_ELECTRA)
+/// for blocks since Prague, see [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691).
+#[inline]
+pub fn calc_blob_gasprice_with_update_fraction(excess_blob_gas: u64, update_fraction: u64) -> u128 {
+    fake_exponential(MIN_BLOB_GASPRICE, excess_blob_gas, update_fraction)
+}
+
+/// Parameters of the blob fee market.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlobParams {
+    /// Target blob gas per block.
+    pub target_blob_gas_per_block: u64,
+    /// Maximum blob gas per block.
+    pub max_blob_gas_per_block: u64,
+    /// Update fraction of the blob gas price.
+    pub update_fraction: u64,
+}
+
+impl Default for BlobParams {
+    fn default() -> Self {
+        Self::cancun()
+    }
+}
+
+impl BlobParams {
+    /// Parameters since Cancun, see [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844).
+    pub const fn cancun() -> Self {
+        Self {
+            target_blob_gas_per_block: TARGET_BLOB_GAS_PER_BLOCK,
+            max_blob_gas_per_block: MAX_BLOB_GAS_PER_BLOCK,
+            update_fraction: BLOB_GASPRICE_UPDATE_FRACTION,
+        }
+    }
+
+    /// Parameters since Prague, see [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691).
+    pub const fn prague() -> Self {
+        Self {
+            target_blob_gas_per_block: TARGET_BLOB_GAS_PER_BLOCK_ELECTRA,
+            max_blob_gas_per_block: MAX_BLOB_GAS_PER_BLOCK_ELECTRA,
+            update_fraction: BLOB_GASPRICE_UPDATE_FRACTION_ELECTRA,
+        }
+    }
+
+    /// Returns the excess blob gas and blob gas price of a block with `excess_blob_gas`.
+    pub fn excess_gas_and_price(&self, excess_blob_gas: u64) -> BlobExcessGasAndPrice {
+        BlobExcessGasAndPrice {
+            excess_blob_gas,
+            blob_gasprice: calc_blob_gasprice_with_update_fraction(
+                excess_blob_gas,
+                self.update_fraction,
+            ),
+        }
+    }
+
+    /// Returns the excess blob gas of the child of a block with `excess_blob_gas` holding
+    /// `blobs` blobs, capped at the maximum blob gas per block.
+    pub fn next_excess_blob_gas(&self, excess_blob_gas: u64, blobs: u64) -> u64 {
+        let blob_gas_used = blobs
+            .saturating_mul(GAS_PER_BLOB)
+            .min(self.max_blob_gas_per_block);
+        calc_excess_blob_gas_with_target(
+            excess_blob_gas,
+            blob_gas_used,
+            self.target_blob_gas_per_block,
+        )
+    }
+}
+
+/// Simulates the blob fee of a sequence of hypothetical blocks, for estimating the blob fees of
+/// future blocks.
+///
+/// The first block has `excess_blob_gas`, and each block holds the next number of blobs of
+/// `blob_counts`, which sets the excess blob gas of the following block. One excess blob gas and
+/// price is returned per block, the fee of the block after the sequence is that of
+/// [BlobParams::next_excess_blob_gas] applied to the last item.
+pub fn simulate_blob_fees<I>(
+    excess_blob_gas: u64,
+    blob_counts: I,
+    params: BlobParams,
+) -> impl Iterator<Item = BlobExcessGasAndPrice>
+where
+    I: IntoIterator<Item = u64>,
+{
+    blob_counts
+        .into_iter()
+        .scan(excess_blob_gas, move |excess_blob_gas, blobs| {
+            let block = params.excess_gas_and_price(*excess_blob_gas);
+            *excess_blob_gas = params.next_excess_blob_gas(*excess_blob_gas, blobs);
+            Some(block)
+        })
+}
+
+/// Parameters of the EIP-1559 base fee calculation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaseFeeParams {
+    /// Bound divisor of the base fee change between blocks.
+    pub max_change_denominator: u64,
+    /// Ratio of the block gas limit to the gas target.
+    pub elasticity_multiplier: u64,
+}
+
+impl Default for BaseFeeParams {
+    fn default() -> Self {
+        Self::ethereum()
+    }
+}
+
+impl BaseFeeParams {
+    /// Ethereum mainnet parameters.
+    pub const fn ethereum() -> Self {
+        Self {
+            max_change_denominator: BASE_FEE_MAX_CHANGE_DENOMINATOR,
+            elasticity_multiplier: ELASTICITY_MULTIPLIER,
+        }
+    }
+}
+
+/// Calculates the base fee of the next block from the parent header's `gas_used`, `gas_limit`
+/// and `base_fee_per_gas`.
+///
+/// See also [the EIP-1559 specification](https://eips.ethereum.org/EIPS/eip-1559#specification).
+#[inline]
+pub fn calc_next_block_base_fee(
+    parent_gas_used: u64,
+    parent_gas_limit: u64,
+    parent_base_fee: u64,
+    params: BaseFeeParams,
+) -> u64 {
+    let gas_target = parent_gas_limit / params.elasticity_multiplier;
+    if gas_target == 0 || parent_gas_used == gas_target {
+        return parent_base_fee;
+    }
+
+    let base_fee = parent_base_fee as u128;
+    let target = gas_target as u128;
+    let denominator = params.max_change_denominator as u128;
+    if parent_gas_used > gas_target {
+        let gas_delta = (parent_gas_used - gas_target) as u128;
+        let delta = (base_fee * gas_delta / target / denominator).max(1);
+        (base_fee + delta).min(u64::MAX as u128) as u64
+    } else {
+        let gas_delta = (gas_target - parent_gas_used) as u128;
+        let delta = base_fee * gas_delta / target / denominator;
+        (base_fee - delta) as u64
+    }
 }
 
 /// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion.
@@ -62,7 +266,36 @@ pub fn fake_exponential(factor: u64, numerator: u64, denominator: u64) -> u128 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::GAS_PER_BLOB;
+    use crate::address;
+
+    #[test]
+    fn test_create_addresses() {
+        let deployer = address!("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(
+            create_address(deployer, 0),
+            address!("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+        );
+        assert_eq!(
+            create_address(deployer, 1),
+            address!("343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+        );
+
+        // EIP-1014 examples 0 and 1.
+        let hash = init_code_hash(&[0x00]);
+        assert_eq!(
+            create2_address(Address::ZERO, U256::ZERO, hash),
+            address!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38")
+        );
+        let deployer = address!("deadbeef00000000000000000000000000000000");
+        let addresses: Vec<_> = create2_addresses(deployer, hash, [U256::ZERO]).collect();
+        assert_eq!(
+            addresses,
+            [(
+                U256::ZERO,
+                address!("B928f69Bb1D91Cd65274e3c79d8986362984fDA3")
+            )]
+        );
+    }
 
     // https://github.com/ethereum/go-ethereum/blob/28857080d732857030eda80c69b9ba2c8926f221/consensus/misc/eip4844/eip4844_test.go#L27
     #[test]
@@ -142,6 +375,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_calc_excess_blob_gas_electra() {
+        use crate::TARGET_BLOB_GAS_PER_BLOCK_ELECTRA;
+
+        let target_blobs = TARGET_BLOB_GAS_PER_BLOCK_ELECTRA / GAS_PER_BLOB;
+        for t @ &(excess, blobs, expected) in &[
+            (0, target_blobs, 0),
+            (0, target_blobs + 1, GAS_PER_BLOB),
+            (GAS_PER_BLOB, target_blobs - 1, 0),
+        ] {
+            let actual = calc_excess_blob_gas_with_target(
+                excess,
+                blobs * GAS_PER_BLOB,
+                TARGET_BLOB_GAS_PER_BLOCK_ELECTRA,
+            );
+            assert_eq!(actual, expected, "test: {t:?}");
+        }
+    }
+
+    #[test]
+    fn test_simulate_blob_fees() {
+        let params = BlobParams::prague();
+        // Full blocks above the maximum are capped, then empty blocks drain the excess.
+        let blocks: Vec<_> = simulate_blob_fees(0, [20, 20, 0, 0, 0], params).collect();
+        let excess: Vec<_> = blocks.iter().map(|block| block.excess_blob_gas).collect();
+        assert_eq!(excess, [0, 3 * GAS_PER_BLOB, 6 * GAS_PER_BLOB, 0, 0]);
+        assert_eq!(blocks[0].blob_gasprice, 1);
+        assert!(blocks[2].blob_gasprice > blocks[1].blob_gasprice);
+        assert_eq!(
+            blocks[2],
+            BlobExcessGasAndPrice {
+                excess_blob_gas: 6 * GAS_PER_BLOB,
+                blob_gasprice: calc_blob_gasprice_with_update_fraction(
+                    6 * GAS_PER_BLOB,
+                    BLOB_GASPRICE_UPDATE_FRACTION_ELECTRA
+                ),
+            }
+        );
+
+        let cancun = BlobParams::default();
+        let excess = cancun.next_excess_blob_gas(0, 4);
+        assert_eq!(excess, calc_excess_blob_gas(0, 4 * GAS_PER_BLOB));
+        assert_eq!(
+            cancun.excess_gas_and_price(excess),
+            BlobExcessGasAndPrice::new(excess)
+        );
+    }
+
+    #[test]
+    fn test_calc_next_block_base_fee() {
+        for t @ &(gas_used, gas_limit, base_fee, expected) in &[
+            (10_000_000, 10_000_000, 1_000_000_000, 1_125_000_000),
+            (10_000_000, 12_000_000, 1_000_000_000, 1_083_333_333),
+            (9_000_000, 10_000_000, 1_072_671_875, 1_179_939_062),
+            (0, 2_000_000, 1_049_238_967, 918_084_097),
+            (5_000_000, 10_000_000, 1_000_000_000, 1_000_000_000),
+            // The base fee increases by at least one when above target.
+            (10_000_000, 18_000_000, 1, 2),
+        ] {
+            let actual =
+                calc_next_block_base_fee(gas_used, gas_limit, base_fee, BaseFeeParams::ethereum());
+            assert_eq!(actual, expected, "test: {t:?}");
+        }
+    }
+
     // https://github.com/ethereum/go-ethereum/blob/28857080d732857030eda80c69b9ba2c8926f221/consensus/misc/eip4844/eip4844_test.go#L78
     #[test]
     fn fake_exp() {
@@ -0,0 +1,192 @@
+//! Witness access events of the stateless gas model, see [EIP-4762].
+//!
+//! Every account header field, storage slot and code chunk is a leaf of the verkle tree,
+//! grouped in branches of 256 leaves. The first access to a branch or a leaf in a transaction
+//! costs gas, and so does the first write to them.
+//!
+//! [EIP-4762]: https://eips.ethereum.org/EIPS/eip-4762
+use crate::{
+    interpreter::gas::{
+        CHUNK_EDIT_COST, CHUNK_FILL_COST, SUBTREE_EDIT_COST, WITNESS_BRANCH_COST,
+        WITNESS_CHUNK_COST,
+    },
+    primitives::{Address, HashMap, U256},
+};
+
+/// Leaf of the account header holding the version, balance, nonce and code size.
+pub const BASIC_DATA_LEAF_KEY: u8 = 0;
+/// Leaf of the account header holding the code hash.
+pub const CODE_HASH_LEAF_KEY: u8 = 1;
+/// Leaf of the account header holding the first storage slot.
+pub const HEADER_STORAGE_OFFSET: u64 = 64;
+/// Position of the first code chunk of an account.
+pub const CODE_OFFSET: u64 = 128;
+/// Number of leaves of a branch.
+pub const VERKLE_NODE_WIDTH: u64 = 256;
+/// Number of code bytes in a chunk.
+pub const CODE_CHUNK_SIZE: usize = 31;
+
+/// Branch of the tree of an account, identified by its tree index.
+type Branch = (Address, U256);
+/// Leaf of a branch, identified by its sub index.
+type Leaf = (Address, U256, u8);
+
+/// Access events of a transaction and the gas they cost.
+///
+/// Set in [JournaledState::access_events](crate::JournaledState::access_events) to record the
+/// accesses of the journal. Events are not reverted with the state: the witness of a
+/// transaction includes what its reverted frames accessed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessEvents {
+    /// Accessed branches, and whether they were written.
+    branches: HashMap<Branch, bool>,
+    /// Accessed leaves, and whether they were written.
+    leaves: HashMap<Leaf, bool>,
+    /// Gas of the events recorded since the last [Self::take_gas].
+    gas: u64,
+}
+
+impl AccessEvents {
+    /// Creates an empty set of events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the gas of the events recorded since the last call, and resets it.
+    pub fn take_gas(&mut self) -> u64 {
+        core::mem::take(&mut self.gas)
+    }
+
+    /// Returns the number of accessed leaves.
+    pub fn leaves(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Records an access to the basic data of `address`.
+    pub fn touch_basic_data(&mut self, address: Address, write: bool) {
+        self.touch(address, U256::ZERO, BASIC_DATA_LEAF_KEY, write, false);
+    }
+
+    /// Records an access to the code hash of `address`.
+    pub fn touch_code_hash(&mut self, address: Address, write: bool) {
+        self.touch(address, U256::ZERO, CODE_HASH_LEAF_KEY, write, false);
+    }
+
+    /// Records an access to a storage slot of `address`.
+    ///
+    /// `fill` is set when a write gives a value to an empty slot.
+    pub fn touch_slot(&mut self, address: Address, slot: U256, write: bool, fill: bool) {
+        let (tree_index, sub_index) = slot_key(slot);
+        self.touch(address, tree_index, sub_index, write, fill);
+    }
+
+    /// Records an access to the chunks of the code of `address` holding the bytes from `start`
+    /// to `end`, exclusive.
+    pub fn touch_code(&mut self, address: Address, start: usize, end: usize, write: bool) {
+        if start >= end {
+            return;
+        }
+        for chunk in start / CODE_CHUNK_SIZE..=(end - 1) / CODE_CHUNK_SIZE {
+            let (tree_index, sub_index) = code_chunk_key(chunk as u64);
+            self.touch(address, tree_index, sub_index, write, write);
+        }
+    }
+
+    fn touch(
+        &mut self,
+        address: Address,
+        tree_index: U256,
+        sub_index: u8,
+        write: bool,
+        fill: bool,
+    ) {
+        let gas = &mut self.gas;
+        let branch = self
+            .branches
+            .entry((address, tree_index))
+            .or_insert_with(|| {
+                *gas += WITNESS_BRANCH_COST;
+                false
+            });
+        if write && !*branch {
+            *branch = true;
+            *gas += SUBTREE_EDIT_COST;
+        }
+
+        let leaf = self
+            .leaves
+            .entry((address, tree_index, sub_index))
+            .or_insert_with(|| {
+                *gas += WITNESS_CHUNK_COST;
+                false
+            });
+        if write && !*leaf {
+            *leaf = true;
+            *gas += CHUNK_EDIT_COST;
+            if fill {
+                *gas += CHUNK_FILL_COST;
+            }
+        }
+    }
+}
+
+/// Returns the tree index and sub index of a storage slot.
+pub fn slot_key(slot: U256) -> (U256, u8) {
+    let header_slots = U256::from(CODE_OFFSET - HEADER_STORAGE_OFFSET);
+    if slot < header_slots {
+        return (U256::ZERO, (HEADER_STORAGE_OFFSET as u8) + slot.to::<u8>());
+    }
+    // The main storage starts at 256^31, a multiple of the node width.
+    let tree_index = (slot >> 8) + (U256::from(1) << 240);
+    (tree_index, slot.byte(0))
+}
+
+/// Returns the tree index and sub index of a code chunk.
+pub fn code_chunk_key(chunk: u64) -> (U256, u8) {
+    let position = CODE_OFFSET + chunk;
+    (
+        U256::from(position / VERKLE_NODE_WIDTH),
+        (position % VERKLE_NODE_WIDTH) as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charge_first_access_and_write() {
+        let address = Address::with_last_byte(1);
+        let mut events = AccessEvents::new();
+
+        events.touch_basic_data(address, false);
+        assert_eq!(events.take_gas(), WITNESS_BRANCH_COST + WITNESS_CHUNK_COST);
+        // Same branch, new leaf.
+        events.touch_code_hash(address, false);
+        assert_eq!(events.take_gas(), WITNESS_CHUNK_COST);
+        events.touch_slot(address, U256::from(1), true, true);
+        assert_eq!(
+            events.take_gas(),
+            WITNESS_CHUNK_COST + SUBTREE_EDIT_COST + CHUNK_EDIT_COST + CHUNK_FILL_COST
+        );
+        events.touch_slot(address, U256::from(1), true, true);
+        events.touch_basic_data(address, false);
+        assert_eq!(events.take_gas(), 0);
+
+        // Chunks 0 to 4 are in the header branch.
+        events.touch_code(address, 0, 5 * CODE_CHUNK_SIZE, false);
+        assert_eq!(events.take_gas(), 5 * WITNESS_CHUNK_COST);
+        assert_eq!(events.leaves(), 8);
+    }
+
+    #[test]
+    fn tree_keys() {
+        assert_eq!(slot_key(U256::from(3)), (U256::ZERO, 67));
+        assert_eq!(
+            slot_key(U256::from(0x1234)),
+            ((U256::from(1) << 240) + U256::from(0x12), 0x34)
+        );
+        assert_eq!(code_chunk_key(0), (U256::ZERO, 128));
+        assert_eq!(code_chunk_key(128), (U256::from(1), 0));
+    }
+}
@@ -0,0 +1,238 @@
+//! Aggregate statistics of the state accessed by executed blocks.
+//!
+//! An [AccessStats] consumes the [ReadWriteSet] of every transaction of a block, usually
+//! recorded with a [RecordingDatabase](crate::db::RecordingDatabase), and keeps only counters:
+//! how often contracts are used and how often transactions of a block conflict. The sets are
+//! not retained, so statistics over many blocks take memory proportional to the number of
+//! distinct contracts. Nothing is collected unless blocks are passed to
+//! [AccessStats::record_block].
+//!
+//! With [AccessStats::anonymized], contracts are counted under a pseudonym derived from a salt,
+//! so that statistics can be shared without revealing which contracts they are about.
+
+use crate::{
+    db::ReadWriteSet,
+    primitives::{keccak256, Address, HashMap, HashSet, B256},
+};
+use std::vec::Vec;
+
+/// Usage of one contract, see [AccessStats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContractUsage {
+    /// Transactions reading or writing the storage of the contract.
+    pub transactions: u64,
+    /// Transactions writing the storage of the contract.
+    pub writes: u64,
+    /// Transactions writing the storage of the contract that another transaction of the block
+    /// also reads or writes.
+    pub contended_writes: u64,
+}
+
+/// Summary of the statistics of [AccessStats].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessSummary {
+    /// Recorded blocks.
+    pub blocks: u64,
+    /// Recorded transactions.
+    pub transactions: u64,
+    /// Ratio of the pairs of transactions of a block that conflict, over all blocks.
+    pub pair_conflict_rate: f64,
+    /// Ratio of the transactions that conflict with an earlier transaction of their block.
+    pub tx_conflict_rate: f64,
+    /// Most used contracts, by decreasing number of transactions.
+    pub hot_contracts: Vec<(Address, ContractUsage)>,
+}
+
+/// Aggregator of access statistics over many blocks, see the [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct AccessStats {
+    salt: Option<B256>,
+    blocks: u64,
+    transactions: u64,
+    pairs: u64,
+    conflicting_pairs: u64,
+    conflicting_transactions: u64,
+    contracts: HashMap<Address, ContractUsage>,
+}
+
+impl AccessStats {
+    /// Creates an empty aggregator counting contracts under their address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty aggregator counting contracts under the last 20 bytes of
+    /// `keccak256(salt ++ address)`.
+    pub fn anonymized(salt: B256) -> Self {
+        Self {
+            salt: Some(salt),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of recorded blocks.
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the number of recorded transactions.
+    pub fn transactions(&self) -> u64 {
+        self.transactions
+    }
+
+    /// Returns the usage of the contract at `address`, given as recorded, before anonymization.
+    pub fn contract(&self, address: Address) -> Option<&ContractUsage> {
+        self.contracts.get(&self.key(address))
+    }
+
+    /// Records the read and write sets of the transactions of a block, in block order.
+    ///
+    /// Every pair of transactions is compared, so this is quadratic in the number of
+    /// transactions of the block.
+    pub fn record_block(&mut self, sets: &[ReadWriteSet]) {
+        self.blocks += 1;
+        self.transactions += sets.len() as u64;
+        let mut contended = vec![false; sets.len()];
+        for (tx, set) in sets.iter().enumerate() {
+            let mut conflicts = false;
+            for (earlier, other) in sets[..tx].iter().enumerate() {
+                self.pairs += 1;
+                if set.conflicts_with(other) {
+                    self.conflicting_pairs += 1;
+                    conflicts = true;
+                    contended[earlier] = true;
+                }
+            }
+            if conflicts {
+                self.conflicting_transactions += 1;
+                contended[tx] = true;
+            }
+        }
+
+        for (set, contended) in sets.iter().zip(contended) {
+            let contracts: HashSet<_> = set
+                .reads
+                .storage
+                .keys()
+                .chain(set.writes.storage.keys())
+                .copied()
+                .collect();
+            for address in contracts {
+                let written = set.writes.storage.contains_key(&address);
+                let key = self.key(address);
+                let usage = self.contracts.entry(key).or_default();
+                usage.transactions += 1;
+                if written {
+                    usage.writes += 1;
+                    usage.contended_writes += contended as u64;
+                }
+            }
+        }
+    }
+
+    /// Returns the summary of the statistics, with the `top` most used contracts.
+    pub fn summary(&self, top: usize) -> AccessSummary {
+        let ratio = |count: u64, total: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 / total as f64
+            }
+        };
+        let mut hot_contracts: Vec<_> = self
+            .contracts
+            .iter()
+            .map(|(address, usage)| (*address, *usage))
+            .collect();
+        hot_contracts.sort_unstable_by(|(a, a_usage), (b, b_usage)| {
+            b_usage
+                .transactions
+                .cmp(&a_usage.transactions)
+                .then(a.cmp(b))
+        });
+        hot_contracts.truncate(top);
+        AccessSummary {
+            blocks: self.blocks,
+            transactions: self.transactions,
+            pair_conflict_rate: ratio(self.conflicting_pairs, self.pairs),
+            tx_conflict_rate: ratio(self.conflicting_transactions, self.transactions),
+            hot_contracts,
+        }
+    }
+
+    /// Returns the key the contract at `address` is counted under.
+    fn key(&self, address: Address) -> Address {
+        match self.salt {
+            Some(salt) => {
+                let mut preimage = [0u8; 52];
+                preimage[..32].copy_from_slice(salt.as_slice());
+                preimage[32..].copy_from_slice(address.as_slice());
+                Address::from_word(keccak256(preimage))
+            }
+            None => address,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::U256;
+
+    fn set(reads: &[(u8, u64)], writes: &[(u8, u64)]) -> ReadWriteSet {
+        let mut set = ReadWriteSet::default();
+        for (address, slot) in reads {
+            set.reads
+                .storage
+                .entry(Address::with_last_byte(*address))
+                .or_default()
+                .insert(U256::from(*slot));
+        }
+        for (address, slot) in writes {
+            set.writes
+                .storage
+                .entry(Address::with_last_byte(*address))
+                .or_default()
+                .insert(U256::from(*slot));
+        }
+        set
+    }
+
+    #[test]
+    fn hot_contracts_and_conflicts() {
+        let pool = Address::with_last_byte(1);
+        let mut stats = AccessStats::new();
+        // The first two transactions write the same slot of the pool, the third only reads
+        // another contract.
+        stats.record_block(&[
+            set(&[(1, 0)], &[(1, 0)]),
+            set(&[], &[(1, 0)]),
+            set(&[(2, 0)], &[]),
+        ]);
+        stats.record_block(&[set(&[(1, 1)], &[])]);
+
+        let summary = stats.summary(1);
+        assert_eq!((summary.blocks, summary.transactions), (2, 4));
+        assert_eq!(summary.pair_conflict_rate, 1.0 / 3.0);
+        assert_eq!(summary.tx_conflict_rate, 1.0 / 4.0);
+        assert_eq!(
+            summary.hot_contracts,
+            [(
+                pool,
+                ContractUsage {
+                    transactions: 3,
+                    writes: 2,
+                    contended_writes: 2,
+                }
+            )]
+        );
+
+        let mut anonymized = AccessStats::anonymized(B256::with_last_byte(7));
+        anonymized.record_block(&[set(&[(1, 0)], &[])]);
+        let (key, _) = anonymized.summary(1).hot_contracts[0];
+        assert_ne!(key, pool);
+        assert_eq!(anonymized.contract(pool).unwrap().transactions, 1);
+    }
+}
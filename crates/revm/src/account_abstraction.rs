@@ -0,0 +1,348 @@
+//! Experimental native account abstraction, in the style of RIP-7560 and EIP-7701.
+//!
+//! An [AaTransaction] separates the validation of a transaction from its execution. First the
+//! sender, and the paymaster if there is one, are called by [AA_ENTRY_POINT] with their own gas
+//! limits and accept the transaction by returning successfully. Then the fee payer is charged
+//! and the sender is called with the data of the transaction environment.
+//!
+//! If a validation frame fails the transaction is invalid: nothing is charged and the state
+//! changes of the validation are discarded. The stages run the handles of the [Evm], so
+//! handle registers apply to them as to any other transaction.
+//!
+//! This is meant for prototyping and does not follow any specification exactly.
+use crate::{
+    handler::mainnet,
+    interpreter::{CallContext, CallInputs, CallScheme, Gas, InterpreterResult, Transfer},
+    primitives::{
+        address, db::Database, spec_to_generic, Address, Bytes, EVMError, EVMResultGeneric, Env,
+        ExecutionStage, InvalidTransaction, ResultAndState, TransactTo, U256,
+    },
+    Evm, FrameOrResult, FrameResult,
+};
+use std::boxed::Box;
+
+/// Caller of the validation and execution frames.
+pub const AA_ENTRY_POINT: Address = address!("0000000000000000000000000000000000007560");
+
+/// Account abstraction fields of a transaction.
+///
+/// The gas limit, gas price, nonce and data of the execution are taken from the transaction
+/// environment.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AaTransaction {
+    /// Smart contract account sending the transaction.
+    pub sender: Address,
+    /// Input of the validation call to the sender.
+    pub validation_data: Bytes,
+    /// Gas limit of the validation call to the sender.
+    pub validation_gas_limit: u64,
+    /// Account paying the fees instead of the sender, if any.
+    pub paymaster: Option<Address>,
+    /// Input of the validation call to the paymaster.
+    pub paymaster_data: Bytes,
+    /// Gas limit of the validation call to the paymaster.
+    pub paymaster_validation_gas_limit: u64,
+}
+
+impl AaTransaction {
+    /// Returns the account paying the fees.
+    pub fn fee_payer(&self) -> Address {
+        self.paymaster.unwrap_or(self.sender)
+    }
+
+    /// Returns the gas limit of the whole validation phase.
+    pub fn total_validation_gas_limit(&self) -> u64 {
+        let paymaster_gas_limit = if self.paymaster.is_some() {
+            self.paymaster_validation_gas_limit
+        } else {
+            0
+        };
+        self.validation_gas_limit
+            .saturating_add(paymaster_gas_limit)
+    }
+}
+
+/// Validation frame of an [AaTransaction].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AaPhase {
+    /// Call to the sender.
+    SenderValidation,
+    /// Call to the paymaster.
+    PaymasterValidation,
+}
+
+/// Outcome of an [AaTransaction].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AaOutcome {
+    /// A validation frame did not accept the transaction, which is invalid.
+    Invalid {
+        /// The failed validation frame.
+        phase: AaPhase,
+        /// Result of the frame.
+        result: InterpreterResult,
+    },
+    /// The transaction was validated and executed.
+    Executed {
+        /// Gas used by the validation frames, included in the gas used of the result.
+        validation_gas_used: u64,
+        /// Result of the execution and the state of both phases.
+        result: ResultAndState,
+    },
+}
+
+impl<EXT, DB: Database> Evm<'_, EXT, DB> {
+    /// Validates and executes an account abstraction transaction, see the
+    /// [module documentation](self).
+    ///
+    /// The caller, target and value of the transaction environment are replaced by
+    /// [AA_ENTRY_POINT], the sender and zero.
+    pub fn transact_aa(&mut self, aa_tx: &AaTransaction) -> EVMResultGeneric<AaOutcome, DB::Error> {
+        let tx = &mut self.context.evm.env.tx;
+        tx.caller = AA_ENTRY_POINT;
+        tx.transact_to = TransactTo::Call(aa_tx.sender);
+        tx.value = U256::ZERO;
+
+        self.handler.validation().env(&self.context.evm.env)?;
+        let initial_gas_spend = self
+            .handler
+            .validation()
+            .initial_tx_gas(&self.context.evm.env)?;
+        self.validate_aa_against_state(aa_tx)
+            .map_err(|e| e.with_stage(ExecutionStage::Validation))?;
+
+        match self.transact_aa_inner(aa_tx, initial_gas_spend)? {
+            AaOutcome::Executed {
+                validation_gas_used,
+                result,
+            } => {
+                let result = self
+                    .handler
+                    .post_execution()
+                    .end(&mut self.context, Ok(result))?;
+                Ok(AaOutcome::Executed {
+                    validation_gas_used,
+                    result,
+                })
+            }
+            invalid => Ok(invalid),
+        }
+    }
+
+    /// Checks the nonce of the sender and that the fee payer can pay for all gas limits.
+    fn validate_aa_against_state(
+        &mut self,
+        aa_tx: &AaTransaction,
+    ) -> Result<(), EVMError<DB::Error>> {
+        let env = &self.context.evm.env;
+        let max_fee = U256::from(env.tx.gas_limit)
+            .checked_add(U256::from(aa_tx.total_validation_gas_limit()))
+            .and_then(|gas_limit| gas_limit.checked_mul(env.tx.gas_price))
+            .ok_or(InvalidTransaction::OverflowPaymentInTransaction)?;
+        let nonce = env.tx.nonce.filter(|_| !env.cfg.is_nonce_check_disabled());
+        let is_balance_check_disabled = env.cfg.is_balance_check_disabled();
+
+        let (sender, _) = self.context.evm.load_account(aa_tx.sender)?;
+        if let Some(tx) = nonce {
+            Env::validate_nonce(tx, sender.info.nonce)?;
+        }
+
+        let (fee_payer, _) = self.context.evm.load_account(aa_tx.fee_payer())?;
+        if max_fee > fee_payer.info.balance {
+            if is_balance_check_disabled {
+                fee_payer.info.balance = max_fee;
+            } else {
+                return Err(InvalidTransaction::LackOfFundForMaxFee {
+                    fee: Box::new(max_fee),
+                    balance: Box::new(fee_payer.info.balance),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the validation and execution phases.
+    fn transact_aa_inner(
+        &mut self,
+        aa_tx: &AaTransaction,
+        initial_gas_spend: u64,
+    ) -> EVMResultGeneric<AaOutcome, DB::Error> {
+        let pre_exec = self.handler.pre_execution();
+        pre_exec
+            .load_accounts(&mut self.context)
+            .map_err(|e| e.with_stage(ExecutionStage::PreExecution))?;
+        let precompiles = pre_exec.load_precompiles();
+        self.context.evm.set_precompiles(precompiles);
+
+        // Validation phase.
+        let mut phases = vec![(
+            AaPhase::SenderValidation,
+            aa_tx.sender,
+            aa_tx.validation_data.clone(),
+            aa_tx.validation_gas_limit,
+        )];
+        if let Some(paymaster) = aa_tx.paymaster {
+            phases.push((
+                AaPhase::PaymasterValidation,
+                paymaster,
+                aa_tx.paymaster_data.clone(),
+                aa_tx.paymaster_validation_gas_limit,
+            ));
+        }
+        let mut validation_gas_used = 0;
+        for (phase, target, input, gas_limit) in phases {
+            let result = self
+                .run_aa_frame(target, input, gas_limit)
+                .map_err(|e| e.with_stage(ExecutionStage::Execution))?
+                .into_interpreter_result();
+            validation_gas_used += result.gas.spent();
+            if !result.is_ok() {
+                // Invalid transactions leave no trace in the state.
+                self.context.evm.journaled_state.finalize();
+                return Ok(AaOutcome::Invalid { phase, result });
+            }
+        }
+
+        // Charge all gas limits, the unused gas is returned after the execution.
+        let gas_limit = self.context.evm.env.tx.gas_limit + aa_tx.total_validation_gas_limit();
+        let gas_cost =
+            U256::from(gas_limit).saturating_mul(self.context.evm.env.effective_gas_price());
+        let (fee_payer, _) = self.context.evm.load_account(aa_tx.fee_payer())?;
+        fee_payer.info.balance = fee_payer.info.balance.saturating_sub(gas_cost);
+        fee_payer.mark_touch();
+        let (sender, _) = self.context.evm.load_account(aa_tx.sender)?;
+        sender.info.nonce = sender.info.nonce.saturating_add(1);
+        sender.mark_touch();
+
+        // Execution phase.
+        let ctx = &mut self.context;
+        let inputs = CallInputs::new_boxed(
+            &ctx.evm.env.tx,
+            ctx.evm.env.tx.gas_limit - initial_gas_spend,
+        )
+        .expect("transaction is a call");
+        let first_frame_or_result = self.handler.execution().call(ctx, inputs)?;
+        let mut result = match first_frame_or_result {
+            FrameOrResult::Frame(frame) => self.start_the_loop(frame)?,
+            FrameOrResult::Result(result) => result,
+        };
+        let ctx = &mut self.context;
+        self.handler
+            .execution()
+            .last_frame_return(ctx, &mut result)
+            .map_err(|e| e.with_stage(ExecutionStage::Execution))?;
+
+        // Both phases are paid for together.
+        let mut gas = Gas::new(gas_limit);
+        gas.record_cost(validation_gas_used + result.gas().spent());
+        gas.record_refund(result.gas().refunded());
+        *result.gas_mut() = gas;
+
+        let post_exec = self.handler.post_execution();
+        let post_execution = || -> EVMResultGeneric<ResultAndState, DB::Error> {
+            let fee_payer = aa_tx.fee_payer();
+            spec_to_generic!(ctx.evm.spec_id(), {
+                mainnet::reimburse_account::<SPEC, EXT, DB>(ctx, &gas, fee_payer)?
+            });
+            post_exec.reward_beneficiary(ctx, &gas)?;
+            post_exec.output(ctx, result)
+        };
+        let result = post_execution().map_err(|e| e.with_stage(ExecutionStage::PostExecution))?;
+        Ok(AaOutcome::Executed {
+            validation_gas_used,
+            result,
+        })
+    }
+
+    /// Runs a call from [AA_ENTRY_POINT] to `target` until it returns.
+    fn run_aa_frame(
+        &mut self,
+        target: Address,
+        input: Bytes,
+        gas_limit: u64,
+    ) -> Result<FrameResult, EVMError<DB::Error>> {
+        let inputs = Box::new(CallInputs {
+            contract: target,
+            transfer: Transfer {
+                source: AA_ENTRY_POINT,
+                target,
+                value: U256::ZERO,
+            },
+            input,
+            gas_limit,
+            context: CallContext {
+                caller: AA_ENTRY_POINT,
+                address: target,
+                code_address: target,
+                apparent_value: U256::ZERO,
+                scheme: CallScheme::Call,
+            },
+            is_static: false,
+            return_memory_offset: 0..0,
+        });
+        let first_frame_or_result = self.handler.execution().call(&mut self.context, inputs)?;
+        match first_frame_or_result {
+            FrameOrResult::Frame(frame) => self.start_the_loop(frame),
+            FrameOrResult::Result(result) => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::InstructionResult,
+        primitives::{AccountInfo, Bytecode},
+        InMemoryDB,
+    };
+
+    fn transact(sender_code: &'static [u8]) -> AaOutcome {
+        let sender = address!("1000000000000000000000000000000000000000");
+        let code = Bytecode::new_raw(Bytes::from_static(sender_code));
+        let info = AccountInfo::new(U256::from(1_000_000), 0, code.hash_slow(), code);
+        Evm::builder()
+            .with_db(InMemoryDB::default())
+            .modify_db(|db| db.insert_account_info(sender, info))
+            .modify_tx_env(|tx| {
+                tx.gas_limit = 50_000;
+                tx.gas_price = U256::from(1);
+            })
+            .build()
+            .transact_aa(&AaTransaction {
+                sender,
+                validation_gas_limit: 10_000,
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn validation_accepts() {
+        // PUSH1 1 POP STOP
+        let AaOutcome::Executed {
+            validation_gas_used,
+            result,
+        } = transact(&[0x60, 0x01, 0x50, 0x00])
+        else {
+            panic!("transaction is valid")
+        };
+        // Both the validation and the execution run the code.
+        assert_eq!(validation_gas_used, 5);
+        assert_eq!(result.result.gas_used(), 21_000 + 5 + 5);
+        let sender = &result.state[&address!("1000000000000000000000000000000000000000")];
+        assert_eq!(sender.info.balance, U256::from(1_000_000 - 21_010));
+        assert_eq!(sender.info.nonce, 1);
+    }
+
+    #[test]
+    fn validation_rejects() {
+        // PUSH1 0 PUSH1 0 REVERT
+        let outcome = transact(&[0x60, 0x00, 0x60, 0x00, 0xfd]);
+        let AaOutcome::Invalid { phase, result } = outcome else {
+            panic!("transaction is invalid")
+        };
+        assert_eq!(phase, AaPhase::SenderValidation);
+        assert_eq!(result.result, InstructionResult::Revert);
+    }
+}
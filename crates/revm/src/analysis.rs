@@ -0,0 +1,34 @@
+//! Analysis of executed transactions for simulation APIs.
+//!
+//! The functions of this module work on the output of a transaction, its logs, its state and the
+//! transfers recorded by the [TransferInspector](crate::inspectors::TransferInspector), and
+//! return normalized descriptions of what the transaction did.
+
+mod approvals;
+mod balances;
+mod transfers;
+
+pub use approvals::{
+    allowance_changes, AllowanceChange, Approval, ApprovalCall, ApprovalSlot,
+    APPROVAL_MAPPING_SLOTS,
+};
+pub use balances::{balance_summary, AccountRole, BalanceCause, BalanceChange};
+pub use transfers::{asset_transfers, token_transfers, Asset, AssetTransfer};
+
+use crate::primitives::{Address, B256, U256};
+
+/// Reads the 32 byte word at `offset` of ABI encoded `data`.
+fn word(data: &[u8], offset: usize) -> Option<U256> {
+    let end = offset.checked_add(32)?;
+    data.get(offset..end).map(U256::from_be_slice)
+}
+
+/// Reads the word at `offset` of ABI encoded `data` as an offset or a length.
+fn word_usize(data: &[u8], offset: usize) -> Option<usize> {
+    word(data, offset)?.try_into().ok()
+}
+
+/// Returns the address of an indexed `address` event parameter, `None` for the zero address.
+fn topic_address(topic: &B256) -> Option<Address> {
+    Some(Address::from_word(*topic)).filter(|address| !address.is_zero())
+}
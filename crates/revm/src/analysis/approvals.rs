@@ -0,0 +1,339 @@
+use super::{topic_address, word, word_usize};
+use crate::primitives::{
+    b256, fixed_bytes, keccak256, Address, FixedBytes, Log, State as EvmState, B256, U256,
+};
+use std::vec::Vec;
+
+/// `Approval(address,address,uint256)` event of ERC-20 and ERC-721 tokens.
+const APPROVAL_EVENT: B256 =
+    b256!("8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925");
+
+/// `ApprovalForAll(address,address,bool)` event of ERC-721 and ERC-1155 tokens.
+const APPROVAL_FOR_ALL_EVENT: B256 =
+    b256!("17307eab39ab6107e8899845ad3d59bd9653f200f220920489ca2b5937696c31");
+
+/// Number of storage slots searched for the mapping holding the approvals of a token.
+pub const APPROVAL_MAPPING_SLOTS: u64 = 64;
+
+/// Approval granted or revoked by an [AllowanceChange].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Approval {
+    /// ERC-20 allowance of the spender.
+    Allowance {
+        /// New allowance.
+        amount: U256,
+    },
+    /// ERC-721 approval of a single token, the spender is `None` if it is cleared.
+    Token {
+        /// Token ID.
+        id: U256,
+    },
+    /// ERC-721 or ERC-1155 approval of an operator for all tokens of the owner.
+    Operator {
+        /// Whether the operator is approved or revoked.
+        approved: bool,
+    },
+}
+
+/// Storage slot holding an approval, with its value before and after the transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApprovalSlot {
+    /// Storage slot of the token.
+    pub slot: U256,
+    /// Value at the start of the transaction.
+    pub original_value: U256,
+    /// Value at the end of the transaction.
+    pub present_value: U256,
+}
+
+/// Approval given by an owner of tokens to a spender.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllowanceChange {
+    /// Token contract.
+    pub token: Address,
+    /// Owner of the tokens.
+    pub owner: Address,
+    /// Approved account, `None` if a token approval is cleared.
+    pub spender: Option<Address>,
+    /// Granted or revoked approval.
+    pub approval: Approval,
+    /// Index of the log among the logs of the transaction.
+    pub log_index: usize,
+    /// Changed slot of the token holding the approval, if the token stores approvals in a
+    /// Solidity mapping.
+    pub slot: Option<ApprovalSlot>,
+}
+
+impl AllowanceChange {
+    /// Returns `true` if the spender can transfer any amount of tokens of the owner: an
+    /// allowance of `U256::MAX` or an approved operator.
+    pub fn is_unlimited(&self) -> bool {
+        match self.approval {
+            Approval::Allowance { amount } => amount == U256::MAX,
+            Approval::Token { .. } => false,
+            Approval::Operator { approved } => approved,
+        }
+    }
+
+    /// Returns `true` if the change removes an approval.
+    pub fn is_revocation(&self) -> bool {
+        match self.approval {
+            Approval::Allowance { amount } => amount.is_zero(),
+            Approval::Token { .. } => self.spender.is_none(),
+            Approval::Operator { approved } => !approved,
+        }
+    }
+}
+
+/// Returns the approvals changed by a transaction with `logs` and `state`, in log order.
+///
+/// Approvals are read from the `Approval` and `ApprovalForAll` events, that `approve`, `permit`
+/// and `setApprovalForAll` calls emit. The slot of each approval is looked up among the changed
+/// storage of the token in `state`, at the location of a Solidity mapping declared in one of the
+/// first [APPROVAL_MAPPING_SLOTS] slots.
+pub fn allowance_changes(logs: &[Log], state: &EvmState) -> Vec<AllowanceChange> {
+    let mut changes = Vec::new();
+    for (log_index, log) in logs.iter().enumerate() {
+        let (topics, data) = (log.data.topics(), &log.data.data[..]);
+        let (owner, spender, approval, keys) = match topics {
+            [event, owner, spender] if *event == APPROVAL_EVENT && data.len() == 32 => {
+                let amount = U256::from_be_slice(data);
+                (
+                    owner,
+                    spender,
+                    Approval::Allowance { amount },
+                    [*owner, *spender],
+                )
+            }
+            [event, owner, spender, id] if *event == APPROVAL_EVENT && data.is_empty() => {
+                let approval = Approval::Token {
+                    id: U256::from_be_bytes(id.0),
+                };
+                (owner, spender, approval, [*id, B256::ZERO])
+            }
+            [event, owner, operator] if *event == APPROVAL_FOR_ALL_EVENT => {
+                let Some(approved) = word(data, 0).filter(|approved| *approved <= U256::from(1))
+                else {
+                    continue;
+                };
+                let approval = Approval::Operator {
+                    approved: approved == U256::from(1),
+                };
+                (owner, operator, approval, [*owner, *operator])
+            }
+            _ => continue,
+        };
+        let token = log.address;
+        // Token approvals are in a mapping from the token ID, the others in a nested mapping
+        // from the owner and the spender.
+        let nested = !matches!(approval, Approval::Token { .. });
+        changes.push(AllowanceChange {
+            token,
+            owner: Address::from_word(*owner),
+            spender: topic_address(spender),
+            approval,
+            log_index,
+            slot: find_slot(state, token, &keys[..1 + nested as usize]),
+        });
+    }
+    changes
+}
+
+/// Returns the changed slot of `token` of the mapping entry at `keys`.
+fn find_slot(state: &EvmState, token: Address, keys: &[B256]) -> Option<ApprovalSlot> {
+    let account = state.get(&token)?;
+    (0..APPROVAL_MAPPING_SLOTS).find_map(|base| {
+        let slot = keys
+            .iter()
+            .fold(U256::from(base), |slot, key| mapping_slot(key, slot));
+        let value = account
+            .storage
+            .get(&slot)
+            .filter(|value| value.is_changed())?;
+        Some(ApprovalSlot {
+            slot,
+            original_value: value.original_value(),
+            present_value: value.present_value(),
+        })
+    })
+}
+
+/// Returns the slot of the entry at `key` of the Solidity mapping declared at `slot`.
+fn mapping_slot(key: &B256, slot: U256) -> U256 {
+    let mut preimage = [0; 64];
+    preimage[..32].copy_from_slice(key.as_slice());
+    preimage[32..].copy_from_slice(&slot.to_be_bytes::<32>());
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+/// Approval granted by calling a token, decoded from the input of the call.
+///
+/// The input of a transaction tells what a wallet is asked to approve before it is executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ApprovalCall {
+    /// `approve(address spender, uint256 amount)`, also used by ERC-721 with a token ID.
+    Approve {
+        /// Approved account.
+        spender: Address,
+        /// Allowance or token ID.
+        amount: U256,
+    },
+    /// `increaseAllowance(address spender, uint256 added)`.
+    IncreaseAllowance {
+        /// Approved account.
+        spender: Address,
+        /// Added allowance.
+        added: U256,
+    },
+    /// EIP-2612 `permit(address owner, address spender, uint256 value, uint256 deadline, ...)`.
+    Permit {
+        /// Owner that signed the permit.
+        owner: Address,
+        /// Approved account.
+        spender: Address,
+        /// Allowance.
+        value: U256,
+        /// Timestamp after which the permit is invalid.
+        deadline: U256,
+    },
+    /// `setApprovalForAll(address operator, bool approved)`.
+    SetApprovalForAll {
+        /// Operator.
+        operator: Address,
+        /// Whether the operator is approved or revoked.
+        approved: bool,
+    },
+}
+
+impl ApprovalCall {
+    const APPROVE: FixedBytes<4> = fixed_bytes!("095ea7b3");
+    const INCREASE_ALLOWANCE: FixedBytes<4> = fixed_bytes!("39509351");
+    const PERMIT: FixedBytes<4> = fixed_bytes!("d505accf");
+    const SET_APPROVAL_FOR_ALL: FixedBytes<4> = fixed_bytes!("a22cb465");
+
+    /// Decodes the call of an approval function from its `input`.
+    pub fn decode(input: &[u8]) -> Option<Self> {
+        let selector = FixedBytes::<4>::try_from(input.get(..4)?).ok()?;
+        let args = &input[4..];
+        let address = |index: usize| {
+            let word = word(args, index * 32)?;
+            // Addresses are left padded with zeros.
+            (word >> 160)
+                .is_zero()
+                .then(|| Address::from_word(word.to_be_bytes::<32>().into()))
+        };
+        let call = if selector == Self::APPROVE {
+            Self::Approve {
+                spender: address(0)?,
+                amount: word(args, 32)?,
+            }
+        } else if selector == Self::INCREASE_ALLOWANCE {
+            Self::IncreaseAllowance {
+                spender: address(0)?,
+                added: word(args, 32)?,
+            }
+        } else if selector == Self::PERMIT {
+            // v, r and s follow the deadline.
+            word(args, 6 * 32)?;
+            Self::Permit {
+                owner: address(0)?,
+                spender: address(1)?,
+                value: word(args, 64)?,
+                deadline: word(args, 96)?,
+            }
+        } else if selector == Self::SET_APPROVAL_FOR_ALL {
+            let approved = word_usize(args, 32).filter(|approved| *approved <= 1)?;
+            Self::SetApprovalForAll {
+                operator: address(0)?,
+                approved: approved == 1,
+            }
+        } else {
+            return None;
+        };
+        Some(call)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{Account, Bytes, LogData, StorageSlot};
+
+    #[test]
+    fn allowance_changes_and_slots() {
+        let token = Address::with_last_byte(0x70);
+        let owner = Address::with_last_byte(0xa1).into_word();
+        let spender = Address::with_last_byte(0x5e).into_word();
+        let logs = [
+            Log {
+                address: token,
+                data: LogData::new(
+                    vec![APPROVAL_EVENT, owner, spender],
+                    Bytes::from(U256::MAX.to_be_bytes::<32>().to_vec()),
+                )
+                .unwrap(),
+            },
+            Log {
+                address: token,
+                data: LogData::new(
+                    vec![APPROVAL_FOR_ALL_EVENT, owner, spender],
+                    Bytes::from(U256::ZERO.to_be_bytes::<32>().to_vec()),
+                )
+                .unwrap(),
+            },
+        ];
+        // The allowances mapping is declared in slot 2.
+        let slot = mapping_slot(&spender, mapping_slot(&owner, U256::from(2)));
+        let mut account = Account::default();
+        account
+            .storage
+            .insert(slot, StorageSlot::new_changed(U256::from(5), U256::MAX));
+        let state = EvmState::from_iter([(token, account)]);
+
+        let changes = allowance_changes(&logs, &state);
+        assert_eq!(
+            changes[0],
+            AllowanceChange {
+                token,
+                owner: Address::from_word(owner),
+                spender: Some(Address::from_word(spender)),
+                approval: Approval::Allowance { amount: U256::MAX },
+                log_index: 0,
+                slot: Some(ApprovalSlot {
+                    slot,
+                    original_value: U256::from(5),
+                    present_value: U256::MAX,
+                }),
+            }
+        );
+        assert!(changes[0].is_unlimited());
+        assert_eq!(changes[1].approval, Approval::Operator { approved: false });
+        assert_eq!(changes[1].slot, None);
+        assert!(changes[1].is_revocation());
+    }
+
+    #[test]
+    fn decode_approval_calls() {
+        let spender = Address::with_last_byte(0x5e);
+        let mut input = ApprovalCall::APPROVE.to_vec();
+        input.extend_from_slice(spender.into_word().as_slice());
+        input.extend_from_slice(&U256::from(10).to_be_bytes::<32>());
+        assert_eq!(
+            ApprovalCall::decode(&input),
+            Some(ApprovalCall::Approve {
+                spender,
+                amount: U256::from(10),
+            })
+        );
+
+        input[..4].copy_from_slice(ApprovalCall::SET_APPROVAL_FOR_ALL.as_slice());
+        assert_eq!(ApprovalCall::decode(&input), None);
+        input[..4].copy_from_slice(ApprovalCall::APPROVE.as_slice());
+        input.truncate(4 + 32);
+        assert_eq!(ApprovalCall::decode(&input), None);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{
+    inspectors::{BalanceTransfer, TransferKind},
+    primitives::{Address, Env, HashMap, TransactTo, I256},
+};
+use std::vec::Vec;
+
+/// Role of an account in a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountRole {
+    /// Sender of the transaction.
+    Caller,
+    /// Account called by the transaction.
+    Recipient,
+    /// Beneficiary of the block.
+    Coinbase,
+    /// Any other account, such as contracts called during execution.
+    Other,
+}
+
+/// Part of a [BalanceChange] with a single cause.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceCause {
+    /// Cause of the transfers.
+    pub kind: TransferKind,
+    /// Net balance change of the transfers.
+    pub delta: I256,
+}
+
+/// Net balance change of an account in a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceChange {
+    /// The account.
+    pub address: Address,
+    /// Role of the account, the first that applies in the order of [AccountRole].
+    pub role: AccountRole,
+    /// Net balance change.
+    pub delta: I256,
+    /// Net balance change by cause, in the order the causes first moved the balance.
+    pub causes: Vec<BalanceCause>,
+}
+
+/// Returns the net balance change of the accounts of a transaction, classified by cause.
+///
+/// `transfers` are recorded by the [TransferInspector](crate::inspectors::TransferInspector),
+/// with the fees added. The caller, the called account and the coinbase come first and are
+/// always included, followed by the other accounts moving value in the order they first
+/// appear. Causes whose transfers cancel out are omitted.
+pub fn balance_summary(env: &Env, transfers: &[BalanceTransfer]) -> Vec<BalanceChange> {
+    let role = |address: Address| {
+        if address == env.tx.caller {
+            AccountRole::Caller
+        } else if env.tx.transact_to == TransactTo::Call(address) {
+            AccountRole::Recipient
+        } else if address == env.effective_coinbase() {
+            AccountRole::Coinbase
+        } else {
+            AccountRole::Other
+        }
+    };
+    let mut changes = Vec::new();
+    let mut indices = HashMap::new();
+    let mut change = |address: Address| {
+        *indices.entry(address).or_insert_with(|| {
+            changes.push(BalanceChange {
+                address,
+                role: role(address),
+                delta: I256::ZERO,
+                causes: Vec::new(),
+            });
+            changes.len() - 1
+        })
+    };
+
+    change(env.tx.caller);
+    if let TransactTo::Call(to) = env.tx.transact_to {
+        change(to);
+    }
+    change(env.effective_coinbase());
+    let mut deltas = Vec::new();
+    for transfer in transfers {
+        let value = I256::from_raw(transfer.value);
+        if let Some(from) = transfer.from {
+            deltas.push((change(from), transfer.kind, -value));
+        }
+        if let Some(to) = transfer.to {
+            deltas.push((change(to), transfer.kind, value));
+        }
+    }
+
+    for (index, kind, delta) in deltas {
+        let change = &mut changes[index];
+        change.delta += delta;
+        match change.causes.iter_mut().find(|cause| cause.kind == kind) {
+            Some(cause) => cause.delta += delta,
+            None => change.causes.push(BalanceCause { kind, delta }),
+        }
+    }
+    for change in &mut changes {
+        change.causes.retain(|cause| !cause.delta.is_zero());
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::U256;
+
+    #[test]
+    fn summary_by_cause() {
+        let caller = Address::with_last_byte(0xca);
+        let to = Address::with_last_byte(0x70);
+        let coinbase = Address::with_last_byte(0xc0);
+        let other = Address::with_last_byte(0x01);
+        let mut env = Env::default();
+        env.tx.caller = caller;
+        env.tx.transact_to = TransactTo::Call(to);
+        env.block.coinbase = coinbase;
+
+        let transfer = |kind, from, to, value: u64| BalanceTransfer {
+            kind,
+            from,
+            to,
+            value: U256::from(value),
+        };
+        let transfers = [
+            transfer(TransferKind::Call, Some(caller), Some(to), 100),
+            transfer(TransferKind::Call, Some(to), Some(other), 40),
+            transfer(TransferKind::Call, Some(other), Some(to), 40),
+            transfer(TransferKind::Fee, Some(caller), Some(coinbase), 5),
+            transfer(TransferKind::Fee, Some(caller), None, 10),
+        ];
+
+        let int = |value: i64| I256::try_from(value).unwrap();
+        let cause = |kind, delta| BalanceCause {
+            kind,
+            delta: int(delta),
+        };
+        assert_eq!(
+            balance_summary(&env, &transfers),
+            [
+                BalanceChange {
+                    address: caller,
+                    role: AccountRole::Caller,
+                    delta: int(-115),
+                    causes: vec![
+                        cause(TransferKind::Call, -100),
+                        cause(TransferKind::Fee, -15)
+                    ],
+                },
+                BalanceChange {
+                    address: to,
+                    role: AccountRole::Recipient,
+                    delta: int(100),
+                    causes: vec![cause(TransferKind::Call, 100)],
+                },
+                BalanceChange {
+                    address: coinbase,
+                    role: AccountRole::Coinbase,
+                    delta: int(5),
+                    causes: vec![cause(TransferKind::Fee, 5)],
+                },
+                BalanceChange {
+                    address: other,
+                    role: AccountRole::Other,
+                    delta: I256::ZERO,
+                    causes: vec![],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use super::{topic_address, word, word_usize};
+use crate::{
+    inspectors::BalanceTransfer,
+    primitives::{b256, Address, Log, B256, U256},
+};
+use std::vec::Vec;
+
+/// `Transfer(address,address,uint256)` event of ERC-20 and ERC-721 tokens.
+const TRANSFER_EVENT: B256 =
+    b256!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+/// `TransferSingle(address,address,address,uint256,uint256)` event of ERC-1155 tokens.
+const TRANSFER_SINGLE_EVENT: B256 =
+    b256!("c3d58168c5ae7397731d063d5bbf3d657854427343f4c083240f7aacaa2d0f62");
+
+/// `TransferBatch(address,address,address,uint256[],uint256[])` event of ERC-1155 tokens.
+const TRANSFER_BATCH_EVENT: B256 =
+    b256!("4a39dc06d4c0dbc64b70af90fd698a233a518aa5d07e595d983b8c0526c8f7fb");
+
+/// Asset moved by an [AssetTransfer].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Asset {
+    /// Ether.
+    Native,
+    /// Fungible ERC-20 token.
+    Erc20 {
+        /// Token contract.
+        token: Address,
+    },
+    /// Non-fungible ERC-721 token.
+    Erc721 {
+        /// Token contract.
+        token: Address,
+        /// Token ID.
+        id: U256,
+    },
+    /// ERC-1155 multi token.
+    Erc1155 {
+        /// Token contract.
+        token: Address,
+        /// Token ID.
+        id: U256,
+    },
+}
+
+/// Movement of an asset between accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssetTransfer {
+    /// Transferred asset.
+    pub asset: Asset,
+    /// Debited account, `None` if the asset is minted.
+    pub from: Option<Address>,
+    /// Credited account, `None` if the asset is burned.
+    pub to: Option<Address>,
+    /// Transferred amount, 1 for ERC-721 tokens.
+    pub amount: U256,
+    /// Index of the log of token transfers among the logs of the transaction.
+    pub log_index: Option<usize>,
+}
+
+/// Returns the native transfers followed by the token transfers of a transaction.
+///
+/// `native` are the transfers recorded by the
+/// [TransferInspector](crate::inspectors::TransferInspector), including internal calls and fees,
+/// and `logs` the logs of the transaction.
+pub fn asset_transfers(logs: &[Log], native: &[BalanceTransfer]) -> Vec<AssetTransfer> {
+    let mut transfers: Vec<_> = native
+        .iter()
+        .map(|transfer| AssetTransfer {
+            asset: Asset::Native,
+            from: transfer.from,
+            to: transfer.to,
+            amount: transfer.value,
+            log_index: None,
+        })
+        .collect();
+    transfers.extend(token_transfers(logs));
+    transfers
+}
+
+/// Returns the ERC-20, ERC-721 and ERC-1155 transfers of `logs`, in log order.
+///
+/// The standards are told apart by their events, ERC-20 and ERC-721 transfers by the number of
+/// indexed parameters. Malformed events are skipped, as are transfers of a zero amount. The zero
+/// address of mints and burns is returned as `None`.
+pub fn token_transfers(logs: &[Log]) -> Vec<AssetTransfer> {
+    let mut transfers = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        let (topics, data) = (log.data.topics(), &log.data.data[..]);
+        let token = log.address;
+        let mut push = |asset, from, to, amount: U256| {
+            if !amount.is_zero() {
+                transfers.push(AssetTransfer {
+                    asset,
+                    from: topic_address(from),
+                    to: topic_address(to),
+                    amount,
+                    log_index: Some(index),
+                });
+            }
+        };
+        match topics {
+            [event, from, to] if *event == TRANSFER_EVENT && data.len() == 32 => {
+                push(Asset::Erc20 { token }, from, to, U256::from_be_slice(data));
+            }
+            [event, from, to, id] if *event == TRANSFER_EVENT && data.is_empty() => {
+                let id = U256::from_be_bytes(id.0);
+                push(Asset::Erc721 { token, id }, from, to, U256::from(1));
+            }
+            [event, _, from, to] if *event == TRANSFER_SINGLE_EVENT && data.len() == 64 => {
+                if let (Some(id), Some(amount)) = (word(data, 0), word(data, 32)) {
+                    push(Asset::Erc1155 { token, id }, from, to, amount);
+                }
+            }
+            [event, _, from, to] if *event == TRANSFER_BATCH_EVENT => {
+                let (Some(ids), Some(amounts)) = (array(data, 0), array(data, 32)) else {
+                    continue;
+                };
+                if ids.len() != amounts.len() {
+                    continue;
+                }
+                for (id, amount) in ids.into_iter().zip(amounts) {
+                    push(Asset::Erc1155 { token, id }, from, to, amount);
+                }
+            }
+            _ => {}
+        }
+    }
+    transfers
+}
+
+/// Reads the `uint256[]` whose offset is at `head` of ABI encoded `data`.
+fn array(data: &[u8], head: usize) -> Option<Vec<U256>> {
+    let offset = word_usize(data, head)?;
+    let len = word_usize(data, offset)?;
+    // Each element takes a word, this bounds the allocation.
+    if len > data.len() / 32 {
+        return None;
+    }
+    (0..len)
+        .map(|i| word(data, offset.checked_add(32 * (i + 1))?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspectors::TransferKind,
+        primitives::{address, Bytes, LogData},
+    };
+
+    fn log(token: Address, topics: Vec<B256>, data: Vec<u8>) -> Log {
+        Log {
+            address: token,
+            data: LogData::new(topics, Bytes::from(data)).unwrap(),
+        }
+    }
+
+    fn words(words: &[u64]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|word| U256::from(*word).to_be_bytes::<32>())
+            .collect()
+    }
+
+    #[test]
+    fn extract_transfers() {
+        let token = address!("00000000000000000000000000000000000070c0");
+        let alice = Address::with_last_byte(0xa1);
+        let bob = Address::with_last_byte(0xb0);
+        let operator = Address::with_last_byte(0x0e).into_word();
+        let (a, b) = (alice.into_word(), bob.into_word());
+
+        let logs = [
+            log(token, vec![TRANSFER_EVENT, a, b], words(&[100])),
+            log(
+                token,
+                vec![TRANSFER_EVENT, B256::ZERO, b, B256::with_last_byte(7)],
+                vec![],
+            ),
+            log(
+                token,
+                vec![TRANSFER_SINGLE_EVENT, operator, a, b],
+                words(&[3, 5]),
+            ),
+            log(
+                token,
+                vec![TRANSFER_BATCH_EVENT, operator, b, B256::ZERO],
+                words(&[0x40, 0xa0, 2, 1, 2, 2, 10, 20]),
+            ),
+            // Other events and zero amounts are skipped.
+            log(token, vec![B256::with_last_byte(1), a, b], words(&[1])),
+            log(token, vec![TRANSFER_EVENT, a, b], words(&[0])),
+        ];
+        let native = [BalanceTransfer {
+            kind: TransferKind::Call,
+            from: Some(alice),
+            to: Some(token),
+            value: U256::from(1),
+        }];
+
+        let transfer = |asset, from, to, amount: u64, log_index| AssetTransfer {
+            asset,
+            from,
+            to,
+            amount: U256::from(amount),
+            log_index,
+        };
+        let id = |id: u64| U256::from(id);
+        assert_eq!(
+            asset_transfers(&logs, &native),
+            [
+                transfer(Asset::Native, Some(alice), Some(token), 1, None),
+                transfer(Asset::Erc20 { token }, Some(alice), Some(bob), 100, Some(0)),
+                transfer(
+                    Asset::Erc721 { token, id: id(7) },
+                    None,
+                    Some(bob),
+                    1,
+                    Some(1)
+                ),
+                transfer(
+                    Asset::Erc1155 { token, id: id(3) },
+                    Some(alice),
+                    Some(bob),
+                    5,
+                    Some(2)
+                ),
+                transfer(
+                    Asset::Erc1155 { token, id: id(1) },
+                    Some(bob),
+                    None,
+                    10,
+                    Some(3)
+                ),
+                transfer(
+                    Asset::Erc1155 { token, id: id(2) },
+                    Some(bob),
+                    None,
+                    20,
+                    Some(3)
+                ),
+            ]
+        );
+    }
+}
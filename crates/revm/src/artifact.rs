@@ -0,0 +1,368 @@
+//! Contract artifacts of Foundry and solc.
+//!
+//! A [ContractArtifact] holds the creation and runtime bytecode of a contract compiled by
+//! Foundry, from the JSON files of its `out/` directory, or by solc, from its standard JSON
+//! output. Libraries are linked with [ContractArtifact::link] before the contract is deployed
+//! into a test database.
+use crate::{
+    db::{CacheDB, Database, DatabaseCommit, DatabaseRef},
+    primitives::{Address, Bytecode, Bytes, EVMError, ExecutionResult, Output, TransactTo, U256},
+    Evm,
+};
+use core::fmt;
+use std::{format, string::String, vec::Vec};
+
+/// Location of the address of a library in unlinked bytecode.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LinkReference {
+    /// Source file declaring the library.
+    pub file: String,
+    /// Name of the library.
+    pub library: String,
+    /// Byte offsets of the address placeholders.
+    pub offsets: Vec<usize>,
+}
+
+impl LinkReference {
+    /// Returns `true` if `library` is the name of the library, optionally qualified by its file
+    /// as `file:name`.
+    pub fn matches(&self, library: &str) -> bool {
+        match library.rsplit_once(':') {
+            Some((file, name)) => file == self.file && name == self.library,
+            None => library == self.library,
+        }
+    }
+}
+
+/// Bytecode with the address placeholders of the libraries it calls.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LinkableCode {
+    /// Bytecode, with the placeholders of unlinked libraries zeroed.
+    pub code: Bytes,
+    /// Libraries left to link.
+    pub link_references: Vec<LinkReference>,
+}
+
+impl LinkableCode {
+    /// Writes `address` at the placeholders of `library`, see [LinkReference::matches].
+    ///
+    /// Returns `false` if the code does not call the library.
+    pub fn link(&mut self, library: &str, address: Address) -> bool {
+        let mut code = None;
+        self.link_references.retain(|reference| {
+            if !reference.matches(library) {
+                return true;
+            }
+            let code = code.get_or_insert_with(|| self.code.to_vec());
+            for offset in &reference.offsets {
+                code[*offset..*offset + 20].copy_from_slice(address.as_slice());
+            }
+            false
+        });
+        let linked = code.is_some();
+        if let Some(code) = code {
+            self.code = code.into();
+        }
+        linked
+    }
+
+    /// Returns `true` if all libraries are linked.
+    pub fn is_linked(&self) -> bool {
+        self.link_references.is_empty()
+    }
+
+    /// Returns the bytecode if all libraries are linked, else their names as `file:name`.
+    pub fn linked(&self) -> Result<&Bytes, Vec<String>> {
+        if self.is_linked() {
+            return Ok(&self.code);
+        }
+        Err(self
+            .link_references
+            .iter()
+            .map(|reference| format!("{}:{}", reference.file, reference.library))
+            .collect())
+    }
+}
+
+/// Creation and runtime bytecode of a compiled contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ContractArtifact {
+    /// Creation bytecode, without constructor arguments.
+    pub bytecode: LinkableCode,
+    /// Runtime bytecode.
+    pub deployed_bytecode: LinkableCode,
+}
+
+/// Errors of deploying a [ContractArtifact].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeployError<DBError> {
+    /// Libraries are not linked, with their names as `file:name`.
+    Unlinked(Vec<String>),
+    /// The creation transaction could not be executed.
+    Evm(EVMError<DBError>),
+    /// The constructor reverted or halted.
+    Failed(ExecutionResult),
+}
+
+impl<DBError: fmt::Display> fmt::Display for DeployError<DBError> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unlinked(libraries) => write!(f, "unlinked libraries: {}", libraries.join(", ")),
+            Self::Evm(error) => write!(f, "deployment failed: {error}"),
+            Self::Failed(result) => write!(f, "constructor failed: {result:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DBError: fmt::Debug + fmt::Display> std::error::Error for DeployError<DBError> {}
+
+impl ContractArtifact {
+    /// Links `library` at `address` in the creation and runtime bytecode.
+    ///
+    /// See [LinkableCode::link].
+    pub fn link(&mut self, library: &str, address: Address) -> &mut Self {
+        self.bytecode.link(library, address);
+        self.deployed_bytecode.link(library, address);
+        self
+    }
+
+    /// Returns `true` if all libraries are linked.
+    pub fn is_linked(&self) -> bool {
+        self.bytecode.is_linked() && self.deployed_bytecode.is_linked()
+    }
+
+    /// Executes the creation bytecode followed by the ABI encoded constructor `args` and commits
+    /// the contract to `db`, pass `&mut db` to keep the database.
+    ///
+    /// The contract is created by `deployer` with its current nonce and without gas fees.
+    /// Returns the address of the contract.
+    pub fn deploy<DB: Database + DatabaseCommit>(
+        &self,
+        db: DB,
+        deployer: Address,
+        args: &[u8],
+    ) -> Result<Address, DeployError<DB::Error>> {
+        let mut data = self
+            .bytecode
+            .linked()
+            .map_err(DeployError::Unlinked)?
+            .to_vec();
+        data.extend_from_slice(args);
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .modify_tx_env(|tx| {
+                tx.caller = deployer;
+                tx.transact_to = TransactTo::create();
+                tx.data = data.into();
+                tx.gas_price = U256::ZERO;
+            })
+            .modify_block_env(|block| block.basefee = U256::ZERO)
+            .build();
+        match evm.transact_commit().map_err(DeployError::Evm)? {
+            ExecutionResult::Success {
+                output: Output::Create(_, Some(address)),
+                ..
+            } => Ok(address),
+            result => Err(DeployError::Failed(result)),
+        }
+    }
+
+    /// Sets the runtime bytecode as the code of `address` in `db`, without running the
+    /// constructor.
+    pub fn install<ExtDB: DatabaseRef>(
+        &self,
+        db: &mut CacheDB<ExtDB>,
+        address: Address,
+    ) -> Result<(), DeployError<ExtDB::Error>> {
+        let code = self
+            .deployed_bytecode
+            .linked()
+            .map_err(DeployError::Unlinked)?;
+        let code = Bytecode::new_raw(code.clone());
+        let mut info = db
+            .accounts
+            .get(&address)
+            .map(|account| account.info.clone())
+            .unwrap_or_default();
+        info.code_hash = code.hash_slow();
+        info.code = Some(code);
+        db.insert_account_info(address, info);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde-json")]
+mod json {
+    use super::*;
+    use crate::primitives::hex;
+    use serde::de::Error as _;
+    use serde_json::Error;
+    use std::collections::BTreeMap;
+
+    /// Bytecode object of Foundry and solc artifacts.
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct BytecodeJson {
+        object: String,
+        #[serde(default)]
+        link_references: BTreeMap<String, BTreeMap<String, Vec<OffsetJson>>>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct OffsetJson {
+        start: usize,
+        length: usize,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ArtifactJson {
+        bytecode: BytecodeJson,
+        deployed_bytecode: BytecodeJson,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct StandardJson {
+        contracts: BTreeMap<String, BTreeMap<String, StandardContractJson>>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct StandardContractJson {
+        evm: ArtifactJson,
+    }
+
+    impl BytecodeJson {
+        /// Decodes the object, zeroing the placeholders of the libraries.
+        fn parse(self) -> Result<LinkableCode, Error> {
+            let object = self.object.strip_prefix("0x").unwrap_or(&self.object);
+            let mut hex_code = object.as_bytes().to_vec();
+            let mut link_references = Vec::new();
+            for (file, libraries) in self.link_references {
+                for (library, offsets) in libraries {
+                    let mut starts = Vec::new();
+                    for offset in offsets {
+                        let placeholder = offset
+                            .start
+                            .checked_mul(2)
+                            .and_then(|start| hex_code.get_mut(start..start.checked_add(40)?))
+                            .filter(|_| offset.length == 20)
+                            .ok_or_else(|| Error::custom("invalid link reference"))?;
+                        placeholder.fill(b'0');
+                        starts.push(offset.start);
+                    }
+                    link_references.push(LinkReference {
+                        file: file.clone(),
+                        library,
+                        offsets: starts,
+                    });
+                }
+            }
+            let code = hex::decode(hex_code).map_err(Error::custom)?;
+            Ok(LinkableCode {
+                code: code.into(),
+                link_references,
+            })
+        }
+    }
+
+    impl ArtifactJson {
+        fn parse(self) -> Result<ContractArtifact, Error> {
+            Ok(ContractArtifact {
+                bytecode: self.bytecode.parse()?,
+                deployed_bytecode: self.deployed_bytecode.parse()?,
+            })
+        }
+    }
+
+    impl ContractArtifact {
+        /// Parses a Foundry artifact, such as `out/Counter.sol/Counter.json`.
+        pub fn from_foundry_json(json: &str) -> Result<Self, Error> {
+            serde_json::from_str::<ArtifactJson>(json)?.parse()
+        }
+
+        /// Parses `contract` of the source `file` in the standard JSON output of solc.
+        pub fn from_standard_json(json: &str, file: &str, contract: &str) -> Result<Self, Error> {
+            let mut output: StandardJson = serde_json::from_str(json)?;
+            output
+                .contracts
+                .get_mut(file)
+                .and_then(|contracts| contracts.remove(contract))
+                .ok_or_else(|| Error::custom(format!("contract {file}:{contract} not found")))?
+                .evm
+                .parse()
+        }
+
+        /// Loads the Foundry artifact of `contract` declared in the source `file` from the `out`
+        /// directory, `out/<file>/<contract>.json`.
+        #[cfg(feature = "std")]
+        pub fn load_foundry(
+            out: impl AsRef<std::path::Path>,
+            file: &str,
+            contract: &str,
+        ) -> std::io::Result<Self> {
+            let path = out.as_ref().join(file).join(format!("{contract}.json"));
+            let json = std::fs::read_to_string(path)?;
+            Self::from_foundry_json(&json)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde-json"))]
+mod tests {
+    use super::*;
+    use crate::db::EmptyDB;
+
+    /// Placeholder of the `Lib` library in unlinked bytecode.
+    const PLACEHOLDER: &str = "__$0123456789abcdef0123456789abcdef01$__";
+
+    #[test]
+    fn link_and_deploy() {
+        // PUSH20 Lib STOP
+        let runtime = format!("73{PLACEHOLDER}00");
+        // CODECOPY(0, 10, 22) RETURN(0, 22) followed by the runtime code.
+        let creation = format!("6016600a5f3960165ff3{runtime}");
+        let references = |start: usize| {
+            format!(r#"{{"src/Lib.sol": {{"Lib": [{{"start": {start}, "length": 20}}]}}}}"#)
+        };
+        let foundry = format!(
+            r#"{{
+                "abi": [],
+                "bytecode": {{"object": "0x{creation}", "linkReferences": {}}},
+                "deployedBytecode": {{"object": "0x{runtime}", "linkReferences": {}}}
+            }}"#,
+            references(11),
+            references(1),
+        );
+        let standard = format!(
+            r#"{{"contracts": {{"src/A.sol": {{"A": {{"evm": {{
+                "bytecode": {{"object": "{creation}", "linkReferences": {}}},
+                "deployedBytecode": {{"object": "{runtime}", "linkReferences": {}}}
+            }}}}}}}}}}"#,
+            references(11),
+            references(1),
+        );
+
+        let mut artifact = ContractArtifact::from_foundry_json(&foundry).unwrap();
+        assert_eq!(
+            ContractArtifact::from_standard_json(&standard, "src/A.sol", "A").unwrap(),
+            artifact
+        );
+        assert!(ContractArtifact::from_standard_json(&standard, "src/A.sol", "B").is_err());
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        let deployer = Address::with_last_byte(0xde);
+        assert_eq!(
+            artifact.deploy(&mut db, deployer, &[]),
+            Err(DeployError::Unlinked(vec![String::from("src/Lib.sol:Lib")]))
+        );
+
+        let library = Address::with_last_byte(0x11);
+        assert!(artifact.link("src/Lib.sol:Lib", library).is_linked());
+        let address = artifact.deploy(&mut db, deployer, &[]).unwrap();
+        let code = db.accounts[&address].info.code.clone().unwrap();
+        assert_eq!(code.original_bytes(), artifact.deployed_bytecode.code);
+        assert_eq!(&code.original_bytes()[1..21], library.as_slice());
+    }
+}
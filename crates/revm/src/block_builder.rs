@@ -0,0 +1,252 @@
+use crate::{
+    db::{Database, DatabaseCommit},
+    primitives::{
+        BlockEnv, EVMError, ExecutionResult, InvalidTransaction, TxEnv, MAX_BLOB_GAS_PER_BLOCK,
+    },
+    Evm, ExecutedTx,
+};
+use std::vec::Vec;
+
+/// What a [BlockBuilder] does with transactions that revert or halt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FailurePolicy {
+    /// Include them in the block, they still pay for the gas they used.
+    #[default]
+    Include,
+    /// Leave them out of the block and discard their state.
+    Evict,
+}
+
+/// Reason a candidate transaction was left out of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The gas limit of the transaction is above the gas left in the block.
+    GasLimit,
+    /// The blob gas of the transaction is above the blob gas left in the block.
+    BlobGasLimit,
+    /// The transaction is invalid on the pending state.
+    Invalid(InvalidTransaction),
+    /// The transaction reverted or halted and [FailurePolicy::Evict] is set.
+    Failed(ExecutionResult),
+}
+
+/// Candidate transaction left out of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedTx {
+    /// The transaction.
+    pub tx: TxEnv,
+    /// Why it was left out.
+    pub reason: Rejection,
+}
+
+/// Block produced by a [BlockBuilder].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuiltBlock {
+    /// Environment the block was built on.
+    pub block: BlockEnv,
+    /// Included transactions, in order.
+    pub transactions: Vec<TxEnv>,
+    /// Results of the included transactions, in order.
+    pub results: Vec<ExecutedTx>,
+    /// Candidates left out of the block, in the order they were added.
+    pub rejected: Vec<RejectedTx>,
+    /// Gas used by the included transactions.
+    pub gas_used: u64,
+    /// Blob gas used by the included transactions.
+    pub blob_gas_used: u64,
+}
+
+/// Builds a block out of candidate transactions on top of a pending state.
+///
+/// Candidates are executed in the order they are added, on the block environment and the
+/// database of the EVM. A candidate is included if it fits in the gas and blob gas left in the
+/// block and is valid on the state left by the previous transactions; its state is then
+/// committed. Candidates that do not fit are rejected without being executed, so smaller ones
+/// added later can still fill the block.
+pub struct BlockBuilder<'a, EXT, DB: Database> {
+    evm: Evm<'a, EXT, DB>,
+    policy: FailurePolicy,
+    max_blob_gas: u64,
+    block: BuiltBlock,
+}
+
+impl<'a, EXT, DB: Database + DatabaseCommit> BlockBuilder<'a, EXT, DB> {
+    /// Creates a builder executing on the block environment and database of `evm`.
+    pub fn new(evm: Evm<'a, EXT, DB>) -> Self {
+        let block = BuiltBlock {
+            block: evm.block().clone(),
+            ..Default::default()
+        };
+        Self {
+            evm,
+            policy: FailurePolicy::default(),
+            max_blob_gas: MAX_BLOB_GAS_PER_BLOCK,
+            block,
+        }
+    }
+
+    /// Sets what is done with transactions that revert or halt.
+    pub fn with_failure_policy(mut self, policy: FailurePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the blob gas limit of the block, [MAX_BLOB_GAS_PER_BLOCK] by default.
+    pub fn with_max_blob_gas(mut self, max_blob_gas: u64) -> Self {
+        self.max_blob_gas = max_blob_gas;
+        self
+    }
+
+    /// Returns the EVM the block is built with.
+    pub fn evm(&mut self) -> &mut Evm<'a, EXT, DB> {
+        &mut self.evm
+    }
+
+    /// Returns the block built so far.
+    pub fn block(&self) -> &BuiltBlock {
+        &self.block
+    }
+
+    /// Returns the gas left in the block.
+    pub fn gas_left(&self) -> u64 {
+        let gas_limit = self.block.block.gas_limit.saturating_to::<u64>();
+        gas_limit.saturating_sub(self.block.gas_used)
+    }
+
+    /// Returns the blob gas left in the block.
+    pub fn blob_gas_left(&self) -> u64 {
+        self.max_blob_gas.saturating_sub(self.block.blob_gas_used)
+    }
+
+    /// Executes the candidate and includes it in the block unless it is rejected.
+    ///
+    /// Returns whether the transaction was included. Database errors are returned and leave the
+    /// block unchanged.
+    pub fn add(&mut self, tx: TxEnv) -> Result<bool, EVMError<DB::Error>> {
+        if tx.gas_limit > self.gas_left() {
+            return Ok(self.reject(tx, Rejection::GasLimit));
+        }
+        let blob_gas = tx.get_total_blob_gas();
+        if blob_gas > self.blob_gas_left() {
+            return Ok(self.reject(tx, Rejection::BlobGasLimit));
+        }
+
+        *self.evm.tx_mut() = tx;
+        let output = self.evm.transact();
+        let tx = core::mem::take(self.evm.tx_mut());
+        let output = match output {
+            Ok(output) => output,
+            Err(EVMError::Transaction(error)) => {
+                return Ok(self.reject(tx, Rejection::Invalid(error)))
+            }
+            Err(error) => return Err(error),
+        };
+        if self.policy == FailurePolicy::Evict && !output.result.is_success() {
+            return Ok(self.reject(tx, Rejection::Failed(output.result)));
+        }
+
+        self.evm.db_mut().commit(output.state);
+        let block = &mut self.block;
+        block.gas_used += output.result.gas_used();
+        block.blob_gas_used += blob_gas;
+        block.results.push(ExecutedTx {
+            index: block.transactions.len(),
+            result: output.result,
+            cumulative_gas_used: block.gas_used,
+        });
+        block.transactions.push(tx);
+        Ok(true)
+    }
+
+    /// Adds the candidates in order, see [Self::add].
+    pub fn extend(
+        &mut self,
+        txs: impl IntoIterator<Item = TxEnv>,
+    ) -> Result<(), EVMError<DB::Error>> {
+        for tx in txs {
+            self.add(tx)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the built block and the EVM, whose database holds the state after the block.
+    pub fn finish(self) -> (BuiltBlock, Evm<'a, EXT, DB>) {
+        (self.block, self.evm)
+    }
+
+    fn reject(&mut self, tx: TxEnv, reason: Rejection) -> bool {
+        self.block.rejected.push(RejectedTx { tx, reason });
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Address, Bytecode, Bytes, TransactTo, U256},
+    };
+
+    fn tx(nonce: u64, to: Address, gas_limit: u64) -> TxEnv {
+        TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(to),
+            gas_limit,
+            nonce: Some(nonce),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pack_and_evict() {
+        let reverting = Address::with_last_byte(3);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            Address::with_last_byte(1),
+            AccountInfo::from_balance(U256::from(1_000_000)),
+        );
+        // REVERT(0, 0)
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x5f, 0x5f, 0xfd]));
+        db.insert_account_info(
+            reverting,
+            AccountInfo::new(U256::ZERO, 1, code.hash_slow(), code),
+        );
+        let evm = Evm::builder()
+            .with_db(db)
+            .modify_block_env(|block| block.gas_limit = U256::from(50_000))
+            .build();
+        let mut builder = BlockBuilder::new(evm).with_failure_policy(FailurePolicy::Evict);
+
+        let transfer = Address::with_last_byte(2);
+        builder
+            .extend([
+                tx(0, transfer, 21_000),
+                // Does not fit in the 29_000 gas left.
+                tx(1, transfer, 30_000),
+                tx(1, reverting, 25_000),
+                // Nonce 1 was not used by the evicted transaction.
+                tx(2, transfer, 21_000),
+                tx(1, transfer, 21_000),
+            ])
+            .unwrap();
+
+        let (block, _) = builder.finish();
+        assert_eq!(block.gas_used, 42_000);
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(block.results[1].cumulative_gas_used, 42_000);
+        let reasons: Vec<_> = block
+            .rejected
+            .iter()
+            .map(|rejected| &rejected.reason)
+            .collect();
+        assert!(matches!(
+            reasons[..],
+            [
+                Rejection::GasLimit,
+                Rejection::Failed(ExecutionResult::Revert { .. }),
+                Rejection::Invalid(InvalidTransaction::NonceTooHigh { .. }),
+            ]
+        ));
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     primitives::{
         BlockEnv, CfgEnv, CfgEnvWithHandlerCfg, Env, EnvWithHandlerCfg, HandlerCfg, SpecId, TxEnv,
     },
-    Context, ContextWithHandlerCfg, Evm, Handler,
+    Context, ContextWithHandlerCfg, Evm, Extension, Handler, LabelRegistry,
 };
 use core::marker::PhantomData;
 use std::boxed::Box;
@@ -382,6 +382,18 @@ impl<'a, BuilderStage, EXT, DB: Database> EvmBuilder<'a, BuilderStage, EXT, DB>
         self
     }
 
+    /// Sets the address labels used by inspectors and trace formatters.
+    pub fn with_labels(mut self, labels: LabelRegistry) -> Self {
+        self.context.evm.labels = Some(labels);
+        self
+    }
+
+    /// Inserts `value` in the [extensions](crate::InnerEvmContext::ext) of the context.
+    pub fn with_extension<T: Extension>(mut self, value: T) -> Self {
+        self.context.evm.ext.insert(value);
+        self
+    }
+
     /// Allows modification of Evm's Transaction Environment.
     pub fn modify_tx_env(mut self, f: impl FnOnce(&mut TxEnv)) -> Self {
         f(&mut self.context.evm.env.tx);
@@ -641,4 +653,45 @@ mod test {
 
         evm.transact().unwrap();
     }
+
+    #[test]
+    fn build_with_extension() {
+        #[derive(Clone)]
+        struct CallCount(u64);
+
+        struct CountingPrecompile;
+
+        impl ContextStatefulPrecompile<EmptyDB> for CountingPrecompile {
+            fn call(
+                &self,
+                _input: &Bytes,
+                _gas_price: u64,
+                context: &mut InnerEvmContext<EmptyDB>,
+            ) -> PrecompileResult {
+                context.ext.get_mut::<CallCount>().unwrap().0 += 1;
+                Ok((10, Bytes::new()))
+            }
+        }
+
+        let mut evm = Evm::builder()
+            .with_empty_db()
+            .with_spec_id(SpecId::HOMESTEAD)
+            .with_extension(CallCount(0))
+            .append_handler_register(|handler| {
+                let precompiles = handler.pre_execution.load_precompiles();
+                handler.pre_execution.load_precompiles = Arc::new(move || {
+                    let mut precompiles = precompiles.clone();
+                    precompiles.extend([(
+                        Address::ZERO,
+                        ContextPrecompile::ContextStateful(Arc::new(CountingPrecompile)),
+                    )]);
+                    precompiles
+                });
+            })
+            .build();
+
+        evm.transact().unwrap();
+        evm.transact().unwrap();
+        assert_eq!(evm.context.evm.ext.get::<CallCount>().unwrap().0, 2);
+    }
 }
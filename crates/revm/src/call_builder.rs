@@ -0,0 +1,365 @@
+use crate::{
+    db::{Database, DatabaseCommit},
+    primitives::{
+        Address, Bytes, EVMError, ExecutionResult, HaltReason, ResultAndState, TransactTo, TxEnv,
+        U256,
+    },
+    Evm,
+};
+#[cfg(feature = "alloy")]
+use alloy_sol_types::{SolCall, SolInterface};
+use core::{fmt, mem};
+use std::string::String;
+
+/// Failure of a call made with a [CallBuilder].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError<DBError> {
+    /// The transaction is invalid or execution failed with an error.
+    Evm(EVMError<DBError>),
+    /// The call reverted.
+    Revert {
+        /// Revert data.
+        output: Bytes,
+        /// Gas used by the call.
+        gas_used: u64,
+    },
+    /// The call halted.
+    Halt {
+        /// Halt reason.
+        reason: HaltReason,
+        /// Gas used by the call.
+        gas_used: u64,
+    },
+    /// The output of the call is not a valid encoding of its return type.
+    Decode {
+        /// Output of the call.
+        output: Bytes,
+    },
+}
+
+impl<DBError> CallError<DBError> {
+    /// `Error(string)` selector of Solidity revert reasons.
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+    /// Returns the message of a revert with a Solidity `Error(string)` reason.
+    pub fn revert_reason(&self) -> Option<String> {
+        let Self::Revert { output, .. } = self else {
+            return None;
+        };
+        let data = output.strip_prefix(&Self::ERROR_SELECTOR)?;
+        let word = |offset: usize| -> Option<usize> {
+            let end = offset.checked_add(32)?;
+            U256::from_be_slice(data.get(offset..end)?).try_into().ok()
+        };
+        let offset = word(0)?;
+        let len = word(offset)?;
+        let start = offset.checked_add(32)?;
+        let message = data.get(start..start.checked_add(len)?)?;
+        String::from_utf8(message.to_vec()).ok()
+    }
+
+    /// Decodes the data of a revert as one of the errors of `E`.
+    ///
+    /// `E` is the errors enum generated by `sol!` for a contract or interface, or
+    /// [GenericContractError](alloy_sol_types::GenericContractError) for the `Error(string)` and
+    /// `Panic(uint256)` errors of Solidity.
+    #[cfg(feature = "alloy")]
+    pub fn decode_revert<E: SolInterface>(&self) -> Option<E> {
+        match self {
+            Self::Revert { output, .. } => E::abi_decode(output, true).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<DBError> From<EVMError<DBError>> for CallError<DBError> {
+    fn from(error: EVMError<DBError>) -> Self {
+        Self::Evm(error)
+    }
+}
+
+impl<DBError: fmt::Display> fmt::Display for CallError<DBError> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm(error) => error.fmt(f),
+            Self::Revert { output, .. } => match self.revert_reason() {
+                Some(reason) => write!(f, "call reverted: {reason}"),
+                None => write!(f, "call reverted with {output}"),
+            },
+            Self::Halt { reason, .. } => write!(f, "call halted: {reason:?}"),
+            Self::Decode { output } => write!(f, "invalid call output {output}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DBError: std::error::Error + 'static> std::error::Error for CallError<DBError> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Evm(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// One-shot call of a contract, created with [Evm::call].
+///
+/// The transaction is sent from the zero address unless [Self::from] is set, without value
+/// and input. It uses the gas limit of the block and pays the base fee, so that it is valid
+/// in any block. The nonce of the caller is not checked.
+///
+/// The transaction of the EVM is restored after the call.
+pub struct CallBuilder<'e, 'a, EXT, DB: Database> {
+    evm: &'e mut Evm<'a, EXT, DB>,
+    tx: TxEnv,
+}
+
+impl<EXT, DB: Database> fmt::Debug for CallBuilder<'_, '_, EXT, DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallBuilder")
+            .field("tx", &self.tx)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'e, 'a, EXT, DB: Database> CallBuilder<'e, 'a, EXT, DB> {
+    fn new(evm: &'e mut Evm<'a, EXT, DB>, to: Address) -> Self {
+        let block = evm.block();
+        let tx = TxEnv {
+            transact_to: TransactTo::Call(to),
+            gas_limit: block.gas_limit.saturating_to(),
+            gas_price: block.basefee,
+            ..Default::default()
+        };
+        Self { evm, tx }
+    }
+
+    /// Sets the caller.
+    pub fn from(mut self, caller: Address) -> Self {
+        self.tx.caller = caller;
+        self
+    }
+
+    /// Sets the input of the call.
+    pub fn data(mut self, data: impl Into<Bytes>) -> Self {
+        self.tx.data = data.into();
+        self
+    }
+
+    /// Sets the transferred value.
+    pub fn value(mut self, value: U256) -> Self {
+        self.tx.value = value;
+        self
+    }
+
+    /// Sets the gas limit.
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.tx.gas_limit = gas_limit;
+        self
+    }
+
+    /// Sets the gas price.
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.tx.gas_price = gas_price;
+        self
+    }
+
+    /// Modifies the other fields of the transaction.
+    pub fn modify_tx(mut self, f: impl FnOnce(&mut TxEnv)) -> Self {
+        f(&mut self.tx);
+        self
+    }
+
+    /// Executes the call and returns its result and state, without committing the state.
+    pub fn transact(self) -> Result<ResultAndState, EVMError<DB::Error>> {
+        let previous = mem::replace(self.evm.tx_mut(), self.tx);
+        let result = self.evm.transact();
+        *self.evm.tx_mut() = previous;
+        result
+    }
+
+    /// Executes the call without committing its state and returns its output.
+    pub fn run(self) -> Result<Bytes, CallError<DB::Error>> {
+        output(self.transact()?.result)
+    }
+
+    /// Executes `call` without committing its state and returns its decoded output.
+    ///
+    /// The input set with [Self::data] is replaced by the encoding of `call`, see
+    /// [CallError::decode_revert] to decode the errors of the contract.
+    #[cfg(feature = "alloy")]
+    pub fn run_sol<C: SolCall>(self, call: &C) -> Result<C::Return, CallError<DB::Error>> {
+        decode_returns::<C, _>(self.data(call.abi_encode()).run()?)
+    }
+}
+
+impl<EXT, DB: Database + DatabaseCommit> CallBuilder<'_, '_, EXT, DB> {
+    /// Executes the call, commits its state and returns its output.
+    ///
+    /// The state of a reverted or halted call is committed too, as it charges the caller.
+    pub fn run_commit(self) -> Result<Bytes, CallError<DB::Error>> {
+        let previous = mem::replace(self.evm.tx_mut(), self.tx);
+        let result = self.evm.transact_commit();
+        *self.evm.tx_mut() = previous;
+        output(result?)
+    }
+
+    /// Executes `call`, commits its state and returns its decoded output.
+    ///
+    /// See [Self::run_commit] and [Self::run_sol].
+    #[cfg(feature = "alloy")]
+    pub fn run_sol_commit<C: SolCall>(self, call: &C) -> Result<C::Return, CallError<DB::Error>> {
+        decode_returns::<C, _>(self.data(call.abi_encode()).run_commit()?)
+    }
+}
+
+fn output<DBError>(result: ExecutionResult) -> Result<Bytes, CallError<DBError>> {
+    match result {
+        ExecutionResult::Success { output, .. } => Ok(output.into_data()),
+        ExecutionResult::Revert { output, gas_used } => Err(CallError::Revert { output, gas_used }),
+        ExecutionResult::Halt { reason, gas_used } => Err(CallError::Halt { reason, gas_used }),
+    }
+}
+
+#[cfg(feature = "alloy")]
+fn decode_returns<C: SolCall, DBError>(output: Bytes) -> Result<C::Return, CallError<DBError>> {
+    match C::abi_decode_returns(&output, true) {
+        Ok(returns) => Ok(returns),
+        Err(_) => Err(CallError::Decode { output }),
+    }
+}
+
+impl<'a, EXT, DB: Database> Evm<'a, EXT, DB> {
+    /// Returns a builder of a call to `to`.
+    ///
+    /// See [CallBuilder].
+    pub fn call(&mut self, to: Address) -> CallBuilder<'_, 'a, EXT, DB> {
+        CallBuilder::new(self, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        interpreter::opcode,
+        primitives::{AccountInfo, Bytecode},
+    };
+
+    #[test]
+    fn call_and_revert() {
+        // if CALLVALUE { REVERT(0, CALLDATASIZE) } else { RETURN(0, CALLDATASIZE) } with the
+        // input copied to memory.
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::CALLDATASIZE,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::CALLDATACOPY,
+            opcode::CALLDATASIZE,
+            opcode::PUSH0,
+            opcode::CALLVALUE,
+            opcode::PUSH1,
+            11,
+            opcode::JUMPI,
+            opcode::RETURN,
+            opcode::JUMPDEST,
+            opcode::REVERT,
+        ]));
+        let contract = Address::with_last_byte(0xcc);
+        let caller = Address::with_last_byte(0xca);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+        );
+        db.insert_account_info(caller, AccountInfo::from_balance(U256::from(10)));
+        let mut evm = Evm::builder().with_db(db).build();
+
+        let output = evm.call(contract).data(vec![1, 2, 3]).run().unwrap();
+        assert_eq!(output, Bytes::from_static(&[1, 2, 3]));
+
+        // Error(string) with the message "no".
+        let mut reason = CallError::<()>::ERROR_SELECTOR.to_vec();
+        reason.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+        reason.extend_from_slice(&U256::from(2).to_be_bytes::<32>());
+        reason.extend_from_slice(&[b'n', b'o']);
+        reason.resize(4 + 3 * 32, 0);
+        let error = evm
+            .call(contract)
+            .from(caller)
+            .value(U256::from(1))
+            .data(reason)
+            .run()
+            .unwrap_err();
+        assert_eq!(error.revert_reason().as_deref(), Some("no"));
+
+        // Calls from an account without balance are invalid.
+        let error = evm.call(contract).value(U256::from(1)).run().unwrap_err();
+        assert!(matches!(error, CallError::Evm(EVMError::Transaction(_))));
+        assert_eq!(evm.tx().transact_to, TxEnv::default().transact_to);
+    }
+
+    #[cfg(feature = "alloy")]
+    #[test]
+    fn typed_calls() {
+        use alloy_sol_types::{sol, SolError};
+
+        sol! {
+            interface Echo {
+                function echo(uint256 value) external returns (uint256);
+                error Failed(uint256 code);
+            }
+        }
+
+        // Copies the input to memory, then if CALLVALUE { REVERT(0, CALLDATASIZE) } else
+        // { RETURN(4, 32) }.
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::CALLDATASIZE,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::CALLDATACOPY,
+            opcode::CALLVALUE,
+            opcode::PUSH1,
+            13,
+            opcode::JUMPI,
+            opcode::PUSH1,
+            32,
+            opcode::PUSH1,
+            4,
+            opcode::RETURN,
+            opcode::JUMPDEST,
+            opcode::CALLDATASIZE,
+            opcode::PUSH0,
+            opcode::REVERT,
+        ]));
+        let contract = Address::with_last_byte(0xcc);
+        let caller = Address::with_last_byte(0xca);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+        );
+        db.insert_account_info(caller, AccountInfo::from_balance(U256::from(10)));
+        let mut evm = Evm::builder().with_db(db).build();
+
+        let value = U256::from(7);
+        let returns = evm
+            .call(contract)
+            .run_sol(&Echo::echoCall { value })
+            .unwrap();
+        assert_eq!(returns._0, value);
+
+        let error = evm
+            .call(contract)
+            .from(caller)
+            .value(U256::from(1))
+            .data(Echo::Failed { code: value }.abi_encode())
+            .run()
+            .unwrap_err();
+        assert!(matches!(
+            error.decode_revert::<Echo::EchoErrors>(),
+            Some(Echo::EchoErrors::Failed(failed)) if failed.code == value
+        ));
+    }
+}
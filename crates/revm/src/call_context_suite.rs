@@ -0,0 +1,494 @@
+//! Conformance cases for the execution context of `DELEGATECALL` and `CALLCODE`.
+//!
+//! Chains with custom handlers or instructions can run these cases against their EVM to check
+//! that `msg.sender`, `msg.value`, `address(this)`, storage and balances still behave as on
+//! mainnet in nested delegate calls and call codes. Each [CallContextCase] deploys small
+//! contracts, executes one transaction without committing it and checks the resulting storage
+//! and balances. [run] executes all cases with an EVM built by the caller:
+//!
+//! ```ignore
+//! let failures = call_context_suite::run(|db| {
+//!     Evm::builder().with_db(db).append_handler_register(my_register).build()
+//! });
+//! assert!(failures.is_empty(), "{failures:?}");
+//! ```
+//!
+//! The contracts store the context they observe: `CALLER`, `CALLVALUE` and `ADDRESS` in three
+//! consecutive slots, and one plus the success flag of the calls they make in slot 3, so that
+//! a failed call is distinguishable from a call that was not made.
+
+use crate::{
+    db::{CacheDB, DatabaseRef, EmptyDB},
+    interpreter::opcode,
+    primitives::{
+        address, AccountInfo, Address, Bytecode, Bytes, EVMError, ExecutionResult, TransactTo,
+        TxEnv, U256,
+    },
+    Evm,
+};
+use core::{convert::Infallible, fmt};
+use std::vec::Vec;
+
+/// Sender of the transactions of the cases.
+pub const SENDER: Address = address!("0000000000000000000000000000000000000010");
+
+const A: Address = address!("00000000000000000000000000000000000000a1");
+const B: Address = address!("00000000000000000000000000000000000000b1");
+const C: Address = address!("00000000000000000000000000000000000000c1");
+/// Account without code.
+const EMPTY: Address = address!("00000000000000000000000000000000000000e1");
+/// The identity precompile.
+const IDENTITY: Address = address!("0000000000000000000000000000000000000004");
+
+/// Slot of one plus the success flag of the last call.
+const RESULT_SLOT: u8 = 3;
+
+/// One transaction and the storage and balances expected after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallContextCase {
+    /// Name of the case.
+    pub name: &'static str,
+    /// Accounts of the state before the transaction, in addition to the funded [SENDER].
+    pub accounts: Vec<(Address, AccountInfo)>,
+    /// Transaction of the case.
+    pub tx: TxEnv,
+    /// Expected storage values as `(address, slot, value)`.
+    pub storage: Vec<(Address, U256, U256)>,
+    /// Expected balances.
+    pub balances: Vec<(Address, U256)>,
+}
+
+/// Difference between the expected and the actual outcome of a [CallContextCase].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// Execution failed with an error.
+    Error(EVMError<Infallible>),
+    /// The transaction did not succeed.
+    Failed(ExecutionResult),
+    /// A storage slot differs.
+    Storage {
+        /// Address of the account.
+        address: Address,
+        /// Slot.
+        slot: U256,
+        /// Expected value.
+        expected: U256,
+        /// Actual value.
+        got: U256,
+    },
+    /// A balance differs.
+    Balance {
+        /// Address of the account.
+        address: Address,
+        /// Expected balance.
+        expected: U256,
+        /// Actual balance.
+        got: U256,
+    },
+}
+
+/// Failed [CallContextCase].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseFailure {
+    /// Name of the case.
+    pub case: &'static str,
+    /// First difference found.
+    pub mismatch: Mismatch,
+}
+
+impl fmt::Display for CaseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.case)?;
+        match &self.mismatch {
+            Mismatch::Error(error) => write!(f, "execution error: {error}"),
+            Mismatch::Failed(result) => write!(f, "transaction failed: {result:?}"),
+            Mismatch::Storage {
+                address,
+                slot,
+                expected,
+                got,
+            } => write!(f, "slot {slot} of {address} is {got}, expected {expected}"),
+            Mismatch::Balance {
+                address,
+                expected,
+                got,
+            } => write!(f, "balance of {address} is {got}, expected {expected}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CaseFailure {}
+
+impl CallContextCase {
+    /// Returns the state before the transaction.
+    pub fn db(&self) -> CacheDB<EmptyDB> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            SENDER,
+            AccountInfo::from_balance(U256::from(1_000_000_000_000_000_000u128)),
+        );
+        for (address, info) in &self.accounts {
+            db.insert_account_info(*address, info.clone());
+        }
+        db
+    }
+
+    /// Executes the transaction of the case without committing it and checks its outcome.
+    ///
+    /// The database of `evm` must be [Self::db].
+    pub fn check<EXT>(&self, evm: &mut Evm<'_, EXT, CacheDB<EmptyDB>>) -> Result<(), CaseFailure> {
+        let fail = |mismatch| CaseFailure {
+            case: self.name,
+            mismatch,
+        };
+        *evm.tx_mut() = self.tx.clone();
+        let output = evm
+            .transact()
+            .map_err(|error| fail(Mismatch::Error(error)))?;
+        if !output.result.is_success() {
+            return Err(fail(Mismatch::Failed(output.result)));
+        }
+
+        let db = evm.db();
+        for &(address, slot, expected) in &self.storage {
+            let got = match output
+                .state
+                .get(&address)
+                .and_then(|account| account.storage.get(&slot))
+            {
+                Some(slot) => slot.present_value,
+                None => db.storage_ref(address, slot).unwrap_or_default(),
+            };
+            if got != expected {
+                return Err(fail(Mismatch::Storage {
+                    address,
+                    slot,
+                    expected,
+                    got,
+                }));
+            }
+        }
+        for &(address, expected) in &self.balances {
+            let got = match output.state.get(&address) {
+                Some(account) => account.info.balance,
+                None => db
+                    .basic_ref(address)
+                    .ok()
+                    .flatten()
+                    .map(|info| info.balance)
+                    .unwrap_or_default(),
+            };
+            if got != expected {
+                return Err(fail(Mismatch::Balance {
+                    address,
+                    expected,
+                    got,
+                }));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Executes all [cases] with the EVMs built by `build` on the state of each case, and returns
+/// the failed cases.
+pub fn run<'a, EXT>(
+    mut build: impl FnMut(CacheDB<EmptyDB>) -> Evm<'a, EXT, CacheDB<EmptyDB>>,
+) -> Vec<CaseFailure> {
+    cases()
+        .into_iter()
+        .filter_map(|case| case.check(&mut build(case.db())).err())
+        .collect()
+}
+
+/// Returns the conformance cases.
+pub fn cases() -> Vec<CallContextCase> {
+    let delegate_b = || call(opcode::DELEGATECALL, B, 0);
+    Vec::from([
+        CallContextCase {
+            name: "delegatecall_keeps_sender_value_and_address",
+            accounts: contracts([
+                (A, 0, [delegate_b(), store_context(4)].concat()),
+                (B, 0, store_context(0)),
+            ]),
+            tx: tx(A, 5),
+            storage: [
+                context_slots(A, 0, SENDER, 5, A),
+                context_slots(A, 4, SENDER, 5, A),
+                context_slots(B, 0, Address::ZERO, 0, Address::ZERO),
+                Vec::from([result_slot(A, true)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(5)), (B, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "callcode_runs_in_caller_with_caller_as_sender",
+            accounts: contracts([
+                (A, 10, call(opcode::CALLCODE, B, 3)),
+                (B, 0, store_context(0)),
+            ]),
+            tx: tx(A, 0),
+            storage: [
+                context_slots(A, 0, A, 3, A),
+                Vec::from([result_slot(A, true)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(10)), (B, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "nested_delegatecalls_keep_outer_context",
+            accounts: contracts([
+                (A, 0, delegate_b()),
+                (B, 0, call(opcode::DELEGATECALL, C, 0)),
+                (C, 0, store_context(0)),
+            ]),
+            tx: tx(A, 7),
+            storage: [
+                context_slots(A, 0, SENDER, 7, A),
+                Vec::from([result_slot(A, true)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(7))]),
+        },
+        CallContextCase {
+            name: "call_from_delegatecall_has_delegating_sender",
+            accounts: contracts([
+                (A, 0, delegate_b()),
+                (B, 0, call(opcode::CALL, C, 0)),
+                (C, 0, store_context(0)),
+            ]),
+            tx: tx(A, 7),
+            storage: [
+                context_slots(C, 0, A, 0, C),
+                Vec::from([result_slot(A, true)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(7)), (C, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "delegatecall_from_callcode",
+            accounts: contracts([
+                (A, 10, call(opcode::CALLCODE, B, 4)),
+                (B, 0, call(opcode::DELEGATECALL, C, 0)),
+                (C, 0, store_context(0)),
+            ]),
+            tx: tx(A, 0),
+            storage: [
+                context_slots(A, 0, A, 4, A),
+                Vec::from([result_slot(A, true)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(10)), (B, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "callcode_from_delegatecall",
+            accounts: contracts([
+                (A, 10, delegate_b()),
+                (B, 0, call(opcode::CALLCODE, C, 2)),
+                (C, 0, store_context(0)),
+            ]),
+            tx: tx(A, 0),
+            storage: [
+                context_slots(A, 0, A, 2, A),
+                Vec::from([result_slot(A, true)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(10)), (B, U256::ZERO), (C, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "delegatecall_revert_discards_storage",
+            accounts: contracts([
+                (A, 0, delegate_b()),
+                (
+                    B,
+                    0,
+                    [
+                        store_context(0),
+                        Vec::from([opcode::PUSH1, 0, opcode::PUSH1, 0, opcode::REVERT]),
+                    ]
+                    .concat(),
+                ),
+            ]),
+            tx: tx(A, 5),
+            storage: [
+                context_slots(A, 0, Address::ZERO, 0, Address::ZERO),
+                Vec::from([result_slot(A, false)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::from(5))]),
+        },
+        CallContextCase {
+            name: "delegatecall_in_static_context_cannot_write",
+            accounts: contracts([
+                (A, 0, call(opcode::STATICCALL, B, 0)),
+                (B, 0, call(opcode::DELEGATECALL, C, 0)),
+                (C, 0, store_context(0)),
+            ]),
+            tx: tx(A, 0),
+            storage: [
+                context_slots(B, 0, Address::ZERO, 0, Address::ZERO),
+                Vec::from([result_slot(A, false), (B, slot(RESULT_SLOT), U256::ZERO)]),
+            ]
+            .concat(),
+            balances: Vec::new(),
+        },
+        CallContextCase {
+            name: "callcode_without_balance_fails",
+            accounts: contracts([
+                (A, 0, call(opcode::CALLCODE, B, 1)),
+                (B, 0, store_context(0)),
+            ]),
+            tx: tx(A, 0),
+            storage: [
+                context_slots(A, 0, Address::ZERO, 0, Address::ZERO),
+                Vec::from([result_slot(A, false)]),
+            ]
+            .concat(),
+            balances: Vec::from([(A, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "delegatecall_to_empty_account_succeeds",
+            accounts: contracts([(A, 0, call(opcode::DELEGATECALL, EMPTY, 0))]),
+            tx: tx(A, 5),
+            storage: Vec::from([result_slot(A, true)]),
+            balances: Vec::from([(A, U256::from(5)), (EMPTY, U256::ZERO)]),
+        },
+        CallContextCase {
+            name: "delegatecall_to_precompile_succeeds",
+            accounts: contracts([(A, 0, call(opcode::DELEGATECALL, IDENTITY, 0))]),
+            tx: tx(A, 5),
+            storage: Vec::from([result_slot(A, true)]),
+            balances: Vec::from([(A, U256::from(5)), (IDENTITY, U256::ZERO)]),
+        },
+    ])
+}
+
+/// Returns the code storing `CALLER`, `CALLVALUE` and `ADDRESS` from slot `base` on.
+fn store_context(base: u8) -> Vec<u8> {
+    Vec::from([
+        opcode::CALLER,
+        opcode::PUSH1,
+        base,
+        opcode::SSTORE,
+        opcode::CALLVALUE,
+        opcode::PUSH1,
+        base + 1,
+        opcode::SSTORE,
+        opcode::ADDRESS,
+        opcode::PUSH1,
+        base + 2,
+        opcode::SSTORE,
+    ])
+}
+
+/// Returns the code calling `target` with `call_opcode` and all remaining gas, with `value`
+/// for `CALL` and `CALLCODE`, and storing one plus the success flag in [RESULT_SLOT].
+fn call(call_opcode: u8, target: Address, value: u8) -> Vec<u8> {
+    let mut code = Vec::from([
+        opcode::PUSH1,
+        0,
+        opcode::PUSH1,
+        0,
+        opcode::PUSH1,
+        0,
+        opcode::PUSH1,
+        0,
+    ]);
+    if matches!(call_opcode, opcode::CALL | opcode::CALLCODE) {
+        code.extend([opcode::PUSH1, value]);
+    }
+    code.push(opcode::PUSH20);
+    code.extend_from_slice(target.as_slice());
+    code.extend([
+        opcode::GAS,
+        call_opcode,
+        opcode::PUSH1,
+        1,
+        opcode::ADD,
+        opcode::PUSH1,
+        RESULT_SLOT,
+        opcode::SSTORE,
+    ]);
+    code
+}
+
+fn contract(address: Address, balance: u64, code: Vec<u8>) -> (Address, AccountInfo) {
+    let code = Bytecode::new_raw(Bytes::from([code, Vec::from([opcode::STOP])].concat()));
+    (
+        address,
+        AccountInfo::new(U256::from(balance), 0, code.hash_slow(), code),
+    )
+}
+
+fn contracts<const N: usize>(
+    contracts: [(Address, u64, Vec<u8>); N],
+) -> Vec<(Address, AccountInfo)> {
+    contracts
+        .into_iter()
+        .map(|(address, balance, code)| contract(address, balance, code))
+        .collect()
+}
+
+fn tx(to: Address, value: u64) -> TxEnv {
+    TxEnv {
+        caller: SENDER,
+        transact_to: TransactTo::Call(to),
+        value: U256::from(value),
+        gas_limit: 1_000_000,
+        ..Default::default()
+    }
+}
+
+fn slot(slot: u8) -> U256 {
+    U256::from(slot)
+}
+
+fn word(address: Address) -> U256 {
+    U256::from_be_slice(address.as_slice())
+}
+
+/// Returns the expected context slots of `address` from `base` on.
+fn context_slots(
+    address: Address,
+    base: u8,
+    caller: Address,
+    value: u64,
+    this: Address,
+) -> Vec<(Address, U256, U256)> {
+    Vec::from([
+        (address, slot(base), word(caller)),
+        (address, slot(base + 1), U256::from(value)),
+        (address, slot(base + 2), word(this)),
+    ])
+}
+
+fn result_slot(address: Address, success: bool) -> (Address, U256, U256) {
+    (address, slot(RESULT_SLOT), U256::from(1 + success as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{inspector_handle_register, inspectors::CallContextInspector};
+
+    #[test]
+    fn mainnet_passes() {
+        let failures = run(|db| Evm::builder().with_db(db).build());
+        assert!(failures.is_empty(), "{failures:?}");
+
+        for case in cases() {
+            let mut evm = Evm::builder()
+                .with_db(case.db())
+                .with_external_context(CallContextInspector::default())
+                .append_handler_register(inspector_handle_register)
+                .build();
+            assert_eq!(case.check(&mut evm), Ok(()));
+            assert!(
+                evm.context.external.violations().is_empty(),
+                "{}",
+                case.name
+            );
+        }
+    }
+}
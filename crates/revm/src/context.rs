@@ -1,13 +1,17 @@
 mod context_precompiles;
 pub(crate) mod evm_context;
+mod extensions;
 mod inner_evm_context;
+mod labels;
 
 pub use context_precompiles::{
     ContextPrecompile, ContextPrecompiles, ContextStatefulPrecompile, ContextStatefulPrecompileArc,
     ContextStatefulPrecompileBox, ContextStatefulPrecompileMut,
 };
 pub use evm_context::EvmContext;
+pub use extensions::{Extension, Extensions};
 pub use inner_evm_context::InnerEvmContext;
+pub use labels::LabelRegistry;
 
 use crate::{
     db::{Database, EmptyDB},
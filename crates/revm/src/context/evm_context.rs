@@ -4,7 +4,7 @@ use crate::{
     interpreter::{
         return_ok, CallInputs, Contract, Gas, InstructionResult, Interpreter, InterpreterResult,
     },
-    primitives::{Address, Bytes, EVMError, Env, HashSet, U256},
+    primitives::{Address, Bytes, EVMError, Env, HashSet, PrecompileCall, SpecId, EOF_MAGIC, U256},
     ContextPrecompiles, FrameOrResult, CALL_STACK_LIMIT,
 };
 use core::{
@@ -110,11 +110,7 @@ impl<DB: Database> EvmContext<DB> {
             .precompiles
             .call(address, input_data, gas.limit(), &mut self.inner)?;
 
-        let mut result = InterpreterResult {
-            result: InstructionResult::Return,
-            gas,
-            output: Bytes::new(),
-        };
+        let mut result = InterpreterResult::new(InstructionResult::Return, Bytes::new(), gas);
 
         match out {
             Ok((gas_used, data)) => {
@@ -133,6 +129,23 @@ impl<DB: Database> EvmContext<DB> {
                 };
             }
         }
+
+        if let Some(calls) = &mut self.inner.precompile_calls {
+            let success = result.is_ok();
+            calls.push(PrecompileCall {
+                address,
+                depth: self.inner.journaled_state.depth() as usize,
+                input: input_data.clone(),
+                gas_limit: gas.limit(),
+                gas_used: if success {
+                    result.gas.spent()
+                } else {
+                    gas.limit()
+                },
+                output: result.output.clone(),
+                success,
+            });
+        }
         Some(result)
     }
 
@@ -146,11 +159,7 @@ impl<DB: Database> EvmContext<DB> {
 
         let return_result = |instruction_result: InstructionResult| {
             Ok(FrameOrResult::new_call_result(
-                InterpreterResult {
-                    result: instruction_result,
-                    gas,
-                    output: Bytes::new(),
-                },
+                InterpreterResult::new(instruction_result, Bytes::new(), gas),
                 inputs.return_memory_offset.clone(),
             ))
         };
@@ -198,12 +207,28 @@ impl<DB: Database> EvmContext<DB> {
                 inputs.return_memory_offset.clone(),
             ))
         } else if !bytecode.is_empty() {
-            let contract = Contract::new_with_context(
+            let mut contract = Contract::new_with_context(
                 inputs.input.clone(),
                 bytecode,
                 code_hash,
                 &inputs.context,
             );
+            if self.spec_id().is_enabled_in(SpecId::OSAKA)
+                && contract
+                    .bytecode
+                    .original_bytecode_slice()
+                    .starts_with(&EOF_MAGIC)
+            {
+                // Containers are validated once per code hash, invalid ones run as legacy code.
+                contract.eof = self
+                    .inner
+                    .eof_cache
+                    .entry(code_hash)
+                    .or_insert_with(|| {
+                        Contract::validated_eof(contract.bytecode.original_bytecode())
+                    })
+                    .clone();
+            }
             // Create interpreter and executes call and push new CallStackFrame.
             Ok(FrameOrResult::new_call_frame(
                 inputs.return_memory_offset.clone(),
@@ -222,10 +247,13 @@ impl<DB: Database> EvmContext<DB> {
 pub(crate) mod test_utils {
     use super::*;
     use crate::{
-        db::{CacheDB, EmptyDB},
+        builder::SetGenericStage,
+        db::{BenchmarkDB, CacheDB, EmptyDB},
         journaled_state::JournaledState,
-        primitives::{address, Address, Bytes, Env, HashSet, SpecId, B256, U256},
-        InnerEvmContext,
+        primitives::{
+            address, Address, Bytecode, Bytes, Env, HashSet, SpecId, TransactTo, B256, U256,
+        },
+        Evm, EvmBuilder, InnerEvmContext,
     };
     use std::boxed::Box;
 
@@ -286,6 +314,12 @@ pub(crate) mod test_utils {
                 journaled_state: JournaledState::new(SpecId::CANCUN, HashSet::new()),
                 db,
                 error: Ok(()),
+                labels: None,
+                ext: Default::default(),
+                precompile_calls: None,
+                eof_cache: Default::default(),
+                #[cfg(feature = "instrumentation")]
+                counters: Default::default(),
                 #[cfg(feature = "optimism")]
                 l1_block_info: None,
             },
@@ -301,12 +335,32 @@ pub(crate) mod test_utils {
                 journaled_state: JournaledState::new(SpecId::CANCUN, HashSet::new()),
                 db,
                 error: Ok(()),
+                labels: None,
+                ext: Default::default(),
+                precompile_calls: None,
+                eof_cache: Default::default(),
+                #[cfg(feature = "instrumentation")]
+                counters: Default::default(),
                 #[cfg(feature = "optimism")]
                 l1_block_info: None,
             },
             precompiles: ContextPrecompiles::default(),
         }
     }
+
+    /// Creates an evm builder with a [`BenchmarkDB`] of the provided bytecode, and a transaction
+    /// that calls it from `Address::with_last_byte(1)` with a gas limit of 100_000.
+    pub fn benchmark_evm_builder<'a>(
+        bytecode: Bytecode,
+    ) -> EvmBuilder<'a, SetGenericStage, (), BenchmarkDB> {
+        Evm::builder()
+            .with_db(BenchmarkDB::new_bytecode(bytecode))
+            .modify_tx_env(|tx| {
+                tx.caller = Address::with_last_byte(1);
+                tx.transact_to = TransactTo::Call(Address::ZERO);
+                tx.gas_limit = 100_000;
+            })
+    }
 }
 
 #[cfg(test)]
@@ -405,4 +459,233 @@ mod tests {
         };
         assert_eq!(call_frame.return_memory_range, 0..0,);
     }
+
+    // Tests that code starting with the EOF magic that is not a valid container does not run
+    // as EOF and that the validation is cached.
+    #[test]
+    fn test_make_call_frame_invalid_eof() {
+        use crate::primitives::hex_literal::hex;
+
+        // RJUMP 5, past the end of the code section.
+        let by = Bytecode::new_raw(
+            hex!("ef000101000402000100030400000000800000e00005")
+                .to_vec()
+                .into(),
+        );
+        let code_hash = by.hash_slow();
+        let contract = address!("dead10000000000000000000000000000001dead");
+        let mut cdb = CacheDB::new(EmptyDB::default());
+        cdb.insert_account_info(
+            contract,
+            crate::primitives::AccountInfo {
+                code_hash,
+                code: Some(by),
+                ..Default::default()
+            },
+        );
+        let mut evm_context = create_cache_db_evm_context(Box::new(Env::default()), cdb);
+        evm_context.journaled_state.set_spec_id(SpecId::OSAKA);
+        let call_inputs = test_utils::create_mock_call_inputs(contract);
+        let Ok(FrameOrResult::Frame(Frame::Call(call_frame))) =
+            evm_context.make_call_frame(&call_inputs)
+        else {
+            panic!("Expected FrameOrResult::Frame(Frame::Call(..))");
+        };
+        assert!(!call_frame.frame_data.interpreter.is_eof());
+        assert_eq!(evm_context.eof_cache.get(&code_hash), Some(&None));
+    }
+
+    // Tests that an EOF creation transaction deploys the container returned by
+    // `RETURNCONTRACT` and that the deployed code runs as EOF.
+    #[test]
+    fn test_eof_create_transaction() {
+        use crate::{
+            primitives::{hex_literal::hex, ExecutionResult, Output, SpecId, TransactTo},
+            Evm,
+        };
+
+        // CALLF 1 PUSH0 SSTORE STOP, section 1: PUSH1 0x2a RETF
+        let runtime = hex!("ef000101000802000200060003040000000080000200010001e300015f5500602ae4");
+        // PUSH0 PUSH0 RETURNCONTRACT 0
+        let initcode = [
+            &hex!("ef00010100040200010004030001002204000000008000025f5fee00")[..],
+            &runtime[..],
+        ]
+        .concat();
+
+        let mut evm = Evm::builder()
+            .with_db(CacheDB::new(EmptyDB::default()))
+            .with_spec_id(SpecId::OSAKA)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::create();
+                tx.data = initcode.into();
+                tx.gas_limit = 100_000;
+            })
+            .build();
+        let ExecutionResult::Success {
+            output: Output::Create(code, Some(address)),
+            ..
+        } = evm.transact_commit().unwrap()
+        else {
+            panic!("Expected a successful creation");
+        };
+        assert_eq!(code[..], runtime);
+
+        evm.tx_mut().transact_to = TransactTo::Call(address);
+        evm.tx_mut().data = Bytes::new();
+        evm.tx_mut().nonce = None;
+        let state = evm.transact().unwrap().state;
+        assert_eq!(
+            state[&address].storage[&U256::ZERO].present_value,
+            U256::from(0x2a)
+        );
+    }
+
+    /// Account with the given code.
+    fn account(code: &[u8]) -> crate::primitives::AccountInfo {
+        let code = Bytecode::new_raw(Bytes::copy_from_slice(code));
+        crate::primitives::AccountInfo {
+            code_hash: code.hash_slow(),
+            code: Some(code),
+            ..Default::default()
+        }
+    }
+
+    // Tests that legacy code sees EOF code as the EOF magic.
+    #[test]
+    fn test_eof_code_seen_from_legacy() {
+        use crate::{
+            primitives::{hex_literal::hex, SpecId, TransactTo, EOF_MAGIC_HASH},
+            Evm,
+        };
+
+        let eof = Address::with_last_byte(0xbb);
+        let legacy = Address::with_last_byte(0xaa);
+        let mut cdb = CacheDB::new(EmptyDB::default());
+        // CALLF 1 PUSH0 SSTORE STOP, section 1: PUSH1 0x2a RETF
+        cdb.insert_account_info(
+            eof,
+            account(&hex!(
+                "ef000101000802000200060003040000000080000200010001e300015f5500602ae4"
+            )),
+        );
+        // SSTORE(0, EXTCODESIZE(eof)) SSTORE(1, EXTCODEHASH(eof))
+        // EXTCODECOPY(eof, 0, 0, 32) SSTORE(2, MLOAD(0))
+        cdb.insert_account_info(
+            legacy,
+            account(&hex!("60bb3b5f5560bb3f60015560205f5f60bb3c5f5160025500")),
+        );
+
+        let mut evm = Evm::builder()
+            .with_db(cdb)
+            .with_spec_id(SpecId::OSAKA)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::Call(legacy);
+                tx.gas_limit = 200_000;
+            })
+            .build();
+        let state = evm.transact().unwrap().state;
+        let storage = &state[&legacy].storage;
+        assert_eq!(storage[&U256::ZERO].present_value, U256::from(2));
+        assert_eq!(
+            storage[&U256::from(1)].present_value,
+            U256::from_be_bytes(EOF_MAGIC_HASH.0)
+        );
+        assert_eq!(
+            storage[&U256::from(2)].present_value,
+            U256::from(0xEF00) << 240
+        );
+    }
+
+    // Tests the status and return data of EOF calls to legacy code.
+    #[test]
+    fn test_eof_calls() {
+        use crate::{
+            primitives::{hex_literal::hex, SpecId, TransactTo},
+            Evm,
+        };
+
+        let caller = Address::with_last_byte(0xbb);
+        let mut cdb = CacheDB::new(EmptyDB::default());
+        // SSTORE(0, EXTCALL(0xcc, 0, 0, 0)) SSTORE(1, RETURNDATALOAD(0))
+        // SSTORE(2, EXTSTATICCALL(0xcc, 0, 0)) SSTORE(3, EXTDELEGATECALL(0xcc, 0, 0)) STOP
+        cdb.insert_account_info(
+            caller,
+            account(&hex!(
+                "ef0001010004020001001e0400000000800004"
+                "5f5f5f60ccf85f555ff76001555f5f60ccfb6002555f5f60ccf960035500"
+            )),
+        );
+        // MSTORE(0, 0x2a) RETURN(0, 32)
+        cdb.insert_account_info(
+            Address::with_last_byte(0xcc),
+            account(&hex!("602a5f5260205ff3")),
+        );
+
+        let mut evm = Evm::builder()
+            .with_db(cdb)
+            .with_spec_id(SpecId::OSAKA)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::Call(caller);
+                tx.gas_limit = 1_000_000;
+            })
+            .build();
+        let result = evm.transact().unwrap();
+        assert!(result.result.is_success());
+        let storage = &result.state[&caller].storage;
+        let slot = |index: u64| storage[&U256::from(index)].present_value;
+        // Calls push 0 on success.
+        assert_eq!(slot(0), U256::ZERO);
+        assert_eq!(slot(1), U256::from(0x2a));
+        assert_eq!(slot(2), U256::ZERO);
+        // Delegate calls to legacy code fail without executing it.
+        assert_eq!(slot(3), U256::from(1));
+    }
+
+    // Tests that `EOFCREATE` deploys the container returned by the initcode.
+    #[test]
+    fn test_eofcreate() {
+        use crate::{
+            primitives::{hex_literal::hex, SpecId, TransactTo, B256},
+            Evm,
+        };
+
+        // STOP
+        let runtime = hex!("ef00010100040200010001040000000080000000");
+        // PUSH0 PUSH0 RETURNCONTRACT 0
+        let initcode = [
+            &hex!("ef00010100040200010004030001001404000000008000025f5fee00")[..],
+            &runtime,
+        ]
+        .concat();
+        // SSTORE(0, EOFCREATE 0 (0, 0, 0, 0)) STOP
+        let factory_code = [
+            &hex!("ef00010100040200010009030001003004000000008000045f5f5f5fec005f5500")[..],
+            &initcode,
+        ]
+        .concat();
+        let factory = Address::with_last_byte(0xbb);
+        let mut cdb = CacheDB::new(EmptyDB::default());
+        cdb.insert_account_info(factory, account(&factory_code));
+
+        let mut evm = Evm::builder()
+            .with_db(cdb)
+            .with_spec_id(SpecId::OSAKA)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::Call(factory);
+                tx.gas_limit = 1_000_000;
+            })
+            .build();
+        let result = evm.transact().unwrap();
+        assert!(result.result.is_success());
+
+        let created = factory.create2_from_code(B256::ZERO, &initcode);
+        assert_eq!(
+            result.state[&factory].storage[&U256::ZERO].present_value,
+            U256::from_be_bytes(created.into_word().0)
+        );
+        let code = result.state[&created].info.code.as_ref().unwrap();
+        assert_eq!(code.original_bytes()[..], runtime);
+        assert_eq!(result.state[&factory].info.nonce, 1);
+    }
 }
@@ -0,0 +1,162 @@
+use crate::primitives::HashMap;
+use core::{
+    any::{Any, TypeId},
+    fmt,
+};
+use dyn_clone::DynClone;
+use std::boxed::Box;
+
+/// Value that can be stored in [Extensions].
+///
+/// Implemented for all cloneable, thread-safe types.
+pub trait Extension: Any + DynClone + Send + Sync {
+    /// Returns the value as [Any] for downcasting.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the value as mutable [Any] for downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Converts the boxed value to a boxed [Any] for downcasting.
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Any + Clone + Send + Sync> Extension for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+dyn_clone::clone_trait_object!(Extension);
+
+/// User data carried through execution, holding at most one value per type.
+///
+/// Set in [InnerEvmContext::ext](crate::InnerEvmContext::ext), where handlers, custom
+/// instructions and context stateful precompiles can all reach it, unlike the external context
+/// of [Context](crate::Context) that precompiles have no access to. Chain extensions use it for
+/// state such as an oracle cache. Values are kept between transactions.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    values: HashMap<TypeId, Box<dyn Extension>>,
+}
+
+impl Extensions {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `value`, returning the previous value of its type.
+    pub fn insert<T: Extension>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(downcast)
+    }
+
+    /// Inserts `value`.
+    pub fn with<T: Extension>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+
+    /// Returns the value of type `T`.
+    pub fn get<T: Extension>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// Returns the value of type `T` mutably.
+    pub fn get_mut<T: Extension>(&mut self) -> Option<&mut T> {
+        self.values
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// Returns the value of type `T`, inserting the result of `f` if there is none.
+    pub fn get_or_insert_with<T: Extension>(&mut self, f: impl FnOnce() -> T) -> &mut T {
+        (**self
+            .values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(f())))
+        .as_any_mut()
+        .downcast_mut()
+        .expect("values are stored under their type ID")
+    }
+
+    /// Removes and returns the value of type `T`.
+    pub fn remove<T: Extension>(&mut self) -> Option<T> {
+        self.values.remove(&TypeId::of::<T>()).map(downcast)
+    }
+
+    /// Returns `true` if there is a value of type `T`.
+    pub fn contains<T: Extension>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Removes all values.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.values.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Unboxes a value stored under the type ID of `T`.
+fn downcast<T: Extension>(value: Box<dyn Extension>) -> T {
+    *value
+        .into_any()
+        .downcast()
+        .expect("values are stored under their type ID")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Oracle {
+        price: u64,
+    }
+
+    #[test]
+    fn values_by_type() {
+        let mut ext = Extensions::new().with(Oracle { price: 1 }).with(7u32);
+        assert_eq!(ext.len(), 2);
+        assert_eq!(ext.get::<u32>(), Some(&7));
+        assert_eq!(ext.get::<u64>(), None);
+
+        ext.get_mut::<Oracle>().unwrap().price = 2;
+        assert_eq!(ext.insert(Oracle { price: 3 }), Some(Oracle { price: 2 }));
+        assert_eq!(ext.get_or_insert_with(Oracle::default).price, 3);
+        assert_eq!(*ext.get_or_insert_with(|| 5u64), 5);
+
+        let copy = ext.clone();
+        assert_eq!(ext.remove::<Oracle>(), Some(Oracle { price: 3 }));
+        assert!(!ext.contains::<Oracle>());
+        assert_eq!(copy.get::<Oracle>(), Some(&Oracle { price: 3 }));
+    }
+}
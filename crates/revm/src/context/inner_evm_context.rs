@@ -1,20 +1,21 @@
+use super::{Extensions, LabelRegistry};
 use crate::{
     db::Database,
     interpreter::{
-        analysis::to_analysed, gas, return_ok, Contract, CreateInputs, Gas, InstructionResult,
-        Interpreter, InterpreterResult, MAX_CODE_SIZE,
+        analysis::to_analysed, gas, return_ok, validate_eof, ContainerKind, Contract, CreateInputs,
+        Gas, InstructionResult, Interpreter, InterpreterResult, MAX_CODE_SIZE,
     },
     journaled_state::JournaledState,
     primitives::{
-        keccak256, Account, Address, AnalysisKind, Bytecode, Bytes, CreateScheme, EVMError, Env,
-        HashSet, Spec,
+        create2_address, create_address, Account, Address, AnalysisKind, Bytecode, Bytes,
+        CreateScheme, DatabaseAccess, EVMError, Env, Eof, HashMap, HashSet, PrecompileCall, Spec,
         SpecId::{self, *},
-        B256, U256,
+        B256, EOF_MAGIC, U256,
     },
     FrameOrResult, JournalCheckpoint, CALL_STACK_LIMIT,
 };
 use revm_interpreter::{SStoreResult, SelfDestructResult};
-use std::boxed::Box;
+use std::{boxed::Box, sync::Arc, vec::Vec};
 
 /// EVM contexts contains data that EVM needs for execution.
 #[derive(Debug)]
@@ -28,6 +29,17 @@ pub struct InnerEvmContext<DB: Database> {
     pub db: DB,
     /// Error that happened during execution.
     pub error: Result<(), EVMError<DB::Error>>,
+    /// Names of addresses shown by inspectors and trace formatters.
+    pub labels: Option<LabelRegistry>,
+    /// User data of chain extensions, see [Extensions].
+    pub ext: Extensions,
+    /// Precompile calls of the current transaction, recorded when set to `Some`.
+    pub precompile_calls: Option<Vec<PrecompileCall>>,
+    /// Validated EOF containers by code hash, `None` for code that is not a valid container.
+    pub eof_cache: HashMap<B256, Option<Arc<Eof>>>,
+    /// Instruction counters of the current transaction.
+    #[cfg(feature = "instrumentation")]
+    pub counters: crate::primitives::ExecutionCounters,
     /// Used as temporary value holder to store L1 block info.
     #[cfg(feature = "optimism")]
     pub l1_block_info: Option<crate::optimism::L1BlockInfo>,
@@ -43,6 +55,12 @@ where
             journaled_state: self.journaled_state.clone(),
             db: self.db.clone(),
             error: self.error.clone(),
+            labels: self.labels.clone(),
+            ext: self.ext.clone(),
+            precompile_calls: self.precompile_calls.clone(),
+            eof_cache: self.eof_cache.clone(),
+            #[cfg(feature = "instrumentation")]
+            counters: self.counters,
             #[cfg(feature = "optimism")]
             l1_block_info: self.l1_block_info.clone(),
         }
@@ -56,6 +74,12 @@ impl<DB: Database> InnerEvmContext<DB> {
             journaled_state: JournaledState::new(SpecId::LATEST, HashSet::new()),
             db,
             error: Ok(()),
+            labels: None,
+            ext: Extensions::default(),
+            precompile_calls: None,
+            eof_cache: HashMap::new(),
+            #[cfg(feature = "instrumentation")]
+            counters: Default::default(),
             #[cfg(feature = "optimism")]
             l1_block_info: None,
         }
@@ -69,6 +93,12 @@ impl<DB: Database> InnerEvmContext<DB> {
             journaled_state: JournaledState::new(SpecId::LATEST, HashSet::new()),
             db,
             error: Ok(()),
+            labels: None,
+            ext: Extensions::default(),
+            precompile_calls: None,
+            eof_cache: HashMap::new(),
+            #[cfg(feature = "instrumentation")]
+            counters: Default::default(),
             #[cfg(feature = "optimism")]
             l1_block_info: None,
         }
@@ -84,6 +114,12 @@ impl<DB: Database> InnerEvmContext<DB> {
             journaled_state: self.journaled_state,
             db,
             error: Ok(()),
+            labels: self.labels,
+            ext: self.ext,
+            precompile_calls: self.precompile_calls,
+            eof_cache: self.eof_cache,
+            #[cfg(feature = "instrumentation")]
+            counters: self.counters,
             #[cfg(feature = "optimism")]
             l1_block_info: self.l1_block_info,
         }
@@ -121,7 +157,9 @@ impl<DB: Database> InnerEvmContext<DB> {
     /// Fetch block hash from database.
     #[inline]
     pub fn block_hash(&mut self, number: U256) -> Result<B256, EVMError<DB::Error>> {
-        self.db.block_hash(number).map_err(EVMError::Database)
+        self.db
+            .block_hash(number)
+            .map_err(|e| EVMError::database_at(e, DatabaseAccess::BlockHash(number)))
     }
 
     /// Mark account as touched as only touched accounts will be added to state.
@@ -235,11 +273,7 @@ impl<DB: Database> InnerEvmContext<DB> {
 
         let return_error = |e| {
             Ok(FrameOrResult::new_create_result(
-                InterpreterResult {
-                    result: e,
-                    gas,
-                    output: Bytes::new(),
-                },
+                InterpreterResult::new(e, Bytes::new(), gas),
                 None,
             ))
         };
@@ -268,13 +302,42 @@ impl<DB: Database> InnerEvmContext<DB> {
         // Create address
         let mut init_code_hash = B256::ZERO;
         let created_address = match inputs.scheme {
-            CreateScheme::Create => inputs.caller.create(old_nonce),
-            CreateScheme::Create2 { salt } => {
-                init_code_hash = keccak256(&inputs.init_code);
-                inputs.caller.create2(salt.to_be_bytes(), init_code_hash)
+            CreateScheme::Create => create_address(inputs.caller, old_nonce),
+            CreateScheme::Create2 { salt } | CreateScheme::EofCreate { salt } => {
+                init_code_hash = crate::primitives::init_code_hash(&inputs.init_code);
+                create2_address(inputs.caller, salt, init_code_hash)
             }
         };
 
+        // EIP-7620: EOF init code.
+        let mut init_code = inputs.init_code.clone();
+        let mut input = inputs.input.clone();
+        let mut eof = None;
+        if spec_id.is_enabled_in(OSAKA) {
+            if let CreateScheme::EofCreate { .. } = inputs.scheme {
+                // Validated together with the container of the creating code.
+                eof = Eof::decode(init_code.clone()).ok().map(Arc::new);
+            } else if init_code.starts_with(&EOF_MAGIC) {
+                // Only creation transactions can deploy EOF with the legacy schemes, the
+                // init container is followed by its call data.
+                if self.journaled_state.depth() > 0 {
+                    return return_error(InstructionResult::CreateInitCodeStartingEF00);
+                }
+                let Some((container, data)) =
+                    Eof::decode_dangling(init_code.clone())
+                        .ok()
+                        .filter(|(container, _)| {
+                            validate_eof(container, ContainerKind::Initcode).is_ok()
+                        })
+                else {
+                    return return_error(InstructionResult::InvalidEOFInitCode);
+                };
+                init_code = container.raw().clone();
+                input = data;
+                eof = Some(Arc::new(container));
+            }
+        }
+
         // Load account so it needs to be marked as warm for access list.
         self.journaled_state
             .load_account(created_address, &mut self.db)?;
@@ -292,16 +355,17 @@ impl<DB: Database> InnerEvmContext<DB> {
             }
         };
 
-        let bytecode = Bytecode::new_raw(inputs.init_code.clone());
+        let bytecode = Bytecode::new_raw(init_code);
 
-        let contract = Contract::new(
-            Bytes::new(),
+        let mut contract = Contract::new(
+            input,
             bytecode,
             init_code_hash,
             created_address,
             inputs.caller,
             inputs.value,
         );
+        contract.eof = eof;
 
         Ok(FrameOrResult::new_create_frame(
             created_address,
@@ -342,7 +406,9 @@ impl<DB: Database> InnerEvmContext<DB> {
         // if ok, check contract creation limit and calculate gas deduction on output len.
         //
         // EIP-3541: Reject new contract code starting with the 0xEF byte
+        // Containers returned by `RETURNCONTRACT` were validated with the init code.
         if SPEC::enabled(LONDON)
+            && interpreter_result.result != InstructionResult::ReturnContract
             && !interpreter_result.output.is_empty()
             && interpreter_result.output.first() == Some(&0xEF)
         {
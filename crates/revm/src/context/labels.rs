@@ -0,0 +1,61 @@
+use crate::primitives::{Address, HashMap};
+use std::string::{String, ToString};
+
+/// Human-readable names of addresses, such as `WETH` for the WETH contract.
+///
+/// Set in [InnerEvmContext::labels](crate::InnerEvmContext::labels), where inspectors can read
+/// it, and used by trace formatters to show names instead of raw addresses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelRegistry {
+    labels: HashMap<Address, String>,
+}
+
+impl LabelRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Labels `address` with `label`, returning its previous label.
+    pub fn insert(&mut self, address: Address, label: impl Into<String>) -> Option<String> {
+        self.labels.insert(address, label.into())
+    }
+
+    /// Labels `address` with `label`.
+    pub fn with_label(mut self, address: Address, label: impl Into<String>) -> Self {
+        self.insert(address, label);
+        self
+    }
+
+    /// Returns the label of `address`.
+    pub fn get(&self, address: &Address) -> Option<&str> {
+        self.labels.get(address).map(String::as_str)
+    }
+
+    /// Returns the label of `address`, or the checksummed address if it has none.
+    pub fn label(&self, address: Address) -> String {
+        self.get(&address)
+            .map_or_else(|| address.to_string(), String::from)
+    }
+
+    /// Returns the number of labeled addresses.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns `true` if no address is labeled.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+impl<L: Into<String>> FromIterator<(Address, L)> for LabelRegistry {
+    fn from_iter<I: IntoIterator<Item = (Address, L)>>(iter: I) -> Self {
+        Self {
+            labels: iter
+                .into_iter()
+                .map(|(address, label)| (address, label.into()))
+                .collect(),
+        }
+    }
+}
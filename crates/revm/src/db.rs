@@ -2,19 +2,47 @@
 
 #[cfg(feature = "alloydb")]
 pub mod alloydb;
+pub mod batching;
 pub mod emptydb;
 #[cfg(feature = "ethersdb")]
 pub mod ethersdb;
+pub mod generator;
+pub mod genesis;
 pub mod in_memory_db;
+pub mod memory;
+pub mod prefetch;
+pub mod recording;
+#[cfg(feature = "std")]
+pub mod retry;
+#[cfg(feature = "std")]
+pub mod shared;
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod speculative;
+pub mod state_commit;
 pub mod states;
 
 pub use crate::primitives::db::*;
 #[cfg(feature = "alloydb")]
 pub use alloydb::AlloyDB;
+pub use batching::{BatchFetcher, BatchingDB, FetchedAccount, StateBatch};
 pub use emptydb::{EmptyDB, EmptyDBTyped};
 #[cfg(feature = "ethersdb")]
 pub use ethersdb::EthersDB;
+pub use generator::{GeneratedAccounts, StateGenerator};
+pub use genesis::{ChainConfig, Genesis};
 pub use in_memory_db::*;
+pub use memory::MemoryUsage;
+pub use prefetch::PrefetchHints;
+pub use recording::{ReadSet, ReadWriteSet, RecordingDatabase, WriteSet};
+#[cfg(feature = "std")]
+pub use retry::{ErrorClass, RetryError, RetryPolicy, RetryingDatabase};
+#[cfg(feature = "std")]
+pub use shared::{SharedCacheDB, SyncDatabase};
+pub use snapshot::{GenesisAccount, SnapshotDecodeError, StateSnapshot};
+#[cfg(feature = "std")]
+pub use speculative::SpeculativeDB;
+pub use state_commit::{AccountCommit, StateCommit, StateCommitDatabase};
 pub use states::{
     AccountRevert, AccountStatus, BundleAccount, BundleState, CacheState, DBBox,
     OriginalValuesKnown, PlainAccount, RevertToSlot, State, StateBuilder, StateDBBox,
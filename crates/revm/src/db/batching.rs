@@ -0,0 +1,243 @@
+//! Fork database that batches state reads into as few round trips as possible.
+//!
+//! Reading state of a remote node one account or slot at a time costs a round trip per read.
+//! [BatchingDB] keeps a queue of reads that are expected to happen soon and, when execution
+//! misses the cache, sends the missing read together with every queued read as one
+//! [StateBatch] to a [BatchFetcher].
+//!
+//! A batch maps directly to one `eth_getProof` call per account sent as a single JSON-RPC
+//! batch, or to one call of a multicall contract.
+use super::{prefetch::PrefetchHints, Database};
+use crate::primitives::{AccountInfo, Address, Bytecode, HashMap, HashSet, B256, U256};
+use std::vec::Vec;
+
+/// Default maximum number of accounts in a batch.
+pub const DEFAULT_MAX_BATCH_ACCOUNTS: usize = 64;
+
+/// Accounts and their storage slots requested in one round trip.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateBatch {
+    /// Requested accounts with the slots to read from them.
+    pub accounts: Vec<(Address, Vec<U256>)>,
+}
+
+/// State of one account returned for a [StateBatch].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FetchedAccount {
+    /// Account info, `None` if the account does not exist.
+    pub info: Option<AccountInfo>,
+    /// Values of the requested slots, in the order of the request.
+    pub storage: Vec<U256>,
+}
+
+/// Backend that answers a [StateBatch] in a single round trip.
+pub trait BatchFetcher {
+    /// The fetcher error type.
+    type Error;
+
+    /// Fetches all accounts and slots of the batch. The response contains one entry per
+    /// requested account, in the order of the request.
+    fn fetch_batch(&mut self, batch: &StateBatch) -> Result<Vec<FetchedAccount>, Self::Error>;
+
+    /// Fetches the code with the given hash.
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error>;
+
+    /// Fetches the hash of the block with the given number.
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error>;
+}
+
+/// Fork database that batches queued and missing reads, see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct BatchingDB<F> {
+    fetcher: F,
+    max_batch_accounts: usize,
+    accounts: HashMap<Address, Option<AccountInfo>>,
+    storage: HashMap<(Address, U256), U256>,
+    queue: HashMap<Address, HashSet<U256>>,
+    round_trips: u64,
+}
+
+impl<F: BatchFetcher> BatchingDB<F> {
+    /// Creates a database on top of the given fetcher.
+    pub fn new(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            max_batch_accounts: DEFAULT_MAX_BATCH_ACCOUNTS,
+            accounts: HashMap::new(),
+            storage: HashMap::new(),
+            queue: HashMap::new(),
+            round_trips: 0,
+        }
+    }
+
+    /// Sets the maximum number of accounts sent in one batch. Queued reads that do not fit are
+    /// sent with a later miss.
+    pub fn with_max_batch_accounts(mut self, max_batch_accounts: usize) -> Self {
+        self.max_batch_accounts = max_batch_accounts.max(1);
+        self
+    }
+
+    /// Returns the fetcher.
+    pub fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    /// Returns the number of batches sent so far.
+    pub fn round_trips(&self) -> u64 {
+        self.round_trips
+    }
+
+    /// Queues an account to be read with the next batch.
+    pub fn queue_account(&mut self, address: Address) {
+        if !self.accounts.contains_key(&address) {
+            self.queue.entry(address).or_default();
+        }
+    }
+
+    /// Queues a storage slot to be read with the next batch.
+    pub fn queue_storage(&mut self, address: Address, index: U256) {
+        if !self.storage.contains_key(&(address, index)) {
+            self.queue.entry(address).or_default().insert(index);
+        }
+    }
+
+    /// Queues all accounts and slots of the hints.
+    pub fn queue_hints(&mut self, hints: &PrefetchHints) {
+        for (address, slots) in hints.iter() {
+            self.queue_account(*address);
+            for slot in slots {
+                self.queue_storage(*address, *slot);
+            }
+        }
+    }
+
+    /// Sends all queued reads, without waiting for a miss.
+    pub fn flush(&mut self) -> Result<(), F::Error> {
+        while !self.queue.is_empty() {
+            self.fetch(None)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a batch with the `missing` read, if any, and as many queued reads as fit.
+    fn fetch(&mut self, missing: Option<(Address, Option<U256>)>) -> Result<(), F::Error> {
+        let mut batch = StateBatch::default();
+        if let Some((address, index)) = missing {
+            let mut slots = self.queue.remove(&address).unwrap_or_default();
+            slots.extend(index);
+            batch.accounts.push((address, slots.into_iter().collect()));
+        }
+        let queued: Vec<_> = self
+            .queue
+            .keys()
+            .take(self.max_batch_accounts - batch.accounts.len())
+            .copied()
+            .collect();
+        for address in queued {
+            let slots = self.queue.remove(&address).unwrap_or_default();
+            batch.accounts.push((address, slots.into_iter().collect()));
+        }
+
+        let fetched = self.fetcher.fetch_batch(&batch)?;
+        self.round_trips += 1;
+        for ((address, slots), account) in batch.accounts.into_iter().zip(fetched) {
+            // Slots of accounts that do not exist are empty.
+            let exists = account.info.is_some();
+            self.accounts.entry(address).or_insert(account.info);
+            for (i, slot) in slots.into_iter().enumerate() {
+                let value = if exists {
+                    account.storage.get(i).copied().unwrap_or_default()
+                } else {
+                    U256::ZERO
+                };
+                self.storage.entry((address, slot)).or_insert(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: BatchFetcher> Database for BatchingDB<F> {
+    type Error = F::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if !self.accounts.contains_key(&address) {
+            self.fetch(Some((address, None)))?;
+        }
+        Ok(self.accounts.get(&address).cloned().flatten())
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.fetcher.code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if !self.storage.contains_key(&(address, index)) {
+            self.fetch(Some((address, Some(index))))?;
+        }
+        Ok(self
+            .storage
+            .get(&(address, index))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        self.fetcher.block_hash(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+
+    /// Answers every slot with its index and records the batches.
+    #[derive(Default)]
+    struct MockFetcher {
+        batches: Vec<StateBatch>,
+    }
+
+    impl BatchFetcher for MockFetcher {
+        type Error = Infallible;
+
+        fn fetch_batch(&mut self, batch: &StateBatch) -> Result<Vec<FetchedAccount>, Infallible> {
+            self.batches.push(batch.clone());
+            Ok(batch
+                .accounts
+                .iter()
+                .map(|(_, slots)| FetchedAccount {
+                    info: Some(AccountInfo::from_balance(U256::from(1))),
+                    storage: slots.clone(),
+                })
+                .collect())
+        }
+
+        fn code_by_hash(&mut self, _code_hash: B256) -> Result<Bytecode, Infallible> {
+            Ok(Bytecode::new())
+        }
+
+        fn block_hash(&mut self, _number: U256) -> Result<B256, Infallible> {
+            Ok(B256::ZERO)
+        }
+    }
+
+    #[test]
+    fn miss_sends_queued_reads() {
+        let a = Address::with_last_byte(1);
+        let b = Address::with_last_byte(2);
+        let mut db = BatchingDB::new(MockFetcher::default());
+        db.queue_storage(a, U256::from(5));
+        db.queue_storage(b, U256::from(6));
+
+        assert_eq!(db.storage(a, U256::from(7)).unwrap(), U256::from(7));
+        assert_eq!(db.round_trips(), 1);
+        assert_eq!(db.fetcher().batches[0].accounts.len(), 2);
+
+        // Everything queued was answered by the first batch.
+        assert_eq!(db.storage(a, U256::from(5)).unwrap(), U256::from(5));
+        assert_eq!(db.storage(b, U256::from(6)).unwrap(), U256::from(6));
+        assert!(db.basic(b).unwrap().is_some());
+        assert_eq!(db.round_trips(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+//! Deterministic generator of synthetic state shaped like Ethereum mainnet.
+
+use super::{AccountState, CacheDB, DbAccount, EmptyDB};
+use crate::primitives::{keccak256, AccountInfo, Address, Bytecode, Bytes, HashMap, U256};
+use std::vec::Vec;
+
+/// Generator of synthetic accounts, contracts and storage for benchmarks.
+///
+/// The state is a function of the seed, so that runs with the same configuration execute
+/// against the same accounts. It follows the rough shape of mainnet:
+///
+/// * most accounts are externally owned, with nonces and balances spread over orders of
+///   magnitude;
+/// * a share of the contracts reuses the code of an earlier contract, as proxies and clones do;
+/// * storage is heavy tailed, most contracts hold a few slots and a few hold many, with slots of
+///   plain variables next to slots of mappings at hashed keys.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StateGenerator {
+    /// Seed of the generator.
+    pub seed: u64,
+    /// Number of generated accounts.
+    pub accounts: usize,
+    /// Percentage of the accounts that are contracts.
+    pub contract_percent: u8,
+    /// Percentage of the contracts that share the code of an earlier contract.
+    pub shared_code_percent: u8,
+    /// Maximum code size of a contract.
+    pub max_code_size: usize,
+    /// Maximum number of storage slots of a contract.
+    pub max_storage_slots: usize,
+}
+
+impl Default for StateGenerator {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            accounts: 1_000,
+            contract_percent: 15,
+            shared_code_percent: 30,
+            max_code_size: 0x6000,
+            max_storage_slots: 4_096,
+        }
+    }
+}
+
+/// Addresses of the accounts created by a [StateGenerator], in generation order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GeneratedAccounts {
+    /// Accounts without code.
+    pub eoas: Vec<Address>,
+    /// Accounts with code.
+    pub contracts: Vec<Address>,
+}
+
+impl StateGenerator {
+    /// Creates a generator of `accounts` accounts from `seed`, with the default distributions.
+    pub fn new(seed: u64, accounts: usize) -> Self {
+        Self {
+            seed,
+            accounts,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a database holding the generated state.
+    pub fn generate(&self) -> (CacheDB<EmptyDB>, GeneratedAccounts) {
+        let mut db = CacheDB::new(EmptyDB::default());
+        let accounts = self.populate(&mut db);
+        (db, accounts)
+    }
+
+    /// Inserts the generated accounts into `db`, replacing the accounts at their addresses.
+    pub fn populate<ExtDB>(&self, db: &mut CacheDB<ExtDB>) -> GeneratedAccounts {
+        let mut rng = SplitMix64(self.seed);
+        let mut generated = GeneratedAccounts::default();
+        let mut codes: Vec<Bytecode> = Vec::new();
+        for _ in 0..self.accounts {
+            let mut address = Address::ZERO;
+            rng.fill(address.as_mut_slice());
+            let balance = rng.log_uniform(U256::BITS - 160);
+
+            if rng.below(100) >= self.contract_percent as u64 {
+                // Nonces of externally owned accounts span from unused accounts to bots.
+                let nonce = rng.log_uniform(16).to::<u64>();
+                let info = AccountInfo {
+                    balance,
+                    nonce,
+                    ..Default::default()
+                };
+                db.insert_account_info(address, info);
+                generated.eoas.push(address);
+                continue;
+            }
+
+            let shared = !codes.is_empty() && rng.below(100) < self.shared_code_percent as u64;
+            let code = if shared {
+                codes[rng.below(codes.len() as u64) as usize].clone()
+            } else {
+                let max_bits = usize::BITS - self.max_code_size.leading_zeros();
+                let size = rng
+                    .log_uniform(max_bits as usize)
+                    .to::<usize>()
+                    .clamp(1, self.max_code_size.max(1));
+                let mut code = vec![0; size];
+                rng.fill(&mut code);
+                let code = Bytecode::new_raw(Bytes::from(code));
+                codes.push(code.clone());
+                code
+            };
+            let mut info = AccountInfo::new(balance, 1, code.hash_slow(), code);
+            db.insert_contract(&mut info);
+
+            let max_bits = usize::BITS - self.max_storage_slots.leading_zeros();
+            let slots = rng
+                .log_uniform(max_bits as usize)
+                .to::<usize>()
+                .min(self.max_storage_slots);
+            let mut storage = HashMap::default();
+            for index in 0..slots {
+                // A quarter of the slots are plain variables, the others entries of mappings.
+                let slot = if rng.below(4) == 0 {
+                    U256::from(index)
+                } else {
+                    let mut key = [0; 64];
+                    rng.fill(&mut key);
+                    U256::from_be_bytes(keccak256(key).0)
+                };
+                storage.insert(slot, rng.log_uniform(U256::BITS).max(U256::from(1)));
+            }
+            db.accounts.insert(
+                address,
+                DbAccount {
+                    info,
+                    account_state: AccountState::StorageCleared,
+                    storage,
+                },
+            );
+            generated.contracts.push(address);
+        }
+        generated
+    }
+}
+
+/// SplitMix64 pseudo-random number generator.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number below `bound`, which must not be zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    /// Returns a number of up to `max_bits` bits, whose bit length is uniformly distributed.
+    fn log_uniform(&mut self, max_bits: usize) -> U256 {
+        let bits = self.below(max_bits as u64 + 1) as usize;
+        let mut bytes = [0; 32];
+        self.fill(&mut bytes);
+        let value = U256::from_be_bytes(bytes);
+        if bits == 0 {
+            U256::ZERO
+        } else {
+            value >> (U256::BITS - bits)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_state() {
+        let generator = StateGenerator {
+            max_storage_slots: 64,
+            ..StateGenerator::new(7, 200)
+        };
+        let (db, accounts) = generator.generate();
+        let (other_db, other_accounts) = generator.generate();
+        assert_eq!(accounts, other_accounts);
+        assert_eq!(accounts.eoas.len() + accounts.contracts.len(), 200);
+        assert!(!accounts.contracts.is_empty());
+        for address in accounts.eoas.iter().chain(&accounts.contracts) {
+            let (account, other) = (&db.accounts[address], &other_db.accounts[address]);
+            assert_eq!(account.info, other.info);
+            assert_eq!(account.storage, other.storage);
+            assert!(account.storage.len() <= 64);
+        }
+        for address in &accounts.contracts {
+            let code = db.accounts[address].info.code.as_ref().unwrap();
+            assert!(!code.is_empty() && code.len() <= 0x6000);
+        }
+
+        let (_, reseeded) = StateGenerator::new(8, 200).generate();
+        assert_ne!(accounts, reseeded);
+    }
+}
@@ -0,0 +1,275 @@
+//! Geth/reth `genesis.json` loader.
+use super::{CacheDB, EmptyDB, StateSnapshot};
+use crate::primitives::{
+    Address, BlobExcessGasAndPrice, BlockEnv, Bytes, CfgEnv, ForkCondition, HardforkSchedule,
+    SpecId, B256, U256,
+};
+
+/// Chain configuration of a genesis file: chain id and hardfork activation points.
+///
+/// Block based forks are activated by block number, forks since Shanghai by timestamp.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "camelCase"))]
+pub struct ChainConfig {
+    /// Chain id used for transaction replay protection.
+    pub chain_id: u64,
+    /// Homestead activation block.
+    pub homestead_block: Option<u64>,
+    /// DAO fork activation block.
+    pub dao_fork_block: Option<u64>,
+    /// Tangerine Whistle (EIP-150) activation block.
+    pub eip150_block: Option<u64>,
+    /// EIP-155 activation block. Not part of the schedule, EIP-155 is enabled with Spurious
+    /// Dragon.
+    pub eip155_block: Option<u64>,
+    /// Spurious Dragon (EIP-158) activation block.
+    pub eip158_block: Option<u64>,
+    /// Byzantium activation block.
+    pub byzantium_block: Option<u64>,
+    /// Constantinople activation block.
+    pub constantinople_block: Option<u64>,
+    /// Petersburg activation block.
+    pub petersburg_block: Option<u64>,
+    /// Istanbul activation block.
+    pub istanbul_block: Option<u64>,
+    /// Muir Glacier activation block.
+    pub muir_glacier_block: Option<u64>,
+    /// Berlin activation block.
+    pub berlin_block: Option<u64>,
+    /// London activation block.
+    pub london_block: Option<u64>,
+    /// Arrow Glacier activation block.
+    pub arrow_glacier_block: Option<u64>,
+    /// Gray Glacier activation block.
+    pub gray_glacier_block: Option<u64>,
+    /// Merge (Paris) activation block.
+    pub merge_netsplit_block: Option<u64>,
+    /// Total difficulty at which the merge happens, see [ChainConfig::hardfork_schedule].
+    pub terminal_total_difficulty: Option<U256>,
+    /// Shanghai activation timestamp.
+    pub shanghai_time: Option<u64>,
+    /// Cancun activation timestamp.
+    pub cancun_time: Option<u64>,
+    /// Prague activation timestamp.
+    pub prague_time: Option<u64>,
+    /// Osaka activation timestamp.
+    pub osaka_time: Option<u64>,
+    /// Bedrock activation block.
+    #[cfg(feature = "optimism")]
+    pub bedrock_block: Option<u64>,
+    /// Regolith activation timestamp.
+    #[cfg(feature = "optimism")]
+    pub regolith_time: Option<u64>,
+    /// Canyon activation timestamp.
+    #[cfg(feature = "optimism")]
+    pub canyon_time: Option<u64>,
+    /// Ecotone activation timestamp.
+    #[cfg(feature = "optimism")]
+    pub ecotone_time: Option<u64>,
+}
+
+impl ChainConfig {
+    /// Returns the [HardforkSchedule] of the chain.
+    ///
+    /// Merge has no block number if only the terminal total difficulty is set. In that case it
+    /// is scheduled at genesis when the terminal total difficulty is zero, otherwise it is left
+    /// out and only implied by later forks.
+    pub fn hardfork_schedule(&self) -> HardforkSchedule {
+        let merge_block = self.merge_netsplit_block.or(self
+            .terminal_total_difficulty
+            .filter(|ttd| ttd.is_zero())
+            .map(|_| 0));
+
+        let blocks = [
+            (SpecId::HOMESTEAD, self.homestead_block),
+            (SpecId::DAO_FORK, self.dao_fork_block),
+            (SpecId::TANGERINE, self.eip150_block),
+            (SpecId::SPURIOUS_DRAGON, self.eip158_block),
+            (SpecId::BYZANTIUM, self.byzantium_block),
+            (SpecId::CONSTANTINOPLE, self.constantinople_block),
+            (SpecId::PETERSBURG, self.petersburg_block),
+            (SpecId::ISTANBUL, self.istanbul_block),
+            (SpecId::MUIR_GLACIER, self.muir_glacier_block),
+            (SpecId::BERLIN, self.berlin_block),
+            (SpecId::LONDON, self.london_block),
+            (SpecId::ARROW_GLACIER, self.arrow_glacier_block),
+            (SpecId::GRAY_GLACIER, self.gray_glacier_block),
+            (SpecId::MERGE, merge_block),
+            #[cfg(feature = "optimism")]
+            (SpecId::BEDROCK, self.bedrock_block),
+        ];
+        let timestamps = [
+            (SpecId::SHANGHAI, self.shanghai_time),
+            (SpecId::CANCUN, self.cancun_time),
+            (SpecId::PRAGUE, self.prague_time),
+            (SpecId::OSAKA, self.osaka_time),
+            #[cfg(feature = "optimism")]
+            (SpecId::REGOLITH, self.regolith_time),
+            #[cfg(feature = "optimism")]
+            (SpecId::CANYON, self.canyon_time),
+            #[cfg(feature = "optimism")]
+            (SpecId::ECOTONE, self.ecotone_time),
+        ];
+
+        let mut schedule =
+            HardforkSchedule::new().with_fork(SpecId::FRONTIER, ForkCondition::Block(0));
+        for (spec_id, block) in blocks {
+            if let Some(block) = block {
+                schedule.insert(spec_id, ForkCondition::Block(block));
+            }
+        }
+        for (spec_id, timestamp) in timestamps {
+            if let Some(timestamp) = timestamp {
+                schedule.insert(spec_id, ForkCondition::Timestamp(timestamp));
+            }
+        }
+        schedule
+    }
+}
+
+/// Parsed `genesis.json` file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "camelCase"))]
+pub struct Genesis {
+    /// Chain configuration.
+    pub config: ChainConfig,
+    /// Timestamp of the genesis block.
+    pub timestamp: U256,
+    /// Extra data of the genesis block.
+    pub extra_data: Bytes,
+    /// Gas limit of the genesis block.
+    pub gas_limit: U256,
+    /// Difficulty of the genesis block.
+    pub difficulty: U256,
+    /// Mix hash of the genesis block, used as `prevrandao` after the merge.
+    pub mix_hash: B256,
+    /// Beneficiary of the genesis block.
+    pub coinbase: Address,
+    /// Base fee of the genesis block.
+    pub base_fee_per_gas: Option<U256>,
+    /// Initial state.
+    pub alloc: StateSnapshot,
+}
+
+impl Genesis {
+    /// Parses a `genesis.json` file.
+    #[cfg(feature = "serde-json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Parses a `genesis.json` file and returns a database initialized with its `alloc` together
+    /// with the hardfork schedule of the chain.
+    #[cfg(feature = "serde-json")]
+    pub fn load(json: &str) -> Result<(CacheDB<EmptyDB>, HardforkSchedule), serde_json::Error> {
+        let genesis = Self::from_json(json)?;
+        let schedule = genesis.hardfork_schedule();
+        Ok((genesis.into_cache_db(), schedule))
+    }
+
+    /// Returns the [HardforkSchedule] of the chain.
+    pub fn hardfork_schedule(&self) -> HardforkSchedule {
+        self.config.hardfork_schedule()
+    }
+
+    /// Returns the [SpecId] of the genesis block.
+    pub fn spec_id(&self) -> SpecId {
+        self.hardfork_schedule()
+            .spec_id_at(0, self.timestamp.saturating_to())
+    }
+
+    /// Returns the [CfgEnv] of the chain.
+    pub fn cfg_env(&self) -> CfgEnv {
+        let mut cfg = CfgEnv::default();
+        cfg.chain_id = self.config.chain_id;
+        cfg
+    }
+
+    /// Returns the [BlockEnv] of the genesis block.
+    pub fn block_env(&self) -> BlockEnv {
+        let cancun = self.spec_id().is_enabled_in(SpecId::CANCUN);
+        BlockEnv {
+            number: U256::ZERO,
+            coinbase: self.coinbase,
+            timestamp: self.timestamp,
+            gas_limit: self.gas_limit,
+            basefee: self.base_fee_per_gas.unwrap_or_default(),
+            difficulty: self.difficulty,
+            prevrandao: Some(self.mix_hash),
+            blob_excess_gas_and_price: cancun.then(|| BlobExcessGasAndPrice::new(0)),
+        }
+    }
+
+    /// Returns a [CacheDB] over an [EmptyDB] initialized with the genesis `alloc`.
+    pub fn into_cache_db(self) -> CacheDB<EmptyDB> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_snapshot(self.alloc);
+        db
+    }
+}
+
+#[cfg(all(test, feature = "serde-json"))]
+mod tests {
+    use super::*;
+    use crate::{db::Database, primitives::address};
+
+    const GENESIS: &str = r#"{
+        "config": {
+            "chainId": 1337,
+            "homesteadBlock": 0,
+            "eip150Block": 0,
+            "eip155Block": 0,
+            "eip158Block": 0,
+            "byzantiumBlock": 0,
+            "constantinopleBlock": 0,
+            "petersburgBlock": 0,
+            "istanbulBlock": 0,
+            "berlinBlock": 0,
+            "londonBlock": 0,
+            "terminalTotalDifficulty": 0,
+            "terminalTotalDifficultyPassed": true,
+            "shanghaiTime": 0,
+            "cancunTime": 100,
+            "pragueTime": 200,
+            "osakaTime": 300
+        },
+        "nonce": "0x0",
+        "timestamp": "0x0",
+        "extraData": "0x",
+        "gasLimit": "0x1c9c380",
+        "difficulty": "0x0",
+        "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "coinbase": "0x0000000000000000000000000000000000000000",
+        "baseFeePerGas": "0x3b9aca00",
+        "alloc": {
+            "0x0000000000000000000000000000000000000001": { "balance": "0x3e8" }
+        }
+    }"#;
+
+    #[test]
+    fn load_genesis() {
+        let genesis = Genesis::from_json(GENESIS).unwrap();
+        assert_eq!(genesis.cfg_env().chain_id, 1337);
+        assert_eq!(genesis.spec_id(), SpecId::SHANGHAI);
+
+        let block = genesis.block_env();
+        assert_eq!(block.gas_limit, U256::from(30_000_000));
+        assert_eq!(block.basefee, U256::from(1_000_000_000));
+        assert_eq!(block.blob_excess_gas_and_price, None);
+
+        let (mut db, schedule) = Genesis::load(GENESIS).unwrap();
+        assert_eq!(schedule.spec_id_at(0, 0), SpecId::SHANGHAI);
+        assert_eq!(schedule.spec_id_at(1, 100), SpecId::CANCUN);
+        assert_eq!(schedule.spec_id_at(2, 200), SpecId::PRAGUE);
+        assert_eq!(schedule.spec_id_at(3, 300), SpecId::OSAKA);
+        assert_eq!(schedule.fork(SpecId::MERGE), Some(ForkCondition::Block(0)));
+
+        let info = db
+            .basic(address!("0000000000000000000000000000000000000001"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.balance, U256::from(1000));
+    }
+}
@@ -1,7 +1,7 @@
-use super::{DatabaseCommit, DatabaseRef, EmptyDB};
+use super::{DatabaseCommit, DatabaseRef, EmptyDB, MemoryUsage};
 use crate::primitives::{
-    hash_map::Entry, Account, AccountInfo, Address, Bytecode, HashMap, Log, B256, KECCAK_EMPTY,
-    U256,
+    hash_map::Entry, Account, AccountInfo, Address, Bytecode, HashMap, HashSet, Log, B256,
+    KECCAK_EMPTY, U256,
 };
 use crate::Database;
 use core::convert::Infallible;
@@ -33,6 +33,9 @@ pub struct CacheDB<ExtDB> {
     ///
     /// Note: this is read-only, data is never written to this database.
     pub db: ExtDB,
+    /// How destroyed accounts and touched empty accounts are committed.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pruning: AccountPruning,
 }
 
 impl<ExtDB: Default> Default for CacheDB<ExtDB> {
@@ -52,53 +55,146 @@ impl<ExtDB> CacheDB<ExtDB> {
             logs: Vec::default(),
             block_hashes: HashMap::new(),
             db,
+            pruning: AccountPruning::default(),
         }
     }
 
+    /// Sets how destroyed accounts and touched empty accounts are committed.
+    pub fn with_pruning(mut self, pruning: AccountPruning) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
     /// Inserts the account's code into the cache.
     ///
     /// Accounts objects and code are stored separately in the cache, this will take the code from the account and instead map it to the code hash.
     ///
     /// Note: This will not insert into the underlying external database.
+    ///
+    /// If the code is already cached, the account's code is replaced with the cached one so all
+    /// accounts with the same code share a single copy.
     pub fn insert_contract(&mut self, account: &mut AccountInfo) {
-        if let Some(code) = &account.code {
-            if !code.is_empty() {
-                if account.code_hash == KECCAK_EMPTY {
-                    account.code_hash = code.hash_slow();
-                }
-                self.contracts
-                    .entry(account.code_hash)
-                    .or_insert_with(|| code.clone());
-            }
-        }
-        if account.code_hash == B256::ZERO {
-            account.code_hash = KECCAK_EMPTY;
-        }
+        share_contract(&mut self.contracts, account)
     }
 
     /// Insert account info but not override storage
     pub fn insert_account_info(&mut self, address: Address, mut info: AccountInfo) {
         self.insert_contract(&mut info);
-        self.accounts.entry(address).or_default().info = info;
+        let account = self.accounts.entry(address).or_default();
+        account.info = info;
+        account.touch();
+    }
+
+    /// Returns the number of cached entries, with an estimate of their memory.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            accounts: self.accounts.len(),
+            storage_slots: self
+                .accounts
+                .values()
+                .map(|account| account.storage.len())
+                .sum(),
+            contracts: self.contracts.len(),
+            code_bytes: self.contracts.values().map(Bytecode::len).sum(),
+            block_hashes: self.block_hashes.len(),
+            estimated_bytes: 0,
+        }
+        .estimate::<(Address, DbAccount), (U256, U256), (B256, Bytecode), (U256, B256)>()
+    }
+
+    /// Evicts the accounts loaded from the underlying database and untouched since, and the
+    /// contracts no longer used by a cached account.
+    ///
+    /// Accounts changed by a commit or inserted with [Self::insert_account_info] or
+    /// [Self::insert_account_storage] are kept. Returns the number of evicted accounts.
+    pub fn evict_untouched(&mut self) -> usize {
+        let len = self.accounts.len();
+        self.accounts
+            .retain(|_, account| account.account_state != AccountState::None);
+        let used: HashSet<B256> = self
+            .accounts
+            .values()
+            .map(|account| account.info.code_hash)
+            .collect();
+        self.contracts
+            .retain(|hash, _| *hash == KECCAK_EMPTY || *hash == B256::ZERO || used.contains(hash));
+        len - self.accounts.len()
+    }
+
+    /// Drops the storage cached for untouched accounts, largest first, until at most
+    /// `max_slots` slots are cached, see [Self::evict_untouched].
+    ///
+    /// Storage of touched accounts is kept, even above the limit. Returns the number of dropped
+    /// slots.
+    pub fn cap_storage_cache(&mut self, max_slots: usize) -> usize {
+        let mut cached = self.memory_usage().storage_slots;
+        let mut untouched: Vec<_> = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| {
+                account.account_state == AccountState::None && !account.storage.is_empty()
+            })
+            .map(|(address, account)| (account.storage.len(), *address))
+            .collect();
+        untouched.sort_unstable();
+        let mut dropped = 0;
+        while cached > max_slots {
+            let Some((slots, address)) = untouched.pop() else {
+                break;
+            };
+            if let Some(account) = self.accounts.get_mut(&address) {
+                account.storage = HashMap::default();
+            }
+            cached -= slots;
+            dropped += slots;
+        }
+        dropped
     }
 }
 
+/// Inserts the account's code into `contracts` and replaces it with the cached copy.
+pub(super) fn share_contract(contracts: &mut HashMap<B256, Bytecode>, account: &mut AccountInfo) {
+    if let Some(code) = &mut account.code {
+        if !code.is_empty() {
+            if account.code_hash == KECCAK_EMPTY {
+                account.code_hash = code.hash_slow();
+            }
+            *code = contracts
+                .entry(account.code_hash)
+                .or_insert_with(|| code.clone())
+                .clone();
+        }
+    }
+    if account.code_hash == B256::ZERO {
+        account.code_hash = KECCAK_EMPTY;
+    }
+}
+
+/// Loads the account from the underlying database, sharing its code with cached contracts.
+fn load_db_account<ExtDB: DatabaseRef>(
+    db: &ExtDB,
+    contracts: &mut HashMap<B256, Bytecode>,
+    address: Address,
+) -> Result<DbAccount, ExtDB::Error> {
+    Ok(db
+        .basic_ref(address)?
+        .map(|mut info| {
+            share_contract(contracts, &mut info);
+            DbAccount::from(info)
+        })
+        .unwrap_or_else(DbAccount::new_not_existing))
+}
+
 impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
     /// Returns the account for the given address.
     ///
     /// If the account was not found in the cache, it will be loaded from the underlying database.
     pub fn load_account(&mut self, address: Address) -> Result<&mut DbAccount, ExtDB::Error> {
-        let db = &self.db;
         match self.accounts.entry(address) {
             Entry::Occupied(entry) => Ok(entry.into_mut()),
-            Entry::Vacant(entry) => Ok(entry.insert(
-                db.basic_ref(address)?
-                    .map(|info| DbAccount {
-                        info,
-                        ..Default::default()
-                    })
-                    .unwrap_or_else(DbAccount::new_not_existing),
-            )),
+            Entry::Vacant(entry) => {
+                Ok(entry.insert(load_db_account(&self.db, &mut self.contracts, address)?))
+            }
         }
     }
 
@@ -111,6 +207,7 @@ impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
     ) -> Result<(), ExtDB::Error> {
         let account = self.load_account(address)?;
         account.storage.insert(slot, value);
+        account.touch();
         Ok(())
     }
 
@@ -133,7 +230,13 @@ impl<ExtDB> DatabaseCommit for CacheDB<ExtDB> {
             if !account.is_touched() {
                 continue;
             }
-            if account.is_selfdestructed() {
+            if account.is_selfdestructed()
+                || (account.is_empty() && self.pruning != AccountPruning::Keep)
+            {
+                if self.pruning == AccountPruning::Prune {
+                    self.accounts.remove(&address);
+                    continue;
+                }
                 let db_account = self.accounts.entry(address).or_default();
                 db_account.storage.clear();
                 db_account.account_state = AccountState::NotExisting;
@@ -171,15 +274,9 @@ impl<ExtDB: DatabaseRef> Database for CacheDB<ExtDB> {
     fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
         let basic = match self.accounts.entry(address) {
             Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => entry.insert(
-                self.db
-                    .basic_ref(address)?
-                    .map(|info| DbAccount {
-                        info,
-                        ..Default::default()
-                    })
-                    .unwrap_or_else(DbAccount::new_not_existing),
-            ),
+            Entry::Vacant(entry) => {
+                entry.insert(load_db_account(&self.db, &mut self.contracts, address)?)
+            }
         };
         Ok(basic.info())
     }
@@ -315,6 +412,13 @@ impl DbAccount {
             Some(self.info.clone())
         }
     }
+
+    /// Marks an account loaded from the database as changed, so that it is not evicted.
+    fn touch(&mut self) {
+        if self.account_state == AccountState::None {
+            self.account_state = AccountState::Touched;
+        }
+    }
 }
 
 impl From<Option<AccountInfo>> for DbAccount {
@@ -333,6 +437,26 @@ impl From<AccountInfo> for DbAccount {
     }
 }
 
+/// How [CacheDB] commits accounts destroyed by a selfdestruct and touched accounts left empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountPruning {
+    /// Destroyed accounts are replaced with tombstones, empty accounts are kept as they are.
+    ///
+    /// Required before the Spurious Dragon hardfork, where empty accounts exist.
+    #[default]
+    Keep,
+    /// Destroyed accounts and empty accounts are replaced with tombstones: accounts in the
+    /// [AccountState::NotExisting] state, without storage, that are not read from the underlying
+    /// database again.
+    Tombstone,
+    /// Destroyed accounts and empty accounts are removed from the cache.
+    ///
+    /// Removed accounts are read from the underlying database again, so this is only correct if
+    /// it does not hold them, as for an [InMemoryDB], or if the same changes are persisted to it.
+    Prune,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccountState {
@@ -488,4 +612,99 @@ mod tests {
             nonce
         );
     }
+
+    #[test]
+    fn test_shared_contract_code() {
+        use crate::primitives::{Bytecode, Bytes};
+
+        let code = || Bytecode::new_raw(Bytes::from(vec![0x60, 0x00, 0x00]));
+        let (first, second) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let mut db = CacheDB::new(EmptyDB::default());
+        for address in [first, second] {
+            let info = AccountInfo {
+                code: Some(code()),
+                ..Default::default()
+            };
+            db.insert_account_info(address, info);
+        }
+
+        let code_of = |address| db.accounts[&address].info.code.clone().unwrap();
+        assert_eq!(
+            code_of(first).original_bytes().as_ptr(),
+            code_of(second).original_bytes().as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_evict_untouched() {
+        let (loaded, inserted) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let mut init_state = CacheDB::new(EmptyDB::default());
+        init_state.insert_account_info(loaded, AccountInfo::from_balance(U256::from(1)));
+        init_state
+            .insert_account_storage(loaded, U256::ZERO, U256::from(5))
+            .unwrap();
+
+        let mut state = CacheDB::new(init_state);
+        state.insert_account_info(inserted, AccountInfo::from_balance(U256::from(2)));
+        assert_eq!(state.storage(loaded, U256::ZERO), Ok(U256::from(5)));
+        let usage = state.memory_usage();
+        assert_eq!((usage.accounts, usage.storage_slots), (2, 1));
+        assert!(usage.estimated_bytes > 0);
+
+        assert_eq!(state.cap_storage_cache(0), 1);
+        assert_eq!(state.evict_untouched(), 1);
+        assert_eq!(state.memory_usage().accounts, 1);
+
+        // Evicted accounts are loaded again from the underlying database.
+        assert_eq!(state.basic(loaded).unwrap().unwrap().balance, U256::from(1));
+        assert_eq!(state.storage(loaded, U256::ZERO), Ok(U256::from(5)));
+        assert_eq!(
+            state.basic(inserted).unwrap().unwrap().balance,
+            U256::from(2)
+        );
+    }
+    #[test]
+    fn test_account_pruning() {
+        use super::{AccountPruning, AccountState};
+        use crate::primitives::{db::DatabaseCommit, Account, HashMap};
+
+        let (destroyed, emptied) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let commit = |pruning| {
+            let mut state = CacheDB::new(EmptyDB::default()).with_pruning(pruning);
+            state.insert_account_info(destroyed, AccountInfo::from_balance(U256::from(1)));
+            state.insert_account_info(emptied, AccountInfo::from_balance(U256::from(1)));
+            let mut changes = HashMap::default();
+            let mut account = Account::from(AccountInfo::from_balance(U256::from(1)));
+            account.mark_touch();
+            account.mark_selfdestruct();
+            changes.insert(destroyed, account);
+            let mut account = Account::from(AccountInfo::default());
+            account.mark_touch();
+            changes.insert(emptied, account);
+            state.commit(changes);
+            state
+        };
+
+        let state = commit(AccountPruning::Keep);
+        assert_eq!(
+            state.accounts[&destroyed].account_state,
+            AccountState::NotExisting
+        );
+        assert_eq!(
+            state.accounts[&emptied].account_state,
+            AccountState::Touched
+        );
+
+        let mut state = commit(AccountPruning::Tombstone);
+        for address in [destroyed, emptied] {
+            assert_eq!(
+                state.accounts[&address].account_state,
+                AccountState::NotExisting
+            );
+            assert_eq!(state.basic(address), Ok(None));
+        }
+
+        let state = commit(AccountPruning::Prune);
+        assert!(state.accounts.is_empty());
+    }
 }
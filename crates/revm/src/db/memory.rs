@@ -0,0 +1,41 @@
+//! Memory usage of the caches of [CacheDB](super::CacheDB) and [State](super::State).
+//!
+//! Long running processes bound the memory of a cache by calling `evict_untouched` and
+//! `cap_storage_cache` between transactions. Both only drop entries that are read from the
+//! underlying database and never changed, so the next access loads them again and execution is
+//! unaffected.
+
+use core::mem::size_of;
+
+/// Number of cached entries of a database, with an estimate of the memory they take.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryUsage {
+    /// Cached accounts.
+    pub accounts: usize,
+    /// Cached storage slots of all accounts.
+    pub storage_slots: usize,
+    /// Cached contracts.
+    pub contracts: usize,
+    /// Total size of the cached bytecode.
+    pub code_bytes: usize,
+    /// Cached block hashes.
+    pub block_hashes: usize,
+    /// Estimate of the memory taken by the entries, in bytes.
+    ///
+    /// Map overhead and excess capacity are not included.
+    pub estimated_bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Computes the estimate of the memory taken by the entries, given the sizes of the entries
+    /// of the account, storage, contract and block hash maps.
+    pub(crate) fn estimate<Account, Slot, Contract, BlockHash>(mut self) -> Self {
+        self.estimated_bytes = self.accounts * size_of::<Account>()
+            + self.storage_slots * size_of::<Slot>()
+            + self.contracts * size_of::<Contract>()
+            + self.code_bytes
+            + self.block_hashes * size_of::<BlockHash>();
+        self
+    }
+}
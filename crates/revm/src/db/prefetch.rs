@@ -0,0 +1,213 @@
+//! Concurrent prefetching of accounts and storage slots into a [CacheDB](super::CacheDB).
+//!
+//! Remote databases answer every miss with a round trip. When the accounts and slots a
+//! transaction touches are known or predicted in advance, for example from its access list,
+//! they can be loaded concurrently before execution starts.
+#[cfg(feature = "std")]
+use super::{in_memory_db::share_contract, AccountState, CacheDB, DatabaseRef, DbAccount};
+#[cfg(feature = "std")]
+use crate::primitives::AccountInfo;
+use crate::primitives::{Address, Env, HashMap, HashSet, State, TransactTo, U256};
+#[cfg(feature = "std")]
+use std::thread;
+use std::vec::Vec;
+
+/// Accounts and storage slots to prefetch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrefetchHints {
+    accounts: HashMap<Address, HashSet<U256>>,
+}
+
+impl PrefetchHints {
+    /// Creates empty hints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates hints from an access list.
+    pub fn from_access_list(access_list: &[(Address, Vec<U256>)]) -> Self {
+        let mut hints = Self::new();
+        for (address, slots) in access_list {
+            hints.add_slots(*address, slots.iter().copied());
+        }
+        hints
+    }
+
+    /// Creates hints for the accounts and slots known to be accessed by the transaction in
+    /// `env`: the caller, the callee, the block beneficiary and the access list.
+    pub fn from_env(env: &Env) -> Self {
+        let mut hints = Self::from_access_list(&env.tx.access_list);
+        hints.add_account(env.tx.caller);
+        hints.add_account(env.effective_coinbase());
+        if let TransactTo::Call(address) = env.tx.transact_to {
+            hints.add_account(address);
+        }
+        hints
+    }
+
+    /// Creates hints from the state touched by a previous execution of a transaction, for
+    /// example to predict the accesses of the same transaction on a newer block.
+    pub fn from_state(state: &State) -> Self {
+        let mut hints = Self::new();
+        for (address, account) in state {
+            hints.add_slots(*address, account.storage.keys().copied());
+        }
+        hints
+    }
+
+    /// Adds an account.
+    pub fn add_account(&mut self, address: Address) {
+        self.accounts.entry(address).or_default();
+    }
+
+    /// Adds storage slots of an account. The account is added as well.
+    pub fn add_slots(&mut self, address: Address, slots: impl IntoIterator<Item = U256>) {
+        self.accounts.entry(address).or_default().extend(slots);
+    }
+
+    /// Returns `true` if there is nothing to prefetch.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Returns the number of accounts to prefetch.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns an iterator over the accounts and their slots.
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &HashSet<U256>)> {
+        self.accounts.iter()
+    }
+}
+
+/// Account and storage loaded by a prefetch worker.
+#[cfg(feature = "std")]
+type Prefetched = (Address, Option<AccountInfo>, Vec<(U256, U256)>);
+
+#[cfg(feature = "std")]
+impl<ExtDB> CacheDB<ExtDB>
+where
+    ExtDB: DatabaseRef + Sync,
+    ExtDB::Error: Send,
+{
+    /// Loads the hinted accounts and slots from the underlying database using up to
+    /// `concurrency` threads.
+    ///
+    /// Accounts and slots that are already cached are not loaded again, cached values are never
+    /// overwritten.
+    pub fn prefetch(
+        &mut self,
+        hints: &PrefetchHints,
+        concurrency: usize,
+    ) -> Result<(), ExtDB::Error> {
+        let pending: Vec<_> = hints
+            .accounts
+            .iter()
+            .filter_map(|(address, slots)| {
+                let cached = self.accounts.get(address);
+                if cached.map_or(false, |account| {
+                    matches!(
+                        account.account_state,
+                        AccountState::NotExisting | AccountState::StorageCleared
+                    )
+                }) {
+                    return None;
+                }
+                let slots: Vec<_> = slots
+                    .iter()
+                    .filter(|slot| {
+                        cached.map_or(true, |account| !account.storage.contains_key(slot))
+                    })
+                    .copied()
+                    .collect();
+                (cached.is_none() || !slots.is_empty()).then_some((*address, slots))
+            })
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let concurrency = concurrency.max(1);
+        let chunk_size = (pending.len() + concurrency - 1) / concurrency;
+        let db = &self.db;
+        let results = thread::scope(|scope| {
+            let workers: Vec<_> = pending
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || fetch(db, chunk)))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("prefetch worker panicked"))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+
+        for (address, info, storage) in results.into_iter().flatten() {
+            let account = self.accounts.entry(address).or_insert_with(|| match info {
+                Some(mut info) => {
+                    share_contract(&mut self.contracts, &mut info);
+                    DbAccount::from(info)
+                }
+                None => DbAccount::new_not_existing(),
+            });
+            if account.account_state == AccountState::NotExisting {
+                continue;
+            }
+            for (slot, value) in storage {
+                account.storage.entry(slot).or_insert(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads the accounts and slots of a chunk.
+#[cfg(feature = "std")]
+fn fetch<ExtDB: DatabaseRef>(
+    db: &ExtDB,
+    chunk: &[(Address, Vec<U256>)],
+) -> Result<Vec<Prefetched>, ExtDB::Error> {
+    chunk
+        .iter()
+        .map(|(address, slots)| {
+            let info = db.basic_ref(*address)?;
+            let storage = match info {
+                Some(_) => slots
+                    .iter()
+                    .map(|slot| Ok((*slot, db.storage_ref(*address, *slot)?)))
+                    .collect::<Result<_, _>>()?,
+                None => Vec::new(),
+            };
+            Ok((*address, info, storage))
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{db::EmptyDB, Database};
+
+    #[test]
+    fn prefetch_from_underlying_db() {
+        let address = Address::with_last_byte(1);
+        let mut remote = CacheDB::new(EmptyDB::default());
+        remote.insert_account_info(address, AccountInfo::from_balance(U256::from(7)));
+        remote
+            .insert_account_storage(address, U256::from(1), U256::from(2))
+            .unwrap();
+
+        let mut hints = PrefetchHints::from_access_list(&[(address, vec![U256::from(1)])]);
+        hints.add_account(Address::with_last_byte(2));
+        let mut db = CacheDB::new(remote);
+        db.prefetch(&hints, 2).unwrap();
+
+        assert_eq!(db.accounts[&address].info.balance, U256::from(7));
+        assert_eq!(db.accounts[&address].storage[&U256::from(1)], U256::from(2));
+        assert_eq!(
+            db.accounts[&Address::with_last_byte(2)].account_state,
+            AccountState::NotExisting
+        );
+        assert_eq!(db.basic(address).unwrap().unwrap().balance, U256::from(7));
+    }
+}
@@ -0,0 +1,314 @@
+//! Read and write sets of executed transactions.
+//!
+//! [RecordingDatabase] records every account, storage slot, code hash and block hash the EVM
+//! reads from the database. The journal reloads state from the database for every transaction,
+//! so after a transaction the recorded reads are exactly its read set. Combined with the state
+//! returned by the transaction, [RecordingDatabase::finish] returns its [ReadWriteSet], from
+//! which a scheduler can build the dependency graph of a block.
+use super::{Database, DatabaseCommit};
+use crate::primitives::{
+    Account, AccountInfo, Address, Bytecode, HashMap, HashSet, State, B256, U256,
+};
+
+/// State read by a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReadSet {
+    /// Accounts read.
+    pub accounts: HashSet<Address>,
+    /// Storage slots read, by account.
+    pub storage: HashMap<Address, HashSet<U256>>,
+    /// Hashes of the code read.
+    pub code: HashSet<B256>,
+    /// Numbers of the block hashes read.
+    pub block_hashes: HashSet<U256>,
+}
+
+impl ReadSet {
+    /// Returns `true` if nothing was read.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+            && self.storage.is_empty()
+            && self.code.is_empty()
+            && self.block_hashes.is_empty()
+    }
+
+    /// Returns `true` if the storage slot was read.
+    pub fn contains_slot(&self, address: &Address, index: &U256) -> bool {
+        self.storage
+            .get(address)
+            .map_or(false, |slots| slots.contains(index))
+    }
+
+    /// Adds the reads of `other`.
+    pub fn extend(&mut self, other: &ReadSet) {
+        self.accounts.extend(other.accounts.iter().copied());
+        for (address, slots) in &other.storage {
+            self.storage
+                .entry(*address)
+                .or_default()
+                .extend(slots.iter().copied());
+        }
+        self.code.extend(other.code.iter().copied());
+        self.block_hashes.extend(other.block_hashes.iter().copied());
+    }
+}
+
+/// State written by a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteSet {
+    /// Accounts whose balance, nonce or code changed, or that were created or selfdestructed.
+    pub accounts: HashSet<Address>,
+    /// Storage slots whose value changed, by account.
+    pub storage: HashMap<Address, HashSet<U256>>,
+}
+
+impl WriteSet {
+    /// Returns `true` if nothing was written.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.storage.is_empty()
+    }
+
+    /// Returns `true` if the storage slot was written.
+    pub fn contains_slot(&self, address: &Address, index: &U256) -> bool {
+        self.storage
+            .get(address)
+            .map_or(false, |slots| slots.contains(index))
+    }
+
+    /// Adds the writes of `other`.
+    pub fn extend(&mut self, other: &WriteSet) {
+        self.accounts.extend(other.accounts.iter().copied());
+        for (address, slots) in &other.storage {
+            self.storage
+                .entry(*address)
+                .or_default()
+                .extend(slots.iter().copied());
+        }
+    }
+
+    /// Returns `true` if this writes any state read in `reads`.
+    pub fn intersects_reads(&self, reads: &ReadSet) -> bool {
+        self.accounts
+            .iter()
+            .any(|address| reads.accounts.contains(address))
+            || self.storage.iter().any(|(address, slots)| {
+                slots
+                    .iter()
+                    .any(|index| reads.contains_slot(address, index))
+            })
+    }
+
+    /// Returns `true` if this and `other` write the same state.
+    pub fn intersects_writes(&self, other: &WriteSet) -> bool {
+        self.accounts
+            .iter()
+            .any(|address| other.accounts.contains(address))
+            || self.storage.iter().any(|(address, slots)| {
+                slots
+                    .iter()
+                    .any(|index| other.contains_slot(address, index))
+            })
+    }
+}
+
+/// Read and write set of one transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReadWriteSet {
+    /// State read by the transaction.
+    pub reads: ReadSet,
+    /// State written by the transaction.
+    pub writes: WriteSet,
+}
+
+impl ReadWriteSet {
+    /// Adds the reads and writes of `other`.
+    pub fn extend(&mut self, other: &ReadWriteSet) {
+        self.reads.extend(&other.reads);
+        self.writes.extend(&other.writes);
+    }
+
+    /// Returns `true` if the two transactions can not be executed in parallel: one of them
+    /// writes state that the other reads or writes.
+    pub fn conflicts_with(&self, other: &ReadWriteSet) -> bool {
+        self.writes.intersects_reads(&other.reads)
+            || other.writes.intersects_reads(&self.reads)
+            || self.writes.intersects_writes(&other.writes)
+    }
+}
+
+/// Database wrapper that records the read set of the executed transactions.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingDatabase<DB> {
+    db: DB,
+    reads: ReadSet,
+    /// Account info as loaded, without code, to tell which accounts were modified.
+    loaded: HashMap<Address, Option<AccountInfo>>,
+}
+
+impl<DB> RecordingDatabase<DB> {
+    /// Wraps the database.
+    pub fn new(db: DB) -> Self {
+        Self {
+            db,
+            reads: ReadSet::default(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Returns the wrapped database.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Returns the wrapped database.
+    pub fn db_mut(&mut self) -> &mut DB {
+        &mut self.db
+    }
+
+    /// Returns the wrapped database.
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+
+    /// Returns the reads recorded since the last call to [Self::finish].
+    pub fn reads(&self) -> &ReadSet {
+        &self.reads
+    }
+
+    /// Returns the state written in `state`, the state returned by the transaction.
+    pub fn writes(&self, state: &State) -> WriteSet {
+        let mut writes = WriteSet::default();
+        for (address, account) in state {
+            if !account.is_touched() {
+                continue;
+            }
+            if self.is_modified(address, account) {
+                writes.accounts.insert(*address);
+            }
+            let slots: HashSet<_> = account
+                .changed_storage_slots()
+                .map(|(index, _)| *index)
+                .collect();
+            if !slots.is_empty() {
+                writes.storage.insert(*address, slots);
+            }
+        }
+        writes
+    }
+
+    /// Returns the read and write set of the transaction that returned `state` and starts
+    /// recording the next one.
+    pub fn finish(&mut self, state: &State) -> ReadWriteSet {
+        let writes = self.writes(state);
+        self.loaded.clear();
+        ReadWriteSet {
+            reads: core::mem::take(&mut self.reads),
+            writes,
+        }
+    }
+
+    fn is_modified(&self, address: &Address, account: &Account) -> bool {
+        if account.is_created() || account.is_selfdestructed() {
+            return true;
+        }
+        let info = &account.info;
+        match self.loaded.get(address) {
+            Some(Some(loaded)) => {
+                loaded.balance != info.balance
+                    || loaded.nonce != info.nonce
+                    || loaded.code_hash != info.code_hash
+            }
+            Some(None) => !info.is_empty(),
+            // Not loaded through this database, assume it was modified.
+            None => true,
+        }
+    }
+}
+
+impl<DB: Database> Database for RecordingDatabase<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic(address)?;
+        self.reads.accounts.insert(address);
+        self.loaded
+            .entry(address)
+            .or_insert_with(|| info.clone().map(AccountInfo::without_code));
+        Ok(info)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let code = self.db.code_by_hash(code_hash)?;
+        self.reads.code.insert(code_hash);
+        Ok(code)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let value = self.db.storage(address, index)?;
+        self.reads.storage.entry(address).or_default().insert(index);
+        Ok(value)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        let hash = self.db.block_hash(number)?;
+        self.reads.block_hashes.insert(number);
+        Ok(hash)
+    }
+}
+
+impl<DB: DatabaseCommit> DatabaseCommit for RecordingDatabase<DB> {
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.db.commit(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::BenchmarkDB,
+        primitives::{Bytes, TransactTo},
+        Evm,
+    };
+
+    #[test]
+    fn records_reads_and_writes() {
+        // PUSH1 0x00 SLOAD PUSH1 0x01 SSTORE PUSH1 0x00 BLOCKHASH STOP
+        let code =
+            Bytes::from_static(&[0x60, 0x00, 0x54, 0x60, 0x01, 0x55, 0x60, 0x00, 0x40, 0x00]);
+        let db = RecordingDatabase::new(BenchmarkDB::new_bytecode(Bytecode::new_raw(code)));
+        let caller = Address::with_last_byte(1);
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .modify_block_env(|block| block.number = U256::from(10))
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TransactTo::Call(Address::ZERO);
+                tx.gas_limit = 100_000;
+            })
+            .build();
+        let result = evm.transact().unwrap();
+        let rw = evm.db_mut().finish(&result.state);
+
+        assert!(rw.reads.accounts.contains(&caller));
+        assert!(rw.reads.accounts.contains(&Address::ZERO));
+        assert!(rw.reads.contains_slot(&Address::ZERO, &U256::ZERO));
+        assert!(rw.reads.contains_slot(&Address::ZERO, &U256::from(1)));
+        assert!(rw.reads.block_hashes.contains(&U256::ZERO));
+
+        // SSTORE of zero to a zero slot does not change it, only the caller's nonce changes.
+        assert!(rw.writes.storage.is_empty());
+        assert_eq!(rw.writes.accounts, [caller].into_iter().collect());
+        assert!(evm.db().reads().is_empty());
+
+        let other = ReadWriteSet {
+            reads: ReadSet {
+                accounts: [caller].into_iter().collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(rw.conflicts_with(&other));
+        assert!(!rw.conflicts_with(&ReadWriteSet::default()));
+    }
+}
@@ -0,0 +1,332 @@
+//! Retries of failed database reads.
+//!
+//! Remote databases fail transiently: requests time out, nodes rate limit or drop connections.
+//! [RetryingDatabase] repeats failed reads with exponential backoff so that such a failure does
+//! not abort the whole transaction. Errors are classified by a hook, permanent errors are
+//! returned immediately.
+use super::{Database, DatabaseRef};
+use crate::primitives::{AccountInfo, Address, Bytecode, B256, U256};
+use core::{fmt, time::Duration};
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+};
+
+/// Classification of a database error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// The read may succeed when repeated.
+    Transient,
+    /// The read will keep failing, it is not repeated.
+    Permanent,
+}
+
+/// When and how often failed reads are repeated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between retries.
+    pub max_backoff: Duration,
+    /// Factor the delay grows by after every retry.
+    pub multiplier: u32,
+    /// Time after which a single attempt is abandoned, `None` to wait indefinitely.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2,
+            timeout: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the given retry, counted from zero.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(retry);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Error of a [RetryingDatabase].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RetryError<E> {
+    /// Error of the last attempt.
+    Database {
+        /// Error returned by the database.
+        error: E,
+        /// Number of attempts made.
+        attempts: u32,
+    },
+    /// The last attempt timed out.
+    TimedOut {
+        /// Number of attempts made.
+        attempts: u32,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database { error, attempts } => {
+                write!(f, "{error} (after {attempts} attempts)")
+            }
+            Self::TimedOut { attempts } => {
+                write!(f, "database read timed out (after {attempts} attempts)")
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for RetryError<E> {}
+
+type Classifier<E> = Arc<dyn Fn(&E) -> ErrorClass + Send + Sync>;
+
+/// Database wrapper that retries failed reads according to a [RetryPolicy].
+pub struct RetryingDatabase<D: DatabaseRef> {
+    db: Arc<D>,
+    policy: RetryPolicy,
+    classify: Classifier<D::Error>,
+}
+
+impl<D: DatabaseRef> Clone for RetryingDatabase<D> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            policy: self.policy,
+            classify: self.classify.clone(),
+        }
+    }
+}
+
+impl<D: DatabaseRef + fmt::Debug> fmt::Debug for RetryingDatabase<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryingDatabase")
+            .field("db", &self.db)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: DatabaseRef> RetryingDatabase<D> {
+    /// Wraps the database with the default policy. All errors are considered transient.
+    pub fn new(db: D) -> Self {
+        Self {
+            db: Arc::new(db),
+            policy: RetryPolicy::default(),
+            classify: Arc::new(|_| ErrorClass::Transient),
+        }
+    }
+
+    /// Sets the retry policy.
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the hook that decides which errors are retried.
+    pub fn with_classifier(
+        mut self,
+        classify: impl Fn(&D::Error) -> ErrorClass + Send + Sync + 'static,
+    ) -> Self {
+        self.classify = Arc::new(classify);
+        self
+    }
+
+    /// Returns the wrapped database.
+    pub fn db(&self) -> &D {
+        &self.db
+    }
+}
+
+impl<D> RetryingDatabase<D>
+where
+    D: DatabaseRef + Send + Sync + 'static,
+    D::Error: Send + 'static,
+{
+    /// Runs `read` until it succeeds, fails permanently or the retries are exhausted.
+    fn retry<T: Send + 'static>(
+        &self,
+        read: impl Fn(&D) -> Result<T, D::Error> + Send + Sync + 'static,
+    ) -> Result<T, RetryError<D::Error>> {
+        let read = Arc::new(read);
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match self.attempt(read.clone()) {
+                Some(Ok(value)) => return Ok(value),
+                Some(Err(error)) => {
+                    if (self.classify)(&error) == ErrorClass::Permanent {
+                        return Err(RetryError::Database { error, attempts });
+                    }
+                    RetryError::Database { error, attempts }
+                }
+                None => RetryError::TimedOut { attempts },
+            };
+            if attempts > self.policy.max_retries {
+                return Err(error);
+            }
+            thread::sleep(self.policy.backoff(attempts - 1));
+        }
+    }
+
+    /// Makes one attempt. Returns `None` if it timed out.
+    ///
+    /// With a timeout, the read runs on its own thread which is abandoned if it does not finish
+    /// in time.
+    fn attempt<T: Send + 'static>(
+        &self,
+        read: Arc<impl Fn(&D) -> Result<T, D::Error> + Send + Sync + 'static>,
+    ) -> Option<Result<T, D::Error>> {
+        let Some(timeout) = self.policy.timeout else {
+            return Some(read(self.db.as_ref()));
+        };
+        let (sender, receiver) = mpsc::channel();
+        let db = self.db.clone();
+        thread::spawn(move || {
+            // The receiver is gone if the attempt timed out.
+            let _ = sender.send(read(db.as_ref()));
+        });
+        receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl<D> DatabaseRef for RetryingDatabase<D>
+where
+    D: DatabaseRef + Send + Sync + 'static,
+    D::Error: Send + 'static,
+{
+    type Error = RetryError<D::Error>;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.retry(move |db| db.basic_ref(address))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.retry(move |db| db.code_by_hash_ref(code_hash))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.retry(move |db| db.storage_ref(address, index))
+    }
+
+    fn block_hash_ref(&self, number: U256) -> Result<B256, Self::Error> {
+        self.retry(move |db| db.block_hash_ref(number))
+    }
+}
+
+impl<D> Database for RetryingDatabase<D>
+where
+    D: DatabaseRef + Send + Sync + 'static,
+    D::Error: Send + 'static,
+{
+    type Error = RetryError<D::Error>;
+
+    #[inline]
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.basic_ref(address)
+    }
+
+    #[inline]
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.code_by_hash_ref(code_hash)
+    }
+
+    #[inline]
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.storage_ref(address, index)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        self.block_hash_ref(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails the first `failures` reads.
+    #[derive(Debug)]
+    struct FlakyDB {
+        failures: u32,
+        reads: AtomicU32,
+    }
+
+    impl DatabaseRef for FlakyDB {
+        type Error = &'static str;
+
+        fn basic_ref(&self, _address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+            if self.reads.fetch_add(1, Ordering::Relaxed) < self.failures {
+                return Err("connection reset");
+            }
+            Ok(Some(AccountInfo::default()))
+        }
+
+        fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
+            Ok(Bytecode::new())
+        }
+
+        fn storage_ref(&self, _address: Address, _index: U256) -> Result<U256, Self::Error> {
+            Ok(U256::ZERO)
+        }
+
+        fn block_hash_ref(&self, _number: U256) -> Result<B256, Self::Error> {
+            Ok(B256::ZERO)
+        }
+    }
+
+    fn flaky(failures: u32) -> RetryingDatabase<FlakyDB> {
+        RetryingDatabase::new(FlakyDB {
+            failures,
+            reads: AtomicU32::new(0),
+        })
+        .with_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let db = flaky(2);
+        assert!(db.basic_ref(Address::ZERO).unwrap().is_some());
+        assert_eq!(db.db().reads.load(Ordering::Relaxed), 3);
+
+        let db = flaky(10);
+        assert_eq!(
+            db.basic_ref(Address::ZERO),
+            Err(RetryError::Database {
+                error: "connection reset",
+                attempts: 4
+            })
+        );
+    }
+
+    #[test]
+    fn permanent_errors_are_not_retried() {
+        let db = flaky(2).with_classifier(|_| ErrorClass::Permanent);
+        assert!(db.basic_ref(Address::ZERO).is_err());
+        assert_eq!(db.db().reads.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), policy.max_backoff);
+    }
+}
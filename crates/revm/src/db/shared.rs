@@ -0,0 +1,250 @@
+//! Database handles that can be shared between threads.
+//!
+//! Every [Evm](crate::Evm) owns its database. To let several EVM instances, possibly on
+//! different threads, read from and commit to the same state, give each of them a clone of one
+//! of these handles:
+//! * [SyncDatabase] wraps any database in a mutex,
+//! * [SharedCacheDB] wraps a [CacheDB] in a read-write lock so that cached reads do not block
+//!   each other.
+use super::{AccountState, CacheDB, Database, DatabaseCommit, DatabaseRef};
+use crate::primitives::{Account, AccountInfo, Address, Bytecode, HashMap, B256, U256};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Database shared behind a mutex.
+///
+/// Cloning the handle is cheap, clones access the same database.
+#[derive(Debug, Default)]
+pub struct SyncDatabase<D> {
+    inner: Arc<Mutex<D>>,
+}
+
+impl<D> Clone for SyncDatabase<D> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<D> SyncDatabase<D> {
+    /// Wraps the database.
+    pub fn new(db: D) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(db)),
+        }
+    }
+
+    /// Locks the database.
+    ///
+    /// A database left poisoned by a panicking thread is still returned, as reads and commits
+    /// do not leave it half updated.
+    pub fn lock(&self) -> MutexGuard<'_, D> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<D: Database> Database for SyncDatabase<D> {
+    type Error = D::Error;
+
+    #[inline]
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.lock().basic(address)
+    }
+
+    #[inline]
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.lock().code_by_hash(code_hash)
+    }
+
+    #[inline]
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.lock().storage(address, index)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        self.lock().block_hash(number)
+    }
+}
+
+impl<D: Database> DatabaseRef for SyncDatabase<D> {
+    type Error = D::Error;
+
+    #[inline]
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.lock().basic(address)
+    }
+
+    #[inline]
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.lock().code_by_hash(code_hash)
+    }
+
+    #[inline]
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.lock().storage(address, index)
+    }
+
+    #[inline]
+    fn block_hash_ref(&self, number: U256) -> Result<B256, Self::Error> {
+        self.lock().block_hash(number)
+    }
+}
+
+impl<D: DatabaseCommit> DatabaseCommit for SyncDatabase<D> {
+    #[inline]
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.lock().commit(changes)
+    }
+}
+
+/// [CacheDB] shared behind a read-write lock.
+///
+/// Reads that hit the cache only take the read lock. Misses take the write lock, load the value
+/// from the underlying database and cache it for all clones. Commits take the write lock.
+#[derive(Debug)]
+pub struct SharedCacheDB<ExtDB> {
+    inner: Arc<RwLock<CacheDB<ExtDB>>>,
+}
+
+impl<ExtDB> Clone for SharedCacheDB<ExtDB> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<ExtDB> From<CacheDB<ExtDB>> for SharedCacheDB<ExtDB> {
+    fn from(cache: CacheDB<ExtDB>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(cache)),
+        }
+    }
+}
+
+impl<ExtDB> SharedCacheDB<ExtDB> {
+    /// Creates a shared cache on top of the given database.
+    pub fn new(db: ExtDB) -> Self {
+        CacheDB::new(db).into()
+    }
+
+    /// Locks the cache for reading.
+    pub fn read(&self) -> RwLockReadGuard<'_, CacheDB<ExtDB>> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the cache for writing.
+    pub fn write(&self) -> RwLockWriteGuard<'_, CacheDB<ExtDB>> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the cache if this is the last handle to it.
+    pub fn try_into_inner(self) -> Result<CacheDB<ExtDB>, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|lock| lock.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(|inner| Self { inner })
+    }
+
+    /// Returns the cached storage value, if it is known without reading the underlying
+    /// database.
+    fn cached_storage(cache: &CacheDB<ExtDB>, address: Address, index: U256) -> Option<U256> {
+        let account = cache.accounts.get(&address)?;
+        match account.storage.get(&index) {
+            Some(value) => Some(*value),
+            None => matches!(
+                account.account_state,
+                AccountState::StorageCleared | AccountState::NotExisting
+            )
+            .then_some(U256::ZERO),
+        }
+    }
+}
+
+impl<ExtDB: DatabaseRef> Database for SharedCacheDB<ExtDB> {
+    type Error = ExtDB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(account) = self.read().accounts.get(&address) {
+            return Ok(account.info());
+        }
+        self.write().basic(address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        if let Some(code) = self.read().contracts.get(&code_hash) {
+            return Ok(code.clone());
+        }
+        self.write().code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = Self::cached_storage(&self.read(), address, index) {
+            return Ok(value);
+        }
+        self.write().storage(address, index)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        if let Some(hash) = self.read().block_hashes.get(&number) {
+            return Ok(*hash);
+        }
+        self.write().block_hash(number)
+    }
+}
+
+impl<ExtDB> DatabaseCommit for SharedCacheDB<ExtDB> {
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.write().commit(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::EmptyDB,
+        primitives::{Address, Bytes, TransactTo},
+        Evm,
+    };
+    use std::thread;
+
+    #[test]
+    fn evms_on_threads_share_state() {
+        let contract = Address::with_last_byte(0x10);
+        let db = SharedCacheDB::new(EmptyDB::default());
+        // PUSH1 0x01 PUSH1 0x00 SLOAD ADD PUSH1 0x00 SSTORE STOP
+        let code =
+            Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x54, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        let code = Bytecode::new_raw(code);
+        db.write().insert_account_info(
+            contract,
+            AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+        );
+
+        let handles: Vec<_> = (1..=4u8)
+            .map(|caller| {
+                let db = db.clone();
+                thread::spawn(move || {
+                    let mut evm = Evm::builder()
+                        .with_db(db)
+                        .modify_tx_env(|tx| {
+                            tx.caller = Address::with_last_byte(caller);
+                            tx.transact_to = TransactTo::Call(contract);
+                            tx.gas_limit = 100_000;
+                        })
+                        .build();
+                    evm.transact_commit().unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Every transaction was committed, although their order is not known.
+        let mut db = db;
+        let value = db.storage(contract, U256::ZERO).unwrap();
+        assert!(value >= U256::from(1) && value <= U256::from(4));
+    }
+}
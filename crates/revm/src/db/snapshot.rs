@@ -0,0 +1,394 @@
+//! State snapshots in the Geth genesis `alloc` format.
+//!
+//! A [StateSnapshot] can be created from a [CacheDB], serialized to JSON (with the `serde-json`
+//! feature) or to a compact binary encoding, and loaded back into a [CacheDB].
+use super::{AccountState, CacheDB};
+use crate::primitives::{AccountInfo, Address, Bytecode, Bytes, B256, KECCAK_EMPTY, U256};
+use core::fmt;
+use std::{collections::BTreeMap, vec::Vec};
+
+/// Version byte prepended to the binary encoding of a [StateSnapshot].
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+/// Account entry of a Geth-style genesis `alloc`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenesisAccount {
+    /// Account balance.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub balance: U256,
+    /// Account nonce.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "quantity", skip_serializing_if = "is_zero")
+    )]
+    pub nonce: u64,
+    /// Account code.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Bytes::is_empty")
+    )]
+    pub code: Bytes,
+    /// Non-zero storage slots of the account.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub storage: BTreeMap<B256, B256>,
+}
+
+impl GenesisAccount {
+    /// Returns the [AccountInfo] of this account. Code hash is computed from the code.
+    pub fn account_info(&self) -> AccountInfo {
+        let code = Bytecode::new_raw(self.code.clone());
+        AccountInfo {
+            balance: self.balance,
+            nonce: self.nonce,
+            code_hash: code.hash_slow(),
+            code: Some(code),
+        }
+    }
+}
+
+/// Full account state, keyed by address.
+///
+/// Serializes to the same JSON shape as the `alloc` field of a Geth genesis file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct StateSnapshot {
+    /// Accounts of the snapshot.
+    pub accounts: BTreeMap<Address, GenesisAccount>,
+}
+
+impl StateSnapshot {
+    /// Creates an empty snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the snapshot from Geth genesis `alloc` JSON.
+    #[cfg(feature = "serde-json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Serializes the snapshot to Geth genesis `alloc` JSON.
+    #[cfg(feature = "serde-json")]
+    pub fn to_json(&self) -> Result<std::string::String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Encodes the snapshot in the compact binary format.
+    ///
+    /// Layout (all integers are big-endian):
+    /// `version: u8`, `accounts: u32`, then for every account
+    /// `address: [u8; 20]`, `balance: [u8; 32]`, `nonce: u64`, `code_len: u32`, `code`,
+    /// `slots: u32` followed by `slots` pairs of `key: [u8; 32]`, `value: [u8; 32]`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.push(SNAPSHOT_VERSION);
+        out.extend_from_slice(&(self.accounts.len() as u32).to_be_bytes());
+        for (address, account) in &self.accounts {
+            out.extend_from_slice(address.as_slice());
+            out.extend_from_slice(&account.balance.to_be_bytes::<32>());
+            out.extend_from_slice(&account.nonce.to_be_bytes());
+            out.extend_from_slice(&(account.code.len() as u32).to_be_bytes());
+            out.extend_from_slice(&account.code);
+            out.extend_from_slice(&(account.storage.len() as u32).to_be_bytes());
+            for (key, value) in &account.storage {
+                out.extend_from_slice(key.as_slice());
+                out.extend_from_slice(value.as_slice());
+            }
+        }
+        out
+    }
+
+    /// Decodes the snapshot from the compact binary format produced by [Self::encode].
+    pub fn decode(bytes: &[u8]) -> Result<Self, SnapshotDecodeError> {
+        let mut reader = Reader(bytes);
+        let version = reader.take::<1>()?[0];
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotDecodeError::UnsupportedVersion(version));
+        }
+        let count = reader.u32()?;
+        let mut accounts = BTreeMap::new();
+        for _ in 0..count {
+            let address = Address::new(reader.take::<20>()?);
+            let balance = U256::from_be_bytes(reader.take::<32>()?);
+            let nonce = u64::from_be_bytes(reader.take::<8>()?);
+            let code_len = reader.u32()? as usize;
+            let code = Bytes::copy_from_slice(reader.slice(code_len)?);
+            let slots = reader.u32()?;
+            let mut storage = BTreeMap::new();
+            for _ in 0..slots {
+                let key = B256::new(reader.take::<32>()?);
+                let value = B256::new(reader.take::<32>()?);
+                storage.insert(key, value);
+            }
+            accounts.insert(
+                address,
+                GenesisAccount {
+                    balance,
+                    nonce,
+                    code,
+                    storage,
+                },
+            );
+        }
+        if !reader.0.is_empty() {
+            return Err(SnapshotDecodeError::TrailingBytes);
+        }
+        Ok(Self { accounts })
+    }
+}
+
+impl FromIterator<(Address, GenesisAccount)> for StateSnapshot {
+    fn from_iter<T: IntoIterator<Item = (Address, GenesisAccount)>>(iter: T) -> Self {
+        Self {
+            accounts: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<ExtDB> CacheDB<ExtDB> {
+    /// Exports all cached accounts as a [StateSnapshot].
+    ///
+    /// Only the state held by this cache is exported; the underlying database is not traversed.
+    /// Accounts that are known not to exist and zero storage slots are skipped.
+    pub fn snapshot(&self) -> StateSnapshot {
+        self.accounts
+            .iter()
+            .filter(|(_, account)| account.account_state != AccountState::NotExisting)
+            .map(|(address, account)| {
+                let code = self
+                    .contracts
+                    .get(&account.info.code_hash)
+                    .or(account.info.code.as_ref())
+                    .map(|code| code.original_bytes())
+                    .unwrap_or_default();
+                let storage = account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(key, value)| {
+                        (
+                            B256::from(key.to_be_bytes::<32>()),
+                            B256::from(value.to_be_bytes::<32>()),
+                        )
+                    })
+                    .collect();
+                let account = GenesisAccount {
+                    balance: account.info.balance,
+                    nonce: account.info.nonce,
+                    code,
+                    storage,
+                };
+                (*address, account)
+            })
+            .collect()
+    }
+
+    /// Loads all accounts of the [StateSnapshot] into the cache.
+    ///
+    /// Existing accounts are overwritten. Storage of every imported account is replaced by the
+    /// storage of the snapshot, slots not present in the snapshot read as zero.
+    pub fn insert_snapshot(&mut self, snapshot: StateSnapshot) {
+        for (address, account) in snapshot.accounts {
+            let mut info = account.account_info();
+            if info.code_hash == KECCAK_EMPTY {
+                info.code = Some(Bytecode::new());
+            }
+            self.insert_account_info(address, info);
+
+            let db_account = self.accounts.entry(address).or_default();
+            db_account.account_state = AccountState::StorageCleared;
+            db_account.storage = account
+                .storage
+                .into_iter()
+                .map(|(key, value)| (U256::from_be_bytes(key.0), U256::from_be_bytes(value.0)))
+                .collect();
+        }
+    }
+}
+
+/// Errors that can occur while decoding a binary [StateSnapshot].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SnapshotDecodeError {
+    /// Version byte is not [SNAPSHOT_VERSION].
+    UnsupportedVersion(u8),
+    /// Input ended before the snapshot was fully decoded.
+    UnexpectedEof,
+    /// Input contains bytes after the last account.
+    TrailingBytes,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnapshotDecodeError {}
+
+impl fmt::Display for SnapshotDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            Self::UnexpectedEof => write!(f, "unexpected end of snapshot"),
+            Self::TrailingBytes => write!(f, "trailing bytes after snapshot"),
+        }
+    }
+}
+
+/// Cursor over the binary snapshot.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn slice(&mut self, len: usize) -> Result<&'a [u8], SnapshotDecodeError> {
+        if self.0.len() < len {
+            return Err(SnapshotDecodeError::UnexpectedEof);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], SnapshotDecodeError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.slice(N)?);
+        Ok(out)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotDecodeError> {
+        self.take::<4>().map(u32::from_be_bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Serde helpers for `u64` encoded as a hex (or decimal) string, as Geth does for nonces.
+#[cfg(feature = "serde")]
+mod quantity {
+    use core::fmt;
+    use serde::{de, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{value:#x}"))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        struct QuantityVisitor;
+
+        impl<'de> de::Visitor<'de> for QuantityVisitor {
+            type Value = u64;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a hex or decimal quantity")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+                Ok(value)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+                match value.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => value.parse(),
+                }
+                .map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(QuantityVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{Database, EmptyDB},
+        primitives::{address, b256, bytes},
+    };
+
+    fn snapshot() -> StateSnapshot {
+        let mut storage = BTreeMap::new();
+        storage.insert(
+            b256!("0000000000000000000000000000000000000000000000000000000000000001"),
+            b256!("00000000000000000000000000000000000000000000000000000000000000ff"),
+        );
+        [
+            (
+                address!("0000000000000000000000000000000000000001"),
+                GenesisAccount {
+                    balance: U256::from(1000),
+                    ..Default::default()
+                },
+            ),
+            (
+                address!("0000000000000000000000000000000000000002"),
+                GenesisAccount {
+                    balance: U256::ZERO,
+                    nonce: 1,
+                    code: bytes!("600160005500"),
+                    storage,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let snapshot = snapshot();
+        let encoded = snapshot.encode();
+        assert_eq!(StateSnapshot::decode(&encoded), Ok(snapshot));
+
+        assert_eq!(
+            StateSnapshot::decode(&encoded[..encoded.len() - 1]),
+            Err(SnapshotDecodeError::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn cache_db_roundtrip() {
+        let snapshot = snapshot();
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_snapshot(snapshot.clone());
+
+        let contract = address!("0000000000000000000000000000000000000002");
+        let info = db.basic(contract).unwrap().unwrap();
+        assert_eq!(info.nonce, 1);
+        assert_eq!(
+            db.code_by_hash(info.code_hash).unwrap().original_bytes(),
+            bytes!("600160005500")
+        );
+        assert_eq!(db.storage(contract, U256::from(1)), Ok(U256::from(0xff)));
+        assert_eq!(db.storage(contract, U256::from(2)), Ok(U256::ZERO));
+
+        assert_eq!(db.snapshot(), snapshot);
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[test]
+    fn geth_alloc_json() {
+        let json = r#"{
+            "0x0000000000000000000000000000000000000001": { "balance": "0x3e8" },
+            "0x0000000000000000000000000000000000000002": {
+                "balance": "0",
+                "nonce": "0x1",
+                "code": "0x600160005500",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": "0x00000000000000000000000000000000000000000000000000000000000000ff"
+                }
+            }
+        }"#;
+        let parsed = StateSnapshot::from_json(json).unwrap();
+        assert_eq!(parsed, snapshot());
+
+        let reparsed = StateSnapshot::from_json(&parsed.to_json().unwrap()).unwrap();
+        assert_eq!(reparsed, parsed);
+    }
+}
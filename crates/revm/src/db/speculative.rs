@@ -0,0 +1,233 @@
+//! Database that fetches predicted state in the background while a transaction executes.
+//!
+//! [SpeculativeDB] wraps a slow [DatabaseRef], usually one backed by a remote node. Accounts
+//! and slots passed to [SpeculativeDB::speculate] are fetched by background threads, and reads
+//! made during execution share the result of a fetch that is already in flight instead of
+//! issuing the same request again.
+//!
+//! Predictions usually come from a cheap discovery pass or from the state touched by a previous
+//! execution of the transaction, see [PrefetchHints::from_state].
+use super::{prefetch::PrefetchHints, DatabaseRef};
+use crate::primitives::{AccountInfo, Address, Bytecode, HashMap, B256, U256};
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+    vec::Vec,
+};
+
+/// Shared result of a fetch. `None` if the background fetch failed, in which case the read is
+/// retried in the foreground so that the error reaches the caller.
+type Fetch<V> = Arc<OnceLock<Option<V>>>;
+
+/// Fetches shared between the foreground reads and the background workers.
+#[derive(Debug)]
+struct Fetches<K, V> {
+    entries: Mutex<HashMap<K, Fetch<V>>>,
+}
+
+impl<K, V> Default for Fetches<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> Fetches<K, V> {
+    /// Returns the fetch of `key`, registering it if it is not known yet.
+    fn entry(&self, key: K) -> Fetch<V> {
+        self.entries
+            .lock()
+            .expect("fetches lock poisoned")
+            .entry(key)
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the value of `key`, waiting for an in-flight fetch or fetching it with `fetch`.
+    fn get<E>(&self, key: K, fetch: impl Fn() -> Result<V, E>) -> Result<V, E> {
+        let entry = self.entry(key);
+        let mut error = None;
+        let value = entry.get_or_init(|| fetch().map_err(|e| error = Some(e)).ok());
+        match (value, error) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(error)) => Err(error),
+            // The background fetch failed, repeat it to report the error.
+            (None, None) => fetch(),
+        }
+    }
+}
+
+/// Database that coordinates speculative background fetches with the reads of execution.
+///
+/// Cloning is cheap and clones share their fetches.
+#[derive(Debug)]
+pub struct SpeculativeDB<ExtDB> {
+    db: Arc<ExtDB>,
+    accounts: Arc<Fetches<Address, Option<AccountInfo>>>,
+    storage: Arc<Fetches<(Address, U256), U256>>,
+}
+
+impl<ExtDB> Clone for SpeculativeDB<ExtDB> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            accounts: self.accounts.clone(),
+            storage: self.storage.clone(),
+        }
+    }
+}
+
+impl<ExtDB> SpeculativeDB<ExtDB> {
+    /// Wraps the given database.
+    pub fn new(db: ExtDB) -> Self {
+        Self {
+            db: Arc::new(db),
+            accounts: Default::default(),
+            storage: Default::default(),
+        }
+    }
+
+    /// Returns the wrapped database.
+    pub fn db(&self) -> &ExtDB {
+        &self.db
+    }
+}
+
+impl<ExtDB> SpeculativeDB<ExtDB>
+where
+    ExtDB: DatabaseRef + Send + Sync + 'static,
+{
+    /// Starts fetching the hinted accounts and slots on up to `concurrency` background threads
+    /// and returns immediately.
+    ///
+    /// Errors of background fetches are not reported, the failed read is repeated when
+    /// execution needs it.
+    pub fn speculate(&self, hints: &PrefetchHints, concurrency: usize) {
+        let jobs: Vec<_> = hints
+            .iter()
+            .map(|(address, slots)| (*address, slots.iter().copied().collect::<Vec<_>>()))
+            .collect();
+        if jobs.is_empty() {
+            return;
+        }
+        let concurrency = concurrency.max(1);
+        let chunk_size = (jobs.len() + concurrency - 1) / concurrency;
+        for chunk in jobs.chunks(chunk_size) {
+            let this = self.clone();
+            let chunk = chunk.to_vec();
+            thread::spawn(move || {
+                for (address, slots) in chunk {
+                    let exists = this
+                        .accounts
+                        .entry(address)
+                        .get_or_init(|| this.db.basic_ref(address).ok())
+                        .as_ref()
+                        .map_or(false, Option::is_some);
+                    if !exists {
+                        continue;
+                    }
+                    for slot in slots {
+                        this.storage
+                            .entry((address, slot))
+                            .get_or_init(|| this.db.storage_ref(address, slot).ok());
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl<ExtDB: DatabaseRef> DatabaseRef for SpeculativeDB<ExtDB> {
+    type Error = ExtDB::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.accounts.get(address, || self.db.basic_ref(address))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.storage
+            .get((address, index), || self.db.storage_ref(address, index))
+    }
+
+    fn block_hash_ref(&self, number: U256) -> Result<B256, Self::Error> {
+        self.db.block_hash_ref(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CacheDB, EmptyDB};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the reads that reach the wrapped database.
+    #[derive(Debug)]
+    struct CountingDB {
+        db: CacheDB<EmptyDB>,
+        reads: AtomicUsize,
+    }
+
+    impl DatabaseRef for CountingDB {
+        type Error = core::convert::Infallible;
+
+        fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.db.basic_ref(address)
+        }
+
+        fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+            self.db.code_by_hash_ref(code_hash)
+        }
+
+        fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.db.storage_ref(address, index)
+        }
+
+        fn block_hash_ref(&self, number: U256) -> Result<B256, Self::Error> {
+            self.db.block_hash_ref(number)
+        }
+    }
+
+    #[test]
+    fn reads_share_speculative_fetches() {
+        let address = Address::with_last_byte(1);
+        let mut inner = CountingDB {
+            db: CacheDB::new(EmptyDB::default()),
+            reads: AtomicUsize::new(0),
+        };
+        inner
+            .db
+            .insert_account_info(address, AccountInfo::from_balance(U256::from(3)));
+        inner
+            .db
+            .insert_account_storage(address, U256::from(1), U256::from(2))
+            .unwrap();
+
+        let db = SpeculativeDB::new(inner);
+        db.speculate(
+            &PrefetchHints::from_access_list(&[(address, vec![U256::from(1)])]),
+            4,
+        );
+
+        assert_eq!(
+            db.basic_ref(address).unwrap().unwrap().balance,
+            U256::from(3)
+        );
+        assert_eq!(
+            db.storage_ref(address, U256::from(1)).unwrap(),
+            U256::from(2)
+        );
+        assert_eq!(
+            db.storage_ref(address, U256::from(1)).unwrap(),
+            U256::from(2)
+        );
+        assert_eq!(db.db().reads.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,201 @@
+//! Observation of committed state, for external state commitments.
+//!
+//! [StateCommitDatabase] wraps a database and passes the changes of every commit to a
+//! [StateCommit] observer before committing them. The changes are sorted by address and slot, so
+//! a trie or another commitment engine can apply them incrementally in a deterministic order
+//! instead of diffing whole states.
+use super::{Database, DatabaseCommit};
+use crate::primitives::{Account, AccountInfo, Address, Bytecode, HashMap, State, B256, U256};
+use std::vec::Vec;
+
+/// Change of an account in a commit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountCommit {
+    /// Address of the account.
+    pub address: Address,
+    /// New info of the account, or `None` if it was selfdestructed and removed with its storage.
+    ///
+    /// Empty accounts are reported as they are, it is up to the observer to remove them after
+    /// Spurious Dragon (EIP-161).
+    pub info: Option<AccountInfo>,
+    /// Whether the previous storage of the account was cleared before applying [Self::storage],
+    /// because the account was created or selfdestructed.
+    pub storage_cleared: bool,
+    /// Changed storage slots and their new values, sorted by slot.
+    pub storage: Vec<(U256, U256)>,
+}
+
+impl AccountCommit {
+    /// Returns the changes of the touched accounts of `state`, sorted by address.
+    pub fn from_state(state: &State) -> Vec<Self> {
+        let mut changes: Vec<_> = state
+            .iter()
+            .filter(|(_, account)| account.is_touched())
+            .map(|(address, account)| Self::new(*address, account))
+            .collect();
+        changes.sort_unstable_by_key(|change| change.address);
+        changes
+    }
+
+    fn new(address: Address, account: &Account) -> Self {
+        if account.is_selfdestructed() {
+            return Self {
+                address,
+                info: None,
+                storage_cleared: true,
+                storage: Vec::new(),
+            };
+        }
+        let mut storage: Vec<_> = account
+            .changed_storage_slots()
+            .map(|(slot, value)| (*slot, value.present_value()))
+            .collect();
+        storage.sort_unstable_by_key(|(slot, _)| *slot);
+        Self {
+            address,
+            info: Some(account.info.clone()),
+            storage_cleared: account.is_created(),
+            storage,
+        }
+    }
+}
+
+/// Observer of the changes committed to a [StateCommitDatabase].
+pub trait StateCommit {
+    /// Called with the changes of a commit, sorted by address, before they are committed.
+    fn on_commit(&mut self, changes: &[AccountCommit]);
+}
+
+impl<F: FnMut(&[AccountCommit])> StateCommit for F {
+    fn on_commit(&mut self, changes: &[AccountCommit]) {
+        self(changes)
+    }
+}
+
+/// Database that reports every commit to a [StateCommit] observer, see the
+/// [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct StateCommitDatabase<DB, O> {
+    db: DB,
+    observer: O,
+}
+
+impl<DB, O> StateCommitDatabase<DB, O> {
+    /// Wraps the database, reporting its commits to `observer`.
+    pub fn new(db: DB, observer: O) -> Self {
+        Self { db, observer }
+    }
+
+    /// Returns the wrapped database.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Returns the wrapped database.
+    pub fn db_mut(&mut self) -> &mut DB {
+        &mut self.db
+    }
+
+    /// Returns the observer.
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Returns the observer.
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Returns the wrapped database and the observer.
+    pub fn into_parts(self) -> (DB, O) {
+        (self.db, self.observer)
+    }
+}
+
+impl<DB: Database, O> Database for StateCommitDatabase<DB, O> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.db.basic(address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.db.storage(address, index)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        self.db.block_hash(number)
+    }
+}
+
+impl<DB: DatabaseCommit, O: StateCommit> DatabaseCommit for StateCommitDatabase<DB, O> {
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.observer
+            .on_commit(&AccountCommit::from_state(&changes));
+        self.db.commit(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{Bytes, TransactTo},
+        Evm,
+    };
+
+    #[test]
+    fn report_sorted_changes() {
+        let caller = Address::with_last_byte(1);
+        let contract = Address::with_last_byte(2);
+        let coinbase = Address::with_last_byte(3);
+        // SSTORE(2, 1) SSTORE(1, 1) STOP
+        let code = Bytes::from_static(&[
+            0x60, 0x01, 0x60, 0x02, 0x55, 0x60, 0x01, 0x60, 0x01, 0x55, 0x00,
+        ]);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(caller, AccountInfo::from_balance(U256::from(1_000_000)));
+        let code = Bytecode::new_raw(code);
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(U256::ZERO, 1, code.hash_slow(), code),
+        );
+
+        let mut commits = Vec::new();
+        let mut evm = Evm::builder()
+            .with_db(StateCommitDatabase::new(
+                db,
+                |changes: &[AccountCommit]| commits.push(changes.to_vec()),
+            ))
+            .modify_block_env(|block| block.coinbase = coinbase)
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TransactTo::Call(contract);
+                tx.gas_limit = 100_000;
+            })
+            .build();
+        assert!(evm.transact_commit().unwrap().is_success());
+        drop(evm);
+
+        assert_eq!(commits.len(), 1);
+        let changes = &commits[0];
+        let addresses: Vec<_> = changes.iter().map(|change| change.address).collect();
+        // The beneficiary is touched by the fee payment, even without fees.
+        assert_eq!(addresses, [caller, contract, coinbase]);
+        assert_eq!(changes[0].info.as_ref().unwrap().nonce, 1);
+        assert!(changes[0].storage.is_empty());
+        assert!(!changes[1].storage_cleared);
+        assert_eq!(
+            changes[1].storage,
+            [
+                (U256::from(1), U256::from(1)),
+                (U256::from(2), U256::from(1))
+            ]
+        );
+    }
+}
@@ -677,6 +677,42 @@ impl BundleState {
         }
     }
 
+    /// Returns the value of a storage slot after the first `transition` transitions of the
+    /// reverts, without reverting the bundle.
+    ///
+    /// The present value is walked back through the reverts of the later transitions. A
+    /// `transition` past the retained reverts returns the present value. Returns `None` if the
+    /// slot was not changed by the bundle at that point and its value is the one in the
+    /// database.
+    pub fn storage_at(&self, address: Address, slot: U256, transition: usize) -> Option<U256> {
+        let mut value = self
+            .state
+            .get(&address)
+            .and_then(|account| account.storage_slot(slot));
+        for reverts in self.reverts.iter().skip(transition).rev() {
+            let Some((_, revert)) = reverts.iter().find(|(revert, _)| *revert == address) else {
+                continue;
+            };
+            if revert.account == AccountInfoRevert::DeleteIt {
+                // the account did not exist before the transition.
+                value = Some(U256::ZERO);
+                continue;
+            }
+            match revert.storage.get(&slot) {
+                Some(RevertToSlot::Some(previous)) => value = Some(*previous),
+                Some(RevertToSlot::Destroyed) => {
+                    value = revert
+                        .previous_status
+                        .is_storage_known()
+                        .then_some(U256::ZERO)
+                }
+                None if revert.wipe_storage => value = None,
+                None => (),
+            }
+        }
+        value
+    }
+
     /// Prepends present the state with the given BundleState.
     /// It adds changes from the given state but does not override any existing changes.
     ///
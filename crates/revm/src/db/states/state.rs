@@ -2,10 +2,11 @@ use super::{
     bundle_state::BundleRetention, cache::CacheState, plain_account::PlainStorage, BundleState,
     CacheAccount, StateBuilder, TransitionAccount, TransitionState,
 };
-use crate::db::EmptyDB;
+use crate::db::{EmptyDB, MemoryUsage};
 use revm_interpreter::primitives::{
     db::{Database, DatabaseCommit},
-    hash_map, Account, AccountInfo, Address, Bytecode, HashMap, B256, BLOCK_HASH_HISTORY, U256,
+    hash_map, Account, AccountInfo, Address, Bytecode, HashMap, HashSet, B256, BLOCK_HASH_HISTORY,
+    U256,
 };
 use std::{
     boxed::Box,
@@ -210,6 +211,97 @@ impl<DB: Database> State<DB> {
     pub fn take_bundle(&mut self) -> BundleState {
         core::mem::take(&mut self.bundle_state)
     }
+
+    /// Returns the value of a storage slot after the first `transition` merged transitions,
+    /// falling back to the database for slots the bundle does not know.
+    ///
+    /// Merging the transitions of every transaction with [BundleRetention::Reverts] makes
+    /// `transition` the index of the transaction the value is read before. Transitions that are
+    /// not merged yet are not visible. See [BundleState::storage_at].
+    pub fn storage_at(
+        &mut self,
+        address: Address,
+        slot: U256,
+        transition: usize,
+    ) -> Result<U256, DB::Error> {
+        match self.bundle_state.storage_at(address, slot, transition) {
+            Some(value) => Ok(value),
+            None => self.database.storage(address, slot),
+        }
+    }
+
+    /// Returns the number of cached entries, with an estimate of their memory.
+    ///
+    /// The bundle state is not included, see [Self::bundle_size_hint].
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let cache = &self.cache;
+        MemoryUsage {
+            accounts: cache.accounts.len(),
+            storage_slots: cache
+                .accounts
+                .values()
+                .filter_map(|account| account.account.as_ref())
+                .map(|account| account.storage.len())
+                .sum(),
+            contracts: cache.contracts.len(),
+            code_bytes: cache.contracts.values().map(Bytecode::len).sum(),
+            block_hashes: self.block_hashes.len(),
+            estimated_bytes: 0,
+        }
+        .estimate::<(Address, CacheAccount), (U256, U256), (B256, Bytecode), (u64, B256)>()
+    }
+
+    /// Evicts the cached accounts loaded from the database and not modified since, and the
+    /// contracts no longer used by a cached account.
+    ///
+    /// Accounts inserted with [Self::insert_account] count as loaded, they are evicted too.
+    /// Returns the number of evicted accounts.
+    pub fn evict_untouched(&mut self) -> usize {
+        let cache = &mut self.cache;
+        let len = cache.accounts.len();
+        cache
+            .accounts
+            .retain(|_, account| !account.status.is_not_modified());
+        let used: HashSet<B256> = cache
+            .accounts
+            .values()
+            .filter_map(|account| account.account.as_ref())
+            .map(|account| account.info.code_hash)
+            .collect();
+        cache.contracts.retain(|hash, _| used.contains(hash));
+        len - cache.accounts.len()
+    }
+
+    /// Evicts untouched accounts with cached storage, largest storage first, until at most
+    /// `max_slots` slots are cached, see [Self::evict_untouched].
+    ///
+    /// Storage of modified accounts is kept, even above the limit. Returns the number of
+    /// dropped slots.
+    pub fn cap_storage_cache(&mut self, max_slots: usize) -> usize {
+        let mut cached = self.memory_usage().storage_slots;
+        let mut untouched: Vec<_> = self
+            .cache
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.status.is_not_modified())
+            .filter_map(|(address, account)| Some((account.account.as_ref()?, *address)))
+            .filter(|(account, _)| !account.storage.is_empty())
+            .map(|(account, address)| (account.storage.len(), address))
+            .collect();
+        untouched.sort_unstable();
+        let mut dropped = 0;
+        while cached > max_slots {
+            let Some((slots, address)) = untouched.pop() else {
+                break;
+            };
+            // The storage loaded with an account from the preloaded bundle is not read again,
+            // so the whole account is evicted.
+            self.cache.accounts.remove(&address);
+            cached -= slots;
+            dropped += slots;
+        }
+        dropped
+    }
 }
 
 impl<DB: Database> Database for State<DB> {
@@ -307,6 +399,90 @@ mod tests {
     };
     use revm_interpreter::primitives::{keccak256, StorageSlot};
 
+    #[test]
+    fn storage_at_transition() {
+        use crate::{
+            db::{CacheDB, EmptyDB},
+            primitives::{Bytecode, Bytes, TransactTo},
+            Evm,
+        };
+
+        let caller = Address::with_last_byte(1);
+        let contract = Address::with_last_byte(2);
+        // SSTORE(0, SLOAD(0) + 1)
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            0x60, 0x01, 0x60, 0x00, 0x54, 0x01, 0x60, 0x00, 0x55, 0x00,
+        ]));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(caller, AccountInfo::from_balance(U256::from(1_000_000)));
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(U256::ZERO, 1, code.hash_slow(), code),
+        );
+        db.insert_account_storage(contract, U256::ZERO, U256::from(5))
+            .unwrap();
+        let mut state = State::builder()
+            .with_database(db)
+            .with_bundle_update()
+            .build();
+
+        let mut evm = Evm::builder()
+            .with_db(&mut state)
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TransactTo::Call(contract);
+                tx.gas_limit = 100_000;
+            })
+            .build();
+        for _ in 0..3 {
+            assert!(evm.transact_commit().unwrap().is_success());
+            evm.db_mut().merge_transitions(BundleRetention::Reverts);
+        }
+        drop(evm);
+
+        let values: Vec<_> = (0..=4)
+            .map(|transition| state.storage_at(contract, U256::ZERO, transition).unwrap())
+            .collect();
+        assert_eq!(values, [5, 6, 7, 8, 8].map(U256::from));
+        assert_eq!(
+            state.bundle_state.storage_at(contract, U256::from(1), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn evict_untouched_accounts() {
+        use crate::db::{CacheDB, EmptyDB};
+
+        let (loaded, changed) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(loaded, AccountInfo::from_balance(U256::from(1)));
+        db.insert_account_storage(loaded, U256::ZERO, U256::from(5))
+            .unwrap();
+        let mut state = State::builder().with_database(db).build();
+
+        state.basic(loaded).unwrap();
+        assert_eq!(state.storage(loaded, U256::ZERO), Ok(U256::from(5)));
+        let mut account = Account::from(AccountInfo::from_balance(U256::from(2)));
+        account.mark_touch();
+        state.basic(changed).unwrap();
+        state.commit(HashMap::from_iter([(changed, account)]));
+        assert_eq!(state.memory_usage().storage_slots, 1);
+
+        assert_eq!(state.cap_storage_cache(0), 1);
+        assert_eq!(state.evict_untouched(), 0);
+        assert_eq!(state.memory_usage().accounts, 1);
+
+        // Evicted accounts are loaded again.
+        assert_eq!(state.basic(loaded).unwrap().unwrap().balance, U256::from(1));
+        assert_eq!(state.storage(loaded, U256::ZERO), Ok(U256::from(5)));
+        assert_eq!(state.evict_untouched(), 1);
+        assert_eq!(
+            state.basic(changed).unwrap().unwrap().balance,
+            U256::from(2)
+        );
+    }
+
     #[test]
     fn block_hash_cache() {
         let mut state = State::builder().build();
@@ -0,0 +1,425 @@
+//! Execution determinism checks.
+//!
+//! A [DeterminismChecker] executes the same transaction on the same pre-state several times and
+//! compares the [ExecutionRecord] of every run byte for byte: the execution result, the state
+//! diff, the post-state and a trace of every executed instruction. Runs can be spread over
+//! threads, and records can be stored and compared with the ones of a build with other features
+//! enabled, to catch nondeterminism introduced by caches or parallel execution.
+use crate::{
+    db::{CacheDB, EmptyDB, StateSnapshot},
+    fixture::ExecutionFixture,
+    inspector_handle_register,
+    interpreter::{
+        CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter, InterpreterResult,
+    },
+    primitives::{keccak256, Address, EVMError, Env, Log, SpecId, State as EvmState, B256},
+    Database, DatabaseCommit, Evm, EvmContext, Inspector, LogPosition,
+};
+use core::{convert::Infallible, fmt};
+use std::{boxed::Box, vec::Vec};
+
+/// Part of an [ExecutionRecord].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RecordSection {
+    /// The execution result.
+    Result,
+    /// The accounts changed by the transaction.
+    StateDiff,
+    /// The state after the transaction was committed.
+    PostState,
+    /// The executed instructions, frames and logs.
+    Trace,
+}
+
+/// Canonical byte encoding of one execution of a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExecutionRecord {
+    /// Encoded execution result.
+    pub result: Vec<u8>,
+    /// Changed accounts, sorted by address, with their storage sorted by key.
+    pub state_diff: Vec<u8>,
+    /// Post-state in the binary [StateSnapshot] encoding.
+    pub post: Vec<u8>,
+    /// Executed instructions, frame results and logs, in execution order.
+    pub trace: Vec<u8>,
+}
+
+impl ExecutionRecord {
+    /// Returns the bytes of `section`.
+    pub fn section(&self, section: RecordSection) -> &[u8] {
+        match section {
+            RecordSection::Result => &self.result,
+            RecordSection::StateDiff => &self.state_diff,
+            RecordSection::PostState => &self.post,
+            RecordSection::Trace => &self.trace,
+        }
+    }
+
+    /// Returns the first section that differs from `other` and the offset of its first
+    /// differing byte.
+    pub fn diff(&self, other: &Self) -> Option<(RecordSection, usize)> {
+        [
+            RecordSection::Result,
+            RecordSection::StateDiff,
+            RecordSection::PostState,
+            RecordSection::Trace,
+        ]
+        .into_iter()
+        .find_map(|section| {
+            let (a, b) = (self.section(section), other.section(section));
+            if a == b {
+                return None;
+            }
+            let offset = a
+                .iter()
+                .zip(b)
+                .position(|(a, b)| a != b)
+                .unwrap_or(a.len().min(b.len()));
+            Some((section, offset))
+        })
+    }
+
+    /// Returns the hash of all sections, to store a record compactly.
+    pub fn digest(&self) -> B256 {
+        let mut bytes = Vec::new();
+        for section in [&self.result, &self.state_diff, &self.post, &self.trace] {
+            bytes.extend_from_slice(&(section.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(section);
+        }
+        keccak256(bytes)
+    }
+}
+
+/// Error of a [DeterminismChecker].
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeterminismError {
+    /// A run could not be executed.
+    Evm(EVMError<Infallible>),
+    /// The record of a run differs from the one of the first run, or from the expected one.
+    Mismatch {
+        /// Index of the differing run.
+        run: usize,
+        /// First differing section.
+        section: RecordSection,
+        /// Offset of the first differing byte in the section.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for DeterminismError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm(error) => write!(f, "execution failed: {error}"),
+            Self::Mismatch {
+                run,
+                section,
+                offset,
+            } => write!(f, "run {run} differs in {section:?} at byte {offset}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DeterminismError {}
+
+/// Executes a transaction repeatedly and checks that every run produces the same record.
+#[derive(Clone, Debug)]
+pub struct DeterminismChecker {
+    spec_id: SpecId,
+    env: Env,
+    pre: StateSnapshot,
+    runs: usize,
+    threads: usize,
+}
+
+impl DeterminismChecker {
+    /// Creates a checker executing the transaction of `env` twice on `pre`.
+    pub fn new(spec_id: SpecId, env: Env, pre: StateSnapshot) -> Self {
+        Self {
+            spec_id,
+            env,
+            pre,
+            runs: 2,
+            threads: 1,
+        }
+    }
+
+    /// Creates a checker for the transaction and pre-state of `fixture`.
+    pub fn from_fixture(fixture: &ExecutionFixture) -> Self {
+        Self::new(fixture.spec_id, fixture.env.clone(), fixture.pre.clone())
+    }
+
+    /// Sets the number of runs, at least two.
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs.max(2);
+        self
+    }
+
+    /// Spreads the runs over `threads` threads executing concurrently.
+    #[cfg(feature = "std")]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Executes the transaction once and returns its record.
+    pub fn record(&self) -> Result<ExecutionRecord, EVMError<Infallible>> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_snapshot(self.pre.clone());
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .with_external_context(TraceRecorder::default())
+            .with_env(Box::new(self.env.clone()))
+            .with_spec_id(self.spec_id)
+            .append_handler_register(inspector_handle_register)
+            .build();
+        let output = evm.transact()?;
+        let state_diff = encode_state(&output.state);
+        evm.db_mut().commit(output.state);
+        Ok(ExecutionRecord {
+            result: format!("{:?}", output.result).into_bytes(),
+            state_diff,
+            post: evm.db().snapshot().encode(),
+            trace: core::mem::take(&mut evm.context.external.trace),
+        })
+    }
+
+    /// Executes all runs and checks that their records are identical.
+    ///
+    /// Returns the record of the first run.
+    pub fn check(&self) -> Result<ExecutionRecord, DeterminismError> {
+        let records = self.records()?;
+        let first = &records[0];
+        for (run, record) in records.iter().enumerate().skip(1) {
+            if let Some((section, offset)) = first.diff(record) {
+                return Err(DeterminismError::Mismatch {
+                    run,
+                    section,
+                    offset,
+                });
+            }
+        }
+        Ok(records.into_iter().next().unwrap())
+    }
+
+    /// Executes all runs and checks that their records equal `expected`, usually recorded by
+    /// another build.
+    pub fn check_against(&self, expected: &ExecutionRecord) -> Result<(), DeterminismError> {
+        for (run, record) in self.records()?.iter().enumerate() {
+            if let Some((section, offset)) = expected.diff(record) {
+                return Err(DeterminismError::Mismatch {
+                    run,
+                    section,
+                    offset,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes all runs, in run order.
+    fn records(&self) -> Result<Vec<ExecutionRecord>, DeterminismError> {
+        #[cfg(feature = "std")]
+        if self.threads > 1 {
+            let per_thread = self.runs.div_ceil(self.threads);
+            let chunks = std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..self.runs)
+                    .step_by(per_thread)
+                    .map(|start| {
+                        let end = (start + per_thread).min(self.runs);
+                        scope.spawn(move || {
+                            (start..end)
+                                .map(|_| self.record())
+                                .collect::<Result<Vec<_>, _>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("determinism run panicked"))
+                    .collect::<Vec<_>>()
+            });
+            let mut records = Vec::with_capacity(self.runs);
+            for chunk in chunks {
+                records.extend(chunk.map_err(DeterminismError::Evm)?);
+            }
+            return Ok(records);
+        }
+        (0..self.runs)
+            .map(|_| self.record().map_err(DeterminismError::Evm))
+            .collect()
+    }
+}
+
+/// Encodes the changed accounts sorted by address, with their changed slots sorted by key.
+fn encode_state(state: &EvmState) -> Vec<u8> {
+    let mut accounts: Vec<_> = state.iter().collect();
+    accounts.sort_unstable_by_key(|(address, _)| **address);
+    let mut out = Vec::new();
+    for (address, account) in accounts {
+        out.extend_from_slice(address.as_slice());
+        out.push(account.status.bits());
+        out.extend_from_slice(&account.info.balance.to_be_bytes::<32>());
+        out.extend_from_slice(&account.info.nonce.to_be_bytes());
+        out.extend_from_slice(account.info.code_hash.as_slice());
+        let mut storage: Vec<_> = account.storage.iter().collect();
+        storage.sort_unstable_by_key(|(key, _)| **key);
+        out.extend_from_slice(&(storage.len() as u32).to_be_bytes());
+        for (key, slot) in storage {
+            out.extend_from_slice(&key.to_be_bytes::<32>());
+            out.extend_from_slice(&slot.original_value().to_be_bytes::<32>());
+            out.extend_from_slice(&slot.present_value().to_be_bytes::<32>());
+        }
+    }
+    out
+}
+
+/// Inspector encoding every step, frame result and log.
+#[derive(Debug, Default)]
+struct TraceRecorder {
+    trace: Vec<u8>,
+}
+
+impl TraceRecorder {
+    fn frame_end(&mut self, result: &InterpreterResult, address: Option<Address>) {
+        self.trace.push(b'R');
+        self.trace.push(result.result as u8);
+        self.trace.push(result.custom_code);
+        self.trace
+            .extend_from_slice(&result.gas.remaining().to_be_bytes());
+        self.trace
+            .extend_from_slice(&result.gas.refunded().to_be_bytes());
+        self.trace
+            .extend_from_slice(address.unwrap_or_default().as_slice());
+        self.trace
+            .extend_from_slice(&(result.output.len() as u32).to_be_bytes());
+        self.trace.extend_from_slice(&result.output);
+    }
+}
+
+impl<DB: Database> Inspector<DB> for TraceRecorder {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        let stack = interp.stack().data();
+        self.trace.push(b'S');
+        self.trace
+            .extend_from_slice(&(context.journaled_state.depth() as u32).to_be_bytes());
+        self.trace
+            .extend_from_slice(&(interp.program_counter() as u32).to_be_bytes());
+        self.trace.push(interp.current_opcode());
+        self.trace
+            .extend_from_slice(&interp.gas().remaining().to_be_bytes());
+        self.trace
+            .extend_from_slice(&(interp.shared_memory.len() as u32).to_be_bytes());
+        self.trace
+            .extend_from_slice(&(stack.len() as u32).to_be_bytes());
+        if let Some(top) = stack.last() {
+            self.trace.extend_from_slice(&top.to_be_bytes::<32>());
+        }
+    }
+
+    fn log(&mut self, _context: &mut EvmContext<DB>, log: &Log, position: &LogPosition) {
+        self.trace.push(b'L');
+        self.trace
+            .extend_from_slice(&(position.index as u32).to_be_bytes());
+        self.trace.extend_from_slice(log.address.as_slice());
+        for topic in log.data.topics() {
+            self.trace.extend_from_slice(topic.as_slice());
+        }
+        self.trace
+            .extend_from_slice(&(log.data.data.len() as u32).to_be_bytes());
+        self.trace.extend_from_slice(&log.data.data);
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.frame_end(&outcome.result, None);
+        outcome
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.frame_end(&outcome.result, outcome.address);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::GenesisAccount,
+        primitives::{bytes, TransactTo, U256},
+    };
+
+    fn checker() -> DeterminismChecker {
+        let caller = Address::with_last_byte(1);
+        let contract = Address::with_last_byte(0x10);
+        let pre = [
+            (
+                caller,
+                GenesisAccount {
+                    balance: U256::from(1_000_000),
+                    ..Default::default()
+                },
+            ),
+            (
+                contract,
+                GenesisAccount {
+                    // SSTORE(0, 1) LOG1(0, 0, 2) SSTORE(1, TIMESTAMP) STOP
+                    code: bytes!("6001600055600260006000a142600155"),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let mut env = Env::default();
+        env.tx.caller = caller;
+        env.tx.transact_to = TransactTo::Call(contract);
+        env.tx.gas_limit = 100_000;
+        env.block.timestamp = U256::from(7);
+        DeterminismChecker::new(SpecId::CANCUN, env, pre)
+    }
+
+    #[test]
+    fn identical_runs() {
+        let record = checker().with_runs(3).check().unwrap();
+        assert!(!record.trace.is_empty());
+        assert_eq!(
+            StateSnapshot::decode(&record.post).unwrap().accounts[&Address::with_last_byte(0x10)]
+                .storage
+                .len(),
+            2
+        );
+        assert_eq!(
+            checker().with_threads(2).with_runs(4).check(),
+            Ok(record.clone())
+        );
+    }
+
+    #[test]
+    fn different_build() {
+        let checker = checker();
+        let mut expected = checker.record().unwrap();
+        assert_eq!(checker.check_against(&expected), Ok(()));
+
+        expected.trace[1] ^= 1;
+        assert_eq!(
+            checker.check_against(&expected),
+            Err(DeterminismError::Mismatch {
+                run: 0,
+                section: RecordSection::Trace,
+                offset: 1,
+            })
+        );
+        assert_ne!(expected.digest(), checker.record().unwrap().digest());
+    }
+}
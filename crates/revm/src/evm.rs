@@ -8,8 +8,8 @@ use crate::{
     },
     primitives::{
         specification::SpecId, Address, BlockEnv, Bytecode, CfgEnv, EVMError, EVMResult, Env,
-        EnvWithHandlerCfg, ExecutionResult, HandlerCfg, Log, ResultAndState, TransactTo, TxEnv,
-        B256, U256,
+        EnvWithHandlerCfg, ExecutionResult, ExecutionStage, HandlerCfg, Log, ResultAndState,
+        TransactTo, TxEnv, B256, U256,
     },
     Context, ContextWithHandlerCfg, Frame, FrameOrResult, FrameResult,
 };
@@ -28,6 +28,8 @@ pub struct Evm<'a, EXT, DB: Database> {
     /// Handler is a component of the of EVM that contains all the logic. Handler contains specification id
     /// and it different depending on the specified fork.
     pub handler: Handler<'a, Self, EXT, DB>,
+    /// Memory buffer kept between transactions, so that its allocation is reused.
+    shared_memory: Option<SharedMemory>,
 }
 
 impl<EXT, DB> fmt::Debug for Evm<'_, EXT, DB>
@@ -46,7 +48,7 @@ where
 impl<EXT, DB: Database + DatabaseCommit> Evm<'_, EXT, DB> {
     /// Commit the changes to the database.
     pub fn transact_commit(&mut self) -> Result<ExecutionResult, EVMError<DB::Error>> {
-        let ResultAndState { result, state } = self.transact()?;
+        let ResultAndState { result, state, .. } = self.transact()?;
         self.context.evm.db.commit(state);
         Ok(result)
     }
@@ -66,7 +68,11 @@ impl<'a, EXT, DB: Database> Evm<'a, EXT, DB> {
         handler: Handler<'a, Self, EXT, DB>,
     ) -> Evm<'a, EXT, DB> {
         context.evm.journaled_state.set_spec_id(handler.cfg.spec_id);
-        Evm { context, handler }
+        Evm {
+            context,
+            handler,
+            shared_memory: None,
+        }
     }
 
     /// Allow for evm setting to be modified by feeding current evm
@@ -77,6 +83,14 @@ impl<'a, EXT, DB: Database> Evm<'a, EXT, DB> {
 }
 
 impl<EXT, DB: Database> Evm<'_, EXT, DB> {
+    /// Sets the memory buffer used by the next transaction, for example one allocated with
+    /// [SharedMemory::with_capacity].
+    ///
+    /// The buffer is kept after the transaction ends and reused by the following ones.
+    pub fn set_shared_memory(&mut self, shared_memory: SharedMemory) {
+        self.shared_memory = Some(shared_memory);
+    }
+
     /// Returns specification (hardfork) that the EVM is instanced with.
     ///
     /// SpecId depends on the handler.
@@ -177,7 +191,8 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
             .initial_tx_gas(&self.context.evm.env)?;
         self.handler
             .validation()
-            .tx_against_state(&mut self.context)?;
+            .tx_against_state(&mut self.context)
+            .map_err(|e| e.with_stage(ExecutionStage::Validation))?;
 
         let output = self.transact_preverified_inner(initial_gas_spend);
         self.handler.post_execution().end(&mut self.context, output)
@@ -248,11 +263,10 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
         let mut call_stack: Vec<Frame> = Vec::with_capacity(1025);
         call_stack.push(first_frame);
 
+        let mut shared_memory = self.shared_memory.take().unwrap_or_default();
+        shared_memory.clear();
         #[cfg(feature = "memory_limit")]
-        let mut shared_memory =
-            SharedMemory::new_with_memory_limit(self.context.evm.env.cfg.memory_limit);
-        #[cfg(not(feature = "memory_limit"))]
-        let mut shared_memory = SharedMemory::new();
+        shared_memory.set_memory_limit(self.context.evm.env.cfg.memory_limit);
 
         shared_memory.new_context();
 
@@ -263,10 +277,25 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
             // run interpreter
             let interpreter = &mut stack_frame.frame_data_mut().interpreter;
             let next_action = interpreter.run(shared_memory, instruction_table, self);
+            #[cfg(feature = "instrumentation")]
+            self.context
+                .evm
+                .counters
+                .merge(&core::mem::take(&mut interpreter.counters));
 
             // take error and break the loop if there is any.
             // This error is set From Interpreter when it's interacting with Host.
-            self.context.evm.take_error()?;
+            // The instruction that failed is the one before the instruction pointer.
+            self.context.evm.take_error().map_err(|e| {
+                match interpreter
+                    .program_counter()
+                    .checked_sub(1)
+                    .and_then(|pc| interpreter.contract.bytecode.bytecode().get(pc))
+                {
+                    Some(opcode) => e.with_opcode(*opcode),
+                    None => e,
+                }
+            })?;
             // take shared memory back.
             shared_memory = interpreter.take_memory();
 
@@ -308,6 +337,7 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
                 FrameOrResult::Result(result) => {
                     let Some(top_frame) = call_stack.last_mut() else {
                         // Break the look if there are no more frames.
+                        self.shared_memory = Some(shared_memory);
                         return Ok(result);
                     };
                     stack_frame = top_frame;
@@ -330,18 +360,27 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
 
     /// Transact pre-verified transaction.
     fn transact_preverified_inner(&mut self, initial_gas_spend: u64) -> EVMResult<DB::Error> {
+        // Counters left by a transaction that failed with an error are dropped.
+        #[cfg(feature = "instrumentation")]
+        {
+            self.context.evm.counters = Default::default();
+        }
         let ctx = &mut self.context;
         let pre_exec = self.handler.pre_execution();
 
         // load access list and beneficiary if needed.
-        pre_exec.load_accounts(ctx)?;
+        pre_exec
+            .load_accounts(ctx)
+            .map_err(|e| e.with_stage(ExecutionStage::PreExecution))?;
 
         // load precompiles
         let precompiles = pre_exec.load_precompiles();
         ctx.evm.set_precompiles(precompiles);
 
         // deduce caller balance with its limit.
-        pre_exec.deduct_caller(ctx)?;
+        pre_exec
+            .deduct_caller(ctx)
+            .map_err(|e| e.with_stage(ExecutionStage::PreExecution))?;
 
         let gas_limit = ctx.evm.env.tx.gas_limit - initial_gas_spend;
 
@@ -351,16 +390,19 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
             TransactTo::Call(_) => exec.call(
                 ctx,
                 CallInputs::new_boxed(&ctx.evm.env.tx, gas_limit).unwrap(),
-            )?,
+            ),
             TransactTo::Create(_) => exec.create(
                 ctx,
                 CreateInputs::new_boxed(&ctx.evm.env.tx, gas_limit).unwrap(),
-            )?,
-        };
+            ),
+        }
+        .map_err(|e| e.with_stage(ExecutionStage::Execution))?;
 
         // Starts the main running loop.
         let mut result = match first_frame_or_result {
-            FrameOrResult::Frame(first_frame) => self.start_the_loop(first_frame)?,
+            FrameOrResult::Frame(first_frame) => self
+                .start_the_loop(first_frame)
+                .map_err(|e| e.with_stage(ExecutionStage::Execution))?,
             FrameOrResult::Result(result) => result,
         };
 
@@ -369,15 +411,19 @@ impl<EXT, DB: Database> Evm<'_, EXT, DB> {
         // handle output of call/create calls.
         self.handler
             .execution()
-            .last_frame_return(ctx, &mut result)?;
+            .last_frame_return(ctx, &mut result)
+            .map_err(|e| e.with_stage(ExecutionStage::Execution))?;
 
         let post_exec = self.handler.post_execution();
-        // Reimburse the caller
-        post_exec.reimburse_caller(ctx, result.gas())?;
-        // Reward beneficiary
-        post_exec.reward_beneficiary(ctx, result.gas())?;
-        // Returns output of transaction.
-        post_exec.output(ctx, result)
+        let post_execution = || -> EVMResult<DB::Error> {
+            // Reimburse the caller
+            post_exec.reimburse_caller(ctx, result.gas())?;
+            // Reward beneficiary
+            post_exec.reward_beneficiary(ctx, result.gas())?;
+            // Returns output of transaction.
+            post_exec.output(ctx, result)
+        };
+        post_execution().map_err(|e| e.with_stage(ExecutionStage::PostExecution))
     }
 }
 
@@ -467,4 +513,15 @@ impl<EXT, DB: Database> Host for Evm<'_, EXT, DB> {
             .map_err(|e| self.context.evm.error = Err(e))
             .ok()
     }
+
+    fn forwarded_gas(&mut self, remaining: u64, requested: u64) -> u64 {
+        self.handler
+            .execution
+            .forwarded_gas(&mut self.context, remaining, requested)
+    }
+
+    #[cfg(feature = "invariant-checks")]
+    fn check_invariants(&self) {
+        self.context.evm.journaled_state.check_invariants();
+    }
 }
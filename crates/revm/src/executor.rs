@@ -0,0 +1,162 @@
+use crate::{
+    db::{Database, DatabaseCommit},
+    primitives::{EVMError, ExecutionResult, TxEnv},
+    Evm,
+};
+
+/// Outcome of one transaction of a block, see [BlockExecutor].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutedTx {
+    /// Index of the transaction in the block.
+    pub index: usize,
+    /// Execution result of the transaction.
+    pub result: ExecutionResult,
+    /// Gas used by the block up to and including this transaction.
+    pub cumulative_gas_used: u64,
+}
+
+/// Executes the transactions of a block one by one, yielding each result as soon as the
+/// transaction is committed.
+///
+/// Results can be persisted while the rest of the block is still executing. The iterator ends
+/// after the last transaction or after the first error, which leaves the state of the failed
+/// transaction uncommitted.
+pub struct BlockExecutor<'e, 'a, EXT, DB: Database, I> {
+    evm: &'e mut Evm<'a, EXT, DB>,
+    txs: I,
+    index: usize,
+    cumulative_gas_used: u64,
+    failed: bool,
+}
+
+impl<'e, 'a, EXT, DB, I> BlockExecutor<'e, 'a, EXT, DB, I>
+where
+    DB: Database + DatabaseCommit,
+    I: Iterator<Item = TxEnv>,
+{
+    /// Creates an executor running `txs` on the environment and database of `evm`.
+    pub fn new(evm: &'e mut Evm<'a, EXT, DB>, txs: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            evm,
+            txs: txs.into_iter(),
+            index: 0,
+            cumulative_gas_used: 0,
+            failed: false,
+        }
+    }
+
+    /// Returns the gas used by the transactions executed so far.
+    pub fn cumulative_gas_used(&self) -> u64 {
+        self.cumulative_gas_used
+    }
+
+    /// Returns the EVM the block is executed with.
+    pub fn evm(&mut self) -> &mut Evm<'a, EXT, DB> {
+        self.evm
+    }
+}
+
+impl<EXT, DB, I> Iterator for BlockExecutor<'_, '_, EXT, DB, I>
+where
+    DB: Database + DatabaseCommit,
+    I: Iterator<Item = TxEnv>,
+{
+    type Item = Result<ExecutedTx, EVMError<DB::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        *self.evm.tx_mut() = self.txs.next()?;
+        let result = match self.evm.transact_commit() {
+            Ok(result) => result,
+            Err(error) => {
+                self.failed = true;
+                return Some(Err(error));
+            }
+        };
+        self.cumulative_gas_used += result.gas_used();
+        let executed = ExecutedTx {
+            index: self.index,
+            result,
+            cumulative_gas_used: self.cumulative_gas_used,
+        };
+        self.index += 1;
+        Some(Ok(executed))
+    }
+}
+
+impl<EXT, DB, I> core::iter::FusedIterator for BlockExecutor<'_, '_, EXT, DB, I>
+where
+    DB: Database + DatabaseCommit,
+    I: core::iter::FusedIterator<Item = TxEnv>,
+{
+}
+
+impl<'a, EXT, DB: Database + DatabaseCommit> Evm<'a, EXT, DB> {
+    /// Returns an iterator executing and committing the transactions one by one.
+    ///
+    /// See [BlockExecutor].
+    pub fn execute_block<I: IntoIterator<Item = TxEnv>>(
+        &mut self,
+        txs: I,
+    ) -> BlockExecutor<'_, 'a, EXT, DB, I::IntoIter> {
+        BlockExecutor::new(self, txs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Address, InvalidTransaction, TransactTo, U256},
+    };
+    use std::vec::Vec;
+
+    fn transfer(nonce: u64) -> TxEnv {
+        TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(Address::with_last_byte(2)),
+            value: U256::from(1),
+            gas_limit: 21_000,
+            nonce: Some(nonce),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn streams_results_until_error() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            Address::with_last_byte(1),
+            AccountInfo::from_balance(U256::from(10)),
+        );
+        let mut evm = Evm::builder().with_db(db).build();
+
+        // The third transaction reuses a nonce, execution stops there.
+        let txs = [transfer(0), transfer(1), transfer(1), transfer(2)];
+        let mut executor = evm.execute_block(txs);
+        let first = executor.next().unwrap().unwrap();
+        assert_eq!((first.index, first.cumulative_gas_used), (0, 21_000));
+        assert_eq!(executor.cumulative_gas_used(), 21_000);
+
+        let rest: Vec<_> = executor.collect();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].as_ref().unwrap().cumulative_gas_used, 42_000);
+        assert!(matches!(
+            rest[1],
+            Err(EVMError::Transaction(
+                InvalidTransaction::NonceTooLow { .. }
+            ))
+        ));
+
+        let balance = evm
+            .db_mut()
+            .load_account(Address::with_last_byte(2))
+            .unwrap()
+            .info
+            .balance;
+        assert_eq!(balance, U256::from(2));
+    }
+}
@@ -0,0 +1,280 @@
+//! Reproducible execution fixtures.
+//!
+//! An [ExecutionFixture] bundles everything needed to replay a transaction: the environment,
+//! the spec, the pre-state and the expected result and post-state. Fixtures can be stored as
+//! JSON and shared as regression cases.
+//!
+//! The `pre` and `post` fields use the Geth genesis `alloc` format, the same format used by the
+//! `pre` and `post` sections of the Ethereum execution spec tests.
+use crate::{
+    db::{CacheDB, EmptyDB, GenesisAccount, StateSnapshot},
+    primitives::{Address, EVMError, Env, ExecutionResult, SpecId},
+    Evm,
+};
+use core::{convert::Infallible, fmt};
+use std::{boxed::Box, vec::Vec};
+
+/// Environment, pre-state and expected outcome of one transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionFixture {
+    /// Spec the transaction is executed with.
+    pub spec_id: SpecId,
+    /// Environment of the transaction.
+    pub env: Env,
+    /// State before the transaction.
+    pub pre: StateSnapshot,
+    /// Expected execution result. Not checked if `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub result: Option<ExecutionResult>,
+    /// Expected state after the transaction. Not checked if `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub post: Option<StateSnapshot>,
+}
+
+/// Result and post-state produced by running an [ExecutionFixture].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixtureOutcome {
+    /// Execution result.
+    pub result: ExecutionResult,
+    /// State after the transaction was committed.
+    pub post: StateSnapshot,
+}
+
+/// Difference between the expected and the actual outcome of a fixture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FixtureMismatch {
+    /// The execution result differs.
+    Result {
+        /// Expected result.
+        expected: ExecutionResult,
+        /// Actual result.
+        got: ExecutionResult,
+    },
+    /// An account of the post-state differs. `None` means the account is absent.
+    Account {
+        /// Address of the account.
+        address: Address,
+        /// Expected account.
+        expected: Option<GenesisAccount>,
+        /// Actual account.
+        got: Option<GenesisAccount>,
+    },
+}
+
+impl fmt::Display for FixtureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Result { expected, got } => {
+                write!(f, "result mismatch: expected {expected:?}, got {got:?}")
+            }
+            Self::Account {
+                address,
+                expected,
+                got,
+            } => write!(
+                f,
+                "post-state mismatch for {address}: expected {expected:?}, got {got:?}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FixtureMismatch {}
+
+/// Errors of the fixture assertion runner.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FixtureError {
+    /// The transaction could not be executed.
+    Evm(EVMError<Infallible>),
+    /// The outcome differs from the expectation.
+    Mismatch(Vec<FixtureMismatch>),
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm(error) => write!(f, "execution failed: {error}"),
+            Self::Mismatch(mismatches) => {
+                write!(f, "{} mismatches", mismatches.len())?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {mismatch}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FixtureError {}
+
+impl ExecutionFixture {
+    /// Creates a fixture without expectations.
+    pub fn new(spec_id: SpecId, env: Env, pre: StateSnapshot) -> Self {
+        Self {
+            spec_id,
+            env,
+            pre,
+            result: None,
+            post: None,
+        }
+    }
+
+    /// Parses a fixture from JSON.
+    #[cfg(feature = "serde-json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Serializes the fixture to JSON.
+    #[cfg(feature = "serde-json")]
+    pub fn to_json(&self) -> Result<std::string::String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Loads a fixture from a JSON file.
+    #[cfg(all(feature = "std", feature = "serde-json"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(Self::from_json(&json)?)
+    }
+
+    /// Saves the fixture to a JSON file.
+    #[cfg(all(feature = "std", feature = "serde-json"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json()?)
+    }
+
+    /// Executes the transaction on the pre-state and commits it.
+    pub fn run(&self) -> Result<FixtureOutcome, EVMError<Infallible>> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_snapshot(self.pre.clone());
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .with_env(Box::new(self.env.clone()))
+            .with_spec_id(self.spec_id)
+            .build();
+        let result = evm.transact_commit()?;
+        Ok(FixtureOutcome {
+            result,
+            post: evm.context.evm.db.snapshot(),
+        })
+    }
+
+    /// Runs the fixture and stores its outcome as the expectation.
+    pub fn record(&mut self) -> Result<(), EVMError<Infallible>> {
+        let outcome = self.run()?;
+        self.result = Some(outcome.result);
+        self.post = Some(outcome.post);
+        Ok(())
+    }
+
+    /// Runs the fixture and compares the outcome with the expectations.
+    pub fn check(&self) -> Result<FixtureOutcome, FixtureError> {
+        let outcome = self.run().map_err(FixtureError::Evm)?;
+        let mut mismatches = Vec::new();
+        if let Some(expected) = &self.result {
+            if *expected != outcome.result {
+                mismatches.push(FixtureMismatch::Result {
+                    expected: expected.clone(),
+                    got: outcome.result.clone(),
+                });
+            }
+        }
+        if let Some(expected) = &self.post {
+            let addresses = expected.accounts.keys().chain(
+                outcome
+                    .post
+                    .accounts
+                    .keys()
+                    .filter(|address| !expected.accounts.contains_key(address)),
+            );
+            for address in addresses {
+                let expected = expected.accounts.get(address);
+                let got = outcome.post.accounts.get(address);
+                if expected != got {
+                    mismatches.push(FixtureMismatch::Account {
+                        address: *address,
+                        expected: expected.cloned(),
+                        got: got.cloned(),
+                    });
+                }
+            }
+        }
+        if mismatches.is_empty() {
+            Ok(outcome)
+        } else {
+            Err(FixtureError::Mismatch(mismatches))
+        }
+    }
+
+    /// Runs the fixture and panics with all mismatches if the outcome differs from the
+    /// expectations.
+    #[track_caller]
+    pub fn assert(&self) -> FixtureOutcome {
+        match self.check() {
+            Ok(outcome) => outcome,
+            Err(error) => panic!("fixture failed: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{address, bytes, TransactTo, U256};
+
+    fn fixture() -> ExecutionFixture {
+        let caller = address!("0000000000000000000000000000000000000001");
+        let contract = address!("0000000000000000000000000000000000000002");
+        let pre = [
+            (
+                caller,
+                GenesisAccount {
+                    balance: U256::from(1_000_000),
+                    ..Default::default()
+                },
+            ),
+            (
+                contract,
+                GenesisAccount {
+                    // PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+                    code: bytes!("600160005500"),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let mut env = Env::default();
+        env.tx.caller = caller;
+        env.tx.transact_to = TransactTo::Call(contract);
+        env.tx.gas_limit = 100_000;
+        ExecutionFixture::new(SpecId::CANCUN, env, pre)
+    }
+
+    #[test]
+    fn record_and_check() {
+        let mut fixture = fixture();
+        fixture.record().unwrap();
+        assert!(fixture.result.as_ref().unwrap().is_success());
+        fixture.assert();
+
+        let contract = address!("0000000000000000000000000000000000000002");
+        fixture
+            .post
+            .as_mut()
+            .unwrap()
+            .accounts
+            .get_mut(&contract)
+            .unwrap()
+            .storage
+            .clear();
+        let Err(FixtureError::Mismatch(mismatches)) = fixture.check() else {
+            panic!("expected a mismatch");
+        };
+        assert_eq!(mismatches.len(), 1);
+    }
+}
@@ -1,7 +1,22 @@
 // Modules.
+pub mod cancellation;
+pub mod code_version;
+pub mod coinbase;
+pub mod failure_snapshot;
+pub mod fault_injection;
+pub mod frame_factory;
+pub mod gas_table;
+pub mod halt_reason;
 mod handle_types;
+pub mod inspection;
 pub mod mainnet;
+pub mod precompile_calls;
+pub mod preimages;
 pub mod register;
+pub mod resources;
+pub mod reward;
+pub mod sponsor;
+pub mod stateless_gas;
 
 // Exports.
 pub use handle_types::*;
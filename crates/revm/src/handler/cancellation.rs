@@ -0,0 +1,195 @@
+//! Cooperative cancellation of running transactions.
+//!
+//! A [CancellationToken] can be cancelled from any thread while a transaction executes. The
+//! interpreter checks it on jumps, function calls, calls and creates, which bounds the work done
+//! between two checks by the size of the code. A cancelled transaction halts with
+//! [HaltReason::Cancelled](crate::primitives::HaltReason::Cancelled) and consumes all of its gas.
+use super::register::{EvmHandler, HandleRegisterBox};
+use crate::{
+    interpreter::{
+        opcode, opcode::InstructionTables, CallOutcome, CreateOutcome, InstructionResult,
+        Interpreter, SharedMemory,
+    },
+    primitives::db::Database,
+    Context, Evm, Frame,
+};
+use core::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::{boxed::Box, rc::Rc, sync::Arc};
+
+/// Default number of checkpoints between two reads of the token.
+pub const DEFAULT_CHECK_INTERVAL: u64 = 1024;
+
+/// Opcodes that check the token before they are executed.
+const CHECKPOINT_OPCODES: [u8; 17] = [
+    opcode::JUMP,
+    opcode::JUMPI,
+    opcode::RJUMP,
+    opcode::RJUMPI,
+    opcode::RJUMPV,
+    opcode::CALLF,
+    opcode::JUMPF,
+    opcode::CALL,
+    opcode::CALLCODE,
+    opcode::DELEGATECALL,
+    opcode::STATICCALL,
+    opcode::EXTCALL,
+    opcode::EXTDELEGATECALL,
+    opcode::EXTSTATICCALL,
+    opcode::CREATE,
+    opcode::CREATE2,
+    opcode::EOFCREATE,
+];
+
+/// Shared flag used to cancel execution.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the execution using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns the handle register that halts execution once the token is cancelled.
+    ///
+    /// The token is read on every `check_interval`-th checkpoint, see the
+    /// [module documentation](self).
+    pub fn into_handle_register<EXT: 'static, DB: Database + 'static>(
+        self,
+        check_interval: u64,
+    ) -> HandleRegisterBox<EXT, DB> {
+        Box::new(move |handler| self.register(handler, check_interval))
+    }
+
+    /// Registers the cancellation checks in the handler.
+    pub fn register<'a, EXT: 'a, DB: Database + 'a>(
+        &self,
+        handler: &mut EvmHandler<'a, EXT, DB>,
+        check_interval: u64,
+    ) {
+        let check_interval = check_interval.max(1);
+        let countdown = Rc::new(Cell::new(check_interval));
+
+        let mut table = handler
+            .take_instruction_table()
+            .expect("Handler must have instruction table");
+        table.convert_boxed();
+        let InstructionTables::Boxed(instructions) = &mut table else {
+            unreachable!("table was converted to boxed variant")
+        };
+        for opcode in CHECKPOINT_OPCODES {
+            let instruction = &mut instructions[opcode as usize];
+            let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+            let token = self.clone();
+            let countdown = countdown.clone();
+            *instruction = Box::new(
+                move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                    let left = countdown.get() - 1;
+                    if left == 0 {
+                        countdown.set(check_interval);
+                        if token.is_cancelled() {
+                            interpreter.instruction_result = InstructionResult::Cancelled;
+                            return;
+                        }
+                    } else {
+                        countdown.set(left);
+                    }
+                    old(interpreter, host)
+                },
+            );
+        }
+        handler.set_instruction_table(table);
+
+        // Halt the parent frame when a sub call or create returns after cancellation.
+        let token = self.clone();
+        let old_handle = handler.execution.insert_call_outcome.clone();
+        handler.execution.insert_call_outcome = Arc::new(
+            move |context: &mut Context<EXT, DB>,
+                  frame: &mut Frame,
+                  shared_memory: &mut SharedMemory,
+                  outcome: CallOutcome| {
+                old_handle(context, frame, shared_memory, outcome)?;
+                if token.is_cancelled() {
+                    frame.interpreter_mut().instruction_result = InstructionResult::Cancelled;
+                }
+                Ok(())
+            },
+        );
+        let token = self.clone();
+        let old_handle = handler.execution.insert_create_outcome.clone();
+        handler.execution.insert_create_outcome = Arc::new(
+            move |context: &mut Context<EXT, DB>, frame: &mut Frame, outcome: CreateOutcome| {
+                old_handle(context, frame, outcome)?;
+                if token.is_cancelled() {
+                    frame.interpreter_mut().instruction_result = InstructionResult::Cancelled;
+                }
+                Ok(())
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{bytes, Bytecode, Bytes, ExecutionResult, HaltReason, SpecId},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn cancel_infinite_loop() {
+        // JUMPDEST PUSH1 0x00 JUMP
+        let code = Bytes::from_static(&[0x5b, 0x60, 0x00, 0x56]);
+        let token = CancellationToken::new();
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .modify_tx_env(|tx| tx.gas_limit = u64::MAX / 2)
+            .append_handler_register_box(token.clone().into_handle_register(16))
+            .build();
+
+        token.cancel();
+        let result = evm.transact().unwrap().result;
+        assert!(matches!(
+            result,
+            ExecutionResult::Halt {
+                reason: HaltReason::Cancelled,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn cancel_eof_loop() {
+        // RJUMP -3
+        let code = bytes!("ef000101000402000100030400000000800000e0fffd");
+        let token = CancellationToken::new();
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_spec_id(SpecId::OSAKA)
+            .modify_tx_env(|tx| tx.gas_limit = u64::MAX / 2)
+            .append_handler_register_box(token.clone().into_handle_register(16))
+            .build();
+
+        token.cancel();
+        let result = evm.transact().unwrap().result;
+        assert!(matches!(
+            result,
+            ExecutionResult::Halt {
+                reason: HaltReason::Cancelled,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,190 @@
+//! Checks of the code format before and during execution.
+//!
+//! Until EOF is activated, code starting with the EOF magic runs as legacy code and halts on
+//! its first byte. A [CodeVersionPolicy] inspects the code of every frame instead and halts
+//! frames whose code the chain should not run with
+//! [HaltReason::UnsupportedCodeVersion](crate::primitives::HaltReason::UnsupportedCodeVersion):
+//! EOF containers of versions that are not enabled, and legacy code built with a compiler older
+//! than a minimum version recorded in its metadata. In EOF code, opcodes that only exist in
+//! legacy code halt with
+//! [HaltReason::LegacyOpcodeInEof](crate::primitives::HaltReason::LegacyOpcodeInEof).
+use super::register::{EvmHandler, HandleRegisterBox};
+use crate::{
+    interpreter::{
+        opcode::{InstructionTables, OpCode},
+        CallInputs, CreateInputs, InstructionResult, Interpreter,
+    },
+    primitives::{db::Database, CodeMetadata, CodeVersion, SolcVersion},
+    Context, Evm, FrameOrResult,
+};
+use std::{boxed::Box, rc::Rc, sync::Arc, vec::Vec};
+
+/// Code formats and compiler versions allowed to execute.
+///
+/// The default policy only allows legacy code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodeVersionPolicy {
+    eof_versions: Vec<u8>,
+    min_solc_version: Option<SolcVersion>,
+}
+
+impl CodeVersionPolicy {
+    /// Creates the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows EOF containers of the given version.
+    pub fn with_eof_version(mut self, version: u8) -> Self {
+        self.eof_versions.push(version);
+        self
+    }
+
+    /// Rejects legacy code whose metadata records an older Solidity compiler.
+    ///
+    /// Code without metadata is allowed.
+    pub fn with_min_solc_version(mut self, version: SolcVersion) -> Self {
+        self.min_solc_version = Some(version);
+        self
+    }
+
+    /// Returns `true` if code with this prefix and metadata is allowed to execute.
+    pub fn allows(&self, code: &[u8]) -> bool {
+        match CodeVersion::of(code) {
+            CodeVersion::Eof(version) => self.eof_versions.contains(&version),
+            CodeVersion::Legacy => match self.min_solc_version {
+                Some(min) => CodeMetadata::parse(code)
+                    .and_then(|metadata| metadata.solc)
+                    .map_or(true, |solc| solc >= min),
+                None => true,
+            },
+        }
+    }
+
+    /// Returns the handle register enforcing the policy.
+    pub fn into_handle_register<EXT: 'static, DB: Database + 'static>(
+        self,
+    ) -> HandleRegisterBox<EXT, DB> {
+        Box::new(move |handler| self.register(handler))
+    }
+
+    /// Registers the checks in the handler.
+    pub fn register<'a, EXT: 'a, DB: Database + 'a>(&self, handler: &mut EvmHandler<'a, EXT, DB>) {
+        let policy = Rc::new(self.clone());
+        let check_frame = move |frame_or_result: &mut FrameOrResult| {
+            if let FrameOrResult::Frame(frame) = frame_or_result {
+                let interpreter = frame.interpreter_mut();
+                if !policy.allows(interpreter.contract.bytecode.original_bytecode_slice()) {
+                    interpreter.instruction_result = InstructionResult::UnsupportedCodeVersion;
+                }
+            }
+        };
+        let check_frame = Rc::new(check_frame);
+
+        let old_handle = handler.execution.call.clone();
+        let check = check_frame.clone();
+        handler.execution.call = Arc::new(
+            move |context: &mut Context<EXT, DB>, inputs: Box<CallInputs>| {
+                let mut frame_or_result = old_handle(context, inputs)?;
+                check(&mut frame_or_result);
+                Ok(frame_or_result)
+            },
+        );
+        let old_handle = handler.execution.create.clone();
+        handler.execution.create = Arc::new(
+            move |context: &mut Context<EXT, DB>, inputs: Box<CreateInputs>| {
+                let mut frame_or_result = old_handle(context, inputs)?;
+                check_frame(&mut frame_or_result);
+                Ok(frame_or_result)
+            },
+        );
+
+        if self.eof_versions.is_empty() {
+            // EOF code never runs, legacy-only opcodes need no check.
+            return;
+        }
+        let mut table = handler
+            .take_instruction_table()
+            .expect("Handler must have instruction table");
+        table.convert_boxed();
+        let InstructionTables::Boxed(instructions) = &mut table else {
+            unreachable!("table was converted to boxed variant")
+        };
+        for (opcode, instruction) in instructions.iter_mut().enumerate() {
+            let is_legacy_only = OpCode::new(opcode as u8).map_or(false, |op| op.is_legacy_only());
+            if !is_legacy_only {
+                continue;
+            }
+            let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+            *instruction = Box::new(
+                move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                    let code = interpreter.contract.bytecode.original_bytecode_slice();
+                    if CodeVersion::of(code).is_eof() {
+                        interpreter.instruction_result = InstructionResult::LegacyOpcodeInEof;
+                        return;
+                    }
+                    old(interpreter, host)
+                },
+            );
+        }
+        handler.set_instruction_table(table);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{Bytecode, Bytes, ExecutionResult, HaltReason},
+        test_utils::benchmark_evm_builder,
+    };
+
+    fn run(code: &'static [u8], policy: CodeVersionPolicy) -> ExecutionResult {
+        benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(code)))
+            .append_handler_register_box(policy.into_handle_register())
+            .build()
+            .transact()
+            .unwrap()
+            .result
+    }
+
+    fn halt_reason(result: ExecutionResult) -> Option<HaltReason> {
+        match result {
+            ExecutionResult::Halt { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn rejects_eof_versions() {
+        // EOF magic, version 1, followed by PC.
+        const EOF: &[u8] = &[0xEF, 0x00, 0x01, 0x58];
+        assert_eq!(
+            halt_reason(run(EOF, CodeVersionPolicy::new())),
+            Some(HaltReason::UnsupportedCodeVersion)
+        );
+    }
+
+    #[test]
+    fn legacy_code_and_solc_version() {
+        // PC STOP followed by metadata {"solc": 0.4.26}
+        const CODE: &[u8] = &[
+            0x58, 0x00, 0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63, 0x43, 0x00, 0x04, 0x1a, 0x00, 0x0a,
+        ];
+        assert!(run(CODE, CodeVersionPolicy::new()).is_success());
+
+        let policy = CodeVersionPolicy::new().with_min_solc_version(SolcVersion::new(0, 5, 0));
+        assert_eq!(
+            halt_reason(run(CODE, policy)),
+            Some(HaltReason::UnsupportedCodeVersion)
+        );
+    }
+
+    #[test]
+    fn allows_enabled_eof_version() {
+        let policy = CodeVersionPolicy::new().with_eof_version(1);
+        assert!(policy.allows(&[0xEF, 0x00, 0x01]));
+        assert!(!policy.allows(&[0xEF, 0x00, 0x02]));
+        assert!(policy.allows(&[0x00]));
+    }
+}
@@ -0,0 +1,135 @@
+//! Tracking of the [CoinbasePayment] of a transaction.
+use super::register::EvmHandler;
+use crate::{
+    interpreter::Gas,
+    primitives::{
+        db::Database, Address, CoinbasePayment, DatabaseAccess, EVMError, ResultAndState, U256,
+    },
+    Context,
+};
+use core::cell::Cell;
+use std::{rc::Rc, sync::Arc};
+
+/// Registers handles that track the balance of the beneficiary and return its change in
+/// [ExecutionMetadata::coinbase_payment](crate::primitives::ExecutionMetadata::coinbase_payment).
+///
+/// The balance before the transaction is read from the database without loading the
+/// beneficiary in the journal, so tracking does not change the warm accounts.
+pub fn coinbase_payment_handle_register<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+) {
+    let payment = Rc::new(Cell::new(CoinbasePayment::default()));
+
+    let old_handle = handler.pre_execution.load_accounts.clone();
+    let tracked = payment.clone();
+    handler.pre_execution.load_accounts = Arc::new(move |context: &mut Context<EXT, DB>| {
+        let beneficiary = context.evm.env.effective_coinbase();
+        let balance_before = context
+            .evm
+            .inner
+            .db
+            .basic(beneficiary)
+            .map_err(|e| EVMError::database_at(e, DatabaseAccess::Account(beneficiary)))?
+            .map(|info| info.balance)
+            .unwrap_or_default();
+        tracked.set(CoinbasePayment {
+            beneficiary,
+            balance_before,
+            balance_after: balance_before,
+            fees: U256::ZERO,
+        });
+        old_handle(context)
+    });
+
+    let old_handle = handler.post_execution.reward_beneficiary.clone();
+    let tracked = payment.clone();
+    handler.post_execution.reward_beneficiary =
+        Arc::new(move |context: &mut Context<EXT, DB>, gas: &Gas| {
+            let beneficiary = tracked.get().beneficiary;
+            let before = beneficiary_balance(context, beneficiary)?;
+            old_handle(context, gas)?;
+            let after = beneficiary_balance(context, beneficiary)?;
+
+            let mut current = tracked.get();
+            current.fees = after.saturating_sub(before);
+            tracked.set(current);
+            Ok(())
+        });
+
+    let old_handle = handler.post_execution.end.clone();
+    handler.post_execution.end = Arc::new(
+        move |context: &mut Context<EXT, DB>,
+              output: Result<ResultAndState, EVMError<DB::Error>>| {
+            let mut payment = payment.take();
+            old_handle(context, output).map(|mut output| {
+                if let Some(account) = output.state.get(&payment.beneficiary) {
+                    payment.balance_after = account.info.balance;
+                }
+                output.metadata.coinbase_payment = Some(payment);
+                output
+            })
+        },
+    );
+}
+
+/// Returns the balance of the beneficiary in the journal.
+fn beneficiary_balance<EXT, DB: Database>(
+    context: &mut Context<EXT, DB>,
+    beneficiary: Address,
+) -> Result<U256, EVMError<DB::Error>> {
+    let (account, _) = context
+        .evm
+        .inner
+        .journaled_state
+        .load_account(beneficiary, &mut context.evm.inner.db)?;
+    Ok(account.info.balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::opcode,
+        primitives::{Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn detect_direct_payment() {
+        let coinbase = Address::with_last_byte(0xcc);
+        // CALL(GAS, COINBASE, 100, 0, 0, 0, 0) STOP
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x00,
+            opcode::PUSH1,
+            0x00,
+            opcode::PUSH1,
+            0x00,
+            opcode::PUSH1,
+            0x00,
+            opcode::PUSH1,
+            100,
+            opcode::COINBASE,
+            opcode::GAS,
+            opcode::CALL,
+            opcode::STOP,
+        ]);
+        let output = benchmark_evm_builder(Bytecode::new_raw(code))
+            .modify_tx_env(|tx| {
+                tx.gas_price = U256::from(10);
+                tx.coinbase = Some(coinbase);
+            })
+            .append_handler_register(coinbase_payment_handle_register)
+            .build()
+            .transact()
+            .unwrap();
+
+        let payment = output.metadata.coinbase_payment.unwrap();
+        let fees = U256::from(output.result.gas_used() * 10);
+        assert_eq!(payment.beneficiary, coinbase);
+        assert_eq!(payment.balance_before, U256::ZERO);
+        assert_eq!(payment.fees, fees);
+        assert_eq!(payment.delta(), fees + U256::from(100));
+        assert_eq!(payment.direct_payment(), U256::from(100));
+    }
+}
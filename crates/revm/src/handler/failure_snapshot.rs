@@ -0,0 +1,244 @@
+//! Collection of the [FailureSnapshot]s of a transaction.
+use super::register::{EvmHandler, HandleRegisterBox};
+use crate::{
+    interpreter::{
+        opcode, opcode::InstructionTables, InstructionResult, Interpreter, Stack, SuccessOrHalt,
+    },
+    primitives::{
+        db::Database, Bytes, EVMError, ExecutedOpcode, FailureSnapshot, ResultAndState, U256,
+    },
+    Context, Evm,
+};
+use core::cell::RefCell;
+use std::{boxed::Box, collections::VecDeque, rc::Rc, sync::Arc, vec::Vec};
+
+/// How much of a failing frame is captured in its [FailureSnapshot].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FailureSnapshotConfig {
+    /// Number of stack items to capture, from the top.
+    pub stack_items: usize,
+    /// Number of bytes to capture from the end of the memory.
+    pub memory_window: usize,
+    /// Number of recently executed instructions to keep for every frame, `0` to not keep them.
+    pub recent_opcodes: usize,
+}
+
+impl Default for FailureSnapshotConfig {
+    fn default() -> Self {
+        Self {
+            stack_items: 16,
+            memory_window: 256,
+            recent_opcodes: 32,
+        }
+    }
+}
+
+impl FailureSnapshotConfig {
+    /// Returns the handle register that collects the snapshots.
+    pub fn into_handle_register<EXT: 'static, DB: Database + 'static>(
+        self,
+    ) -> HandleRegisterBox<EXT, DB> {
+        Box::new(move |handler| self.register(handler))
+    }
+
+    /// Registers handles that snapshot every frame that reverts or halts and return the
+    /// snapshots in
+    /// [ExecutionMetadata::failure_snapshots](crate::primitives::ExecutionMetadata::failure_snapshots)
+    /// if the transaction fails.
+    ///
+    /// Every instruction is wrapped to check its result, so this is meant for debugging.
+    pub fn register<'a, EXT: 'a, DB: Database + 'a>(&self, handler: &mut EvmHandler<'a, EXT, DB>) {
+        let config = *self;
+        let snapshots = Rc::new(RefCell::new(Vec::new()));
+        let recent = Rc::new(RefCell::new(RecentOpcodes::new(config.recent_opcodes)));
+
+        let mut table = handler
+            .take_instruction_table()
+            .expect("Handler must have instruction table");
+        table.convert_boxed();
+        let InstructionTables::Boxed(instructions) = &mut table else {
+            unreachable!("table was converted to boxed variant")
+        };
+        for (op, instruction) in instructions.iter_mut().enumerate() {
+            let op = op as u8;
+            let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+            let snapshots = snapshots.clone();
+            let recent = recent.clone();
+            *instruction = Box::new(
+                move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                    // The instruction pointer is already past the opcode.
+                    let pc = interpreter.program_counter() - 1;
+                    let depth = host.context.evm.journaled_state.depth() as usize;
+                    recent.borrow_mut().record(
+                        depth,
+                        ExecutedOpcode {
+                            pc,
+                            opcode: op,
+                            gas_remaining: interpreter.gas.remaining(),
+                        },
+                    );
+                    let operands = (op == opcode::REVERT)
+                        .then(|| top_of_stack(&interpreter.stack, config.stack_items));
+                    old(interpreter, host);
+
+                    let result = interpreter.instruction_result;
+                    if matches!(
+                        result,
+                        InstructionResult::Continue | InstructionResult::CallOrCreate
+                    ) {
+                        return;
+                    }
+                    let recent_opcodes = recent.borrow_mut().end_frame();
+                    if !result.is_revert() && !result.is_error() {
+                        return;
+                    }
+                    let memory = interpreter.shared_memory.context_memory();
+                    let memory_offset = memory.len().saturating_sub(config.memory_window);
+                    snapshots.borrow_mut().push(FailureSnapshot {
+                        address: interpreter.contract.address,
+                        depth,
+                        pc,
+                        opcode: op,
+                        halt_reason: SuccessOrHalt::from_result(result, interpreter.custom_code)
+                            .to_halt(),
+                        stack: operands.unwrap_or_else(|| {
+                            top_of_stack(&interpreter.stack, config.stack_items)
+                        }),
+                        memory_offset,
+                        memory: Bytes::copy_from_slice(&memory[memory_offset..]),
+                        recent_opcodes,
+                    });
+                },
+            );
+        }
+        handler.set_instruction_table(table);
+
+        let old_handle = handler.post_execution.end.clone();
+        handler.post_execution.end = Arc::new(
+            move |context: &mut Context<EXT, DB>,
+                  output: Result<ResultAndState, EVMError<DB::Error>>| {
+                let snapshots = snapshots.take();
+                recent.borrow_mut().clear();
+                old_handle(context, output).map(|mut output| {
+                    if !output.result.is_success() {
+                        output.metadata.failure_snapshots = Some(snapshots);
+                    }
+                    output
+                })
+            },
+        );
+    }
+}
+
+/// Ring buffers of the last instructions executed by the active frames.
+struct RecentOpcodes {
+    capacity: usize,
+    /// Buffer of every active frame, indexed by call depth starting at 1.
+    frames: Vec<VecDeque<ExecutedOpcode>>,
+}
+
+impl RecentOpcodes {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Records an instruction of the frame at `depth`.
+    ///
+    /// Frames end with the instruction that stops them, so a frame at a depth without a buffer
+    /// is a new one.
+    fn record(&mut self, depth: usize, entry: ExecutedOpcode) {
+        if self.capacity == 0 {
+            return;
+        }
+        let capacity = self.capacity;
+        self.frames.truncate(depth);
+        self.frames
+            .resize_with(depth, || VecDeque::with_capacity(capacity));
+        let buffer = &mut self.frames[depth - 1];
+        if buffer.len() == capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+
+    /// Removes the buffer of the innermost frame and returns its instructions, oldest first.
+    fn end_frame(&mut self) -> Vec<ExecutedOpcode> {
+        self.frames.pop().map(Vec::from).unwrap_or_default()
+    }
+
+    fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// Returns the topmost `count` items of the stack, top first.
+fn top_of_stack(stack: &Stack, count: usize) -> Vec<U256> {
+    stack.data().iter().rev().take(count).copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{Address, Bytecode},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn snapshot_revert() {
+        // PUSH1 0x07 MSTORE(0, 0x2a) REVERT(0, 0x20)
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x07,
+            opcode::PUSH1,
+            0x2a,
+            opcode::PUSH1,
+            0x00,
+            opcode::MSTORE,
+            opcode::PUSH1,
+            0x20,
+            opcode::PUSH1,
+            0x00,
+            opcode::REVERT,
+        ]);
+        let config = FailureSnapshotConfig {
+            stack_items: 2,
+            memory_window: 16,
+            recent_opcodes: 3,
+        };
+        let output = benchmark_evm_builder(Bytecode::new_raw(code))
+            .append_handler_register_box(config.into_handle_register())
+            .build()
+            .transact()
+            .unwrap();
+
+        let mut snapshots = output.metadata.failure_snapshots.unwrap();
+        let recent = core::mem::take(&mut snapshots[0].recent_opcodes);
+        let executed: Vec<_> = recent.iter().map(|op| (op.pc, op.opcode)).collect();
+        assert_eq!(
+            executed,
+            [(7, opcode::PUSH1), (9, opcode::PUSH1), (11, opcode::REVERT)]
+        );
+        assert_eq!(recent[0].gas_remaining - recent[2].gas_remaining, 6);
+
+        let mut memory = [0u8; 16];
+        memory[15] = 0x2a;
+        assert_eq!(
+            snapshots,
+            vec![FailureSnapshot {
+                address: Address::ZERO,
+                depth: 1,
+                pc: 11,
+                opcode: opcode::REVERT,
+                halt_reason: None,
+                stack: vec![U256::ZERO, U256::from(0x20)],
+                memory_offset: 16,
+                memory: Bytes::copy_from_slice(&memory),
+                recent_opcodes: Vec::new(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Deterministic fault injection for testing error handling.
+//!
+//! A [FaultSchedule] lists faults to inject into a transaction:
+//! * [Fault::OutOfGas] halts with out of gas before the N-th executed instruction,
+//! * [Fault::Revert] reverts the K-th frame before it executes its first instruction,
+//! * [Fault::DatabaseError] fails the M-th database read.
+//!
+//! Instructions, frames and reads are counted from zero in execution order, so the same schedule
+//! always fails at the same point of the same transaction. Instruction and frame faults are
+//! installed with [FaultSchedule::register], database faults by wrapping the database with
+//! [FaultSchedule::wrap_database].
+use super::register::{EvmHandler, HandleRegisterBox};
+use crate::{
+    interpreter::{
+        opcode::InstructionTables, CallInputs, CreateInputs, InstructionResult, Interpreter,
+    },
+    primitives::{
+        db::Database, AccountInfo, Address, Bytecode, DatabaseError, EVMError, B256, U256,
+    },
+    Context, Evm, FrameOrResult,
+};
+use core::{cell::Cell, fmt};
+use std::{boxed::Box, rc::Rc, sync::Arc, vec::Vec};
+
+/// Fault injected at a deterministic point of execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// Halt with out of gas instead of executing the instruction with this index.
+    OutOfGas {
+        /// Index of the instruction.
+        instruction: u64,
+    },
+    /// Revert the frame with this index without executing it.
+    Revert {
+        /// Index of the frame, the transaction frame has index zero.
+        frame: u64,
+    },
+    /// Fail the database read with this index.
+    DatabaseError {
+        /// Index of the read.
+        read: u64,
+    },
+}
+
+/// Set of faults to inject, see the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FaultSchedule {
+    faults: Vec<Fault>,
+}
+
+impl FaultSchedule {
+    /// Creates an empty schedule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a fault to the schedule.
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    /// Returns the scheduled faults.
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    fn indices(&self, f: impl Fn(&Fault) -> Option<u64>) -> Rc<[u64]> {
+        self.faults.iter().filter_map(f).collect()
+    }
+
+    /// Returns the handle register that injects the instruction and frame faults.
+    pub fn into_handle_register<EXT: 'static, DB: Database + 'static>(
+        self,
+    ) -> HandleRegisterBox<EXT, DB> {
+        Box::new(move |handler| self.register(handler))
+    }
+
+    /// Registers the instruction and frame faults in the handler.
+    pub fn register<'a, EXT: 'a, DB: Database + 'a>(&self, handler: &mut EvmHandler<'a, EXT, DB>) {
+        let out_of_gas = self.indices(|fault| match fault {
+            Fault::OutOfGas { instruction } => Some(*instruction),
+            _ => None,
+        });
+        if !out_of_gas.is_empty() {
+            let executed = Rc::new(Cell::new(0u64));
+            let mut table = handler
+                .take_instruction_table()
+                .expect("Handler must have instruction table");
+            table.convert_boxed();
+            let InstructionTables::Boxed(instructions) = &mut table else {
+                unreachable!("table was converted to boxed variant")
+            };
+            for instruction in instructions.iter_mut() {
+                let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+                let executed = executed.clone();
+                let out_of_gas = out_of_gas.clone();
+                *instruction = Box::new(
+                    move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                        let index = executed.get();
+                        executed.set(index + 1);
+                        if out_of_gas.contains(&index) {
+                            interpreter.instruction_result = InstructionResult::OutOfGas;
+                            return;
+                        }
+                        old(interpreter, host)
+                    },
+                );
+            }
+            handler.set_instruction_table(table);
+        }
+
+        let revert = self.indices(|fault| match fault {
+            Fault::Revert { frame } => Some(*frame),
+            _ => None,
+        });
+        if !revert.is_empty() {
+            let frames = Rc::new(Cell::new(0u64));
+            let next_frame = move |frame_or_result: &mut FrameOrResult| {
+                let index = frames.get();
+                frames.set(index + 1);
+                if let FrameOrResult::Frame(frame) = frame_or_result {
+                    if revert.contains(&index) {
+                        frame.interpreter_mut().instruction_result = InstructionResult::Revert;
+                    }
+                }
+            };
+            let next_frame = Rc::new(next_frame);
+
+            let old_handle = handler.execution.call.clone();
+            let inject = next_frame.clone();
+            handler.execution.call = Arc::new(
+                move |context: &mut Context<EXT, DB>, inputs: Box<CallInputs>| {
+                    let mut frame_or_result = old_handle(context, inputs)?;
+                    inject(&mut frame_or_result);
+                    Ok(frame_or_result)
+                },
+            );
+            let old_handle = handler.execution.create.clone();
+            handler.execution.create = Arc::new(
+                move |context: &mut Context<EXT, DB>, inputs: Box<CreateInputs>| {
+                    let mut frame_or_result = old_handle(context, inputs)?;
+                    next_frame(&mut frame_or_result);
+                    Ok(frame_or_result)
+                },
+            );
+        }
+    }
+
+    /// Wraps the database so that the scheduled reads fail.
+    pub fn wrap_database<DB>(&self, db: DB) -> FaultyDatabase<DB> {
+        let mut fail_reads: Vec<_> = self
+            .faults
+            .iter()
+            .filter_map(|fault| match fault {
+                Fault::DatabaseError { read } => Some(*read),
+                _ => None,
+            })
+            .collect();
+        fail_reads.sort_unstable();
+        FaultyDatabase {
+            db,
+            fail_reads,
+            reads: 0,
+        }
+    }
+}
+
+/// Error of a [FaultyDatabase].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FaultyDatabaseError<E> {
+    /// The read was failed by the fault schedule.
+    Injected {
+        /// Index of the failed read.
+        read: u64,
+    },
+    /// Error of the wrapped database.
+    Database(E),
+}
+
+impl<E: fmt::Display> fmt::Display for FaultyDatabaseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Injected { read } => write!(f, "injected failure of database read {read}"),
+            Self::Database(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> std::error::Error for FaultyDatabaseError<E> {}
+
+/// Database that fails reads according to a [FaultSchedule].
+#[derive(Clone, Debug)]
+pub struct FaultyDatabase<DB> {
+    db: DB,
+    fail_reads: Vec<u64>,
+    reads: u64,
+}
+
+impl<DB> FaultyDatabase<DB> {
+    /// Returns the number of reads made so far.
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    /// Returns the wrapped database.
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+
+    fn next_read<E>(&mut self) -> Result<(), FaultyDatabaseError<E>> {
+        let read = self.reads;
+        self.reads += 1;
+        if self.fail_reads.binary_search(&read).is_ok() {
+            return Err(FaultyDatabaseError::Injected { read });
+        }
+        Ok(())
+    }
+}
+
+impl<DB: Database> Database for FaultyDatabase<DB> {
+    type Error = FaultyDatabaseError<DB::Error>;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.next_read()?;
+        self.db
+            .basic(address)
+            .map_err(FaultyDatabaseError::Database)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.next_read()?;
+        self.db
+            .code_by_hash(code_hash)
+            .map_err(FaultyDatabaseError::Database)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.next_read()?;
+        self.db
+            .storage(address, index)
+            .map_err(FaultyDatabaseError::Database)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        self.next_read()?;
+        self.db
+            .block_hash(number)
+            .map_err(FaultyDatabaseError::Database)
+    }
+}
+
+/// Returns `true` if the error was injected by a [FaultyDatabase].
+pub fn is_injected<E>(error: &EVMError<FaultyDatabaseError<E>>) -> bool {
+    matches!(
+        error,
+        EVMError::Database(DatabaseError {
+            error: FaultyDatabaseError::Injected { .. },
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::BenchmarkDB,
+        primitives::{Bytes, DatabaseAccess, ExecutionResult, ExecutionStage, HaltReason},
+        test_utils::benchmark_evm_builder,
+    };
+
+    fn evm<'a>(schedule: FaultSchedule) -> Evm<'a, (), BenchmarkDB> {
+        benchmark_evm_builder(code())
+            .append_handler_register_box(schedule.into_handle_register())
+            .build()
+    }
+
+    fn code() -> Bytecode {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+        Bytecode::new_raw(Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x55, 0x00]))
+    }
+
+    #[test]
+    fn out_of_gas_at_instruction() {
+        let schedule = FaultSchedule::new().with_fault(Fault::OutOfGas { instruction: 2 });
+        let result = evm(schedule).transact().unwrap().result;
+        assert!(matches!(
+            result,
+            ExecutionResult::Halt {
+                reason: HaltReason::OutOfGas(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn revert_frame() {
+        let schedule = FaultSchedule::new().with_fault(Fault::Revert { frame: 0 });
+        let result = evm(schedule).transact().unwrap().result;
+        assert!(matches!(result, ExecutionResult::Revert { .. }));
+    }
+
+    #[test]
+    fn fail_database_read() {
+        let schedule = FaultSchedule::new().with_fault(Fault::DatabaseError { read: 0 });
+        let db = schedule.wrap_database(BenchmarkDB::new_bytecode(code()));
+        let error = benchmark_evm_builder(code())
+            .with_db(db)
+            .build()
+            .transact()
+            .unwrap_err();
+        assert!(is_injected(&error));
+
+        // The caller is the first account read, when the transaction is validated.
+        let EVMError::Database(error) = error else {
+            panic!("expected a database error");
+        };
+        assert_eq!(
+            error.access,
+            Some(DatabaseAccess::Account(Address::with_last_byte(1)))
+        );
+        assert_eq!(error.stage, Some(ExecutionStage::Validation));
+        assert_eq!(error.opcode, None);
+    }
+}
@@ -0,0 +1,148 @@
+//! Custom construction of call and create frames.
+//!
+//! Every call and create, including the one of the transaction, goes through the
+//! [ExecutionHandler::call](super::ExecutionHandler::call) and
+//! [ExecutionHandler::create](super::ExecutionHandler::create) handles before the frame is
+//! created. A [FrameFactory] intercepts them to change the semantics of calls without patching
+//! the instructions: chains with delegated accounts can rewrite the inputs to execute the code of
+//! another account, and precompile-mediated calls can return their outcome without a frame.
+use super::{
+    register::{EvmHandler, HandleRegisterBox},
+    FrameCallHandle, FrameCreateHandle,
+};
+use crate::{
+    interpreter::{CallInputs, CreateInputs},
+    primitives::{db::Database, EVMError},
+    Context, FrameOrResult,
+};
+use std::{boxed::Box, sync::Arc};
+
+/// Constructs the frames of calls and creates.
+///
+/// `next` is the handle registered before the factory, it creates the frame as usual. Methods
+/// that are not implemented forward to it.
+pub trait FrameFactory<EXT, DB: Database> {
+    /// Returns the frame executing `inputs`, or the outcome of the call if it runs no code.
+    fn call(
+        &self,
+        context: &mut Context<EXT, DB>,
+        inputs: Box<CallInputs>,
+        next: &FrameCallHandle<'_, EXT, DB>,
+    ) -> Result<FrameOrResult, EVMError<DB::Error>> {
+        next(context, inputs)
+    }
+
+    /// Returns the frame executing `inputs`, or the outcome of the create if it runs no code.
+    fn create(
+        &self,
+        context: &mut Context<EXT, DB>,
+        inputs: Box<CreateInputs>,
+        next: &FrameCreateHandle<'_, EXT, DB>,
+    ) -> Result<FrameOrResult, EVMError<DB::Error>> {
+        next(context, inputs)
+    }
+}
+
+/// Returns the handle register that constructs frames with `factory`.
+pub fn frame_factory_handle_register<EXT: 'static, DB: Database + 'static>(
+    factory: Arc<dyn FrameFactory<EXT, DB>>,
+) -> HandleRegisterBox<EXT, DB> {
+    Box::new(move |handler| register_frame_factory(handler, factory.clone()))
+}
+
+/// Registers `factory` in the handler, wrapping the call and create handles.
+pub fn register_frame_factory<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+    factory: Arc<dyn FrameFactory<EXT, DB> + 'a>,
+) {
+    let old_handle = handler.execution.call.clone();
+    let call_factory = factory.clone();
+    handler.execution.call = Arc::new(
+        move |context: &mut Context<EXT, DB>, inputs: Box<CallInputs>| {
+            call_factory.call(context, inputs, &old_handle)
+        },
+    );
+
+    let old_handle = handler.execution.create.clone();
+    handler.execution.create = Arc::new(
+        move |context: &mut Context<EXT, DB>, inputs: Box<CreateInputs>| {
+            factory.create(context, inputs, &old_handle)
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::{opcode, CallOutcome, Gas, InstructionResult, InterpreterResult},
+        primitives::{
+            Address, Bytecode, Bytes, ExecutionResult, Output, SuccessReason, TransactTo,
+        },
+        test_utils::benchmark_evm_builder,
+        FrameResult,
+    };
+
+    /// Answers calls to [Oracle::ADDRESS] without executing code.
+    struct Oracle;
+
+    impl Oracle {
+        const ADDRESS: Address = Address::new([0xdd; 20]);
+    }
+
+    impl<EXT, DB: Database> FrameFactory<EXT, DB> for Oracle {
+        fn call(
+            &self,
+            context: &mut Context<EXT, DB>,
+            inputs: Box<CallInputs>,
+            next: &FrameCallHandle<'_, EXT, DB>,
+        ) -> Result<FrameOrResult, EVMError<DB::Error>> {
+            if inputs.contract != Self::ADDRESS {
+                return next(context, inputs);
+            }
+            let result = InterpreterResult::new(
+                InstructionResult::Return,
+                Bytes::from_static(b"price"),
+                Gas::new(inputs.gas_limit),
+            );
+            Ok(FrameOrResult::Result(FrameResult::Call(CallOutcome::new(
+                result,
+                inputs.return_memory_offset.clone(),
+            ))))
+        }
+    }
+
+    fn transact(to: Address) -> ExecutionResult {
+        benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::RETURN,
+        ])))
+        .modify_tx_env(|tx| tx.transact_to = TransactTo::Call(to))
+        .append_handler_register_box(frame_factory_handle_register(Arc::new(Oracle)))
+        .build()
+        .transact()
+        .unwrap()
+        .result
+    }
+
+    #[test]
+    fn intercept_calls() {
+        let result = transact(Oracle::ADDRESS);
+        assert_eq!(
+            result,
+            ExecutionResult::Success {
+                reason: SuccessReason::Return,
+                gas_used: 21_000,
+                gas_refunded: 0,
+                logs: Vec::new(),
+                output: Output::Call(Bytes::from_static(b"price")),
+            }
+        );
+
+        // Other calls create frames as usual.
+        let result = transact(Address::ZERO);
+        assert!(result.is_success());
+        assert_eq!(result.output(), Some(&Bytes::new()));
+    }
+}
@@ -0,0 +1,91 @@
+//! Repricing of opcodes with the [GasTable] set in
+//! [CfgEnv::gas_table](crate::primitives::CfgEnv::gas_table).
+//!
+//! Instructions charge the static costs of the spec. [gas_table_handle_register] wraps every
+//! instruction to charge or give back the difference to the cost in the table, which is read
+//! from the environment at execution so the table can be changed between transactions.
+//!
+//! [GasTable]: crate::primitives::GasTable
+use super::register::EvmHandler;
+use crate::{
+    interpreter::{
+        opcode::{spec_opcode_gas, InstructionTables},
+        InstructionResult, Interpreter,
+    },
+    primitives::db::Database,
+    Evm,
+};
+use std::boxed::Box;
+
+/// Registers the repricing of opcodes by the gas table of the environment.
+pub fn gas_table_handle_register<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+) {
+    let spec_costs = spec_opcode_gas(handler.cfg.spec_id);
+    let mut table = handler
+        .take_instruction_table()
+        .expect("Handler must have instruction table");
+    table.convert_boxed();
+    let InstructionTables::Boxed(instructions) = &mut table else {
+        unreachable!("table was converted to boxed variant")
+    };
+    for (opcode, instruction) in instructions.iter_mut().enumerate() {
+        let spec_cost = spec_costs[opcode].get_gas() as u64;
+        let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+        *instruction = Box::new(
+            move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                if let Some(gas_table) = &host.context.evm.env.cfg.gas_table {
+                    let cost = gas_table.cost(opcode as u8);
+                    if cost >= spec_cost {
+                        if !interpreter.gas.record_cost(cost - spec_cost) {
+                            interpreter.instruction_result = InstructionResult::OutOfGas;
+                            return;
+                        }
+                    } else {
+                        interpreter.gas.erase_cost(spec_cost - cost);
+                    }
+                }
+                old(interpreter, host)
+            },
+        );
+    }
+    handler.set_instruction_table(table);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::opcode::{self, spec_gas_table},
+        primitives::{Bytecode, Bytes, GasTable, SpecId},
+        test_utils::benchmark_evm_builder,
+    };
+    use std::sync::Arc;
+
+    fn gas_used(gas_table: Option<GasTable>) -> u64 {
+        // PUSH1 1 PUSH1 2 ADD STOP
+        benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[
+            0x60, 0x01, 0x60, 0x02, 0x01, 0x00,
+        ])))
+        .modify_cfg_env(|cfg| cfg.gas_table = gas_table.map(Arc::new))
+        .append_handler_register(gas_table_handle_register)
+        .build()
+        .transact()
+        .unwrap()
+        .result
+        .gas_used()
+    }
+
+    #[test]
+    fn reprices_opcodes() {
+        let spec_table = spec_gas_table(SpecId::LATEST);
+        assert_eq!(spec_table.cost(opcode::ADD), 3);
+        let spec_gas_used = gas_used(None);
+        assert_eq!(gas_used(Some(spec_table.clone())), spec_gas_used);
+
+        let expensive = spec_table.clone().with_cost(opcode::ADD, 10);
+        assert_eq!(gas_used(Some(expensive)), spec_gas_used + 7);
+        let cheap = spec_table.with_cost(opcode::ADD, 1);
+        assert_eq!(gas_used(Some(cheap)), spec_gas_used - 2);
+    }
+}
@@ -0,0 +1,113 @@
+//! Halt reasons defined by the chain.
+//!
+//! Interpreter results are converted to the halt reasons of Ethereum mainnet. A
+//! [HaltReasonMapping] registered with [halt_reason_handle_register] replaces the reason of halted
+//! transactions with one defined by the chain, usually a [HaltReason::Custom] created from a
+//! [CustomHaltReason](crate::primitives::CustomHaltReason).
+use super::register::HandleRegisterBox;
+use crate::{
+    interpreter::InterpreterResult,
+    primitives::{db::Database, ExecutionResult, HaltReason},
+    Context, FrameResult,
+};
+use std::{boxed::Box, sync::Arc};
+
+/// Mapping of the result of a halted transaction to its halt reason.
+///
+/// Implemented for closures taking the same arguments as [Self::halt_reason].
+pub trait HaltReasonMapping<EXT, DB: Database> {
+    /// Returns the halt reason of a transaction whose last frame halted with `result`, or `None`
+    /// to keep the mainnet reason.
+    ///
+    /// Called after the transaction state is finalized, the context holds the environment and
+    /// the external context of the transaction.
+    fn halt_reason(
+        &self,
+        context: &mut Context<EXT, DB>,
+        result: &InterpreterResult,
+    ) -> Option<HaltReason>;
+}
+
+impl<EXT, DB: Database, F> HaltReasonMapping<EXT, DB> for F
+where
+    F: Fn(&mut Context<EXT, DB>, &InterpreterResult) -> Option<HaltReason>,
+{
+    fn halt_reason(
+        &self,
+        context: &mut Context<EXT, DB>,
+        result: &InterpreterResult,
+    ) -> Option<HaltReason> {
+        self(context, result)
+    }
+}
+
+/// Returns the handle register that wraps the `output` handle to map the reason of halted
+/// transactions with `mapping`.
+///
+/// Successful and reverted transactions are not mapped, neither is the gas used.
+pub fn halt_reason_handle_register<EXT: 'static, DB: Database + 'static>(
+    mapping: impl HaltReasonMapping<EXT, DB> + 'static,
+) -> HandleRegisterBox<EXT, DB> {
+    let mapping = Arc::new(mapping);
+    Box::new(move |handler| {
+        let mapping = mapping.clone();
+        let old_handle = handler.post_execution.output.clone();
+        handler.post_execution.output = Arc::new(
+            move |context: &mut Context<EXT, DB>, frame_result: FrameResult| {
+                let result = frame_result.interpreter_result().clone();
+                let mut output = old_handle(context, frame_result)?;
+                if let ExecutionResult::Halt { reason, .. } = &mut output.result {
+                    if let Some(mapped) = mapping.halt_reason(context, &result) {
+                        *reason = mapped;
+                    }
+                }
+                Ok(output)
+            },
+        );
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::BenchmarkDB,
+        interpreter::{opcode, InstructionResult},
+        primitives::{Bytecode, Bytes, CustomHaltReason},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum L2HaltReason {
+        L1FeeInsufficient,
+    }
+
+    impl CustomHaltReason for L2HaltReason {
+        fn code(&self) -> u8 {
+            1
+        }
+
+        fn from_code(code: u8) -> Option<Self> {
+            (code == 1).then_some(Self::L1FeeInsufficient)
+        }
+    }
+
+    #[test]
+    fn map_halt_reason() {
+        let mapping = |_: &mut Context<(), BenchmarkDB>, result: &InterpreterResult| {
+            (result.result == InstructionResult::InvalidFEOpcode)
+                .then(|| HaltReason::custom(L2HaltReason::L1FeeInsufficient))
+        };
+        let mut evm =
+            benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[opcode::INVALID])))
+                .append_handler_register_box(halt_reason_handle_register(mapping))
+                .build();
+
+        let result = evm.transact().unwrap().result;
+        assert_eq!(
+            result.custom_halt_reason(),
+            Some(L2HaltReason::L1FeeInsufficient)
+        );
+        assert_eq!(result.gas_used(), 100_000);
+    }
+}
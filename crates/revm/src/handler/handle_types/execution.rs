@@ -73,6 +73,10 @@ pub type InsertCreateOutcomeHandle<'a, EXT, DB> = Arc<
         + 'a,
 >;
 
+/// Gas forwarded to a sub frame, given the remaining gas of the caller and the requested gas,
+/// see [Host::forwarded_gas](crate::interpreter::Host::forwarded_gas).
+pub type ForwardedGasHandle<'a, EXT, DB> = Arc<dyn Fn(&mut Context<EXT, DB>, u64, u64) -> u64 + 'a>;
+
 /// Handles related to stack frames.
 pub struct ExecutionHandler<'a, EXT, DB: Database> {
     /// Handles last frame return, modified gas for refund and
@@ -90,6 +94,9 @@ pub struct ExecutionHandler<'a, EXT, DB: Database> {
     pub create_return: FrameCreateReturnHandle<'a, EXT, DB>,
     /// Insert create outcome.
     pub insert_create_outcome: InsertCreateOutcomeHandle<'a, EXT, DB>,
+    /// Gas forwarded by the `CALL` and `CREATE` instruction families, the 63/64 rule on
+    /// mainnet.
+    pub forwarded_gas: ForwardedGasHandle<'a, EXT, DB>,
 }
 
 impl<'a, EXT: 'a, DB: Database + 'a> ExecutionHandler<'a, EXT, DB> {
@@ -103,6 +110,7 @@ impl<'a, EXT: 'a, DB: Database + 'a> ExecutionHandler<'a, EXT, DB> {
             create: Arc::new(mainnet::create::<SPEC, EXT, DB>),
             create_return: Arc::new(mainnet::create_return::<SPEC, EXT, DB>),
             insert_create_outcome: Arc::new(mainnet::insert_create_outcome),
+            forwarded_gas: Arc::new(mainnet::forwarded_gas),
         }
     }
 }
@@ -182,4 +190,15 @@ impl<'a, EXT, DB: Database> ExecutionHandler<'a, EXT, DB> {
     ) -> Result<(), EVMError<DB::Error>> {
         (self.insert_create_outcome)(context, frame, outcome)
     }
+
+    /// Call handler for the gas forwarded to a sub frame.
+    #[inline]
+    pub fn forwarded_gas(
+        &self,
+        context: &mut Context<EXT, DB>,
+        remaining: u64,
+        requested: u64,
+    ) -> u64 {
+        (self.forwarded_gas)(context, remaining, requested)
+    }
 }
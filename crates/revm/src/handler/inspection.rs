@@ -0,0 +1,181 @@
+//! Gas-free execution for analyzers.
+//!
+//! In [InspectionMode] the transaction executes with [INSPECTION_GAS] instead of its gas limit,
+//! so that no frame runs out of gas and every path of a call can be explored, while everything
+//! else behaves as usual. The gas used by execution is not charged: the transaction only pays
+//! its intrinsic gas if it succeeds or reverts. As gas no longer bounds execution, the number
+//! of executed instructions is; a transaction reaching the step limit halts with
+//! [HaltReason::StepLimit](crate::primitives::HaltReason::StepLimit).
+use super::register::{EvmHandler, HandleRegisterBox};
+use crate::{
+    interpreter::{
+        opcode::InstructionTables, CallInputs, CreateInputs, Gas, InstructionResult, Interpreter,
+    },
+    primitives::db::Database,
+    Context, Evm, FrameResult,
+};
+use core::cell::Cell;
+use std::{boxed::Box, rc::Rc, sync::Arc};
+
+/// Gas limit of the transaction frame in [InspectionMode].
+///
+/// Sub calls still receive at most 63/64 of the gas left, this leaves enough gas for the
+/// deepest call stack.
+pub const INSPECTION_GAS: u64 = u64::MAX / 4;
+
+/// Default number of instructions a transaction can execute in [InspectionMode].
+pub const DEFAULT_STEP_LIMIT: u64 = 10_000_000;
+
+/// Gas-free execution mode, see the [module documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InspectionMode {
+    step_limit: u64,
+}
+
+impl Default for InspectionMode {
+    fn default() -> Self {
+        Self::new(DEFAULT_STEP_LIMIT)
+    }
+}
+
+impl InspectionMode {
+    /// Creates the mode halting transactions after `step_limit` instructions.
+    pub fn new(step_limit: u64) -> Self {
+        Self { step_limit }
+    }
+
+    /// Returns the number of instructions a transaction can execute.
+    pub fn step_limit(&self) -> u64 {
+        self.step_limit
+    }
+
+    /// Returns the handle register enabling the mode.
+    pub fn into_handle_register<EXT: 'static, DB: Database + 'static>(
+        self,
+    ) -> HandleRegisterBox<EXT, DB> {
+        Box::new(move |handler| self.register(handler))
+    }
+
+    /// Registers the mode in the handler.
+    pub fn register<'a, EXT: 'a, DB: Database + 'a>(&self, handler: &mut EvmHandler<'a, EXT, DB>) {
+        let step_limit = self.step_limit;
+        let steps = Rc::new(Cell::new(0u64));
+        // Gas limit of the transaction frame, restored before the gas is settled.
+        let gas_limit = Rc::new(Cell::new(0u64));
+
+        let mut table = handler
+            .take_instruction_table()
+            .expect("Handler must have instruction table");
+        table.convert_boxed();
+        let InstructionTables::Boxed(instructions) = &mut table else {
+            unreachable!("table was converted to boxed variant")
+        };
+        for instruction in instructions.iter_mut() {
+            let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+            let steps = steps.clone();
+            *instruction = Box::new(
+                move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                    if steps.get() >= step_limit {
+                        interpreter.instruction_result = InstructionResult::StepLimit;
+                        return;
+                    }
+                    steps.set(steps.get() + 1);
+                    old(interpreter, host)
+                },
+            );
+        }
+        handler.set_instruction_table(table);
+
+        let old_handle = handler.execution.call.clone();
+        let (call_steps, call_gas_limit) = (steps.clone(), gas_limit.clone());
+        handler.execution.call = Arc::new(
+            move |context: &mut Context<EXT, DB>, mut inputs: Box<CallInputs>| {
+                if context.evm.journaled_state.depth() == 0 {
+                    call_steps.set(0);
+                    call_gas_limit.set(inputs.gas_limit);
+                    inputs.gas_limit = INSPECTION_GAS;
+                }
+                old_handle(context, inputs)
+            },
+        );
+        let old_handle = handler.execution.create.clone();
+        let (create_steps, create_gas_limit) = (steps, gas_limit.clone());
+        handler.execution.create = Arc::new(
+            move |context: &mut Context<EXT, DB>, mut inputs: Box<CreateInputs>| {
+                if context.evm.journaled_state.depth() == 0 {
+                    create_steps.set(0);
+                    create_gas_limit.set(inputs.gas_limit);
+                    inputs.gas_limit = INSPECTION_GAS;
+                }
+                old_handle(context, inputs)
+            },
+        );
+
+        let old_handle = handler.execution.last_frame_return.clone();
+        handler.execution.last_frame_return = Arc::new(
+            move |context: &mut Context<EXT, DB>, frame_result: &mut FrameResult| {
+                *frame_result.gas_mut() = Gas::new(gas_limit.get());
+                old_handle(context, frame_result)
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::BenchmarkDB,
+        interpreter::opcode,
+        primitives::{Address, Bytecode, Bytes, ExecutionResult, HaltReason},
+        test_utils::benchmark_evm_builder,
+    };
+
+    fn evm(code: &'static [u8], mode: InspectionMode) -> Evm<'static, (), BenchmarkDB> {
+        benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(code)))
+            .modify_tx_env(|tx| tx.gas_limit = 30_000)
+            .append_handler_register_box(mode.into_handle_register())
+            .build()
+    }
+
+    #[test]
+    fn ignore_gas() {
+        // SSTORE(1, 1) SSTORE(2, 1) STOP costs more than the gas limit.
+        let code = &[
+            opcode::PUSH1,
+            1,
+            opcode::PUSH1,
+            1,
+            opcode::SSTORE,
+            opcode::PUSH1,
+            1,
+            opcode::PUSH1,
+            2,
+            opcode::SSTORE,
+            opcode::STOP,
+        ];
+        let output = evm(code, InspectionMode::default()).transact().unwrap();
+        let ExecutionResult::Success { gas_used, .. } = output.result else {
+            panic!("transaction failed: {:?}", output.result);
+        };
+        assert_eq!(gas_used, 21_000);
+        assert_eq!(output.state[&Address::ZERO].storage.len(), 2);
+    }
+
+    #[test]
+    fn halt_on_step_limit() {
+        // JUMPDEST PUSH0 JUMP
+        let code = &[opcode::JUMPDEST, opcode::PUSH0, opcode::JUMP];
+        let mut evm = evm(code, InspectionMode::new(1000));
+        for _ in 0..2 {
+            let result = evm.transact().unwrap().result;
+            assert_eq!(
+                result,
+                ExecutionResult::Halt {
+                    reason: HaltReason::StepLimit,
+                    gas_used: 30_000,
+                }
+            );
+        }
+    }
+}
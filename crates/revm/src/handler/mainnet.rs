@@ -6,9 +6,11 @@ mod pre_execution;
 mod validation;
 
 pub use execution::{
-    call, call_return, create, create_return, frame_return_with_refund_flag, insert_call_outcome,
-    insert_create_outcome, last_frame_return,
+    call, call_return, create, create_return, forwarded_gas, frame_return_with_refund_flag,
+    insert_call_outcome, insert_create_outcome, last_frame_return, limit_return_data,
+};
+pub use post_execution::{end, output, reimburse_account, reimburse_caller, reward_beneficiary};
+pub use pre_execution::{
+    deduct_caller, deduct_caller_inner, deduct_fee_inner, load_accounts, load_precompiles,
 };
-pub use post_execution::{end, output, reimburse_caller, reward_beneficiary};
-pub use pre_execution::{deduct_caller, deduct_caller_inner, load_accounts, load_precompiles};
 pub use validation::{validate_env, validate_initial_tx_gas, validate_tx_against_state};
@@ -1,10 +1,10 @@
 use crate::{
     db::Database,
     interpreter::{
-        return_ok, return_revert, CallInputs, CreateInputs, CreateOutcome, Gas, InstructionResult,
-        SharedMemory,
+        gas, return_ok, return_revert, CallInputs, CreateInputs, CreateOutcome, Gas,
+        InstructionResult, SharedMemory,
     },
-    primitives::{EVMError, Env, Spec, SpecId},
+    primitives::{Bytes, CfgEnv, EVMError, Env, ReturnDataLimitAction, Spec},
     CallFrame, Context, CreateFrame, Frame, FrameOrResult, FrameResult,
 };
 use revm_interpreter::{CallOutcome, InterpreterResult};
@@ -39,11 +39,12 @@ pub fn frame_return_with_refund_flag<SPEC: Spec>(
 
     // Calculate gas refund for transaction.
     // If config is set to disable gas refund, it will return 0.
-    // If spec is set to london, it will decrease the maximum refund amount to 5th part of
-    // gas spend. (Before london it was 2th part of gas spend)
+    // Otherwise the refund is capped by the refund policy, which by default limits it to the
+    // 5th part of gas spend since london. (Before london it was 2th part of gas spend)
     if refund_enabled {
         // EIP-3529: Reduction in refunds
-        gas.set_final_refund(SPEC::SPEC_ID.is_enabled_in(SpecId::LONDON));
+        let max_refund = env.cfg.refund_policy.max_refund(SPEC::SPEC_ID, gas.spent());
+        gas.cap_refund(max_refund);
     }
 }
 
@@ -66,12 +67,36 @@ pub fn call<SPEC: Spec, EXT, DB: Database>(
     context.evm.make_call_frame(&inputs)
 }
 
+/// Applies the [ReturnDataLimit](crate::primitives::ReturnDataLimit) of the config to the
+/// result of the frame at the given journal depth.
+#[inline]
+pub fn limit_return_data(cfg: &CfgEnv, depth: u64, interpreter_result: &mut InterpreterResult) {
+    let Some(limit) = cfg.return_data_limit else {
+        return;
+    };
+    if !limit.applies_to_depth(depth) || interpreter_result.output.len() <= limit.max_size {
+        return;
+    }
+    match limit.action {
+        ReturnDataLimitAction::Halt => {
+            interpreter_result.result = InstructionResult::ReturnDataLimit;
+            interpreter_result.output = Bytes::new();
+        }
+        ReturnDataLimitAction::Truncate => interpreter_result.output.truncate(limit.max_size),
+    }
+}
+
 #[inline]
 pub fn call_return<EXT, DB: Database>(
     context: &mut Context<EXT, DB>,
     frame: Box<CallFrame>,
-    interpreter_result: InterpreterResult,
+    mut interpreter_result: InterpreterResult,
 ) -> Result<CallOutcome, EVMError<DB::Error>> {
+    limit_return_data(
+        &context.evm.env.cfg,
+        context.evm.journaled_state.depth(),
+        &mut interpreter_result,
+    );
     context
         .evm
         .call_return(&interpreter_result, frame.frame_data.checkpoint);
@@ -111,6 +136,13 @@ pub fn create_return<SPEC: Spec, EXT, DB: Database>(
     frame: Box<CreateFrame>,
     mut interpreter_result: InterpreterResult,
 ) -> Result<CreateOutcome, EVMError<DB::Error>> {
+    if interpreter_result.result.is_revert() {
+        limit_return_data(
+            &context.evm.env.cfg,
+            context.evm.journaled_state.depth(),
+            &mut interpreter_result,
+        );
+    }
     context.evm.create_return::<SPEC>(
         &mut interpreter_result,
         frame.created_address,
@@ -136,11 +168,27 @@ pub fn insert_create_outcome<EXT, DB: Database>(
     Ok(())
 }
 
+/// Forwards all but one 64th of the remaining gas, as in EIP-150.
+#[inline]
+pub fn forwarded_gas<EXT, DB: Database>(
+    _context: &mut Context<EXT, DB>,
+    remaining: u64,
+    requested: u64,
+) -> u64 {
+    gas::forwarded_gas(remaining, requested)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        interpreter::opcode,
+        primitives::{Address, Bytecode, ExecutionResult, HaltReason, ReturnDataLimit, TransactTo},
+        test_utils::benchmark_evm_builder,
+        Evm,
+    };
     use revm_interpreter::primitives::CancunSpec;
-    use revm_precompile::Bytes;
+    use std::sync::Arc;
 
     /// Creates frame result.
     fn call_last_frame_return(instruction_result: InstructionResult, gas: Gas) -> Gas {
@@ -148,11 +196,7 @@ mod tests {
         env.tx.gas_limit = 100;
 
         let mut first_frame = FrameResult::Call(CallOutcome::new(
-            InterpreterResult {
-                result: instruction_result,
-                output: Bytes::new(),
-                gas,
-            },
+            InterpreterResult::new(instruction_result, Bytes::new(), gas),
             0..0,
         ));
         frame_return_with_refund_flag::<CancunSpec>(&env, &mut first_frame, true);
@@ -191,4 +235,103 @@ mod tests {
         assert_eq!(gas.spent(), 10);
         assert_eq!(gas.refunded(), 0);
     }
+
+    #[test]
+    fn test_return_data_limit() {
+        // returns 64 zero bytes
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x40,
+            opcode::PUSH1,
+            0x00,
+            opcode::RETURN,
+        ]);
+        let transact = |limit: ReturnDataLimit| {
+            benchmark_evm_builder(Bytecode::new_raw(code.clone()))
+                .modify_cfg_env(|cfg| cfg.return_data_limit = Some(limit))
+                .build()
+                .transact()
+                .unwrap()
+                .result
+        };
+
+        assert_eq!(
+            transact(ReturnDataLimit::new(64)).output().unwrap().len(),
+            64
+        );
+        assert_eq!(
+            transact(ReturnDataLimit::new(32).truncating())
+                .output()
+                .unwrap()
+                .len(),
+            32
+        );
+        assert!(matches!(
+            transact(ReturnDataLimit::new(32)),
+            ExecutionResult::Halt {
+                reason: HaltReason::ReturnDataLimit,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_forwarded_gas_handle() {
+        use crate::{
+            db::{CacheDB, EmptyDB},
+            primitives::{AccountInfo, U256},
+        };
+
+        // Child: SSTORE(0, GAS).
+        let child = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::GAS,
+            opcode::PUSH0,
+            opcode::SSTORE,
+            opcode::STOP,
+        ]));
+        // Parent: CALL(GAS, child, 0, 0, 0, 0, 0).
+        let parent = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH1,
+            2,
+            opcode::GAS,
+            opcode::CALL,
+            opcode::STOP,
+        ]));
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, code) in [(1, parent), (2, child)] {
+            let info = AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code);
+            db.insert_account_info(Address::with_last_byte(address), info);
+        }
+        let child_gas = |forward_all: bool| {
+            let mut evm = Evm::builder()
+                .with_db(db.clone())
+                .modify_tx_env(|tx| {
+                    tx.caller = Address::with_last_byte(0x10);
+                    tx.transact_to = TransactTo::Call(Address::with_last_byte(1));
+                    tx.gas_limit = 100_000;
+                })
+                .build();
+            if forward_all {
+                // Without EIP-150 forwarding, the caller keeps nothing.
+                evm.handler.execution.forwarded_gas = Arc::new(
+                    |_: &mut Context<(), CacheDB<EmptyDB>>, remaining: u64, requested: u64| {
+                        remaining.min(requested)
+                    },
+                );
+            }
+            let state = evm.transact().unwrap().state;
+            state[&Address::with_last_byte(2)].storage[&U256::ZERO]
+                .present_value
+                .to::<u64>()
+        };
+
+        // The child observes its gas after paying 2 for `GAS`.
+        let (all, mainnet) = (child_gas(true), child_gas(false));
+        assert_eq!(all - mainnet, (all + 2) / 64);
+    }
 }
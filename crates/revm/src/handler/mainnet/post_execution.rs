@@ -1,11 +1,39 @@
 use crate::{
-    interpreter::{Gas, SuccessOrHalt},
+    interpreter::{gas, Gas, SuccessOrHalt},
     primitives::{
-        db::Database, EVMError, ExecutionResult, ResultAndState, Spec, SpecId::LONDON, U256,
+        db::Database,
+        Address, CalldataFloor, EVMError, ExecutionMetadata, ExecutionResult, ResultAndState, Spec,
+        SpecId::{LONDON, PRAGUE},
+        U256,
     },
     Context, FrameResult,
 };
 
+/// Returns the gas used by the execution, after refunds, against the calldata floor of the
+/// transaction if Prague is enabled.
+#[inline]
+pub fn calldata_floor<EXT, DB: Database>(
+    context: &Context<EXT, DB>,
+    gas: &Gas,
+) -> Option<CalldataFloor> {
+    if !context.evm.spec_id().is_enabled_in(PRAGUE) {
+        return None;
+    }
+    Some(CalldataFloor {
+        execution_gas_used: gas.spent() - gas.refunded() as u64,
+        floor_gas: gas::calc_tx_floor_cost(&context.evm.env.tx.data),
+    })
+}
+
+/// Returns the gas charged to the transaction.
+#[inline]
+fn charged_gas<EXT, DB: Database>(context: &Context<EXT, DB>, gas: &Gas) -> u64 {
+    match calldata_floor(context, gas) {
+        Some(floor) => floor.gas_used(),
+        None => gas.spent() - gas.refunded() as u64,
+    }
+}
+
 /// Mainnet end handle does not change the output.
 #[inline]
 pub fn end<EXT, DB: Database>(
@@ -21,7 +49,12 @@ pub fn reward_beneficiary<SPEC: Spec, EXT, DB: Database>(
     context: &mut Context<EXT, DB>,
     gas: &Gas,
 ) -> Result<(), EVMError<DB::Error>> {
-    let beneficiary = context.evm.env.block.coinbase;
+    // System transactions pay no fee.
+    if context.evm.env.tx.is_system_tx {
+        return Ok(());
+    }
+    let beneficiary = context.evm.env.effective_coinbase();
+    let gas_used = charged_gas(context, gas);
     let effective_gas_price = context.evm.env.effective_gas_price();
 
     // transfer fee to coinbase/beneficiary.
@@ -42,7 +75,7 @@ pub fn reward_beneficiary<SPEC: Spec, EXT, DB: Database>(
     coinbase_account.info.balance = coinbase_account
         .info
         .balance
-        .saturating_add(coinbase_gas_price * U256::from(gas.spent() - gas.refunded() as u64));
+        .saturating_add(coinbase_gas_price * U256::from(gas_used));
 
     Ok(())
 }
@@ -53,19 +86,33 @@ pub fn reimburse_caller<SPEC: Spec, EXT, DB: Database>(
     gas: &Gas,
 ) -> Result<(), EVMError<DB::Error>> {
     let caller = context.evm.env.tx.caller;
+    reimburse_account::<SPEC, EXT, DB>(context, gas, caller)
+}
+
+/// Returns the balance of not spent gas to the account that paid for it.
+#[inline]
+pub fn reimburse_account<SPEC: Spec, EXT, DB: Database>(
+    context: &mut Context<EXT, DB>,
+    gas: &Gas,
+    fee_payer: Address,
+) -> Result<(), EVMError<DB::Error>> {
+    if context.evm.env.tx.is_system_tx {
+        return Ok(());
+    }
+    let gas_used = charged_gas(context, gas);
     let effective_gas_price = context.evm.env.effective_gas_price();
 
     // return balance of not spend gas.
-    let (caller_account, _) = context
+    let (fee_payer_account, _) = context
         .evm
         .inner
         .journaled_state
-        .load_account(caller, &mut context.evm.inner.db)?;
+        .load_account(fee_payer, &mut context.evm.inner.db)?;
 
-    caller_account.info.balance = caller_account
+    fee_payer_account.info.balance = fee_payer_account
         .info
         .balance
-        .saturating_add(effective_gas_price * U256::from(gas.remaining() + gas.refunded() as u64));
+        .saturating_add(effective_gas_price * U256::from(gas.limit() - gas_used));
 
     Ok(())
 }
@@ -78,15 +125,35 @@ pub fn output<EXT, DB: Database>(
 ) -> Result<ResultAndState, EVMError<DB::Error>> {
     context.evm.take_error()?;
     // used gas with refund calculated.
-    let gas_refunded = result.gas().refunded() as u64;
-    let final_gas_used = result.gas().spent() - gas_refunded;
+    let calldata_floor = calldata_floor(context, result.gas());
+    let mut gas_refunded = result.gas().refunded() as u64;
+    let mut final_gas_used = result.gas().spent() - gas_refunded;
+    // EIP-7623: the floor takes the refund back first.
+    if let Some(floor) = calldata_floor.filter(CalldataFloor::is_applied) {
+        final_gas_used = floor.floor_gas;
+        gas_refunded = result.gas().spent().saturating_sub(final_gas_used);
+    }
+    let fees = context
+        .evm
+        .env
+        .transaction_fees(context.evm.spec_id(), final_gas_used);
     let output = result.output();
     let instruction_result = result.into_interpreter_result();
+    #[cfg(feature = "instrumentation")]
+    let (counters, code_cache_stats) = (
+        Some(core::mem::take(&mut context.evm.counters)),
+        Some(context.evm.journaled_state.code_cache_stats),
+    );
+    #[cfg(not(feature = "instrumentation"))]
+    let (counters, code_cache_stats) = (None, None);
+
+    let preimages = context.evm.journaled_state.preimages.take();
+    let precompile_calls = context.evm.precompile_calls.take();
 
     // reset journal and return present state.
     let (state, logs) = context.evm.journaled_state.finalize();
 
-    let result = match instruction_result.result.into() {
+    let result = match instruction_result.success_or_halt() {
         SuccessOrHalt::Success(reason) => ExecutionResult::Success {
             reason,
             gas_used: final_gas_used,
@@ -113,5 +180,216 @@ pub fn output<EXT, DB: Database>(
         }
     };
 
-    Ok(ResultAndState { result, state })
+    let mut metadata = ExecutionMetadata::default();
+    metadata.calldata_floor = calldata_floor;
+    metadata.counters = counters;
+    metadata.code_cache_stats = code_cache_stats;
+    metadata.fees = fees;
+    metadata.preimages = preimages;
+    metadata.precompile_calls = precompile_calls;
+
+    Ok(ResultAndState {
+        result,
+        state,
+        metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        primitives::{
+            Address, Bytecode, Bytes, EVMError, InvalidTransaction, ResultAndState, SpecId,
+            TransactTo, U256,
+        },
+        test_utils::benchmark_evm_builder,
+        Evm,
+    };
+
+    use core::convert::Infallible;
+
+    fn transact(spec_id: SpecId, gas_limit: u64) -> Result<ResultAndState, EVMError<Infallible>> {
+        benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[0x00])))
+            .with_spec_id(spec_id)
+            .modify_tx_env(|tx| {
+                tx.gas_limit = gas_limit;
+                // 100 non-zero bytes cost 22_600 gas to execute and have a floor of 25_000.
+                tx.data = Bytes::from(vec![1; 100]);
+            })
+            .build()
+            .transact()
+    }
+
+    #[test]
+    fn calldata_floor() {
+        let output = transact(SpecId::CANCUN, 100_000).unwrap();
+        assert_eq!(output.result.gas_used(), 22_600);
+        assert_eq!(output.metadata.calldata_floor, None);
+
+        let output = transact(SpecId::PRAGUE, 100_000).unwrap();
+        assert_eq!(output.result.gas_used(), 25_000);
+        let floor = output.metadata.calldata_floor.unwrap();
+        assert!(floor.is_applied());
+        assert_eq!(floor.execution_gas_used, 22_600);
+        assert_eq!(floor.floor_gas, 25_000);
+
+        assert!(matches!(
+            transact(SpecId::PRAGUE, 24_000),
+            Err(EVMError::Transaction(
+                InvalidTransaction::GasFloorMoreThanGasLimit
+            ))
+        ));
+    }
+
+    #[test]
+    fn default_spec_applies_calldata_floor() {
+        // The builder defaults to `SpecId::LATEST`, which includes Prague.
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[0x00])))
+            .modify_tx_env(|tx| {
+                tx.gas_limit = 24_000;
+                tx.data = Bytes::from(vec![1; 100]);
+            })
+            .build();
+        assert_eq!(evm.spec_id(), SpecId::LATEST);
+        assert!(matches!(
+            evm.transact(),
+            Err(EVMError::Transaction(
+                InvalidTransaction::GasFloorMoreThanGasLimit
+            ))
+        ));
+    }
+
+    #[test]
+    fn system_tx_pays_no_fee() {
+        let caller = Address::with_last_byte(2);
+        let coinbase = Address::with_last_byte(3);
+        let output = benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[0x00])))
+            .modify_block_env(|block| {
+                block.coinbase = coinbase;
+                block.basefee = U256::from(100);
+            })
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.gas_price = U256::from(10);
+                tx.nonce = Some(5);
+                tx.is_system_tx = true;
+            })
+            .build()
+            .transact()
+            .unwrap();
+
+        assert!(output.result.is_success());
+        let caller = &output.state[&caller];
+        assert_eq!(caller.info.balance, U256::ZERO);
+        assert_eq!(caller.info.nonce, 0);
+        let coinbase = output.state.get(&coinbase);
+        assert_eq!(
+            coinbase
+                .map(|account| account.info.balance)
+                .unwrap_or_default(),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn transaction_fees() {
+        let coinbase = Address::with_last_byte(3);
+        let output = benchmark_evm_builder(Bytecode::new_raw(Bytes::from_static(&[0x00])))
+            .modify_block_env(|block| {
+                block.coinbase = coinbase;
+                block.basefee = U256::from(7);
+            })
+            .modify_tx_env(|tx| {
+                tx.gas_price = U256::from(10);
+                tx.gas_priority_fee = Some(U256::from(2));
+            })
+            .build()
+            .transact()
+            .unwrap();
+
+        let gas_used = U256::from(output.result.gas_used());
+        let fees = output.metadata.fees;
+        assert_eq!(fees.effective_gas_price, U256::from(9));
+        assert_eq!(fees.priority_fee, gas_used * U256::from(2));
+        assert_eq!(fees.burnt_base_fee, gas_used * U256::from(7));
+        assert_eq!(fees.blob_fee, U256::ZERO);
+        assert_eq!(fees.total(), gas_used * U256::from(9));
+        assert_eq!(output.state[&coinbase].info.balance, fees.priority_fee);
+    }
+
+    #[cfg(feature = "instrumentation")]
+    #[test]
+    fn count_instructions() {
+        use crate::{interpreter::opcode, primitives::ExecutionCounters};
+
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x01,
+            opcode::PUSH1,
+            0x00,
+            opcode::SSTORE,
+            opcode::PUSH1,
+            0x00,
+            opcode::SLOAD,
+            opcode::PUSH1,
+            0x00,
+            opcode::MSTORE,
+            opcode::STOP,
+        ]);
+        let output = benchmark_evm_builder(Bytecode::new_raw(code))
+            .build()
+            .transact()
+            .unwrap();
+
+        assert_eq!(
+            output.metadata.counters,
+            Some(ExecutionCounters {
+                instructions: 8,
+                memory_expansions: 1,
+                sloads: 1,
+                sstores: 1,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[cfg(feature = "instrumentation")]
+    #[test]
+    fn code_cache_stats() {
+        use crate::{
+            db::{CacheDB, EmptyDB},
+            primitives::{AccountInfo, CodeCacheStats},
+        };
+
+        // EXTCODESIZE(2) STOP, shared by both accounts.
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x02, 0x3b, 0x00]));
+        let code_hash = code.hash_slow();
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.contracts.insert(code_hash, code);
+        for address in [Address::with_last_byte(1), Address::with_last_byte(2)] {
+            db.insert_account_info(
+                address,
+                AccountInfo {
+                    code_hash,
+                    code: None,
+                    ..Default::default()
+                },
+            );
+        }
+        let output = Evm::builder()
+            .with_db(db)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::Call(Address::with_last_byte(1));
+                tx.gas_limit = 100_000;
+            })
+            .build()
+            .transact()
+            .unwrap();
+
+        // The call reads the code from the database, `EXTCODESIZE` from the cache.
+        assert_eq!(
+            output.metadata.code_cache_stats,
+            Some(CodeCacheStats { hits: 1, misses: 1 })
+        );
+    }
 }
@@ -28,12 +28,14 @@ pub fn load_accounts<SPEC: Spec, EXT, DB: Database>(
 ) -> Result<(), EVMError<DB::Error>> {
     // set journaling state flag.
     context.evm.journaled_state.set_spec_id(SPEC::SPEC_ID);
+    let carryover = context.evm.env.cfg.warm_carryover();
+    context.evm.journaled_state.set_warm_carryover(carryover);
 
     // load coinbase
     // EIP-3651: Warm COINBASE. Starts the `COINBASE` address warm
     if SPEC::enabled(SHANGHAI) {
         context.evm.inner.journaled_state.initial_account_load(
-            context.evm.inner.env.block.coinbase,
+            context.evm.inner.env.effective_coinbase(),
             &[],
             &mut context.evm.inner.db,
         )?;
@@ -43,10 +45,9 @@ pub fn load_accounts<SPEC: Spec, EXT, DB: Database>(
     Ok(())
 }
 
-/// Helper function that deducts the caller balance.
+/// Helper function that deducts the gas cost of the transaction from the account paying it.
 #[inline]
-pub fn deduct_caller_inner<SPEC: Spec>(caller_account: &mut Account, env: &Env) {
-    // Subtract gas costs from the caller's account.
+pub fn deduct_fee_inner<SPEC: Spec>(fee_payer_account: &mut Account, env: &Env) {
     // We need to saturate the gas cost to prevent underflow in case that `disable_balance_check` is enabled.
     let mut gas_cost = U256::from(env.tx.gas_limit).saturating_mul(env.effective_gas_price());
 
@@ -56,8 +57,21 @@ pub fn deduct_caller_inner<SPEC: Spec>(caller_account: &mut Account, env: &Env)
         gas_cost = gas_cost.saturating_add(data_fee);
     }
 
-    // set new caller account balance.
-    caller_account.info.balance = caller_account.info.balance.saturating_sub(gas_cost);
+    fee_payer_account.info.balance = fee_payer_account.info.balance.saturating_sub(gas_cost);
+    fee_payer_account.mark_touch();
+}
+
+/// Helper function that deducts the caller balance.
+///
+/// System transactions pay no fee and leave the caller untouched.
+#[inline]
+pub fn deduct_caller_inner<SPEC: Spec>(caller_account: &mut Account, env: &Env) {
+    if env.tx.is_system_tx {
+        return;
+    }
+
+    // Subtract gas costs from the caller's account.
+    deduct_fee_inner::<SPEC>(caller_account, env);
 
     // bump the nonce for calls. Nonce for CREATE will be bumped in `handle_create`.
     if matches!(env.tx.transact_to, TransactTo::Call(_)) {
@@ -1,7 +1,7 @@
 use revm_interpreter::gas;
 
 use crate::{
-    primitives::{db::Database, EVMError, Env, InvalidTransaction, Spec},
+    primitives::{db::Database, EVMError, Env, InvalidTransaction, Spec, SpecId::PRAGUE},
     Context,
 };
 
@@ -50,5 +50,10 @@ pub fn validate_initial_tx_gas<SPEC: Spec, DB: Database>(
     if initial_gas_spend > env.tx.gas_limit {
         return Err(InvalidTransaction::CallGasCostMoreThanGasLimit.into());
     }
+
+    // EIP-7623: Increase calldata cost
+    if SPEC::enabled(PRAGUE) && gas::calc_tx_floor_cost(input) > env.tx.gas_limit {
+        return Err(InvalidTransaction::GasFloorMoreThanGasLimit.into());
+    }
     Ok(initial_gas_spend)
 }
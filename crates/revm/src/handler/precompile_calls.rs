@@ -0,0 +1,75 @@
+//! Recording of the precompile calls of a transaction.
+use super::register::EvmHandler;
+use crate::{primitives::db::Database, Context};
+use std::{sync::Arc, vec::Vec};
+
+/// Registers a handle that records the input and output of every precompile call of the
+/// transaction and returns them in
+/// [ExecutionMetadata::precompile_calls](crate::primitives::ExecutionMetadata::precompile_calls).
+///
+/// Proving pipelines can use them to prove or verify the precompile work separately from the
+/// execution trace. Calls of reverted frames and failed calls are recorded too.
+pub fn precompile_call_handle_register<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+) {
+    let old_handle = handler.pre_execution.load_accounts.clone();
+    handler.pre_execution.load_accounts = Arc::new(move |context: &mut Context<EXT, DB>| {
+        context.evm.precompile_calls = Some(Vec::new());
+        old_handle(context)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::opcode,
+        primitives::{Address, Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn record_precompile_calls() {
+        // MSTORE8(0, 0xaa) STATICCALL(gas, identity, 0, 1, 0, 0) POP
+        // STATICCALL(10, sha256, 0, 1, 0, 0) POP STOP
+        let mut code = vec![opcode::PUSH1, 0xaa, opcode::PUSH0, opcode::MSTORE8];
+        for (gas, precompile) in [(None, 4), (Some(10), 2)] {
+            code.extend([
+                opcode::PUSH0,
+                opcode::PUSH0,
+                opcode::PUSH1,
+                1,
+                opcode::PUSH0,
+            ]);
+            code.extend([opcode::PUSH1, precompile]);
+            match gas {
+                Some(gas) => code.extend([opcode::PUSH1, gas]),
+                None => code.push(opcode::GAS),
+            }
+            code.extend([opcode::STATICCALL, opcode::POP]);
+        }
+        code.push(opcode::STOP);
+
+        let output = benchmark_evm_builder(Bytecode::new_raw(code.into()))
+            .append_handler_register(precompile_call_handle_register)
+            .build()
+            .transact()
+            .unwrap();
+        assert!(output.result.is_success());
+
+        let calls = output.metadata.precompile_calls.unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].address, Address::with_last_byte(4));
+        assert_eq!(calls[0].depth, 2);
+        assert_eq!(calls[0].input, Bytes::from_static(&[0xaa]));
+        assert_eq!(calls[0].output, calls[0].input);
+        assert_eq!(calls[0].gas_used, 18);
+        assert!(calls[0].success);
+        // sha256 of one word costs 72 gas.
+        assert_eq!(calls[1].address, Address::with_last_byte(2));
+        assert_eq!(calls[1].gas_limit, 10);
+        assert_eq!(calls[1].gas_used, 10);
+        assert!(calls[1].output.is_empty());
+        assert!(!calls[1].success);
+    }
+}
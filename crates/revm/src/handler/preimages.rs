@@ -0,0 +1,55 @@
+//! Recording of the keccak preimages of the state keys of a transaction.
+use super::register::EvmHandler;
+use crate::{
+    primitives::{db::Database, HashMap},
+    Context,
+};
+use std::sync::Arc;
+
+/// Registers a handle that records the keccak preimages of the addresses and storage keys
+/// loaded by the transaction and returns them in
+/// [ExecutionMetadata::preimages](crate::primitives::ExecutionMetadata::preimages).
+///
+/// The hashes are the keys of the accounts and storage slots in the state trie, so the preimages
+/// let archive tools and trie migrations map the trie back to addresses and slots. Loads of
+/// reverted frames are recorded too.
+pub fn preimage_handle_register<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+) {
+    let old_handle = handler.pre_execution.load_accounts.clone();
+    handler.pre_execution.load_accounts = Arc::new(move |context: &mut Context<EXT, DB>| {
+        context.evm.journaled_state.preimages = Some(HashMap::new());
+        old_handle(context)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::opcode,
+        primitives::{keccak256, Address, Bytecode, Bytes, U256},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn record_address_and_slot_preimages() {
+        // PUSH1 0x05 SLOAD STOP
+        let code = Bytes::from_static(&[opcode::PUSH1, 0x05, opcode::SLOAD, opcode::STOP]);
+        let caller = Address::with_last_byte(1);
+        let output = benchmark_evm_builder(Bytecode::new_raw(code))
+            .append_handler_register(preimage_handle_register)
+            .build()
+            .transact()
+            .unwrap();
+
+        let preimages = output.metadata.preimages.unwrap();
+        let slot = U256::from(5).to_be_bytes::<32>();
+        assert_eq!(preimages[&keccak256(caller)].as_ref(), caller.as_slice());
+        assert_eq!(
+            preimages[&keccak256(Address::ZERO)].as_ref(),
+            Address::ZERO.as_slice()
+        );
+        assert_eq!(preimages[&keccak256(slot)].as_ref(), slot.as_slice());
+    }
+}
@@ -0,0 +1,98 @@
+//! Collection of the [ResourceReport] of a transaction.
+use super::register::EvmHandler;
+use crate::{
+    interpreter::{opcode::InstructionTables, Interpreter},
+    primitives::{db::Database, EVMError, ResourceReport, ResultAndState},
+    Context, Evm,
+};
+use core::cell::Cell;
+use std::{boxed::Box, rc::Rc, sync::Arc};
+
+/// Registers handles that collect the peak memory, call depth and stack depth of the
+/// transaction and return them in
+/// [ExecutionMetadata::resources](crate::primitives::ExecutionMetadata::resources).
+///
+/// Every instruction is wrapped to sample the interpreter after it executes, so this is meant
+/// for profiling and has a noticeable cost.
+pub fn resource_report_handle_register<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+) {
+    let report = Rc::new(Cell::new(ResourceReport::default()));
+
+    let mut table = handler
+        .take_instruction_table()
+        .expect("Handler must have instruction table");
+    table.convert_boxed();
+    let InstructionTables::Boxed(instructions) = &mut table else {
+        unreachable!("table was converted to boxed variant")
+    };
+    for instruction in instructions.iter_mut() {
+        let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+        let report = report.clone();
+        *instruction = Box::new(
+            move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                old(interpreter, host);
+                let mut current = report.get();
+                current.max_memory = current
+                    .max_memory
+                    .max(interpreter.shared_memory.total_len());
+                current.max_stack_depth = current.max_stack_depth.max(interpreter.stack.len());
+                current.max_call_depth = current
+                    .max_call_depth
+                    .max(host.context.evm.journaled_state.depth() as usize);
+                report.set(current);
+            },
+        );
+    }
+    handler.set_instruction_table(table);
+
+    let old_handle = handler.post_execution.end.clone();
+    handler.post_execution.end = Arc::new(
+        move |context: &mut Context<EXT, DB>,
+              output: Result<ResultAndState, EVMError<DB::Error>>| {
+            let resources = report.take();
+            old_handle(context, output).map(|mut output| {
+                output.metadata.resources = Some(resources);
+                output
+            })
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::opcode,
+        primitives::{Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn report_memory_and_stack() {
+        // PUSH1 0x01 PUSH1 0x02 PUSH1 0x40 MSTORE STOP
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x01,
+            opcode::PUSH1,
+            0x02,
+            opcode::PUSH1,
+            0x40,
+            opcode::MSTORE,
+            opcode::STOP,
+        ]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .append_handler_register(resource_report_handle_register)
+            .build();
+
+        let resources = evm.transact().unwrap().metadata.resources;
+        assert_eq!(
+            resources,
+            Some(ResourceReport {
+                max_memory: 0x60,
+                max_call_depth: 1,
+                max_stack_depth: 3,
+            })
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Declarative distribution of transaction fees.
+//!
+//! By default the base fee is burned and the priority fee is paid to the block beneficiary.
+//! [RewardPolicy] allows redirecting both parts, for example to a treasury or a fee vault,
+//! without rewriting the `reward_beneficiary` handle.
+use super::{register::HandleRegisterBox, RewardBeneficiaryHandle};
+use crate::{
+    interpreter::Gas,
+    primitives::{db::Database, Address, EVMError, SpecId, U256},
+    Context,
+};
+use std::{boxed::Box, sync::Arc, vec::Vec};
+
+/// Destination of a part of the transaction fee.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FeeDestination {
+    /// Fee is not credited to any account.
+    Burn,
+    /// Fee is credited to the block beneficiary (`coinbase`).
+    Beneficiary,
+    /// Fee is credited to the given address.
+    Address(Address),
+    /// Fee is split between destinations in proportion to their weights.
+    ///
+    /// Rounding remainder is credited to the last destination.
+    Split(Vec<(FeeDestination, u64)>),
+}
+
+/// Distribution of the base fee and the priority fee of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RewardPolicy {
+    /// Destination of `basefee * gas_used`. Only used since London.
+    pub base_fee: FeeDestination,
+    /// Destination of the fee above the base fee, the whole fee before London.
+    pub priority_fee: FeeDestination,
+}
+
+impl Default for RewardPolicy {
+    /// Mainnet policy: base fee is burned, priority fee goes to the beneficiary.
+    fn default() -> Self {
+        Self {
+            base_fee: FeeDestination::Burn,
+            priority_fee: FeeDestination::Beneficiary,
+        }
+    }
+}
+
+impl RewardPolicy {
+    /// Returns the `reward_beneficiary` handle distributing fees by this policy.
+    pub fn into_handle<'a, EXT: 'a, DB: Database + 'a>(
+        self,
+    ) -> RewardBeneficiaryHandle<'a, EXT, DB> {
+        Arc::new(move |context: &mut Context<EXT, DB>, gas: &Gas| {
+            reward_with_policy(&self, context, gas)
+        })
+    }
+
+    /// Returns the handle register that replaces the `reward_beneficiary` handle with one
+    /// distributing fees by this policy.
+    pub fn into_handle_register<EXT: 'static, DB: Database + 'static>(
+        self,
+    ) -> HandleRegisterBox<EXT, DB> {
+        Box::new(move |handler| {
+            handler.post_execution.reward_beneficiary = self.clone().into_handle();
+        })
+    }
+}
+
+/// Distributes the fees of the transaction by the given [RewardPolicy].
+pub fn reward_with_policy<EXT, DB: Database>(
+    policy: &RewardPolicy,
+    context: &mut Context<EXT, DB>,
+    gas: &Gas,
+) -> Result<(), EVMError<DB::Error>> {
+    let effective_gas_price = context.evm.env.effective_gas_price();
+    let base_fee_per_gas = if context.evm.spec_id().is_enabled_in(SpecId::LONDON) {
+        context.evm.env.block.basefee.min(effective_gas_price)
+    } else {
+        U256::ZERO
+    };
+    let gas_used = U256::from(gas.spent() - gas.refunded() as u64);
+
+    credit(&policy.base_fee, base_fee_per_gas * gas_used, context)?;
+    credit(
+        &policy.priority_fee,
+        (effective_gas_price - base_fee_per_gas) * gas_used,
+        context,
+    )
+}
+
+fn credit<EXT, DB: Database>(
+    destination: &FeeDestination,
+    amount: U256,
+    context: &mut Context<EXT, DB>,
+) -> Result<(), EVMError<DB::Error>> {
+    let address = match destination {
+        FeeDestination::Burn => return Ok(()),
+        FeeDestination::Beneficiary => context.evm.env.effective_coinbase(),
+        FeeDestination::Address(address) => *address,
+        FeeDestination::Split(shares) => {
+            let total_weight = shares
+                .iter()
+                .fold(U256::ZERO, |acc, (_, weight)| acc + U256::from(*weight));
+            let mut remaining = amount;
+            for (index, (destination, weight)) in shares.iter().enumerate() {
+                let share = if index + 1 == shares.len() {
+                    remaining
+                } else if total_weight.is_zero() {
+                    U256::ZERO
+                } else {
+                    amount * U256::from(*weight) / total_weight
+                };
+                remaining -= share;
+                credit(destination, share, context)?;
+            }
+            return Ok(());
+        }
+    };
+
+    let (account, _) = context.evm.load_account(address)?;
+    account.mark_touch();
+    account.info.balance = account.info.balance.saturating_add(amount);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{address, AccountInfo, TransactTo},
+        Evm, InMemoryDB,
+    };
+
+    #[test]
+    fn treasury_and_vault_split() {
+        let caller = address!("1000000000000000000000000000000000000000");
+        let coinbase = address!("2000000000000000000000000000000000000000");
+        let treasury = address!("3000000000000000000000000000000000000000");
+        let vault = address!("4000000000000000000000000000000000000000");
+
+        let policy = RewardPolicy {
+            base_fee: FeeDestination::Address(treasury),
+            priority_fee: FeeDestination::Split(vec![
+                (FeeDestination::Beneficiary, 1),
+                (FeeDestination::Address(vault), 1),
+            ]),
+        };
+
+        let mut evm = Evm::builder()
+            .with_db(InMemoryDB::default())
+            .modify_db(|db| {
+                db.insert_account_info(caller, AccountInfo::from_balance(U256::from(1_000_000_000)))
+            })
+            .modify_block_env(|block| {
+                block.coinbase = coinbase;
+                block.basefee = U256::from(4);
+            })
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TransactTo::Call(Address::ZERO);
+                tx.gas_limit = 21_000;
+                tx.gas_price = U256::from(10);
+            })
+            .append_handler_register_box(policy.into_handle_register())
+            .build();
+
+        let state = evm.transact().unwrap().state;
+        assert_eq!(state[&treasury].info.balance, U256::from(4 * 21_000));
+        assert_eq!(state[&coinbase].info.balance, U256::from(3 * 21_000));
+        assert_eq!(state[&vault].info.balance, U256::from(3 * 21_000));
+    }
+}
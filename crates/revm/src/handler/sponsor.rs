@@ -0,0 +1,183 @@
+//! Transactions whose fees are paid by a sponsor instead of the caller, as done by
+//! meta-transactions and native account abstraction on some L2s.
+//!
+//! A [FeePayerHandle] picks the fee payer of every transaction. If it returns an account other
+//! than the caller, that account must afford the maximum fee, is charged the gas limit before
+//! execution and gets the unused gas back after it. The caller only needs the balance for the
+//! transferred value and still has its nonce bumped. Transactions without a fee payer are
+//! handled by the registered handles as before.
+use super::register::{EvmHandler, HandleRegisterBox};
+use crate::{
+    handler::mainnet,
+    interpreter::Gas,
+    primitives::{db::Database, spec_to_generic, Address, EVMError, Env, Spec, TransactTo},
+    Context,
+};
+use std::{boxed::Box, sync::Arc};
+
+/// Returns the account paying the fees of the transaction, or `None` if the caller pays them.
+pub type FeePayerHandle = Arc<dyn Fn(&Env) -> Option<Address>>;
+
+/// Returns the handle register that charges the fees of transactions to the account returned
+/// by `fee_payer`.
+pub fn fee_payer_handle_register<EXT: 'static, DB: Database + 'static>(
+    fee_payer: FeePayerHandle,
+) -> HandleRegisterBox<EXT, DB> {
+    Box::new(move |handler| register_fee_payer(handler, fee_payer.clone()))
+}
+
+/// Registers the fee payer in the handler, wrapping the validation against state, the
+/// deduction of the caller and its reimbursement.
+pub fn register_fee_payer<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+    fee_payer: FeePayerHandle,
+) {
+    // A transaction paid by its caller is not sponsored.
+    let sponsor = move |env: &Env| fee_payer(env).filter(|payer| *payer != env.tx.caller);
+    let sponsor = Arc::new(sponsor);
+
+    spec_to_generic!(handler.cfg.spec_id, {
+        let old_handle = handler.validation.tx_against_state.clone();
+        let fee_payer = sponsor.clone();
+        handler.validation.tx_against_state =
+            Arc::new(
+                move |context: &mut Context<EXT, DB>| match fee_payer(&context.evm.env) {
+                    Some(fee_payer) => validate_sponsored_tx::<SPEC, EXT, DB>(context, fee_payer),
+                    None => old_handle(context),
+                },
+            );
+
+        let old_handle = handler.pre_execution.deduct_caller.clone();
+        let fee_payer = sponsor.clone();
+        handler.pre_execution.deduct_caller =
+            Arc::new(
+                move |context: &mut Context<EXT, DB>| match fee_payer(&context.evm.env) {
+                    Some(fee_payer) => deduct_fee_payer::<SPEC, EXT, DB>(context, fee_payer),
+                    None => old_handle(context),
+                },
+            );
+
+        let old_handle = handler.post_execution.reimburse_caller.clone();
+        let fee_payer = sponsor.clone();
+        handler.post_execution.reimburse_caller =
+            Arc::new(move |context: &mut Context<EXT, DB>, gas: &Gas| {
+                match fee_payer(&context.evm.env) {
+                    Some(fee_payer) => {
+                        mainnet::reimburse_account::<SPEC, EXT, DB>(context, gas, fee_payer)
+                    }
+                    None => old_handle(context, gas),
+                }
+            });
+    });
+}
+
+/// Validates the caller and the fee payer of a sponsored transaction against the state.
+pub fn validate_sponsored_tx<SPEC: Spec, EXT, DB: Database>(
+    context: &mut Context<EXT, DB>,
+    fee_payer: Address,
+) -> Result<(), EVMError<DB::Error>> {
+    let tx_caller = context.evm.env.tx.caller;
+    let (caller_account, _) = context
+        .evm
+        .inner
+        .journaled_state
+        .load_account(tx_caller, &mut context.evm.inner.db)?;
+    context
+        .evm
+        .inner
+        .env
+        .validate_sponsored_tx_against_state::<SPEC>(caller_account)
+        .map_err(EVMError::Transaction)?;
+
+    let (fee_payer_account, _) = context
+        .evm
+        .inner
+        .journaled_state
+        .load_account(fee_payer, &mut context.evm.inner.db)?;
+    context
+        .evm
+        .inner
+        .env
+        .validate_fee_payer_against_state::<SPEC>(fee_payer_account)
+        .map_err(EVMError::Transaction)?;
+
+    Ok(())
+}
+
+/// Deducts the gas limit from the fee payer and bumps the nonce of the caller.
+pub fn deduct_fee_payer<SPEC: Spec, EXT, DB: Database>(
+    context: &mut Context<EXT, DB>,
+    fee_payer: Address,
+) -> Result<(), EVMError<DB::Error>> {
+    let (fee_payer_account, _) = context
+        .evm
+        .inner
+        .journaled_state
+        .load_account(fee_payer, &mut context.evm.inner.db)?;
+    mainnet::deduct_fee_inner::<SPEC>(fee_payer_account, &context.evm.inner.env);
+
+    let (caller_account, _) = context
+        .evm
+        .inner
+        .journaled_state
+        .load_account(context.evm.inner.env.tx.caller, &mut context.evm.inner.db)?;
+    // bump the nonce for calls. Nonce for CREATE will be bumped in `handle_create`.
+    if matches!(context.evm.inner.env.tx.transact_to, TransactTo::Call(_)) {
+        caller_account.info.nonce = caller_account.info.nonce.saturating_add(1);
+    }
+    caller_account.mark_touch();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{address, AccountInfo, InvalidTransaction, U256},
+        Evm, InMemoryDB,
+    };
+
+    #[test]
+    fn sponsor_pays_fees() {
+        let caller = address!("1000000000000000000000000000000000000000");
+        let sponsor = address!("2000000000000000000000000000000000000000");
+
+        let transact = |sponsor_balance: u64| {
+            Evm::builder()
+                .with_db(InMemoryDB::default())
+                .modify_db(|db| {
+                    db.insert_account_info(
+                        sponsor,
+                        AccountInfo::from_balance(U256::from(sponsor_balance)),
+                    )
+                })
+                .modify_tx_env(|tx| {
+                    tx.caller = caller;
+                    tx.transact_to = TransactTo::Call(Address::ZERO);
+                    tx.gas_limit = 30_000;
+                    tx.gas_price = U256::from(10);
+                })
+                .append_handler_register_box(fee_payer_handle_register(Arc::new(move |_: &Env| {
+                    Some(sponsor)
+                })))
+                .build()
+                .transact()
+        };
+
+        let state = transact(1_000_000).unwrap().state;
+        assert_eq!(
+            state[&sponsor].info.balance,
+            U256::from(1_000_000 - 10 * 21_000)
+        );
+        assert_eq!(state[&caller].info.balance, U256::ZERO);
+        assert_eq!(state[&caller].info.nonce, 1);
+
+        assert!(matches!(
+            transact(100_000),
+            Err(EVMError::Transaction(
+                InvalidTransaction::LackOfFundForMaxFee { .. }
+            ))
+        ));
+    }
+}
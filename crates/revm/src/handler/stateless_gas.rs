@@ -0,0 +1,128 @@
+//! Experimental stateless gas model of [EIP-4762], for verkle research.
+//!
+//! [EIP-4762]: https://eips.ethereum.org/EIPS/eip-4762
+use super::register::EvmHandler;
+use crate::{
+    interpreter::{
+        opcode, opcode::InstructionTables, InstructionResult, Interpreter, InterpreterAction,
+    },
+    primitives::{db::Database, TransactTo, KECCAK_EMPTY},
+    AccessEvents, Context, Evm,
+};
+use std::{boxed::Box, sync::Arc};
+
+/// Registers handles that charge the witness gas of EIP-4762 on top of the gas of the spec.
+///
+/// The accesses of the journal are recorded in
+/// [JournaledState::access_events](crate::JournaledState::access_events) and charged after every
+/// instruction, together with the code chunks the instruction is read from. Accounts and storage
+/// slots are always warm, the witness gas replaces the cold access costs. The accounts of the
+/// sender and of the target of the transaction are accessed for free.
+///
+/// The model is experimental and differs from the EIP in a few places:
+/// * Accesses made when entering a frame, like the transfer of the call value, are charged to
+///   the next executed instruction.
+/// * Code chunks are attributed to the address of the frame, also for `DELEGATECALL` and
+///   `CALLCODE`, and reading init code is free.
+/// * `CODECOPY` and `EXTCODECOPY` do not access code chunks.
+pub fn stateless_gas_handle_register<'a, EXT: 'a, DB: Database + 'a>(
+    handler: &mut EvmHandler<'a, EXT, DB>,
+) {
+    let old_handle = handler.pre_execution.load_accounts.clone();
+    handler.pre_execution.load_accounts = Arc::new(move |context: &mut Context<EXT, DB>| {
+        context.evm.journaled_state.access_events = Some(AccessEvents::new());
+        old_handle(context)
+    });
+
+    let old_handle = handler.pre_execution.deduct_caller.clone();
+    handler.pre_execution.deduct_caller = Arc::new(move |context: &mut Context<EXT, DB>| {
+        old_handle(context)?;
+        let tx = &context.evm.inner.env.tx;
+        let caller = tx.caller;
+        let target = match tx.transact_to {
+            TransactTo::Call(target) => Some(target),
+            TransactTo::Create(_) => None,
+        };
+        if let Some(events) = &mut context.evm.inner.journaled_state.access_events {
+            events.touch_basic_data(caller, true);
+            events.touch_code_hash(caller, false);
+            if let Some(target) = target {
+                events.touch_basic_data(target, false);
+                events.touch_code_hash(target, false);
+            }
+            events.take_gas();
+        }
+        Ok(())
+    });
+
+    let mut table = handler
+        .take_instruction_table()
+        .expect("Handler must have instruction table");
+    table.convert_boxed();
+    let InstructionTables::Boxed(instructions) = &mut table else {
+        unreachable!("table was converted to boxed variant")
+    };
+    for (op, instruction) in instructions.iter_mut().enumerate() {
+        let op = op as u8;
+        let immediate_size = if (opcode::PUSH1..=opcode::PUSH32).contains(&op) {
+            (op - opcode::PUSH0) as usize
+        } else {
+            0
+        };
+        let old = core::mem::replace(instruction, Box::new(|_, _| ()));
+        *instruction = Box::new(
+            move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                let journal = &mut host.context.evm.journaled_state;
+                if let Some(events) = &mut journal.access_events {
+                    let address = interpreter.contract.address;
+                    let runs_init_code = journal.state.get(&address).map_or(false, |account| {
+                        account.is_created() && account.info.code_hash == KECCAK_EMPTY
+                    });
+                    if !interpreter.is_eof() && !runs_init_code {
+                        // The instruction pointer is already past the opcode.
+                        let pc = interpreter.program_counter() - 1;
+                        events.touch_code(address, pc, pc + 1 + immediate_size, false);
+                    }
+                }
+
+                old(interpreter, host);
+
+                let Some(events) = &mut host.context.evm.journaled_state.access_events else {
+                    return;
+                };
+                if !interpreter.gas.record_cost(events.take_gas()) {
+                    interpreter.instruction_result = InstructionResult::OutOfGas;
+                    interpreter.next_action = InterpreterAction::None;
+                }
+            },
+        );
+    }
+    handler.set_instruction_table(table);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::gas::{WARM_STORAGE_READ_COST, WITNESS_CHUNK_COST},
+        primitives::{Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn charge_witness_gas() {
+        // PUSH1 0x00 SLOAD STOP
+        let code = Bytes::from_static(&[opcode::PUSH1, 0x00, opcode::SLOAD, opcode::STOP]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .append_handler_register(stateless_gas_handle_register)
+            .build();
+
+        let gas_used = evm.transact().unwrap().result.gas_used();
+        // The header branch of the target is accessed by the transaction, the first code chunk
+        // and the slot are new leaves of it. The slot is warm.
+        assert_eq!(
+            gas_used,
+            21_000 + 3 + WARM_STORAGE_READ_COST + 2 * WITNESS_CHUNK_COST
+        );
+    }
+}
@@ -1,17 +1,32 @@
 use crate::{
     interpreter::{CallInputs, CreateInputs, Interpreter},
-    primitives::{db::Database, Address, Log, U256},
+    primitives::{db::Database, Address, Bytecode, Log, B256, U256},
     EvmContext,
 };
 use auto_impl::auto_impl;
 
+mod call_context;
 #[cfg(feature = "std")]
 mod customprinter;
 #[cfg(all(feature = "std", feature = "serde-json"))]
 mod eip3155;
+mod erc4337;
 mod gas;
+mod gas_series;
 mod handler_register;
 mod noop;
+mod parity;
+mod policy;
+#[cfg(feature = "std")]
+mod profiler;
+mod reentrancy;
+mod storage_layout;
+mod trace_filter;
+mod trace_format;
+mod tracer;
+mod transfer;
+#[cfg(feature = "wasm-tracer")]
+mod wasm;
 
 // Exports.
 
@@ -20,12 +35,108 @@ use revm_interpreter::{CallOutcome, CreateOutcome};
 
 /// [Inspector] implementations.
 pub mod inspectors {
+    pub use super::call_context::{CallContextInspector, ContextViolation, FrameContext};
     #[cfg(feature = "std")]
     pub use super::customprinter::CustomPrintTracer;
     #[cfg(all(feature = "std", feature = "serde-json"))]
-    pub use super::eip3155::TracerEip3155;
+    pub use super::eip3155::{Eip3155Step, Eip3155Summary, Eip3155Writer, TracerEip3155};
+    pub use super::erc4337::{
+        simulate_validation, Erc4337Entity, Erc4337Inspector, Erc4337Violation,
+        UserOperationEntities, ValidationSimulation, ERC4337_BANNED_OPCODES,
+    };
     pub use super::gas::GasInspector;
+    pub use super::gas_series::{GasSample, GasSeriesInspector};
     pub use super::noop::NoOpInspector;
+    pub use super::parity::{
+        state_diff, AccountDiff, Action, CallAction, CallOutput, CallType, CreateAction,
+        CreateOutput, Delta, MemoryDelta, ParityTracer, SelfdestructAction, StorageDelta,
+        TraceOutput, TransactionTrace, VmExecutedOperation, VmInstruction, VmTrace,
+    };
+    pub use super::policy::{
+        ExecutionPolicy, PolicyInspector, PolicyViolation, PolicyViolationKind, StorageRange,
+    };
+    #[cfg(feature = "std")]
+    pub use super::profiler::{ProfileEntry, ProfilerInspector, DEFAULT_PROFILER_BATCH};
+    pub use super::reentrancy::{Reentrancy, ReentrancyInspector};
+    pub use super::storage_layout::{StorageLayout, StorageVariable};
+    pub use super::trace_filter::{ParseTraceFilterError, TraceFilter};
+    pub use super::trace_format::TraceFormatter;
+    pub use super::tracer::{
+        FrameInput, FrameKind, FrameResult, Step, Tracer, TracerContext, TracerInspector,
+    };
+    pub use super::transfer::{BalanceTransfer, TransferInspector, TransferKind};
+    #[cfg(feature = "wasm-tracer")]
+    pub use super::wasm::{WasmTracer, WasmTracerError, DEFAULT_FUEL_PER_HOOK};
+}
+
+/// Storage slot read by `SLOAD` or written by `SSTORE`, passed to [Inspector::sload] and
+/// [Inspector::sstore].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageAccess {
+    /// Address of the account owning the storage.
+    pub address: Address,
+    /// Storage slot.
+    pub key: U256,
+    /// Value of the slot before the instruction.
+    pub old_value: U256,
+    /// Value of the slot after the instruction, equal to `old_value` for `SLOAD`.
+    pub new_value: U256,
+    /// Value of the slot at the start of the transaction.
+    pub original_value: U256,
+    /// Whether the slot was cold and the instruction paid for the cold access.
+    pub is_cold: bool,
+    /// Gas charged by the instruction.
+    pub gas_cost: u64,
+    /// Refund added by the instruction, negative if it removed refund.
+    pub gas_refund: i64,
+}
+
+/// Position of an emitted log, passed to [Inspector::log].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogPosition {
+    /// Index of the log among the logs of the transaction.
+    ///
+    /// Logs of reverted frames are discarded, so this is the index of the log in the receipt if
+    /// its frame and all of its callers succeed.
+    pub index: usize,
+    /// Call depth of the emitting frame, starting at 1.
+    pub depth: usize,
+    /// Program counter of the `LOG` instruction.
+    pub pc: usize,
+}
+
+/// Contract deployed by a successful create, passed to [Inspector::contract_created].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractCreation {
+    /// Address of the created contract.
+    pub address: Address,
+    /// Deployed code of the contract.
+    pub code: Bytecode,
+    /// Gas spent by the create frame, including the code deposit.
+    pub gas_used: u64,
+    /// Size of the deployed code, in bytes.
+    pub code_size: usize,
+    /// Gas charged for depositing the deployed code, included in [Self::gas_used].
+    pub code_deposit_gas: u64,
+    /// Hash of the init code.
+    pub init_code_hash: B256,
+    /// Salt of `CREATE2` and `EOFCREATE`, `None` for `CREATE`.
+    pub salt: Option<U256>,
+}
+
+/// Effect of a successful `SELFDESTRUCT`, passed to [Inspector::contract_selfdestructed].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContractSelfDestruct {
+    /// Address of the self-destructed contract.
+    pub address: Address,
+    /// Beneficiary of the balance.
+    pub target: Address,
+    /// Balance swept from the contract, burnt if the contract is its own target.
+    pub value: U256,
+    /// Whether the account is removed at the end of the transaction. Since Cancun (EIP-6780)
+    /// only contracts created in the same transaction are removed, others only lose their
+    /// balance.
+    pub destroyed: bool,
 }
 
 /// EVM [Interpreter] callbacks.
@@ -65,11 +176,26 @@ pub trait Inspector<DB: Database> {
         let _ = context;
     }
 
-    /// Called when a log is emitted.
+    /// Called when a log is emitted, with its position in the transaction.
     #[inline]
-    fn log(&mut self, context: &mut EvmContext<DB>, log: &Log) {
+    fn log(&mut self, context: &mut EvmContext<DB>, log: &Log, position: &LogPosition) {
         let _ = context;
         let _ = log;
+        let _ = position;
+    }
+
+    /// Called after a successful `SLOAD`, [StorageAccess::new_value] is the loaded value.
+    #[inline]
+    fn sload(&mut self, context: &mut EvmContext<DB>, access: &StorageAccess) {
+        let _ = context;
+        let _ = access;
+    }
+
+    /// Called after a successful `SSTORE`.
+    #[inline]
+    fn sstore(&mut self, context: &mut EvmContext<DB>, access: &StorageAccess) {
+        let _ = context;
+        let _ = access;
     }
 
     /// Called whenever a call to a contract is about to start.
@@ -135,6 +261,25 @@ pub trait Inspector<DB: Database> {
         outcome
     }
 
+    /// Called when a create has deployed a contract, before [Inspector::create_end].
+    #[inline]
+    fn contract_created(&mut self, context: &mut EvmContext<DB>, created: &ContractCreation) {
+        let _ = context;
+        let _ = created;
+    }
+
+    /// Called after every successful `SELFDESTRUCT`, including the ones that only transfer the
+    /// balance since Cancun.
+    #[inline]
+    fn contract_selfdestructed(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        selfdestruct: &ContractSelfDestruct,
+    ) {
+        let _ = context;
+        let _ = selfdestruct;
+    }
+
     /// Called when a contract has been self-destructed with funds transferred to target.
     #[inline]
     fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
@@ -0,0 +1,347 @@
+//! Validation of the execution context of call frames.
+//!
+//! The context of a frame, `msg.sender`, `msg.value` and `address(this)`, follows from the
+//! context of its parent and the call scheme. `DELEGATECALL` keeps the sender and value of the
+//! parent and `CALLCODE` runs the code in the parent account with the parent as sender, which
+//! custom handlers and instructions easily get wrong. [FrameContext::check_call] derives the
+//! expected context of a call and compares it with its inputs, [CallContextInspector] does so
+//! for every frame of a transaction.
+
+use super::tracer::FrameKind;
+use crate::{
+    interpreter::{CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, Transfer},
+    primitives::{db::Database, Address, TxEnv, U256},
+    EvmContext, Inspector,
+};
+use core::fmt;
+use std::vec::Vec;
+
+/// Effective execution context of a frame.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FrameContext {
+    /// Kind of the frame.
+    pub kind: FrameKind,
+    /// `address(this)`, the account whose storage and balance are used.
+    pub address: Address,
+    /// `msg.sender`.
+    pub caller: Address,
+    /// Account whose code is executed, `None` for creations.
+    pub code_address: Option<Address>,
+    /// `msg.value`.
+    pub value: U256,
+    /// Whether the frame is static.
+    pub is_static: bool,
+}
+
+impl FrameContext {
+    /// Returns the context of the sender of `tx`, the parent of the first frame of the
+    /// transaction.
+    pub fn sender(tx: &TxEnv) -> Self {
+        Self {
+            kind: FrameKind::Call(CallScheme::Call),
+            address: tx.caller,
+            caller: tx.caller,
+            code_address: None,
+            value: tx.value,
+            is_static: false,
+        }
+    }
+
+    /// Returns the context of a call as given by its inputs.
+    pub fn of_call(inputs: &CallInputs) -> Self {
+        Self {
+            kind: FrameKind::Call(inputs.context.scheme),
+            address: inputs.context.address,
+            caller: inputs.context.caller,
+            code_address: Some(inputs.context.code_address),
+            value: inputs.context.apparent_value,
+            is_static: inputs.is_static,
+        }
+    }
+
+    /// Returns the context of a creation, `nonce` is the nonce of the caller before the
+    /// creation.
+    pub fn of_create(inputs: &CreateInputs, nonce: u64) -> Self {
+        Self {
+            kind: FrameKind::Create(inputs.scheme),
+            address: inputs.created_address(nonce),
+            caller: inputs.caller,
+            code_address: None,
+            value: inputs.value,
+            is_static: false,
+        }
+    }
+
+    /// Checks the inputs of a call made by the frame with this context against the context
+    /// implied by the call scheme.
+    pub fn check_call(&self, inputs: &CallInputs) -> Result<(), ContextViolation> {
+        let target = inputs.contract;
+        let transfer = |target, value| Transfer {
+            source: self.address,
+            target,
+            value,
+        };
+        let value = inputs.transfer.value;
+        let (address, caller, value, expected) = match inputs.context.scheme {
+            CallScheme::Call => (target, self.address, value, transfer(target, value)),
+            CallScheme::CallCode => (
+                self.address,
+                self.address,
+                value,
+                transfer(self.address, value),
+            ),
+            CallScheme::DelegateCall => (
+                self.address,
+                self.caller,
+                self.value,
+                transfer(self.address, U256::ZERO),
+            ),
+            CallScheme::StaticCall => (
+                target,
+                self.address,
+                U256::ZERO,
+                transfer(self.address, U256::ZERO),
+            ),
+        };
+        let context = &inputs.context;
+        if context.address != address {
+            Err(ContextViolation::Address {
+                expected: address,
+                got: context.address,
+            })
+        } else if context.caller != caller {
+            Err(ContextViolation::Caller {
+                expected: caller,
+                got: context.caller,
+            })
+        } else if context.code_address != target {
+            Err(ContextViolation::CodeAddress {
+                expected: target,
+                got: context.code_address,
+            })
+        } else if context.apparent_value != value {
+            Err(ContextViolation::Value {
+                expected: value,
+                got: context.apparent_value,
+            })
+        } else if inputs.transfer != expected {
+            Err(ContextViolation::Transfer {
+                expected,
+                got: inputs.transfer.clone(),
+            })
+        } else if inputs.is_static != (self.is_static || context.scheme == CallScheme::StaticCall) {
+            Err(ContextViolation::Static {
+                got: inputs.is_static,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the inputs of a creation made by the frame with this context.
+    pub fn check_create(&self, inputs: &CreateInputs) -> Result<(), ContextViolation> {
+        if inputs.caller != self.address {
+            Err(ContextViolation::Caller {
+                expected: self.address,
+                got: inputs.caller,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Field of the inputs of a frame that differs from the context implied by its parent.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ContextViolation {
+    /// `address(this)` differs.
+    Address {
+        /// Expected address.
+        expected: Address,
+        /// Address of the inputs.
+        got: Address,
+    },
+    /// `msg.sender` differs.
+    Caller {
+        /// Expected sender.
+        expected: Address,
+        /// Sender of the inputs.
+        got: Address,
+    },
+    /// The account whose code is executed differs from the called account.
+    CodeAddress {
+        /// Expected code address.
+        expected: Address,
+        /// Code address of the inputs.
+        got: Address,
+    },
+    /// `msg.value` differs.
+    Value {
+        /// Expected value.
+        expected: U256,
+        /// Value of the inputs.
+        got: U256,
+    },
+    /// The value transfer differs.
+    Transfer {
+        /// Expected transfer.
+        expected: Transfer,
+        /// Transfer of the inputs.
+        got: Transfer,
+    },
+    /// The frame is static although its parent is not and it is not a `STATICCALL`, or the
+    /// other way around.
+    Static {
+        /// Whether the inputs are static.
+        got: bool,
+    },
+}
+
+impl fmt::Display for ContextViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address { expected, got } => {
+                write!(f, "address {got}, expected {expected}")
+            }
+            Self::Caller { expected, got } => write!(f, "caller {got}, expected {expected}"),
+            Self::CodeAddress { expected, got } => {
+                write!(f, "code address {got}, expected {expected}")
+            }
+            Self::Value { expected, got } => write!(f, "value {got}, expected {expected}"),
+            Self::Transfer { expected, got } => {
+                write!(f, "transfer {got:?}, expected {expected:?}")
+            }
+            Self::Static { got } => write!(f, "static {got}, expected {}", !got),
+        }
+    }
+}
+
+/// Inspector checking the context of every frame against its parent, see the
+/// [module documentation](self).
+///
+/// Frames are checked against the context given by their inputs, so a violation is reported
+/// once, on the frame that introduced it.
+#[derive(Clone, Debug, Default)]
+pub struct CallContextInspector {
+    frames: Vec<FrameContext>,
+    violations: Vec<(usize, ContextViolation)>,
+}
+
+impl CallContextInspector {
+    /// Returns the contexts of the frames executing, the current frame last.
+    pub fn frames(&self) -> &[FrameContext] {
+        &self.frames
+    }
+
+    /// Returns the context of the current frame.
+    pub fn current(&self) -> Option<&FrameContext> {
+        self.frames.last()
+    }
+
+    /// Returns the violations found, with the depth of the offending frame.
+    pub fn violations(&self) -> &[(usize, ContextViolation)] {
+        &self.violations
+    }
+
+    fn parent<DB: Database>(&self, context: &EvmContext<DB>) -> FrameContext {
+        self.frames
+            .last()
+            .cloned()
+            .unwrap_or_else(|| FrameContext::sender(&context.env.tx))
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallContextInspector {
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        if let Err(violation) = self.parent(context).check_call(inputs) {
+            self.violations.push((self.frames.len(), violation));
+        }
+        self.frames.push(FrameContext::of_call(inputs));
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.frames.pop();
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        if let Err(violation) = self.parent(context).check_create(inputs) {
+            self.violations.push((self.frames.len(), violation));
+        }
+        let nonce = context
+            .journaled_state
+            .state
+            .get(&inputs.caller)
+            .map_or(0, |account| account.info.nonce);
+        self.frames.push(FrameContext::of_create(inputs, nonce));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.frames.pop();
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interpreter::CallContext, test_utils::create_mock_call_inputs};
+
+    #[test]
+    fn delegatecall_context() {
+        let sender = Address::with_last_byte(1);
+        let parent = FrameContext {
+            address: Address::with_last_byte(2),
+            value: U256::from(5),
+            ..FrameContext::sender(&TxEnv {
+                caller: sender,
+                ..Default::default()
+            })
+        };
+
+        let mut inputs = create_mock_call_inputs(Address::with_last_byte(3));
+        inputs.context = CallContext {
+            address: parent.address,
+            caller: sender,
+            code_address: inputs.contract,
+            apparent_value: parent.value,
+            scheme: CallScheme::DelegateCall,
+        };
+        inputs.transfer = Transfer {
+            source: parent.address,
+            target: parent.address,
+            value: U256::ZERO,
+        };
+        assert_eq!(parent.check_call(&inputs), Ok(()));
+
+        // A delegate call made with the sender of a regular call.
+        inputs.context.caller = parent.address;
+        assert_eq!(
+            parent.check_call(&inputs),
+            Err(ContextViolation::Caller {
+                expected: sender,
+                got: parent.address,
+            })
+        );
+    }
+}
@@ -75,12 +75,17 @@ impl<DB: Database> Inspector<DB> for CustomPrintTracer {
 
     fn call(
         &mut self,
-        _context: &mut EvmContext<DB>,
+        context: &mut EvmContext<DB>,
         inputs: &mut CallInputs,
     ) -> Option<CallOutcome> {
+        let label = context
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(&inputs.contract));
         println!(
-            "SM CALL:   {:?}, context:{:?}, is_static:{:?}, transfer:{:?}, input_size:{:?}",
+            "SM CALL:   {:?}, label:{:?}, context:{:?}, is_static:{:?}, transfer:{:?}, input_size:{:?}",
             inputs.contract,
+            label,
             inputs.context,
             inputs.is_static,
             inputs.transfer,
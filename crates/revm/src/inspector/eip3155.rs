@@ -1,18 +1,19 @@
 use crate::{
     inspectors::GasInspector,
     interpreter::{
-        opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter,
-        InterpreterResult,
+        opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, InstructionResult,
+        Interpreter, InterpreterResult,
     },
-    primitives::{db::Database, hex, HashMap, B256, U256},
+    primitives::{db::Database, B256, U256},
     EvmContext, Inspector,
 };
-use serde::Serialize;
 use std::io::Write;
 
 /// [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155) tracer [Inspector].
+///
+/// Lines are written with an [Eip3155Writer] and the output is flushed after each line.
 pub struct TracerEip3155 {
-    output: Box<dyn Write>,
+    output: Eip3155Writer<Box<dyn Write>>,
     gas_inspector: GasInspector,
 
     /// Print summary of the execution.
@@ -26,79 +27,148 @@ pub struct TracerEip3155 {
     mem_size: usize,
     skip: bool,
     include_memory: bool,
-    memory: Option<String>,
+    memory: Vec<u8>,
 }
 
-// # Output
-// The CUT MUST output a `json` object for EACH operation.
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Output {
-    // Required fields:
-    /// Program counter
-    pc: u64,
-    /// OpCode
-    op: u8,
-    /// Gas left before executing this operation
-    gas: String,
-    /// Gas cost of this operation
-    gas_cost: String,
-    /// Array of all values on the stack
-    stack: Vec<String>,
-    /// Depth of the call stack
-    depth: u64,
-    /// Data returned by the function call
-    return_data: String,
-    /// Amount of **global** gas refunded
-    refund: String,
-    /// Size of memory array
-    mem_size: String,
-
-    // Optional fields:
-    /// Name of the operation
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    op_name: Option<&'static str>,
-    /// Description of an error (should contain revert reason if supported)
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
-    /// Array of all allocated values
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    memory: Option<String>,
-    /// Array of all stored values
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    storage: Option<HashMap<String, String>>,
-    /// Array of values, Stack of the called function
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    return_stack: Option<Vec<String>>,
+/// Operation of an [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155) trace, borrowing the
+/// stack and memory of the interpreter.
+#[derive(Clone, Copy, Debug)]
+pub struct Eip3155Step<'a> {
+    /// Program counter.
+    pub pc: u64,
+    /// Opcode.
+    pub op: u8,
+    /// Gas left before executing the operation.
+    pub gas: u64,
+    /// Gas cost of the operation.
+    pub gas_cost: u64,
+    /// Stack before executing the operation, bottom first.
+    pub stack: &'a [U256],
+    /// Depth of the call stack.
+    pub depth: u64,
+    /// Amount of **global** gas refunded.
+    pub refund: u64,
+    /// Size of the memory.
+    pub mem_size: usize,
+    /// Name of the operation.
+    pub op_name: Option<&'static str>,
+    /// Error of the operation.
+    pub error: Option<InstructionResult>,
+    /// Memory before executing the operation, written if set.
+    pub memory: Option<&'a [u8]>,
 }
 
-// # Summary and error handling
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Summary {
-    // Required fields:
-    /// Root of the state trie after executing the transaction
-    state_root: String,
-    /// Return values of the function
-    output: String,
-    /// All gas used by the transaction
-    gas_used: String,
-    /// Bool whether transaction was executed successfully
-    pass: bool,
-
-    // Optional fields:
-    /// Time in nanoseconds needed to execute the transaction
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    time: Option<u128>,
-    /// Name of the fork rules used for execution
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    fork: Option<String>,
+/// Summary ending an [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155) trace.
+#[derive(Clone, Copy, Debug)]
+pub struct Eip3155Summary<'a> {
+    /// Root of the state trie after executing the transaction.
+    pub state_root: B256,
+    /// Return data of the transaction.
+    pub output: &'a [u8],
+    /// Gas used by the transaction.
+    pub gas_used: u64,
+    /// Whether the transaction was executed successfully.
+    pub pass: bool,
+    /// Time in nanoseconds needed to execute the transaction.
+    pub time: Option<u128>,
+    /// Name of the fork rules used for execution.
+    pub fork: Option<&'a str>,
+}
+
+/// Streaming writer of [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155) traces, one JSON
+/// object per line.
+///
+/// Operations are serialized field by field straight into the writer, nothing is collected
+/// and no string is allocated per operation, so traces of any length are written with bounded
+/// memory. The writer is not flushed between lines, wrap unbuffered writers in a
+/// [BufWriter](std::io::BufWriter).
+#[derive(Debug)]
+pub struct Eip3155Writer<W> {
+    writer: W,
+}
+
+impl<W: Write> Eip3155Writer<W> {
+    /// Creates a writer of traces to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying writer, consuming this one.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Writes the line of an operation.
+    pub fn write_step(&mut self, step: &Eip3155Step<'_>) -> std::io::Result<()> {
+        let w = &mut self.writer;
+        write!(
+            w,
+            r#"{{"pc":{},"op":{},"gas":"{:#x}","gasCost":"{:#x}","stack":["#,
+            step.pc, step.op, step.gas, step.gas_cost
+        )?;
+        for (i, value) in step.stack.iter().enumerate() {
+            if i != 0 {
+                w.write_all(b",")?;
+            }
+            w.write_all(b"\"")?;
+            write_u256(w, value)?;
+            w.write_all(b"\"")?;
+        }
+        write!(
+            w,
+            r#"],"depth":{},"returnData":"0x","refund":"{:#x}","memSize":"{}""#,
+            step.depth, step.refund, step.mem_size
+        )?;
+        if let Some(op_name) = step.op_name {
+            w.write_all(br#","opName":"#)?;
+            write_str(w, op_name)?;
+        }
+        if let Some(error) = step.error {
+            write!(w, r#","error":"{error:?}""#)?;
+        }
+        if let Some(memory) = step.memory {
+            w.write_all(br#","memory":""#)?;
+            write_hex(w, memory)?;
+            w.write_all(b"\"")?;
+        }
+        w.write_all(b"}\n")
+    }
+
+    /// Writes the summary line of a transaction.
+    pub fn write_summary(&mut self, summary: &Eip3155Summary<'_>) -> std::io::Result<()> {
+        let w = &mut self.writer;
+        write!(w, r#"{{"stateRoot":"{}","output":""#, summary.state_root)?;
+        write_hex(w, summary.output)?;
+        write!(
+            w,
+            r#"","gasUsed":"{:#x}","pass":{}"#,
+            summary.gas_used, summary.pass
+        )?;
+        if let Some(time) = summary.time {
+            write!(w, r#","time":{time}"#)?;
+        }
+        if let Some(fork) = summary.fork {
+            w.write_all(br#","fork":"#)?;
+            write_str(w, fork)?;
+        }
+        w.write_all(b"}\n")
+    }
 }
 
 impl TracerEip3155 {
     /// Sets the writer to use for the output.
     pub fn set_writer(&mut self, writer: Box<dyn Write>) {
-        self.output = writer;
+        self.output = Eip3155Writer::new(writer);
     }
 
     /// Resets the Tracer to its initial state of [Self::new].
@@ -129,7 +199,7 @@ impl TracerEip3155 {
 impl TracerEip3155 {
     pub fn new(output: Box<dyn Write>) -> Self {
         Self {
-            output,
+            output: Eip3155Writer::new(output),
             gas_inspector: GasInspector::default(),
             print_summary: true,
             include_memory: false,
@@ -156,12 +226,6 @@ impl TracerEip3155 {
         self
     }
 
-    fn write_value(&mut self, value: &impl serde::Serialize) -> std::io::Result<()> {
-        serde_json::to_writer(&mut *self.output, value)?;
-        self.output.write_all(b"\n")?;
-        self.output.flush()
-    }
-
     fn print_summary<DB: Database>(
         &mut self,
         result: &InterpreterResult,
@@ -169,18 +233,18 @@ impl TracerEip3155 {
     ) {
         if self.print_summary {
             let spec_name: &str = context.spec_id().into();
-            let value = Summary {
-                state_root: B256::ZERO.to_string(),
-                output: result.output.to_string(),
-                gas_used: hex_number(
-                    context.inner.env().tx.gas_limit - self.gas_inspector.gas_remaining(),
-                ),
+            let summary = Eip3155Summary {
+                state_root: B256::ZERO,
+                output: &result.output,
+                gas_used: context.inner.env().tx.gas_limit - self.gas_inspector.gas_remaining(),
                 pass: result.is_ok(),
-
                 time: None,
-                fork: Some(spec_name.to_string()),
+                fork: Some(spec_name),
             };
-            let _ = self.write_value(&value);
+            let _ = self
+                .output
+                .write_summary(&summary)
+                .and_then(|()| self.output.flush());
         }
     }
 }
@@ -192,12 +256,12 @@ impl<DB: Database> Inspector<DB> for TracerEip3155 {
 
     fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
         self.gas_inspector.step(interp, context);
-        self.stack = interp.stack.data().clone();
-        self.memory = if self.include_memory {
-            Some(hex::encode_prefixed(interp.shared_memory.context_memory()))
-        } else {
-            None
-        };
+        self.stack.clone_from(interp.stack.data());
+        if self.include_memory {
+            self.memory.clear();
+            self.memory
+                .extend_from_slice(interp.shared_memory.context_memory());
+        }
         self.pc = interp.program_counter();
         self.opcode = interp.current_opcode();
         self.mem_size = interp.shared_memory.len();
@@ -212,28 +276,23 @@ impl<DB: Database> Inspector<DB> for TracerEip3155 {
             return;
         }
 
-        let value = Output {
+        let step = Eip3155Step {
             pc: self.pc as u64,
             op: self.opcode,
-            gas: hex_number(self.gas),
-            gas_cost: hex_number(self.gas_inspector.last_gas_cost()),
-            stack: self.stack.iter().map(hex_number_u256).collect(),
+            gas: self.gas,
+            gas_cost: self.gas_inspector.last_gas_cost(),
+            stack: &self.stack,
             depth: context.journaled_state.depth(),
-            return_data: "0x".to_string(),
-            refund: hex_number(self.refunded as u64),
-            mem_size: self.mem_size.to_string(),
-
+            refund: self.refunded as u64,
+            mem_size: self.mem_size,
             op_name: opcode::OPCODE_JUMPMAP[self.opcode as usize],
-            error: if !interp.instruction_result.is_ok() {
-                Some(format!("{:?}", interp.instruction_result))
-            } else {
-                None
-            },
-            memory: self.memory.take(),
-            storage: None,
-            return_stack: None,
+            error: (!interp.instruction_result.is_ok()).then_some(interp.instruction_result),
+            memory: self.include_memory.then_some(self.memory.as_slice()),
         };
-        let _ = self.write_value(&value);
+        let _ = self
+            .output
+            .write_step(&step)
+            .and_then(|()| self.output.flush());
     }
 
     fn call_end(
@@ -272,16 +331,89 @@ impl<DB: Database> Inspector<DB> for TracerEip3155 {
     }
 }
 
-fn hex_number(uint: u64) -> String {
-    format!("0x{uint:x}")
+/// Writes `value` as a hex number without leading zeros.
+fn write_u256(w: &mut impl Write, value: &U256) -> std::io::Result<()> {
+    let limbs = value.as_limbs();
+    let Some(top) = limbs.iter().rposition(|limb| *limb != 0) else {
+        return w.write_all(b"0x0");
+    };
+    write!(w, "{:#x}", limbs[top])?;
+    for limb in limbs[..top].iter().rev() {
+        write!(w, "{limb:016x}")?;
+    }
+    Ok(())
 }
 
-fn hex_number_u256(b: &U256) -> String {
-    let s = hex::encode(b.to_be_bytes::<32>());
-    let s = s.trim_start_matches('0');
-    if s.is_empty() {
-        "0x0".to_string()
-    } else {
-        format!("0x{s}")
+/// Writes `bytes` as a `0x` prefixed hex string, without allocating.
+fn write_hex(w: &mut impl Write, bytes: &[u8]) -> std::io::Result<()> {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut buffer = [0u8; 256];
+    w.write_all(b"0x")?;
+    for chunk in bytes.chunks(buffer.len() / 2) {
+        for (i, byte) in chunk.iter().enumerate() {
+            buffer[2 * i] = DIGITS[(byte >> 4) as usize];
+            buffer[2 * i + 1] = DIGITS[(byte & 0xf) as usize];
+        }
+        w.write_all(&buffer[..2 * chunk.len()])?;
+    }
+    Ok(())
+}
+
+/// Writes `value` as an escaped JSON string.
+fn write_str(w: &mut impl Write, value: &str) -> std::io::Result<()> {
+    serde_json::to_writer(w, value).map_err(std::io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_step_and_summary() {
+        let mut writer = Eip3155Writer::new(Vec::new());
+        let stack = [U256::ZERO, U256::from(0x1234), U256::from(1) << 64];
+        writer
+            .write_step(&Eip3155Step {
+                pc: 2,
+                op: opcode::ADD,
+                gas: 0x10,
+                gas_cost: 3,
+                stack: &stack,
+                depth: 1,
+                refund: 0,
+                mem_size: 32,
+                op_name: Some("ADD"),
+                error: Some(InstructionResult::OutOfGas),
+                memory: Some(&[0x00, 0xab]),
+            })
+            .unwrap();
+        writer
+            .write_summary(&Eip3155Summary {
+                state_root: B256::ZERO,
+                output: &[0xff],
+                gas_used: 21_000,
+                pass: false,
+                time: None,
+                fork: Some("CANCUN"),
+            })
+            .unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            r#"{"pc":2,"op":1,"gas":"0x10","gasCost":"0x3","stack":["0x0","0x1234","0x10000000000000000"],"depth":1,"returnData":"0x","refund":"0x0","memSize":"32","opName":"ADD","error":"OutOfGas","memory":"0x00ab"}"#
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            format!(
+                r#"{{"stateRoot":"{}","output":"0xff","gasUsed":"0x5208","pass":false,"fork":"CANCUN"}}"#,
+                B256::ZERO
+            )
+        );
+        // Every line is valid JSON.
+        for line in output.lines() {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
     }
 }
@@ -0,0 +1,431 @@
+//! Simulation of the validation of ERC-4337 user operations.
+//!
+//! Bundlers simulate the validation of a user operation before including it and reject it if
+//! the validation depends on state other than the one of its sender (ERC-7562). The
+//! [Erc4337Inspector] attributes every frame to the entity of the user operation it runs for
+//! and records the violations of the opcode and storage access rules of unstaked entities.
+//! [simulate_validation] runs a transaction with it, usually a call to `simulateValidation` of
+//! the entry point.
+use super::policy::{ExecutionPolicy, PolicyViolationKind};
+use crate::{
+    db::Database,
+    inspector::inspector_handle_register,
+    interpreter::{opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter},
+    primitives::{Address, CreateScheme, EVMError, Env, ExecutionResult, HashSet, SpecId, U256},
+    Evm, EvmContext, Inspector,
+};
+use std::{boxed::Box, vec::Vec};
+
+/// Opcodes whose result depends on the block or on other accounts, banned in validation.
+///
+/// `GAS` is allowed right before a call.
+pub const ERC4337_BANNED_OPCODES: [u8; 16] = [
+    opcode::GASPRICE,
+    opcode::GASLIMIT,
+    opcode::DIFFICULTY,
+    opcode::TIMESTAMP,
+    opcode::BASEFEE,
+    opcode::BLOCKHASH,
+    opcode::NUMBER,
+    opcode::SELFBALANCE,
+    opcode::BALANCE,
+    opcode::ORIGIN,
+    opcode::GAS,
+    opcode::CREATE,
+    opcode::COINBASE,
+    opcode::SELFDESTRUCT,
+    opcode::BLOBHASH,
+    opcode::BLOBBASEFEE,
+];
+
+/// Number of slots following a slot derived from the sender that are associated with it.
+const ASSOCIATED_SLOTS: u64 = 128;
+
+/// Accounts taking part in the validation of a user operation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UserOperationEntities {
+    /// Entry point contract running the validation.
+    pub entry_point: Address,
+    /// Account sending the user operation.
+    pub sender: Address,
+    /// Factory deploying the sender, if it is not deployed yet.
+    pub factory: Option<Address>,
+    /// Paymaster paying for the user operation, if any.
+    pub paymaster: Option<Address>,
+}
+
+impl UserOperationEntities {
+    /// Returns the opcode and call rules of the validation as an [ExecutionPolicy].
+    ///
+    /// Storage rules depend on the keys computed during validation and are checked by the
+    /// [Erc4337Inspector] itself.
+    pub fn validation_policy(&self) -> ExecutionPolicy {
+        ExecutionPolicy {
+            forbidden_opcodes: ERC4337_BANNED_OPCODES.to_vec(),
+            allowed_before_call: vec![opcode::GAS],
+            ..Default::default()
+        }
+        .allow_value_recipient(self.entry_point)
+    }
+
+    /// Returns the entity of the given account, if it is one.
+    pub fn entity_of(&self, address: Address) -> Option<Erc4337Entity> {
+        if address == self.sender {
+            Some(Erc4337Entity::Sender)
+        } else if Some(address) == self.factory {
+            Some(Erc4337Entity::Factory)
+        } else if Some(address) == self.paymaster {
+            Some(Erc4337Entity::Paymaster)
+        } else {
+            None
+        }
+    }
+}
+
+/// Entity of a user operation a frame runs for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Erc4337Entity {
+    /// Deployment of the sender.
+    Factory,
+    /// Validation of the user operation by the sender.
+    Sender,
+    /// Validation of the user operation by the paymaster.
+    Paymaster,
+}
+
+/// Violation of a validation rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Erc4337Violation {
+    /// Use of a banned opcode, see [ERC4337_BANNED_OPCODES].
+    BannedOpcode {
+        /// Entity the frame runs for.
+        entity: Erc4337Entity,
+        /// Contract executing the opcode.
+        contract: Address,
+        /// The opcode.
+        opcode: u8,
+    },
+    /// Access to a storage slot that is not associated with the sender.
+    UnassociatedStorage {
+        /// Entity the frame runs for.
+        entity: Erc4337Entity,
+        /// Contract whose storage is accessed.
+        contract: Address,
+        /// The slot.
+        slot: U256,
+    },
+    /// Call transferring value to another account than the entry point.
+    CallWithValue {
+        /// Entity the frame runs for.
+        entity: Erc4337Entity,
+        /// Called account.
+        target: Address,
+    },
+    /// `CREATE2` outside of the deployment of the sender, or more than once.
+    Create2 {
+        /// Entity the frame runs for.
+        entity: Erc4337Entity,
+    },
+}
+
+/// [Inspector] that records the violations of the ERC-4337 validation rules, see the
+/// [module documentation](self).
+///
+/// A frame runs for an entity if the entity is its target or if its parent frame runs for it.
+/// Frames of the entry point itself are not checked.
+#[derive(Clone, Debug)]
+pub struct Erc4337Inspector {
+    entities: UserOperationEntities,
+    policy: ExecutionPolicy,
+    violations: Vec<Erc4337Violation>,
+    /// Entity of each of the currently executing frames.
+    frames: Vec<Option<Erc4337Entity>>,
+    /// Results of `KECCAK256` over data starting with the sender.
+    sender_keys: HashSet<U256>,
+    /// Whether the executing instruction hashes data starting with the sender.
+    hashing_sender: bool,
+    create2_count: usize,
+}
+
+impl Erc4337Inspector {
+    /// Creates an inspector validating the user operation of the given entities.
+    pub fn new(entities: UserOperationEntities) -> Self {
+        Self {
+            entities,
+            policy: entities.validation_policy(),
+            violations: Vec::new(),
+            frames: Vec::new(),
+            sender_keys: HashSet::default(),
+            hashing_sender: false,
+            create2_count: 0,
+        }
+    }
+
+    /// Returns the entities of the user operation.
+    pub fn entities(&self) -> &UserOperationEntities {
+        &self.entities
+    }
+
+    /// Returns the recorded violations in execution order.
+    pub fn violations(&self) -> &[Erc4337Violation] {
+        &self.violations
+    }
+
+    /// Consumes the inspector and returns the recorded violations.
+    pub fn into_violations(self) -> Vec<Erc4337Violation> {
+        self.violations
+    }
+
+    /// Returns `true` if the slot is associated with the sender: the sender address itself or
+    /// one of the slots following a hash of data starting with the sender, as used by mappings.
+    pub fn is_associated_slot(&self, slot: U256) -> bool {
+        slot == U256::from_be_bytes(self.entities.sender.into_word().0)
+            || self.sender_keys.iter().any(|key| {
+                slot.checked_sub(*key)
+                    .is_some_and(|offset| offset <= U256::from(ASSOCIATED_SLOTS))
+            })
+    }
+
+    fn current_entity(&self) -> Option<Erc4337Entity> {
+        self.frames.last().copied().flatten()
+    }
+
+    fn enter_frame(&mut self, target: Address) {
+        let entity = self
+            .current_entity()
+            .or_else(|| self.entities.entity_of(target));
+        self.frames.push(entity);
+    }
+
+    fn check_opcode(&mut self, entity: Erc4337Entity, interp: &Interpreter) {
+        let contract = interp.contract.address;
+        if let Some(PolicyViolationKind::ForbiddenOpcode { opcode }) =
+            self.policy.check_instruction(interp)
+        {
+            self.violations.push(Erc4337Violation::BannedOpcode {
+                entity,
+                contract,
+                opcode,
+            });
+            return;
+        }
+
+        match interp.current_opcode() {
+            opcode::SLOAD | opcode::SSTORE | opcode::TLOAD | opcode::TSTORE => {
+                let Ok(slot) = interp.stack.peek(0) else {
+                    return;
+                };
+                if contract != self.entities.sender && !self.is_associated_slot(slot) {
+                    self.violations.push(Erc4337Violation::UnassociatedStorage {
+                        entity,
+                        contract,
+                        slot,
+                    });
+                }
+            }
+            opcode::KECCAK256 => {
+                let (Ok(offset), Ok(len)) = (interp.stack.peek(0), interp.stack.peek(1)) else {
+                    return;
+                };
+                let offset = offset.saturating_to::<usize>();
+                self.hashing_sender = len >= U256::from(32)
+                    && offset.saturating_add(32) <= interp.shared_memory.len()
+                    && interp.shared_memory.slice(offset, 32)
+                        == self.entities.sender.into_word().as_slice();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Erc4337Inspector {
+    fn default() -> Self {
+        Self::new(UserOperationEntities::default())
+    }
+}
+
+impl<DB: Database> Inspector<DB> for Erc4337Inspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if let Some(entity) = self.current_entity() {
+            self.check_opcode(entity, interp);
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if core::mem::take(&mut self.hashing_sender) {
+            if let Ok(key) = interp.stack.peek(0) {
+                self.sender_keys.insert(key);
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        if let Some(entity) = self.current_entity() {
+            for violation in self.policy.check_call(inputs, 0) {
+                if let PolicyViolationKind::ValueTransfer { target } = violation {
+                    self.violations
+                        .push(Erc4337Violation::CallWithValue { entity, target });
+                }
+            }
+        }
+        self.enter_frame(inputs.contract);
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.frames.pop();
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        // `CREATE` is reported as a banned opcode.
+        if let Some(entity) = self.current_entity() {
+            if matches!(inputs.scheme, CreateScheme::Create2 { .. }) {
+                self.create2_count += 1;
+                if entity != Erc4337Entity::Factory || self.create2_count > 1 {
+                    self.violations.push(Erc4337Violation::Create2 { entity });
+                }
+            }
+        }
+        self.frames.push(self.current_entity());
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.frames.pop();
+        outcome
+    }
+}
+
+/// Result of [simulate_validation].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationSimulation {
+    /// Result of the simulated transaction.
+    pub result: ExecutionResult,
+    /// Violations of the validation rules.
+    pub violations: Vec<Erc4337Violation>,
+}
+
+impl ValidationSimulation {
+    /// Returns `true` if the validation followed the rules.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Simulates the transaction of `env`, usually a call to `simulateValidation` of the entry
+/// point, and checks it against the validation rules of the user operation of `entities`.
+///
+/// The state changes are discarded.
+pub fn simulate_validation<DB: Database>(
+    db: DB,
+    env: Box<Env>,
+    spec_id: SpecId,
+    entities: UserOperationEntities,
+) -> Result<ValidationSimulation, EVMError<DB::Error>> {
+    let mut evm = Evm::builder()
+        .with_db(db)
+        .with_env(env)
+        .with_spec_id(spec_id)
+        .with_external_context(Erc4337Inspector::new(entities))
+        .append_handler_register(inspector_handle_register)
+        .build();
+    let result = evm.transact()?.result;
+    Ok(ValidationSimulation {
+        result,
+        violations: evm.into_context().external.into_violations(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{address, AccountInfo, Bytecode, Bytes, TransactTo},
+        InMemoryDB,
+    };
+
+    #[test]
+    fn banned_opcode_and_storage() {
+        let sender = address!("5e00000000000000000000000000000000000000");
+        let token = address!("70c0000000000000000000000000000000000000");
+
+        // TIMESTAMP POP, then CALL the token forwarding GAS.
+        let mut sender_code = vec![opcode::TIMESTAMP, opcode::POP];
+        sender_code.extend_from_slice(&[opcode::PUSH1, 0].repeat(5));
+        sender_code.push(opcode::PUSH20);
+        sender_code.extend_from_slice(token.as_slice());
+        sender_code.extend_from_slice(&[opcode::GAS, opcode::CALL, opcode::STOP]);
+
+        // SLOAD slot 5, then SLOAD the slot of the sender in a mapping at slot 0.
+        let mut token_code = vec![opcode::PUSH1, 5, opcode::SLOAD, opcode::POP];
+        token_code.push(opcode::PUSH20);
+        token_code.extend_from_slice(sender.as_slice());
+        token_code.extend_from_slice(&[
+            opcode::PUSH1,
+            0,
+            opcode::MSTORE,
+            opcode::PUSH1,
+            0x40,
+            opcode::PUSH1,
+            0,
+            opcode::KECCAK256,
+            opcode::SLOAD,
+            opcode::STOP,
+        ]);
+
+        let mut db = InMemoryDB::default();
+        for (address, code) in [(sender, sender_code), (token, token_code)] {
+            let code = Bytecode::new_raw(Bytes::from(code));
+            db.insert_account_info(
+                address,
+                AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+            );
+        }
+        let mut env = Box::<Env>::default();
+        env.tx.transact_to = TransactTo::Call(sender);
+        env.tx.gas_limit = 100_000;
+
+        let entities = UserOperationEntities {
+            sender,
+            ..Default::default()
+        };
+        let simulation = simulate_validation(db, env, SpecId::CANCUN, entities).unwrap();
+        assert!(simulation.result.is_success());
+        assert_eq!(
+            simulation.violations,
+            [
+                Erc4337Violation::BannedOpcode {
+                    entity: Erc4337Entity::Sender,
+                    contract: sender,
+                    opcode: opcode::TIMESTAMP,
+                },
+                Erc4337Violation::UnassociatedStorage {
+                    entity: Erc4337Entity::Sender,
+                    contract: token,
+                    slot: U256::from(5),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    interpreter::{CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter},
+    Database, EvmContext, Inspector,
+};
+use core::fmt::Write;
+use std::{string::String, vec::Vec};
+
+/// Remaining gas of the transaction after an executed instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GasSample {
+    /// Number of instructions executed by the transaction, starting at 1.
+    pub step: u64,
+    /// Remaining gas of the transaction, including the gas kept by the calling frames.
+    pub remaining: u64,
+}
+
+/// [Inspector] recording the remaining gas of the transaction after each instruction, for
+/// plotting the gas consumption of a transaction over its lifetime.
+///
+/// The remaining gas is the gas of the executing frame plus the gas kept by its callers, so the
+/// series does not jump when a call starts or returns. Samples are taken every
+/// [interval](Self::new) instructions. If the number of samples exceeds the
+/// [maximum](Self::with_max_samples), every other sample is dropped and the interval doubled,
+/// which bounds the size of the series of long transactions. The last instruction of the
+/// transaction is always sampled.
+#[derive(Clone, Debug)]
+pub struct GasSeriesInspector {
+    base_interval: u64,
+    interval: u64,
+    max_samples: usize,
+    step: u64,
+    /// Remaining gas of the calling frames, innermost last.
+    callers: Vec<u64>,
+    /// Sum of [Self::callers].
+    held: u64,
+    last_remaining: u64,
+    samples: Vec<GasSample>,
+}
+
+impl Default for GasSeriesInspector {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl GasSeriesInspector {
+    /// Creates an inspector sampling every `interval` instructions, without a maximum number of
+    /// samples.
+    pub fn new(interval: u64) -> Self {
+        let interval = interval.max(1);
+        Self {
+            base_interval: interval,
+            interval,
+            max_samples: usize::MAX,
+            step: 0,
+            callers: Vec::new(),
+            held: 0,
+            last_remaining: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of samples, at least 2.
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = max_samples.max(2);
+        self
+    }
+
+    /// Returns the samples of the last transaction.
+    pub fn samples(&self) -> &[GasSample] {
+        &self.samples
+    }
+
+    /// Returns the interval between the samples of the last transaction.
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Exports the samples as CSV, with a `step,remaining` header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,remaining\n");
+        for sample in &self.samples {
+            let _ = writeln!(csv, "{},{}", sample.step, sample.remaining);
+        }
+        csv
+    }
+
+    /// Records the remaining gas, the sample of the last instruction is kept when dropping
+    /// samples.
+    fn push(&mut self, last: bool) {
+        self.samples.push(GasSample {
+            step: self.step,
+            remaining: self.last_remaining,
+        });
+        if self.samples.len() > self.max_samples {
+            let len = self.samples.len();
+            let mut index = 0;
+            self.samples.retain(|_| {
+                index += 1;
+                index % 2 == 1 || (last && index == len)
+            });
+            self.interval *= 2;
+        }
+    }
+
+    fn frame_start(&mut self) {
+        if self.callers.is_empty() {
+            self.step = 0;
+            self.interval = self.base_interval;
+            self.samples.clear();
+            self.held = 0;
+            self.last_remaining = 0;
+        }
+        let caller_remaining = self.last_remaining - self.held;
+        self.callers.push(caller_remaining);
+        self.held += caller_remaining;
+    }
+
+    fn frame_end(&mut self) {
+        let caller_remaining = self.callers.pop().unwrap_or_default();
+        self.held -= caller_remaining;
+        let last_sampled = self.samples.last().map(|sample| sample.step);
+        if self.callers.is_empty() && self.step != 0 && last_sampled != Some(self.step) {
+            self.push(true);
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for GasSeriesInspector {
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        self.step += 1;
+        self.last_remaining = self.held + interp.gas.remaining();
+        if (self.step - 1) % self.interval == 0 {
+            self.push(false);
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        self.frame_start();
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.frame_end();
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.frame_start();
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.frame_end();
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector::inspector_handle_register,
+        interpreter::opcode,
+        primitives::{Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    fn run(inspector: GasSeriesInspector) -> GasSeriesInspector {
+        // 20 times PUSH0 POP, then STOP.
+        let mut code = [opcode::PUSH0, opcode::POP].repeat(20);
+        code.push(opcode::STOP);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(Bytes::from(code)))
+            .with_external_context(inspector)
+            .append_handler_register(inspector_handle_register)
+            .build();
+        evm.transact().unwrap();
+        evm.context.external
+    }
+
+    #[test]
+    fn gas_series() {
+        let inspector = run(GasSeriesInspector::default());
+        let samples = inspector.samples();
+        assert_eq!(samples.len(), 41);
+        assert_eq!(samples[0].remaining, 100_000 - 21_000 - 2);
+        assert!(samples
+            .windows(2)
+            .all(|pair| pair[0].remaining >= pair[1].remaining));
+        assert_eq!(samples[40].step, 41);
+        assert!(inspector.to_csv().starts_with("step,remaining\n1,78998\n"));
+
+        let inspector = run(GasSeriesInspector::new(4).with_max_samples(4));
+        assert!(inspector.samples().len() <= 4);
+        assert_eq!(inspector.interval(), 16);
+        let steps: Vec<_> = inspector.samples().iter().map(|s| s.step).collect();
+        assert_eq!(steps, [1, 17, 33, 41]);
+    }
+}
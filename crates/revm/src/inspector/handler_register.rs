@@ -1,9 +1,13 @@
 use crate::{
     db::Database,
     handler::register::EvmHandler,
-    interpreter::{opcode, opcode::BoxedInstruction, InstructionResult, Interpreter},
-    primitives::EVMError,
-    Evm, FrameOrResult, FrameResult, Inspector, JournalEntry,
+    interpreter::{
+        gas, opcode, opcode::BoxedInstruction, CreateInputs, CreateOutcome, InstructionResult,
+        Interpreter,
+    },
+    primitives::{init_code_hash, CreateScheme, EVMError, U256},
+    ContractCreation, ContractSelfDestruct, Evm, EvmContext, FrameOrResult, FrameResult, Inspector,
+    JournalEntry, LogPosition, StorageAccess,
 };
 use core::cell::RefCell;
 use revm_interpreter::opcode::InstructionTables;
@@ -32,8 +36,8 @@ impl<DB: Database, INSP: Inspector<DB>> GetInspector<DB> for INSP {
 /// to use this register with any other register.
 ///
 /// A few instructions handlers are wrapped twice once for `step` and `step_end`
-/// and in case of Logs and Selfdestruct wrapper is wrapped again for the
-/// `log` and `selfdestruct` calls.
+/// and in case of Logs, Selfdestruct and storage instructions wrapper is wrapped
+/// again for the `log`, `selfdestruct`, `sload` and `sstore` calls.
 pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
     handler: &mut EvmHandler<'a, EXT, DB>,
 ) {
@@ -59,6 +63,12 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
             *i = Box::new(
                 move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
                     let old_log_len = host.context.evm.journaled_state.logs.len();
+                    let position = LogPosition {
+                        index: old_log_len,
+                        depth: host.context.evm.journaled_state.depth() as usize,
+                        // The instruction pointer is already past the opcode.
+                        pc: interpreter.program_counter() - 1,
+                    };
                     old(interpreter, host);
                     // check if log was added. It is possible that revert happened
                     // cause of gas or stack underflow.
@@ -75,10 +85,11 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
                             .unwrap()
                             .clone();
                         // call Inspector
-                        host.context
-                            .external
-                            .get_inspector()
-                            .log(&mut host.context.evm, &last_log);
+                        host.context.external.get_inspector().log(
+                            &mut host.context.evm,
+                            &last_log,
+                            &position,
+                        );
                     }
                 },
             )
@@ -96,22 +107,32 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
         let old = core::mem::replace(i, Box::new(|_, _| ()));
         *i = Box::new(
             move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                // entries of the current frame before selfdestruct, to not mistake an
+                // older entry for the one made by this selfdestruct.
+                let journal_len = host
+                    .context
+                    .evm
+                    .journaled_state
+                    .journal
+                    .last()
+                    .map(Vec::len);
                 // execute selfdestruct
                 old(interpreter, host);
+                let last_entry = host
+                    .context
+                    .evm
+                    .journaled_state
+                    .journal
+                    .last()
+                    .filter(|entries| journal_len.map_or(true, |len| entries.len() > len))
+                    .and_then(|entries| entries.last());
                 // check if selfdestruct was successful and if journal entry is made.
                 if let Some(JournalEntry::AccountDestroyed {
                     address,
                     target,
                     had_balance,
                     ..
-                }) = host
-                    .context
-                    .evm
-                    .journaled_state
-                    .journal
-                    .last()
-                    .unwrap()
-                    .last()
+                }) = last_entry
                 {
                     host.context.external.get_inspector().selfdestruct(
                         *address,
@@ -119,10 +140,107 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
                         *had_balance,
                     );
                 }
+
+                if interpreter.instruction_result != InstructionResult::SelfDestruct {
+                    return;
+                }
+                let address = interpreter.contract.address;
+                let selfdestruct = match last_entry {
+                    Some(JournalEntry::AccountDestroyed {
+                        target,
+                        had_balance,
+                        ..
+                    }) => ContractSelfDestruct {
+                        address,
+                        target: *target,
+                        value: *had_balance,
+                        destroyed: true,
+                    },
+                    Some(JournalEntry::BalanceTransfer { to, balance, .. }) => {
+                        ContractSelfDestruct {
+                            address,
+                            target: *to,
+                            value: *balance,
+                            destroyed: false,
+                        }
+                    }
+                    // since Cancun, a contract that is not new and targets itself is unchanged.
+                    _ => ContractSelfDestruct {
+                        address,
+                        target: address,
+                        value: U256::ZERO,
+                        destroyed: false,
+                    },
+                };
+                host.context
+                    .external
+                    .get_inspector()
+                    .contract_selfdestructed(&mut host.context.evm, &selfdestruct);
             },
         )
     }
 
+    // Register inspector storage instructions.
+    let mut inspect_storage = |index: u8| {
+        if let Some(i) = table.get_mut(index as usize) {
+            let old = core::mem::replace(i, Box::new(|_, _| ()));
+            *i = Box::new(
+                move |interpreter: &mut Interpreter, host: &mut Evm<'a, EXT, DB>| {
+                    let address = interpreter.contract.address;
+                    let Ok(key) = interpreter.stack.peek(0) else {
+                        return old(interpreter, host);
+                    };
+                    let journal = &host.context.evm.journaled_state;
+                    let is_cold = !journal.is_storage_warm(address, key);
+                    let present_value = journal
+                        .state
+                        .get(&address)
+                        .and_then(|account| account.storage.get(&key))
+                        .map(|slot| slot.present_value);
+                    let remaining = interpreter.gas.remaining();
+                    let refunded = interpreter.gas.refunded();
+
+                    old(interpreter, host);
+
+                    // the instruction can fail on gas, stack or static call checks.
+                    if interpreter.instruction_result != InstructionResult::Continue {
+                        return;
+                    }
+                    let Some(slot) = host
+                        .context
+                        .evm
+                        .journaled_state
+                        .state
+                        .get(&address)
+                        .and_then(|account| account.storage.get(&key))
+                    else {
+                        return;
+                    };
+                    let access = StorageAccess {
+                        address,
+                        key,
+                        // a cold slot was loaded with its original value.
+                        old_value: present_value.unwrap_or(slot.original_value()),
+                        new_value: slot.present_value,
+                        original_value: slot.original_value(),
+                        is_cold,
+                        gas_cost: remaining - interpreter.gas.remaining(),
+                        gas_refund: interpreter.gas.refunded() - refunded,
+                    };
+                    let inspector = host.context.external.get_inspector();
+                    if index == opcode::SLOAD {
+                        inspector.sload(&mut host.context.evm, &access);
+                    } else {
+                        inspector.sstore(&mut host.context.evm, &access);
+                    }
+                },
+            )
+        }
+    };
+
+    inspect_storage(opcode::SLOAD);
+    inspect_storage(opcode::SSTORE);
+
     // cast vector to array.
     handler.set_instruction_table(InstructionTables::Boxed(
         table.try_into().unwrap_or_else(|_| unreachable!()),
@@ -196,10 +314,11 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
     let old_handle = handler.execution.insert_create_outcome.clone();
     handler.execution.insert_create_outcome = Arc::new(move |ctx, frame, mut outcome| {
         let create_inputs = create_input_stack_inner.borrow_mut().pop().unwrap();
-        outcome = ctx
-            .external
-            .get_inspector()
-            .create_end(&mut ctx.evm, &create_inputs, outcome);
+        let inspector = ctx.external.get_inspector();
+        if let Some(created) = contract_creation(&ctx.evm, &create_inputs, &outcome) {
+            inspector.contract_created(&mut ctx.evm, &created);
+        }
+        outcome = inspector.create_end(&mut ctx.evm, &create_inputs, outcome);
         old_handle(ctx, frame, outcome)
     });
 
@@ -214,6 +333,9 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
             }
             FrameResult::Create(outcome) => {
                 let create_inputs = create_input_stack.borrow_mut().pop().unwrap();
+                if let Some(created) = contract_creation(&ctx.evm, &create_inputs, outcome) {
+                    inspector.contract_created(&mut ctx.evm, &created);
+                }
                 *outcome = inspector.create_end(&mut ctx.evm, &create_inputs, outcome.clone());
             }
         }
@@ -221,6 +343,39 @@ pub fn inspector_handle_register<'a, DB: Database, EXT: GetInspector<DB>>(
     });
 }
 
+/// Returns the contract deployed by a create frame, if it succeeded.
+fn contract_creation<DB: Database>(
+    context: &EvmContext<DB>,
+    inputs: &CreateInputs,
+    outcome: &CreateOutcome,
+) -> Option<ContractCreation> {
+    if !outcome.result.is_ok() {
+        return None;
+    }
+    let address = outcome.address?;
+    let code = context
+        .journaled_state
+        .state
+        .get(&address)?
+        .info
+        .code
+        .clone()
+        .unwrap_or_default();
+    let salt = match inputs.scheme {
+        CreateScheme::Create => None,
+        CreateScheme::Create2 { salt } | CreateScheme::EofCreate { salt } => Some(salt),
+    };
+    Some(ContractCreation {
+        address,
+        code_size: code.len(),
+        code_deposit_gas: gas::CODEDEPOSIT * code.len() as u64,
+        code,
+        gas_used: outcome.gas().spent(),
+        init_code_hash: init_code_hash(&inputs.init_code),
+        salt,
+    })
+}
+
 /// Outer closure that calls Inspector for every instruction.
 pub fn inspector_instruction<
     'a,
@@ -265,7 +420,8 @@ mod tests {
         db::EmptyDB,
         inspectors::NoOpInspector,
         interpreter::{opcode::*, CallInputs, CallOutcome, CreateInputs, CreateOutcome},
-        primitives::BerlinSpec,
+        primitives::{BerlinSpec, Log},
+        test_utils::benchmark_evm_builder,
         EvmContext,
     };
 
@@ -404,6 +560,227 @@ mod tests {
         assert!(inspector.call_end);
     }
 
+    #[derive(Default)]
+    struct StorageInspector {
+        sloads: Vec<StorageAccess>,
+        sstores: Vec<StorageAccess>,
+    }
+
+    impl<DB: Database> Inspector<DB> for StorageInspector {
+        fn sload(&mut self, _context: &mut EvmContext<DB>, access: &StorageAccess) {
+            self.sloads.push(*access);
+        }
+
+        fn sstore(&mut self, _context: &mut EvmContext<DB>, access: &StorageAccess) {
+            self.sstores.push(*access);
+        }
+    }
+
+    #[test]
+    fn test_inspector_storage() {
+        use crate::{
+            interpreter::gas::{COLD_SLOAD_COST, SSTORE_SET, WARM_STORAGE_READ_COST},
+            primitives::{Address, Bytecode, Bytes, U256},
+        };
+
+        // SSTORE(0, 1) SLOAD(0) STOP
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x01,
+            opcode::PUSH1,
+            0x00,
+            opcode::SSTORE,
+            opcode::PUSH1,
+            0x00,
+            opcode::SLOAD,
+            opcode::STOP,
+        ]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(StorageInspector::default())
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        let inspector = evm.into_context().external;
+        assert_eq!(
+            inspector.sstores,
+            [StorageAccess {
+                address: Address::ZERO,
+                key: U256::ZERO,
+                old_value: U256::ZERO,
+                new_value: U256::from(1),
+                original_value: U256::ZERO,
+                is_cold: true,
+                gas_cost: SSTORE_SET + COLD_SLOAD_COST,
+                gas_refund: 0,
+            }]
+        );
+        assert_eq!(
+            inspector.sloads,
+            [StorageAccess {
+                address: Address::ZERO,
+                key: U256::ZERO,
+                old_value: U256::from(1),
+                new_value: U256::from(1),
+                original_value: U256::ZERO,
+                is_cold: false,
+                gas_cost: WARM_STORAGE_READ_COST,
+                gas_refund: 0,
+            }]
+        );
+    }
+
+    #[derive(Default)]
+    struct LogInspector {
+        positions: Vec<LogPosition>,
+    }
+
+    impl<DB: Database> Inspector<DB> for LogInspector {
+        fn log(&mut self, _context: &mut EvmContext<DB>, _log: &Log, position: &LogPosition) {
+            self.positions.push(*position);
+        }
+    }
+
+    #[test]
+    fn test_inspector_log_position() {
+        use crate::primitives::{Bytecode, Bytes};
+
+        // LOG0(0, 0) LOG0(0, 0)
+        let code = Bytes::from(vec![
+            opcode::PUSH1,
+            0x00,
+            opcode::PUSH1,
+            0x00,
+            opcode::LOG0,
+            opcode::PUSH1,
+            0x00,
+            opcode::PUSH1,
+            0x00,
+            opcode::LOG0,
+        ]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(LogInspector::default())
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        assert_eq!(
+            evm.into_context().external.positions,
+            [
+                LogPosition {
+                    index: 0,
+                    depth: 1,
+                    pc: 4
+                },
+                LogPosition {
+                    index: 1,
+                    depth: 1,
+                    pc: 9
+                }
+            ]
+        );
+    }
+
+    #[derive(Default)]
+    struct LifecycleInspector {
+        created: Vec<ContractCreation>,
+        selfdestructed: Vec<ContractSelfDestruct>,
+    }
+
+    impl<DB: Database> Inspector<DB> for LifecycleInspector {
+        fn contract_created(&mut self, _context: &mut EvmContext<DB>, created: &ContractCreation) {
+            self.created.push(created.clone());
+        }
+
+        fn contract_selfdestructed(
+            &mut self,
+            _context: &mut EvmContext<DB>,
+            selfdestruct: &ContractSelfDestruct,
+        ) {
+            self.selfdestructed.push(*selfdestruct);
+        }
+    }
+
+    #[test]
+    fn test_inspector_lifecycle() {
+        use crate::primitives::{Address, Bytecode, Bytes, TransactTo};
+
+        // SELFDESTRUCT(2)
+        let code = Bytes::from(vec![opcode::PUSH1, 0x02, opcode::SELFDESTRUCT]);
+        let caller = Address::with_last_byte(1);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(LifecycleInspector::default())
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        // Deploys a single zero byte: RETURN(0, 1)
+        evm.tx_mut().transact_to = TransactTo::create();
+        evm.tx_mut().data = Bytes::from(vec![
+            opcode::PUSH1,
+            0x01,
+            opcode::PUSH1,
+            0x00,
+            opcode::RETURN,
+        ]);
+        assert!(evm.transact().unwrap().result.is_success());
+
+        let inspector = evm.into_context().external;
+        // The contract is not created in the transaction, so it keeps existing since Cancun.
+        assert_eq!(
+            inspector.selfdestructed,
+            [ContractSelfDestruct {
+                address: Address::ZERO,
+                target: Address::with_last_byte(2),
+                value: U256::from(10_000_000),
+                destroyed: false,
+            }]
+        );
+        assert_eq!(inspector.created.len(), 1);
+        let created = &inspector.created[0];
+        assert_eq!(created.address, caller.create(0));
+        assert_eq!(created.code.original_bytes().as_ref(), [0x00]);
+        // Two pushes, the memory expansion and the code deposit.
+        assert_eq!(created.gas_used, 3 + 3 + 3 + 200);
+        assert_eq!((created.code_size, created.code_deposit_gas), (1, 200));
+        assert_eq!(created.salt, None);
+        assert_eq!(
+            created.init_code_hash,
+            crate::primitives::keccak256([
+                opcode::PUSH1,
+                0x01,
+                opcode::PUSH1,
+                0x00,
+                opcode::RETURN
+            ])
+        );
+    }
+
+    #[test]
+    fn test_inspector_selfdestruct_to_self() {
+        use crate::primitives::{Address, Bytecode, Bytes};
+
+        // SELFDESTRUCT(ADDRESS) in a frame whose journal starts with the value transfer.
+        let code = Bytes::from(vec![opcode::ADDRESS, opcode::SELFDESTRUCT]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(LifecycleInspector::default())
+            .modify_tx_env(|tx| tx.value = U256::from(1000))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        // Since Cancun the contract and its balance are left as they are.
+        assert_eq!(
+            evm.into_context().external.selfdestructed,
+            [ContractSelfDestruct {
+                address: Address::ZERO,
+                target: Address::ZERO,
+                value: U256::ZERO,
+                destroyed: false,
+            }]
+        );
+    }
+
     #[test]
     fn test_inspector_reg() {
         let mut noop = NoOpInspector;
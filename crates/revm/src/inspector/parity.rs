@@ -0,0 +1,775 @@
+//! Parity/OpenEthereum `trace_*` format tracer.
+//!
+//! [ParityTracer] produces the `trace` and `vmTrace` parts of a `trace_call` or
+//! `trace_replayTransaction` response, [state_diff] the `stateDiff` part.
+
+use crate::{
+    interpreter::{
+        opcode, CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome,
+        InstructionResult, Interpreter,
+    },
+    primitives::{
+        db::{Database, DatabaseRef},
+        Account, Address, Bytecode, Bytes, HashMap, B256, KECCAK_EMPTY, U256,
+    },
+    EvmContext, Inspector,
+};
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Single entry of the `trace` array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TransactionTrace {
+    /// Action of the trace, serialized as `type` and `action` fields.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub action: Action,
+    /// Error message of a failed frame.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub error: Option<String>,
+    /// Result of a successful frame.
+    pub result: Option<TraceOutput>,
+    /// Number of direct child traces.
+    pub subtraces: usize,
+    /// Path of the trace in the call tree.
+    pub trace_address: Vec<usize>,
+}
+
+/// Action of a [TransactionTrace].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "action", rename_all = "lowercase")
+)]
+pub enum Action {
+    /// Message call.
+    Call(CallAction),
+    /// Contract creation.
+    Create(CreateAction),
+    /// Self-destruct.
+    #[cfg_attr(feature = "serde", serde(rename = "suicide"))]
+    Selfdestruct(SelfdestructAction),
+}
+
+/// Kind of a message call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum CallType {
+    /// `CALL`.
+    Call,
+    /// `CALLCODE`.
+    CallCode,
+    /// `DELEGATECALL`.
+    DelegateCall,
+    /// `STATICCALL`.
+    StaticCall,
+}
+
+impl From<CallScheme> for CallType {
+    fn from(scheme: CallScheme) -> Self {
+        match scheme {
+            CallScheme::Call => Self::Call,
+            CallScheme::CallCode => Self::CallCode,
+            CallScheme::DelegateCall => Self::DelegateCall,
+            CallScheme::StaticCall => Self::StaticCall,
+        }
+    }
+}
+
+/// Message call action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct CallAction {
+    /// Caller.
+    pub from: Address,
+    /// Called address, the code address for `DELEGATECALL` and `CALLCODE`.
+    pub to: Address,
+    /// Value, the apparent value for `DELEGATECALL`.
+    pub value: U256,
+    /// Gas available to the call.
+    pub gas: u64,
+    /// Call data.
+    pub input: Bytes,
+    /// Kind of the call.
+    pub call_type: CallType,
+}
+
+/// Contract creation action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct CreateAction {
+    /// Creator.
+    pub from: Address,
+    /// Endowment.
+    pub value: U256,
+    /// Gas available to the init code.
+    pub gas: u64,
+    /// Init code.
+    pub init: Bytes,
+}
+
+/// Self-destruct action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct SelfdestructAction {
+    /// Self-destructed contract.
+    pub address: Address,
+    /// Beneficiary of the balance.
+    pub refund_address: Address,
+    /// Balance of the contract.
+    pub balance: U256,
+}
+
+/// Result of a successful frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum TraceOutput {
+    /// Result of a message call.
+    Call(CallOutput),
+    /// Result of a contract creation.
+    Create(CreateOutput),
+}
+
+/// Result of a message call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct CallOutput {
+    /// Gas used by the call.
+    pub gas_used: u64,
+    /// Return data.
+    pub output: Bytes,
+}
+
+/// Result of a contract creation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct CreateOutput {
+    /// Gas used by the init code.
+    pub gas_used: u64,
+    /// Deployed code.
+    pub code: Bytes,
+    /// Address of the created contract.
+    pub address: Address,
+}
+
+/// The `vmTrace` of a frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VmTrace {
+    /// Executed code.
+    pub code: Bytes,
+    /// Executed instructions.
+    pub ops: Vec<VmInstruction>,
+}
+
+/// Instruction of a [VmTrace].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VmInstruction {
+    /// Program counter.
+    pub pc: usize,
+    /// Gas cost, including gas forwarded to sub calls.
+    pub cost: u64,
+    /// Effects of the instruction, `None` if it failed.
+    pub ex: Option<VmExecutedOperation>,
+    /// Trace of the sub call made by the instruction.
+    pub sub: Option<VmTrace>,
+}
+
+/// Effects of an executed [VmInstruction].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VmExecutedOperation {
+    /// Gas remaining after the instruction.
+    pub used: u64,
+    /// Stack items written by the instruction, bottom first.
+    pub push: Vec<U256>,
+    /// Memory written by the instruction.
+    pub mem: Option<MemoryDelta>,
+    /// Storage written by the instruction.
+    pub store: Option<StorageDelta>,
+}
+
+/// Memory written by an instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryDelta {
+    /// Offset of the written memory.
+    pub off: usize,
+    /// Written bytes.
+    pub data: Bytes,
+}
+
+/// Storage written by an instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageDelta {
+    /// Storage key.
+    pub key: U256,
+    /// New value.
+    pub val: U256,
+}
+
+/// Instruction whose effects are not recorded yet.
+#[derive(Debug)]
+struct PendingInstruction {
+    index: usize,
+    opcode: u8,
+    gas_before: u64,
+    mem: Option<(usize, usize)>,
+    store: Option<StorageDelta>,
+}
+
+/// [VmTrace] of a frame that is being executed.
+#[derive(Debug)]
+struct VmFrame {
+    trace: VmTrace,
+    pending: Option<PendingInstruction>,
+}
+
+/// [Inspector] that produces Parity-style `trace` and, optionally, `vmTrace` output.
+#[derive(Debug, Default)]
+pub struct ParityTracer {
+    record_vm_trace: bool,
+    traces: Vec<TransactionTrace>,
+    /// Indices of traces of the frames that are being executed.
+    trace_stack: Vec<usize>,
+    /// VM traces of the frames that are being executed, `None` for frames without code.
+    vm_stack: Vec<Option<VmFrame>>,
+    vm_trace: Option<VmTrace>,
+}
+
+impl ParityTracer {
+    /// Creates a tracer that records the `trace` output.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also record the `vmTrace` output.
+    pub fn with_vm_trace(mut self) -> Self {
+        self.record_vm_trace = true;
+        self
+    }
+
+    /// Returns the recorded `trace` output.
+    pub fn traces(&self) -> &[TransactionTrace] {
+        &self.traces
+    }
+
+    /// Consumes the tracer and returns the recorded `trace` output.
+    pub fn into_traces(self) -> Vec<TransactionTrace> {
+        self.traces
+    }
+
+    /// Returns the recorded `vmTrace` output, if enabled.
+    pub fn vm_trace(&self) -> Option<&VmTrace> {
+        self.vm_trace.as_ref()
+    }
+
+    /// Clears the recorded output so the tracer can be used for the next transaction.
+    pub fn clear(&mut self) {
+        self.traces.clear();
+        self.trace_stack.clear();
+        self.vm_stack.clear();
+        self.vm_trace = None;
+    }
+
+    fn start_trace(&mut self, action: Action) -> Vec<usize> {
+        let trace_address = match self.trace_stack.last() {
+            Some(&parent) => {
+                let parent = &mut self.traces[parent];
+                let mut address = parent.trace_address.clone();
+                address.push(parent.subtraces);
+                parent.subtraces += 1;
+                address
+            }
+            None => Vec::new(),
+        };
+        self.traces.push(TransactionTrace {
+            action,
+            error: None,
+            result: None,
+            subtraces: 0,
+            trace_address: trace_address.clone(),
+        });
+        trace_address
+    }
+
+    fn enter(&mut self, action: Action) {
+        self.start_trace(action);
+        self.trace_stack.push(self.traces.len() - 1);
+        if self.record_vm_trace {
+            self.vm_stack.push(None);
+        }
+    }
+
+    fn exit(&mut self, result: InstructionResult, output: Result<TraceOutput, ()>) {
+        if let Some(index) = self.trace_stack.pop() {
+            let trace = &mut self.traces[index];
+            match output {
+                Ok(output) if result.is_ok() => trace.result = Some(output),
+                _ => trace.error = Some(error_message(result)),
+            }
+        }
+
+        if !self.record_vm_trace {
+            return;
+        }
+        let Some(frame) = self.vm_stack.pop().flatten() else {
+            return;
+        };
+        match self.vm_stack.last_mut() {
+            Some(Some(parent)) => {
+                if let Some(pending) = &parent.pending {
+                    parent.trace.ops[pending.index].sub = Some(frame.trace);
+                }
+            }
+            Some(None) => {}
+            None => self.vm_trace = Some(frame.trace),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ParityTracer {
+    fn initialize_interp(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if let Some(frame) = self.vm_stack.last_mut() {
+            *frame = Some(VmFrame {
+                trace: VmTrace {
+                    code: interp.contract.bytecode.original_bytecode(),
+                    ops: Vec::new(),
+                },
+                pending: None,
+            });
+        }
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        let Some(Some(frame)) = self.vm_stack.last_mut() else {
+            return;
+        };
+        // Effects of calls and creates are only known when the frame resumes.
+        if let Some(pending) = frame.pending.take() {
+            finish_instruction(&mut frame.trace, pending, interp);
+        }
+
+        let opcode = interp.current_opcode();
+        let stack = interp.stack();
+        let peek = |n| {
+            stack
+                .peek(n)
+                .map(|v| v.saturating_to::<usize>())
+                .unwrap_or_default()
+        };
+        let mem = match opcode {
+            opcode::MSTORE => Some((peek(0), 32)),
+            opcode::MSTORE8 => Some((peek(0), 1)),
+            opcode::CALLDATACOPY | opcode::CODECOPY | opcode::RETURNDATACOPY | opcode::MCOPY => {
+                Some((peek(0), peek(2)))
+            }
+            opcode::EXTCODECOPY => Some((peek(1), peek(3))),
+            opcode::CALL | opcode::CALLCODE => Some((peek(5), peek(6))),
+            opcode::DELEGATECALL | opcode::STATICCALL => Some((peek(4), peek(5))),
+            _ => None,
+        };
+        let store = match opcode {
+            opcode::SSTORE => match (stack.peek(0), stack.peek(1)) {
+                (Ok(key), Ok(val)) => Some(StorageDelta { key, val }),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        frame.trace.ops.push(VmInstruction {
+            pc: interp.program_counter(),
+            cost: 0,
+            ex: None,
+            sub: None,
+        });
+        frame.pending = Some(PendingInstruction {
+            index: frame.trace.ops.len() - 1,
+            opcode,
+            gas_before: interp.gas.remaining(),
+            mem,
+            store,
+        });
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        let Some(Some(frame)) = self.vm_stack.last_mut() else {
+            return;
+        };
+        let is_call_or_create = frame.pending.as_ref().map_or(false, |pending| {
+            matches!(
+                pending.opcode,
+                opcode::CALL
+                    | opcode::CALLCODE
+                    | opcode::DELEGATECALL
+                    | opcode::STATICCALL
+                    | opcode::CREATE
+                    | opcode::CREATE2
+            )
+        });
+        if is_call_or_create && interp.instruction_result == InstructionResult::CallOrCreate {
+            return;
+        }
+        if let Some(pending) = frame.pending.take() {
+            finish_instruction(&mut frame.trace, pending, interp);
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let value = match inputs.context.scheme {
+            CallScheme::DelegateCall => inputs.context.apparent_value,
+            _ => inputs.transfer.value,
+        };
+        self.enter(Action::Call(CallAction {
+            from: inputs.context.caller,
+            to: inputs.contract,
+            value,
+            gas: inputs.gas_limit,
+            input: inputs.input.clone(),
+            call_type: inputs.context.scheme.into(),
+        }));
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        let output = TraceOutput::Call(CallOutput {
+            gas_used: outcome.gas().spent(),
+            output: outcome.output().clone(),
+        });
+        self.exit(*outcome.instruction_result(), Ok(output));
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.enter(Action::Create(CreateAction {
+            from: inputs.caller,
+            value: inputs.value,
+            gas: inputs.gas_limit,
+            init: inputs.init_code.clone(),
+        }));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        let output = outcome.address.ok_or(()).map(|address| {
+            TraceOutput::Create(CreateOutput {
+                gas_used: outcome.gas().spent(),
+                code: outcome.output().clone(),
+                address,
+            })
+        });
+        self.exit(*outcome.instruction_result(), output);
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        self.start_trace(Action::Selfdestruct(SelfdestructAction {
+            address: contract,
+            refund_address: target,
+            balance: value,
+        }));
+    }
+}
+
+/// Records the effects of the instruction using the interpreter state after its execution.
+fn finish_instruction(trace: &mut VmTrace, pending: PendingInstruction, interp: &Interpreter) {
+    let instruction = &mut trace.ops[pending.index];
+    let remaining = interp.gas.remaining();
+    instruction.cost = pending.gas_before.saturating_sub(remaining);
+    if interp.instruction_result != InstructionResult::Continue
+        && !interp.instruction_result.is_ok()
+    {
+        return;
+    }
+
+    let stack = interp.stack().data();
+    let pushed = stack_outputs(pending.opcode).min(stack.len());
+    let mem = pending.mem.and_then(|(off, len)| {
+        let end = off.checked_add(len)?;
+        (len != 0 && end <= interp.shared_memory.len()).then(|| MemoryDelta {
+            off,
+            data: Bytes::copy_from_slice(interp.shared_memory.slice(off, len)),
+        })
+    });
+    instruction.ex = Some(VmExecutedOperation {
+        used: remaining,
+        push: stack[stack.len() - pushed..].to_vec(),
+        mem,
+        store: pending.store,
+    });
+}
+
+/// Number of stack items written by the instruction, as reported in the `push` field.
+fn stack_outputs(opcode: u8) -> usize {
+    match opcode {
+        opcode::DUP1..=opcode::DUP16 => (opcode - opcode::DUP1) as usize + 2,
+        opcode::SWAP1..=opcode::SWAP16 => (opcode - opcode::SWAP1) as usize + 2,
+        opcode::PUSH0..=opcode::PUSH32 => 1,
+        opcode::STOP
+        | opcode::CALLDATACOPY
+        | opcode::CODECOPY
+        | opcode::EXTCODECOPY
+        | opcode::RETURNDATACOPY
+        | opcode::POP
+        | opcode::MSTORE
+        | opcode::MSTORE8
+        | opcode::SSTORE
+        | opcode::JUMP
+        | opcode::JUMPI
+        | opcode::JUMPDEST
+        | opcode::TSTORE
+        | opcode::MCOPY
+        | opcode::LOG0..=opcode::LOG4
+        | opcode::RETURN
+        | opcode::REVERT
+        | opcode::INVALID
+        | opcode::SELFDESTRUCT => 0,
+        _ => 1,
+    }
+}
+
+/// Parity error message of a failed frame.
+fn error_message(result: InstructionResult) -> String {
+    match result {
+        InstructionResult::Revert => "Reverted".into(),
+        InstructionResult::OutOfGas
+        | InstructionResult::MemoryOOG
+        | InstructionResult::MemoryLimitOOG
+        | InstructionResult::PrecompileOOG
+        | InstructionResult::InvalidOperandOOG => "Out of gas".into(),
+        InstructionResult::OpcodeNotFound | InstructionResult::InvalidFEOpcode => {
+            "Bad instruction".into()
+        }
+        InstructionResult::InvalidJump => "Bad jump destination".into(),
+        InstructionResult::StackUnderflow | InstructionResult::StackOverflow => {
+            "Out of stack".into()
+        }
+        InstructionResult::CallTooDeep => "Call depth limit exceeded".into(),
+        InstructionResult::StateChangeDuringStaticCall
+        | InstructionResult::CallNotAllowedInsideStatic => "Mutable call in static context".into(),
+        InstructionResult::OutOfFunds => "Insufficient balance for transfer".into(),
+        InstructionResult::PrecompileError => "Built-in failed".into(),
+        result => format!("{result:?}"),
+    }
+}
+
+/// Change of a value in a [state diff](AccountDiff).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Delta<T> {
+    /// Value did not change.
+    #[cfg_attr(feature = "serde", serde(rename = "="))]
+    Unchanged,
+    /// Value was created together with the account.
+    #[cfg_attr(feature = "serde", serde(rename = "+"))]
+    Added(T),
+    /// Value was removed together with the account.
+    #[cfg_attr(feature = "serde", serde(rename = "-"))]
+    Removed(T),
+    /// Value changed.
+    #[cfg_attr(feature = "serde", serde(rename = "*"))]
+    Changed {
+        /// Value before the transaction.
+        from: T,
+        /// Value after the transaction.
+        to: T,
+    },
+}
+
+impl<T: PartialEq> Delta<T> {
+    /// Creates the delta between the values before and after the transaction.
+    pub fn new(from: T, to: T) -> Self {
+        if from == to {
+            Self::Unchanged
+        } else {
+            Self::Changed { from, to }
+        }
+    }
+
+    /// Returns `true` if the value did not change.
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Self::Unchanged)
+    }
+}
+
+/// State changes of an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountDiff {
+    /// Balance change.
+    pub balance: Delta<U256>,
+    /// Nonce change.
+    pub nonce: Delta<u64>,
+    /// Code change.
+    pub code: Delta<Bytes>,
+    /// Changed storage slots.
+    pub storage: BTreeMap<B256, Delta<B256>>,
+}
+
+/// Computes the `stateDiff` of a transaction from the database it was executed on and the
+/// resulting state.
+pub fn state_diff<DB: DatabaseRef>(
+    db: &DB,
+    state: &HashMap<Address, Account>,
+) -> Result<BTreeMap<Address, AccountDiff>, DB::Error> {
+    let code = |info_code: &Option<Bytecode>, code_hash: B256| -> Result<Bytes, DB::Error> {
+        Ok(match info_code {
+            Some(code) => code.original_bytes(),
+            None if code_hash == KECCAK_EMPTY => Bytes::new(),
+            None => db.code_by_hash_ref(code_hash)?.original_bytes(),
+        })
+    };
+    let word = |value: U256| B256::from(value.to_be_bytes::<32>());
+
+    let mut diff = BTreeMap::new();
+    for (address, account) in state {
+        if !account.is_touched() {
+            continue;
+        }
+        let post_code = code(&account.info.code, account.info.code_hash)?;
+        let account_diff = match db.basic_ref(*address)? {
+            Some(pre) if account.is_selfdestructed() => AccountDiff {
+                balance: Delta::Removed(pre.balance),
+                nonce: Delta::Removed(pre.nonce),
+                code: Delta::Removed(code(&pre.code, pre.code_hash)?),
+                storage: account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| !slot.original_value().is_zero())
+                    .map(|(key, slot)| (word(*key), Delta::Removed(word(slot.original_value()))))
+                    .collect(),
+            },
+            Some(pre) => AccountDiff {
+                balance: Delta::new(pre.balance, account.info.balance),
+                nonce: Delta::new(pre.nonce, account.info.nonce),
+                code: Delta::new(code(&pre.code, pre.code_hash)?, post_code),
+                storage: account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(key, slot)| {
+                        let delta =
+                            Delta::new(word(slot.original_value()), word(slot.present_value()));
+                        (word(*key), delta)
+                    })
+                    .collect(),
+            },
+            None if account.is_selfdestructed() || account.is_empty() => continue,
+            None => AccountDiff {
+                balance: Delta::Added(account.info.balance),
+                nonce: Delta::Added(account.info.nonce),
+                code: Delta::Added(post_code),
+                storage: account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| !slot.present_value().is_zero())
+                    .map(|(key, slot)| (word(*key), Delta::Added(word(slot.present_value()))))
+                    .collect(),
+            },
+        };
+        let unchanged = account_diff.balance.is_unchanged()
+            && account_diff.nonce.is_unchanged()
+            && account_diff.code.is_unchanged()
+            && account_diff.storage.is_empty();
+        if !unchanged {
+            diff.insert(*address, account_diff);
+        }
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::EmptyDB,
+        inspector::inspector_handle_register,
+        primitives::{address, AccountInfo},
+        test_utils::benchmark_evm_builder,
+        InMemoryDB,
+    };
+
+    #[test]
+    fn call_trace_and_vm_trace() {
+        // PUSH1 0x2a PUSH1 0x00 SSTORE STOP
+        let code = Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x55, 0x00]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code.clone()))
+            .with_external_context(ParityTracer::new().with_vm_trace())
+            .append_handler_register(inspector_handle_register)
+            .build();
+        evm.transact().unwrap();
+
+        let tracer = &evm.context.external;
+        let traces = tracer.traces();
+        assert_eq!(traces.len(), 1);
+        assert!(traces[0].trace_address.is_empty());
+        assert!(matches!(traces[0].result, Some(TraceOutput::Call(_))));
+
+        let vm_trace = tracer.vm_trace().unwrap();
+        assert_eq!(vm_trace.code, code);
+        let pcs: Vec<_> = vm_trace.ops.iter().map(|op| op.pc).collect();
+        assert_eq!(pcs, [0, 2, 4, 5]);
+        let push = &vm_trace.ops[0].ex.as_ref().unwrap().push;
+        assert_eq!(push, &[U256::from(0x2a)]);
+        let store = vm_trace.ops[2].ex.as_ref().unwrap().store.as_ref().unwrap();
+        assert_eq!((store.key, store.val), (U256::ZERO, U256::from(0x2a)));
+    }
+
+    #[test]
+    fn state_diff_of_new_and_changed_accounts() {
+        let existing = address!("1000000000000000000000000000000000000000");
+        let created = address!("2000000000000000000000000000000000000000");
+        let info = AccountInfo::from_balance(U256::from(5));
+        let mut db = InMemoryDB::new(EmptyDB::default());
+        db.insert_account_info(existing, info.clone());
+
+        let mut state = HashMap::new();
+        let mut account = Account::from(info);
+        account.info.balance = U256::from(3);
+        account.mark_touch();
+        state.insert(existing, account);
+        let mut account = Account::new_not_existing();
+        account.info.balance = U256::from(2);
+        account.mark_touch();
+        state.insert(created, account);
+
+        let diff = state_diff(&db, &state).unwrap();
+        assert_eq!(
+            diff[&existing].balance,
+            Delta::Changed {
+                from: U256::from(5),
+                to: U256::from(3)
+            }
+        );
+        assert!(diff[&existing].nonce.is_unchanged());
+        assert_eq!(diff[&created].balance, Delta::Added(U256::from(2)));
+    }
+}
@@ -0,0 +1,406 @@
+//! Inspector checking execution against a declarative [ExecutionPolicy].
+//!
+//! A policy forbids opcodes, restricts the storage that can be accessed and restricts calls.
+//! The [PolicyInspector] does not stop the execution, it records every [PolicyViolation] so
+//! the caller can decide what to do with them, as bundlers do with ERC-4337 user operations.
+use crate::{
+    db::Database,
+    interpreter::{opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter},
+    primitives::{Address, U256},
+    EvmContext, Inspector,
+};
+use std::vec::Vec;
+
+/// Range of storage slots, of one contract or of all contracts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageRange {
+    /// Contract owning the slots, `None` for all contracts.
+    pub contract: Option<Address>,
+    /// First slot of the range.
+    pub start: U256,
+    /// Last slot of the range, inclusive.
+    pub end: U256,
+}
+
+impl StorageRange {
+    /// Creates a range covering the whole storage of a contract.
+    pub const fn contract(contract: Address) -> Self {
+        Self {
+            contract: Some(contract),
+            start: U256::ZERO,
+            end: U256::MAX,
+        }
+    }
+
+    /// Creates a range of `len` slots of any contract, starting at `start`.
+    pub fn slots(start: U256, len: u64) -> Self {
+        Self {
+            contract: None,
+            start,
+            end: start.saturating_add(U256::from(len.saturating_sub(1))),
+        }
+    }
+
+    /// Returns `true` if the slot of the contract is in the range.
+    pub fn contains(&self, contract: Address, slot: U256) -> bool {
+        self.contract.map_or(true, |owner| owner == contract)
+            && self.start <= slot
+            && slot <= self.end
+    }
+}
+
+/// Rules of an [ExecutionPolicy] that can be violated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PolicyViolationKind {
+    /// Execution of a forbidden opcode.
+    ForbiddenOpcode {
+        /// The opcode.
+        opcode: u8,
+    },
+    /// Access to a storage or transient storage slot outside of the allowed ranges.
+    StorageAccess {
+        /// The slot.
+        slot: U256,
+    },
+    /// Call to an account that is not an allowed target.
+    ForbiddenCall {
+        /// Called account.
+        target: Address,
+    },
+    /// Value transferred to an account that is not an allowed recipient.
+    ValueTransfer {
+        /// Account receiving the value.
+        target: Address,
+    },
+    /// Frame deeper than the maximum depth.
+    CallDepth {
+        /// Depth of the frame, the first checked frame has depth zero.
+        depth: usize,
+    },
+    /// Contract creation while creations are forbidden.
+    Create,
+}
+
+/// Violation of an [ExecutionPolicy].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolicyViolation {
+    /// Contract whose code violates the policy.
+    pub contract: Address,
+    /// Violated rule.
+    pub kind: PolicyViolationKind,
+}
+
+/// Declarative restrictions of an execution.
+///
+/// The default policy allows everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionPolicy {
+    /// Opcodes that must not be executed.
+    pub forbidden_opcodes: Vec<u8>,
+    /// Forbidden opcodes allowed when the next instruction is a call, such as `GAS` forwarding
+    /// the remaining gas.
+    pub allowed_before_call: Vec<u8>,
+    /// Storage slots that can be accessed, `None` if storage is not restricted.
+    pub allowed_storage: Option<Vec<StorageRange>>,
+    /// Accounts that can be called, `None` if calls are not restricted.
+    pub allowed_call_targets: Option<Vec<Address>>,
+    /// Accounts that can receive value from calls, `None` if value transfers are not restricted.
+    pub allowed_value_recipients: Option<Vec<Address>>,
+    /// Maximum depth of frames, `None` if depth is not restricted.
+    pub max_call_depth: Option<usize>,
+    /// Whether contract creations are forbidden.
+    pub forbid_create: bool,
+}
+
+impl ExecutionPolicy {
+    /// Forbids the opcode.
+    pub fn forbid_opcode(mut self, opcode: u8) -> Self {
+        self.forbidden_opcodes.push(opcode);
+        self
+    }
+
+    /// Allows access to the storage range, restricting storage to the allowed ranges.
+    pub fn allow_storage(mut self, range: StorageRange) -> Self {
+        self.allowed_storage
+            .get_or_insert_with(Vec::new)
+            .push(range);
+        self
+    }
+
+    /// Allows calls to the account, restricting calls to the allowed targets.
+    pub fn allow_call_target(mut self, target: Address) -> Self {
+        self.allowed_call_targets
+            .get_or_insert_with(Vec::new)
+            .push(target);
+        self
+    }
+
+    /// Allows value transfers to the account, restricting transfers to the allowed recipients.
+    pub fn allow_value_recipient(mut self, recipient: Address) -> Self {
+        self.allowed_value_recipients
+            .get_or_insert_with(Vec::new)
+            .push(recipient);
+        self
+    }
+
+    /// Returns `true` if the slot of the contract can be accessed.
+    pub fn is_storage_allowed(&self, contract: Address, slot: U256) -> bool {
+        self.allowed_storage.as_ref().map_or(true, |ranges| {
+            ranges.iter().any(|range| range.contains(contract, slot))
+        })
+    }
+
+    /// Checks the instruction the interpreter is about to execute.
+    pub fn check_instruction(&self, interp: &Interpreter) -> Option<PolicyViolationKind> {
+        let op = interp.current_opcode();
+        if self.forbidden_opcodes.contains(&op) {
+            let next = interp
+                .contract
+                .bytecode
+                .bytecode()
+                .get(interp.program_counter() + 1)
+                .copied();
+            let before_call = matches!(
+                next,
+                Some(opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL)
+            );
+            if !(before_call && self.allowed_before_call.contains(&op)) {
+                return Some(PolicyViolationKind::ForbiddenOpcode { opcode: op });
+            }
+        }
+        if matches!(
+            op,
+            opcode::SLOAD | opcode::SSTORE | opcode::TLOAD | opcode::TSTORE
+        ) {
+            let slot = interp.stack.peek(0).ok()?;
+            if !self.is_storage_allowed(interp.contract.address, slot) {
+                return Some(PolicyViolationKind::StorageAccess { slot });
+            }
+        }
+        None
+    }
+
+    /// Checks a call made from a frame at the given depth.
+    pub fn check_call(&self, inputs: &CallInputs, depth: usize) -> Vec<PolicyViolationKind> {
+        let mut violations = Vec::new();
+        if let Some(targets) = &self.allowed_call_targets {
+            if !targets.contains(&inputs.contract) {
+                violations.push(PolicyViolationKind::ForbiddenCall {
+                    target: inputs.contract,
+                });
+            }
+        }
+        let transfer = &inputs.transfer;
+        if let Some(recipients) = &self.allowed_value_recipients {
+            if !transfer.value.is_zero() && !recipients.contains(&transfer.target) {
+                violations.push(PolicyViolationKind::ValueTransfer {
+                    target: transfer.target,
+                });
+            }
+        }
+        violations.extend(self.check_depth(depth + 1));
+        violations
+    }
+
+    /// Checks a creation made from a frame at the given depth.
+    pub fn check_create(&self, depth: usize) -> Vec<PolicyViolationKind> {
+        let mut violations = Vec::new();
+        if self.forbid_create {
+            violations.push(PolicyViolationKind::Create);
+        }
+        violations.extend(self.check_depth(depth + 1));
+        violations
+    }
+
+    fn check_depth(&self, depth: usize) -> Option<PolicyViolationKind> {
+        self.max_call_depth
+            .filter(|max_depth| depth > *max_depth)
+            .map(|_| PolicyViolationKind::CallDepth { depth })
+    }
+}
+
+/// [Inspector] that records the violations of an [ExecutionPolicy], see the
+/// [module documentation](self).
+///
+/// By default every frame is checked. With [PolicyInspector::with_scope] only the frames of the
+/// given contracts and the frames they start are.
+#[derive(Clone, Debug, Default)]
+pub struct PolicyInspector {
+    policy: ExecutionPolicy,
+    scope: Option<Vec<Address>>,
+    violations: Vec<PolicyViolation>,
+    /// Depth in the checked scope of each of the currently executing frames.
+    frames: Vec<Option<usize>>,
+}
+
+impl PolicyInspector {
+    /// Creates an inspector checking every frame against the policy.
+    pub fn new(policy: ExecutionPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Only checks the frames of the given contracts and the frames they start.
+    pub fn with_scope(mut self, contracts: Vec<Address>) -> Self {
+        self.scope = Some(contracts);
+        self
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &ExecutionPolicy {
+        &self.policy
+    }
+
+    /// Returns the policy, for example to allow storage computed during execution.
+    pub fn policy_mut(&mut self) -> &mut ExecutionPolicy {
+        &mut self.policy
+    }
+
+    /// Returns the recorded violations in execution order.
+    pub fn violations(&self) -> &[PolicyViolation] {
+        &self.violations
+    }
+
+    /// Consumes the inspector and returns the recorded violations.
+    pub fn into_violations(self) -> Vec<PolicyViolation> {
+        self.violations
+    }
+
+    /// Returns the depth of the current frame in the checked scope, `None` if it is not checked.
+    fn current_depth(&self) -> Option<usize> {
+        self.frames.last().copied().flatten()
+    }
+
+    fn enter_frame(&mut self, contract: Address) {
+        let depth = match (self.current_depth(), &self.scope) {
+            (Some(depth), _) => Some(depth + 1),
+            (None, None) => Some(0),
+            (None, Some(scope)) => scope.contains(&contract).then_some(0),
+        };
+        self.frames.push(depth);
+    }
+
+    fn record(&mut self, contract: Address, kinds: impl IntoIterator<Item = PolicyViolationKind>) {
+        self.violations.extend(
+            kinds
+                .into_iter()
+                .map(|kind| PolicyViolation { contract, kind }),
+        );
+    }
+}
+
+impl<DB: Database> Inspector<DB> for PolicyInspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if self.current_depth().is_some() {
+            let violation = self.policy.check_instruction(interp);
+            self.record(interp.contract.address, violation);
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        if let Some(depth) = self.current_depth() {
+            let violations = self.policy.check_call(inputs, depth);
+            self.record(inputs.context.caller, violations);
+        }
+        self.enter_frame(inputs.contract);
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.frames.pop();
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        if let Some(depth) = self.current_depth() {
+            let violations = self.policy.check_create(depth);
+            self.record(inputs.caller, violations);
+        }
+        // Created contracts have no address to scope them by yet, they inherit the scope.
+        self.frames
+            .push(self.current_depth().map(|depth| depth + 1));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.frames.pop();
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector::inspector_handle_register,
+        primitives::{Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn records_violations() {
+        // TIMESTAMP POP, SLOAD slot 1 and slot 9, CALL 0xbb with value 1 and all gas.
+        let code = Bytes::from_static(&[
+            0x42, 0x50, 0x60, 0x01, 0x54, 0x50, 0x60, 0x09, 0x54, 0x50, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x60, 0xbb, 0x5a, 0xf1, 0x00,
+        ]);
+        let policy = ExecutionPolicy {
+            allowed_before_call: vec![opcode::GAS],
+            max_call_depth: Some(0),
+            ..Default::default()
+        }
+        .forbid_opcode(opcode::TIMESTAMP)
+        .forbid_opcode(opcode::GAS)
+        .allow_storage(StorageRange::slots(U256::ZERO, 8))
+        .allow_value_recipient(Address::with_last_byte(0xaa));
+
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(PolicyInspector::new(policy))
+            .modify_tx_env(|tx| tx.value = U256::from(1))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        evm.transact().unwrap();
+
+        let violations = evm.context.external.violations();
+        let kinds: Vec<_> = violations.iter().map(|v| v.kind).collect();
+        let callee = Address::with_last_byte(0xbb);
+        assert_eq!(
+            kinds,
+            [
+                PolicyViolationKind::ForbiddenOpcode {
+                    opcode: opcode::TIMESTAMP
+                },
+                PolicyViolationKind::StorageAccess {
+                    slot: U256::from(9)
+                },
+                PolicyViolationKind::ValueTransfer { target: callee },
+                PolicyViolationKind::CallDepth { depth: 1 },
+            ]
+        );
+        assert!(violations.iter().all(|v| v.contract == Address::ZERO));
+    }
+}
@@ -0,0 +1,326 @@
+//! Wall-clock time profiler of opcodes, contracts and precompiles.
+
+use crate::{
+    interpreter::{opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter},
+    primitives::{Address, HashMap},
+    Database, EvmContext, Inspector,
+};
+use std::{
+    fmt::Write,
+    string::String,
+    time::{Duration, Instant},
+    vec::Vec,
+};
+
+/// Number of executed instructions between two timestamps of [ProfilerInspector::default].
+pub const DEFAULT_PROFILER_BATCH: usize = 64;
+
+/// Executions and time spent in an opcode, a contract or a precompile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProfileEntry {
+    /// Number of executions.
+    pub count: u64,
+    /// Wall-clock time of the executions.
+    pub time: Duration,
+}
+
+impl ProfileEntry {
+    fn add(&mut self, count: u64, time: Duration) {
+        self.count += count;
+        self.time += time;
+    }
+}
+
+/// Last element of a folded stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Leaf {
+    Opcode(u8),
+    Precompile(Address),
+}
+
+/// [Inspector] measuring the wall-clock time spent per opcode, per contract and per precompile.
+///
+/// Reading the clock at every instruction would cost more than most instructions, so the clock
+/// is read once per batch of instructions and the elapsed time is split evenly between them.
+/// Precompiles are timed individually and their time is not attributed to the calling
+/// instruction. Times are only meaningful over many executions.
+///
+/// [Self::folded_stacks] returns the times in the folded stack format of flamegraph tools.
+#[derive(Clone, Debug)]
+pub struct ProfilerInspector {
+    batch_size: usize,
+    /// Instructions executed since the last timestamp, with the path of their frame.
+    batch: Vec<(u8, usize)>,
+    last_timestamp: Option<Instant>,
+    /// Start of the running precompile.
+    precompile: Option<(Address, Instant)>,
+    /// Addresses of the frames of the call stack, outermost first.
+    stack: Vec<Address>,
+    /// Index of the path of [Self::stack] in [Self::paths], if known.
+    current_path: Option<usize>,
+    paths: Vec<Vec<Address>>,
+    path_ids: HashMap<Vec<Address>, usize>,
+    opcodes: Vec<ProfileEntry>,
+    contracts: HashMap<Address, ProfileEntry>,
+    precompiles: HashMap<Address, ProfileEntry>,
+    folded: HashMap<(usize, Leaf), Duration>,
+}
+
+impl Default for ProfilerInspector {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROFILER_BATCH)
+    }
+}
+
+impl ProfilerInspector {
+    /// Creates a profiler reading the clock every `batch_size` instructions.
+    pub fn new(batch_size: usize) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            batch: Vec::with_capacity(batch_size),
+            last_timestamp: None,
+            precompile: None,
+            stack: Vec::new(),
+            current_path: None,
+            paths: Vec::new(),
+            path_ids: HashMap::new(),
+            opcodes: vec![ProfileEntry::default(); 256],
+            contracts: HashMap::new(),
+            precompiles: HashMap::new(),
+            folded: HashMap::new(),
+        }
+    }
+
+    /// Returns the executed opcodes and their profile.
+    pub fn opcodes(&self) -> impl Iterator<Item = (u8, &ProfileEntry)> {
+        self.opcodes
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.count > 0)
+            .map(|(opcode, entry)| (opcode as u8, entry))
+    }
+
+    /// Returns the profile of the instructions executed by every contract.
+    ///
+    /// The count is the number of executed instructions.
+    pub fn contracts(&self) -> &HashMap<Address, ProfileEntry> {
+        &self.contracts
+    }
+
+    /// Returns the profile of the called precompiles.
+    pub fn precompiles(&self) -> &HashMap<Address, ProfileEntry> {
+        &self.precompiles
+    }
+
+    /// Returns the times in nanoseconds in the folded stack format, one sorted line per stack of
+    /// contract addresses ending with an opcode or a precompile.
+    pub fn folded_stacks(&self) -> String {
+        let mut lines: Vec<_> = self
+            .folded
+            .iter()
+            .map(|((path, leaf), time)| {
+                let mut line = String::new();
+                for address in &self.paths[*path] {
+                    let _ = write!(line, "{address};");
+                }
+                let _ = match leaf {
+                    Leaf::Opcode(op) => {
+                        let name = opcode::OPCODE_JUMPMAP[*op as usize].unwrap_or("UNKNOWN");
+                        write!(line, "{name}")
+                    }
+                    Leaf::Precompile(address) => write!(line, "precompile {address}"),
+                };
+                let _ = write!(line, " {}", time.as_nanos());
+                line
+            })
+            .collect();
+        lines.sort_unstable();
+        lines.iter().fold(String::new(), |mut out, line| {
+            out.push_str(line);
+            out.push('\n');
+            out
+        })
+    }
+
+    /// Returns the index of the path of the current call stack.
+    fn path(&mut self) -> usize {
+        if let Some(path) = self.current_path {
+            return path;
+        }
+        let path = match self.path_ids.get(&self.stack) {
+            Some(path) => *path,
+            None => {
+                let path = self.paths.len();
+                self.paths.push(self.stack.clone());
+                self.path_ids.insert(self.stack.clone(), path);
+                path
+            }
+        };
+        self.current_path = Some(path);
+        path
+    }
+
+    /// Flushes the batch and stops the clock until the next transaction.
+    fn end_transaction(&mut self, now: Instant) {
+        self.flush(now);
+        self.last_timestamp = None;
+        self.stack.clear();
+        self.current_path = None;
+    }
+
+    /// Splits the time elapsed since the last timestamp between the batched instructions.
+    fn flush(&mut self, now: Instant) {
+        let Some(last) = self.last_timestamp.replace(now) else {
+            return;
+        };
+        if self.batch.is_empty() {
+            return;
+        }
+        let share = now.duration_since(last) / self.batch.len() as u32;
+        for (op, path) in self.batch.drain(..) {
+            self.opcodes[op as usize].time += share;
+            if let Some(address) = self.paths[path].last() {
+                self.contracts.entry(*address).or_default().time += share;
+            }
+            *self.folded.entry((path, Leaf::Opcode(op))).or_default() += share;
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ProfilerInspector {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        let depth = context.journaled_state.depth() as usize;
+        let address = interp.contract.address;
+        if self.stack.len() != depth || self.stack.last() != Some(&address) {
+            self.stack.truncate(depth.saturating_sub(1));
+            self.stack.push(address);
+            self.current_path = None;
+        }
+        let path = self.path();
+        let op = interp.current_opcode();
+        self.opcodes[op as usize].count += 1;
+        self.contracts.entry(address).or_default().count += 1;
+
+        if self.last_timestamp.is_none() {
+            self.last_timestamp = Some(Instant::now());
+        }
+        self.batch.push((op, path));
+        if self.batch.len() >= self.batch_size {
+            self.flush(Instant::now());
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        if context.precompiles.contains_key(&inputs.contract) {
+            // Close the batch so that the precompile is not attributed to the instructions.
+            let now = Instant::now();
+            self.flush(now);
+            self.precompile = Some((inputs.contract, now));
+        }
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        let now = Instant::now();
+        if let Some((address, start)) = self.precompile.take() {
+            let time = now.duration_since(start);
+            self.precompiles.entry(address).or_default().add(1, time);
+            let path = self.path();
+            *self
+                .folded
+                .entry((path, Leaf::Precompile(address)))
+                .or_default() += time;
+            if self.last_timestamp.is_some() {
+                self.last_timestamp = Some(now);
+            }
+        }
+        if context.journaled_state.depth() == 0 {
+            self.end_transaction(now);
+        }
+        outcome
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        if context.journaled_state.depth() == 0 {
+            self.end_transaction(Instant::now());
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector_handle_register,
+        primitives::{Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn profile_opcodes_and_precompiles() {
+        // STATICCALL(gas, identity, 0, 0, 0, 0) POP STOP
+        let code = Bytes::from(vec![
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH1,
+            0x04,
+            opcode::GAS,
+            opcode::STATICCALL,
+            opcode::POP,
+            opcode::STOP,
+        ]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(ProfilerInspector::new(4))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        let profiler = evm.into_context().external;
+        let counts: Vec<_> = profiler
+            .opcodes()
+            .map(|(op, entry)| (op, entry.count))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                (opcode::STOP, 1),
+                (opcode::POP, 1),
+                (opcode::GAS, 1),
+                (opcode::PUSH0, 4),
+                (opcode::PUSH1, 1),
+                (opcode::STATICCALL, 1),
+            ]
+        );
+        assert_eq!(profiler.contracts()[&Address::ZERO].count, 9);
+        assert_eq!(profiler.precompiles()[&Address::with_last_byte(4)].count, 1);
+
+        let folded = profiler.folded_stacks();
+        let stacks: Vec<_> = folded
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0)
+            .collect();
+        let frame = Address::ZERO.to_string();
+        assert!(stacks.contains(&format!("{frame};STATICCALL").as_str()));
+        assert!(
+            stacks.contains(&format!("{frame};precompile {}", Address::with_last_byte(4)).as_str())
+        );
+        assert_eq!(stacks.len(), 7);
+    }
+}
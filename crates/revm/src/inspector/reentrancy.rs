@@ -0,0 +1,257 @@
+//! Inspector detecting reentrant calls that write storage.
+
+use crate::{
+    interpreter::{CallInputs, CallOutcome, CreateInputs, CreateOutcome},
+    primitives::{db::Database, Address, U256},
+    EvmContext, Inspector, StorageAccess,
+};
+use std::vec::Vec;
+
+/// Contract re-entered while one of its frames was executing, with storage written around the
+/// reentrant call.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reentrancy {
+    /// Re-entered contract, the owner of the storage of the frames.
+    pub contract: Address,
+    /// Depth of the re-entered frame, starting at 1.
+    pub outer_depth: usize,
+    /// Depth of the reentrant frame.
+    pub inner_depth: usize,
+    /// Storage contexts of the frames from the re-entered frame to the reentrant one, both
+    /// included.
+    pub path: Vec<Address>,
+    /// Slots of the contract written by the reentrant frame and its sub calls.
+    pub inner_writes: Vec<U256>,
+    /// Slots of the contract written by the re-entered frame after the reentrant frame returned.
+    pub outer_writes: Vec<U256>,
+}
+
+/// [Inspector] that reports contracts re-entered through another contract, with storage written
+/// by the reentrant frame or by the re-entered frame after it.
+///
+/// Frames execute in the storage context of their target, so a delegate call into a library
+/// counts as the contract that delegated. Direct calls of a contract to itself are not
+/// reentrancy. Reentrancies whose frames revert are discarded, as are those that write no
+/// storage.
+#[derive(Clone, Debug, Default)]
+pub struct ReentrancyInspector {
+    reentrancies: Vec<Reentrancy>,
+    /// Storage contexts of the currently executing frames.
+    frames: Vec<Address>,
+    /// Reentrancies whose re-entered frame is still executing, with whether the reentrant frame
+    /// returned.
+    open: Vec<(Reentrancy, bool)>,
+}
+
+impl ReentrancyInspector {
+    /// Returns the detected reentrancies, in the order their re-entered frames ended.
+    pub fn reentrancies(&self) -> &[Reentrancy] {
+        &self.reentrancies
+    }
+
+    /// Consumes the inspector and returns the detected reentrancies.
+    pub fn into_reentrancies(self) -> Vec<Reentrancy> {
+        self.reentrancies
+    }
+
+    /// Clears the detected reentrancies.
+    pub fn clear(&mut self) {
+        self.reentrancies.clear();
+        self.frames.clear();
+        self.open.clear();
+    }
+
+    fn enter_frame(&mut self, contract: Address) {
+        let depth = self.frames.len();
+        // The closest executing frame of the contract, if it is not the caller.
+        let outer = self
+            .frames
+            .iter()
+            .rposition(|frame| *frame == contract)
+            .filter(|outer| outer + 1 < depth);
+        self.frames.push(contract);
+        if let Some(outer) = outer {
+            let reentrancy = Reentrancy {
+                contract,
+                outer_depth: outer + 1,
+                inner_depth: depth + 1,
+                path: self.frames[outer..].to_vec(),
+                inner_writes: Vec::new(),
+                outer_writes: Vec::new(),
+            };
+            self.open.push((reentrancy, false));
+        }
+    }
+
+    fn end_frame(&mut self, success: bool) {
+        let depth = self.frames.len();
+        self.frames.pop();
+        if !success {
+            self.open
+                .retain(|(reentrancy, _)| reentrancy.inner_depth < depth);
+        }
+        let mut index = 0;
+        while index < self.open.len() {
+            let (reentrancy, returned) = &mut self.open[index];
+            if reentrancy.inner_depth == depth {
+                *returned = true;
+            }
+            if reentrancy.outer_depth == depth {
+                let (reentrancy, _) = self.open.remove(index);
+                if !reentrancy.inner_writes.is_empty() || !reentrancy.outer_writes.is_empty() {
+                    self.reentrancies.push(reentrancy);
+                }
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ReentrancyInspector {
+    fn sstore(&mut self, _context: &mut EvmContext<DB>, access: &StorageAccess) {
+        let depth = self.frames.len();
+        for (reentrancy, returned) in &mut self.open {
+            if reentrancy.contract != access.address {
+                continue;
+            }
+            let writes = if !*returned && depth >= reentrancy.inner_depth {
+                &mut reentrancy.inner_writes
+            } else if *returned && depth == reentrancy.outer_depth {
+                &mut reentrancy.outer_writes
+            } else {
+                continue;
+            };
+            if !writes.contains(&access.key) {
+                writes.push(access.key);
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        self.enter_frame(inputs.context.address);
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.end_frame(outcome.instruction_result().is_ok());
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        let nonce = context
+            .journaled_state
+            .state
+            .get(&inputs.caller)
+            .map_or(0, |account| account.info.nonce);
+        self.enter_frame(inputs.created_address(nonce));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.end_frame(outcome.instruction_result().is_ok());
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector::inspector_handle_register,
+        interpreter::opcode,
+        primitives::{address, AccountInfo, Bytecode, Bytes, TransactTo},
+        Evm, InMemoryDB,
+    };
+
+    /// Pushes the arguments of `CALL(gas, target, 0, 0, 0, 0, 0)` and the call.
+    fn call(code: &mut Vec<u8>, target: Address) {
+        code.extend_from_slice(&[opcode::PUSH0; 5]);
+        code.push(opcode::PUSH20);
+        code.extend_from_slice(target.as_slice());
+        code.extend_from_slice(&[opcode::GAS, opcode::CALL, opcode::POP]);
+    }
+
+    fn reentrancies(to: Address) -> Vec<Reentrancy> {
+        let vault = address!("0000000000000000000000000000000000000aa1");
+        let attacker = address!("0000000000000000000000000000000000000bb2");
+
+        // if CALLER == attacker { SSTORE(1, 1) } else { CALL(attacker) SSTORE(0, 1) }
+        let mut vault_code = vec![opcode::CALLER, opcode::PUSH20];
+        vault_code.extend_from_slice(attacker.as_slice());
+        vault_code.extend_from_slice(&[opcode::EQ, opcode::PUSH1, 0, opcode::JUMPI]);
+        let jump = vault_code.len() - 2;
+        call(&mut vault_code, attacker);
+        vault_code.extend_from_slice(&[opcode::PUSH1, 1, opcode::PUSH0, opcode::SSTORE]);
+        vault_code.push(opcode::STOP);
+        let reentered = vault_code.len();
+        vault_code[jump] = reentered as u8;
+        vault_code.extend_from_slice(&[opcode::JUMPDEST, opcode::PUSH1, 1, opcode::PUSH1, 1]);
+        vault_code.extend_from_slice(&[opcode::SSTORE, opcode::STOP]);
+
+        // CALL(vault)
+        let mut attacker_code = Vec::new();
+        call(&mut attacker_code, vault);
+        attacker_code.push(opcode::STOP);
+
+        let mut db = InMemoryDB::default();
+        for (address, code) in [(vault, vault_code), (attacker, attacker_code)] {
+            let code = Bytecode::new_raw(Bytes::from(code));
+            db.insert_account_info(
+                address,
+                AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+            );
+        }
+
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .with_external_context(ReentrancyInspector::default())
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::Call(to);
+                tx.gas_limit = 200_000;
+            })
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+        evm.into_context().external.into_reentrancies()
+    }
+
+    #[test]
+    fn detect_reentrancy() {
+        let vault = address!("0000000000000000000000000000000000000aa1");
+        let attacker = address!("0000000000000000000000000000000000000bb2");
+        assert_eq!(
+            reentrancies(vault),
+            [Reentrancy {
+                contract: vault,
+                outer_depth: 1,
+                inner_depth: 3,
+                path: vec![vault, attacker, vault],
+                inner_writes: vec![U256::from(1)],
+                outer_writes: vec![U256::ZERO],
+            }]
+        );
+
+        // The vault is called by the attacker without being re-entered.
+        assert!(reentrancies(attacker).is_empty());
+    }
+}
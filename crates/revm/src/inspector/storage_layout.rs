@@ -0,0 +1,293 @@
+//! Storage layouts of contracts, used to name and decode storage slots in traces.
+//!
+//! The layout of a contract is the `storageLayout` output of solc, also written by Foundry in the
+//! artifacts of the contracts. Slots of variables declared in the contract are annotated with the
+//! name of the variables, and the values of variables packed into a slot are extracted. Slots at
+//! hashed keys, the entries of mappings and dynamic arrays, are not part of the layout.
+
+use crate::primitives::{hex, Address, U256};
+use std::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Variable stored in a slot of a [StorageLayout].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageVariable {
+    /// Name of the variable, members of structs are named `variable.member`.
+    pub label: String,
+    /// Solidity type of the variable, for example `uint128` or `address`.
+    pub type_label: String,
+    /// Slot holding the variable.
+    pub slot: U256,
+    /// Offset of the variable in the slot, in bytes from the least significant byte.
+    pub offset: usize,
+    /// Size of the variable, in bytes.
+    pub size: usize,
+}
+
+impl StorageVariable {
+    /// Extracts the value of the variable from the word stored in its slot.
+    pub fn decode(&self, word: U256) -> U256 {
+        let bits = self.size.min(32) * 8;
+        let value = word >> (self.offset * 8);
+        if bits == U256::BITS {
+            value
+        } else {
+            value & ((U256::from(1) << bits) - U256::from(1))
+        }
+    }
+
+    /// Renders the value of the variable in the word stored in its slot.
+    ///
+    /// Addresses, booleans and integers are rendered as such, other types as hex.
+    pub fn format(&self, word: U256) -> String {
+        let value = self.decode(word);
+        let type_label = self.type_label.as_str();
+        if type_label == "address" || type_label.starts_with("contract ") {
+            Address::from_word(value.to_be_bytes::<32>().into()).to_string()
+        } else if type_label == "bool" {
+            (!value.is_zero()).to_string()
+        } else if type_label.starts_with("uint") || type_label.starts_with("enum ") {
+            value.to_string()
+        } else if type_label.starts_with("int") {
+            let bits = self.size.min(32) * 8;
+            if value.bit(bits - 1) {
+                let magnitude = if bits == U256::BITS {
+                    value.wrapping_neg()
+                } else {
+                    (U256::from(1) << bits) - value
+                };
+                format!("-{magnitude}")
+            } else {
+                value.to_string()
+            }
+        } else {
+            let bytes = value.to_be_bytes::<32>();
+            hex::encode_prefixed(&bytes[32 - self.size.min(32)..])
+        }
+    }
+}
+
+/// Storage layout of a contract, see the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageLayout {
+    /// Variables, ordered by slot and offset.
+    variables: Vec<StorageVariable>,
+}
+
+impl StorageLayout {
+    /// Creates a layout from its variables.
+    pub fn new(mut variables: Vec<StorageVariable>) -> Self {
+        variables.sort_by(|a, b| (a.slot, a.offset).cmp(&(b.slot, b.offset)));
+        Self { variables }
+    }
+
+    /// Returns the variables, ordered by slot and offset.
+    pub fn variables(&self) -> &[StorageVariable] {
+        &self.variables
+    }
+
+    /// Returns the variables stored in `slot`, ordered by offset.
+    pub fn variables_at(&self, slot: U256) -> &[StorageVariable] {
+        let start = self
+            .variables
+            .partition_point(|variable| variable.slot < slot);
+        let end = self
+            .variables
+            .partition_point(|variable| variable.slot <= slot);
+        &self.variables[start..end]
+    }
+
+    /// Renders the variables stored in `slot` with their values in `word`, for example
+    /// `owner: address = 0x…, paused: bool = true`.
+    ///
+    /// Returns `None` if no variable of the layout is stored in `slot`.
+    pub fn describe(&self, slot: U256, word: U256) -> Option<String> {
+        let variables = self.variables_at(slot);
+        if variables.is_empty() {
+            return None;
+        }
+        let described: Vec<_> = variables
+            .iter()
+            .map(|variable| {
+                format!(
+                    "{}: {} = {}",
+                    variable.label,
+                    variable.type_label,
+                    variable.format(word)
+                )
+            })
+            .collect();
+        Some(described.join(", "))
+    }
+}
+
+#[cfg(feature = "serde-json")]
+mod json {
+    use super::{StorageLayout, StorageVariable};
+    use crate::primitives::U256;
+    use std::{collections::BTreeMap, format, string::String, vec::Vec};
+
+    #[derive(serde::Deserialize)]
+    struct LayoutJson {
+        storage: Vec<EntryJson>,
+        #[serde(default)]
+        types: BTreeMap<String, TypeJson>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct EntryJson {
+        label: String,
+        offset: usize,
+        slot: String,
+        #[serde(rename = "type")]
+        ty: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TypeJson {
+        encoding: String,
+        label: String,
+        number_of_bytes: String,
+        #[serde(default)]
+        members: Option<Vec<EntryJson>>,
+    }
+
+    impl StorageLayout {
+        /// Parses the `storageLayout` output of solc, the object holding the `storage` and
+        /// `types` fields.
+        ///
+        /// Members of structs are flattened into variables of their own. Other variables
+        /// spanning several slots, such as static arrays, are split into a variable per slot,
+        /// named `variable[slot index]`.
+        pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+            let layout: LayoutJson = serde_json::from_str(json)?;
+            let mut variables = Vec::new();
+            flatten(
+                &layout.storage,
+                &layout.types,
+                U256::ZERO,
+                "",
+                &mut variables,
+            )?;
+            Ok(Self::new(variables))
+        }
+    }
+
+    fn flatten(
+        entries: &[EntryJson],
+        types: &BTreeMap<String, TypeJson>,
+        base_slot: U256,
+        prefix: &str,
+        variables: &mut Vec<StorageVariable>,
+    ) -> Result<(), serde_json::Error> {
+        let invalid = |field: &str, value: &str| {
+            <serde_json::Error as serde::de::Error>::custom(format!("invalid {field} `{value}`"))
+        };
+        for entry in entries {
+            let slot = base_slot
+                + entry
+                    .slot
+                    .parse::<U256>()
+                    .map_err(|_| invalid("slot", &entry.slot))?;
+            let label = format!("{prefix}{}", entry.label);
+            let Some(ty) = types.get(&entry.ty) else {
+                variables.push(StorageVariable {
+                    label,
+                    type_label: entry.ty.clone(),
+                    slot,
+                    offset: entry.offset,
+                    size: 32,
+                });
+                continue;
+            };
+            let size = ty
+                .number_of_bytes
+                .parse::<usize>()
+                .map_err(|_| invalid("numberOfBytes", &ty.number_of_bytes))?;
+            match &ty.members {
+                Some(members) if ty.encoding == "inplace" => {
+                    flatten(members, types, slot, &format!("{label}."), variables)?;
+                }
+                _ if ty.encoding == "inplace" && size > 32 => {
+                    for index in 0..size.div_ceil(32) {
+                        variables.push(StorageVariable {
+                            label: format!("{label}[{index}]"),
+                            type_label: ty.label.clone(),
+                            slot: slot + U256::from(index),
+                            offset: 0,
+                            size: 32,
+                        });
+                    }
+                }
+                _ => variables.push(StorageVariable {
+                    label,
+                    type_label: ty.label.clone(),
+                    slot,
+                    offset: entry.offset,
+                    size: size.min(32),
+                }),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde-json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_packed_slot() {
+        let layout = StorageLayout::from_json(
+            r#"{
+                "storage": [
+                    {"label": "owner", "offset": 0, "slot": "0", "type": "t_address"},
+                    {"label": "paused", "offset": 20, "slot": "0", "type": "t_bool"},
+                    {"label": "delta", "offset": 21, "slot": "0", "type": "t_int8"},
+                    {"label": "config", "offset": 0, "slot": "1", "type": "t_struct(Config)"},
+                    {"label": "balances", "offset": 0, "slot": "3", "type": "t_mapping(t_address,t_uint256)"}
+                ],
+                "types": {
+                    "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+                    "t_bool": {"encoding": "inplace", "label": "bool", "numberOfBytes": "1"},
+                    "t_int8": {"encoding": "inplace", "label": "int8", "numberOfBytes": "1"},
+                    "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"},
+                    "t_mapping(t_address,t_uint256)": {"encoding": "mapping", "label": "mapping(address => uint256)", "numberOfBytes": "32"},
+                    "t_struct(Config)": {
+                        "encoding": "inplace",
+                        "label": "struct Config",
+                        "numberOfBytes": "64",
+                        "members": [
+                            {"label": "fee", "offset": 0, "slot": "0", "type": "t_uint256"},
+                            {"label": "admin", "offset": 0, "slot": "1", "type": "t_address"}
+                        ]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(layout.variables().len(), 6);
+
+        // 0xff (delta = -1), 0x01 (paused), owner = 0x…2a.
+        let word = (U256::from(0xff01) << 160) | U256::from(0x2a);
+        assert_eq!(
+            layout.describe(U256::ZERO, word).unwrap(),
+            format!(
+                "owner: address = {}, paused: bool = true, delta: int8 = -1",
+                Address::with_last_byte(0x2a)
+            )
+        );
+        let admin = &layout.variables_at(U256::from(2))[0];
+        assert_eq!(admin.label, "config.admin");
+        assert_eq!(
+            layout.describe(U256::from(1), U256::from(30)).unwrap(),
+            "config.fee: uint256 = 30"
+        );
+        assert!(layout.describe(U256::from(4), U256::ZERO).is_none());
+    }
+}
@@ -0,0 +1,164 @@
+//! Filters applied by [TracerInspector](super::tracer::TracerInspector) while a trace is collected.
+//!
+//! Frames and instructions left out by the filter never reach the [Tracer](super::tracer::Tracer), so
+//! targeted queries such as "every `SSTORE` of a contract" do not pay for recording the rest of
+//! the transaction. Filters are built with the methods of [TraceFilter] or parsed from a list of
+//! comma separated clauses, for example `to=0x…, op=SSTORE, depth<3, !static`:
+//!
+//! - `to=<address>`: only frames calling the address, repeated clauses match any of them.
+//! - `op=<name>`: only instructions with the opcode, repeated clauses match any of them.
+//! - `depth<<n>`: only frames at a depth below `n`, the transaction is at depth 0.
+//! - `!static`: no static frames, which includes the frames they make.
+//!
+//! Instructions and logs are kept only if the frame executing them is kept.
+
+use super::tracer::FrameInput;
+use crate::{interpreter::opcode::OPCODE_JUMPMAP, primitives::Address};
+use core::{fmt, str::FromStr};
+use std::{string::String, vec::Vec};
+
+/// Filter of the frames and instructions passed to a tracer, see the
+/// [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceFilter {
+    /// Addresses of the called accounts of the kept frames, all frames if empty.
+    pub to: Vec<Address>,
+    /// Opcodes of the kept instructions, all instructions if empty.
+    pub opcodes: Vec<u8>,
+    /// Depth of the first frames left out.
+    pub max_depth: Option<usize>,
+    /// Whether static frames are left out.
+    pub skip_static: bool,
+}
+
+impl TraceFilter {
+    /// Creates a filter keeping everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps frames calling `address`, in addition to the addresses already kept.
+    pub fn to(mut self, address: Address) -> Self {
+        self.to.push(address);
+        self
+    }
+
+    /// Keeps instructions with `opcode`, in addition to the opcodes already kept.
+    pub fn opcode(mut self, opcode: u8) -> Self {
+        self.opcodes.push(opcode);
+        self
+    }
+
+    /// Keeps frames at a depth below `depth`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Leaves static frames out.
+    pub fn skip_static(mut self) -> Self {
+        self.skip_static = true;
+        self
+    }
+
+    /// Returns `true` if the filter keeps everything.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns whether the frame is kept.
+    pub fn matches_frame(&self, frame: &FrameInput) -> bool {
+        self.max_depth.map_or(true, |max| frame.depth < max)
+            && !(self.skip_static && frame.is_static)
+            && (self.to.is_empty() || frame.to.is_some_and(|to| self.to.contains(&to)))
+    }
+
+    /// Returns whether instructions with `opcode` are kept, in kept frames.
+    pub fn matches_opcode(&self, opcode: u8) -> bool {
+        self.opcodes.is_empty() || self.opcodes.contains(&opcode)
+    }
+}
+
+/// Error returned when parsing a [TraceFilter] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTraceFilterError {
+    /// The clause that is not understood.
+    pub clause: String,
+}
+
+impl fmt::Display for ParseTraceFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid trace filter clause `{}`", self.clause)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseTraceFilterError {}
+
+impl FromStr for TraceFilter {
+    type Err = ParseTraceFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::new();
+        for clause in s
+            .split(',')
+            .map(str::trim)
+            .filter(|clause| !clause.is_empty())
+        {
+            let invalid = || ParseTraceFilterError {
+                clause: clause.into(),
+            };
+            if clause == "!static" {
+                filter.skip_static = true;
+            } else if let Some(depth) = clause.strip_prefix("depth<") {
+                filter.max_depth = Some(depth.trim().parse().map_err(|_| invalid())?);
+            } else if let Some(address) = clause.strip_prefix("to=") {
+                filter
+                    .to
+                    .push(address.trim().parse().map_err(|_| invalid())?);
+            } else if let Some(name) = clause.strip_prefix("op=") {
+                let name = name.trim();
+                let opcode = OPCODE_JUMPMAP
+                    .iter()
+                    .position(|op| op.is_some_and(|op| op.eq_ignore_ascii_case(name)))
+                    .ok_or_else(invalid)?;
+                filter.opcodes.push(opcode as u8);
+            } else {
+                return Err(invalid());
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::opcode;
+
+    #[test]
+    fn parse_filter() {
+        let target = Address::with_last_byte(0x2a);
+        let filter: TraceFilter = format!("to={target}, op=sstore, op=CALL, depth<3, !static")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            filter,
+            TraceFilter::new()
+                .to(target)
+                .opcode(opcode::SSTORE)
+                .opcode(opcode::CALL)
+                .max_depth(3)
+                .skip_static()
+        );
+        assert!(filter.matches_opcode(opcode::SSTORE));
+        assert!(!filter.matches_opcode(opcode::SLOAD));
+        assert!("".parse::<TraceFilter>().unwrap().is_empty());
+        assert_eq!(
+            "op=NOPE".parse::<TraceFilter>().unwrap_err().clause,
+            "op=NOPE"
+        );
+        assert!("to=0x12".parse::<TraceFilter>().is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! Text rendering of call traces.
+//!
+//! [TraceFormatter] renders the traces recorded by the
+//! [ParityTracer](crate::inspectors::ParityTracer) as an indented tree, one line per frame
+//! followed by its subtraces and its result:
+//!
+//! ```text
+//! [24000] Router::swap(0x0000…){value: 1}
+//! ├─ [2300] WETH::deposit()
+//! │  └─ ← 0x
+//! └─ ← 0x0000000000000000000000000000000000000000000000000000000000000001
+//! ```
+//!
+//! Storage accesses and state diffs are rendered with the names and decoded values of the
+//! variables of contracts with a [StorageLayout]:
+//!
+//! ```text
+//! Router
+//!   nonce: 0 → 1
+//!   owner: 0x0000…0001 → 0x0000…0002
+//! ```
+use super::{
+    parity::{AccountDiff, Action, CallType, Delta, TraceOutput, TransactionTrace},
+    storage_layout::StorageLayout,
+};
+use crate::{
+    primitives::{hex, Address, Bytes, HashMap, B256, U256},
+    LabelRegistry, StorageAccess,
+};
+use core::fmt::Write;
+use std::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Renders call traces as a text tree, see the [module documentation](self).
+///
+/// Addresses are shown with their label in the [LabelRegistry] if they have one, and the
+/// selector of the input of calls is replaced by its function signature if it is known.
+#[derive(Clone, Debug, Default)]
+pub struct TraceFormatter {
+    labels: LabelRegistry,
+    signatures: HashMap<[u8; 4], String>,
+    layouts: HashMap<Address, StorageLayout>,
+}
+
+impl TraceFormatter {
+    /// Creates a formatter without labels and signatures.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `labels` for the addresses, usually the registry of the
+    /// [context](crate::InnerEvmContext::labels) the traces were recorded with.
+    pub fn with_labels(mut self, labels: LabelRegistry) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Labels `address` with `label`.
+    pub fn with_label(mut self, address: Address, label: impl Into<String>) -> Self {
+        self.labels.insert(address, label);
+        self
+    }
+
+    /// Decodes `selector` as the function `signature`, for example `transfer(address,uint256)`.
+    ///
+    /// Only the name of the function is shown, arguments are shown as hex.
+    pub fn with_signature(mut self, selector: [u8; 4], signature: impl Into<String>) -> Self {
+        self.signatures.insert(selector, signature.into());
+        self
+    }
+
+    /// Decodes the storage of the contract at `address` with `layout`.
+    pub fn with_storage_layout(mut self, address: Address, layout: StorageLayout) -> Self {
+        self.layouts.insert(address, layout);
+        self
+    }
+
+    /// Returns the label of `address`, or the checksummed address if it has none.
+    pub fn label(&self, address: Address) -> String {
+        self.labels.label(address)
+    }
+
+    /// Renders the traces of a transaction, in the order produced by the
+    /// [ParityTracer](crate::inspectors::ParityTracer).
+    pub fn format(&self, traces: &[TransactionTrace]) -> String {
+        let mut out = String::new();
+        let mut next = 0;
+        while next < traces.len() {
+            self.write_trace(&mut out, traces, &mut next, None);
+        }
+        out
+    }
+
+    /// Renders a storage access, for example `Token::owner: address = 0x…` for a read and
+    /// `Token::totalSupply: 1 → 2` for a write.
+    ///
+    /// Only the variables changed by a write are shown, slots outside of the storage layout of
+    /// the contract are shown as hex.
+    pub fn format_storage_access(&self, access: &StorageAccess) -> String {
+        let slot = self.format_slot(
+            access.address,
+            access.key,
+            Some(access.old_value),
+            Some(access.new_value),
+        );
+        format!("{}::{slot}", self.label(access.address))
+    }
+
+    /// Renders a state diff computed by [state_diff](crate::inspectors::state_diff), one line per
+    /// changed field below the label of each account.
+    pub fn format_state_diff(&self, diff: &BTreeMap<Address, AccountDiff>) -> String {
+        let mut out = String::new();
+        for (address, account) in diff {
+            let _ = writeln!(out, "{}", self.label(*address));
+            if let Some(line) = delta_line(&account.balance, |balance| balance.to_string()) {
+                let _ = writeln!(out, "  balance: {line}");
+            }
+            if let Some(line) = delta_line(&account.nonce, |nonce| nonce.to_string()) {
+                let _ = writeln!(out, "  nonce: {line}");
+            }
+            if let Some(line) = delta_line(&account.code, |code| format!("{} bytes", code.len())) {
+                let _ = writeln!(out, "  code: {line}");
+            }
+            for (key, delta) in &account.storage {
+                let word = |value: &B256| U256::from_be_bytes(value.0);
+                let (from, to) = match delta {
+                    Delta::Unchanged => continue,
+                    Delta::Added(to) => (None, Some(word(to))),
+                    Delta::Removed(from) => (Some(word(from)), None),
+                    Delta::Changed { from, to } => (Some(word(from)), Some(word(to))),
+                };
+                let slot = self.format_slot(*address, word(key), from, to);
+                let _ = writeln!(out, "  {slot}");
+            }
+        }
+        out
+    }
+
+    /// Renders the change of a storage slot, a missing value being an absent slot.
+    fn format_slot(
+        &self,
+        address: Address,
+        slot: U256,
+        from: Option<U256>,
+        to: Option<U256>,
+    ) -> String {
+        let variables = self
+            .layouts
+            .get(&address)
+            .map(|layout| layout.variables_at(slot))
+            .unwrap_or_default();
+        let (old, new) = (from.unwrap_or_default(), to.unwrap_or_default());
+        let described: Vec<_> = variables
+            .iter()
+            .filter(|variable| old == new || variable.decode(old) != variable.decode(new))
+            .map(|variable| {
+                if old == new {
+                    format!(
+                        "{}: {} = {}",
+                        variable.label,
+                        variable.type_label,
+                        variable.format(new)
+                    )
+                } else {
+                    let value = |value: Option<U256>| value.map(|word| variable.format(word));
+                    format!("{}: {}", variable.label, change(value(from), value(to)))
+                }
+            })
+            .collect();
+        if !described.is_empty() {
+            return described.join(", ");
+        }
+        if from == to {
+            format!("[{slot:#x}] = {new:#x}")
+        } else {
+            let value = |value: Option<U256>| value.map(|word| format!("{word:#x}"));
+            format!("[{slot:#x}]: {}", change(value(from), value(to)))
+        }
+    }
+
+    /// Writes the trace at `next` and its subtraces, below a parent drawn with `prefix`.
+    ///
+    /// The result line closes every frame, so subtraces are never the last entry of their
+    /// parent.
+    fn write_trace(
+        &self,
+        out: &mut String,
+        traces: &[TransactionTrace],
+        next: &mut usize,
+        prefix: Option<&str>,
+    ) {
+        let trace = &traces[*next];
+        *next += 1;
+
+        let (line_prefix, child_prefix) = match prefix {
+            None => (String::new(), String::new()),
+            Some(prefix) => (format!("{prefix}├─ "), format!("{prefix}│  ")),
+        };
+        let _ = writeln!(out, "{line_prefix}{}", self.action_line(trace));
+
+        for _ in 0..trace.subtraces {
+            if *next >= traces.len() {
+                break;
+            }
+            self.write_trace(out, traces, next, Some(&child_prefix));
+        }
+        if !matches!(trace.action, Action::Selfdestruct(_)) {
+            let _ = writeln!(out, "{child_prefix}└─ ← {}", result_line(trace));
+        }
+    }
+
+    fn action_line(&self, trace: &TransactionTrace) -> String {
+        match &trace.action {
+            Action::Call(call) => {
+                let gas_used = match &trace.result {
+                    Some(TraceOutput::Call(output)) => output.gas_used,
+                    _ => call.gas,
+                };
+                let kind = match call.call_type {
+                    CallType::Call => "",
+                    CallType::CallCode => " [callcode]",
+                    CallType::DelegateCall => " [delegatecall]",
+                    CallType::StaticCall => " [staticcall]",
+                };
+                format!(
+                    "[{gas_used}] {}::{}{}{kind}",
+                    self.label(call.to),
+                    self.function(&call.input),
+                    value_suffix(call.value),
+                )
+            }
+            Action::Create(create) => {
+                let (gas_used, created) = match &trace.result {
+                    Some(TraceOutput::Create(output)) => {
+                        (output.gas_used, self.label(output.address))
+                    }
+                    _ => (create.gas, String::from("<failed>")),
+                };
+                format!("[{gas_used}] → new {created}{}", value_suffix(create.value))
+            }
+            Action::Selfdestruct(selfdestruct) => format!(
+                "selfdestruct {} → {}{}",
+                self.label(selfdestruct.address),
+                self.label(selfdestruct.refund_address),
+                value_suffix(selfdestruct.balance),
+            ),
+        }
+    }
+
+    /// Returns the called function and its arguments.
+    fn function(&self, input: &Bytes) -> String {
+        if input.is_empty() {
+            return String::from("fallback()");
+        }
+        let Some(selector) = input.get(..4) else {
+            return format!("fallback({})", hex::encode_prefixed(input));
+        };
+        let selector: [u8; 4] = selector.try_into().expect("slice has 4 bytes");
+        let name = match self.signatures.get(&selector) {
+            Some(signature) => signature.split('(').next().unwrap_or(signature).to_string(),
+            None => hex::encode_prefixed(selector),
+        };
+        let args = &input[4..];
+        if args.is_empty() {
+            format!("{name}()")
+        } else {
+            format!("{name}({})", hex::encode_prefixed(args))
+        }
+    }
+}
+
+fn value_suffix(value: U256) -> String {
+    if value.is_zero() {
+        String::new()
+    } else {
+        format!("{{value: {value}}}")
+    }
+}
+
+/// Renders a change between two optional values, a missing value being shown as `∅`.
+fn change(from: Option<String>, to: Option<String>) -> String {
+    let value = |value: Option<String>| value.unwrap_or_else(|| String::from("∅"));
+    format!("{} → {}", value(from), value(to))
+}
+
+fn delta_line<T>(delta: &Delta<T>, render: impl Fn(&T) -> String) -> Option<String> {
+    Some(match delta {
+        Delta::Unchanged => return None,
+        Delta::Added(to) => change(None, Some(render(to))),
+        Delta::Removed(from) => change(Some(render(from)), None),
+        Delta::Changed { from, to } => change(Some(render(from)), Some(render(to))),
+    })
+}
+
+fn result_line(trace: &TransactionTrace) -> String {
+    match (&trace.result, &trace.error) {
+        (Some(TraceOutput::Call(output)), _) => hex::encode_prefixed(&output.output),
+        (Some(TraceOutput::Create(output)), _) => format!("{} bytes of code", output.code.len()),
+        (None, Some(error)) => error.clone(),
+        (None, None) => String::from("<no result>"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::parity::{CallAction, CallOutput};
+
+    fn call(
+        to: Address,
+        input: &'static [u8],
+        gas_used: u64,
+        subtraces: usize,
+    ) -> TransactionTrace {
+        TransactionTrace {
+            action: Action::Call(CallAction {
+                from: Address::ZERO,
+                to,
+                value: U256::ZERO,
+                gas: 100_000,
+                input: Bytes::from_static(input),
+                call_type: CallType::Call,
+            }),
+            error: None,
+            result: Some(TraceOutput::Call(CallOutput {
+                gas_used,
+                output: Bytes::new(),
+            })),
+            subtraces,
+            trace_address: vec![],
+        }
+    }
+
+    #[test]
+    fn format_tree() {
+        let router = Address::with_last_byte(1);
+        let weth = Address::with_last_byte(2);
+        let mut root = call(router, &[0x12, 0x34, 0x56, 0x78, 0xff], 24_000, 2);
+        if let Action::Call(call) = &mut root.action {
+            call.value = U256::from(1);
+        }
+        let mut failed = call(weth, &[], 2_300, 0);
+        failed.result = None;
+        failed.error = Some(String::from("Reverted"));
+        let traces = vec![
+            root,
+            call(weth, &[0xd0, 0xe3, 0x0d, 0xb0], 2_300, 0),
+            failed,
+        ];
+
+        let labels = LabelRegistry::new().with_label(weth, "WETH");
+        let formatter = TraceFormatter::new()
+            .with_labels(labels)
+            .with_label(router, "Router")
+            .with_signature([0x12, 0x34, 0x56, 0x78], "swap(bytes)")
+            .with_signature([0xd0, 0xe3, 0x0d, 0xb0], "deposit()");
+        assert_eq!(
+            formatter.format(&traces),
+            "[24000] Router::swap(0xff){value: 1}\n\
+             ├─ [2300] WETH::deposit()\n\
+             │  └─ ← 0x\n\
+             ├─ [100000] WETH::fallback()\n\
+             │  └─ ← Reverted\n\
+             └─ ← 0x\n"
+        );
+    }
+
+    #[test]
+    fn format_storage() {
+        use crate::inspectors::StorageVariable;
+
+        let token = Address::with_last_byte(1);
+        let variable = |label: &str, type_label: &str, offset, size| StorageVariable {
+            label: label.to_string(),
+            type_label: type_label.to_string(),
+            slot: U256::ZERO,
+            offset,
+            size,
+        };
+        let layout = StorageLayout::new(vec![
+            variable("paused", "bool", 20, 1),
+            variable("owner", "address", 0, 20),
+        ]);
+        let formatter = TraceFormatter::new()
+            .with_label(token, "Token")
+            .with_storage_layout(token, layout);
+
+        // Unpausing keeps the owner, only the changed variable is shown.
+        let paused = (U256::from(1) << 160) | U256::from(2);
+        let access = StorageAccess {
+            address: token,
+            key: U256::ZERO,
+            old_value: paused,
+            new_value: U256::from(2),
+            original_value: paused,
+            is_cold: false,
+            gas_cost: 2_900,
+            gas_refund: 0,
+        };
+        assert_eq!(
+            formatter.format_storage_access(&access),
+            "Token::paused: true → false"
+        );
+        let read = StorageAccess {
+            new_value: paused,
+            ..access
+        };
+        assert_eq!(
+            formatter.format_storage_access(&read),
+            format!(
+                "Token::owner: address = {}, paused: bool = true",
+                Address::with_last_byte(2)
+            )
+        );
+
+        let word = |value: u64| B256::from(U256::from(value).to_be_bytes());
+        let diff = BTreeMap::from([(
+            token,
+            AccountDiff {
+                balance: Delta::Unchanged,
+                nonce: Delta::Changed { from: 0, to: 1 },
+                code: Delta::Unchanged,
+                storage: BTreeMap::from([
+                    (word(0), Delta::Added(word(2))),
+                    (word(5), Delta::Removed(word(3))),
+                ]),
+            },
+        )]);
+        assert_eq!(
+            formatter.format_state_diff(&diff),
+            format!(
+                "Token\n  \
+                 nonce: 0 → 1\n  \
+                 owner: ∅ → {}\n  \
+                 [0x5]: 0x3 → ∅\n",
+                Address::with_last_byte(2)
+            )
+        );
+    }
+}
@@ -0,0 +1,688 @@
+//! Tracer plugin interface.
+//!
+//! [Tracer] is a higher level alternative to [Inspector] for user-defined tracers. Hooks receive
+//! decoded frame inputs and results instead of raw interpreter structures, and a
+//! [TracerContext] with read-only access to the environment and the current state. Tracers are
+//! not generic over the database, so they can be compiled separately and used as trait objects.
+//!
+//! A tracer is executed by wrapping it in a [TracerInspector], optionally with a
+//! [TraceFilter] selecting what reaches the tracer.
+
+use super::trace_filter::TraceFilter;
+use crate::{
+    interpreter::{
+        gas, CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, InstructionResult,
+        Interpreter,
+    },
+    primitives::{
+        db::Database, init_code_hash, AccountInfo, Address, Bytecode, Bytes, CreateScheme, Env,
+        Log, B256, KECCAK_EMPTY, U256,
+    },
+    EvmContext, Inspector, LabelRegistry, LogPosition,
+};
+use std::{boxed::Box, vec::Vec};
+
+/// Kind of an execution frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrameKind {
+    /// Message call.
+    Call(CallScheme),
+    /// Contract creation.
+    Create(CreateScheme),
+}
+
+/// Decoded inputs of an execution frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameInput {
+    /// Kind of the frame.
+    pub kind: FrameKind,
+    /// Depth of the frame, `0` for the transaction.
+    pub depth: usize,
+    /// Caller of the frame.
+    pub from: Address,
+    /// Account whose storage is used by the frame. `None` for creations, see
+    /// [FrameResult::created].
+    pub to: Option<Address>,
+    /// Account whose code is executed. `None` for creations.
+    pub code_address: Option<Address>,
+    /// Value of the frame, the apparent value for `DELEGATECALL`.
+    pub value: U256,
+    /// Call data or init code.
+    pub input: Bytes,
+    /// Gas available to the frame.
+    pub gas_limit: u64,
+    /// Whether the frame is static.
+    pub is_static: bool,
+    /// Address the contract is created at, for creations. The address is derived before the
+    /// frame executes, the creation may still fail.
+    pub created_address: Option<Address>,
+    /// Hash of the init code, for creations.
+    pub init_code_hash: Option<B256>,
+}
+
+impl FrameInput {
+    fn from_call(inputs: &CallInputs, depth: usize) -> Self {
+        let value = match inputs.context.scheme {
+            CallScheme::DelegateCall => inputs.context.apparent_value,
+            _ => inputs.transfer.value,
+        };
+        Self {
+            kind: FrameKind::Call(inputs.context.scheme),
+            depth,
+            from: inputs.context.caller,
+            to: Some(inputs.context.address),
+            code_address: Some(inputs.context.code_address),
+            value,
+            input: inputs.input.clone(),
+            gas_limit: inputs.gas_limit,
+            is_static: inputs.is_static,
+            created_address: None,
+            init_code_hash: None,
+        }
+    }
+
+    /// `nonce` is the nonce of the caller before the creation.
+    fn from_create(inputs: &CreateInputs, depth: usize, nonce: u64) -> Self {
+        Self {
+            kind: FrameKind::Create(inputs.scheme),
+            depth,
+            from: inputs.caller,
+            to: None,
+            code_address: None,
+            value: inputs.value,
+            input: inputs.init_code.clone(),
+            gas_limit: inputs.gas_limit,
+            is_static: false,
+            created_address: Some(inputs.created_address(nonce)),
+            init_code_hash: Some(init_code_hash(&inputs.init_code)),
+        }
+    }
+
+    /// Returns `true` if the frame creates a contract, executing [Self::input] as init code.
+    pub fn is_create(&self) -> bool {
+        matches!(self.kind, FrameKind::Create(_))
+    }
+
+    /// Returns the salt of `CREATE2` and `EOFCREATE` frames.
+    pub fn salt(&self) -> Option<U256> {
+        match self.kind {
+            FrameKind::Create(CreateScheme::Create2 { salt })
+            | FrameKind::Create(CreateScheme::EofCreate { salt }) => Some(salt),
+            _ => None,
+        }
+    }
+}
+
+/// Result of an execution frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameResult {
+    /// Depth of the frame, `0` for the transaction.
+    pub depth: usize,
+    /// Result of the frame.
+    pub result: InstructionResult,
+    /// Gas used by the frame.
+    pub gas_used: u64,
+    /// Return data, revert data or deployed code.
+    pub output: Bytes,
+    /// Address of the created contract, for creations.
+    pub created: Option<Address>,
+    /// Size of the deployed code, for successful creations.
+    pub code_size: Option<usize>,
+    /// Gas charged for depositing the deployed code, included in [Self::gas_used], for
+    /// successful creations.
+    pub code_deposit_gas: Option<u64>,
+}
+
+impl FrameResult {
+    /// Returns `true` if the frame succeeded.
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Interpreter state of a single instruction.
+#[derive(Clone, Copy, Debug)]
+pub struct Step<'a> {
+    /// Depth of the frame, `0` for the transaction.
+    pub depth: usize,
+    /// Program counter of the instruction.
+    pub pc: usize,
+    /// Opcode of the instruction.
+    pub opcode: u8,
+    /// Account whose storage is used by the frame.
+    pub address: Address,
+    /// Remaining gas.
+    pub gas_remaining: u64,
+    /// Gas refund counter.
+    pub gas_refunded: i64,
+    /// Stack, top item last.
+    pub stack: &'a [U256],
+    /// Memory of the frame.
+    pub memory: &'a [u8],
+    /// Result of the instruction. Always `Continue` before the instruction is executed.
+    pub result: InstructionResult,
+}
+
+impl<'a> Step<'a> {
+    fn new(interp: &'a Interpreter, depth: usize) -> Self {
+        Self {
+            depth,
+            pc: interp.program_counter(),
+            opcode: interp.current_opcode(),
+            address: interp.contract.address,
+            gas_remaining: interp.gas.remaining(),
+            gas_refunded: interp.gas.refunded(),
+            stack: interp.stack().data(),
+            memory: interp.shared_memory.context_memory(),
+            result: interp.instruction_result,
+        }
+    }
+}
+
+/// Read-only view of the environment and the state during execution.
+///
+/// State reads see the changes made by the transaction so far and do not warm accounts or
+/// storage slots. Missing accounts and database errors are reported as `None`.
+pub struct TracerContext<'a> {
+    inner: &'a mut dyn StateAccess,
+}
+
+impl core::fmt::Debug for TracerContext<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TracerContext").finish_non_exhaustive()
+    }
+}
+
+impl<'a> TracerContext<'a> {
+    /// Returns the environment of the transaction.
+    pub fn env(&self) -> &Env {
+        self.inner.env()
+    }
+
+    /// Returns the account info, `None` if the account does not exist.
+    pub fn account(&mut self, address: Address) -> Option<AccountInfo> {
+        self.inner.account(address)
+    }
+
+    /// Returns the balance of the account.
+    pub fn balance(&mut self, address: Address) -> Option<U256> {
+        self.account(address).map(|info| info.balance)
+    }
+
+    /// Returns the nonce of the account.
+    pub fn nonce(&mut self, address: Address) -> Option<u64> {
+        self.account(address).map(|info| info.nonce)
+    }
+
+    /// Returns the code of the account.
+    pub fn code(&mut self, address: Address) -> Option<Bytecode> {
+        let info = self.account(address)?;
+        match info.code {
+            Some(code) => Some(code),
+            None => self.inner.code_by_hash(info.code_hash),
+        }
+    }
+
+    /// Returns the value of the storage slot.
+    pub fn storage(&mut self, address: Address, index: U256) -> Option<U256> {
+        self.inner.storage(address, index)
+    }
+
+    /// Returns the label of the address in the [LabelRegistry] of the context.
+    pub fn label(&self, address: Address) -> Option<&str> {
+        self.inner.labels()?.get(&address)
+    }
+}
+
+/// State access used by [TracerContext], implemented for [EvmContext].
+trait StateAccess {
+    fn env(&self) -> &Env;
+    fn labels(&self) -> Option<&LabelRegistry>;
+    fn account(&mut self, address: Address) -> Option<AccountInfo>;
+    fn code_by_hash(&mut self, code_hash: B256) -> Option<Bytecode>;
+    fn storage(&mut self, address: Address, index: U256) -> Option<U256>;
+}
+
+impl<DB: Database> StateAccess for EvmContext<DB> {
+    fn env(&self) -> &Env {
+        &self.env
+    }
+
+    fn labels(&self) -> Option<&LabelRegistry> {
+        self.labels.as_ref()
+    }
+
+    fn account(&mut self, address: Address) -> Option<AccountInfo> {
+        match self.journaled_state.state.get(&address) {
+            Some(account) if account.is_loaded_as_not_existing() && !account.is_touched() => None,
+            Some(account) => Some(account.info.clone()),
+            None => self.db.basic(address).ok().flatten(),
+        }
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Option<Bytecode> {
+        if code_hash == KECCAK_EMPTY {
+            return Some(Bytecode::new());
+        }
+        self.db.code_by_hash(code_hash).ok()
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Option<U256> {
+        if let Some(account) = self.journaled_state.state.get(&address) {
+            if let Some(slot) = account.storage.get(&index) {
+                return Some(slot.present_value());
+            }
+            if account.is_created() {
+                return Some(U256::ZERO);
+            }
+        }
+        self.db.storage(address, index).ok()
+    }
+}
+
+/// User-defined tracer.
+///
+/// All hooks have empty default implementations. [Tracer::result] is called once the traced
+/// execution is finished and returns the output of the tracer.
+pub trait Tracer {
+    /// Output of the tracer.
+    type Output;
+
+    /// Called when a call or a creation starts.
+    fn enter_frame(&mut self, frame: &FrameInput, context: &mut TracerContext<'_>) {
+        let _ = frame;
+        let _ = context;
+    }
+
+    /// Called when a call or a creation ends.
+    fn exit_frame(&mut self, result: &FrameResult, context: &mut TracerContext<'_>) {
+        let _ = result;
+        let _ = context;
+    }
+
+    /// Called before an instruction is executed.
+    fn step(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+        let _ = step;
+        let _ = context;
+    }
+
+    /// Called after an instruction is executed. `step.pc` and `step.opcode` still refer to the
+    /// executed instruction.
+    fn step_end(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+        let _ = step;
+        let _ = context;
+    }
+
+    /// Called when a log is emitted.
+    fn log(&mut self, log: &Log, context: &mut TracerContext<'_>) {
+        let _ = log;
+        let _ = context;
+    }
+
+    /// Called when a contract self-destructs, sending `value` to `target`.
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        let _ = contract;
+        let _ = target;
+        let _ = value;
+    }
+
+    /// Returns the output of the tracer.
+    fn result(&mut self) -> Self::Output;
+}
+
+impl<T: Tracer + ?Sized> Tracer for Box<T> {
+    type Output = T::Output;
+
+    fn enter_frame(&mut self, frame: &FrameInput, context: &mut TracerContext<'_>) {
+        (**self).enter_frame(frame, context)
+    }
+
+    fn exit_frame(&mut self, result: &FrameResult, context: &mut TracerContext<'_>) {
+        (**self).exit_frame(result, context)
+    }
+
+    fn step(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+        (**self).step(step, context)
+    }
+
+    fn step_end(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+        (**self).step_end(step, context)
+    }
+
+    fn log(&mut self, log: &Log, context: &mut TracerContext<'_>) {
+        (**self).log(log, context)
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        (**self).selfdestruct(contract, target, value)
+    }
+
+    fn result(&mut self) -> Self::Output {
+        (**self).result()
+    }
+}
+
+/// [Inspector] that drives a [Tracer].
+#[derive(Clone, Debug, Default)]
+pub struct TracerInspector<T> {
+    tracer: T,
+    filter: TraceFilter,
+    /// Number of frames that are being executed.
+    depth: usize,
+    /// Whether the frames that are being executed are kept by the filter, innermost last.
+    kept: Vec<bool>,
+    /// Program counter and opcode of the instruction that is being executed.
+    current: (usize, u8),
+}
+
+impl<T: Tracer> TracerInspector<T> {
+    /// Wraps the tracer.
+    pub fn new(tracer: T) -> Self {
+        Self {
+            tracer,
+            filter: TraceFilter::default(),
+            depth: 0,
+            kept: Vec::new(),
+            current: (0, 0),
+        }
+    }
+
+    /// Passes only the frames and instructions kept by `filter` to the tracer.
+    pub fn with_filter(mut self, filter: TraceFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Returns the filter of the frames and instructions passed to the tracer.
+    pub fn filter(&self) -> &TraceFilter {
+        &self.filter
+    }
+
+    /// Returns the tracer.
+    pub fn tracer(&self) -> &T {
+        &self.tracer
+    }
+
+    /// Returns the tracer mutably.
+    pub fn tracer_mut(&mut self) -> &mut T {
+        &mut self.tracer
+    }
+
+    /// Returns the output of the tracer.
+    pub fn result(&mut self) -> T::Output {
+        self.tracer.result()
+    }
+
+    /// Consumes the inspector and returns the tracer.
+    pub fn into_tracer(self) -> T {
+        self.tracer
+    }
+
+    /// Returns whether the frame that is being executed is kept by the filter.
+    fn in_kept_frame(&self) -> bool {
+        self.kept.last().copied().unwrap_or(true)
+    }
+
+    fn enter<DB: Database>(&mut self, context: &mut EvmContext<DB>, frame: FrameInput) {
+        self.depth += 1;
+        let kept = self.filter.matches_frame(&frame);
+        self.kept.push(kept);
+        if kept {
+            self.tracer
+                .enter_frame(&frame, &mut TracerContext { inner: context });
+        }
+    }
+
+    fn exit<DB: Database>(&mut self, context: &mut EvmContext<DB>, result: FrameResult) {
+        if self.kept.pop().unwrap_or(true) {
+            self.tracer
+                .exit_frame(&result, &mut TracerContext { inner: context });
+        }
+    }
+
+    /// Returns whether the instruction that is being executed is passed to the tracer.
+    fn step_kept(&self) -> bool {
+        self.in_kept_frame() && self.filter.matches_opcode(self.current.1)
+    }
+}
+
+impl<DB: Database, T: Tracer> Inspector<DB> for TracerInspector<T> {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.current = (interp.program_counter(), interp.current_opcode());
+        if !self.step_kept() {
+            return;
+        }
+        let step = Step::new(interp, self.depth.saturating_sub(1));
+        self.tracer
+            .step(&step, &mut TracerContext { inner: context });
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        if !self.step_kept() {
+            return;
+        }
+        let mut step = Step::new(interp, self.depth.saturating_sub(1));
+        // The program counter already points to the next instruction.
+        (step.pc, step.opcode) = self.current;
+        self.tracer
+            .step_end(&step, &mut TracerContext { inner: context });
+    }
+
+    fn log(&mut self, context: &mut EvmContext<DB>, log: &Log, _position: &LogPosition) {
+        if self.in_kept_frame() {
+            self.tracer.log(log, &mut TracerContext { inner: context });
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let frame = FrameInput::from_call(inputs, self.depth);
+        self.enter(context, frame);
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.depth = self.depth.saturating_sub(1);
+        let result = FrameResult {
+            depth: self.depth,
+            result: *outcome.instruction_result(),
+            gas_used: outcome.gas().spent(),
+            output: outcome.output().clone(),
+            created: None,
+            code_size: None,
+            code_deposit_gas: None,
+        };
+        self.exit(context, result);
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        let nonce = context
+            .journaled_state
+            .state
+            .get(&inputs.caller)
+            .map_or(0, |account| account.info.nonce);
+        let frame = FrameInput::from_create(inputs, self.depth, nonce);
+        self.enter(context, frame);
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        self.depth = self.depth.saturating_sub(1);
+        let code_size = outcome
+            .instruction_result()
+            .is_ok()
+            .then(|| outcome.output().len());
+        let result = FrameResult {
+            depth: self.depth,
+            result: *outcome.instruction_result(),
+            gas_used: outcome.gas().spent(),
+            output: outcome.output().clone(),
+            created: outcome.address,
+            code_size,
+            code_deposit_gas: code_size.map(|size| gas::CODEDEPOSIT * size as u64),
+        };
+        self.exit(context, result);
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        if self.in_kept_frame() {
+            self.tracer.selfdestruct(contract, target, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector::inspector_handle_register, interpreter::opcode, primitives::TransactTo,
+        test_utils::benchmark_evm_builder,
+    };
+    use std::vec::Vec;
+
+    /// Collects executed opcodes and the storage value written by the transaction.
+    #[derive(Default)]
+    struct OpcodeTracer {
+        opcodes: Vec<u8>,
+        frames: usize,
+        stored: Option<U256>,
+    }
+
+    impl Tracer for OpcodeTracer {
+        type Output = (Vec<u8>, usize, Option<U256>);
+
+        fn enter_frame(&mut self, frame: &FrameInput, _context: &mut TracerContext<'_>) {
+            assert_eq!(frame.kind, FrameKind::Call(CallScheme::Call));
+            self.frames += 1;
+        }
+
+        fn step_end(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+            self.opcodes.push(step.opcode);
+            if step.opcode == opcode::SSTORE {
+                self.stored = context.storage(step.address, U256::ZERO);
+            }
+        }
+
+        fn result(&mut self) -> Self::Output {
+            (core::mem::take(&mut self.opcodes), self.frames, self.stored)
+        }
+    }
+
+    #[test]
+    fn boxed_tracer() {
+        // PUSH1 0x2a PUSH1 0x00 SSTORE STOP
+        let code = Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x55, 0x00]);
+        let tracer: Box<dyn Tracer<Output = _>> = Box::<OpcodeTracer>::default();
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(TracerInspector::new(tracer))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        evm.transact().unwrap();
+
+        let (opcodes, frames, stored) = evm.context.external.result();
+        assert_eq!(
+            opcodes,
+            [opcode::PUSH1, opcode::PUSH1, opcode::SSTORE, opcode::STOP]
+        );
+        assert_eq!(frames, 1);
+        assert_eq!(stored, Some(U256::from(0x2a)));
+    }
+
+    #[test]
+    fn filtered_tracer() {
+        let code = Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x55, 0x00]);
+        let run = |filter: TraceFilter| {
+            let mut evm = benchmark_evm_builder(Bytecode::new_raw(code.clone()))
+                .with_external_context(
+                    TracerInspector::new(OpcodeTracer::default()).with_filter(filter),
+                )
+                .append_handler_register(inspector_handle_register)
+                .build();
+            evm.transact().unwrap();
+            evm.context.external.result()
+        };
+
+        let (opcodes, frames, stored) = run(TraceFilter::new().opcode(opcode::SSTORE));
+        assert_eq!(opcodes, [opcode::SSTORE]);
+        assert_eq!((frames, stored), (1, Some(U256::from(0x2a))));
+
+        let (opcodes, frames, _) = run(TraceFilter::new().to(Address::with_last_byte(2)));
+        assert!(opcodes.is_empty());
+        assert_eq!(frames, 0);
+    }
+
+    /// Collects the frames and results of a transaction.
+    #[derive(Default)]
+    struct FrameTracer {
+        frames: Vec<(FrameInput, Option<FrameResult>)>,
+    }
+
+    impl Tracer for FrameTracer {
+        type Output = Vec<(FrameInput, Option<FrameResult>)>;
+
+        fn enter_frame(&mut self, frame: &FrameInput, _context: &mut TracerContext<'_>) {
+            self.frames.push((frame.clone(), None));
+        }
+
+        fn exit_frame(&mut self, result: &FrameResult, _context: &mut TracerContext<'_>) {
+            self.frames[result.depth].1 = Some(result.clone());
+        }
+
+        fn result(&mut self) -> Self::Output {
+            core::mem::take(&mut self.frames)
+        }
+    }
+
+    #[test]
+    fn create_frame() {
+        // RETURN(0, 1), deploying a single zero byte.
+        let init_code =
+            Bytes::from_static(&[opcode::PUSH1, 0x01, opcode::PUSH1, 0x00, opcode::RETURN]);
+        let caller = Address::with_last_byte(1);
+        let mut evm = benchmark_evm_builder(Bytecode::new())
+            .with_external_context(TracerInspector::new(FrameTracer::default()))
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::create();
+                tx.data = init_code.clone();
+            })
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        let frames = evm.context.external.result();
+        assert_eq!(frames.len(), 1);
+        let (frame, result) = &frames[0];
+        assert!(frame.is_create());
+        assert_eq!(frame.salt(), None);
+        assert_eq!(frame.created_address, Some(caller.create(0)));
+        assert_eq!(
+            frame.init_code_hash,
+            Some(crate::primitives::keccak256(&init_code))
+        );
+        let result = result.as_ref().unwrap();
+        assert_eq!(result.created, frame.created_address);
+        assert_eq!(
+            (result.code_size, result.code_deposit_gas),
+            (Some(1), Some(200))
+        );
+    }
+}
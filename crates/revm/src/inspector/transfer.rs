@@ -0,0 +1,312 @@
+//! Inspector that records value transfers, including implicit ones.
+
+use crate::{
+    inspector::ContractSelfDestruct,
+    interpreter::{CallInputs, CallOutcome, CreateInputs, CreateOutcome},
+    primitives::{db::Database, Address, Env, HashMap, SpecId, I256, U256},
+    EvmContext, Inspector,
+};
+use std::vec::Vec;
+
+/// Cause of a [BalanceTransfer].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransferKind {
+    /// Value of a call.
+    Call,
+    /// Endowment of a created contract.
+    Create,
+    /// Balance swept to the beneficiary of a self-destructed contract. The balance is burned if
+    /// the contract is destroyed and is its own beneficiary.
+    SelfDestruct,
+    /// Transaction fee paid by the caller. A fee without recipient is burned.
+    Fee,
+    /// Block reward minted to the beneficiary.
+    BlockReward,
+    /// Withdrawal minted to its recipient.
+    Withdrawal,
+}
+
+/// Movement of value between accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceTransfer {
+    /// Cause of the transfer.
+    pub kind: TransferKind,
+    /// Debited account, `None` if the value is minted.
+    pub from: Option<Address>,
+    /// Credited account, `None` if the value is burned.
+    pub to: Option<Address>,
+    /// Transferred value.
+    pub value: U256,
+}
+
+/// [Inspector] that records all non-zero balance transfers of a transaction.
+///
+/// Call, create and self-destruct transfers are recorded during execution, transfers of reverted
+/// frames are discarded. Transfers that happen outside of the interpreter are added with
+/// [TransferInspector::record_fees], [TransferInspector::record_block_reward] and
+/// [TransferInspector::record_withdrawal].
+#[derive(Clone, Debug, Default)]
+pub struct TransferInspector {
+    transfers: Vec<BalanceTransfer>,
+    /// Number of transfers recorded when each of the currently executing frames started.
+    checkpoints: Vec<usize>,
+}
+
+impl TransferInspector {
+    /// Returns the recorded transfers in execution order.
+    pub fn transfers(&self) -> &[BalanceTransfer] {
+        &self.transfers
+    }
+
+    /// Consumes the inspector and returns the recorded transfers.
+    pub fn into_transfers(self) -> Vec<BalanceTransfer> {
+        self.transfers
+    }
+
+    /// Clears the recorded transfers.
+    pub fn clear(&mut self) {
+        self.transfers.clear();
+        self.checkpoints.clear();
+    }
+
+    /// Returns the net balance change of every account touched by the recorded transfers.
+    ///
+    /// Accounts whose changes cancel out are included with a zero change.
+    pub fn balance_changes(&self) -> HashMap<Address, I256> {
+        let mut changes = HashMap::new();
+        for transfer in &self.transfers {
+            let value = I256::from_raw(transfer.value);
+            if let Some(from) = transfer.from {
+                *changes.entry(from).or_insert(I256::ZERO) -= value;
+            }
+            if let Some(to) = transfer.to {
+                *changes.entry(to).or_insert(I256::ZERO) += value;
+            }
+        }
+        changes
+    }
+
+    /// Records the fee paid by the caller of the transaction in `env` that used `gas_used` gas.
+    ///
+    /// Since London the base fee part is burned and the rest goes to the block beneficiary.
+    pub fn record_fees(&mut self, env: &Env, spec_id: SpecId, gas_used: u64) {
+        let gas_price = env.effective_gas_price();
+        let base_fee = if spec_id.is_enabled_in(SpecId::LONDON) {
+            env.block.basefee.min(gas_price)
+        } else {
+            U256::ZERO
+        };
+        let gas_used = U256::from(gas_used);
+        self.push(BalanceTransfer {
+            kind: TransferKind::Fee,
+            from: Some(env.tx.caller),
+            to: Some(env.effective_coinbase()),
+            value: (gas_price - base_fee) * gas_used,
+        });
+        self.push(BalanceTransfer {
+            kind: TransferKind::Fee,
+            from: Some(env.tx.caller),
+            to: None,
+            value: base_fee * gas_used,
+        });
+    }
+
+    /// Records a block reward paid to `beneficiary`.
+    pub fn record_block_reward(&mut self, beneficiary: Address, value: U256) {
+        self.push(BalanceTransfer {
+            kind: TransferKind::BlockReward,
+            from: None,
+            to: Some(beneficiary),
+            value,
+        });
+    }
+
+    /// Records a withdrawal credited to `recipient`.
+    pub fn record_withdrawal(&mut self, recipient: Address, value: U256) {
+        self.push(BalanceTransfer {
+            kind: TransferKind::Withdrawal,
+            from: None,
+            to: Some(recipient),
+            value,
+        });
+    }
+
+    fn push(&mut self, transfer: BalanceTransfer) {
+        if !transfer.value.is_zero() {
+            self.transfers.push(transfer);
+        }
+    }
+
+    /// Ends the current frame, discarding its transfers if it did not succeed.
+    fn end_frame(&mut self, success: bool) -> usize {
+        let checkpoint = self.checkpoints.pop().unwrap_or_default();
+        if !success {
+            self.transfers.truncate(checkpoint);
+        }
+        checkpoint
+    }
+}
+
+impl<DB: Database> Inspector<DB> for TransferInspector {
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        self.checkpoints.push(self.transfers.len());
+        let transfer = &inputs.transfer;
+        if transfer.source != transfer.target {
+            self.push(BalanceTransfer {
+                kind: TransferKind::Call,
+                from: Some(transfer.source),
+                to: Some(transfer.target),
+                value: transfer.value,
+            });
+        }
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.end_frame(outcome.instruction_result().is_ok());
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.checkpoints.push(self.transfers.len());
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        let success = outcome.instruction_result().is_ok();
+        let checkpoint = self.end_frame(success);
+        // The endowment is transferred before the init code runs.
+        if let (true, Some(address)) = (success, outcome.address) {
+            if !inputs.value.is_zero() {
+                let transfer = BalanceTransfer {
+                    kind: TransferKind::Create,
+                    from: Some(inputs.caller),
+                    to: Some(address),
+                    value: inputs.value,
+                };
+                self.transfers.insert(checkpoint, transfer);
+            }
+        }
+        outcome
+    }
+
+    fn contract_selfdestructed(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        selfdestruct: &ContractSelfDestruct,
+    ) {
+        // A contract that targets itself keeps its balance, unless it is destroyed.
+        let to = Some(selfdestruct.target).filter(|target| *target != selfdestruct.address);
+        self.push(BalanceTransfer {
+            kind: TransferKind::SelfDestruct,
+            from: Some(selfdestruct.address),
+            to,
+            value: selfdestruct.value,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector::inspector_handle_register,
+        interpreter::opcode,
+        primitives::{address, Bytecode, Bytes},
+        test_utils::benchmark_evm_builder,
+    };
+
+    #[test]
+    fn call_and_selfdestruct_transfers() {
+        let caller = Address::with_last_byte(1);
+        let beneficiary = address!("00000000000000000000000000000000000000be");
+        // SELFDESTRUCT to the beneficiary.
+        let mut code = vec![opcode::PUSH20];
+        code.extend_from_slice(beneficiary.as_slice());
+        code.push(opcode::SELFDESTRUCT);
+        let bytecode = Bytecode::new_raw(Bytes::from(code));
+
+        let mut evm = benchmark_evm_builder(bytecode)
+            .with_external_context(TransferInspector::default())
+            .modify_tx_env(|tx| tx.value = U256::from(10))
+            .append_handler_register(inspector_handle_register)
+            .build();
+
+        evm.transact().unwrap();
+        let (env, spec_id) = (evm.context.evm.env.clone(), evm.handler.cfg.spec_id);
+        let inspector = &mut evm.context.external;
+        // Gas price is zero, no fee transfers are recorded.
+        inspector.record_fees(&env, spec_id, 21_000);
+
+        assert_eq!(
+            inspector.transfers(),
+            &[
+                BalanceTransfer {
+                    kind: TransferKind::Call,
+                    from: Some(caller),
+                    to: Some(Address::ZERO),
+                    value: U256::from(10),
+                },
+                BalanceTransfer {
+                    kind: TransferKind::SelfDestruct,
+                    from: Some(Address::ZERO),
+                    to: Some(beneficiary),
+                    value: U256::from(10_000_010),
+                },
+            ]
+        );
+        let changes = inspector.balance_changes();
+        assert_eq!(changes[&caller], I256::try_from(-10).unwrap());
+        assert_eq!(
+            changes[&Address::ZERO],
+            I256::try_from(-10_000_000).unwrap()
+        );
+        assert_eq!(changes[&beneficiary], I256::try_from(10_000_010).unwrap());
+    }
+
+    fn selfdestruct_to_self(spec_id: SpecId) -> Vec<BalanceTransfer> {
+        let bytecode = Bytecode::new_raw(Bytes::from(vec![opcode::ADDRESS, opcode::SELFDESTRUCT]));
+        let mut evm = benchmark_evm_builder(bytecode)
+            .with_external_context(TransferInspector::default())
+            .with_spec_id(spec_id)
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+        evm.context.external.transfers().to_vec()
+    }
+
+    #[test]
+    fn selfdestruct_to_self_burns() {
+        assert_eq!(
+            selfdestruct_to_self(SpecId::SHANGHAI),
+            [BalanceTransfer {
+                kind: TransferKind::SelfDestruct,
+                from: Some(Address::ZERO),
+                to: None,
+                value: U256::from(10_000_000),
+            }]
+        );
+        // Since Cancun the contract is not destroyed and keeps its balance.
+        assert!(selfdestruct_to_self(SpecId::CANCUN).is_empty());
+    }
+}
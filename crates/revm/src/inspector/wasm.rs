@@ -0,0 +1,532 @@
+//! [Tracer] running a user-provided WebAssembly module.
+//!
+//! The module is executed by the `wasmi` interpreter with fuel metering and limits on its memory,
+//! tables and output, so untrusted tracers can't escape the sandbox, stall execution or exhaust
+//! the memory of the host.
+//!
+//! # Guest interface
+//!
+//! The module may export a `memory` and any of the following functions:
+//!
+//! - `step()` and `step_end()`, called before and after every instruction,
+//! - `enter(depth: i32)` and `exit(depth: i32, success: i32)`, called when a frame starts and
+//!   ends,
+//! - `result()`, called once at the end to produce the output.
+//!
+//! Functions imported from the `revm` module read the current execution. Pointers and lengths
+//! refer to the exported `memory`, words are 32 bytes big-endian and addresses 20 bytes.
+//! Functions returning `i32` return `0` on success and `-1` if the value is not available.
+//!
+//! | Import | Signature | Description |
+//! |---|---|---|
+//! | `pc` | `() -> i32` | Program counter |
+//! | `opcode` | `() -> i32` | Opcode of the instruction |
+//! | `depth` | `() -> i32` | Depth of the frame |
+//! | `gas` | `() -> i64` | Remaining gas |
+//! | `address` | `(out) -> i32` | Address of the executing account |
+//! | `stack_len` | `() -> i32` | Number of stack items |
+//! | `stack_peek` | `(n, out) -> i32` | `n`-th stack item from the top |
+//! | `memory_len` | `() -> i32` | Size of the EVM memory |
+//! | `memory_read` | `(offset, len, out) -> i32` | Reads EVM memory |
+//! | `balance` | `(address, out) -> i32` | Balance of an account |
+//! | `storage` | `(address, key, out) -> i32` | Storage slot of an account |
+//! | `output` | `(ptr, len)` | Appends bytes to the tracer output |
+//!
+//! Step functions are only available in `step` and `step_end`, state functions in all hooks
+//! except `result`.
+
+use super::tracer::{FrameInput, FrameResult, Step, Tracer, TracerContext};
+use crate::primitives::{Address, Bytes, U256};
+use core::{fmt, ptr};
+use std::{string::String, vec::Vec};
+use wasmi::{
+    Caller, Config, Engine, Extern, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc,
+};
+
+/// Default fuel available to a single hook invocation.
+pub const DEFAULT_FUEL_PER_HOOK: u64 = 1_000_000;
+
+/// Default maximum size of the memory of the module, in bytes.
+pub const DEFAULT_MAX_MEMORY: usize = 16 * 1024 * 1024;
+
+/// Default maximum size of the output, in bytes.
+pub const DEFAULT_MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Maximum number of elements of a table of the module.
+const MAX_TABLE_ELEMENTS: u32 = 10_000;
+
+/// Error of a [WasmTracer].
+#[derive(Debug)]
+pub enum WasmTracerError {
+    /// Module could not be compiled or instantiated.
+    Instantiate(String),
+    /// Module trapped, ran out of fuel or exceeded a limit.
+    Trap(String),
+}
+
+impl std::error::Error for WasmTracerError {}
+
+impl fmt::Display for WasmTracerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instantiate(err) => write!(f, "failed to instantiate tracer: {err}"),
+            Self::Trap(err) => write!(f, "tracer trapped: {err}"),
+        }
+    }
+}
+
+/// Data of the running hook, accessible to host functions.
+struct HostState {
+    /// Step of a `step` or `step_end` hook, null otherwise.
+    step: *const Step<'static>,
+    /// Context of the hook, null in `result`.
+    context: *mut TracerContext<'static>,
+    output: Vec<u8>,
+    /// Maximum size of `output`, the `output` import traps when it would be exceeded.
+    max_output: usize,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn step(&self) -> Option<Step<'_>> {
+        // SAFETY: The pointer is only set while the hook borrowing the step runs, see
+        // `WasmTracer::call`.
+        unsafe { self.step.as_ref() }.copied()
+    }
+
+    fn context(&mut self) -> Option<&mut TracerContext<'static>> {
+        // SAFETY: Same as for `step`, the context outlives the hook invocation.
+        unsafe { self.context.as_mut() }
+    }
+}
+
+/// Limits of the store of a module with a single instance, memory and table. Growing the memory
+/// or a table past them traps.
+fn store_limits(max_memory: usize) -> StoreLimits {
+    StoreLimitsBuilder::new()
+        .memory_size(max_memory)
+        .table_elements(MAX_TABLE_ELEMENTS)
+        .instances(1)
+        .memories(1)
+        .tables(1)
+        .trap_on_grow_failure(true)
+        .build()
+}
+
+/// Exported hooks of the guest module.
+struct Hooks {
+    step: Option<TypedFunc<(), ()>>,
+    step_end: Option<TypedFunc<(), ()>>,
+    enter: Option<TypedFunc<i32, ()>>,
+    exit: Option<TypedFunc<(i32, i32), ()>>,
+    result: Option<TypedFunc<(), ()>>,
+}
+
+/// [Tracer] executing a WebAssembly module, see the [module documentation](self).
+///
+/// The output is the data passed to the `output` import. After a trap the remaining hooks are
+/// skipped and the output is the error.
+pub struct WasmTracer {
+    store: Store<HostState>,
+    hooks: Hooks,
+    fuel_per_hook: u64,
+    error: Option<WasmTracerError>,
+}
+
+impl fmt::Debug for WasmTracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmTracer")
+            .field("fuel_per_hook", &self.fuel_per_hook)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmTracer {
+    /// Compiles and instantiates the module in the WebAssembly binary format.
+    pub fn new(wasm: &[u8]) -> Result<Self, WasmTracerError> {
+        let instantiate = |err: wasmi::Error| WasmTracerError::Instantiate(err.to_string());
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(instantiate)?;
+
+        let mut store = Store::new(
+            &engine,
+            HostState {
+                step: ptr::null(),
+                context: ptr::null_mut(),
+                output: Vec::new(),
+                max_output: DEFAULT_MAX_OUTPUT,
+                limits: store_limits(DEFAULT_MAX_MEMORY),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store
+            .add_fuel(DEFAULT_FUEL_PER_HOOK)
+            .map_err(|err| WasmTracerError::Instantiate(err.to_string()))?;
+        let mut linker = Linker::new(&engine);
+        define_imports(&mut linker).map_err(instantiate)?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(instantiate)?;
+
+        let hooks = Hooks {
+            step: hook(&instance, &store, "step"),
+            step_end: hook(&instance, &store, "step_end"),
+            enter: hook(&instance, &store, "enter"),
+            exit: hook(&instance, &store, "exit"),
+            result: hook(&instance, &store, "result"),
+        };
+        Ok(Self {
+            store,
+            hooks,
+            fuel_per_hook: DEFAULT_FUEL_PER_HOOK,
+            error: None,
+        })
+    }
+
+    /// Sets the fuel available to a single hook invocation.
+    pub fn with_fuel_per_hook(mut self, fuel: u64) -> Self {
+        self.fuel_per_hook = fuel;
+        self
+    }
+
+    /// Sets the maximum size of the memory of the module, in bytes.
+    ///
+    /// Applies to growing the memory after instantiation, modules declaring a larger initial
+    /// memory than [DEFAULT_MAX_MEMORY] fail to instantiate.
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.store.data_mut().limits = store_limits(bytes);
+        self
+    }
+
+    /// Sets the maximum size of the output, in bytes.
+    pub fn with_max_output(mut self, bytes: usize) -> Self {
+        self.store.data_mut().max_output = bytes;
+        self
+    }
+
+    /// Returns the error that stopped the tracer, if any.
+    pub fn error(&self) -> Option<&WasmTracerError> {
+        self.error.as_ref()
+    }
+
+    /// Calls a hook with the step and the context available to host functions.
+    fn call<P: wasmi::WasmParams>(
+        &mut self,
+        func: Option<TypedFunc<P, ()>>,
+        params: P,
+        step: Option<&Step<'_>>,
+        context: Option<&mut TracerContext<'_>>,
+    ) {
+        let Some(func) = func else { return };
+        if self.error.is_some() {
+            return;
+        }
+        let remaining = self.store.consume_fuel(0).unwrap_or_default();
+        let _ = self
+            .store
+            .add_fuel(self.fuel_per_hook.saturating_sub(remaining));
+
+        let state = self.store.data_mut();
+        state.step = step.map_or(ptr::null(), |step| {
+            step as *const Step<'_> as *const Step<'static>
+        });
+        state.context = context.map_or(ptr::null_mut(), |context| {
+            context as *mut TracerContext<'_> as *mut TracerContext<'static>
+        });
+        let result = func.call(&mut self.store, params);
+        let state = self.store.data_mut();
+        state.step = ptr::null();
+        state.context = ptr::null_mut();
+
+        if let Err(err) = result {
+            self.error = Some(WasmTracerError::Trap(err.to_string()));
+        }
+    }
+}
+
+impl Tracer for WasmTracer {
+    type Output = Result<Bytes, WasmTracerError>;
+
+    fn enter_frame(&mut self, frame: &FrameInput, context: &mut TracerContext<'_>) {
+        self.call(self.hooks.enter, frame.depth as i32, None, Some(context));
+    }
+
+    fn exit_frame(&mut self, result: &FrameResult, context: &mut TracerContext<'_>) {
+        let params = (result.depth as i32, result.is_success() as i32);
+        self.call(self.hooks.exit, params, None, Some(context));
+    }
+
+    fn step(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+        self.call(self.hooks.step, (), Some(step), Some(context));
+    }
+
+    fn step_end(&mut self, step: &Step<'_>, context: &mut TracerContext<'_>) {
+        self.call(self.hooks.step_end, (), Some(step), Some(context));
+    }
+
+    fn result(&mut self) -> Self::Output {
+        self.call(self.hooks.result, (), None, None);
+        let output = core::mem::take(&mut self.store.data_mut().output);
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(output.into()),
+        }
+    }
+}
+
+fn hook<P: wasmi::WasmParams>(
+    instance: &Instance,
+    store: &Store<HostState>,
+    name: &str,
+) -> Option<TypedFunc<P, ()>> {
+    instance.get_typed_func(store, name).ok()
+}
+
+fn read<const N: usize>(caller: &Caller<'_, HostState>, ptr: i32) -> Option<[u8; N]> {
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)?;
+    let mut buf = [0; N];
+    memory.read(caller, ptr as u32 as usize, &mut buf).ok()?;
+    Some(buf)
+}
+
+fn write(caller: &mut Caller<'_, HostState>, ptr: i32, data: &[u8]) -> i32 {
+    let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+        return -1;
+    };
+    match memory.write(caller, ptr as u32 as usize, data) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+fn define_imports(linker: &mut Linker<HostState>) -> Result<(), wasmi::Error> {
+    linker.func_wrap("revm", "pc", |caller: Caller<'_, HostState>| {
+        caller.data().step().map_or(-1, |step| step.pc as i32)
+    })?;
+    linker.func_wrap("revm", "opcode", |caller: Caller<'_, HostState>| {
+        caller.data().step().map_or(-1, |step| step.opcode as i32)
+    })?;
+    linker.func_wrap("revm", "depth", |caller: Caller<'_, HostState>| {
+        caller.data().step().map_or(-1, |step| step.depth as i32)
+    })?;
+    linker.func_wrap("revm", "gas", |caller: Caller<'_, HostState>| {
+        caller
+            .data()
+            .step()
+            .map_or(-1, |step| step.gas_remaining as i64)
+    })?;
+    linker.func_wrap(
+        "revm",
+        "address",
+        |mut caller: Caller<'_, HostState>, out: i32| {
+            let Some(address) = caller.data().step().map(|step| step.address) else {
+                return -1;
+            };
+            write(&mut caller, out, address.as_slice())
+        },
+    )?;
+    linker.func_wrap("revm", "stack_len", |caller: Caller<'_, HostState>| {
+        caller
+            .data()
+            .step()
+            .map_or(-1, |step| step.stack.len() as i32)
+    })?;
+    linker.func_wrap(
+        "revm",
+        "stack_peek",
+        |mut caller: Caller<'_, HostState>, n: i32, out: i32| {
+            let word = caller.data().step().and_then(|step| {
+                let index = step.stack.len().checked_sub(1 + usize::try_from(n).ok()?)?;
+                Some(step.stack[index].to_be_bytes::<32>())
+            });
+            match word {
+                Some(word) => write(&mut caller, out, &word),
+                None => -1,
+            }
+        },
+    )?;
+    linker.func_wrap("revm", "memory_len", |caller: Caller<'_, HostState>| {
+        caller
+            .data()
+            .step()
+            .map_or(-1, |step| step.memory.len() as i32)
+    })?;
+    linker.func_wrap(
+        "revm",
+        "memory_read",
+        |mut caller: Caller<'_, HostState>, offset: i32, len: i32, out: i32| {
+            let data = caller.data().step().and_then(|step| {
+                let offset = usize::try_from(offset).ok()?;
+                let end = offset.checked_add(usize::try_from(len).ok()?)?;
+                step.memory.get(offset..end).map(<[u8]>::to_vec)
+            });
+            match data {
+                Some(data) => write(&mut caller, out, &data),
+                None => -1,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        "revm",
+        "balance",
+        |mut caller: Caller<'_, HostState>, address: i32, out: i32| {
+            let Some(address) = read::<20>(&caller, address).map(Address::from) else {
+                return -1;
+            };
+            let balance = caller
+                .data_mut()
+                .context()
+                .and_then(|context| context.balance(address));
+            match balance {
+                Some(balance) => write(&mut caller, out, &balance.to_be_bytes::<32>()),
+                None => -1,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        "revm",
+        "storage",
+        |mut caller: Caller<'_, HostState>, address: i32, key: i32, out: i32| {
+            let (Some(address), Some(key)) =
+                (read::<20>(&caller, address), read::<32>(&caller, key))
+            else {
+                return -1;
+            };
+            let value = caller
+                .data_mut()
+                .context()
+                .and_then(|context| context.storage(address.into(), U256::from_be_bytes(key)));
+            match value {
+                Some(value) => write(&mut caller, out, &value.to_be_bytes::<32>()),
+                None => -1,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        "revm",
+        "output",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), wasmi::core::Trap> {
+            let memory = caller
+                .get_export("memory")
+                .and_then(Extern::into_memory)
+                .ok_or_else(|| wasmi::core::Trap::new("missing memory export"))?;
+            let start = ptr as u32 as usize;
+            let end = start.saturating_add(len as u32 as usize);
+            let data = memory
+                .data(&caller)
+                .get(start..end)
+                .ok_or_else(|| wasmi::core::Trap::new("output out of bounds"))?
+                .to_vec();
+            let state = caller.data_mut();
+            if state.output.len().saturating_add(data.len()) > state.max_output {
+                return Err(wasmi::core::Trap::new("output limit exceeded"));
+            }
+            state.output.extend_from_slice(&data);
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        inspector::{inspector_handle_register, inspectors::TracerInspector},
+        primitives::Bytecode,
+        test_utils::benchmark_evm_builder,
+    };
+
+    /// Module counting `step` calls and writing the count as a little-endian `u32` in `result`.
+    const STEP_COUNTER: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x09, 0x02, 0x60, 0x00, 0x00, 0x60, 0x02, 0x7f, 0x7f, 0x00, // types
+        0x02, 0x0f, 0x01, 0x04, b'r', b'e', b'v', b'm', 0x06, b'o', b'u', b't', b'p', b'u', b't',
+        0x00, 0x01, // import revm.output
+        0x03, 0x03, 0x02, 0x00, 0x00, // functions
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory
+        0x06, 0x06, 0x01, 0x7f, 0x01, 0x41, 0x00, 0x0b, // global counter
+        0x07, 0x1a, 0x03, 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x04, b's', b't',
+        b'e', b'p', 0x00, 0x01, 0x06, b'r', b'e', b's', b'u', b'l', b't', 0x00,
+        0x02, // exports
+        0x0a, 0x1b, 0x02, // code
+        0x09, 0x00, 0x23, 0x00, 0x41, 0x01, 0x6a, 0x24, 0x00, 0x0b, // step
+        0x0f, 0x00, 0x41, 0x00, 0x23, 0x00, 0x36, 0x02, 0x00, 0x41, 0x00, 0x41, 0x04, 0x10, 0x00,
+        0x0b, // result
+    ];
+
+    /// Module growing its memory by 1024 pages, 64 MiB, in `step`.
+    const MEMORY_GROWER: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // types
+        0x03, 0x02, 0x01, 0x00, // functions
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory
+        0x07, 0x11, 0x02, 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x04, b's', b't',
+        b'e', b'p', 0x00, 0x00, // exports
+        0x0a, 0x0a, 0x01, // code
+        0x08, 0x00, 0x41, 0x80, 0x08, 0x40, 0x00, 0x1a, 0x0b, // step
+    ];
+
+    /// Module passing its whole memory, 64 KiB, to `output` in `step`.
+    const OUTPUT_FLOODER: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x09, 0x02, 0x60, 0x00, 0x00, 0x60, 0x02, 0x7f, 0x7f, 0x00, // types
+        0x02, 0x0f, 0x01, 0x04, b'r', b'e', b'v', b'm', 0x06, b'o', b'u', b't', b'p', b'u', b't',
+        0x00, 0x01, // import revm.output
+        0x03, 0x02, 0x01, 0x00, // functions
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory
+        0x07, 0x11, 0x02, 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x04, b's', b't',
+        b'e', b'p', 0x00, 0x01, // exports
+        0x0a, 0x0c, 0x01, // code
+        0x0a, 0x00, 0x41, 0x00, 0x41, 0x80, 0x80, 0x04, 0x10, 0x00, 0x0b, // step
+    ];
+
+    /// Traces `PUSH1 0x2a PUSH1 0x00 SSTORE STOP` with `tracer`.
+    fn trace(tracer: WasmTracer) -> Result<Bytes, WasmTracerError> {
+        let code = Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x55, 0x00]);
+        let mut evm = benchmark_evm_builder(Bytecode::new_raw(code))
+            .with_external_context(TracerInspector::new(tracer))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        evm.transact().unwrap();
+        evm.context.external.result()
+    }
+
+    #[test]
+    fn counts_steps() {
+        let output = trace(WasmTracer::new(STEP_COUNTER).unwrap()).unwrap();
+        assert_eq!(output[..], 4u32.to_le_bytes());
+    }
+
+    #[test]
+    fn memory_limit() {
+        assert!(matches!(
+            trace(WasmTracer::new(MEMORY_GROWER).unwrap()),
+            Err(WasmTracerError::Trap(_))
+        ));
+    }
+
+    #[test]
+    fn output_limit() {
+        // Four steps output 256 KiB.
+        let output = trace(WasmTracer::new(OUTPUT_FLOODER).unwrap()).unwrap();
+        assert_eq!(output.len(), 4 << 16);
+        let tracer = WasmTracer::new(OUTPUT_FLOODER)
+            .unwrap()
+            .with_max_output(100_000);
+        assert!(matches!(trace(tracer), Err(WasmTracerError::Trap(_))));
+    }
+
+    #[test]
+    fn invalid_module() {
+        assert!(matches!(
+            WasmTracer::new(&[0x00, 0x61, 0x73]),
+            Err(WasmTracerError::Instantiate(_))
+        ));
+    }
+}
@@ -1,8 +1,10 @@
 use crate::interpreter::{InstructionResult, SelfDestructResult};
 use crate::primitives::{
-    db::Database, hash_map::Entry, Account, Address, Bytecode, EVMError, HashMap, HashSet, Log,
-    SpecId::*, State, StorageSlot, TransientStorage, KECCAK_EMPTY, PRECOMPILE3, U256,
+    db::Database, hash_map::Entry, keccak256, Account, Address, Bytecode, Bytes, CodeCacheStats,
+    DatabaseAccess, EVMError, HashMap, HashSet, Log, SpecId::*, State, StorageSlot,
+    TransientStorage, WarmCarryover, B256, KECCAK_EMPTY, PRECOMPILE3, U256,
 };
+use crate::AccessEvents;
 use core::mem;
 use revm_interpreter::primitives::SpecId;
 use revm_interpreter::SStoreResult;
@@ -34,6 +36,36 @@ pub struct JournaledState {
     /// Note that this not include newly loaded accounts, account and storage
     /// is considered warm if it is found in the `State`.
     pub warm_preloaded_addresses: HashSet<Address>,
+    /// What stays warm for the next transaction when the state is finalized.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warm_carryover: WarmCarryover,
+    /// Addresses, and their storage slots, kept warm from previous transactions.
+    ///
+    /// Empty unless [Self::warm_carryover] is enabled, see [Self::clear_warm_carryover].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub carried_warm: HashMap<Address, HashSet<U256>>,
+    /// Code loaded from the database during the transaction, by code hash.
+    ///
+    /// Code of a hash never changes, so entries stay valid when accounts are created or
+    /// self-destructed. Accounts sharing code, like proxies, load it only once.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub code_cache: HashMap<B256, Bytecode>,
+    /// Statistics of code loads of the transaction.
+    ///
+    /// Reset by [Self::finalize], the output of the transaction holds them with the
+    /// `instrumentation` feature.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub code_cache_stats: CodeCacheStats,
+    /// Witness access events of the transaction, recorded if set.
+    ///
+    /// Recording them replaces the cold access costs of EIP-2929, accounts and storage slots
+    /// are always loaded warm.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub access_events: Option<AccessEvents>,
+    /// Keccak preimages of the addresses and storage keys loaded by the transaction, recorded
+    /// if set.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub preimages: Option<HashMap<B256, Bytes>>,
 }
 
 impl JournaledState {
@@ -57,6 +89,12 @@ impl JournaledState {
             depth: 0,
             spec,
             warm_preloaded_addresses,
+            warm_carryover: WarmCarryover::None,
+            carried_warm: HashMap::new(),
+            code_cache: HashMap::new(),
+            code_cache_stats: CodeCacheStats::default(),
+            access_events: None,
+            preimages: None,
         }
     }
 
@@ -72,6 +110,24 @@ impl JournaledState {
         self.spec = spec;
     }
 
+    /// Sets what stays warm for the next transaction.
+    ///
+    /// Switching to [WarmCarryover::None] also forgets what was carried so far.
+    #[inline]
+    pub fn set_warm_carryover(&mut self, carryover: WarmCarryover) {
+        if carryover == WarmCarryover::None {
+            self.clear_warm_carryover();
+        }
+        self.warm_carryover = carryover;
+    }
+
+    /// Forgets the addresses and storage slots carried from previous transactions, for example
+    /// at a block boundary.
+    #[inline]
+    pub fn clear_warm_carryover(&mut self) {
+        self.carried_warm.clear();
+    }
+
     /// Mark account as touched as only touched accounts will be added to state.
     /// This is especially important for state clear where touched empty accounts needs to
     /// be removed from state.
@@ -102,12 +158,44 @@ impl JournaledState {
             logs,
             depth,
             journal,
+            code_cache,
+            code_cache_stats,
+            carried_warm,
+            warm_carryover,
+            access_events,
+            preimages,
             // kept, see [Self::new]
             spec: _,
             warm_preloaded_addresses: _,
         } = self;
 
+        match warm_carryover {
+            WarmCarryover::None => {}
+            WarmCarryover::Addresses => {
+                for address in state.keys() {
+                    carried_warm.entry(*address).or_default();
+                }
+            }
+            WarmCarryover::AddressesAndSlots => {
+                for (address, account) in state.iter() {
+                    carried_warm
+                        .entry(*address)
+                        .or_default()
+                        .extend(account.storage.keys().copied());
+                }
+            }
+        }
+
         *transient_storage = TransientStorage::default();
+        // Recording stays enabled, the recordings of the next transaction start empty.
+        if let Some(events) = access_events {
+            *events = AccessEvents::new();
+        }
+        if let Some(preimages) = preimages {
+            preimages.clear();
+        }
+        code_cache.clear();
+        *code_cache_stats = CodeCacheStats::default();
         *journal = vec![vec![]];
         *depth = 0;
         let state = mem::take(state);
@@ -136,6 +224,63 @@ impl JournaledState {
         self.depth as u64
     }
 
+    /// Returns `true` if loading the storage slot does not pay the cold access cost, because it
+    /// was already loaded, is warm from a previous transaction or witness gas is charged instead.
+    #[inline]
+    pub fn is_storage_warm(&self, address: Address, key: U256) -> bool {
+        self.access_events.is_some()
+            || self
+                .state
+                .get(&address)
+                .map_or(false, |account| account.storage.contains_key(&key))
+            || self
+                .carried_warm
+                .get(&address)
+                .map_or(false, |slots| slots.contains(&key))
+    }
+
+    /// Validates the journal and panics with a dump of it if it is inconsistent.
+    ///
+    /// Every open call frame must have a journal entry set and every journaled account must be
+    /// loaded in the state.
+    #[cfg(feature = "invariant-checks")]
+    #[track_caller]
+    pub fn check_invariants(&self) {
+        if self.journal.len() <= self.depth {
+            panic!(
+                "journal invariants violated: {} entry sets for depth {}\njournal: {:?}",
+                self.journal.len(),
+                self.depth,
+                self.journal
+            );
+        }
+        for entry in self.journal.iter().flatten() {
+            let (address, other) = match entry {
+                JournalEntry::AccountLoaded { address }
+                | JournalEntry::AccountTouched { address }
+                | JournalEntry::NonceChange { address }
+                | JournalEntry::AccountCreated { address }
+                | JournalEntry::StorageChange { address, .. }
+                | JournalEntry::TransientStorageChange { address, .. }
+                | JournalEntry::CodeChange { address } => (*address, None),
+                JournalEntry::AccountDestroyed {
+                    address, target, ..
+                } => (*address, Some(*target)),
+                JournalEntry::BalanceTransfer { from, to, .. } => (*from, Some(*to)),
+            };
+            if let Some(address) = core::iter::once(address)
+                .chain(other)
+                .find(|address| !self.state.contains_key(address))
+            {
+                panic!(
+                    "journal invariants violated: {address} is journaled but not loaded\n\
+                     depth: {}\nentry: {entry:?}\njournal: {:?}",
+                    self.depth, self.journal
+                );
+            }
+        }
+    }
+
     /// use it only if you know that acc is warm
     /// Assume account is warm
     #[inline]
@@ -148,6 +293,11 @@ impl JournaledState {
             .unwrap()
             .push(JournalEntry::CodeChange { address });
 
+        if let Some(events) = &mut self.access_events {
+            events.touch_basic_data(address, true);
+            events.touch_code_hash(address, true);
+            events.touch_code(address, 0, code.len(), true);
+        }
         account.info.code_hash = code.hash_slow();
         account.info.code = Some(code);
     }
@@ -164,6 +314,9 @@ impl JournaledState {
             .last_mut()
             .unwrap()
             .push(JournalEntry::NonceChange { address });
+        if let Some(events) = &mut self.access_events {
+            events.touch_basic_data(address, true);
+        }
 
         account.info.nonce += 1;
 
@@ -182,6 +335,10 @@ impl JournaledState {
         // load accounts
         self.load_account(*from, db)?;
         self.load_account(*to, db)?;
+        if let Some(events) = self.access_events.as_mut().filter(|_| !balance.is_zero()) {
+            events.touch_basic_data(*from, true);
+            events.touch_basic_data(*to, true);
+        }
 
         // sub balance from
         let from_account = &mut self.state.get_mut(from).unwrap();
@@ -259,6 +416,11 @@ impl JournaledState {
 
         // set account status to created.
         account.mark_created();
+        if let Some(events) = &mut self.access_events {
+            events.touch_basic_data(caller, true);
+            events.touch_basic_data(address, true);
+            events.touch_code_hash(address, true);
+        }
 
         // this entry will revert set nonce.
         last_journal.push(JournalEntry::AccountCreated { address });
@@ -461,6 +623,10 @@ impl JournaledState {
         db: &mut DB,
     ) -> Result<SelfDestructResult, EVMError<DB::Error>> {
         let (is_cold, target_exists) = self.load_account_exist(target, db)?;
+        if let Some(events) = &mut self.access_events {
+            events.touch_basic_data(address, true);
+            events.touch_basic_data(target, true);
+        }
 
         if address != target {
             // Both accounts are loaded before this point, `address` as we execute its contract.
@@ -527,7 +693,7 @@ impl JournaledState {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(vac) => vac.insert(
                 db.basic(address)
-                    .map_err(EVMError::Database)?
+                    .map_err(|e| EVMError::database_at(e, DatabaseAccess::Account(address)))?
                     .map(|i| i.into())
                     .unwrap_or(Account::new_not_existing()),
             ),
@@ -535,7 +701,9 @@ impl JournaledState {
         // preload storages.
         for slot in slots {
             if let Entry::Vacant(entry) = account.storage.entry(*slot) {
-                let storage = db.storage(address, *slot).map_err(EVMError::Database)?;
+                let storage = db.storage(address, *slot).map_err(|e| {
+                    EVMError::database_at(e, DatabaseAccess::Storage(address, *slot))
+                })?;
                 entry.insert(StorageSlot::new(storage));
             }
         }
@@ -549,15 +717,26 @@ impl JournaledState {
         address: Address,
         db: &mut DB,
     ) -> Result<(&mut Account, bool), EVMError<DB::Error>> {
+        if let Some(events) = &mut self.access_events {
+            events.touch_basic_data(address, false);
+        }
         Ok(match self.state.entry(address) {
             Entry::Occupied(entry) => (entry.into_mut(), false),
             Entry::Vacant(vac) => {
-                let account =
-                    if let Some(account) = db.basic(address).map_err(EVMError::Database)? {
-                        account.into()
-                    } else {
-                        Account::new_not_existing()
-                    };
+                let account = if let Some(account) = db
+                    .basic(address)
+                    .map_err(|e| EVMError::database_at(e, DatabaseAccess::Account(address)))?
+                {
+                    account.into()
+                } else {
+                    Account::new_not_existing()
+                };
+
+                if let Some(preimages) = &mut self.preimages {
+                    preimages
+                        .entry(keccak256(address))
+                        .or_insert_with(|| Bytes::copy_from_slice(address.as_slice()));
+                }
 
                 // journal loading of account. AccessList touch.
                 self.journal
@@ -566,7 +745,9 @@ impl JournaledState {
                     .push(JournalEntry::AccountLoaded { address });
 
                 // precompiles are warm loaded so we need to take that into account
-                let is_cold = !self.warm_preloaded_addresses.contains(&address);
+                let is_cold = self.access_events.is_none()
+                    && !self.warm_preloaded_addresses.contains(&address)
+                    && !self.carried_warm.contains_key(&address);
 
                 (vac.insert(account), is_cold)
             }
@@ -603,19 +784,30 @@ impl JournaledState {
         address: Address,
         db: &mut DB,
     ) -> Result<(&mut Account, bool), EVMError<DB::Error>> {
+        if let Some(events) = &mut self.access_events {
+            events.touch_code_hash(address, false);
+        }
         let (acc, is_cold) = self.load_account(address, db)?;
-        if acc.info.code.is_none() {
-            if acc.info.code_hash == KECCAK_EMPTY {
-                let empty = Bytecode::new();
-                acc.info.code = Some(empty);
+        if acc.info.code.is_some() {
+            self.code_cache_stats.hits += 1;
+        } else {
+            let code_hash = acc.info.code_hash;
+            let code = if code_hash == KECCAK_EMPTY {
+                Bytecode::new()
+            } else if let Some(code) = self.code_cache.get(&code_hash) {
+                self.code_cache_stats.hits += 1;
+                code.clone()
             } else {
                 let code = db
-                    .code_by_hash(acc.info.code_hash)
-                    .map_err(EVMError::Database)?;
-                acc.info.code = Some(code);
-            }
+                    .code_by_hash(code_hash)
+                    .map_err(|e| EVMError::database_at(e, DatabaseAccess::Code(code_hash)))?;
+                self.code_cache_stats.misses += 1;
+                self.code_cache.insert(code_hash, code.clone());
+                code
+            };
+            self.state.get_mut(&address).unwrap().info.code = Some(code);
         }
-        Ok((acc, is_cold))
+        Ok((self.state.get_mut(&address).unwrap(), is_cold))
     }
 
     /// Load storage slot
@@ -630,6 +822,9 @@ impl JournaledState {
         key: U256,
         db: &mut DB,
     ) -> Result<(U256, bool), EVMError<DB::Error>> {
+        if let Some(events) = &mut self.access_events {
+            events.touch_slot(address, key, false, false);
+        }
         // assume acc is warm
         let account = self.state.get_mut(&address).unwrap();
         // only if account is created in this tx we can assume that storage is empty.
@@ -641,8 +836,16 @@ impl JournaledState {
                 let value = if is_newly_created {
                     U256::ZERO
                 } else {
-                    db.storage(address, key).map_err(EVMError::Database)?
+                    db.storage(address, key).map_err(|e| {
+                        EVMError::database_at(e, DatabaseAccess::Storage(address, key))
+                    })?
                 };
+                if let Some(preimages) = &mut self.preimages {
+                    let key = key.to_be_bytes::<32>();
+                    preimages
+                        .entry(keccak256(key))
+                        .or_insert_with(|| Bytes::copy_from_slice(&key));
+                }
                 // add it to journal as cold loaded.
                 self.journal
                     .last_mut()
@@ -655,7 +858,12 @@ impl JournaledState {
 
                 vac.insert(StorageSlot::new(value));
 
-                (value, true)
+                let is_cold = self.access_events.is_none()
+                    && !self
+                        .carried_warm
+                        .get(&address)
+                        .map_or(false, |slots| slots.contains(&key));
+                (value, is_cold)
             }
         };
         Ok(load)
@@ -681,6 +889,10 @@ impl JournaledState {
 
         // if there is no original value in dirty return present value, that is our original.
         let slot = acc.storage.get_mut(&key).unwrap();
+        if let Some(events) = &mut self.access_events {
+            let fill = slot.previous_or_original_value.is_zero() && !new.is_zero();
+            events.touch_slot(address, key, true, fill);
+        }
 
         // new value is same as present, we don't need to do anything
         if present == new {
@@ -839,3 +1051,95 @@ pub struct JournalCheckpoint {
     log_i: usize,
     journal_i: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::EmptyDB;
+
+    #[test]
+    fn warm_carryover() {
+        let mut db = EmptyDB::default();
+        let address = Address::with_last_byte(1);
+        let mut journal = JournaledState::new(SpecId::CANCUN, HashSet::new());
+        journal.set_warm_carryover(WarmCarryover::AddressesAndSlots);
+
+        assert!(journal.load_account(address, &mut db).unwrap().1);
+        assert!(journal.sload(address, U256::ZERO, &mut db).unwrap().1);
+        journal.finalize();
+
+        assert!(!journal.load_account(address, &mut db).unwrap().1);
+        assert!(!journal.sload(address, U256::ZERO, &mut db).unwrap().1);
+        assert!(journal.sload(address, U256::from(1), &mut db).unwrap().1);
+        journal.finalize();
+
+        journal.set_warm_carryover(WarmCarryover::None);
+        assert!(journal.carried_warm.is_empty());
+        assert!(journal.load_account(address, &mut db).unwrap().1);
+    }
+
+    #[test]
+    fn finalize_resets_recordings() {
+        let mut journal = JournaledState::new(SpecId::CANCUN, HashSet::new());
+        let mut events = AccessEvents::new();
+        events.touch_basic_data(Address::with_last_byte(1), true);
+        journal.access_events = Some(events);
+        journal.preimages = Some(HashMap::from_iter([(B256::ZERO, Bytes::new())]));
+
+        journal.finalize();
+        assert_eq!(journal.access_events, Some(AccessEvents::new()));
+        assert_eq!(journal.preimages, Some(HashMap::new()));
+    }
+
+    #[test]
+    fn code_cache_stats() {
+        use crate::{db::CacheDB, primitives::AccountInfo};
+
+        // Two proxies sharing their code.
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x00]));
+        let code_hash = code.hash_slow();
+        let proxies = [Address::with_last_byte(1), Address::with_last_byte(2)];
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.contracts.insert(code_hash, code);
+        for address in proxies {
+            db.insert_account_info(
+                address,
+                AccountInfo {
+                    code_hash,
+                    code: None,
+                    ..Default::default()
+                },
+            );
+        }
+        let mut journal = JournaledState::new(SpecId::CANCUN, HashSet::new());
+
+        journal.load_code(proxies[0], &mut db).unwrap();
+        assert_eq!(
+            journal.code_cache_stats,
+            CodeCacheStats { hits: 0, misses: 1 }
+        );
+        // Served by the code cache, then by the loaded account.
+        journal.load_code(proxies[1], &mut db).unwrap();
+        journal.load_code(proxies[0], &mut db).unwrap();
+        assert_eq!(
+            journal.code_cache_stats,
+            CodeCacheStats { hits: 2, misses: 1 }
+        );
+
+        journal.finalize();
+        assert_eq!(journal.code_cache_stats, CodeCacheStats::default());
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[test]
+    fn deserialize_without_warm_carryover() {
+        let journal = JournaledState::new(SpecId::CANCUN, HashSet::new());
+        let mut json = serde_json::to_value(&journal).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("warm_carryover");
+        fields.remove("carried_warm");
+
+        let decoded: JournaledState = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, journal);
+    }
+}
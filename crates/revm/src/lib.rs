@@ -11,46 +11,83 @@ extern crate alloc as std;
 
 // Define modules.
 
+pub mod access_events;
+pub mod access_stats;
+pub mod analysis;
+pub mod artifact;
+#[cfg(feature = "native-aa")]
+pub mod account_abstraction;
+mod block_builder;
 mod builder;
+mod call_builder;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod call_context_suite;
 mod context;
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
 
 pub mod db;
+pub mod determinism;
 mod evm;
+mod executor;
+pub mod fixture;
 mod frame;
 pub mod handler;
 mod inspector;
 mod journaled_state;
+mod mempool;
+mod min_gas;
 #[cfg(feature = "optimism")]
 pub mod optimism;
+mod pool;
+mod reorg;
+pub mod scheduler;
+mod simulator;
+mod trace_cache;
+#[cfg(all(feature = "zkvm", target_os = "zkvm"))]
+mod zkvm;
 
 // Export items.
 
+pub use access_events::AccessEvents;
+pub use block_builder::{BlockBuilder, BuiltBlock, FailurePolicy, RejectedTx, Rejection};
 pub use builder::EvmBuilder;
+pub use call_builder::{CallBuilder, CallError};
 pub use context::{
     Context, ContextPrecompile, ContextPrecompiles, ContextStatefulPrecompile,
     ContextStatefulPrecompileArc, ContextStatefulPrecompileBox, ContextStatefulPrecompileMut,
-    ContextWithHandlerCfg, EvmContext, InnerEvmContext,
+    ContextWithHandlerCfg, EvmContext, Extension, Extensions, InnerEvmContext, LabelRegistry,
 };
 pub use db::{
     CacheState, DBBox, State, StateBuilder, StateDBBox, TransitionAccount, TransitionState,
 };
 pub use db::{Database, DatabaseCommit, DatabaseRef, InMemoryDB};
 pub use evm::{Evm, CALL_STACK_LIMIT};
+pub use executor::{BlockExecutor, ExecutedTx};
 pub use frame::{CallFrame, CreateFrame, Frame, FrameData, FrameOrResult, FrameResult};
 pub use handler::Handler;
 pub use inspector::{
-    inspector_handle_register, inspector_instruction, inspectors, GetInspector, Inspector,
+    inspector_handle_register, inspector_instruction, inspectors, ContractCreation,
+    ContractSelfDestruct, GetInspector, Inspector, LogPosition, StorageAccess,
 };
 pub use journaled_state::{JournalCheckpoint, JournalEntry, JournaledState};
+pub use mempool::{Admission, AdmissionError, PendingState, DEFAULT_PRICE_BUMP};
+pub use min_gas::{MinGasError, MinGasLimit};
+pub use pool::{EvmPool, PooledEvm};
+pub use reorg::Reorg;
+pub use simulator::Simulator;
+pub use trace_cache::TraceBlockCache;
 // export Optimism types, helpers, and constants
 #[cfg(feature = "optimism")]
 pub use optimism::{L1BlockInfo, BASE_FEE_RECIPIENT, L1_BLOCK_CONTRACT, L1_FEE_RECIPIENT};
 
 // Reexport libraries
 
+#[cfg(feature = "alloy")]
+pub use alloy_sol_types;
+#[cfg(feature = "alloy-rpc-types")]
+pub use alloy_rpc_types;
 #[doc(inline)]
 pub use revm_interpreter as interpreter;
 #[doc(inline)]
@@ -0,0 +1,345 @@
+use crate::{
+    db::{CacheDB, DatabaseRef},
+    primitives::{Address, HashMap, TxEnv, U256},
+};
+use core::fmt;
+use std::{collections::BTreeMap, vec::Vec};
+
+/// Percentage by which a replacement must raise the fees of the transaction it replaces.
+pub const DEFAULT_PRICE_BUMP: u64 = 10;
+
+/// How a transaction accepted by [PendingState::validate] relates to the pending ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Admission {
+    /// The transaction has the next nonce of its sender and can be executed.
+    Ready,
+    /// The transaction leaves a nonce gap and waits for the missing nonces.
+    Queued,
+    /// The transaction replaces the pending one with the same nonce.
+    Replacement,
+}
+
+/// Reason a transaction is refused by [PendingState::validate].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AdmissionError<E> {
+    /// The nonce is already used by the state.
+    NonceTooLow {
+        /// Nonce of the transaction.
+        tx: u64,
+        /// Nonce of the sender in the state.
+        state: u64,
+    },
+    /// The fees of the replacement are not bumped enough.
+    Underpriced {
+        /// Minimum gas price of the replacement.
+        required_gas_price: U256,
+        /// Minimum priority fee of the replacement.
+        required_priority_fee: U256,
+    },
+    /// The balance left by the pending transactions of the sender does not cover the cost.
+    InsufficientFunds {
+        /// Maximum cost of the transaction.
+        cost: U256,
+        /// Balance left after the pending transactions with a lower nonce.
+        balance: U256,
+    },
+    /// Error of the database.
+    Database(E),
+}
+
+impl<E: fmt::Display> fmt::Display for AdmissionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonceTooLow { tx, state } => {
+                write!(f, "nonce {tx} too low, expected at least {state}")
+            }
+            Self::Underpriced {
+                required_gas_price,
+                required_priority_fee,
+            } => write!(
+                f,
+                "replacement underpriced, requires a gas price of {required_gas_price} \
+                 and a priority fee of {required_priority_fee}"
+            ),
+            Self::InsufficientFunds { cost, balance } => {
+                write!(f, "cost {cost} exceeds the pending balance {balance}")
+            }
+            Self::Database(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> std::error::Error for AdmissionError<E> {}
+
+/// Fees and cost of a pending transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PendingTx {
+    gas_price: U256,
+    priority_fee: U256,
+    cost: U256,
+}
+
+impl PendingTx {
+    fn new(tx: &TxEnv) -> Self {
+        let blob_fee = tx
+            .max_fee_per_blob_gas
+            .unwrap_or_default()
+            .saturating_mul(U256::from(tx.get_total_blob_gas()));
+        Self {
+            gas_price: tx.gas_price,
+            priority_fee: tx.gas_priority_fee.unwrap_or(tx.gas_price),
+            cost: tx
+                .gas_price
+                .saturating_mul(U256::from(tx.gas_limit))
+                .saturating_add(tx.value)
+                .saturating_add(blob_fee),
+        }
+    }
+}
+
+/// Pending view of the senders of a transaction pool, for admission checks.
+///
+/// The state of the accounts is read from a [CacheDB], usually layered over the latest block,
+/// and the pending transactions are tracked by sender and nonce with their maximum cost, so the
+/// next nonce and the balance left to a sender are known without executing its transactions.
+#[derive(Clone, Debug)]
+pub struct PendingState<DB> {
+    db: CacheDB<DB>,
+    price_bump: u64,
+    pending: HashMap<Address, BTreeMap<u64, PendingTx>>,
+}
+
+impl<DB: DatabaseRef> PendingState<DB> {
+    /// Creates a view over `db` without pending transactions.
+    pub fn new(db: CacheDB<DB>) -> Self {
+        Self {
+            db,
+            price_bump: DEFAULT_PRICE_BUMP,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Sets the percentage by which replacements must raise the fees, [DEFAULT_PRICE_BUMP] by
+    /// default.
+    pub fn with_price_bump(mut self, price_bump: u64) -> Self {
+        self.price_bump = price_bump;
+        self
+    }
+
+    /// Returns the state the pending transactions apply to.
+    pub fn db(&self) -> &CacheDB<DB> {
+        &self.db
+    }
+
+    /// Returns the state the pending transactions apply to, to update it after a block.
+    ///
+    /// See [Self::prune] to drop the transactions included by the block.
+    pub fn db_mut(&mut self) -> &mut CacheDB<DB> {
+        &mut self.db
+    }
+
+    /// Returns the number of pending transactions of `address`.
+    pub fn pending_count(&self, address: Address) -> usize {
+        self.pending.get(&address).map_or(0, BTreeMap::len)
+    }
+
+    /// Returns the nonce of the next executable transaction of `address`, after the pending
+    /// transactions without a nonce gap.
+    pub fn next_nonce(&self, address: Address) -> Result<u64, DB::Error> {
+        let (mut nonce, _) = self.account(address)?;
+        if let Some(pending) = self.pending.get(&address) {
+            while pending.contains_key(&nonce) {
+                nonce += 1;
+            }
+        }
+        Ok(nonce)
+    }
+
+    /// Returns the balance of `address` left after the maximum cost of its executable pending
+    /// transactions.
+    pub fn pending_balance(&self, address: Address) -> Result<U256, DB::Error> {
+        let next_nonce = self.next_nonce(address)?;
+        let (_, balance) = self.account(address)?;
+        Ok(balance.saturating_sub(self.pending_cost(address, next_nonce)))
+    }
+
+    /// Checks whether `tx` can join the pending transactions of its sender.
+    ///
+    /// A transaction without a nonce is given the next nonce of the sender. Its cost is checked
+    /// against the balance left after the pending transactions with a lower nonce.
+    pub fn validate(&self, tx: &TxEnv) -> Result<Admission, AdmissionError<DB::Error>> {
+        let (state_nonce, balance) = self.account(tx.caller).map_err(AdmissionError::Database)?;
+        let next_nonce = self
+            .next_nonce(tx.caller)
+            .map_err(AdmissionError::Database)?;
+        let nonce = tx.nonce.unwrap_or(next_nonce);
+        if nonce < state_nonce {
+            return Err(AdmissionError::NonceTooLow {
+                tx: nonce,
+                state: state_nonce,
+            });
+        }
+
+        let candidate = PendingTx::new(tx);
+        let replaced = self
+            .pending
+            .get(&tx.caller)
+            .and_then(|pending| pending.get(&nonce));
+        if let Some(replaced) = replaced {
+            let required_gas_price = self.bumped(replaced.gas_price);
+            let required_priority_fee = self.bumped(replaced.priority_fee);
+            if candidate.gas_price < required_gas_price
+                || candidate.priority_fee < required_priority_fee
+            {
+                return Err(AdmissionError::Underpriced {
+                    required_gas_price,
+                    required_priority_fee,
+                });
+            }
+        }
+
+        let balance = balance.saturating_sub(self.pending_cost(tx.caller, nonce));
+        if candidate.cost > balance {
+            return Err(AdmissionError::InsufficientFunds {
+                cost: candidate.cost,
+                balance,
+            });
+        }
+
+        Ok(if replaced.is_some() {
+            Admission::Replacement
+        } else if nonce == next_nonce {
+            Admission::Ready
+        } else {
+            Admission::Queued
+        })
+    }
+
+    /// Validates `tx` and adds it to the pending transactions, replacing the one with the same
+    /// nonce.
+    pub fn insert(&mut self, tx: &TxEnv) -> Result<Admission, AdmissionError<DB::Error>> {
+        let admission = self.validate(tx)?;
+        let nonce = match tx.nonce {
+            Some(nonce) => nonce,
+            None => self
+                .next_nonce(tx.caller)
+                .map_err(AdmissionError::Database)?,
+        };
+        self.pending
+            .entry(tx.caller)
+            .or_default()
+            .insert(nonce, PendingTx::new(tx));
+        Ok(admission)
+    }
+
+    /// Removes the pending transaction of `address` with `nonce`, returning whether it existed.
+    pub fn remove(&mut self, address: Address, nonce: u64) -> bool {
+        let Some(pending) = self.pending.get_mut(&address) else {
+            return false;
+        };
+        let removed = pending.remove(&nonce).is_some();
+        if pending.is_empty() {
+            self.pending.remove(&address);
+        }
+        removed
+    }
+
+    /// Drops the pending transactions whose nonce is used by the state.
+    pub fn prune(&mut self) -> Result<(), DB::Error> {
+        let mut senders = Vec::with_capacity(self.pending.len());
+        for address in self.pending.keys() {
+            senders.push((*address, self.account(*address)?.0));
+        }
+        for (address, state_nonce) in senders {
+            if let Some(pending) = self.pending.get_mut(&address) {
+                *pending = pending.split_off(&state_nonce);
+                if pending.is_empty() {
+                    self.pending.remove(&address);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the nonce and balance of `address` in the state.
+    fn account(&self, address: Address) -> Result<(u64, U256), DB::Error> {
+        Ok(self
+            .db
+            .basic_ref(address)?
+            .map(|info| (info.nonce, info.balance))
+            .unwrap_or_default())
+    }
+
+    /// Returns the cost of the pending transactions of `address` with a nonce below `nonce`.
+    fn pending_cost(&self, address: Address, nonce: u64) -> U256 {
+        self.pending.get(&address).map_or(U256::ZERO, |pending| {
+            pending
+                .range(..nonce)
+                .fold(U256::ZERO, |cost, (_, tx)| cost.saturating_add(tx.cost))
+        })
+    }
+
+    /// Returns the minimum fee of a replacement, which is always above the replaced one.
+    fn bumped(&self, fee: U256) -> U256 {
+        let bumped = fee.saturating_mul(U256::from(100 + self.price_bump)) / U256::from(100);
+        bumped.max(fee.saturating_add(U256::from(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::EmptyDB,
+        primitives::{AccountInfo, TransactTo},
+    };
+
+    fn tx(nonce: u64, gas_price: u64) -> TxEnv {
+        TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(Address::with_last_byte(2)),
+            gas_limit: 21_000,
+            gas_price: U256::from(gas_price),
+            nonce: Some(nonce),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nonce_gap_and_replacement() {
+        let sender = Address::with_last_byte(1);
+        let mut db = CacheDB::new(EmptyDB::default());
+        let mut info = AccountInfo::from_balance(U256::from(100_000));
+        info.nonce = 3;
+        db.insert_account_info(sender, info);
+        let mut pool = PendingState::new(db);
+
+        assert_eq!(
+            pool.insert(&tx(2, 1)),
+            Err(AdmissionError::NonceTooLow { tx: 2, state: 3 })
+        );
+        assert_eq!(pool.insert(&tx(3, 1)), Ok(Admission::Ready));
+        assert_eq!(pool.insert(&tx(5, 1)), Ok(Admission::Queued));
+        assert_eq!(pool.next_nonce(sender), Ok(4));
+        assert_eq!(pool.pending_balance(sender), Ok(U256::from(79_000)));
+
+        assert!(matches!(
+            pool.insert(&tx(3, 1)),
+            Err(AdmissionError::Underpriced { .. })
+        ));
+        assert_eq!(pool.insert(&tx(3, 2)), Ok(Admission::Replacement));
+        // The nonce gap is filled, the balance covers two more transactions at most.
+        assert_eq!(pool.insert(&tx(4, 1)), Ok(Admission::Ready));
+        assert_eq!(pool.next_nonce(sender), Ok(6));
+        assert!(matches!(
+            pool.validate(&tx(6, 2)),
+            Err(AdmissionError::InsufficientFunds { .. })
+        ));
+
+        pool.db_mut().accounts.get_mut(&sender).unwrap().info.nonce = 5;
+        pool.prune().unwrap();
+        assert_eq!(pool.pending_count(sender), 1);
+        assert_eq!(pool.next_nonce(sender), Ok(6));
+    }
+}
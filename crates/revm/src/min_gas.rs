@@ -0,0 +1,203 @@
+use crate::{
+    db::Database,
+    primitives::{EVMError, ExecutionResult},
+    Evm,
+};
+use core::fmt;
+
+/// Minimum gas limit of a transaction, found by [Evm::min_gas_limit].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MinGasLimit {
+    /// Smallest gas limit the transaction succeeds with. The transaction fails with one gas
+    /// less, both were checked by execution.
+    pub gas_limit: u64,
+    /// Gas used by the transaction with [Self::gas_limit].
+    pub gas_used: u64,
+    /// Number of executions of the search.
+    pub executions: u64,
+}
+
+/// Failure of a search for the minimum gas limit of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MinGasError<DBError> {
+    /// The transaction is invalid with its gas limit, or execution failed with an error.
+    Evm(EVMError<DBError>),
+    /// The transaction does not succeed with its gas limit.
+    Failed(ExecutionResult),
+}
+
+impl<DBError> From<EVMError<DBError>> for MinGasError<DBError> {
+    fn from(error: EVMError<DBError>) -> Self {
+        Self::Evm(error)
+    }
+}
+
+impl<DBError: fmt::Display> fmt::Display for MinGasError<DBError> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm(error) => error.fmt(f),
+            Self::Failed(result) => write!(f, "transaction fails with its gas limit: {result:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DBError: std::error::Error + 'static> std::error::Error for MinGasError<DBError> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Evm(error) => Some(error),
+            Self::Failed(_) => None,
+        }
+    }
+}
+
+impl<EXT, DB: Database> Evm<'_, EXT, DB> {
+    /// Searches the smallest gas limit up to the gas limit of the transaction with which the
+    /// transaction succeeds, executing it without committing.
+    ///
+    /// The gas spent by a successful execution is not enough in general: a call forwards at most
+    /// 63/64 of the remaining gas, so the caller keeps gas it never spends, and code may take
+    /// different paths depending on the remaining gas. Candidates are checked by execution,
+    /// starting with the spent gas and the bound of the 63/64 rule before bisecting, and the
+    /// result is certified by an execution failing with one gas less. With gas dependent code, a
+    /// smaller limit taking another successful path may exist below a failing one.
+    ///
+    /// Limits making the transaction invalid, such as below the intrinsic gas, count as failing.
+    /// The transaction of the EVM is restored after the search.
+    pub fn min_gas_limit(&mut self) -> Result<MinGasLimit, MinGasError<DB::Error>> {
+        let gas_limit = self.tx().gas_limit;
+        let result = self.min_gas_limit_inner(gas_limit);
+        self.tx_mut().gas_limit = gas_limit;
+        result
+    }
+
+    fn min_gas_limit_inner(
+        &mut self,
+        gas_limit: u64,
+    ) -> Result<MinGasLimit, MinGasError<DB::Error>> {
+        self.tx_mut().gas_limit = gas_limit;
+        let output = self.transact()?;
+        let ExecutionResult::Success {
+            gas_used,
+            gas_refunded,
+            ..
+        } = output.result
+        else {
+            return Err(MinGasError::Failed(output.result));
+        };
+
+        let mut executions = 1;
+        let mut run = |evm: &mut Self, limit: u64| -> Result<Option<u64>, EVMError<DB::Error>> {
+            executions += 1;
+            evm.tx_mut().gas_limit = limit;
+            match evm.transact() {
+                Ok(output) if output.result.is_success() => Ok(Some(output.result.gas_used())),
+                Ok(_) | Err(EVMError::Transaction(_)) => Ok(None),
+                Err(error) => Err(error),
+            }
+        };
+
+        // `high` succeeds using `high_used` gas, `low` failed when executed. No transaction is
+        // valid with a zero gas limit, so `low` is known to fail before it is first executed.
+        let (mut high, mut high_used) = (gas_limit, gas_used);
+        let mut low = 0;
+        let spent = gas_used + gas_refunded;
+        let candidates = [
+            spent,
+            spent.saturating_mul(64) / 63 + 1,
+            spent.saturating_mul(2),
+        ];
+        for candidate in candidates {
+            if candidate <= low || candidate >= high {
+                continue;
+            }
+            match run(self, candidate)? {
+                Some(used) => {
+                    (high, high_used) = (candidate, used);
+                    break;
+                }
+                None => low = candidate,
+            }
+        }
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            match run(self, middle)? {
+                Some(used) => (high, high_used) = (middle, used),
+                None => low = middle,
+            }
+        }
+        Ok(MinGasLimit {
+            gas_limit: high,
+            gas_used: high_used,
+            executions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        interpreter::opcode,
+        primitives::{AccountInfo, Address, Bytecode, Bytes, TransactTo, U256},
+    };
+
+    #[test]
+    fn min_gas_limit_of_forwarding_call() {
+        // Child: SSTORE(0, 1).
+        let child = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH1,
+            1,
+            opcode::PUSH0,
+            opcode::SSTORE,
+            opcode::STOP,
+        ]));
+        // Parent: CALL(GAS, child, 0, 0, 0, 0, 0), reverts if the call failed.
+        let parent = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::PUSH1,
+            2,
+            opcode::GAS,
+            opcode::CALL,
+            opcode::ISZERO,
+            opcode::PUSH1,
+            14,
+            opcode::JUMPI,
+            opcode::STOP,
+            opcode::JUMPDEST,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::REVERT,
+        ]));
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, code) in [(1, parent), (2, child)] {
+            let info = AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code);
+            db.insert_account_info(Address::with_last_byte(address), info);
+        }
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .modify_tx_env(|tx| {
+                tx.caller = Address::with_last_byte(0x10);
+                tx.transact_to = TransactTo::Call(Address::with_last_byte(1));
+                tx.gas_limit = 1_000_000;
+            })
+            .build();
+
+        let min = evm.min_gas_limit().unwrap();
+        assert_eq!(evm.tx().gas_limit, 1_000_000);
+        // The parent keeps 1/64 of its gas, the limit is above the spent gas.
+        assert!(min.gas_limit > min.gas_used);
+        for (limit, success) in [(min.gas_limit, true), (min.gas_limit - 1, false)] {
+            evm.tx_mut().gas_limit = limit;
+            assert_eq!(evm.transact().unwrap().result.is_success(), success);
+        }
+
+        evm.tx_mut().gas_limit = 30_000;
+        assert!(matches!(evm.min_gas_limit(), Err(MinGasError::Failed(_))));
+    }
+}
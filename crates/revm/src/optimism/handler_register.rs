@@ -8,8 +8,9 @@ use crate::{
     interpreter::{return_ok, return_revert, Gas, InstructionResult},
     optimism,
     primitives::{
-        db::Database, spec_to_generic, Account, EVMError, Env, ExecutionResult, HaltReason,
-        HashMap, InvalidTransaction, ResultAndState, Spec, SpecId, SpecId::REGOLITH, U256,
+        db::Database, spec_to_generic, Account, EVMError, Env, ExecutionMetadata, ExecutionResult,
+        HaltReason, HashMap, InvalidTransaction, ResultAndState, Spec, SpecId, SpecId::REGOLITH,
+        U256,
     },
     Context, FrameResult,
 };
@@ -133,7 +134,8 @@ pub fn last_frame_return<SPEC: Spec, EXT, DB: Database>(
     // Prior to Regolith, deposit transactions did not receive gas refunds.
     let is_gas_refund_disabled = env.cfg.is_gas_refund_disabled() || (is_deposit && !is_regolith);
     if !is_gas_refund_disabled {
-        gas.set_final_refund(SPEC::SPEC_ID.is_enabled_in(SpecId::LONDON));
+        let max_refund = env.cfg.refund_policy.max_refund(SPEC::SPEC_ID, gas.spent());
+        gas.cap_refund(max_refund);
     }
     Ok(())
 }
@@ -148,7 +150,7 @@ pub fn load_accounts<SPEC: Spec, EXT, DB: Database>(
     if context.evm.inner.env.tx.optimism.source_hash.is_none() {
         let l1_block_info =
             crate::optimism::L1BlockInfo::try_fetch(&mut context.evm.inner.db, SPEC::SPEC_ID)
-                .map_err(EVMError::Database)?;
+                .map_err(EVMError::database)?;
 
         // storage l1 block info for later use.
         context.evm.inner.l1_block_info = Some(l1_block_info);
@@ -197,7 +199,9 @@ pub fn deduct_caller<SPEC: Spec, EXT, DB: Database>(
             .as_ref()
             .expect("L1BlockInfo should be loaded")
             .calculate_tx_l1_cost(enveloped_tx, SPEC::SPEC_ID);
-        if tx_l1_cost.gt(&caller_account.info.balance) {
+        if tx_l1_cost.gt(&caller_account.info.balance)
+            && !context.evm.inner.env.cfg.is_balance_check_disabled()
+        {
             return Err(EVMError::Transaction(
                 InvalidTransaction::LackOfFundForMaxFee {
                     fee: tx_l1_cost.into(),
@@ -360,6 +364,7 @@ pub fn end<SPEC: Spec, EXT, DB: Database>(
                     gas_used,
                 },
                 state,
+                metadata: ExecutionMetadata::default(),
             })
         } else {
             Err(err)
@@ -390,11 +395,7 @@ mod tests {
         let mut ctx = Context::new_empty();
         ctx.evm.inner.env = Box::new(env);
         let mut first_frame = FrameResult::Call(CallOutcome::new(
-            InterpreterResult {
-                result: instruction_result,
-                output: Bytes::new(),
-                gas,
-            },
+            InterpreterResult::new(instruction_result, Bytes::new(), gas),
             0..0,
         ));
         last_frame_return::<SPEC, _, _>(&mut ctx, &mut first_frame).unwrap();
@@ -0,0 +1,239 @@
+use crate::{
+    db::Database,
+    interpreter::SharedMemory,
+    primitives::{Env, TxEnv},
+    Evm,
+};
+use core::{
+    cell::RefCell,
+    fmt,
+    ops::{Deref, DerefMut},
+};
+use std::{boxed::Box, vec::Vec};
+
+/// Pool of built EVMs, checked out to execute a request and returned to the pool when dropped.
+///
+/// Building an [Evm] creates its handler, including the registers and the instruction table,
+/// and every transaction allocates the memory of the interpreter. Servers executing many
+/// independent calls, such as `eth_call`, reuse the instances of the pool instead: the
+/// handler is kept and the memory buffer of an instance is reused by its next transaction.
+///
+/// Returned instances are reset to the environment they were built with, their transaction
+/// state is discarded and the error of a failed execution is cleared. The database and the
+/// external context are kept, [Self::checkout_with_db] replaces the database of the instance.
+///
+/// As EVMs are not `Send`, a multithreaded server keeps a pool per thread.
+pub struct EvmPool<'a, EXT, DB: Database> {
+    build: Box<dyn Fn() -> Evm<'a, EXT, DB> + 'a>,
+    idle: RefCell<Vec<Evm<'a, EXT, DB>>>,
+    env: Box<Env>,
+    memory_capacity: usize,
+    max_idle: usize,
+}
+
+impl<EXT, DB: Database> fmt::Debug for EvmPool<'_, EXT, DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EvmPool")
+            .field("idle", &self.idle.borrow().len())
+            .field("memory_capacity", &self.memory_capacity)
+            .field("max_idle", &self.max_idle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, EXT, DB: Database> EvmPool<'a, EXT, DB> {
+    /// Default initial capacity of the memory buffer of the instances, 4KiB.
+    pub const DEFAULT_MEMORY_CAPACITY: usize = 4 * 1024;
+
+    /// Creates a pool of instances created by `build`.
+    ///
+    /// `build` is called once to record the environment of the instances, then whenever an
+    /// instance is checked out of an empty pool.
+    pub fn new(build: impl Fn() -> Evm<'a, EXT, DB> + 'a) -> Self {
+        let evm = build();
+        let env = evm.context.evm.env.clone();
+        let pool = Self {
+            build: Box::new(build),
+            idle: RefCell::new(Vec::new()),
+            env,
+            memory_capacity: Self::DEFAULT_MEMORY_CAPACITY,
+            max_idle: usize::MAX,
+        };
+        pool.release(evm);
+        pool
+    }
+
+    /// Sets the initial capacity of the memory buffer of the instances built after this call.
+    pub fn with_memory_capacity(mut self, capacity: usize) -> Self {
+        self.memory_capacity = capacity;
+        self
+    }
+
+    /// Sets the number of idle instances kept, the others are dropped when returned.
+    pub fn with_max_idle(mut self, max_idle: usize) -> Self {
+        self.max_idle = max_idle;
+        self.idle.get_mut().truncate(max_idle);
+        self
+    }
+
+    /// Builds instances until `count` are idle.
+    pub fn prewarm(&self, count: usize) {
+        let count = count.min(self.max_idle);
+        while self.idle() < count {
+            let evm = self.build();
+            self.idle.borrow_mut().push(evm);
+        }
+    }
+
+    /// Returns the number of idle instances.
+    pub fn idle(&self) -> usize {
+        self.idle.borrow().len()
+    }
+
+    /// Checks out an idle instance, building one if there is none.
+    pub fn checkout(&self) -> PooledEvm<'_, 'a, EXT, DB> {
+        let evm = self.idle.borrow_mut().pop();
+        PooledEvm {
+            evm: Some(evm.unwrap_or_else(|| self.build())),
+            pool: self,
+        }
+    }
+
+    /// Checks out an instance executing against `db`.
+    pub fn checkout_with_db(&self, db: DB) -> PooledEvm<'_, 'a, EXT, DB> {
+        let mut evm = self.checkout();
+        *evm.db_mut() = db;
+        evm
+    }
+
+    /// Checks out an instance with `tx` set, see [Self::checkout_with_db].
+    pub fn checkout_with_tx(&self, db: DB, tx: TxEnv) -> PooledEvm<'_, 'a, EXT, DB> {
+        let mut evm = self.checkout_with_db(db);
+        *evm.tx_mut() = tx;
+        evm
+    }
+
+    fn build(&self) -> Evm<'a, EXT, DB> {
+        let mut evm = (self.build)();
+        evm.set_shared_memory(SharedMemory::with_capacity(self.memory_capacity));
+        evm
+    }
+
+    fn release(&self, mut evm: Evm<'a, EXT, DB>) {
+        let mut idle = self.idle.borrow_mut();
+        if idle.len() >= self.max_idle {
+            return;
+        }
+        let context = &mut evm.context.evm;
+        context.journaled_state.finalize();
+        context.error = Ok(());
+        context.env.clone_from(&self.env);
+        idle.push(evm);
+    }
+}
+
+/// [Evm] checked out of an [EvmPool], returned to the pool when dropped.
+pub struct PooledEvm<'p, 'a, EXT, DB: Database> {
+    evm: Option<Evm<'a, EXT, DB>>,
+    pool: &'p EvmPool<'a, EXT, DB>,
+}
+
+impl<EXT, DB: Database> fmt::Debug for PooledEvm<'_, '_, EXT, DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledEvm").finish_non_exhaustive()
+    }
+}
+
+impl<'a, EXT, DB: Database> PooledEvm<'_, 'a, EXT, DB> {
+    /// Takes the instance out of the pool, it is not returned when dropped.
+    pub fn detach(mut self) -> Evm<'a, EXT, DB> {
+        self.evm.take().expect("instance is present until dropped")
+    }
+}
+
+impl<'a, EXT, DB: Database> Deref for PooledEvm<'_, 'a, EXT, DB> {
+    type Target = Evm<'a, EXT, DB>;
+
+    fn deref(&self) -> &Self::Target {
+        self.evm
+            .as_ref()
+            .expect("instance is present until dropped")
+    }
+}
+
+impl<EXT, DB: Database> DerefMut for PooledEvm<'_, '_, EXT, DB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.evm
+            .as_mut()
+            .expect("instance is present until dropped")
+    }
+}
+
+impl<EXT, DB: Database> Drop for PooledEvm<'_, '_, EXT, DB> {
+    fn drop(&mut self) {
+        if let Some(evm) = self.evm.take() {
+            self.pool.release(evm);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::BenchmarkDB,
+        interpreter::opcode,
+        primitives::{Address, Bytecode, Bytes, TransactTo, U256},
+    };
+    use core::cell::Cell;
+
+    #[test]
+    fn reuse_instances() {
+        // MSTORE(0, 1) RETURN(0, 32)
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH1,
+            1,
+            opcode::PUSH0,
+            opcode::MSTORE,
+            opcode::PUSH1,
+            32,
+            opcode::PUSH0,
+            opcode::RETURN,
+        ]));
+        let builds = Cell::new(0);
+        let pool = EvmPool::new(|| {
+            builds.set(builds.get() + 1);
+            Evm::builder()
+                .with_db(BenchmarkDB::new_bytecode(code.clone()))
+                .modify_tx_env(|tx| tx.caller = Address::with_last_byte(1))
+                .build()
+        })
+        .with_max_idle(2);
+        pool.prewarm(4);
+        assert_eq!((pool.idle(), builds.get()), (2, 2));
+
+        let tx = TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(Address::ZERO),
+            data: Bytes::from_static(&[1]),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        for _ in 0..3 {
+            let mut evm =
+                pool.checkout_with_tx(BenchmarkDB::new_bytecode(code.clone()), tx.clone());
+            let output = evm.transact().unwrap().result.into_output().unwrap();
+            assert_eq!(U256::from_be_slice(&output), U256::from(1));
+        }
+        assert_eq!((pool.idle(), builds.get()), (2, 2));
+
+        // Returned instances are reset to the environment they were built with.
+        let (first, second) = (pool.checkout(), pool.checkout());
+        assert!(first.tx().data.is_empty());
+        assert_eq!(first.tx().caller, Address::with_last_byte(1));
+        let third = pool.checkout();
+        assert_eq!(builds.get(), 3);
+        drop((first, second, third));
+        assert_eq!(pool.idle(), 2);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{
+    db::{states::bundle_state::BundleRetention, BundleAccount, CacheState, Database, State},
+    primitives::{Address, BlockEnv, EVMError, TxEnv, U256},
+    Evm, ExecutedTx,
+};
+use std::{collections::BTreeSet, vec::Vec};
+
+/// Outcome of [Evm::reorg].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reorg {
+    /// Number of blocks unwound, less than the requested depth if not enough reverts were
+    /// retained.
+    pub unwound: usize,
+    /// Results of the transactions of the blocks of the new chain, per block.
+    pub executed: Vec<Vec<ExecutedTx>>,
+    /// Accounts whose info or storage at the new tip differs from the old tip, sorted.
+    pub diverged: Vec<Address>,
+}
+
+impl<EXT, DB: Database> Evm<'_, EXT, State<DB>> {
+    /// Executes and commits blocks, merging the transitions of each block into the bundle with
+    /// their reverts, so they can be unwound by [Self::unwind_blocks].
+    ///
+    /// The state must be built with
+    /// [StateBuilder::with_bundle_update](crate::db::StateBuilder::with_bundle_update). On error,
+    /// the transactions of the failed block executed before the error are left unmerged.
+    pub fn execute_blocks<B, T>(
+        &mut self,
+        blocks: B,
+    ) -> Result<Vec<Vec<ExecutedTx>>, EVMError<DB::Error>>
+    where
+        B: IntoIterator<Item = (BlockEnv, T)>,
+        T: IntoIterator<Item = TxEnv>,
+    {
+        let mut executed = Vec::new();
+        for (block, txs) in blocks {
+            *self.block_mut() = block;
+            executed.push(self.execute_block(txs).collect::<Result<Vec<_>, _>>()?);
+            self.db_mut().merge_transitions(BundleRetention::Reverts);
+        }
+        Ok(executed)
+    }
+
+    /// Unwinds the latest `depth` blocks merged into the bundle with their reverts, and returns
+    /// the number of blocks unwound.
+    ///
+    /// Transitions not merged yet are discarded. The cache is cleared and the bundle is used as a
+    /// layer over the database, so execution continues from the state before the unwound blocks.
+    pub fn unwind_blocks(&mut self, depth: usize) -> usize {
+        let state = self.db_mut();
+        if let Some(transition_state) = state.transition_state.as_mut() {
+            transition_state.take();
+        }
+        let retained = state.bundle_state.reverts.len();
+        state.bundle_state.revert(depth);
+        state.cache = CacheState::new(state.cache.has_state_clear);
+        state.use_preloaded_bundle = true;
+        retained - state.bundle_state.reverts.len()
+    }
+
+    /// Replaces the latest `depth` blocks with `blocks`, as in a chain reorganization, and
+    /// reports the accounts whose state diverged between the two chains.
+    ///
+    /// The blocks are unwound with [Self::unwind_blocks] and the new ones executed with
+    /// [Self::execute_blocks], transitions not merged yet are discarded. Accounts are compared at the tips of both chains, so an account
+    /// changed by both chains to the same state does not diverge.
+    pub fn reorg<B, T>(&mut self, depth: usize, blocks: B) -> Result<Reorg, EVMError<DB::Error>>
+    where
+        B: IntoIterator<Item = (BlockEnv, T)>,
+        T: IntoIterator<Item = TxEnv>,
+    {
+        let old_tip = self.db().bundle_state.state.clone();
+        let unwound = self.unwind_blocks(depth);
+        let executed = self.execute_blocks(blocks)?;
+        let new_tip = &self.db().bundle_state.state;
+        let diverged = old_tip
+            .keys()
+            .chain(new_tip.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|address| diverges(old_tip.get(*address), new_tip.get(*address)))
+            .copied()
+            .collect();
+        Ok(Reorg {
+            unwound,
+            executed,
+            diverged,
+        })
+    }
+}
+
+/// Returns whether the present state of an account differs between two bundles. An account
+/// missing from a bundle has the original state known by the other.
+fn diverges(old: Option<&BundleAccount>, new: Option<&BundleAccount>) -> bool {
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (old, new),
+        (Some(account), None) | (None, Some(account)) => {
+            return account.is_info_changed()
+                || account.was_destroyed()
+                || account
+                    .storage
+                    .values()
+                    .any(|slot| slot.present_value != slot.previous_or_original_value)
+        }
+        (None, None) => return false,
+    };
+    let present = |account: &BundleAccount, other: &BundleAccount, slot: &U256| {
+        account
+            .storage_slot(*slot)
+            .unwrap_or_else(|| other.storage[slot].previous_or_original_value)
+    };
+    old.info != new.info
+        || old
+            .storage
+            .keys()
+            .chain(new.storage.keys())
+            .any(|slot| present(old, new, slot) != present(new, old, slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, TransactTo},
+    };
+
+    fn transfer(nonce: u64, to: u8) -> TxEnv {
+        TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(Address::with_last_byte(to)),
+            value: U256::from(1),
+            gas_limit: 21_000,
+            nonce: Some(nonce),
+            ..Default::default()
+        }
+    }
+
+    fn block(number: u64) -> BlockEnv {
+        BlockEnv {
+            number: U256::from(number),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reorg_reports_diverged_accounts() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            Address::with_last_byte(1),
+            AccountInfo::from_balance(U256::from(10)),
+        );
+        let state = State::builder()
+            .with_database(db)
+            .with_bundle_update()
+            .build();
+        let mut evm = Evm::builder().with_db(state).build();
+
+        evm.execute_blocks([
+            (block(1), vec![transfer(0, 2)]),
+            (block(2), vec![transfer(1, 3)]),
+        ])
+        .unwrap();
+
+        // The second block is replaced by one sending to another account, the sender ends in
+        // the same state on both chains.
+        let reorg = evm.reorg(1, [(block(2), vec![transfer(1, 4)])]).unwrap();
+        assert_eq!(reorg.unwound, 1);
+        assert_eq!(reorg.executed[0][0].cumulative_gas_used, 21_000);
+        assert_eq!(
+            reorg.diverged,
+            [Address::with_last_byte(3), Address::with_last_byte(4)]
+        );
+
+        let balance = |evm: &mut Evm<'_, (), State<CacheDB<EmptyDB>>>, address: u8| {
+            evm.db_mut()
+                .basic(Address::with_last_byte(address))
+                .unwrap()
+                .map(|info| info.balance)
+        };
+        assert_eq!(balance(&mut evm, 2), Some(U256::from(1)));
+        assert_eq!(balance(&mut evm, 3), None);
+        assert_eq!(balance(&mut evm, 4), Some(U256::from(1)));
+
+        // Unwinding past the retained reverts stops at the first block.
+        assert_eq!(evm.unwind_blocks(5), 2);
+        assert_eq!(balance(&mut evm, 1), Some(U256::from(10)));
+    }
+}
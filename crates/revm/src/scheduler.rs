@@ -0,0 +1,190 @@
+//! Scheduling of block transactions for parallel execution.
+//!
+//! A [BlockScheduler] takes the predicted [ReadWriteSet] of every transaction of a block and
+//! groups the transactions into waves. Transactions of one wave do not conflict with each other
+//! and only depend on transactions of earlier waves, so the waves can be executed one after the
+//! other with the transactions of each wave executed in parallel.
+//!
+//! Predictions come from [predict_access], which combines what is known from the transaction
+//! itself with the read and write set recorded when the transaction, or a similar one, was
+//! executed before. A wrong prediction does not make the schedule unsafe to use as long as the
+//! executor validates the recorded sets against the predicted ones and re-executes the
+//! transactions whose prediction was wrong.
+use crate::{
+    db::ReadWriteSet,
+    primitives::{Address, HashSet, TransactTo, TxEnv, U256},
+};
+use std::vec::Vec;
+
+/// Returns the predicted read and write set of a transaction.
+///
+/// The caller is read and written, the callee is read and also written if value is transferred.
+/// Access list slots are assumed to be both read and written, as the access list does not
+/// distinguish them. The recorded `history` of the transaction, if any, is added to that.
+pub fn predict_access(tx: &TxEnv, history: Option<&ReadWriteSet>) -> ReadWriteSet {
+    let mut set = history.cloned().unwrap_or_default();
+    set.reads.accounts.insert(tx.caller);
+    set.writes.accounts.insert(tx.caller);
+    if let TransactTo::Call(to) = tx.transact_to {
+        set.reads.accounts.insert(to);
+        if tx.value != U256::ZERO {
+            set.writes.accounts.insert(to);
+        }
+    }
+    for (address, slots) in &tx.access_list {
+        set.reads.accounts.insert(*address);
+        set.reads
+            .storage
+            .entry(*address)
+            .or_default()
+            .extend(slots.iter().copied());
+        set.writes
+            .storage
+            .entry(*address)
+            .or_default()
+            .extend(slots.iter().copied());
+    }
+    set
+}
+
+/// Execution schedule of a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schedule {
+    waves: Vec<Vec<usize>>,
+    dependencies: Vec<Vec<usize>>,
+}
+
+impl Schedule {
+    /// Returns the waves, each a list of transaction indices in block order.
+    pub fn waves(&self) -> &[Vec<usize>] {
+        &self.waves
+    }
+
+    /// Returns the indices of the earlier transactions the transaction conflicts with.
+    pub fn dependencies(&self, tx: usize) -> &[usize] {
+        &self.dependencies[tx]
+    }
+
+    /// Returns the number of scheduled transactions.
+    pub fn len(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    /// Returns `true` if no transactions are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+}
+
+/// Builds [Schedule]s from predicted read and write sets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockScheduler {
+    ignored: HashSet<Address>,
+}
+
+impl BlockScheduler {
+    /// Creates a scheduler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignores conflicts on the balance, nonce and code of the account.
+    ///
+    /// Every transaction pays the block beneficiary, which would otherwise serialize the whole
+    /// block. Executors that apply such commutative balance increments after the fact can
+    /// ignore the beneficiary.
+    pub fn ignore_account(mut self, address: Address) -> Self {
+        self.ignored.insert(address);
+        self
+    }
+
+    /// Schedules transactions given their read and write sets in block order.
+    ///
+    /// Every pair of transactions is compared, so this is quadratic in the number of
+    /// transactions.
+    pub fn schedule(&self, sets: &[ReadWriteSet]) -> Schedule {
+        let sets: Vec<_> = sets.iter().map(|set| self.strip(set)).collect();
+        let mut waves: Vec<Vec<usize>> = Vec::new();
+        let mut wave_of = Vec::with_capacity(sets.len());
+        let mut dependencies = Vec::with_capacity(sets.len());
+        for (tx, set) in sets.iter().enumerate() {
+            let deps: Vec<usize> = (0..tx)
+                .filter(|earlier| set.conflicts_with(&sets[*earlier]))
+                .collect();
+            let wave = deps.iter().map(|dep| wave_of[*dep] + 1).max().unwrap_or(0);
+            if wave == waves.len() {
+                waves.push(Vec::new());
+            }
+            waves[wave].push(tx);
+            wave_of.push(wave);
+            dependencies.push(deps);
+        }
+        Schedule {
+            waves,
+            dependencies,
+        }
+    }
+
+    fn strip(&self, set: &ReadWriteSet) -> ReadWriteSet {
+        let mut set = set.clone();
+        if !self.ignored.is_empty() {
+            set.reads
+                .accounts
+                .retain(|address| !self.ignored.contains(address));
+            set.writes
+                .accounts
+                .retain(|address| !self.ignored.contains(address));
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(caller: u8, access_list: Vec<(Address, Vec<U256>)>) -> TxEnv {
+        TxEnv {
+            caller: Address::with_last_byte(caller),
+            transact_to: TransactTo::Call(Address::with_last_byte(0x10)),
+            access_list,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn waves_of_non_conflicting_transactions() {
+        let pool = Address::with_last_byte(0x20);
+        let txs = [
+            tx(1, vec![(pool, vec![U256::from(1)])]),
+            tx(2, vec![(pool, vec![U256::from(1)])]),
+            tx(3, vec![(pool, vec![U256::from(2)])]),
+            tx(1, vec![]),
+        ];
+        let sets: Vec<_> = txs.iter().map(|tx| predict_access(tx, None)).collect();
+        let schedule = BlockScheduler::new().schedule(&sets);
+
+        assert_eq!(schedule.waves(), &[vec![0, 2], vec![1, 3]]);
+        assert_eq!(schedule.dependencies(1), &[0]);
+        // Same caller, the nonce is written by both.
+        assert_eq!(schedule.dependencies(3), &[0]);
+    }
+
+    #[test]
+    fn history_and_ignored_accounts() {
+        let beneficiary = Address::with_last_byte(0xff);
+        let mut history = ReadWriteSet::default();
+        history.reads.accounts.insert(beneficiary);
+        history.writes.accounts.insert(beneficiary);
+        let sets = [
+            predict_access(&tx(1, vec![]), Some(&history)),
+            predict_access(&tx(2, vec![]), Some(&history)),
+        ];
+
+        assert_eq!(BlockScheduler::new().schedule(&sets).waves().len(), 2);
+        let schedule = BlockScheduler::new()
+            .ignore_account(beneficiary)
+            .schedule(&sets);
+        assert_eq!(schedule.waves(), &[vec![0, 1]]);
+    }
+}
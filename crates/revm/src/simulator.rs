@@ -0,0 +1,277 @@
+use crate::{
+    db::{CacheDB, Database, DatabaseRef},
+    primitives::{Address, DatabaseAccess, EVMError, ExecutionResult, TxEnv, U256},
+    Evm,
+};
+use core::ops::RangeInclusive;
+use std::{string::String, vec::Vec};
+
+/// Runs chained transactions on top of a [CacheDB], for scripting multi-transaction scenarios.
+///
+/// Every transaction is committed to the cache, and transactions without a nonce are sent with
+/// the current nonce of their caller. Labeled checkpoints snapshot the cache so that the
+/// scenario can be rewound to them.
+pub struct Simulator<'a, EXT, DB: DatabaseRef> {
+    evm: Evm<'a, EXT, CacheDB<DB>>,
+    checkpoints: Vec<(String, CacheDB<DB>)>,
+}
+
+impl<'a, EXT, DB: DatabaseRef + Clone> Simulator<'a, EXT, DB> {
+    /// Creates a simulator running transactions with `evm`.
+    pub fn new(evm: Evm<'a, EXT, CacheDB<DB>>) -> Self {
+        Self {
+            evm,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /// Returns the EVM the transactions are run with.
+    pub fn evm(&mut self) -> &mut Evm<'a, EXT, CacheDB<DB>> {
+        &mut self.evm
+    }
+
+    /// Returns the state of the scenario.
+    pub fn db(&self) -> &CacheDB<DB> {
+        self.evm.db()
+    }
+
+    /// Returns the EVM, dropping the checkpoints.
+    pub fn into_evm(self) -> Evm<'a, EXT, CacheDB<DB>> {
+        self.evm
+    }
+
+    /// Returns the nonce of `address` in the current state.
+    pub fn nonce(&mut self, address: Address) -> Result<u64, EVMError<DB::Error>> {
+        let info = self
+            .evm
+            .db_mut()
+            .basic(address)
+            .map_err(|e| EVMError::database_at(e, DatabaseAccess::Account(address)))?;
+        Ok(info.map(|info| info.nonce).unwrap_or_default())
+    }
+
+    /// Executes the transaction and commits its state.
+    ///
+    /// If the transaction has no nonce, it is sent with the current nonce of its caller.
+    pub fn transact(&mut self, mut tx: TxEnv) -> Result<ExecutionResult, EVMError<DB::Error>> {
+        if tx.nonce.is_none() {
+            tx.nonce = Some(self.nonce(tx.caller)?);
+        }
+        *self.evm.tx_mut() = tx;
+        self.evm.transact_commit()
+    }
+
+    /// Executes the transaction without committing its state.
+    pub fn call(&mut self, tx: TxEnv) -> Result<ExecutionResult, EVMError<DB::Error>> {
+        *self.evm.tx_mut() = tx;
+        Ok(self.evm.transact()?.result)
+    }
+
+    /// Saves the current state under `label`.
+    pub fn checkpoint(&mut self, label: impl Into<String>) {
+        let snapshot = self.evm.db().clone();
+        self.checkpoints.push((label.into(), snapshot));
+    }
+
+    /// Returns the labels of the checkpoints, oldest first.
+    pub fn checkpoints(&self) -> impl Iterator<Item = &str> {
+        self.checkpoints.iter().map(|(label, _)| label.as_str())
+    }
+
+    /// Restores the state of the latest checkpoint labeled `label` and drops the checkpoints
+    /// taken after it.
+    ///
+    /// Returns `false` if there is no such checkpoint.
+    pub fn revert_to(&mut self, label: &str) -> bool {
+        let Some(index) = self
+            .checkpoints
+            .iter()
+            .rposition(|(checkpoint, _)| checkpoint == label)
+        else {
+            return false;
+        };
+        self.checkpoints.truncate(index + 1);
+        *self.evm.db_mut() = self.checkpoints[index].1.clone();
+        true
+    }
+
+    /// Returns whether `tx` succeeds once `change` is applied to the EVM, without committing.
+    ///
+    /// The change is applied after `tx` is set, it may override the state, the environment or
+    /// the transaction itself. The state and the environment are restored afterwards.
+    pub fn succeeds_with(
+        &mut self,
+        tx: &TxEnv,
+        change: impl FnOnce(&mut Evm<'a, EXT, CacheDB<DB>>),
+    ) -> Result<bool, EVMError<DB::Error>> {
+        let db = self.evm.db().clone();
+        let env = self.evm.context.evm.env.clone();
+        *self.evm.tx_mut() = tx.clone();
+        change(&mut self.evm);
+        let result = self.evm.transact();
+        *self.evm.db_mut() = db;
+        self.evm.context.evm.env = env;
+        Ok(result?.result.is_success())
+    }
+
+    /// Bisects the number of leading `changes` needed for a failing `tx` to succeed.
+    ///
+    /// Returns `Some(n)` if `tx` succeeds with the first `n` changes applied and fails with
+    /// `n - 1`, so the last needed change is `changes[n - 1]`. `Some(0)` means that `tx`
+    /// already succeeds and `None` that it fails even with all the changes applied. Changes are
+    /// assumed to only help, a change making `tx` fail again after it succeeded is not detected.
+    pub fn bisect_changes<F>(
+        &mut self,
+        tx: &TxEnv,
+        changes: &[F],
+    ) -> Result<Option<usize>, EVMError<DB::Error>>
+    where
+        F: Fn(&mut Evm<'a, EXT, CacheDB<DB>>),
+    {
+        let mut succeeds = |count: usize| {
+            self.succeeds_with(tx, |evm| {
+                changes[..count].iter().for_each(|change| change(evm))
+            })
+        };
+        if !succeeds(changes.len())? {
+            return Ok(None);
+        }
+        // `tx` fails with `low` changes and succeeds with `high`.
+        let (mut low, mut high) = (0, changes.len());
+        if succeeds(low)? {
+            return Ok(Some(0));
+        }
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if succeeds(middle)? {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Ok(Some(high))
+    }
+
+    /// Bisects the smallest value of `range` for which `tx` succeeds once `apply` sets it, for
+    /// example the minimum balance of the caller or the block timestamp a call waits for.
+    ///
+    /// Returns `None` if `tx` fails with the largest value. Success is assumed to be monotonic
+    /// in the value, see [Self::bisect_changes].
+    pub fn bisect_threshold(
+        &mut self,
+        tx: &TxEnv,
+        range: RangeInclusive<U256>,
+        apply: impl Fn(&mut Evm<'a, EXT, CacheDB<DB>>, U256),
+    ) -> Result<Option<U256>, EVMError<DB::Error>> {
+        let (mut low, mut high) = range.into_inner();
+        let mut succeeds = |value: U256| self.succeeds_with(tx, |evm| apply(evm, value));
+        if low > high || !succeeds(high)? {
+            return Ok(None);
+        }
+        if succeeds(low)? {
+            return Ok(Some(low));
+        }
+        while high - low > U256::from(1) {
+            let middle = low + (high - low) / U256::from(2);
+            if succeeds(middle)? {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Ok(Some(high))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::EmptyDB,
+        interpreter::opcode,
+        primitives::{AccountInfo, Bytecode, Bytes, TransactTo},
+    };
+
+    fn transfer(to: u8) -> TxEnv {
+        TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(Address::with_last_byte(to)),
+            value: U256::from(1),
+            gas_limit: 21_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chain_and_revert() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            Address::with_last_byte(1),
+            AccountInfo::from_balance(U256::from(100)),
+        );
+        let mut simulator = Simulator::new(Evm::builder().with_db(db).build());
+
+        assert!(simulator.transact(transfer(2)).unwrap().is_success());
+        simulator.checkpoint("funded");
+        assert!(simulator.transact(transfer(2)).unwrap().is_success());
+        assert!(simulator.transact(transfer(3)).unwrap().is_success());
+        assert_eq!(simulator.nonce(Address::with_last_byte(1)).unwrap(), 3);
+
+        assert!(simulator.revert_to("funded"));
+        assert!(!simulator.revert_to("unknown"));
+        assert_eq!(simulator.checkpoints().collect::<Vec<_>>(), ["funded"]);
+        assert_eq!(simulator.nonce(Address::with_last_byte(1)).unwrap(), 1);
+        let balance = simulator.db().accounts[&Address::with_last_byte(2)]
+            .info
+            .balance;
+        assert_eq!(balance, U256::from(1));
+    }
+
+    #[test]
+    fn bisect_timestamp() {
+        // Reverts while the block timestamp is below 1000.
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH2,
+            0x03,
+            0xe8,
+            opcode::TIMESTAMP,
+            opcode::LT,
+            opcode::PUSH1,
+            9,
+            opcode::JUMPI,
+            opcode::STOP,
+            opcode::JUMPDEST,
+            opcode::PUSH0,
+            opcode::PUSH0,
+            opcode::REVERT,
+        ]));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            Address::with_last_byte(2),
+            AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+        );
+        let mut simulator = Simulator::new(Evm::builder().with_db(db).build());
+        let tx = TxEnv {
+            caller: Address::with_last_byte(1),
+            transact_to: TransactTo::Call(Address::with_last_byte(2)),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+
+        let threshold =
+            simulator.bisect_threshold(&tx, U256::ZERO..=U256::from(5000), |evm, value| {
+                evm.block_mut().timestamp = value;
+            });
+        assert_eq!(threshold.unwrap(), Some(U256::from(1000)));
+        assert_eq!(simulator.evm().block().timestamp, U256::ZERO);
+
+        let at = |timestamp: u64| {
+            move |evm: &mut Evm<'_, (), CacheDB<EmptyDB>>| {
+                evm.block_mut().timestamp = U256::from(timestamp)
+            }
+        };
+        let changes = [at(10), at(500), at(2000), at(3000)];
+        assert_eq!(simulator.bisect_changes(&tx, &changes).unwrap(), Some(3));
+        assert_eq!(simulator.bisect_changes(&tx, &changes[..2]).unwrap(), None);
+    }
+}
@@ -0,0 +1,301 @@
+#[cfg(feature = "std")]
+use crate::{db::DatabaseCommit, primitives::ResultAndState};
+use crate::{
+    db::{CacheDB, DatabaseRef},
+    primitives::{EVMError, TxEnv},
+    Evm,
+};
+use core::fmt;
+use std::vec::Vec;
+
+/// Caches the state between the transactions of a block, so that a single transaction can be
+/// traced without replaying the whole block.
+///
+/// The state before every [interval](Self::with_interval)-th transaction is kept as a
+/// checkpoint. Tracing transaction `i` restores the nearest checkpoint at or before `i` and
+/// executes the transactions in between, caching the checkpoints reached on the way. With the
+/// default interval of 1 the state before every transaction is cached and nothing is replayed
+/// once the block was executed, at the cost of a copy of the state per transaction.
+///
+/// Transactions are replayed with the EVM of the cache, so its inspector also observes them.
+pub struct TraceBlockCache<'a, EXT, DB: DatabaseRef> {
+    evm: Evm<'a, EXT, CacheDB<DB>>,
+    txs: Vec<TxEnv>,
+    interval: usize,
+    /// State before the transactions at multiples of the interval, starting with the state
+    /// before the block.
+    checkpoints: Vec<CacheDB<DB>>,
+}
+
+impl<EXT, DB: DatabaseRef> fmt::Debug for TraceBlockCache<'_, EXT, DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceBlockCache")
+            .field("txs", &self.txs.len())
+            .field("interval", &self.interval)
+            .field("checkpoints", &self.checkpoints.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, EXT, DB: DatabaseRef + Clone> TraceBlockCache<'a, EXT, DB> {
+    /// Creates a cache for the transactions `txs` of the block, executed on the block
+    /// environment of `evm` and starting from the state of its database.
+    pub fn new(evm: Evm<'a, EXT, CacheDB<DB>>, txs: Vec<TxEnv>) -> Self {
+        let checkpoints = Vec::from([evm.db().clone()]);
+        Self {
+            evm,
+            txs,
+            interval: 1,
+            checkpoints,
+        }
+    }
+
+    /// Keeps a checkpoint every `interval` transactions, at least 1.
+    ///
+    /// Must be set before any transaction is traced.
+    pub fn with_interval(mut self, interval: usize) -> Self {
+        self.interval = interval.max(1);
+        self.checkpoints.truncate(1);
+        self
+    }
+
+    /// Returns the transactions of the block.
+    pub fn txs(&self) -> &[TxEnv] {
+        &self.txs
+    }
+
+    /// Returns the interval between the checkpoints.
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Returns the cached checkpoints, the state before transaction `k * interval` at index `k`.
+    pub fn checkpoints(&self) -> &[CacheDB<DB>] {
+        &self.checkpoints
+    }
+
+    /// Returns the EVM the transactions are executed with.
+    pub fn evm(&mut self) -> &mut Evm<'a, EXT, CacheDB<DB>> {
+        &mut self.evm
+    }
+
+    /// Executes the whole block, caching all its checkpoints.
+    pub fn fill(&mut self) -> Result<(), EVMError<DB::Error>> {
+        self.restore(self.txs.len())
+    }
+
+    /// Returns the state before the transaction at `index`, or after the block if `index` is
+    /// the number of transactions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of transactions.
+    pub fn state_before(&mut self, index: usize) -> Result<&CacheDB<DB>, EVMError<DB::Error>> {
+        self.restore(index)?;
+        Ok(self.evm.db())
+    }
+
+    /// Restores the state before the transaction at `index`, sets the transaction and passes
+    /// the EVM to `trace`, which usually executes it without committing and reads the inspector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn trace<R>(
+        &mut self,
+        index: usize,
+        trace: impl FnOnce(&mut Evm<'a, EXT, CacheDB<DB>>) -> R,
+    ) -> Result<R, EVMError<DB::Error>> {
+        assert!(index < self.txs.len(), "transaction index out of bounds");
+        self.restore(index)?;
+        *self.evm.tx_mut() = self.txs[index].clone();
+        Ok(trace(&mut self.evm))
+    }
+
+    /// Sets the database of the EVM to the state before the transaction at `index`.
+    fn restore(&mut self, index: usize) -> Result<(), EVMError<DB::Error>> {
+        assert!(index <= self.txs.len(), "transaction index out of bounds");
+        let checkpoint = (index / self.interval).min(self.checkpoints.len() - 1);
+        *self.evm.db_mut() = self.checkpoints[checkpoint].clone();
+        for position in checkpoint * self.interval..index {
+            *self.evm.tx_mut() = self.txs[position].clone();
+            self.evm.transact_commit()?;
+            let next = position + 1;
+            if next % self.interval == 0 && next / self.interval == self.checkpoints.len() {
+                self.checkpoints.push(self.evm.db().clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, EXT, DB> TraceBlockCache<'a, EXT, DB>
+where
+    DB: DatabaseRef + Clone + Send + Sync,
+    DB::Error: Send,
+{
+    /// Traces all transactions of the block on `threads` threads and returns the traces in
+    /// block order.
+    ///
+    /// The block is first executed with the EVM of the cache to fill the checkpoints, which is
+    /// much cheaper than tracing it. The checkpoints are then split into contiguous ranges, one
+    /// per thread. A thread builds its EVM with `build` on the state of the first checkpoint of
+    /// its range, sets the environment of the block, and executes the transactions of the range
+    /// in order, committing each. `collect` turns the EVM and the output of every transaction
+    /// into its trace, usually by taking the trace out of the inspector.
+    ///
+    /// Returns the first error in block order.
+    pub fn trace_parallel<'b, EXT2, R, B, C>(
+        &mut self,
+        threads: usize,
+        build: B,
+        collect: C,
+    ) -> Result<Vec<R>, EVMError<DB::Error>>
+    where
+        R: Send,
+        B: Fn(CacheDB<DB>) -> Evm<'b, EXT2, CacheDB<DB>> + Sync,
+        C: Fn(&mut Evm<'b, EXT2, CacheDB<DB>>, &ResultAndState) -> R + Sync,
+    {
+        self.fill()?;
+        let env = &self.evm.context.evm.env;
+        let (txs, interval) = (&self.txs, self.interval);
+        let segments = txs.len().div_ceil(interval);
+        let per_thread = segments.div_ceil(threads.max(1)).max(1);
+        let (build, collect) = (&build, &collect);
+        let chunks = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .checkpoints
+                .iter()
+                .take(segments)
+                .step_by(per_thread)
+                .enumerate()
+                .map(|(thread, checkpoint)| {
+                    let start = thread * per_thread * interval;
+                    let end = (start + per_thread * interval).min(txs.len());
+                    let checkpoint = checkpoint.clone();
+                    let env = env.clone();
+                    scope.spawn(move || {
+                        let mut evm = build(checkpoint);
+                        evm.context.evm.env = env;
+                        let mut traces = Vec::with_capacity(end - start);
+                        for tx in &txs[start..end] {
+                            *evm.tx_mut() = tx.clone();
+                            let output = evm.transact()?;
+                            traces.push(collect(&mut evm, &output));
+                            evm.db_mut().commit(output.state);
+                        }
+                        Ok::<_, EVMError<DB::Error>>(traces)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("tracing thread panicked"))
+                .collect::<Vec<_>>()
+        });
+        let mut traces = Vec::with_capacity(txs.len());
+        for chunk in chunks {
+            traces.extend(chunk?);
+        }
+        Ok(traces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::EmptyDB,
+        interpreter::opcode,
+        primitives::{AccountInfo, Address, Bytecode, Bytes, TransactTo, U256},
+    };
+
+    /// Block of 5 calls incrementing slot 0 of the returned contract.
+    fn counter_block() -> (Address, Vec<TxEnv>, CacheDB<EmptyDB>) {
+        // SSTORE(0, SLOAD(0) + 1)
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            opcode::PUSH1,
+            1,
+            opcode::PUSH0,
+            opcode::SLOAD,
+            opcode::ADD,
+            opcode::PUSH0,
+            opcode::SSTORE,
+            opcode::STOP,
+        ]));
+        let contract = Address::with_last_byte(2);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(U256::ZERO, 0, code.hash_slow(), code),
+        );
+        let txs = (0..5)
+            .map(|nonce| TxEnv {
+                caller: Address::with_last_byte(1),
+                transact_to: TransactTo::Call(contract),
+                gas_limit: 100_000,
+                nonce: Some(nonce),
+                ..Default::default()
+            })
+            .collect();
+        (contract, txs, db)
+    }
+
+    #[test]
+    fn trace_from_checkpoint() {
+        let (contract, txs, db) = counter_block();
+        let evm = Evm::builder().with_db(db).build();
+        let mut cache = TraceBlockCache::new(evm, txs).with_interval(2);
+
+        let counter = |cache: &mut TraceBlockCache<'_, (), EmptyDB>, index| {
+            let db = cache.state_before(index).unwrap();
+            db.storage_ref(contract, U256::ZERO).unwrap()
+        };
+        assert_eq!(counter(&mut cache, 3), U256::from(3));
+        assert_eq!(cache.checkpoints().len(), 2);
+        assert_eq!(counter(&mut cache, 1), U256::from(1));
+
+        let traced = cache
+            .trace(4, |evm| {
+                let state = evm.transact().unwrap().state;
+                state[&contract].storage[&U256::ZERO].present_value
+            })
+            .unwrap();
+        assert_eq!(traced, U256::from(5));
+
+        cache.fill().unwrap();
+        assert_eq!(cache.checkpoints().len(), 3);
+        assert_eq!(counter(&mut cache, 5), U256::from(5));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn trace_parallel_in_block_order() {
+        use crate::{inspector_handle_register, inspectors::GasSeriesInspector};
+
+        let (contract, txs, db) = counter_block();
+        let evm = Evm::builder().with_db(db).build();
+        let mut cache = TraceBlockCache::new(evm, txs).with_interval(2);
+        let traces = cache
+            .trace_parallel(
+                2,
+                |db| {
+                    Evm::builder()
+                        .with_db(db)
+                        .with_external_context(GasSeriesInspector::default())
+                        .append_handler_register(inspector_handle_register)
+                        .build()
+                },
+                |evm, output| {
+                    let steps = evm.context.external.samples().len();
+                    let value = output.state[&contract].storage[&U256::ZERO].present_value;
+                    (value, steps)
+                },
+            )
+            .unwrap();
+        let values: Vec<_> = traces.iter().map(|(value, _)| value.to::<u64>()).collect();
+        assert_eq!(values, [1, 2, 3, 4, 5]);
+        assert!(traces.iter().all(|(_, steps)| *steps == 7));
+    }
+}
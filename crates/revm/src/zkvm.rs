@@ -0,0 +1,42 @@
+//! Compile-time enforcement of the `zkvm` profile.
+//!
+//! zkVM guest programs (SP1, RISC Zero and similar provers) must execute deterministically and
+//! without access to the host, and their crypto is only fast through the crates they patch. The
+//! profile is enabled with:
+//!
+//! ```toml
+//! revm = { version = "...", default-features = false, features = ["zkvm"] }
+//! ```
+//!
+//! With it:
+//! - `std` is disabled, so there is no host entropy (maps use the fixed seeded `hashbrown`
+//!   hasher), no threads, no clock and no I/O.
+//! - precompiles only use pure Rust crates (`k256`, `sha2`, `ripemd`, `substrate-bn` and
+//!   `aurora-engine-modexp`) that can be replaced by accelerated versions with `[patch.crates-io]`.
+//! - the KZG point evaluation precompile is not included, as its only backend is the `c-kzg`
+//!   C library. Guests that need it can install one backed by the prover as a stateful
+//!   precompile through the handler.
+//!
+//! The checks of this module only apply when compiling for a zkVM target (`target_os = "zkvm"`),
+//! so that the host side of a prover can share a build with the features it needs.
+
+#[cfg(feature = "std")]
+compile_error!(
+    "the `zkvm` profile is incompatible with the `std` feature, \
+     disable the default features of revm"
+);
+
+#[cfg(feature = "c-kzg")]
+compile_error!("the `zkvm` profile is incompatible with the `c-kzg` feature, a C library");
+
+#[cfg(feature = "secp256k1")]
+compile_error!("the `zkvm` profile is incompatible with the `secp256k1` feature, a C library");
+
+#[cfg(feature = "asm-keccak")]
+compile_error!(
+    "the `zkvm` profile is incompatible with the `asm-keccak` feature, \
+     keccak is patched through `tiny-keccak`"
+);
+
+#[cfg(feature = "wasm-tracer")]
+compile_error!("the `zkvm` profile is incompatible with the `wasm-tracer` feature");